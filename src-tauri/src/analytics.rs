@@ -0,0 +1,221 @@
+// 🎓 TEACHING: Request analytics
+// Every send of a saved request, from the editor, the collection runner or a monitor,
+// records a small sample: status, duration and response size (never the body). For a time
+// range the samples become latency percentiles, an error rate and an average payload size,
+// overall and per time bucket, so a slow creep in an API shows up as a trend.
+//
+// Percentiles use the nearest-rank method over sends that got a response; a send that
+// failed outright has no meaningful latency. It still counts as an error, as does any
+// 4xx/5xx status. Cached responses aren't sampled, since nothing was sent.
+
+use crate::database::{Database, RequestSample};
+use crate::ApiResponse;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+// Trend buckets a range is split into
+const TREND_BUCKETS: i64 = 24;
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub p50_ms: Option<u64>,
+    pub p90_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+    pub error_rate: f64,                 // 0.0 to 1.0 of all samples
+    pub avg_response_size: Option<u64>, // Bytes, over sends that got a response
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TrendBucket {
+    pub start: DateTime<Utc>,
+    #[serde(flatten)]
+    pub stats: LatencyStats,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RequestAnalytics {
+    pub request_id: String,
+    pub range: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    #[serde(flatten)]
+    pub overall: LatencyStats,
+    pub trend: Vec<TrendBucket>, // TREND_BUCKETS equal slices of the range, oldest first
+}
+
+// Best effort: losing a sample shouldn't fail the send it describes
+pub async fn record(db: &Database, request_id: &str, duration_ms: u64, response: Option<&ApiResponse>) {
+    if response.is_some_and(|response| response.from_cache == Some(true)) {
+        return;
+    }
+    let trace = response.and_then(|response| response.trace.as_ref());
+    let sample = RequestSample {
+        id: Uuid::new_v4().to_string(),
+        request_id: request_id.to_string(),
+        status: response.map(|response| response.status),
+        duration_ms,
+        response_size: response.map(|response| response.body.len() as u64).unwrap_or(0),
+        sent_at: Utc::now(),
+        trace_id: trace.and_then(|trace| trace.trace_id.clone()),
+        correlation_id: trace.and_then(|trace| trace.request_id.clone()),
+    };
+    if let Err(e) = db.save_request_sample(&sample).await {
+        crate::logging::warn(format!("Failed to record request sample: {}", e));
+    }
+}
+
+// "30m", "24h", "7d", "4w", or "all"; None means everything
+pub fn parse_range(range: &str) -> Result<Option<Duration>> {
+    let range = range.trim();
+    if range == "all" {
+        return Ok(None);
+    }
+    let invalid = || anyhow::anyhow!("Invalid range '{}' (use e.g. 24h, 7d, 4w or all)", range);
+    let unit = range.chars().last().ok_or_else(invalid)?;
+    let amount: i64 = range[..range.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+    if amount <= 0 {
+        return Err(invalid());
+    }
+    let duration = match unit {
+        'm' => Duration::minutes(amount),
+        'h' => Duration::hours(amount),
+        'd' => Duration::days(amount),
+        'w' => Duration::weeks(amount),
+        _ => return Err(invalid()),
+    };
+    Ok(Some(duration))
+}
+
+pub async fn request_analytics(db: &Database, request_id: &str, range: &str) -> Result<RequestAnalytics> {
+    let to = Utc::now();
+    let since = parse_range(range)?.map(|duration| to - duration);
+    let samples = db.get_request_samples(request_id, since).await?;
+    // "all" starts at the first sample (or now, without any)
+    let from = since.unwrap_or_else(|| samples.first().map(|sample| sample.sent_at).unwrap_or(to));
+
+    Ok(RequestAnalytics {
+        request_id: request_id.to_string(),
+        range: range.to_string(),
+        from,
+        to,
+        overall: stats(&samples),
+        trend: trend(&samples, from, to),
+    })
+}
+
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+pub fn stats(samples: &[RequestSample]) -> LatencyStats {
+    let answered: Vec<&RequestSample> = samples.iter().filter(|sample| sample.status.is_some()).collect();
+    let mut durations: Vec<u64> = answered.iter().map(|sample| sample.duration_ms).collect();
+    durations.sort_unstable();
+    let errors = samples
+        .iter()
+        .filter(|sample| sample.status.is_none_or(|status| status >= 400))
+        .count();
+
+    LatencyStats {
+        samples: samples.len(),
+        p50_ms: percentile(&durations, 50.0),
+        p90_ms: percentile(&durations, 90.0),
+        p99_ms: percentile(&durations, 99.0),
+        error_rate: if samples.is_empty() { 0.0 } else { errors as f64 / samples.len() as f64 },
+        avg_response_size: (!answered.is_empty())
+            .then(|| answered.iter().map(|sample| sample.response_size).sum::<u64>() / answered.len() as u64),
+    }
+}
+
+// Empty buckets are kept (with no percentiles) so the trend has an even time axis
+pub fn trend(samples: &[RequestSample], from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<TrendBucket> {
+    let span = (to - from).num_milliseconds().max(TREND_BUCKETS);
+    let width = span / TREND_BUCKETS;
+    let mut buckets: Vec<Vec<RequestSample>> = vec![Vec::new(); TREND_BUCKETS as usize];
+    for sample in samples {
+        let offset = (sample.sent_at - from).num_milliseconds();
+        if offset < 0 {
+            continue;
+        }
+        let index = ((offset / width) as usize).min(buckets.len() - 1);
+        buckets[index].push(sample.clone());
+    }
+
+    buckets
+        .iter()
+        .enumerate()
+        .map(|(index, bucket)| TrendBucket {
+            start: from + Duration::milliseconds(width * index as i64),
+            stats: stats(bucket),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample(minute: u32, status: Option<u16>, duration_ms: u64) -> RequestSample {
+        RequestSample {
+            id: format!("s{}", minute),
+            request_id: "r".to_string(),
+            status,
+            duration_ms,
+            response_size: 100,
+            sent_at: Utc.with_ymd_and_hms(2024, 3, 1, 10, minute, 0).unwrap(),
+            trace_id: None,
+            correlation_id: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("24h").unwrap(), Some(Duration::hours(24)));
+        assert_eq!(parse_range("7d").unwrap(), Some(Duration::days(7)));
+        assert_eq!(parse_range("all").unwrap(), None);
+        assert!(parse_range("0d").is_err());
+        assert!(parse_range("7y").is_err());
+        assert!(parse_range("d").is_err());
+        assert!(parse_range("").is_err());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut samples: Vec<RequestSample> = (1..=10).map(|i| sample(i, Some(200), i as u64 * 10)).collect();
+        samples.push(sample(11, Some(503), 1000));
+        samples.push(sample(12, None, 5));
+
+        let stats = stats(&samples);
+        assert_eq!(stats.samples, 12);
+        assert_eq!(stats.p50_ms, Some(60));
+        assert_eq!(stats.p90_ms, Some(100));
+        assert_eq!(stats.p99_ms, Some(1000));
+        assert!((stats.error_rate - 2.0 / 12.0).abs() < 1e-9);
+        assert_eq!(stats.avg_response_size, Some(100));
+
+        assert_eq!(super::stats(&[]), LatencyStats::default());
+    }
+
+    #[test]
+    fn test_trend() {
+        let from = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap();
+        let to = from + Duration::hours(24);
+        let samples = vec![sample(0, Some(200), 10), sample(59, Some(200), 30), sample(30, None, 1)];
+
+        let buckets = trend(&samples, from, to);
+        assert_eq!(buckets.len(), 24);
+        assert_eq!(buckets[0].stats.samples, 3);
+        assert_eq!(buckets[0].stats.p50_ms, Some(10));
+        assert_eq!(buckets[1].start, from + Duration::hours(1));
+        assert_eq!(buckets[1].stats.samples, 0);
+        assert_eq!(buckets[1].stats.p50_ms, None);
+    }
+}
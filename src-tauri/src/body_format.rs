@@ -0,0 +1,537 @@
+// 🎓 TEACHING: Body formatting
+// The viewer's pretty-print, minify and convert buttons run here rather than in the
+// frontend, so every view of a body formats it the same way, and a big payload doesn't
+// tie up the renderer while it's being reformatted.
+//
+//   from        to
+//   json        json (pretty, 2 spaces), json_minified, xml
+//   xml         xml (pretty), xml_minified, json
+//   urlencoded  text (one decoded `key=value` per line), json
+//
+// JSON is reformatted token by token instead of being parsed and printed again, so keys keep
+// their order and numbers keep their exact digits.
+//
+// XML and JSON don't map onto each other exactly, so conversion follows the usual convention:
+// attributes become "@name" keys, text beside attributes or child elements becomes "#text",
+// repeated child elements become an array and an empty element is null. Values stay strings.
+// JSON -> XML reverses it, wrapping the document in <root> unless it's an object with one key.
+
+use anyhow::{anyhow, Result};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+pub fn format(content: &str, from: &str, to: &str) -> Result<String> {
+    match (from, to) {
+        ("json", "json") => reformat_json(content, true),
+        ("json", "json_minified") => reformat_json(content, false),
+        ("json", "xml") => json_to_xml(content),
+        ("xml", "xml") => reformat_xml(content, true),
+        ("xml", "xml_minified") => reformat_xml(content, false),
+        ("xml", "json") => xml_to_json(content),
+        ("urlencoded", "text") => Ok(url_decoded_text(content)),
+        ("urlencoded", "json") => url_decoded_json(content),
+        (from, to) => Err(anyhow!("Can't format {} as {}", from, to)),
+    }
+}
+
+// ---- JSON ----
+
+fn reformat_json(content: &str, pretty: bool) -> Result<String> {
+    serde_json::from_str::<de::IgnoredAny>(content).map_err(|e| anyhow!("Invalid JSON: {}", e))?;
+    let mut out = String::with_capacity(content.len());
+    let mut depth = 0;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push('"');
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '{' | '[' => {
+                out.push(c);
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                // Empty ones stay on one line
+                if let Some(close) = chars.next_if(|c| *c == '}' || *c == ']') {
+                    out.push(close);
+                } else {
+                    depth += 1;
+                    new_line(&mut out, depth, pretty);
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                new_line(&mut out, depth, pretty);
+                out.push(c);
+            }
+            ',' => {
+                out.push(',');
+                new_line(&mut out, depth, pretty);
+            }
+            ':' => out.push_str(if pretty { ": " } else { ":" }),
+            c if c.is_whitespace() => {}
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+fn new_line(out: &mut String, depth: usize, pretty: bool) {
+    if pretty {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    }
+}
+
+// 🎓 TEACHING: serde_json's own Value sorts object keys, which would scramble a converted
+// document, so conversions go through this one, which keeps them in order.
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(serde_json::Number),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    // The text of a scalar, as an XML attribute or element holds it
+    fn scalar_text(&self) -> Option<String> {
+        match self {
+            Json::Null => Some(String::new()),
+            Json::Bool(b) => Some(b.to_string()),
+            Json::Number(n) => Some(n.to_string()),
+            Json::String(s) => Some(s.clone()),
+            Json::Array(_) | Json::Object(_) => None,
+        }
+    }
+}
+
+impl Serialize for Json {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Json::Null => serializer.serialize_unit(),
+            Json::Bool(b) => serializer.serialize_bool(*b),
+            Json::Number(n) => n.serialize(serializer),
+            Json::String(s) => serializer.serialize_str(s),
+            Json::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Json::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Json {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = Json;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Json, E> {
+        Ok(Json::Bool(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Json, E> {
+        Ok(Json::Number(n.into()))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Json, E> {
+        Ok(Json::Number(n.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, n: f64) -> Result<Json, E> {
+        serde_json::Number::from_f64(n).map(Json::Number).ok_or_else(|| E::custom("invalid number"))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Json, E> {
+        Ok(Json::String(s.to_string()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Json, E> {
+        Ok(Json::String(s))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Json, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Json::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Json, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Json::Object(entries))
+    }
+}
+
+// ---- XML ----
+
+fn xml_error(reader: &Reader<&[u8]>, e: impl std::fmt::Display) -> anyhow::Error {
+    anyhow!("Invalid XML at byte {}: {}", reader.error_position(), e)
+}
+
+fn reformat_xml(content: &str, pretty: bool) -> Result<String> {
+    let mut reader = Reader::from_str(content);
+    let mut writer = if pretty { Writer::new_with_indent(Vec::new(), b' ', 2) } else { Writer::new(Vec::new()) };
+    // Text arrives in pieces around entity references (`a &amp; b`); it's written as one
+    let mut text = String::new();
+    loop {
+        match reader.read_event().map_err(|e| xml_error(&reader, e))? {
+            Event::Text(t) => text.push_str(&t.decode()?),
+            Event::GeneralRef(r) => {
+                text.push('&');
+                text.push_str(&r.decode()?);
+                text.push(';');
+            }
+            Event::Eof => break,
+            event => {
+                write_text(&mut writer, &mut text)?;
+                writer.write_event(event)?;
+            }
+        }
+    }
+    write_text(&mut writer, &mut text)?;
+    Ok(String::from_utf8(writer.into_inner())?.trim_start().to_string())
+}
+
+// Whitespace between elements is the old layout, so it's dropped; real text is kept as it was
+fn write_text(writer: &mut Writer<Vec<u8>>, text: &mut String) -> Result<()> {
+    if !text.trim().is_empty() {
+        writer.write_event(Event::Text(BytesText::from_escaped(text.as_str())))?;
+    }
+    text.clear();
+    Ok(())
+}
+
+struct Element {
+    name: String,
+    entries: Vec<(String, Json)>, // Attributes, then child elements
+    text: String,
+}
+
+impl Element {
+    fn new(start: &BytesStart, reader: &Reader<&[u8]>) -> Result<Self> {
+        let mut entries = Vec::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|e| xml_error(reader, e))?;
+            let name = String::from_utf8_lossy(attribute.key.as_ref());
+            let value = attribute.unescape_value().map_err(|e| xml_error(reader, e))?;
+            entries.push((format!("@{}", name), Json::String(value.into_owned())));
+        }
+        Ok(Element {
+            name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+            entries,
+            text: String::new(),
+        })
+    }
+
+    fn add_child(&mut self, name: String, value: Json) {
+        match self.entries.iter_mut().find(|(key, _)| *key == name) {
+            // Elements only ever become arrays by repeating
+            Some((_, Json::Array(items))) => items.push(value),
+            Some((_, existing)) => {
+                let first = std::mem::replace(existing, Json::Null);
+                *existing = Json::Array(vec![first, value]);
+            }
+            None => self.entries.push((name, value)),
+        }
+    }
+
+    fn into_json(mut self) -> (String, Json) {
+        let text = self.text.trim();
+        let value = if self.entries.is_empty() {
+            if text.is_empty() {
+                Json::Null
+            } else {
+                Json::String(text.to_string())
+            }
+        } else {
+            // Text between child elements is formatting, unless there's more to it
+            if !text.is_empty() {
+                self.entries.push(("#text".to_string(), Json::String(text.to_string())));
+            }
+            Json::Object(self.entries)
+        };
+        (self.name, value)
+    }
+}
+
+fn xml_to_json(content: &str) -> Result<String> {
+    let mut reader = Reader::from_str(content);
+    let mut open: Vec<Element> = Vec::new();
+    let mut root = None;
+    loop {
+        let event = reader.read_event().map_err(|e| xml_error(&reader, e))?;
+        let closed = match event {
+            Event::Start(start) => {
+                open.push(Element::new(&start, &reader)?);
+                None
+            }
+            Event::Empty(start) => Some(Element::new(&start, &reader)?),
+            Event::End(_) => open.pop(),
+            Event::Text(t) => {
+                if let Some(element) = open.last_mut() {
+                    let text = t.decode()?;
+                    element.text.push_str(&quick_xml::escape::unescape(&text).map_err(|e| xml_error(&reader, e))?);
+                }
+                None
+            }
+            Event::CData(data) => {
+                if let Some(element) = open.last_mut() {
+                    element.text.push_str(&data.decode()?);
+                }
+                None
+            }
+            Event::GeneralRef(reference) => {
+                let name = reference.decode()?;
+                let resolved = match reference.resolve_char_ref()? {
+                    Some(c) => c.to_string(),
+                    None => quick_xml::escape::resolve_predefined_entity(&name)
+                        .ok_or_else(|| xml_error(&reader, format!("unknown entity &{};", name)))?
+                        .to_string(),
+                };
+                if let Some(element) = open.last_mut() {
+                    element.text.push_str(&resolved);
+                }
+                None
+            }
+            Event::Eof => break,
+            _ => None, // Declarations, comments, processing instructions, doctypes
+        };
+        if let Some(element) = closed {
+            let (name, value) = element.into_json();
+            match open.last_mut() {
+                Some(parent) => parent.add_child(name, value),
+                None => root = Some(Json::Object(vec![(name, value)])),
+            }
+        }
+    }
+    if let Some(element) = open.last() {
+        return Err(anyhow!("Invalid XML: <{}> is never closed", element.name));
+    }
+    let root = root.ok_or_else(|| anyhow!("Invalid XML: there's no root element"))?;
+    Ok(serde_json::to_string_pretty(&root)?)
+}
+
+fn json_to_xml(content: &str) -> Result<String> {
+    let json: Json = serde_json::from_str(content).map_err(|e| anyhow!("Invalid JSON: {}", e))?;
+    let (name, value) = match json {
+        Json::Object(mut entries) if entries.len() == 1 && !is_special_key(&entries[0].0) => entries.remove(0),
+        other => ("root".to_string(), other),
+    };
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    write_element(&mut writer, &name, &value)?;
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+fn is_special_key(key: &str) -> bool {
+    key.starts_with('@') || key == "#text"
+}
+
+fn write_element(writer: &mut Writer<Vec<u8>>, name: &str, value: &Json) -> Result<()> {
+    let valid = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || "_-.:".contains(c));
+    if !valid {
+        return Err(anyhow!("\"{}\" can't be an XML element name", name));
+    }
+    let entries = match value {
+        // An array is the same element repeated
+        Json::Array(items) => {
+            for item in items {
+                write_element(writer, name, item)?;
+            }
+            return Ok(());
+        }
+        Json::Object(entries) => entries.as_slice(),
+        Json::Null => &[],
+        scalar => {
+            let text = scalar.scalar_text().unwrap_or_default();
+            writer.write_event(Event::Start(BytesStart::new(name)))?;
+            writer.write_event(Event::Text(BytesText::new(&text)))?;
+            writer.write_event(Event::End(BytesEnd::new(name)))?;
+            return Ok(());
+        }
+    };
+
+    let mut start = BytesStart::new(name);
+    for (key, value) in entries {
+        if let Some(attribute) = key.strip_prefix('@') {
+            let text = value.scalar_text().ok_or_else(|| anyhow!("Attribute {} must be a single value", key))?;
+            start.push_attribute((attribute, text.as_str()));
+        }
+    }
+    let content: Vec<&(String, Json)> = entries.iter().filter(|(key, _)| !key.starts_with('@')).collect();
+    if content.is_empty() {
+        writer.write_event(Event::Empty(start))?;
+        return Ok(());
+    }
+    writer.write_event(Event::Start(start))?;
+    for (key, value) in content {
+        if key == "#text" {
+            let text = value.scalar_text().unwrap_or_default();
+            writer.write_event(Event::Text(BytesText::new(&text)))?;
+        } else {
+            write_element(writer, key, value)?;
+        }
+    }
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+    Ok(())
+}
+
+// ---- URL-encoded ----
+
+// A form body, a query string or a whole URL (whose query is used)
+fn query_part(content: &str) -> &str {
+    let content = content.trim();
+    let is_url = content.contains("://") || content.starts_with('/') || content.starts_with('?');
+    match content.find('?') {
+        Some(i) if is_url => content[i + 1..].split('#').next().unwrap_or_default(),
+        _ => content,
+    }
+}
+
+// Each pair decoded; the value is None for a bare `key` with no `=`
+fn decoded_pairs(content: &str) -> Vec<(String, Option<String>)> {
+    query_part(content)
+        .split('&')
+        .filter(|piece| !piece.is_empty())
+        .filter_map(|piece| {
+            let (key, value) = url::form_urlencoded::parse(piece.as_bytes()).next()?;
+            Some((key.into_owned(), piece.contains('=').then(|| value.into_owned())))
+        })
+        .collect()
+}
+
+fn url_decoded_text(content: &str) -> String {
+    let lines: Vec<String> = decoded_pairs(content)
+        .into_iter()
+        .map(|(key, value)| match value {
+            Some(value) => format!("{}={}", key, value),
+            None => key,
+        })
+        .collect();
+    lines.join("\n")
+}
+
+// Repeated keys become arrays, in the order they came
+fn url_decoded_json(content: &str) -> Result<String> {
+    let mut element = Element { name: String::new(), entries: Vec::new(), text: String::new() };
+    for (key, value) in decoded_pairs(content) {
+        element.add_child(key, value.map(Json::String).unwrap_or(Json::Null));
+    }
+    Ok(serde_json::to_string_pretty(&Json::Object(element.entries))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json() {
+        let ugly = r#"{"zeta":1,"alpha":[1, 2.50, {}],"big":123456789012345678901234567890,"s":"a, b: {\"c\"}","e":[]}"#;
+        let pretty = format(ugly, "json", "json").unwrap();
+        assert_eq!(
+            pretty,
+            "{\n  \"zeta\": 1,\n  \"alpha\": [\n    1,\n    2.50,\n    {}\n  ],\n  \
+             \"big\": 123456789012345678901234567890,\n  \"s\": \"a, b: {\\\"c\\\"}\",\n  \"e\": []\n}"
+        );
+        let minified = format(&pretty, "json", "json_minified").unwrap();
+        assert_eq!(minified, ugly.replacen("[1, 2.50, {}]", "[1,2.50,{}]", 1));
+        assert!(format("{\"a\":", "json", "json").unwrap_err().to_string().starts_with("Invalid JSON"));
+    }
+
+    #[test]
+    fn test_xml() {
+        let xml = "<?xml version=\"1.0\"?><a x=\"1\"><b>one &amp; two</b>  <c/><!-- note --></a>";
+        assert_eq!(
+            format(xml, "xml", "xml").unwrap(),
+            "<?xml version=\"1.0\"?>\n<a x=\"1\">\n  <b>one &amp; two</b>\n  <c/>\n  <!-- note -->\n</a>"
+        );
+        assert_eq!(
+            format(&format(xml, "xml", "xml").unwrap(), "xml", "xml_minified").unwrap(),
+            "<?xml version=\"1.0\"?><a x=\"1\"><b>one &amp; two</b><c/><!-- note --></a>"
+        );
+        assert!(format("<a><b></a>", "xml", "xml").is_err());
+    }
+
+    #[test]
+    fn test_xml_json_round_trip() {
+        let xml = "<order id=\"7\"><item>pen</item><item>ink</item><note lang=\"en\">fast &lt;please&gt;</note><gift/></order>";
+        let json = format(xml, "xml", "json").unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"order": {
+                "@id": "7",
+                "item": ["pen", "ink"],
+                "note": {"@lang": "en", "#text": "fast <please>"},
+                "gift": null,
+            }})
+        );
+        // Keys keep the document's order
+        assert!(json.find("\"item\"").unwrap() < json.find("\"note\"").unwrap());
+
+        assert_eq!(
+            format(&json, "json", "xml").unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<order id=\"7\">\n  <item>pen</item>\n  <item>ink</item>\n  \
+             <note lang=\"en\">fast &lt;please&gt;</note>\n  <gift/>\n</order>"
+        );
+        assert!(format("[1, 2]", "json", "xml").unwrap().contains("<root>1</root>\n<root>2</root>"));
+        assert!(format(r#"{"a b": 1}"#, "json", "xml").is_err());
+        assert!(format("<a>", "xml", "json").is_err());
+    }
+
+    #[test]
+    fn test_url_encoded() {
+        let form = "name=Ada+Lovelace&tag=a%26b&tag=c&flag&token=abc%3D%3D";
+        assert_eq!(
+            format(form, "urlencoded", "text").unwrap(),
+            "name=Ada Lovelace\ntag=a&b\ntag=c\nflag\ntoken=abc=="
+        );
+        let json: serde_json::Value = serde_json::from_str(&format(form, "urlencoded", "json").unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"name": "Ada Lovelace", "tag": ["a&b", "c"], "flag": null, "token": "abc=="})
+        );
+        assert_eq!(format("https://x.io/s?q=%C3%A9t%C3%A9#top", "urlencoded", "text").unwrap(), "q=été");
+        assert!(format("a=1", "urlencoded", "xml").is_err());
+    }
+}
@@ -0,0 +1,498 @@
+// 🎓 TEACHING: File-based collections (Bruno-style .bru files)
+// A JSON export is one big file, so a one-line change shows up as a noisy diff. Here a
+// collection is a folder instead: `collection.bru` holds the collection itself and every
+// request is its own small text file, which reads well in `git diff` and code review.
+//
+// A .bru file is a list of blocks. Key/value blocks hold one `key: value` per line
+// (a leading `~` marks a disabled entry); text blocks hold raw text indented by two spaces:
+//
+//   meta {
+//     name: Get user
+//     seq: 1
+//   }
+//
+//   get {
+//     url: {{base_url}}/users/:id
+//     body: json
+//   }
+//
+//   body:json {
+//     { "active": true }
+//   }
+
+use crate::importer_exporter::{JsonCollection, JsonRequest, CURRENT_SCHEMA_VERSION};
+use crate::params::QueryParam;
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+pub const COLLECTION_FILE: &str = "collection.bru";
+
+#[derive(Debug, Clone, PartialEq)]
+enum BlockContent {
+    Pairs(Vec<(String, String, bool)>), // key, value, enabled
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+struct Block {
+    name: String,
+    content: BlockContent,
+}
+
+// Blocks whose content is free text rather than `key: value` lines
+fn is_text_block(name: &str) -> bool {
+    name.starts_with("body:") || name.starts_with("auth:") || name == "docs" || name == "grpc"
+}
+
+fn write_pairs(out: &mut String, name: &str, pairs: &[(String, String, bool)]) {
+    if pairs.is_empty() {
+        return;
+    }
+    out.push_str(&format!("{} {{\n", name));
+    for (key, value, enabled) in pairs {
+        let marker = if *enabled { "" } else { "~" };
+        out.push_str(&format!("  {}{}: {}\n", marker, key, value));
+    }
+    out.push_str("}\n\n");
+}
+
+fn write_text(out: &mut String, name: &str, text: &str) {
+    out.push_str(&format!("{} {{\n", name));
+    for line in text.lines() {
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            out.push_str(&format!("  {}\n", line));
+        }
+    }
+    out.push_str("}\n\n");
+}
+
+fn pair(key: &str, value: impl Into<String>) -> (String, String, bool) {
+    (key.to_string(), value.into(), true)
+}
+
+// Pretty-print JSON auth data so each field gets its own line in diffs
+fn pretty_json(text: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(text)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .unwrap_or_else(|_| text.to_string())
+}
+
+fn write_auth(out: &mut String, auth_type: Option<&str>, auth_data: Option<&str>) {
+    if let (Some(auth_type), Some(auth_data)) = (auth_type, auth_data) {
+        write_text(out, &format!("auth:{}", auth_type), &pretty_json(auth_data));
+    }
+}
+
+pub fn request_to_bru(request: &JsonRequest, seq: usize) -> Result<String> {
+    let mut out = String::new();
+    let kind = match request.body_type.as_str() {
+        "graphql" => "graphql",
+        "grpc" => "grpc",
+        _ => "http",
+    };
+    let mut meta = vec![pair("name", &request.name), pair("type", kind), pair("seq", seq.to_string())];
+    if let Some(operation_name) = &request.graphql_operation_name {
+        meta.push(pair("operation", operation_name));
+    }
+    write_pairs(&mut out, "meta", &meta);
+
+    let mut call = vec![pair("url", &request.url), pair("body", &request.body_type)];
+    if let Some(auth_type) = &request.auth_type {
+        call.push(pair("auth", auth_type));
+    }
+    write_pairs(&mut out, &request.method.to_lowercase(), &call);
+
+    let params = crate::params::parse_query_params(&request.params)?;
+    let params: Vec<_> = params.into_iter().map(|p| (p.key, p.value, p.enabled)).collect();
+    write_pairs(&mut out, "params:query", &params);
+
+    // Sorted, so re-exporting an unchanged request gives an identical file
+    let path_params: BTreeMap<String, String> = serde_json::from_str(&request.path_params)?;
+    let path_params: Vec<_> = path_params.into_iter().map(|(k, v)| (k, v, true)).collect();
+    write_pairs(&mut out, "params:path", &path_params);
+
+    let headers: BTreeMap<String, String> = serde_json::from_str(&request.headers)?;
+    let headers: Vec<_> = headers.into_iter().map(|(k, v)| (k, v, true)).collect();
+    write_pairs(&mut out, "headers", &headers);
+
+    write_auth(&mut out, request.auth_type.as_deref(), request.auth_data.as_deref());
+
+    if let Some(body) = &request.body_str {
+        write_text(&mut out, &format!("body:{}", request.body_type), body);
+    }
+    if let Some(variables) = &request.graphql_variables {
+        write_text(&mut out, "body:graphql:vars", variables);
+    }
+    if let Some(config) = &request.grpc_config {
+        write_text(&mut out, "grpc", &pretty_json(config));
+    }
+    if let Some(captures) = &request.captures {
+        write_text(&mut out, "captures", &pretty_json(captures));
+    }
+    if let Some(scripts) = &request.scripts {
+        write_text(&mut out, "scripts", &pretty_json(scripts));
+    }
+    if let Some(settings) = &request.settings {
+        write_text(&mut out, "settings", &pretty_json(settings));
+    }
+    if let Some(docs) = &request.docs {
+        write_text(&mut out, "docs", docs);
+    }
+
+    Ok(out.trim_end().to_string() + "\n")
+}
+
+pub fn collection_to_bru(collection: &JsonCollection) -> String {
+    let mut out = String::new();
+    write_pairs(&mut out, "meta", &[pair("name", &collection.name)]);
+    write_auth(&mut out, collection.auth_type.as_deref(), collection.auth_data.as_deref());
+    if let Some(description) = &collection.description {
+        write_text(&mut out, "docs", description);
+    }
+    out.trim_end().to_string() + "\n"
+}
+
+fn parse_blocks(text: &str) -> Result<Vec<Block>> {
+    let mut blocks = Vec::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((number, line)) = lines.next() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            continue;
+        }
+        let name = line
+            .strip_suffix('{')
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && !line.starts_with(' '))
+            .ok_or_else(|| anyhow::anyhow!("Line {}: expected `<block> {{`, found `{}`", number + 1, line))?
+            .to_string();
+
+        let mut body = Vec::new();
+        let mut closed = false;
+        for (_, line) in lines.by_ref() {
+            if line.trim_end() == "}" && !line.starts_with(' ') {
+                closed = true;
+                break;
+            }
+            body.push(line);
+        }
+        if !closed {
+            return Err(anyhow::anyhow!("Block `{}` is never closed", name));
+        }
+
+        let content = if is_text_block(&name) {
+            let text: Vec<&str> = body.iter().map(|line| line.strip_prefix("  ").unwrap_or(line)).collect();
+            BlockContent::Text(text.join("\n"))
+        } else {
+            let mut pairs = Vec::new();
+            for line in body.iter().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+                let (key, value) = line
+                    .split_once(':')
+                    .ok_or_else(|| anyhow::anyhow!("Block `{}`: expected `key: value`, found `{}`", name, line))?;
+                let (key, enabled) = match key.strip_prefix('~') {
+                    Some(key) => (key, false),
+                    None => (key, true),
+                };
+                pairs.push((key.trim().to_string(), value.strip_prefix(' ').unwrap_or(value).to_string(), enabled));
+            }
+            BlockContent::Pairs(pairs)
+        };
+        blocks.push(Block { name, content });
+    }
+    Ok(blocks)
+}
+
+fn block_pairs<'a>(blocks: &'a [Block], name: &str) -> &'a [(String, String, bool)] {
+    blocks
+        .iter()
+        .find(|b| b.name == name)
+        .and_then(|b| match &b.content {
+            BlockContent::Pairs(pairs) => Some(pairs.as_slice()),
+            BlockContent::Text(_) => None,
+        })
+        .unwrap_or(&[])
+}
+
+fn block_text<'a>(blocks: &'a [Block], name: &str) -> Option<&'a str> {
+    blocks.iter().find(|b| b.name == name).and_then(|b| match &b.content {
+        BlockContent::Text(text) => Some(text.as_str()),
+        BlockContent::Pairs(_) => None,
+    })
+}
+
+fn value<'a>(pairs: &'a [(String, String, bool)], key: &str) -> Option<&'a str> {
+    pairs.iter().find(|(k, _, _)| k == key).map(|(_, v, _)| v.as_str())
+}
+
+// Auth is stored as `auth:<type>`; the type is everything after the colon
+fn auth_block(blocks: &[Block]) -> Option<(String, String)> {
+    blocks.iter().find_map(|block| match (&block.content, block.name.strip_prefix("auth:")) {
+        (BlockContent::Text(text), Some(auth_type)) => Some((auth_type.to_string(), text.clone())),
+        _ => None,
+    })
+}
+
+const METHODS: [&str; 7] = ["get", "post", "put", "patch", "delete", "head", "options"];
+
+// Returns the request and its `seq`, which orders requests within the collection
+pub fn parse_request(text: &str) -> Result<(JsonRequest, usize)> {
+    let blocks = parse_blocks(text)?;
+    let meta = block_pairs(&blocks, "meta");
+    let call = blocks
+        .iter()
+        .find(|b| METHODS.contains(&b.name.as_str()))
+        .ok_or_else(|| anyhow::anyhow!("No request block (get, post, ...) found"))?;
+    let BlockContent::Pairs(call_pairs) = &call.content else {
+        return Err(anyhow::anyhow!("Request block `{}` must hold key/value lines", call.name));
+    };
+
+    let body_type = value(call_pairs, "body").unwrap_or("none").to_string();
+    let params: Vec<QueryParam> = block_pairs(&blocks, "params:query")
+        .iter()
+        .map(|(key, value, enabled)| QueryParam {
+            key: key.clone(),
+            value: value.clone(),
+            enabled: *enabled,
+        })
+        .collect();
+    let path_params: BTreeMap<&str, &str> =
+        block_pairs(&blocks, "params:path").iter().map(|(k, v, _)| (k.as_str(), v.as_str())).collect();
+    let headers: BTreeMap<&str, &str> = block_pairs(&blocks, "headers")
+        .iter()
+        .filter(|(_, _, enabled)| *enabled)
+        .map(|(k, v, _)| (k.as_str(), v.as_str()))
+        .collect();
+    let (auth_type, auth_data) = match auth_block(&blocks) {
+        Some((auth_type, auth_data)) => (Some(auth_type), Some(auth_data)),
+        None => (value(call_pairs, "auth").map(str::to_string), None),
+    };
+
+    let request = JsonRequest {
+        name: value(meta, "name").unwrap_or("Untitled").to_string(),
+        method: call.name.to_uppercase(),
+        url: value(call_pairs, "url").unwrap_or("").to_string(),
+        params: serde_json::to_string(&params)?,
+        headers: serde_json::to_string(&headers)?,
+        path_params: serde_json::to_string(&path_params)?,
+        body_str: block_text(&blocks, &format!("body:{}", body_type)).map(str::to_string),
+        body_type,
+        graphql_variables: block_text(&blocks, "body:graphql:vars").map(str::to_string),
+        graphql_operation_name: value(meta, "operation").map(str::to_string),
+        grpc_config: block_text(&blocks, "grpc").map(str::to_string),
+        captures: block_text(&blocks, "captures").map(str::to_string),
+        scripts: block_text(&blocks, "scripts").map(str::to_string),
+        settings: block_text(&blocks, "settings").map(str::to_string),
+        docs: block_text(&blocks, "docs").map(str::to_string),
+        auth_type,
+        auth_data,
+    };
+    let seq = value(meta, "seq").and_then(|seq| seq.trim().parse().ok()).unwrap_or(usize::MAX);
+    Ok((request, seq))
+}
+
+// File names only need to be readable and unique within the folder
+fn file_name(name: &str, used: &mut HashSet<String>) -> String {
+    let mut stem: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | ' ' | '.') { c } else { '-' })
+        .collect::<String>()
+        .trim()
+        .trim_matches('.')
+        .to_string();
+    if stem.is_empty() || stem.eq_ignore_ascii_case("collection") {
+        stem = "request".to_string();
+    }
+
+    let mut candidate = stem.clone();
+    let mut counter = 2;
+    while !used.insert(candidate.to_lowercase()) {
+        candidate = format!("{} {}", stem, counter);
+        counter += 1;
+    }
+    format!("{}.bru", candidate)
+}
+
+// File name -> text for each file of a collection's folder
+pub fn collection_files(collection: &JsonCollection) -> Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    files.insert(COLLECTION_FILE.to_string(), collection_to_bru(collection));
+    let mut used = HashSet::new();
+    for (index, request) in collection.requests.iter().enumerate() {
+        files.insert(file_name(&request.name, &mut used), request_to_bru(request, index + 1)?);
+    }
+    Ok(files)
+}
+
+// 🎓 TEACHING: Writing over a previous export replaces its .bru files, so renamed or
+// deleted requests don't linger. Anything else in the folder (README, .git) is left alone.
+pub fn export_to_directory(collection: &JsonCollection, dir: &Path) -> Result<usize> {
+    if dir.join(COLLECTION_FILE).exists() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "bru") {
+                std::fs::remove_file(path)?;
+            }
+        }
+    } else if dir.exists() && std::fs::read_dir(dir)?.next().is_some() {
+        return Err(anyhow::anyhow!("{} is not empty and does not hold a collection", dir.display()));
+    }
+    std::fs::create_dir_all(dir)?;
+
+    for (name, text) in collection_files(collection)? {
+        std::fs::write(dir.join(name), text)?;
+    }
+    Ok(collection.requests.len())
+}
+
+// Requests may be spread over subfolders; they are all read into one collection
+pub fn import_from_directory(dir: &Path) -> Result<JsonCollection> {
+    let mut paths = Vec::new();
+    collect_bru_files(dir, &mut paths)?;
+    let mut files = BTreeMap::new();
+    for path in paths {
+        let name = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().to_string();
+        files.insert(name, std::fs::read_to_string(&path)?);
+    }
+    let fallback_name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Imported collection".to_string());
+    parse_collection_files(&files, &fallback_name).map_err(|e| anyhow::anyhow!("{}: {}", dir.display(), e))
+}
+
+// The reverse of collection_files; without a collection.bru the collection gets `fallback_name`
+pub fn parse_collection_files(files: &BTreeMap<String, String>, fallback_name: &str) -> Result<JsonCollection> {
+    let (name, description, auth) = match files.get(COLLECTION_FILE) {
+        Some(text) => {
+            let blocks = parse_blocks(text).map_err(|e| anyhow::anyhow!("{}: {}", COLLECTION_FILE, e))?;
+            let name = value(block_pairs(&blocks, "meta"), "name").map(str::to_string);
+            (name, block_text(&blocks, "docs").map(str::to_string), auth_block(&blocks))
+        }
+        None => (None, None, None),
+    };
+
+    let mut requests = Vec::new();
+    for (file, text) in files.iter().filter(|(file, _)| *file != COLLECTION_FILE) {
+        let (request, seq) = parse_request(text).map_err(|e| anyhow::anyhow!("{}: {}", file, e))?;
+        requests.push((seq, request));
+    }
+    requests.sort_by_key(|(seq, _)| *seq);
+
+    let (auth_type, auth_data) = auth.unzip();
+    Ok(JsonCollection {
+        schema_version: CURRENT_SCHEMA_VERSION,
+        name: name.unwrap_or_else(|| fallback_name.to_string()),
+        description,
+        docs: None,
+        auth_type,
+        auth_data,
+        requests: requests.into_iter().map(|(_, request)| request).collect(),
+    })
+}
+
+fn collect_bru_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_bru_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "bru") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_request() -> JsonRequest {
+        JsonRequest {
+            name: "Create user".to_string(),
+            method: "POST".to_string(),
+            url: "{{base_url}}/users/:team".to_string(),
+            params: r#"[{"key":"notify","value":"true","enabled":true},{"key":"debug","value":"1","enabled":false}]"#.to_string(),
+            headers: r#"{"Content-Type":"application/json"}"#.to_string(),
+            path_params: r#"{"team":"core"}"#.to_string(),
+            body_type: "json".to_string(),
+            body_str: Some("{\n  \"name\": \"Ada\",\n\n  \"nested\": {\n    \"x\": 1\n  }\n}".to_string()),
+            graphql_variables: None,
+            graphql_operation_name: None,
+            grpc_config: None,
+            captures: None,
+            scripts: None,
+            settings: None,
+            docs: Some("Creates a user in `team`.\n\n- name is required".to_string()),
+            auth_type: Some("bearer".to_string()),
+            auth_data: Some(r#"{"token":"{{token}}"}"#.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let original = sample_request();
+        let text = request_to_bru(&original, 3).unwrap();
+        assert!(text.contains("post {\n  url: {{base_url}}/users/:team\n"));
+        assert!(text.contains("  ~debug: 1\n"));
+
+        let (parsed, seq) = parse_request(&text).unwrap();
+        assert_eq!(seq, 3);
+        assert_eq!(parsed.name, original.name);
+        assert_eq!(parsed.method, "POST");
+        assert_eq!(parsed.url, original.url);
+        assert_eq!(parsed.params, original.params);
+        assert_eq!(parsed.headers, original.headers);
+        assert_eq!(parsed.path_params, original.path_params);
+        assert_eq!(parsed.body_str, original.body_str);
+        assert_eq!(parsed.docs, original.docs);
+        assert_eq!(parsed.auth_type.as_deref(), Some("bearer"));
+        let auth: serde_json::Value = serde_json::from_str(parsed.auth_data.as_deref().unwrap()).unwrap();
+        assert_eq!(auth["token"], "{{token}}");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_request("meta {\n  name: x\n").is_err());
+        assert!(parse_request("meta {\n  name: x\n}\n").is_err());
+        assert!(parse_request("get {\n  url\n}\n").is_err());
+    }
+
+    #[test]
+    fn test_directory_round_trip() {
+        let dir = std::env::temp_dir().join(format!("bru-{}", uuid::Uuid::new_v4()));
+        let mut second = sample_request();
+        second.name = "Create user".to_string(); // Duplicate names still get their own file
+        second.method = "GET".to_string();
+        second.body_type = "none".to_string();
+        second.body_str = None;
+        let collection = JsonCollection {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            name: "Users API".to_string(),
+            description: Some("Everything about users".to_string()),
+            docs: None,
+            auth_type: None,
+            auth_data: None,
+            requests: vec![sample_request(), second],
+        };
+
+        assert_eq!(export_to_directory(&collection, &dir).unwrap(), 2);
+        assert!(dir.join("Create user 2.bru").exists());
+        // Exporting again replaces the previous files
+        assert_eq!(export_to_directory(&collection, &dir).unwrap(), 2);
+
+        let imported = import_from_directory(&dir).unwrap();
+        assert_eq!(imported.name, "Users API");
+        assert_eq!(imported.description.as_deref(), Some("Everything about users"));
+        let methods: Vec<&str> = imported.requests.iter().map(|r| r.method.as_str()).collect();
+        assert_eq!(methods, vec!["POST", "GET"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+// 🎓 TEACHING: Cache maintenance
+// While the app is open, a background task tidies the response cache every MAINTENANCE_TICK:
+// expired entries are dropped, the oldest entries go once the cache grows past its size
+// limit (AppSettings::cache_max_bytes), bodies nothing uses any more are deleted, and a
+// `cache-stats` event tells the UI what's left.
+// Nobody has to remember to press "clear expired" any more.
+//
+// The same pass prunes history past the retention policy (AppSettings::history_retention).
+
+use crate::database::Database;
+use crate::settings::AppSettings;
+use crate::DatabaseState;
+use anyhow::Result;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const MAINTENANCE_TICK: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CacheStatsEvent {
+    pub entries: u64,
+    pub expired_entries: u64,
+    pub size_bytes: u64,
+    pub max_bytes: u64,
+    pub cleared_expired: u64, // Removed by this pass because they expired
+    pub evicted: u64,         // Removed by this pass to get under the size limit
+}
+
+// Start the background task; called once from the app's setup
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticks = tokio::time::interval(MAINTENANCE_TICK);
+        loop {
+            ticks.tick().await;
+            let db = {
+                let db_state = app.state::<DatabaseState>();
+                let db_guard = db_state.lock().unwrap();
+                db_guard.as_ref().cloned()
+            };
+            let Some(db) = db else { continue }; // Not opened yet
+
+            // A settings file that can't be read shouldn't stop the cleanup
+            let settings = crate::app_dirs(&app)
+                .ok()
+                .and_then(|(config_dir, _)| AppSettings::load(&config_dir).ok())
+                .unwrap_or_default();
+            match run(&db, settings.cache_max_bytes()).await {
+                Ok(stats) => {
+                    let _ = app.emit("cache-stats", &stats);
+                }
+                Err(e) => crate::logging::warn(format!("Cache maintenance: {}", e)),
+            }
+
+            let retention = &settings.history_retention;
+            match db.prune_history(retention.max_entries, retention.max_age(), retention.max_bytes).await {
+                Ok(0) => {}
+                Ok(pruned) => crate::logging::debug(format!("Pruned {} history entries", pruned)),
+                Err(e) => crate::logging::warn(format!("History pruning: {}", e)),
+            }
+        }
+    });
+}
+
+// One maintenance pass
+pub async fn run(db: &Database, max_bytes: u64) -> Result<CacheStatsEvent> {
+    let cleared_expired = db.clear_expired_cache().await?;
+    let evicted = db.trim_cache(max_bytes).await?;
+    db.delete_orphaned_blobs().await?;
+    let (entries, expired_entries) = db.get_cache_stats().await?;
+    Ok(CacheStatsEvent {
+        entries,
+        expired_entries,
+        size_bytes: db.get_cache_size_bytes().await?,
+        max_bytes,
+        cleared_expired,
+        evicted,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    async fn cache(db: &Database, url: &str, body: &str, seconds: Option<u64>) {
+        db.cache_response(
+            "GET".to_string(),
+            url.to_string(),
+            "{}".to_string(),
+            String::new(),
+            200,
+            "{}".to_string(),
+            body.to_string(),
+            seconds,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_clears_expired_and_trims_to_size() {
+        let dir = std::env::temp_dir().join(format!("cache-maintenance-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(&crate::settings::database_url(&dir.join("cache.db")), 1).await.unwrap();
+
+        cache(&db, "https://api.example.com/old", &"o".repeat(5000), None).await;
+        let old_bytes = db.get_cache_size_bytes().await.unwrap();
+        cache(&db, "https://api.example.com/new", &"n".repeat(3000), Some(3600)).await;
+        let new_bytes = db.get_cache_size_bytes().await.unwrap() - old_bytes;
+        cache(&db, "https://api.example.com/stale", "expired", Some(0)).await;
+
+        // Room for the newest entry only
+        let stats = run(&db, new_bytes).await.unwrap();
+        assert_eq!((stats.cleared_expired, stats.evicted), (1, 1));
+        assert_eq!((stats.entries, stats.expired_entries), (1, 0));
+        assert_eq!((stats.size_bytes, stats.max_bytes), (new_bytes, new_bytes));
+        // The pass already removed the bodies it left unused
+        assert_eq!(db.delete_orphaned_blobs().await.unwrap(), 0);
+
+        let again = run(&db, new_bytes).await.unwrap();
+        assert_eq!((again.cleared_expired, again.evicted, again.entries), (0, 0, 1));
+
+        db.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+// 🎓 TEACHING: Cache policies
+// Rules that turn response caching on (or off) for whole hosts or collections, so a request
+// doesn't have to opt in by itself. A request that sets `use_cache` keeps its own choice.
+// When several rules match, the nearest collection wins (a folder before its parents), then
+// the most specific host pattern.
+
+use crate::database::CachePolicy;
+use anyhow::Result;
+
+// `api.example.com` matches that host only; `*.example.com` matches its subdomains; `*` matches any host
+pub fn normalize_host_pattern(pattern: &str) -> Result<String> {
+    let pattern = pattern.trim().to_lowercase();
+    // A fully qualified `example.com.` is the same host, but `*.` is not a pattern for every host
+    let pattern = match pattern.strip_suffix('.') {
+        Some(trimmed) if trimmed != "*" => trimmed.to_string(),
+        _ => pattern,
+    };
+    let host = pattern.strip_prefix("*.").unwrap_or(&pattern);
+    let valid = pattern == "*"
+        || (!host.is_empty()
+            && host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_'));
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid host pattern '{}': use a host name like api.example.com, *.example.com or *",
+            pattern
+        ));
+    }
+    Ok(pattern)
+}
+
+pub fn host_matches(pattern: &str, host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    if pattern == "*" {
+        return true;
+    }
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.')),
+        None => host == pattern,
+    }
+}
+
+// Longer patterns are more specific; an exact host beats a wildcard of the same length
+pub fn specificity(pattern: &str) -> (usize, bool) {
+    (pattern.trim_start_matches("*.").len(), !pattern.starts_with('*'))
+}
+
+// `collections` runs from the request's own collection up to its root
+pub fn select<'a>(policies: &'a [CachePolicy], host: &str, collections: &[String]) -> Option<&'a CachePolicy> {
+    let enabled = || policies.iter().filter(|policy| policy.enabled);
+    collections
+        .iter()
+        .find_map(|id| enabled().find(|policy| policy.collection_id.as_ref() == Some(id)))
+        .or_else(|| {
+            enabled()
+                .filter_map(|policy| Some((policy, policy.host_pattern.as_deref()?)))
+                .filter(|(_, pattern)| host_matches(pattern, host))
+                .max_by_key(|(_, pattern)| specificity(pattern))
+                .map(|(policy, _)| policy)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn policy(host_pattern: Option<&str>, collection_id: Option<&str>, ttl_seconds: u64) -> CachePolicy {
+        CachePolicy {
+            id: ttl_seconds.to_string(),
+            host_pattern: host_pattern.map(str::to_string),
+            collection_id: collection_id.map(str::to_string),
+            enabled: true,
+            ttl_seconds: Some(ttl_seconds),
+            bypass: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_host_patterns() {
+        assert_eq!(normalize_host_pattern(" *.Example.com. ").unwrap(), "*.example.com");
+        assert!(normalize_host_pattern("https://example.com/").is_err());
+        assert!(normalize_host_pattern("*.").is_err());
+
+        assert!(host_matches("*.example.com", "api.example.com"));
+        assert!(host_matches("*.example.com", "a.b.Example.com"));
+        assert!(!host_matches("*.example.com", "example.com"));
+        assert!(!host_matches("*.example.com", "badexample.com"));
+        assert!(host_matches("example.com", "EXAMPLE.com."));
+        assert!(host_matches("*", "localhost"));
+    }
+
+    #[test]
+    fn test_select() {
+        let mut policies = vec![
+            policy(Some("*"), None, 1),
+            policy(Some("*.example.com"), None, 2),
+            policy(Some("api.example.com"), None, 3),
+            policy(None, Some("root"), 4),
+            policy(None, Some("folder"), 5),
+        ];
+        let ttl = |policies: &[CachePolicy], host: &str, collections: &[&str]| {
+            let collections: Vec<String> = collections.iter().map(|id| id.to_string()).collect();
+            select(policies, host, &collections).and_then(|policy| policy.ttl_seconds)
+        };
+
+        assert_eq!(ttl(&policies, "api.example.com", &[]), Some(3));
+        assert_eq!(ttl(&policies, "www.example.com", &[]), Some(2));
+        assert_eq!(ttl(&policies, "localhost", &["other"]), Some(1));
+        assert_eq!(ttl(&policies, "api.example.com", &["folder", "root"]), Some(5));
+        assert_eq!(ttl(&policies, "api.example.com", &["other", "root"]), Some(4));
+
+        // Switched-off rules are skipped
+        policies[4].enabled = false;
+        policies[2].enabled = false;
+        assert_eq!(ttl(&policies, "api.example.com", &["folder", "root"]), Some(4));
+        assert_eq!(ttl(&policies, "api.example.com", &[]), Some(2));
+        assert_eq!(ttl(&[], "api.example.com", &[]), None);
+    }
+}
@@ -0,0 +1,146 @@
+// 🎓 TEACHING: Response captures
+// Rules saved with a request that pull values out of its response and write them into
+// variables - the login call's token, the id a create call returned - so the next request
+// can use `{{token}}` / `{{user_id}}` without any scripting.
+
+use crate::session::{extract_json_path, find_cookie, find_header};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CaptureRule {
+    pub source: String,     // "json" (JSON path into the body), "header", "regex" (on the body), or "cookie"
+    pub expression: String, // `$.data.id`, a header or cookie name, or a regex (its first group, if any)
+    pub variable: String,   // Variable to write in the active environment
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CaptureResult {
+    pub variable: String,
+    pub value: Option<String>, // None when nothing matched (the variable is left alone)
+    pub error: Option<String>,
+}
+
+// Stored as JSON in the `requests.captures` column
+pub fn parse_rules(json: Option<&str>) -> Result<Vec<CaptureRule>> {
+    match json.map(str::trim).filter(|json| !json.is_empty()) {
+        Some(json) => Ok(serde_json::from_str(json)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn capture(rule: &CaptureRule, headers: &HashMap<String, String>, body: &str) -> Result<Option<String>> {
+    let value = match rule.source.as_str() {
+        "json" => {
+            let json: serde_json::Value =
+                serde_json::from_str(body).map_err(|e| anyhow::anyhow!("Response is not JSON: {}", e))?;
+            match extract_json_path(&json, &rule.expression) {
+                Some(serde_json::Value::String(s)) => Some(s.clone()),
+                Some(other) => Some(other.to_string()),
+                None => None,
+            }
+        }
+        "header" => find_header(headers, &rule.expression).map(str::to_string),
+        "cookie" => find_cookie(headers, &rule.expression),
+        "regex" => {
+            let regex = regex::Regex::new(&rule.expression)?;
+            regex.captures(body).and_then(|captures| {
+                captures
+                    .get(1)
+                    .or_else(|| captures.get(0))
+                    .map(|m| m.as_str().to_string())
+            })
+        }
+        other => return Err(anyhow::anyhow!("Unsupported capture source: {}", other)),
+    };
+    Ok(value)
+}
+
+// Run every enabled rule against a response. A failing rule doesn't stop the others.
+pub fn apply(rules: &[CaptureRule], headers: &HashMap<String, String>, body: &str) -> Vec<CaptureResult> {
+    rules
+        .iter()
+        .filter(|rule| rule.enabled)
+        .map(|rule| {
+            let (value, error) = match capture(rule, headers, body) {
+                Ok(value) => (value, None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            CaptureResult {
+                variable: rule.variable.clone(),
+                value,
+                error,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(source: &str, expression: &str, variable: &str) -> CaptureRule {
+        CaptureRule {
+            source: source.to_string(),
+            expression: expression.to_string(),
+            variable: variable.to_string(),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut headers = HashMap::new();
+        headers.insert("Location".to_string(), "/users/42".to_string());
+        headers.insert("set-cookie".to_string(), "sid=abc; Path=/".to_string());
+        let body = r#"{"data": {"id": 42, "token": "t0k"}}"#;
+
+        let mut disabled = rule("json", "$.data.id", "skipped");
+        disabled.enabled = false;
+        let rules = vec![
+            rule("json", "$.data.token", "token"),
+            rule("json", "$.data.id", "user_id"),
+            rule("header", "location", "location"),
+            rule("cookie", "sid", "session"),
+            rule("regex", r#""id":\s*(\d+)"#, "from_regex"),
+            rule("json", "$.data.missing", "missing"),
+            rule("regex", "(", "bad_regex"),
+            disabled,
+        ];
+        let results = apply(&rules, &headers, body);
+        let values: Vec<(&str, Option<&str>)> = results
+            .iter()
+            .map(|r| (r.variable.as_str(), r.value.as_deref()))
+            .collect();
+
+        assert_eq!(
+            values,
+            vec![
+                ("token", Some("t0k")),
+                ("user_id", Some("42")),
+                ("location", Some("/users/42")),
+                ("session", Some("abc")),
+                ("from_regex", Some("42")),
+                ("missing", None),
+                ("bad_regex", None),
+            ]
+        );
+        assert!(results[6].error.is_some());
+        assert!(results[5].error.is_none());
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert!(parse_rules(None).unwrap().is_empty());
+        assert!(parse_rules(Some("  ")).unwrap().is_empty());
+        let rules = parse_rules(Some(r#"[{"source":"header","expression":"ETag","variable":"etag"}]"#)).unwrap();
+        assert!(rules[0].enabled);
+    }
+}
@@ -0,0 +1,53 @@
+// 🎓 TEACHING: Compressed bodies
+// Cached responses and proxy recordings can be large, and JSON compresses very well, so
+// bodies are zstd-compressed on the way into the database and expanded on the way out.
+// Short bodies aren't worth it and are stored as plain UTF-8. Compressed data is recognised
+// by the zstd frame's magic number, so rows written before compression still read fine.
+
+use anyhow::Result;
+
+// Below this, compression saves too little to matter
+const MIN_COMPRESSED_LEN: usize = 1024;
+const LEVEL: i32 = 3;
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+pub fn compress_body(body: &str) -> Vec<u8> {
+    if body.len() < MIN_COMPRESSED_LEN {
+        return body.as_bytes().to_vec();
+    }
+    match zstd::encode_all(body.as_bytes(), LEVEL) {
+        Ok(compressed) if compressed.len() < body.len() => compressed,
+        _ => body.as_bytes().to_vec(), // Already compressed or random data
+    }
+}
+
+// Text never starts with the magic number: 0xB5 can't follow 0x28 in UTF-8
+pub fn decompress_body(stored: Vec<u8>) -> Result<String> {
+    let bytes = if stored.starts_with(&ZSTD_MAGIC) {
+        zstd::decode_all(stored.as_slice())?
+    } else {
+        stored
+    };
+    Ok(String::from_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let short = r#"{"ok":true}"#;
+        assert_eq!(compress_body(short), short.as_bytes());
+        assert_eq!(decompress_body(compress_body(short)).unwrap(), short);
+
+        let long = format!("[{}]", vec![r#"{"id":1,"name":"Ada Lovelace","tags":["math"]}"#; 200].join(","));
+        let compressed = compress_body(&long);
+        assert!(compressed.starts_with(&ZSTD_MAGIC));
+        assert!(compressed.len() < long.len() / 10);
+        assert_eq!(decompress_body(compressed).unwrap(), long);
+
+        // Rows written before compression are plain text
+        assert_eq!(decompress_body("(µ plain".as_bytes().to_vec()).unwrap(), "(µ plain");
+    }
+}
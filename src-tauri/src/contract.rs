@@ -0,0 +1,579 @@
+// 🎓 TEACHING: Contract testing
+// Runs a collection and checks every response against the OpenAPI spec the API is meant to
+// follow: is the status code documented for that operation, are the documented required
+// headers and content type there, and does the JSON body match the response schema?
+//
+// Each saved request is matched to an operation by method and path. Path templates match
+// any segment (`/users/{id}` matches `/users/42`), and so do the request's own `:id`,
+// `{id}` and `{{var}}` segments; when several operations match, the one with the most
+// literal segments wins, so `/users/me` beats `/users/{id}`.
+//
+// The checks become ordinary test results named "Contract: ...", so they show up in the
+// run history and in JUnit/HTML reports like any script test.
+
+use crate::database::{CollectionRun, Database, Request};
+use crate::error::AppError;
+use crate::openapi::{self, Operation};
+use crate::runner::{self, ResponseCheck, RunOptions, RunRequestResult};
+use crate::scripting::TestResult;
+use crate::session::SessionCache;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+pub const TEST_PREFIX: &str = "Contract: ";
+
+// Enough to see what's wrong without flooding the report for a badly broken body
+const MAX_SCHEMA_ERRORS: usize = 20;
+const MAX_SCHEMA_DEPTH: usize = 32;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ContractResult {
+    pub iteration: usize,
+    pub request_id: String,
+    pub name: String,
+    pub method: String,
+    pub operation: Option<String>, // e.g. "GET /users/{id}"; None if nothing in the spec matches
+    pub status: Option<u16>,
+    pub error: Option<String>, // Set if the request couldn't be sent (so nothing was checked)
+    pub violations: Vec<String>,
+    pub conforms: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ContractReport {
+    pub spec_title: Option<String>,
+    pub run: CollectionRun,
+    pub results: Vec<ContractResult>,
+    pub conforming: usize,
+    pub untested_operations: Vec<String>, // Documented operations no request in the run hit
+}
+
+pub struct Contract {
+    spec: Value,
+    base_path: String,
+    operations: Vec<Operation>,
+}
+
+impl Contract {
+    pub fn parse(spec_text: &str) -> Result<Self> {
+        let spec = openapi::parse_spec(spec_text)?;
+        Ok(Contract {
+            base_path: openapi::base_path(&spec),
+            operations: openapi::operations(&spec),
+            spec,
+        })
+    }
+
+    // The operation a saved request calls, by method and URL path
+    pub fn find_operation(&self, method: &str, url: &str) -> Option<&Operation> {
+        let path = url_path(url);
+        let mut candidates = vec![path.clone()];
+        if let Some(relative) = path.strip_prefix(&self.base_path).filter(|_| !self.base_path.is_empty()) {
+            candidates.push(relative.to_string());
+        }
+
+        self.operations
+            .iter()
+            .filter(|operation| operation.method.eq_ignore_ascii_case(method))
+            .filter_map(|operation| {
+                candidates
+                    .iter()
+                    .filter_map(|candidate| path_match_score(&operation.path, candidate))
+                    .max()
+                    .map(|score| (score, operation))
+            })
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, operation)| operation)
+    }
+
+    // One test result per kind of check, failed ones carrying what was wrong
+    pub fn check(
+        &self,
+        method: &str,
+        url: &str,
+        status: u16,
+        headers: &HashMap<String, String>,
+        body: &str,
+    ) -> Vec<TestResult> {
+        let Some(operation) = self.find_operation(method, url) else {
+            return vec![test(
+                "operation is documented",
+                vec![format!("No operation in the spec matches {} {}", method, url_path(url))],
+            )];
+        };
+        let label = operation_label(operation);
+        let Some(documented) = response_for_status(operation, status) else {
+            return vec![test(
+                &format!("{} documents status {}", label, status),
+                vec![format!("Status {} is not documented for {}", status, label)],
+            )];
+        };
+
+        let mut tests = vec![test(&format!("{} documents status {}", label, status), Vec::new())];
+        tests.push(test("response headers", self.header_violations(documented, headers, body)));
+        if let Some(schema) = openapi::response_body(&self.spec, documented).and_then(|body| body.schema) {
+            tests.push(test("response body matches the schema", self.body_violations(&schema, headers, body)));
+        }
+        tests
+    }
+
+    fn header_violations(&self, documented: &Value, headers: &HashMap<String, String>, body: &str) -> Vec<String> {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let mut violations = Vec::new();
+
+        if let Some(headers) = documented.get("headers").and_then(Value::as_object) {
+            for (name, definition) in headers {
+                let definition = openapi::resolve(&self.spec, definition);
+                let required = definition.get("required").and_then(Value::as_bool).unwrap_or(false);
+                if required && header(name).is_none() {
+                    violations.push(format!("Missing required header {}", name));
+                }
+            }
+        }
+
+        // OpenAPI 3 lists the media types a response may have
+        if let Some(content) = documented.get("content").and_then(Value::as_object) {
+            if let Some(actual) = header("content-type").filter(|_| !body.is_empty()) {
+                let media_type = actual.split(';').next().unwrap_or("").trim().to_lowercase();
+                if !content.keys().any(|documented| media_type_matches(documented, &media_type)) {
+                    let expected: Vec<&str> = content.keys().map(String::as_str).collect();
+                    violations.push(format!(
+                        "Content-Type {} is not one of {}",
+                        media_type,
+                        expected.join(", ")
+                    ));
+                }
+            }
+        }
+        violations
+    }
+
+    fn body_violations(&self, schema: &Value, headers: &HashMap<String, String>, body: &str) -> Vec<String> {
+        if body.trim().is_empty() {
+            return vec!["The response has no body but the spec documents one".to_string()];
+        }
+        let body: Value = match serde_json::from_str(body) {
+            Ok(body) => body,
+            // Only JSON bodies are checked against the schema
+            Err(_) if !is_json(headers) => return Vec::new(),
+            Err(e) => return vec![format!("The body is not valid JSON: {}", e)],
+        };
+        let mut errors = Vec::new();
+        validate(&self.spec, schema, &body, "$", &mut errors, 0);
+        if errors.len() > MAX_SCHEMA_ERRORS {
+            let more = errors.len() - MAX_SCHEMA_ERRORS;
+            errors.truncate(MAX_SCHEMA_ERRORS);
+            errors.push(format!("...and {} more", more));
+        }
+        errors
+    }
+
+    pub fn operation_labels(&self) -> Vec<String> {
+        self.operations.iter().map(operation_label).collect()
+    }
+}
+
+fn test(name: &str, violations: Vec<String>) -> TestResult {
+    TestResult {
+        name: format!("{}{}", TEST_PREFIX, name),
+        passed: violations.is_empty(),
+        error: (!violations.is_empty()).then(|| violations.join("; ")),
+    }
+}
+
+fn operation_label(operation: &Operation) -> String {
+    format!("{} {}", operation.method, operation.path)
+}
+
+fn is_json(headers: &HashMap<String, String>) -> bool {
+    headers
+        .iter()
+        .any(|(key, value)| key.eq_ignore_ascii_case("content-type") && value.contains("json"))
+}
+
+// `application/*` and `*/*` are allowed in a spec's content map
+fn media_type_matches(documented: &str, actual: &str) -> bool {
+    let documented = documented.to_lowercase();
+    match documented.split_once('/') {
+        Some(("*", "*")) => true,
+        Some((kind, "*")) => actual.split('/').next() == Some(kind),
+        _ => documented == actual,
+    }
+}
+
+// The path part of a saved URL, which may start with `{{base_url}}` instead of a host
+fn url_path(url: &str) -> String {
+    let start = match url.find("://") {
+        Some(scheme_end) => url[scheme_end + 3..].find('/').map(|offset| scheme_end + 3 + offset),
+        None => url.find('/'),
+    };
+    let Some(start) = start else {
+        return "/".to_string();
+    };
+    let path = &url[start..];
+    let end = path.find(['?', '#']).unwrap_or(path.len());
+    path[..end].trim_end_matches('/').to_string()
+}
+
+fn is_placeholder(segment: &str) -> bool {
+    segment.starts_with(':') || (segment.starts_with('{') && segment.ends_with('}'))
+}
+
+// None if the paths don't match; otherwise how many segments matched literally
+fn path_match_score(template: &str, path: &str) -> Option<usize> {
+    let template: Vec<&str> = template.trim_end_matches('/').split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    if template.len() != path.len() {
+        return None;
+    }
+    let mut literal = 0;
+    for (expected, actual) in template.iter().zip(&path) {
+        if expected == actual {
+            literal += 1;
+        } else if !is_placeholder(expected) && !is_placeholder(actual) {
+            return None;
+        }
+    }
+    Some(literal)
+}
+
+// The documented response for a status: the exact code, then a range like 4XX, then default
+fn response_for_status(operation: &Operation, status: u16) -> Option<&Value> {
+    let exact = status.to_string();
+    let range = format!("{}XX", status / 100);
+    for key in [exact.as_str(), range.as_str(), "default"] {
+        if let Some((_, response)) = operation.responses.iter().find(|(code, _)| code.eq_ignore_ascii_case(key)) {
+            return Some(response);
+        }
+    }
+    None
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn allows_null(schema: &Value) -> bool {
+    schema.get("nullable").and_then(Value::as_bool).unwrap_or(false)
+        || schema.get("x-nullable").and_then(Value::as_bool).unwrap_or(false)
+        || schema
+            .get("type")
+            .and_then(Value::as_array)
+            .is_some_and(|kinds| kinds.iter().any(|kind| kind == "null"))
+}
+
+// 🎓 TEACHING: A JSON Schema check covering what API specs actually use: types (with
+// OpenAPI's `nullable`), enum, required and additional properties, items, and the usual
+// length and range limits. `oneOf` is checked like `anyOf`, since overlapping alternatives
+// are common in real specs. Formats and patterns aren't checked.
+pub fn validate(spec: &Value, schema: &Value, value: &Value, at: &str, errors: &mut Vec<String>, depth: usize) {
+    if depth > MAX_SCHEMA_DEPTH {
+        return;
+    }
+    let schema = openapi::resolve(spec, schema);
+
+    if value.is_null() && allows_null(schema) {
+        return;
+    }
+    if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+        for part in parts {
+            validate(spec, part, value, at, errors, depth + 1);
+        }
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(options) = schema.get(key).and_then(Value::as_array) {
+            let matches_one = options.iter().any(|option| {
+                let mut option_errors = Vec::new();
+                validate(spec, option, value, at, &mut option_errors, depth + 1);
+                option_errors.is_empty()
+            });
+            if !matches_one {
+                errors.push(format!("{}: matches none of the {} alternatives", at, key));
+            }
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(format!("{}: {} is not one of the allowed values", at, value));
+        }
+    }
+
+    let actual = type_name(value);
+    if let Some(expected) = openapi::schema_type(schema) {
+        let compatible = expected == actual || (expected == "number" && actual == "integer");
+        if !compatible {
+            errors.push(format!("{}: expected {}, got {}", at, expected, actual));
+            return;
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(name) {
+                        errors.push(format!("{}: missing required property {}", at, name));
+                    }
+                }
+            }
+            for (name, property_value) in object {
+                let property_at = format!("{}.{}", at, name);
+                match (properties.and_then(|properties| properties.get(name)), schema.get("additionalProperties")) {
+                    (Some(property), _) => validate(spec, property, property_value, &property_at, errors, depth + 1),
+                    (None, Some(Value::Bool(false))) => errors.push(format!("{}: unexpected property", property_at)),
+                    (None, Some(extra)) if extra.is_object() => {
+                        validate(spec, extra, property_value, &property_at, errors, depth + 1)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            let limit = |key: &str| schema.get(key).and_then(Value::as_u64);
+            if limit("minItems").is_some_and(|min| (items.len() as u64) < min) {
+                errors.push(format!("{}: fewer than {} items", at, limit("minItems").unwrap_or(0)));
+            }
+            if limit("maxItems").is_some_and(|max| items.len() as u64 > max) {
+                errors.push(format!("{}: more than {} items", at, limit("maxItems").unwrap_or(0)));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate(spec, item_schema, item, &format!("{}[{}]", at, index), errors, depth + 1);
+                }
+            }
+        }
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64).filter(|min| length < *min) {
+                errors.push(format!("{}: shorter than {} characters", at, min));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64).filter(|max| length > *max) {
+                errors.push(format!("{}: longer than {} characters", at, max));
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or(0.0);
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64).filter(|min| number < *min) {
+                errors.push(format!("{}: below the minimum of {}", at, min));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64).filter(|max| number > *max) {
+                errors.push(format!("{}: above the maximum of {}", at, max));
+            }
+        }
+        _ => {}
+    }
+}
+
+// Run the collection with the contract checks and summarise per request
+pub async fn run(
+    db: &Database,
+    session_cache: &SessionCache,
+    collection_id: &str,
+    spec_text: &str,
+    options: RunOptions,
+    progress: runner::ProgressSink,
+) -> Result<ContractReport, AppError> {
+    let contract = Arc::new(Contract::parse(spec_text).map_err(|e| AppError::validation(e.to_string()))?);
+    let check: ResponseCheck = {
+        let contract = contract.clone();
+        Arc::new(move |request, response| {
+            contract.check(&request.method, &request.url, response.status, &response.headers, &response.body)
+        })
+    };
+    let run = runner::run_collection(db, session_cache, collection_id, options, progress, Some(check)).await?;
+    let results: Vec<RunRequestResult> = serde_json::from_str(&run.results)?;
+
+    let mut requests: HashMap<String, Request> = HashMap::new();
+    for result in &results {
+        if !requests.contains_key(&result.request_id) {
+            if let Some(request) = db.get_request_by_id(&result.request_id).await? {
+                requests.insert(request.id.clone(), request);
+            }
+        }
+    }
+
+    let mut tested = HashSet::new();
+    let contract_results: Vec<ContractResult> = results
+        .into_iter()
+        .map(|result| {
+            let operation = requests
+                .get(&result.request_id)
+                .and_then(|request| contract.find_operation(&request.method, &request.url))
+                .map(operation_label);
+            if let Some(label) = &operation {
+                tested.insert(label.clone());
+            }
+            let violations: Vec<String> = result
+                .tests
+                .iter()
+                .filter(|test| test.name.starts_with(TEST_PREFIX) && !test.passed)
+                .filter_map(|test| test.error.clone())
+                .collect();
+            ContractResult {
+                iteration: result.iteration,
+                request_id: result.request_id,
+                name: result.name,
+                method: result.method,
+                operation,
+                status: result.status,
+                conforms: result.error.is_none() && violations.is_empty(),
+                error: result.error,
+                violations,
+            }
+        })
+        .collect();
+
+    Ok(ContractReport {
+        spec_title: openapi::title(&contract.spec),
+        conforming: contract_results.iter().filter(|result| result.conforms).count(),
+        untested_operations: contract
+            .operation_labels()
+            .into_iter()
+            .filter(|label| !tested.contains(label))
+            .collect(),
+        results: contract_results,
+        run,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r##"
+openapi: 3.0.3
+servers:
+  - url: https://api.example.com/v1
+paths:
+  /users/{id}:
+    get:
+      responses:
+        200:
+          description: A user
+          headers:
+            X-Rate-Limit:
+              required: true
+              schema: { type: integer }
+          content:
+            application/json:
+              schema:
+                $ref: '#/components/schemas/User'
+        4XX:
+          description: Client error
+  /users/me:
+    get:
+      responses:
+        200:
+          description: The current user
+components:
+  schemas:
+    User:
+      type: object
+      required: [id, name]
+      additionalProperties: false
+      properties:
+        id: { type: integer, minimum: 1 }
+        name: { type: string, minLength: 1 }
+        email: { type: string, nullable: true }
+        role: { type: string, enum: [admin, member] }
+        tags: { type: array, items: { type: string } }
+"##;
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_find_operation() {
+        let contract = Contract::parse(SPEC).unwrap();
+        let path = |method: &str, url: &str| contract.find_operation(method, url).map(|op| op.path.clone());
+        assert_eq!(path("GET", "{{base_url}}/users/:id"), Some("/users/{id}".to_string()));
+        assert_eq!(path("GET", "https://api.example.com/v1/users/42?full=1"), Some("/users/{id}".to_string()));
+        assert_eq!(path("GET", "{{base_url}}/users/me"), Some("/users/me".to_string()));
+        assert_eq!(path("GET", "{{base_url}}/users/{{userId}}"), Some("/users/{id}".to_string()));
+        assert_eq!(path("DELETE", "{{base_url}}/users/1"), None);
+        assert_eq!(path("GET", "{{base_url}}/orders/1"), None);
+    }
+
+    #[test]
+    fn test_check_conforming_response() {
+        let contract = Contract::parse(SPEC).unwrap();
+        let tests = contract.check(
+            "GET",
+            "{{base_url}}/users/:id",
+            200,
+            &headers(&[("content-type", "application/json; charset=utf-8"), ("x-rate-limit", "10")]),
+            r#"{"id": 1, "name": "Ada", "email": null, "tags": ["a"]}"#,
+        );
+        assert_eq!(tests.len(), 3);
+        assert!(tests.iter().all(|test| test.passed), "{:?}", tests);
+        assert!(tests[0].name.starts_with(TEST_PREFIX));
+    }
+
+    #[test]
+    fn test_check_violations() {
+        let contract = Contract::parse(SPEC).unwrap();
+        let tests = contract.check(
+            "GET",
+            "{{base_url}}/users/:id",
+            200,
+            &headers(&[("Content-Type", "text/html")]),
+            r#"{"id": 0, "role": "owner", "tags": [1], "extra": true}"#,
+        );
+        let header_errors = tests[1].error.as_deref().unwrap();
+        assert!(header_errors.contains("Missing required header X-Rate-Limit"));
+        assert!(header_errors.contains("Content-Type text/html"));
+        let body = tests[2].error.as_deref().unwrap();
+        assert!(body.contains("$: missing required property name"));
+        assert!(body.contains("$.id: below the minimum of 1"));
+        assert!(body.contains("$.role: \"owner\" is not one of the allowed values"));
+        assert!(body.contains("$.tags[0]: expected string, got integer"));
+        assert!(body.contains("$.extra: unexpected property"));
+
+        // 404 falls under 4XX; 500 isn't documented at all
+        let not_found = contract.check("GET", "{{base_url}}/users/9", 404, &headers(&[]), "");
+        assert!(not_found.iter().all(|test| test.passed));
+        let server_error = contract.check("GET", "{{base_url}}/users/9", 500, &headers(&[]), "");
+        assert_eq!(server_error.len(), 1);
+        assert!(!server_error[0].passed);
+
+        let unknown = contract.check("GET", "{{base_url}}/orders", 200, &headers(&[]), "");
+        assert_eq!(unknown[0].error.as_deref(), Some("No operation in the spec matches GET /orders"));
+    }
+
+    #[test]
+    fn test_validate_combinators() {
+        let spec = serde_json::json!({});
+        let schema = serde_json::json!({
+            "oneOf": [{ "type": "string" }, { "type": "object", "required": ["id"] }]
+        });
+        let mut errors = Vec::new();
+        validate(&spec, &schema, &serde_json::json!({"id": 1}), "$", &mut errors, 0);
+        validate(&spec, &schema, &serde_json::json!("x"), "$", &mut errors, 0);
+        assert!(errors.is_empty());
+        validate(&spec, &schema, &serde_json::json!(3), "$", &mut errors, 0);
+        assert_eq!(errors, vec!["$: matches none of the oneOf alternatives"]);
+
+        let mut errors = Vec::new();
+        let number = serde_json::json!({ "type": "number" });
+        validate(&spec, &number, &serde_json::json!(3), "$", &mut errors, 0);
+        assert!(errors.is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+// 🎓 TEACHING: Cookies
+// Set-Cookie headers are read into structured cookies (RFC 6265 section 5.2) so the response
+// view can show name, value, domain, path, expiry and flags instead of the raw header, and
+// so requests with `use_cookie_jar` can keep them and send them back like a browser would.
+//
+// A few browser rules are followed and the rest left out:
+//   - a Domain attribute the request host isn't inside makes the cookie be ignored
+//   - without Domain, the cookie goes back to exactly the host that set it ("host only")
+//   - Max-Age beats Expires; a past expiry or Max-Age <= 0 deletes the cookie
+//   - Secure cookies only go over https
+// There's no public suffix list, so a server could set a cookie for a whole TLD, and only
+// the final response's cookies are seen, not those set on the way through redirects.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String, // Lowercase, without a leading dot
+    pub path: String,
+    pub expires: Option<DateTime<Utc>>, // None for a session cookie
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: Option<String>, // "Strict", "Lax" or "None", as the server wrote it
+    pub host_only: bool,           // No Domain attribute: only sent to `domain` itself
+}
+
+impl Cookie {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    // 🎓 TEACHING: Whether a browser would send this cookie with a request to `url`
+    pub fn matches(&self, url: &url::Url, now: DateTime<Utc>) -> bool {
+        let Some(host) = url.host_str().map(|host| host.to_ascii_lowercase()) else {
+            return false;
+        };
+        let domain_ok = if self.host_only { host == self.domain } else { domain_matches(&host, &self.domain) };
+        let secure_ok = !self.secure || matches!(url.scheme(), "https" | "wss");
+        domain_ok && secure_ok && path_matches(url.path(), &self.path) && !self.is_expired(now)
+    }
+}
+
+// One Set-Cookie header from a response to `url`; None if a browser would ignore it
+pub fn parse_set_cookie(header: &str, url: &url::Url, now: DateTime<Utc>) -> Option<Cookie> {
+    let host = url.host_str()?.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+    let mut parts = header.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut cookie = Cookie {
+        name: name.to_string(),
+        value: value.trim().trim_matches('"').to_string(),
+        domain: host.clone(),
+        path: default_path(url.path()),
+        expires: None,
+        secure: false,
+        http_only: false,
+        same_site: None,
+        host_only: true,
+    };
+    let mut max_age = None;
+    for attribute in parts {
+        let (key, value) = match attribute.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => (attribute.trim(), ""),
+        };
+        match key.to_ascii_lowercase().as_str() {
+            "expires" => {
+                if let Some(expires) = parse_cookie_date(value) {
+                    cookie.expires = Some(expires);
+                }
+            }
+            "max-age" => {
+                if let Ok(seconds) = value.parse::<i64>() {
+                    max_age = Some(seconds);
+                }
+            }
+            "domain" if !value.is_empty() => {
+                let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                if !domain_matches(&host, &domain) {
+                    return None;
+                }
+                cookie.domain = domain;
+                cookie.host_only = false;
+            }
+            "path" if value.starts_with('/') => cookie.path = value.to_string(),
+            "secure" => cookie.secure = true,
+            "httponly" => cookie.http_only = true,
+            "samesite" if !value.is_empty() => cookie.same_site = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    if let Some(seconds) = max_age {
+        let expires = if seconds <= 0 { DateTime::UNIX_EPOCH } else { now + chrono::Duration::seconds(seconds) };
+        cookie.expires = Some(expires);
+    }
+    Some(cookie)
+}
+
+// Adds the jar's cookies to a Cookie header the request already has; its own values win
+pub fn merge_cookie_header(existing: &str, jar: &[Cookie]) -> String {
+    let existing = existing.trim().trim_end_matches(';');
+    let taken: Vec<&str> = existing.split(';').filter_map(|pair| pair.split('=').next()).map(str::trim).collect();
+    let mut pairs: Vec<String> =
+        existing.split(';').map(str::trim).filter(|pair| !pair.is_empty()).map(str::to_string).collect();
+    for cookie in jar {
+        if !taken.contains(&cookie.name.as_str()) {
+            pairs.push(format!("{}={}", cookie.name, cookie.value));
+        }
+    }
+    pairs.join("; ")
+}
+
+// `host` is `domain` or one of its subdomains; IP addresses only match themselves
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<std::net::IpAddr>().is_err())
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+// The request path up to its last slash: "/api/users/1" -> "/api/users"
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(end) => path[..end].to_string(),
+    }
+}
+
+// Servers still send all three of HTTP's historical date formats, with or without dashes
+fn parse_cookie_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc2822(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    let value = value.replace('-', " ");
+    let value = value.trim_end_matches("GMT").trim_end_matches("UTC").trim();
+    ["%a, %d %b %Y %H:%M:%S", "%A, %d %b %y %H:%M:%S", "%a %b %e %H:%M:%S %Y", "%d %b %Y %H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|date| date.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(text: &str) -> url::Url {
+        url::Url::parse(text).unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2026-01-01T00:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_set_cookie() {
+        let cookie = parse_set_cookie(
+            "sid=abc123; Domain=.Example.com; Path=/app; Expires=Wed, 21 Oct 2026 07:28:00 GMT; Secure; HttpOnly; SameSite=Lax",
+            &url("https://api.example.com/login"),
+            now(),
+        )
+        .unwrap();
+        assert_eq!((cookie.name.as_str(), cookie.value.as_str()), ("sid", "abc123"));
+        assert_eq!((cookie.domain.as_str(), cookie.path.as_str(), cookie.host_only), ("example.com", "/app", false));
+        assert_eq!(cookie.expires, Some("2026-10-21T07:28:00Z".parse().unwrap()));
+        assert!(cookie.secure && cookie.http_only);
+        assert_eq!(cookie.same_site.as_deref(), Some("Lax"));
+
+        // Defaults: this host only, the request's directory, a session cookie
+        let cookie = parse_set_cookie("theme=\"dark\"", &url("http://Localhost:8080/a/b/c"), now()).unwrap();
+        assert_eq!((cookie.value.as_str(), cookie.domain.as_str(), cookie.path.as_str()), ("dark", "localhost", "/a/b"));
+        assert!(cookie.host_only && cookie.expires.is_none());
+
+        // Max-Age wins over Expires, and dashed dates still parse
+        let cookie =
+            parse_set_cookie("a=1; Expires=Thu, 01-Jan-2099 00:00:00 GMT; Max-Age=60", &url("http://x.test/"), now());
+        assert_eq!(cookie.unwrap().expires, Some(now() + chrono::Duration::seconds(60)));
+        let cookie = parse_set_cookie("a=1; Expires=Thu, 01-Jan-2099 00:00:00 GMT", &url("http://x.test/"), now());
+        assert_eq!(cookie.unwrap().expires, Some("2099-01-01T00:00:00Z".parse().unwrap()));
+        assert!(parse_set_cookie("a=; Max-Age=0", &url("http://x.test/"), now()).unwrap().is_expired(now()));
+
+        // Ignored: no name, or a domain the host isn't part of
+        assert!(parse_set_cookie("novalue", &url("http://x.test/"), now()).is_none());
+        assert!(parse_set_cookie("=1", &url("http://x.test/"), now()).is_none());
+        assert!(parse_set_cookie("a=1; Domain=other.com", &url("http://x.test/"), now()).is_none());
+        assert!(parse_set_cookie("a=1; Domain=ample.com", &url("http://example.com/"), now()).is_none());
+    }
+
+    #[test]
+    fn test_matches() {
+        let cookie = parse_set_cookie("a=1; Domain=example.com; Path=/api; Secure", &url("https://example.com/"), now())
+            .unwrap();
+        assert!(cookie.matches(&url("https://example.com/api"), now()));
+        assert!(cookie.matches(&url("https://www.example.com/api/users"), now()));
+        assert!(!cookie.matches(&url("http://example.com/api"), now())); // Secure
+        assert!(!cookie.matches(&url("https://example.com/apis"), now()));
+        assert!(!cookie.matches(&url("https://badexample.com/api"), now()));
+
+        let host_only = parse_set_cookie("b=2", &url("http://example.com/x"), now()).unwrap();
+        assert!(host_only.matches(&url("http://example.com/y"), now()));
+        assert!(!host_only.matches(&url("http://www.example.com/"), now()));
+
+        let expiring = parse_set_cookie("c=3; Max-Age=10", &url("http://example.com/"), now()).unwrap();
+        assert!(!expiring.matches(&url("http://example.com/"), now() + chrono::Duration::seconds(10)));
+    }
+
+    #[test]
+    fn test_merge_cookie_header() {
+        let jar: Vec<Cookie> = ["a=jar", "b=2"]
+            .iter()
+            .map(|header| parse_set_cookie(header, &url("http://x.test/"), now()).unwrap())
+            .collect();
+        assert_eq!(merge_cookie_header("a=mine;", &jar), "a=mine; b=2");
+        assert_eq!(merge_cookie_header("", &jar), "a=jar; b=2");
+    }
+}
@@ -2,15 +2,255 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{Row, SqlitePool};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::{Connection, Row, SqlitePool};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 use uuid::Uuid;
 
+use crate::cache_policy;
+use crate::cookies::Cookie;
+use crate::compression;
+use crate::default_headers;
+use crate::error::{AppError, ErrorKind};
+use crate::faker;
+use crate::logging;
+use crate::placeholders;
+use crate::providers::{ProviderCache, VariableSource};
+use crate::rate_limit::{self, RateLimiter, SendPermit};
+use crate::redact::Redactor;
+use crate::secrets::{self, KeychainBackend, SecretBackend, SecretCipher, SecretEncryptionStatus};
+use crate::trace::{self, TraceContext};
+
+// Checks kept per monitor; older results are dropped as new ones come in
+const MAX_MONITOR_RESULTS: i64 = 500;
+// Timing samples kept per saved request for analytics
+const MAX_REQUEST_SAMPLES: i64 = 5000;
+// How long a connection waits for another one's write before giving up
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+// 🎓 TEACHING: A schema change applied once per database, in version order.
+// Never edit or renumber a migration that has shipped; add a new one instead.
+struct Migration {
+    version: i64,
+    description: &'static str,
+    statements: &'static [&'static str],
+    // SQLite can't change a table's constraints, only rebuild it. Dropping the old table with
+    // foreign keys on would cascade into its children, so those migrations run with them off
+    // and `foreign_key_check` confirms nothing was left dangling before committing.
+    rebuilds_tables: bool,
+}
+
+// Version 1 is the pre-versioning schema, built by `baseline_schema`
+const BASELINE_VERSION: i64 = 1;
+
+// Append new migrations here, starting at version 2
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 2,
+        description: "Sidebar sort order for collections and requests",
+        statements: &[
+            "ALTER TABLE collections ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0",
+            "ALTER TABLE requests ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0",
+            // Number existing siblings alphabetically, the order the sidebar showed until now
+            "UPDATE collections SET sort_order = (SELECT COUNT(*) FROM collections other WHERE other.parent_id IS collections.parent_id AND (other.name < collections.name OR (other.name = collections.name AND other.id < collections.id)))",
+            "UPDATE requests SET sort_order = (SELECT COUNT(*) FROM requests other WHERE other.collection_id = requests.collection_id AND (other.name < requests.name OR (other.name = requests.name AND other.id < requests.id)))",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 3,
+        description: "ON DELETE rules for foreign keys",
+        statements: &[
+            // Folders go with their parent; a parent that no longer exists becomes top level
+            "CREATE TABLE collections_new (id TEXT PRIMARY KEY, name TEXT NOT NULL, description TEXT, parent_id TEXT REFERENCES collections(id) ON DELETE CASCADE, auth_type TEXT, auth_data TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, sort_order INTEGER NOT NULL DEFAULT 0)",
+            "INSERT INTO collections_new (id, name, description, parent_id, auth_type, auth_data, created_at, updated_at, sort_order) SELECT id, name, description, CASE WHEN parent_id IN (SELECT id FROM collections) THEN parent_id END, auth_type, auth_data, created_at, updated_at, sort_order FROM collections",
+            "DROP TABLE collections",
+            "ALTER TABLE collections_new RENAME TO collections",
+            // Requests go with their collection
+            "CREATE TABLE requests_new (id TEXT PRIMARY KEY, collection_id TEXT NOT NULL REFERENCES collections(id) ON DELETE CASCADE, name TEXT NOT NULL, method TEXT NOT NULL, url TEXT NOT NULL, params TEXT NOT NULL DEFAULT '[]', headers TEXT NOT NULL DEFAULT '{}', path_params TEXT NOT NULL DEFAULT '{}', body_type TEXT NOT NULL DEFAULT 'none', body_str TEXT, graphql_variables TEXT, graphql_operation_name TEXT, grpc_config TEXT, captures TEXT, scripts TEXT, auth_type TEXT, auth_data TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, sort_order INTEGER NOT NULL DEFAULT 0)",
+            "INSERT INTO requests_new (id, collection_id, name, method, url, params, headers, path_params, body_type, body_str, graphql_variables, graphql_operation_name, grpc_config, captures, scripts, auth_type, auth_data, created_at, updated_at, sort_order) SELECT id, collection_id, name, method, url, params, headers, path_params, body_type, body_str, graphql_variables, graphql_operation_name, grpc_config, captures, scripts, auth_type, auth_data, created_at, updated_at, sort_order FROM requests WHERE collection_id IN (SELECT id FROM collections)",
+            "DROP TABLE requests",
+            "ALTER TABLE requests_new RENAME TO requests",
+            // Variables go with whichever environment, collection or request owns them
+            "CREATE TABLE variables_new (id TEXT PRIMARY KEY, environment_id TEXT REFERENCES environments(id) ON DELETE CASCADE, key TEXT NOT NULL, value TEXT NOT NULL, is_secret BOOLEAN NOT NULL DEFAULT FALSE, source TEXT, collection_id TEXT REFERENCES collections(id) ON DELETE CASCADE, request_id TEXT REFERENCES requests(id) ON DELETE CASCADE, value_type TEXT NOT NULL DEFAULT 'string', created_at TEXT NOT NULL, updated_at TEXT NOT NULL, UNIQUE(environment_id, key))",
+            "INSERT INTO variables_new (id, environment_id, key, value, is_secret, source, collection_id, request_id, value_type, created_at, updated_at) SELECT id, environment_id, key, value, is_secret, source, collection_id, request_id, value_type, created_at, updated_at FROM variables WHERE (environment_id IS NULL OR environment_id IN (SELECT id FROM environments)) AND (collection_id IS NULL OR collection_id IN (SELECT id FROM collections)) AND (request_id IS NULL OR request_id IN (SELECT id FROM requests))",
+            "DROP TABLE variables",
+            "ALTER TABLE variables_new RENAME TO variables",
+            "CREATE TABLE mock_responses_new (request_id TEXT PRIMARY KEY REFERENCES requests(id) ON DELETE CASCADE, status INTEGER NOT NULL, headers TEXT NOT NULL, body TEXT NOT NULL, latency_ms INTEGER, updated_at TEXT NOT NULL)",
+            "INSERT INTO mock_responses_new (request_id, status, headers, body, latency_ms, updated_at) SELECT request_id, status, headers, body, latency_ms, updated_at FROM mock_responses WHERE request_id IN (SELECT id FROM requests)",
+            "DROP TABLE mock_responses",
+            "ALTER TABLE mock_responses_new RENAME TO mock_responses",
+            "CREATE TABLE request_samples_new (id TEXT PRIMARY KEY, request_id TEXT NOT NULL REFERENCES requests(id) ON DELETE CASCADE, status INTEGER, duration_ms INTEGER NOT NULL, response_size INTEGER NOT NULL, sent_at TEXT NOT NULL)",
+            "INSERT INTO request_samples_new (id, request_id, status, duration_ms, response_size, sent_at) SELECT id, request_id, status, duration_ms, response_size, sent_at FROM request_samples WHERE request_id IN (SELECT id FROM requests)",
+            "DROP TABLE request_samples",
+            "ALTER TABLE request_samples_new RENAME TO request_samples",
+            "CREATE INDEX IF NOT EXISTS idx_request_samples_request ON request_samples (request_id, sent_at)",
+            "CREATE TABLE monitor_results_new (id TEXT PRIMARY KEY, monitor_id TEXT NOT NULL REFERENCES monitors(id) ON DELETE CASCADE, passed BOOLEAN NOT NULL, total_requests INTEGER NOT NULL, failed_requests INTEGER NOT NULL, error TEXT, results TEXT NOT NULL, started_at TEXT NOT NULL, finished_at TEXT NOT NULL)",
+            "INSERT INTO monitor_results_new (id, monitor_id, passed, total_requests, failed_requests, error, results, started_at, finished_at) SELECT id, monitor_id, passed, total_requests, failed_requests, error, results, started_at, finished_at FROM monitor_results WHERE monitor_id IN (SELECT id FROM monitors)",
+            "DROP TABLE monitor_results",
+            "ALTER TABLE monitor_results_new RENAME TO monitor_results",
+            // Run history outlives the environment it ran against
+            "CREATE TABLE collection_runs_new (id TEXT PRIMARY KEY, collection_id TEXT NOT NULL REFERENCES collections(id) ON DELETE CASCADE, environment_id TEXT REFERENCES environments(id) ON DELETE SET NULL, iterations INTEGER NOT NULL DEFAULT 1, total_requests INTEGER NOT NULL, passed_requests INTEGER NOT NULL, failed_requests INTEGER NOT NULL, stopped_early BOOLEAN NOT NULL DEFAULT FALSE, results TEXT NOT NULL, started_at TEXT NOT NULL, finished_at TEXT NOT NULL)",
+            "INSERT INTO collection_runs_new (id, collection_id, environment_id, iterations, total_requests, passed_requests, failed_requests, stopped_early, results, started_at, finished_at) SELECT id, collection_id, CASE WHEN environment_id IN (SELECT id FROM environments) THEN environment_id END, iterations, total_requests, passed_requests, failed_requests, stopped_early, results, started_at, finished_at FROM collection_runs WHERE collection_id IN (SELECT id FROM collections)",
+            "DROP TABLE collection_runs",
+            "ALTER TABLE collection_runs_new RENAME TO collection_runs",
+        ],
+        rebuilds_tables: true,
+    },
+    Migration {
+        version: 4,
+        description: "Tags for requests and collections",
+        statements: &[
+            "CREATE TABLE tags (id TEXT PRIMARY KEY, name TEXT NOT NULL UNIQUE COLLATE NOCASE, color TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL)",
+            "CREATE TABLE request_tags (request_id TEXT NOT NULL REFERENCES requests(id) ON DELETE CASCADE, tag_id TEXT NOT NULL REFERENCES tags(id) ON DELETE CASCADE, PRIMARY KEY (request_id, tag_id))",
+            "CREATE TABLE collection_tags (collection_id TEXT NOT NULL REFERENCES collections(id) ON DELETE CASCADE, tag_id TEXT NOT NULL REFERENCES tags(id) ON DELETE CASCADE, PRIMARY KEY (collection_id, tag_id))",
+            // The primary keys cover lookups by item; these cover filtering by tag
+            "CREATE INDEX idx_request_tags_tag ON request_tags (tag_id)",
+            "CREATE INDEX idx_collection_tags_tag ON collection_tags (tag_id)",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 5,
+        description: "Favorite and recently used requests",
+        statements: &[
+            "ALTER TABLE requests ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT FALSE",
+            "ALTER TABLE requests ADD COLUMN last_used_at TEXT",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 6,
+        description: "Row versions for detecting conflicting saves",
+        statements: &[
+            "ALTER TABLE requests ADD COLUMN version INTEGER NOT NULL DEFAULT 1",
+            "ALTER TABLE collections ADD COLUMN version INTEGER NOT NULL DEFAULT 1",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 7,
+        description: "Per-request send settings",
+        statements: &["ALTER TABLE requests ADD COLUMN settings TEXT"],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 8,
+        description: "Markdown docs for requests and collections",
+        statements: &[
+            "ALTER TABLE requests ADD COLUMN docs TEXT",
+            "ALTER TABLE collections ADD COLUMN docs TEXT",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 9,
+        description: "Indexes for sidebar, variable, cache and history lookups",
+        statements: &[
+            "CREATE INDEX idx_collections_parent ON collections (parent_id, sort_order)",
+            "CREATE INDEX idx_requests_collection ON requests (collection_id, sort_order)",
+            "CREATE INDEX idx_requests_last_used ON requests (last_used_at)",
+            // Lookups by environment already use the UNIQUE (environment_id, key) index
+            "CREATE INDEX idx_variables_collection ON variables (collection_id)",
+            "CREATE INDEX idx_variables_request ON variables (request_id)",
+            "CREATE INDEX idx_response_cache_expires ON response_cache (expires_at)",
+            "CREATE INDEX idx_collection_runs_collection ON collection_runs (collection_id, started_at)",
+            "CREATE INDEX idx_monitor_results_monitor ON monitor_results (monitor_id, started_at)",
+            "CREATE INDEX idx_webhook_captures_received ON webhook_captures (received_at)",
+            "CREATE INDEX idx_proxy_captures_collection ON proxy_captures (collection_id, captured_at)",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 10,
+        description: "Cache policies by host pattern or collection",
+        statements: &[
+            "CREATE TABLE cache_policies (id TEXT PRIMARY KEY, host_pattern TEXT, collection_id TEXT REFERENCES collections(id) ON DELETE CASCADE, enabled BOOLEAN NOT NULL DEFAULT TRUE, ttl_seconds INTEGER, bypass BOOLEAN NOT NULL DEFAULT FALSE, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, CHECK ((host_pattern IS NULL) <> (collection_id IS NULL)))",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 11,
+        description: "Response bodies stored once per content hash",
+        statements: &[
+            "CREATE TABLE blobs (hash TEXT PRIMARY KEY, data BLOB NOT NULL, size INTEGER NOT NULL)",
+            // Rows written before this keep their body inline, with no hash
+            "ALTER TABLE response_cache ADD COLUMN response_body_hash TEXT",
+            "ALTER TABLE proxy_captures ADD COLUMN response_body_hash TEXT",
+            // Finding blobs nothing refers to any more
+            "CREATE INDEX idx_response_cache_body ON response_cache (response_body_hash)",
+            "CREATE INDEX idx_proxy_captures_body ON proxy_captures (response_body_hash)",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 12,
+        description: "Trace context settings per collection and trace ids in send history",
+        statements: &[
+            "ALTER TABLE collections ADD COLUMN trace_context TEXT",
+            "ALTER TABLE request_samples ADD COLUMN trace_id TEXT",
+            "ALTER TABLE request_samples ADD COLUMN correlation_id TEXT",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 13,
+        description: "Cookie jar",
+        statements: &[
+            "CREATE TABLE cookies (domain TEXT NOT NULL, path TEXT NOT NULL, name TEXT NOT NULL, value TEXT NOT NULL, expires_at TEXT, secure BOOLEAN NOT NULL DEFAULT FALSE, http_only BOOLEAN NOT NULL DEFAULT FALSE, same_site TEXT, host_only BOOLEAN NOT NULL DEFAULT TRUE, updated_at TEXT NOT NULL, PRIMARY KEY (domain, path, name))",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 14,
+        description: "Default headers per collection and environment",
+        statements: &[
+            "ALTER TABLE collections ADD COLUMN default_headers TEXT",
+            "ALTER TABLE environments ADD COLUMN default_headers TEXT NOT NULL DEFAULT '{}'",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 15,
+        description: "Outbound rate limits by host pattern",
+        statements: &[
+            "CREATE TABLE rate_limits (id TEXT PRIMARY KEY, host_pattern TEXT NOT NULL, enabled BOOLEAN NOT NULL DEFAULT TRUE, requests_per_second REAL, max_concurrent INTEGER, created_at TEXT NOT NULL, updated_at TEXT NOT NULL)",
+        ],
+        rebuilds_tables: false,
+    },
+    Migration {
+        version: 16,
+        description: "Secrets key kept in the OS keychain",
+        statements: &[
+            // The keychain entry holding the key; NULL when the key comes from a passphrase
+            "ALTER TABLE secret_encryption ADD COLUMN keychain_key TEXT",
+        ],
+        rebuilds_tables: false,
+    },
+];
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Collection {
     pub id: String,                  // Unique identifier for the collection
     pub name: String,                // Display name of the collection
     pub description: Option<String>, // optional description
+    #[serde(default)]
+    pub docs: Option<String>,        // markdown usage notes and examples
     pub parent_id: Option<String>,   // optional parent collection id (for nested collections)
+    #[serde(default)]
+    pub auth_type: Option<String>,   // auth inherited by requests inside (None or "inherit" defers to the parent)
+    #[serde(default)]
+    pub auth_data: Option<String>,   // JSON string of auth details
+    #[serde(default)]
+    pub sort_order: i64,             // position among its siblings in the sidebar
+    pub version: i64,                // bumped on every save; an update must send the one it was based on
+    #[serde(default)]
+    pub trace_context: Option<String>, // JSON trace header settings (see trace.rs); None defers to the parent
+    #[serde(default)]
+    pub default_headers: Option<String>, // JSON object of headers sent by requests inside (see default_headers.rs)
     pub created_at: DateTime<Utc>,   // timestamp of creation
     pub updated_at: DateTime<Utc>,   // timestamp of last update
 }
@@ -24,20 +264,79 @@ pub struct Request {
     pub url: String,           // URL of the request or the API endpoint
     pub params: String,        // JSON string of query parameters
     pub headers: String,       // JSON string of headers
+    #[serde(default = "empty_json_object")]
+    pub path_params: String,   // JSON string of path params (values for `:id` / `{id}`)
     pub body_type: String,     // Request body type (e.g. "json", "form-data", "raw")
 
-    pub body_str: Option<String>, // Request body for POST, PUT, PATCH requests
+    pub body_str: Option<String>, // Request body for POST, PUT, PATCH requests (the query for GraphQL)
+    #[serde(default)]
+    pub graphql_variables: Option<String>, // GraphQL variables as a JSON object string
+    #[serde(default)]
+    pub graphql_operation_name: Option<String>, // GraphQL operationName, for documents with several operations
+    #[serde(default)]
+    pub grpc_config: Option<String>, // JSON gRPC service/method and descriptor source (body_type "grpc")
+    #[serde(default)]
+    pub captures: Option<String>, // JSON list of response capture rules (see capture.rs)
+    pub scripts: Option<String>,  // JSON pre-request and test scripts (see scripting.rs)
+    #[serde(default)]
+    pub settings: Option<String>, // JSON timeout, redirect, proxy, TLS and cache options (see request_settings.rs)
+    #[serde(default)]
+    pub docs: Option<String>, // Markdown usage notes and example payloads
     pub auth_type: Option<String>, // Authentication type (e.g. "basic", "bearer", "api-key")
     pub auth_data: Option<String>, // JSON string of auth details
+    #[serde(default)]
+    pub sort_order: i64, // Position within its collection in the sidebar
+    #[serde(default)]
+    pub pinned: bool, // Shown in the favorites panel
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>, // When it was last sent from the request builder
+    pub version: i64, // Bumped on every save; an update must send the one it was based on
     pub created_at: DateTime<Utc>, // Timestamp of creation
     pub updated_at: DateTime<Utc>, // Timestamp of last update
 }
 
+// 🎓 TEACHING: Returned (inside the anyhow error) when a save was based on an older copy
+// than the one in the database, e.g. the same request open in two tabs. `details.current`
+// is the newer copy so the UI can show it instead of silently overwriting it. Overwriting it
+// anyway takes an explicit `force`.
+fn version_conflict(current: &impl Serialize) -> Result<AppError> {
+    let current = serde_json::to_value(current)?;
+    let message = format!(
+        "'{}' was changed somewhere else since it was opened",
+        current["name"].as_str().unwrap_or_default()
+    );
+    Ok(AppError::new(ErrorKind::Conflict, message).with_details(serde_json::json!({ "current": current })))
+}
+
+// A collection with its requests and subfolders, for showing the whole sidebar at once
+#[derive(Debug, Serialize, Clone)]
+pub struct CollectionNode {
+    #[serde(flatten)]
+    pub collection: Collection,
+    pub requests: Vec<Request>,
+    pub children: Vec<CollectionNode>,
+}
+
+// 🎓 TEACHING: A label such as "smoke-test" or "deprecated", attached to any number of
+// requests and collections
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Tag {
+    pub id: String,
+    pub name: String,          // Unique, ignoring case
+    pub color: Option<String>, // CSS colour for the tag's chip in the sidebar
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Environment {
     pub id: String,
     pub name: String,              // Display name of the environment
     pub is_active: bool,           // Whether this environment is active and can be used
+    #[serde(default = "empty_json_object")]
+    pub host_overrides: String,    // JSON object mapping hostnames to IPs (like curl's --resolve)
+    #[serde(default = "empty_json_object")]
+    pub default_headers: String,   // JSON object of headers sent while it's in use (see default_headers.rs)
     pub created_at: DateTime<Utc>, // Timestamp of creation
     pub updated_at: DateTime<Utc>, // Timestamp of last update
 }
@@ -49,10 +348,58 @@ pub struct Variable {
     pub key: String,                    // Variable name like "api_base_url"
     pub value: String,                  // Variable value like "https://api.example.com"
     pub is_secret: bool,                // If true, we'll hide the value in UI
+    #[serde(default)]
+    pub source: Option<String>,         // JSON provider config (e.g. AWS Secrets Manager); value is fetched on use
+    #[serde(default)]
+    pub collection_id: Option<String>,  // Set for collection (or folder) variables, which have no environment
+    #[serde(default)]
+    pub request_id: Option<String>,     // Set for variables that belong to a single saved request
+    #[serde(default = "default_value_type")]
+    pub value_type: String,             // "string", "number", "boolean", or "json" (see placeholders::interpolate_json)
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+fn default_value_type() -> String {
+    "string".to_string()
+}
+
+// A variable to create in bulk. Like `create_variable`, it belongs to at most one of an
+// environment, a collection or a request; none of them makes it global.
+#[derive(Debug, Deserialize, Clone)]
+pub struct NewVariable {
+    #[serde(default)]
+    pub environment_id: Option<String>,
+    #[serde(default)]
+    pub collection_id: Option<String>,
+    #[serde(default)]
+    pub request_id: Option<String>,
+    pub key: String,
+    pub value: String,
+    #[serde(default)]
+    pub is_secret: bool,
+}
+
+impl Variable {
+    // A runtime-only value that is never saved, e.g. a per-send override
+    pub fn ephemeral(key: &str, value: &str) -> Self {
+        let now = Utc::now();
+        Variable {
+            id: String::new(),
+            environment_id: None,
+            key: key.to_string(),
+            value: value.to_string(),
+            is_secret: false,
+            source: None,
+            collection_id: None,
+            request_id: None,
+            value_type: default_value_type(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
 // 🎓 TEACHING: Response Cache for Phase 2
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ResponseCache {
@@ -67,37 +414,630 @@ pub struct ResponseCache {
     pub expires_at: Option<DateTime<Utc>>, // When this cache expires (optional)
 }
 
+// 🎓 TEACHING: A cache entry as the cache browser lists it - without the stored body
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheEntrySummary {
+    pub id: String,
+    pub method: String,
+    pub url: String,
+    pub response_status: u16,
+    pub size_bytes: u64, // Stored headers and body
+    pub cache_time: DateTime<Utc>,
+    pub age_seconds: i64,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub expired: bool,
+}
+
+// Every field is optional; an empty filter lists everything
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CacheEntryFilter {
+    pub url: Option<String>,    // Part of the URL, ignoring case
+    pub method: Option<String>,
+    pub expired: Option<bool>,  // Only expired (true) or only fresh (false) entries
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheEntryPage {
+    pub entries: Vec<CacheEntrySummary>,
+    pub total: u64, // Entries matching the filter, across all pages
+    pub page: u32,
+    pub page_size: u32,
+}
+
+pub const CACHE_PAGE_SIZE: u32 = 50;
+
+// 🎓 TEACHING: A caching rule for a host pattern or a collection (exactly one of the two).
+// See cache_policy.rs for how matching rules are picked.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CachePolicy {
+    pub id: String,
+    pub host_pattern: Option<String>,  // e.g. "api.example.com", "*.example.com" or "*"
+    pub collection_id: Option<String>, // Also covers the folders inside it
+    pub enabled: bool,                 // Switched-off rules are kept but ignored
+    pub ttl_seconds: Option<u64>,      // None follows the response's caching headers
+    pub bypass: bool,                  // Never cache matching requests, whatever broader rules say
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 🎓 TEACHING: Slows down sends to matching hosts (see rate_limit.rs)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RateLimit {
+    pub id: String,
+    pub host_pattern: String, // e.g. "api.example.com", "*.example.com" or "*"
+    pub enabled: bool,
+    pub requests_per_second: Option<f64>, // May be below 1, e.g. 0.2 for one send every 5 seconds
+    pub max_concurrent: Option<u32>,      // Sends in flight at once
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 🎓 TEACHING: An installed WASM auth plugin (the module bytes are loaded separately)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuthPlugin {
+    pub id: String,
+    pub name: String,               // Unique name referenced from auth_data
+    pub size_bytes: u64,            // Size of the WASM module
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 🎓 TEACHING: A shared script module that request scripts can `import` by name
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScriptModule {
+    pub id: String,
+    pub name: String,     // Unique; scripts use `import "name" as alias;`
+    pub language: String, // Same values as a request script's language
+    pub code: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Space used by one table, its indexes included
+#[derive(Debug, Serialize, Clone)]
+pub struct TableStats {
+    pub name: String,
+    pub rows: i64,
+    pub bytes: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StorageStats {
+    pub database_bytes: i64, // The database file, not counting the write-ahead log
+    pub free_bytes: i64,     // Unused pages that compacting would give back
+    pub cache_entries: i64,
+    pub cache_bytes: i64,
+    pub history_bytes: i64, // Collection runs, monitor results, timing samples and captured traffic
+    pub body_bytes: i64,    // Response bodies, shared by cache entries and proxy recordings
+    pub tables: Vec<TableStats>, // Largest first
+}
+
+// Tables that only grow with use, counted together as history
+const HISTORY_TABLES: &[&str] = &[
+    "collection_runs",
+    "monitor_results",
+    "request_samples",
+    "webhook_captures",
+    "proxy_captures",
+];
+
+// 🎓 TEACHING: The history tables the retention policy prunes, with the column that dates each
+// row and roughly how many bytes a row takes. Proxy captures are left alone: replay serves
+// them, so they're recordings the user made rather than history.
+const RETAINED_HISTORY: &[(&str, &str, &str)] = &[
+    ("collection_runs", "started_at", "length(CAST(results AS BLOB))"),
+    ("monitor_results", "started_at", "length(CAST(results AS BLOB)) + COALESCE(length(CAST(error AS BLOB)), 0)"),
+    ("request_samples", "sent_at", "0"),
+    ("webhook_captures", "received_at", "length(CAST(headers AS BLOB)) + length(CAST(body AS BLOB))"),
+];
+// Ids, timestamps and counters, on top of the text columns above
+const HISTORY_ROW_BYTES: i64 = 128;
+
+// 🎓 TEACHING: One request received by a webhook listener
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookCapture {
+    pub id: String,
+    pub port: u16,                  // Listener that received it
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: String,            // JSON object of request headers
+    pub body: String,               // Request body (binary bodies are stored lossily as UTF-8)
+    pub remote_addr: String,
+    pub received_at: DateTime<Utc>,
+}
+
+// 🎓 TEACHING: One request/response pair recorded by the capture proxy.
+// Each distinct method + path + query becomes a saved request; re-recording it
+// replaces the stored response, which is what replay serves.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProxyCapture {
+    pub id: String,
+    pub collection_id: String,
+    pub request_id: String,        // Saved request created for this call
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub request_headers: String,   // JSON object
+    pub request_body: String,
+    pub status: u16,
+    pub response_headers: String,  // JSON object
+    pub response_body_base64: String, // Base64, so binary bodies replay byte for byte
+    pub duration_ms: u64,
+    pub captured_at: DateTime<Utc>,
+}
+
+// 🎓 TEACHING: The summary of one collection run, kept for the run history
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CollectionRun {
+    pub id: String,
+    pub collection_id: String,
+    pub environment_id: Option<String>, // Environment the run used, if any
+    pub iterations: u32,                // Rows of the iteration data file (1 without one)
+    pub total_requests: u32,            // Requests times iterations
+    pub passed_requests: u32,
+    pub failed_requests: u32,
+    pub stopped_early: bool,            // Stop-on-failure cut the run short
+    pub results: String,                // JSON list of per-request results (see runner.rs)
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+// 🎓 TEACHING: A request or collection checked on a cron schedule (see monitor.rs)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Monitor {
+    pub id: String,
+    pub name: String,
+    pub target_type: String,             // "request" or "collection"
+    pub target_id: String,
+    pub cron: String,                    // Five-field cron expression, in local time
+    pub environment_id: Option<String>,  // None uses the active environment at check time
+    pub enabled: bool,
+    pub notify: bool,                    // Show a system notification when a check fails
+    pub webhook_url: Option<String>,     // POSTed a JSON summary when a check fails
+    #[serde(default)]
+    pub retry: Option<String>,           // JSON retry policy (see runner.rs); None sends once
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 🎓 TEACHING: One check made by a monitor, kept for its result history
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MonitorResult {
+    pub id: String,
+    pub monitor_id: String,
+    pub passed: bool,
+    pub total_requests: u32,
+    pub failed_requests: u32,
+    pub error: Option<String>, // Why the check couldn't run at all, e.g. its target is gone
+    pub results: String,       // JSON list of per-request results, as in a collection run
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+// 🎓 TEACHING: Timing of one send of a saved request, the raw data for its analytics
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RequestSample {
+    pub id: String,
+    pub request_id: String,
+    pub status: Option<u16>, // None if the request couldn't be sent
+    pub duration_ms: u64,
+    pub response_size: u64, // Body bytes (0 without a response)
+    pub sent_at: DateTime<Utc>,
+    #[serde(default)]
+    pub trace_id: Option<String>, // From the traceparent header the send carried, if any
+    #[serde(default)]
+    pub correlation_id: Option<String>, // The generated request id header, if any
+}
+
+// 🎓 TEACHING: One send as the history export lists it: a request sample plus the request it was for
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub sent_at: DateTime<Utc>,
+    pub request_id: String,
+    pub request_name: String,
+    pub method: String,
+    pub url: String, // As saved, before variables are filled in
+    pub collection_id: String,
+    pub status: Option<u16>, // None if the request couldn't be sent
+    pub duration_ms: u64,
+    pub response_size: u64,
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+// Every field is optional; an empty filter exports everything
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct HistoryFilter {
+    pub request_id: Option<String>,
+    pub collection_id: Option<String>, // Includes the requests in its folders
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub failed_only: bool, // Sends that got no response or a 4xx/5xx status
+}
+
+// 🎓 TEACHING: Saved requests wired into a graph and run in dependency order (see workflow.rs)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Workflow {
+    pub id: String,
+    pub name: String,
+    pub nodes: String, // JSON list of nodes, each running a saved request
+    pub edges: String, // JSON list of edges: what they pass along and when they're followed
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 🎓 TEACHING: What the mock server answers for a saved request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MockResponse {
+    pub request_id: String,
+    pub status: u16,
+    pub headers: String,         // JSON object of response headers
+    pub body: String,            // May use {{variables}} and {{request.*}} placeholders
+    pub latency_ms: Option<u64>, // Artificial delay before answering
+    pub updated_at: DateTime<Utc>,
+}
+
+fn empty_json_object() -> String {
+    "{}".to_string()
+}
+
+// Module names are written inside `import "..."` strings, so keep them plain
+fn validate_module_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+    if !valid {
+        return Err(AppError::validation(format!(
+            "Invalid module name: {} (use letters, digits, `_`, `-`, `.` and `/`)",
+            name
+        )).into());
+    }
+    Ok(())
+}
+
 // 🎓 TEACHING: Adding Clone derive so we can clone the database connection
 #[derive(Clone)]
 pub struct Database {
     pool: SqlitePool,
+    // Shared by every clone, so unlocking once unlocks the whole app
+    secrets: Arc<RwLock<SecretState>>,
+    // OS secret store for variables moved out of SQLite
+    keychain: Arc<dyn SecretBackend>,
+    // Recently fetched values of provider-backed variables
+    provider_cache: Arc<ProviderCache>,
+    // Token buckets and concurrency slots of the rate limits
+    rate_limiter: Arc<RateLimiter>,
+    // Where the app settings file is, so every send can read the current settings
+    settings_dir: Option<PathBuf>,
+    // The workspace this file belongs to, so background tasks can tell a switch is under way
+    workspace_id: Option<String>,
+}
+
+// 🎓 TEACHING: Encryption state for secrets at rest.
+// `enabled` comes from the database; `cipher` is only present after the user unlocks.
+#[derive(Default)]
+struct SecretState {
+    enabled: bool,
+    in_keychain: bool, // The key is in the OS keychain rather than derived from a passphrase
+    cipher: Option<SecretCipher>,
+}
+
+// Ids of a folder's items in sidebar order, leaving out the one being moved
+async fn sibling_ids(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    query: &str,
+    parent: Option<&str>,
+    exclude: &str,
+) -> Result<Vec<String>> {
+    let rows = sqlx::query(query).bind(parent).bind(exclude).fetch_all(&mut *tx).await?;
+    Ok(rows.iter().map(|row| row.get("id")).collect())
+}
+
+async fn renumber(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, table: &str, ids: &[String]) -> Result<()> {
+    for (index, id) in ids.iter().enumerate() {
+        sqlx::query(&format!("UPDATE {} SET sort_order = ? WHERE id = ?", table))
+            .bind(index as i64)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    Ok(())
+}
+
+// Match `text` literally in a LIKE pattern written with ESCAPE '\'
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+// 🎓 TEACHING: Response bodies live in `blobs`, once per distinct content, so polling an
+// endpoint that keeps returning the same multi-MB body stores it a single time. Rows refer
+// to their body by hash; blobs nothing refers to are removed by `delete_orphaned_blobs`.
+// Runs in the caller's transaction, so the blob can't be cleaned up before the row is saved.
+async fn store_blob(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, body: &str) -> Result<String> {
+    use sha2::{Digest, Sha256};
+    let hash = format!("{:x}", Sha256::digest(body.as_bytes()));
+    sqlx::query("INSERT OR IGNORE INTO blobs (hash, data, size) VALUES (?, ?, ?)")
+        .bind(&hash)
+        .bind(compression::compress_body(body))
+        .bind(body.len() as i64)
+        .execute(&mut *tx)
+        .await?;
+    Ok(hash)
+}
+
+// The joined blob (`blob_data`), or the inline column for rows saved before blobs existed
+fn stored_body(row: &sqlx::sqlite::SqliteRow, inline_column: &str) -> Result<String> {
+    match row.get::<Option<Vec<u8>>, _>("blob_data") {
+        Some(data) => compression::decompress_body(data),
+        None => compression::decompress_body(row.get(inline_column)),
+    }
 }
 
 impl Database {
     // Initialize the database connection
-    pub async fn new(database_url: &str) -> Result<Self> {
-        println!("🔧 Attempting to connect to database: {}", database_url);
+    pub async fn new(database_url: &str, max_connections: u32) -> Result<Self> {
+        logging::info(format!("Connecting to database: {}", database_url));
 
-        let pool = SqlitePool::connect(database_url).await.map_err(|e| {
-            println!("❌ Database connection failed: {}", e);
+        // 🎓 TEACHING: Foreign keys are off by default in SQLite, per connection. Turning them on
+        // for every connection the pool opens is what makes the ON DELETE rules take effect.
+        // In WAL mode readers no longer wait for a writer, and the busy timeout makes a
+        // connection wait for another one's write instead of failing with "database is locked".
+        // NORMAL sync is still crash-safe with WAL; only the last commits can be lost on power loss.
+        let options = SqliteConnectOptions::from_str(database_url)?
+            .foreign_keys(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT);
+        let pool_options = || SqlitePoolOptions::new().max_connections(max_connections.max(1));
+        let pool = pool_options().connect_with(options.clone()).await.map_err(|e| {
+            logging::error(format!("Database connection failed: {}", e));
             e
         })?;
 
-        println!("✅ Database connection successful");
-
-        let db = Self { pool };
+        let mut db = Self {
+            pool,
+            secrets: Arc::new(RwLock::new(SecretState::default())),
+            keychain: Arc::new(KeychainBackend),
+            provider_cache: Arc::new(ProviderCache::default()),
+            rate_limiter: Arc::new(RateLimiter::default()),
+            settings_dir: None,
+            workspace_id: None,
+        };
 
-        println!("🔧 Running database migrations...");
+        logging::debug("Running database migrations");
         db.run_migrations().await.map_err(|e| {
-            println!("❌ Database migrations failed: {}", e);
+            logging::error(format!("Database migrations failed: {}", e));
             e
         })?;
 
-        println!("✅ Database migrations completed successfully");
+        // 🎓 TEACHING: A connection that read the schema before a migration altered a table can
+        // prepare `SELECT *` with the old column list, so the pool starts over with fresh ones
+        db.pool.close().await;
+        db.pool = pool_options().connect_with(options).await?;
+
+        let encryption = db.secret_encryption_row().await?;
+        {
+            let mut state = db.secrets.write().unwrap();
+            state.enabled = encryption.is_some();
+            state.in_keychain = encryption.is_some_and(|(_, _, keychain_key)| keychain_key.is_some());
+        }
+
+        // 🎓 TEACHING: A key kept in the OS keychain unlocks the workspace on open, without a prompt.
+        // If the keychain can't be read the workspace stays locked, just like a passphrase one.
+        if db.secret_encryption_status().in_keychain {
+            if let Err(e) = db.unlock_secrets_from_keychain().await {
+                logging::warn(format!("Could not unlock secrets from the OS keychain: {}", e));
+            }
+        }
         Ok(db)
     }
 
+    // 🎓 TEACHING: Versioned migrations
+    // `schema_version` records every migration applied to this database file. On startup we
+    // apply, in order, the ones it hasn't seen yet, each inside a transaction, so a failure
+    // leaves the database exactly as it was. Version 1 is the schema as it stood before
+    // versioning; it only adds what's missing, so databases from any earlier release are
+    // brought up to date without touching their data. New schema changes go in MIGRATIONS.
     async fn run_migrations(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let current = self.schema_version().await?;
+        let latest = MIGRATIONS.last().map_or(BASELINE_VERSION, |migration| migration.version);
+        if current > latest {
+            // Written by a newer release; changing it could lose data that release relies on
+            return Err(AppError::new(ErrorKind::Database, format!(
+                "Database schema version {} is newer than this version of the app supports ({}). Please update the app.",
+                current,
+                latest
+            )).into());
+        }
+
+        if current < BASELINE_VERSION {
+            self.baseline_schema().await?;
+            self.record_schema_version(BASELINE_VERSION, "Baseline schema").await?;
+        }
+
+        for migration in MIGRATIONS.iter().filter(|migration| migration.version > current) {
+            logging::info(format!("Applying migration {}: {}", migration.version, migration.description));
+            // The pragma is ignored inside a transaction, so it's set on the connection first
+            let mut conn = self.pool.acquire().await?;
+            if migration.rebuilds_tables {
+                sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut conn).await?;
+            }
+            let applied = Self::apply_migration(&mut conn, migration).await;
+            if migration.rebuilds_tables {
+                sqlx::query("PRAGMA foreign_keys = ON").execute(&mut conn).await?;
+            }
+            applied?;
+        }
+
+        Ok(())
+    }
+
+    async fn apply_migration(conn: &mut sqlx::SqliteConnection, migration: &Migration) -> Result<()> {
+        let mut tx = conn.begin().await?;
+        for statement in migration.statements {
+            sqlx::query(statement).execute(&mut tx).await.map_err(|e| {
+                AppError::from(e).context(format!("Migration {} ({}) failed", migration.version, migration.description))
+            })?;
+        }
+        if migration.rebuilds_tables {
+            let violations = sqlx::query("PRAGMA foreign_key_check").fetch_all(&mut tx).await?;
+            if let Some(row) = violations.first() {
+                return Err(AppError::new(ErrorKind::Database, format!(
+                    "Migration {} ({}) left {} row(s) with a broken reference, first in table {}",
+                    migration.version,
+                    migration.description,
+                    violations.len(),
+                    row.get::<String, _>("table")
+                )).into());
+            }
+        }
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 🎓 TEACHING: VACUUM INTO writes a consistent, compacted copy of the live database,
+    // including anything still sitting in the write-ahead log, which copying the file wouldn't
+    pub async fn backup_to(&self, path: &Path) -> Result<()> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.display().to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // 🎓 TEACHING: Sizes come from the `dbstat` virtual table, which reports the pages each
+    // table and index actually occupies on disk
+    pub async fn get_storage_stats(&self) -> Result<StorageStats> {
+        let page_size = self.pragma("page_size").await?;
+
+        let sizes: HashMap<String, i64> = sqlx::query(
+            "SELECT m.tbl_name AS name, SUM(s.pgsize) AS bytes FROM dbstat s JOIN sqlite_master m ON m.name = s.name GROUP BY m.tbl_name",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| (row.get("name"), row.get("bytes")))
+        .collect();
+
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut tables = Vec::with_capacity(names.len());
+        for name in names {
+            let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")))
+                .fetch_one(&self.pool)
+                .await?;
+            let bytes = sizes.get(&name).copied().unwrap_or(0);
+            tables.push(TableStats { name, rows, bytes });
+        }
+        tables.sort_by_key(|table| std::cmp::Reverse(table.bytes));
+
+        let table = |name: &str| tables.iter().find(|table| table.name == name);
+        Ok(StorageStats {
+            database_bytes: self.pragma("page_count").await? * page_size,
+            free_bytes: self.pragma("freelist_count").await? * page_size,
+            cache_entries: table("response_cache").map_or(0, |table| table.rows),
+            cache_bytes: table("response_cache").map_or(0, |table| table.bytes),
+            history_bytes: HISTORY_TABLES.iter().filter_map(|name| table(name)).map(|table| table.bytes).sum(),
+            body_bytes: table("blobs").map_or(0, |table| table.bytes),
+            tables,
+        })
+    }
+
+    // 🎓 TEACHING: VACUUM rewrites the whole file without its free pages; the checkpoint
+    // then empties the write-ahead log, which VACUUM fills with the rewritten pages.
+    // Returns how many bytes the database file shrank by.
+    pub async fn compact_database(&self) -> Result<i64> {
+        let before = self.pragma("page_count").await? * self.pragma("page_size").await?;
+        self.delete_orphaned_blobs().await?;
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&self.pool).await?;
+        let after = self.pragma("page_count").await? * self.pragma("page_size").await?;
+
+        Ok(before - after)
+    }
+
+    async fn pragma(&self, name: &str) -> Result<i64> {
+        Ok(sqlx::query_scalar(&format!("PRAGMA {}", name)).fetch_one(&self.pool).await?)
+    }
+
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    // Keep the unlocked secrets key, provider cache and rate limit state when switching to a moved copy
+    pub fn carry_over_from(&mut self, previous: &Database) {
+        self.secrets = previous.secrets.clone();
+        self.provider_cache = previous.provider_cache.clone();
+        self.rate_limiter = previous.rate_limiter.clone();
+        self.settings_dir = previous.settings_dir.clone();
+        self.workspace_id = previous.workspace_id.clone();
+    }
+
+    // Without it, sends use the default settings
+    pub fn set_settings_dir(&mut self, dir: PathBuf) {
+        self.settings_dir = Some(dir);
+    }
+
+    pub fn settings_dir(&self) -> Option<&Path> {
+        self.settings_dir.as_deref()
+    }
+
+    pub fn set_workspace_id(&mut self, id: &str) {
+        self.workspace_id = Some(id.to_string());
+    }
+
+    pub fn workspace_id(&self) -> Option<&str> {
+        self.workspace_id.as_deref()
+    }
+
+    pub async fn schema_version(&self) -> Result<i64> {
+        let row = sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_version")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.get("version"))
+    }
+
+    async fn record_schema_version(&self, version: i64, description: &str) -> Result<()> {
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)")
+            .bind(version)
+            .bind(description)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // The schema before versioned migrations. Safe to run on any earlier database: tables
+    // are created if missing and columns added since the first release are filled in.
+    async fn baseline_schema(&self) -> Result<()> {
         // Collections table - stores folders/groups of requests
         sqlx::query(
             r#"
@@ -106,6 +1046,8 @@ impl Database {
             name TEXT NOT NULL,
             description TEXT,
             parent_id TEXT REFERENCES collections(id),
+            auth_type TEXT,
+            auth_data TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
         )
@@ -125,8 +1067,14 @@ impl Database {
             url TEXT NOT NULL,
             params TEXT NOT NULL DEFAULT '[]',
             headers TEXT NOT NULL DEFAULT '{}',
+            path_params TEXT NOT NULL DEFAULT '{}',
             body_type TEXT NOT NULL DEFAULT 'none',
             body_str TEXT,
+            graphql_variables TEXT,
+            graphql_operation_name TEXT,
+            grpc_config TEXT,
+            captures TEXT,
+            scripts TEXT,
             auth_type TEXT,
             auth_data TEXT,
             created_at TEXT NOT NULL,
@@ -144,6 +1092,7 @@ impl Database {
             id TEXT PRIMARY KEY,
             name TEXT NOT NULL UNIQUE,
             is_active BOOLEAN NOT NULL DEFAULT FALSE,
+            host_overrides TEXT NOT NULL DEFAULT '{}',
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
         )
@@ -161,6 +1110,10 @@ impl Database {
             key TEXT NOT NULL,
             value TEXT NOT NULL,
             is_secret BOOLEAN NOT NULL DEFAULT FALSE,
+            source TEXT,
+            collection_id TEXT REFERENCES collections(id),
+            request_id TEXT REFERENCES requests(id),
+            value_type TEXT NOT NULL DEFAULT 'string',
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL,
             UNIQUE(environment_id, key)
@@ -189,37 +1142,284 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
-        Ok(())
-    }
+        // Auth plugins table - custom signing schemes as WASM modules
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS auth_plugins (
+            id TEXT PRIMARY KEY,
+            name TEXT UNIQUE NOT NULL,
+            wasm BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
 
-    // Create a new collection
-    pub async fn create_collection(
-        &self,
-        name: String,
-        description: Option<String>,
-        parent_id: Option<String>,
-    ) -> Result<Collection> {
-        let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
+        // Script modules table - helper functions shared by every request's scripts
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS script_modules (
+            id TEXT PRIMARY KEY,
+            name TEXT UNIQUE NOT NULL,
+            language TEXT NOT NULL DEFAULT 'rhai',
+            code TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
 
-        let collection = Collection {
-            id: id.clone(),
-            name,
-            description,
-            parent_id,
-            created_at: now,
-            updated_at: now,
-        };
+        // Webhook captures table - requests received by the local webhook listener
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS webhook_captures (
+            id TEXT PRIMARY KEY,
+            port INTEGER NOT NULL,
+            method TEXT NOT NULL,
+            path TEXT NOT NULL,
+            query TEXT,
+            headers TEXT NOT NULL,
+            body TEXT NOT NULL,
+            remote_addr TEXT NOT NULL,
+            received_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
 
+        // Mock responses table - what the mock server returns for each saved request
         sqlx::query(
-        "INSERT INTO collections (id, name, description, parent_id, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)"
-    )
-    .bind(&collection.id)
-    .bind(&collection.name)
-    .bind(&collection.description)
-    .bind(&collection.parent_id)
-    .bind(collection.created_at.to_rfc3339())
-    .bind(collection.updated_at.to_rfc3339())
+            r#"
+        CREATE TABLE IF NOT EXISTS mock_responses (
+            request_id TEXT PRIMARY KEY,
+            status INTEGER NOT NULL,
+            headers TEXT NOT NULL,
+            body TEXT NOT NULL,
+            latency_ms INTEGER,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Proxy captures table - traffic recorded by the record/replay proxy
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS proxy_captures (
+            id TEXT PRIMARY KEY,
+            collection_id TEXT NOT NULL,
+            request_id TEXT NOT NULL,
+            method TEXT NOT NULL,
+            path TEXT NOT NULL,
+            query TEXT,
+            request_headers TEXT NOT NULL,
+            request_body TEXT NOT NULL,
+            status INTEGER NOT NULL,
+            response_headers TEXT NOT NULL,
+            response_body_base64 TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            captured_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Collection runs table - summaries of runner results
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS collection_runs (
+            id TEXT PRIMARY KEY,
+            collection_id TEXT NOT NULL,
+            environment_id TEXT,
+            iterations INTEGER NOT NULL DEFAULT 1,
+            total_requests INTEGER NOT NULL,
+            passed_requests INTEGER NOT NULL,
+            failed_requests INTEGER NOT NULL,
+            stopped_early BOOLEAN NOT NULL DEFAULT FALSE,
+            results TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            finished_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Monitors table - requests or collections checked on a schedule
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS monitors (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            target_type TEXT NOT NULL,
+            target_id TEXT NOT NULL,
+            cron TEXT NOT NULL,
+            environment_id TEXT,
+            enabled BOOLEAN NOT NULL DEFAULT TRUE,
+            notify BOOLEAN NOT NULL DEFAULT TRUE,
+            webhook_url TEXT,
+            retry TEXT,
+            last_run_at TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Monitor results table - the history of each monitor's checks
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS monitor_results (
+            id TEXT PRIMARY KEY,
+            monitor_id TEXT NOT NULL REFERENCES monitors(id),
+            passed BOOLEAN NOT NULL,
+            total_requests INTEGER NOT NULL,
+            failed_requests INTEGER NOT NULL,
+            error TEXT,
+            results TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            finished_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Request samples table - per-send timings for request analytics
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS request_samples (
+            id TEXT PRIMARY KEY,
+            request_id TEXT NOT NULL REFERENCES requests(id),
+            status INTEGER,
+            duration_ms INTEGER NOT NULL,
+            response_size INTEGER NOT NULL,
+            sent_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_request_samples_request ON request_samples (request_id, sent_at)")
+            .execute(&self.pool)
+            .await?;
+
+        // Workflows table - request graphs with data passing between nodes
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS workflows (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            nodes TEXT NOT NULL DEFAULT '[]',
+            edges TEXT NOT NULL DEFAULT '[]',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Secret encryption table - the passphrase salt and a verifier (never the key itself)
+        sqlx::query(
+            r#"
+        CREATE TABLE IF NOT EXISTS secret_encryption (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            salt TEXT NOT NULL,
+            verifier TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Columns added after the first release
+        self.add_column_if_missing("requests", "path_params", "TEXT NOT NULL DEFAULT '{}'")
+            .await?;
+        self.add_column_if_missing("environments", "host_overrides", "TEXT NOT NULL DEFAULT '{}'")
+            .await?;
+        self.add_column_if_missing("collections", "auth_type", "TEXT").await?;
+        self.add_column_if_missing("collections", "auth_data", "TEXT").await?;
+        self.add_column_if_missing("variables", "source", "TEXT").await?;
+        self.add_column_if_missing("requests", "graphql_variables", "TEXT").await?;
+        self.add_column_if_missing("requests", "graphql_operation_name", "TEXT").await?;
+        self.add_column_if_missing("requests", "grpc_config", "TEXT").await?;
+        self.add_column_if_missing("requests", "captures", "TEXT").await?;
+        self.add_column_if_missing("requests", "scripts", "TEXT").await?;
+        self.add_column_if_missing("variables", "collection_id", "TEXT REFERENCES collections(id)").await?;
+        self.add_column_if_missing("variables", "request_id", "TEXT REFERENCES requests(id)").await?;
+        self.add_column_if_missing("variables", "value_type", "TEXT NOT NULL DEFAULT 'string'").await?;
+        self.add_column_if_missing("collection_runs", "iterations", "INTEGER NOT NULL DEFAULT 1").await?;
+        self.add_column_if_missing("monitors", "retry", "TEXT").await?;
+
+        Ok(())
+    }
+
+    // 🎓 TEACHING: SQLite has no "ADD COLUMN IF NOT EXISTS", so we check the table info first
+    async fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let rows = sqlx::query(&format!("PRAGMA table_info({})", table))
+            .fetch_all(&self.pool)
+            .await?;
+
+        let exists = rows.iter().any(|row| row.get::<String, _>("name") == column);
+        if !exists {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // Create a new collection
+    pub async fn create_collection(
+        &self,
+        name: String,
+        description: Option<String>,
+        parent_id: Option<String>,
+    ) -> Result<Collection> {
+        self.check_parent(None, parent_id.as_deref()).await?;
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let collection = Collection {
+            id: id.clone(),
+            name,
+            description,
+            docs: None,
+            sort_order: self.next_collection_sort_order(parent_id.as_deref()).await?,
+            parent_id,
+            auth_type: None,
+            auth_data: None,
+            version: 1,
+            trace_context: None,
+            default_headers: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        sqlx::query(
+        "INSERT INTO collections (id, name, description, parent_id, auth_type, auth_data, sort_order, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&collection.id)
+    .bind(&collection.name)
+    .bind(&collection.description)
+    .bind(&collection.parent_id)
+    .bind(&collection.auth_type)
+    .bind(&collection.auth_data)
+    .bind(collection.sort_order)
+    .bind(collection.created_at.to_rfc3339())
+    .bind(collection.updated_at.to_rfc3339())
     .execute(&self.pool)
     .await?;
 
@@ -228,74 +1428,167 @@ impl Database {
 
     // Get all collections
     pub async fn get_collections(&self) -> Result<Vec<Collection>> {
-        let rows = sqlx::query("SELECT * FROM collections ORDER BY name")
+        let rows = sqlx::query("SELECT * FROM collections ORDER BY sort_order, name")
             .fetch_all(&self.pool)
             .await?;
 
         let mut collections = Vec::new();
         for row in rows {
-            collections.push(Collection {
-                id: row.get("id"),
-                name: row.get("name"),
-                description: row.get("description"),
-                parent_id: row.get("parent_id"),
-                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
-                    .with_timezone(&Utc),
-            });
+            collections.push(self.collection_from_row(&row)?);
         }
 
         Ok(collections)
     }
 
+    fn collection_from_row(&self, row: &sqlx::sqlite::SqliteRow) -> Result<Collection> {
+        Ok(Collection {
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            docs: row.get("docs"),
+            parent_id: row.get("parent_id"),
+            auth_type: row.get("auth_type"),
+            auth_data: self.reveal_opt(row.get("auth_data"))?,
+            sort_order: row.get("sort_order"),
+            version: row.get("version"),
+            trace_context: row.get("trace_context"),
+            default_headers: row.get("default_headers"),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
+                .with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
+                .with_timezone(&Utc),
+        })
+    }
+
+    // 🎓 TEACHING: The whole hierarchy in two queries. A folder whose parent is missing is
+    // shown at the top level rather than dropped.
+    pub async fn get_collection_tree(&self) -> Result<Vec<CollectionNode>> {
+        let collections = self.get_collections().await?;
+        let rows = sqlx::query("SELECT * FROM requests ORDER BY sort_order, name")
+            .fetch_all(&self.pool)
+            .await?;
+        let mut requests: HashMap<String, Vec<Request>> = HashMap::new();
+        for row in rows {
+            let request = self.request_from_row(&row)?;
+            requests.entry(request.collection_id.clone()).or_default().push(request);
+        }
+
+        fn build(
+            collection: &Collection,
+            collections: &[Collection],
+            requests: &mut HashMap<String, Vec<Request>>,
+            visited: &mut std::collections::HashSet<String>,
+        ) -> CollectionNode {
+            visited.insert(collection.id.clone());
+            let children = collections
+                .iter()
+                .filter(|child| child.parent_id.as_deref() == Some(collection.id.as_str()))
+                .filter(|child| !visited.contains(&child.id))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|child| build(child, collections, requests, visited))
+                .collect();
+            CollectionNode {
+                collection: collection.clone(),
+                requests: requests.remove(&collection.id).unwrap_or_default(),
+                children,
+            }
+        }
+
+        let ids: std::collections::HashSet<&str> = collections.iter().map(|c| c.id.as_str()).collect();
+        let mut visited = std::collections::HashSet::new();
+        Ok(collections
+            .iter()
+            .filter(|c| c.parent_id.as_deref().is_none_or(|parent| !ids.contains(parent)))
+            .map(|root| build(root, &collections, &mut requests, &mut visited))
+            .collect())
+    }
+
     // 🎓 TEACHING: This function updates an existing collection in the database.
-    pub async fn update_collection(&self, collection: Collection) -> Result<Collection> {
+    // The save only applies if `version` still matches the stored row (see version_conflict),
+    // unless `force` says to overwrite whatever is there.
+    // Docs have their own setter, so saving a collection never changes them.
+    pub async fn update_collection(&self, collection: Collection, force: bool) -> Result<Collection> {
+        self.check_parent(Some(&collection.id), collection.parent_id.as_deref()).await?;
         let now = Utc::now();
         let updated_collection = Collection {
             updated_at: now,
             ..collection
         };
 
-        sqlx::query(
+        let result = sqlx::query(
             r#"
             UPDATE collections
-            SET name = ?, description = ?, parent_id = ?, updated_at = ?
-            WHERE id = ?
+            SET name = ?, description = ?, parent_id = ?, auth_type = ?, auth_data = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND (? OR version = ?)
             "#,
         )
         .bind(&updated_collection.name)
         .bind(&updated_collection.description)
         .bind(&updated_collection.parent_id)
+        .bind(&updated_collection.auth_type)
+        .bind(self.seal_opt(updated_collection.auth_data.as_deref())?)
         .bind(updated_collection.updated_at.to_rfc3339())
         .bind(&updated_collection.id)
+        .bind(force)
+        .bind(updated_collection.version)
         .execute(&self.pool)
         .await?;
 
-        Ok(updated_collection)
+        let current = self
+            .get_collection_by_id(&updated_collection.id)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Collection not found: {}", updated_collection.id)))?;
+        if result.rows_affected() == 0 {
+            return Err(version_conflict(&current)?.into());
+        }
+
+        Ok(current)
     }
 
     // 🎓 TEACHING: This function deletes a collection from the database.
     // It takes the `id` of the collection to be deleted as input.
-    // It also deletes all the requests associated with this collection.
+    // Subfolders, requests and everything that belongs to them go with it through ON DELETE CASCADE.
+    // Proxy captures and monitors refer to it without a foreign key, so they're removed here,
+    // in the same transaction: either the whole tree goes or nothing does.
     pub async fn delete_collection(&self, id: &str) -> Result<()> {
-        println!("🗑️ DB: delete_collection called with id: {}", id);
-        
-        // First, delete all requests in the collection
-        println!("🔄 DB: Deleting requests for collection...");
-        let requests_result = sqlx::query("DELETE FROM requests WHERE collection_id = ?")
+        logging::debug(format!("Deleting collection {}", id));
+
+        // The folder and every folder under it
+        const SUBTREE: &str = "WITH RECURSIVE subtree(id) AS (SELECT ? UNION SELECT collections.id FROM collections JOIN subtree ON collections.parent_id = subtree.id)";
+
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query(&format!(
+            "{} SELECT value FROM variables WHERE collection_id IN subtree OR request_id IN (SELECT id FROM requests WHERE collection_id IN subtree)",
+            SUBTREE
+        ))
+        .bind(id)
+        .fetch_all(&mut tx)
+        .await?;
+        let keychain_values: Vec<String> = rows.iter().map(|row| row.get("value")).collect();
+
+        sqlx::query(&format!("{} DELETE FROM proxy_captures WHERE collection_id IN subtree", SUBTREE))
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut tx)
             .await?;
-        println!("✅ DB: Deleted {} requests", requests_result.rows_affected());
-
-        // Then, delete the collection itself
-        println!("🔄 DB: Deleting collection...");
-        let collection_result = sqlx::query("DELETE FROM collections WHERE id = ?")
+        sqlx::query(&format!(
+            "{} DELETE FROM monitors WHERE (target_type = 'collection' AND target_id IN subtree) OR (target_type = 'request' AND target_id IN (SELECT id FROM requests WHERE collection_id IN subtree))",
+            SUBTREE
+        ))
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+        let result = sqlx::query("DELETE FROM collections WHERE id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut tx)
             .await?;
-        println!("✅ DB: Deleted {} collection(s)", collection_result.rows_affected());
+        tx.commit().await?;
+        logging::debug(format!("Deleted {} collection(s)", result.rows_affected()));
+
+        // The keychain isn't part of the transaction, so it's only cleaned up once the rows are gone
+        for value in keychain_values {
+            self.delete_keychain_entry(&value).await;
+        }
 
         Ok(())
     }
@@ -308,21 +1601,102 @@ impl Database {
             .await?;
 
         if let Some(row) = row {
-            Ok(Some(Collection {
-                id: row.get("id"),
-                name: row.get("name"),
-                description: row.get("description"),
-                parent_id: row.get("parent_id"),
-                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
-                    .with_timezone(&Utc),
-            }))
+            Ok(Some(self.collection_from_row(&row)?))
         } else {
             Ok(None)
         }
     }
 
+    // 🎓 TEACHING: A folder's parent must exist and must not be the folder itself or anything
+    // inside it, otherwise the tree would loop and never reach a root
+    async fn check_parent(&self, collection_id: Option<&str>, parent_id: Option<&str>) -> Result<()> {
+        let mut ancestor = parent_id.map(str::to_string);
+        let mut visited = std::collections::HashSet::new();
+        while let Some(id) = ancestor {
+            if Some(id.as_str()) == collection_id {
+                return Err(AppError::validation("A folder cannot be moved inside itself").into());
+            }
+            if !visited.insert(id.clone()) {
+                break; // An existing loop higher up; not one this change creates
+            }
+            ancestor = self
+                .get_collection_by_id(&id)
+                .await?
+                .ok_or_else(|| AppError::not_found(format!("Collection not found: {}", id)))?
+                .parent_id;
+        }
+        Ok(())
+    }
+
+    // 🎓 TEACHING: Walk up from a folder to its root collection and return the first auth found.
+    // A collection with no auth (or "inherit") defers to its parent; we stop if we see a cycle.
+    pub async fn resolve_inherited_auth(
+        &self,
+        collection_id: &str,
+    ) -> Result<Option<(String, Option<String>)>> {
+        let mut visited = std::collections::HashSet::new();
+        let mut current = Some(collection_id.to_string());
+
+        while let Some(id) = current {
+            if !visited.insert(id.clone()) {
+                break;
+            }
+            let Some(collection) = self.get_collection_by_id(&id).await? else {
+                break;
+            };
+            match collection.auth_type {
+                Some(auth_type) if auth_type != "inherit" => {
+                    return Ok(Some((auth_type, collection.auth_data)));
+                }
+                _ => current = collection.parent_id,
+            }
+        }
+
+        Ok(None)
+    }
+
+    // The trace header settings of the nearest folder that has any, walking up like auth
+    pub async fn resolve_trace_context(&self, collection_id: &str) -> Result<Option<TraceContext>> {
+        let mut visited = std::collections::HashSet::new();
+        let mut current = Some(collection_id.to_string());
+
+        while let Some(id) = current {
+            if !visited.insert(id.clone()) {
+                break;
+            }
+            let Some(collection) = self.get_collection_by_id(&id).await? else {
+                break;
+            };
+            if let Some(context) = trace::parse_trace_context(collection.trace_context.as_deref())? {
+                return Ok(Some(context));
+            }
+            current = collection.parent_id;
+        }
+
+        Ok(None)
+    }
+
+    // 🎓 TEACHING: Unlike auth and trace settings, default headers add up: every folder on the
+    // way to the collection contributes, and the one nearest the request wins a clash
+    pub async fn resolve_default_headers(&self, collection_id: &str) -> Result<HashMap<String, String>> {
+        let mut visited = std::collections::HashSet::new();
+        let mut current = Some(collection_id.to_string());
+        let mut levels = Vec::new();
+
+        while let Some(id) = current {
+            if !visited.insert(id.clone()) {
+                break;
+            }
+            let Some(collection) = self.get_collection_by_id(&id).await? else {
+                break;
+            };
+            levels.push(default_headers::parse(collection.default_headers.as_deref())?);
+            current = collection.parent_id;
+        }
+
+        Ok(default_headers::merge(levels.into_iter().rev()))
+    }
+
     // Create a new request
     pub async fn create_request(
         &self,
@@ -336,22 +1710,34 @@ impl Database {
 
         let request = Request {
             id: id.clone(),
+            sort_order: self.next_request_sort_order(&collection_id).await?,
             collection_id,
             name,
             method,
             url,
             params: "[]".to_string(),
             headers: "{}".to_string(), // Empty JSON object as default
+            path_params: "{}".to_string(),
             body_type: "none".to_string(),
             body_str: None,
+            graphql_variables: None,
+            graphql_operation_name: None,
+            grpc_config: None,
+            captures: None,
+            scripts: None,
+            settings: None,
+            docs: None,
             auth_type: None,
             auth_data: None,
+            pinned: false,
+            last_used_at: None,
+            version: 1,
             created_at: now,
             updated_at: now,
         };
 
         sqlx::query(
-            "INSERT INTO requests (id, collection_id, name,method, url, params, headers, body_type, body_str,auth_type, auth_data, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO requests (id, collection_id, name,method, url, params, headers, path_params, body_type, body_str,auth_type, auth_data, sort_order, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&request.id)
         .bind(&request.collection_id)
@@ -360,10 +1746,12 @@ impl Database {
         .bind(&request.url)
         .bind(&request.params)
         .bind(&request.headers)
+        .bind(&request.path_params)
         .bind(&request.body_type)
         .bind(&request.body_str)
         .bind(&request.auth_type)
         .bind(&request.auth_data)
+        .bind(request.sort_order)
         .bind(request.created_at.to_rfc3339())
         .bind(request.updated_at.to_rfc3339())
         .execute(&self.pool)
@@ -374,51 +1762,84 @@ impl Database {
 
     // Get requests for a specific collection
     pub async fn get_requests_by_collection(&self, collection_id: &str) -> Result<Vec<Request>> {
-        let rows = sqlx::query("SELECT * FROM requests WHERE collection_id = ? ORDER BY name")
+        let rows = sqlx::query("SELECT * FROM requests WHERE collection_id = ? ORDER BY sort_order, name")
             .bind(collection_id)
             .fetch_all(&self.pool)
             .await?;
 
         let mut requests = Vec::new();
         for row in rows {
-            requests.push(Request {
-                id: row.get("id"),
-                collection_id: row.get("collection_id"),
-                name: row.get("name"),
-                method: row.get("method"),
-                url: row.get("url"),
-                params: row.get("params"),
-                headers: row.get("headers"),
-                body_type: row.get("body_type"),
-                body_str: row.get("body_str"),
-                auth_type: row.get("auth_type"),
-                auth_data: row.get("auth_data"),
-                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
-                    .with_timezone(&Utc),
-            });
+            requests.push(self.request_from_row(&row)?);
         }
 
         Ok(requests)
     }
 
+    fn request_from_row(&self, row: &sqlx::sqlite::SqliteRow) -> Result<Request> {
+        let last_used_at: Option<String> = row.get("last_used_at");
+        Ok(Request {
+            id: row.get("id"),
+            collection_id: row.get("collection_id"),
+            name: row.get("name"),
+            method: row.get("method"),
+            url: row.get("url"),
+            params: row.get("params"),
+            headers: row.get("headers"),
+            path_params: row.get("path_params"),
+            body_type: row.get("body_type"),
+            body_str: row.get("body_str"),
+            graphql_variables: row.get("graphql_variables"),
+            graphql_operation_name: row.get("graphql_operation_name"),
+            grpc_config: row.get("grpc_config"),
+            captures: row.get("captures"),
+            scripts: row.get("scripts"),
+            settings: row.get("settings"),
+            docs: row.get("docs"),
+            auth_type: row.get("auth_type"),
+            auth_data: self.reveal_opt(row.get("auth_data"))?,
+            sort_order: row.get("sort_order"),
+            pinned: row.get("pinned"),
+            last_used_at: last_used_at
+                .map(|at| DateTime::parse_from_rfc3339(&at).map(|at| at.with_timezone(&Utc)))
+                .transpose()?,
+            version: row.get("version"),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
+                .with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
+                .with_timezone(&Utc),
+        })
+    }
+
     // 🎓 TEACHING: This function updates an existing request in the database.
     // It takes a `Request` struct as input, which contains the new data.
     // The `id` field of the `Request` struct is used to identify the request to be updated.
     // We also update the `updated_at` timestamp to the current time.
-    pub async fn update_request(&self, request: Request) -> Result<Request> {
+    // pinned, last_used_at and docs have their own setters, so saving a request never changes them.
+    // The save only applies if `version` still matches the stored row (see version_conflict),
+    // unless `force` says to overwrite whatever is there.
+    pub async fn update_request(&self, request: Request, force: bool) -> Result<Request> {
         let now = Utc::now();
         let updated_request = Request {
             updated_at: now,
             ..request
         };
 
-        sqlx::query(
+        let mut conn = self.pool.acquire().await?;
+        self.write_request(&mut conn, &updated_request, force).await
+    }
+
+    // Saves every editable field and returns the stored copy, with its new version
+    async fn write_request(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        updated_request: &Request,
+        force: bool,
+    ) -> Result<Request> {
+        let result = sqlx::query(
             r#"
             UPDATE requests
-            SET collection_id = ?, name = ?, method = ?, url = ?, params = ?, headers = ?, body_type = ?, body_str = ?, auth_type = ?, auth_data = ?, updated_at = ?
-            WHERE id = ?
+            SET collection_id = ?, name = ?, method = ?, url = ?, params = ?, headers = ?, path_params = ?, body_type = ?, body_str = ?, graphql_variables = ?, graphql_operation_name = ?, grpc_config = ?, captures = ?, scripts = ?, settings = ?, auth_type = ?, auth_data = ?, updated_at = ?, version = version + 1
+            WHERE id = ? AND (? OR version = ?)
             "#,
         )
         .bind(&updated_request.collection_id)
@@ -427,29 +1848,42 @@ impl Database {
         .bind(&updated_request.url)
         .bind(&updated_request.params)
         .bind(&updated_request.headers)
+        .bind(&updated_request.path_params)
         .bind(&updated_request.body_type)
         .bind(&updated_request.body_str)
+        .bind(&updated_request.graphql_variables)
+        .bind(&updated_request.graphql_operation_name)
+        .bind(&updated_request.grpc_config)
+        .bind(&updated_request.captures)
+        .bind(&updated_request.scripts)
+        .bind(&updated_request.settings)
         .bind(&updated_request.auth_type)
-        .bind(&updated_request.auth_data)
+        .bind(self.seal_opt(updated_request.auth_data.as_deref())?)
         .bind(updated_request.updated_at.to_rfc3339())
         .bind(&updated_request.id)
-        .execute(&self.pool)
+        .bind(force)
+        .bind(updated_request.version)
+        .execute(&mut *conn)
         .await?;
 
-        Ok(updated_request)
+        let row = sqlx::query("SELECT * FROM requests WHERE id = ?")
+            .bind(&updated_request.id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Request not found: {}", updated_request.id)))?;
+        let current = self.request_from_row(&row)?;
+        if result.rows_affected() == 0 {
+            return Err(version_conflict(&current)?.into());
+        }
+
+        Ok(current)
     }
 
     // 🎓 TEACHING: This function deletes a request from the database.
     // It takes the `id` of the request to be deleted as input.
     pub async fn delete_request(&self, id: &str) -> Result<()> {
-        println!("🗑️ DB: delete_request called with id: {}", id);
-        
-        println!("🔄 DB: Deleting request...");
-        let result = sqlx::query("DELETE FROM requests WHERE id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
-        println!("✅ DB: Deleted {} request(s)", result.rows_affected());
+        let deleted = self.bulk_delete_requests(&[id.to_string()]).await?;
+        logging::debug(format!("Deleted {} request(s) for {}", deleted, id));
 
         Ok(())
     }
@@ -463,62 +1897,528 @@ impl Database {
             .await?;
 
         if let Some(row) = row {
-            Ok(Some(Request {
-                id: row.get("id"),
-                collection_id: row.get("collection_id"),
-                name: row.get("name"),
-                method: row.get("method"),
-                url: row.get("url"),
-                params: row.get("params"),
-                headers: row.get("headers"),
-                body_type: row.get("body_type"),
-                body_str: row.get("body_str"),
-                auth_type: row.get("auth_type"),
-                auth_data: row.get("auth_data"),
-                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
-                    .with_timezone(&Utc),
-            }))
+            Ok(Some(self.request_from_row(&row)?))
         } else {
             Ok(None)
         }
     }
 
-    // ============ PHASE 2: ENVIRONMENT MANAGEMENT ============
-    
-    // 🎓 TEACHING: Create a new environment
-    // Environments help organize variables for different stages (dev, prod, staging)
-    pub async fn create_environment(&self, name: String) -> Result<Environment> {
-        let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
-
-        let environment = Environment {
-            id: id.clone(),
-            name,
-            is_active: false, // New environments start inactive
-            created_at: now,
-            updated_at: now,
-        };
-
-        sqlx::query(
-            "INSERT INTO environments (id, name, is_active, created_at, updated_at) VALUES (?, ?, ?, ?, ?)"
-        )
-        .bind(&environment.id)
-        .bind(&environment.name)
-        .bind(environment.is_active)
-        .bind(environment.created_at.to_rfc3339())
-        .bind(environment.updated_at.to_rfc3339())
-        .execute(&self.pool)
-        .await?;
+    // ============ SIDEBAR ORDER ============
 
-        Ok(environment)
+    async fn next_collection_sort_order(&self, parent_id: Option<&str>) -> Result<i64> {
+        let row = sqlx::query("SELECT COALESCE(MAX(sort_order) + 1, 0) AS next FROM collections WHERE parent_id IS ?")
+            .bind(parent_id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.get("next"))
     }
 
-    // 🎓 TEACHING: Get all environments
-    pub async fn get_environments(&self) -> Result<Vec<Environment>> {
-        let rows = sqlx::query("SELECT * FROM environments ORDER BY name")
-            .fetch_all(&self.pool)
+    async fn next_request_sort_order(&self, collection_id: &str) -> Result<i64> {
+        let row = sqlx::query("SELECT COALESCE(MAX(sort_order) + 1, 0) AS next FROM requests WHERE collection_id = ?")
+            .bind(collection_id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.get("next"))
+    }
+
+    // 🎓 TEACHING: Drag and drop in the sidebar
+    // Moving renumbers every sibling in the destination (and the source, to close the gap)
+    // inside one transaction, so the sidebar never sees a half-applied order. `position`
+    // is the index among the destination's other items; past the end means last.
+    pub async fn move_request(&self, request_id: &str, target_collection_id: &str, position: usize) -> Result<Request> {
+        let request = self
+            .get_request_by_id(request_id)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Request not found: {}", request_id)))?;
+        if self.get_collection_by_id(target_collection_id).await?.is_none() {
+            return Err(AppError::not_found(format!("Collection not found: {}", target_collection_id)).into());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let siblings = "SELECT id FROM requests WHERE collection_id = ? AND id != ? ORDER BY sort_order, name";
+        let mut order = sibling_ids(&mut tx, siblings, Some(target_collection_id), request_id).await?;
+        order.insert(position.min(order.len()), request_id.to_string());
+        renumber(&mut tx, "requests", &order).await?;
+        sqlx::query("UPDATE requests SET collection_id = ?, updated_at = ?, version = version + 1 WHERE id = ?")
+            .bind(target_collection_id)
+            .bind(Utc::now().to_rfc3339())
+            .bind(request_id)
+            .execute(&mut tx)
+            .await?;
+        if request.collection_id != target_collection_id {
+            let left = sibling_ids(&mut tx, siblings, Some(&request.collection_id), request_id).await?;
+            renumber(&mut tx, "requests", &left).await?;
+        }
+        tx.commit().await?;
+
+        self.get_request_by_id(request_id)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Request not found: {}", request_id)).into())
+    }
+
+    // Same for folders; `target_parent_id` None moves it to the top level
+    pub async fn move_collection(
+        &self,
+        collection_id: &str,
+        target_parent_id: Option<&str>,
+        position: usize,
+    ) -> Result<Collection> {
+        let collection = self
+            .get_collection_by_id(collection_id)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Collection not found: {}", collection_id)))?;
+
+        self.check_parent(Some(collection_id), target_parent_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+        let siblings = "SELECT id FROM collections WHERE parent_id IS ? AND id != ? ORDER BY sort_order, name";
+        let mut order = sibling_ids(&mut tx, siblings, target_parent_id, collection_id).await?;
+        order.insert(position.min(order.len()), collection_id.to_string());
+        renumber(&mut tx, "collections", &order).await?;
+        sqlx::query("UPDATE collections SET parent_id = ?, updated_at = ?, version = version + 1 WHERE id = ?")
+            .bind(target_parent_id)
+            .bind(Utc::now().to_rfc3339())
+            .bind(collection_id)
+            .execute(&mut tx)
+            .await?;
+        if collection.parent_id.as_deref() != target_parent_id {
+            let left = sibling_ids(&mut tx, siblings, collection.parent_id.as_deref(), collection_id).await?;
+            renumber(&mut tx, "collections", &left).await?;
+        }
+        tx.commit().await?;
+
+        self.get_collection_by_id(collection_id)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Collection not found: {}", collection_id)).into())
+    }
+
+    // ============ TAGS ============
+
+    pub async fn create_tag(&self, name: String, color: Option<String>) -> Result<Tag> {
+        let name = self.check_tag_name(None, &name).await?;
+        let now = Utc::now();
+        let tag = Tag {
+            id: Uuid::new_v4().to_string(),
+            name,
+            color,
+            created_at: now,
+            updated_at: now,
+        };
+
+        sqlx::query("INSERT INTO tags (id, name, color, created_at, updated_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&tag.id)
+            .bind(&tag.name)
+            .bind(&tag.color)
+            .bind(tag.created_at.to_rfc3339())
+            .bind(tag.updated_at.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(tag)
+    }
+
+    pub async fn get_tags(&self) -> Result<Vec<Tag>> {
+        let rows = sqlx::query("SELECT * FROM tags ORDER BY name COLLATE NOCASE")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Self::tag_from_row).collect()
+    }
+
+    pub async fn update_tag(&self, tag: Tag) -> Result<Tag> {
+        let updated_tag = Tag {
+            name: self.check_tag_name(Some(&tag.id), &tag.name).await?,
+            updated_at: Utc::now(),
+            ..tag
+        };
+
+        let result = sqlx::query("UPDATE tags SET name = ?, color = ?, updated_at = ? WHERE id = ?")
+            .bind(&updated_tag.name)
+            .bind(&updated_tag.color)
+            .bind(updated_tag.updated_at.to_rfc3339())
+            .bind(&updated_tag.id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Tag not found").into());
+        }
+
+        Ok(updated_tag)
+    }
+
+    // Removing a tag untags everything it was on (ON DELETE CASCADE)
+    pub async fn delete_tag(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM tags WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Names are trimmed and must be unique ignoring case, so "Smoke" and "smoke" can't both exist
+    async fn check_tag_name(&self, tag_id: Option<&str>, name: &str) -> Result<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::validation("Tag name cannot be empty").into());
+        }
+        let taken = sqlx::query("SELECT id FROM tags WHERE name = ? COLLATE NOCASE AND id IS NOT ?")
+            .bind(name)
+            .bind(tag_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        if taken.is_some() {
+            return Err(AppError::validation(format!("A tag named {} already exists", name)).into());
+        }
+        Ok(name.to_string())
+    }
+
+    fn tag_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Tag> {
+        Ok(Tag {
+            id: row.get("id"),
+            name: row.get("name"),
+            color: row.get("color"),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&Utc),
+        })
+    }
+
+    // Tagging twice is a no-op
+    pub async fn tag_request(&self, request_id: &str, tag_id: &str) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO request_tags (request_id, tag_id) VALUES (?, ?)")
+            .bind(request_id)
+            .bind(tag_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn untag_request(&self, request_id: &str, tag_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM request_tags WHERE request_id = ? AND tag_id = ?")
+            .bind(request_id)
+            .bind(tag_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn tag_collection(&self, collection_id: &str, tag_id: &str) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO collection_tags (collection_id, tag_id) VALUES (?, ?)")
+            .bind(collection_id)
+            .bind(tag_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn untag_collection(&self, collection_id: &str, tag_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM collection_tags WHERE collection_id = ? AND tag_id = ?")
+            .bind(collection_id)
+            .bind(tag_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_request_tags(&self, request_id: &str) -> Result<Vec<Tag>> {
+        let rows = sqlx::query(
+            "SELECT t.* FROM tags t JOIN request_tags rt ON rt.tag_id = t.id WHERE rt.request_id = ? ORDER BY t.name COLLATE NOCASE"
+        )
+        .bind(request_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::tag_from_row).collect()
+    }
+
+    pub async fn get_collection_tags(&self, collection_id: &str) -> Result<Vec<Tag>> {
+        let rows = sqlx::query(
+            "SELECT t.* FROM tags t JOIN collection_tags ct ON ct.tag_id = t.id WHERE ct.collection_id = ? ORDER BY t.name COLLATE NOCASE"
+        )
+        .bind(collection_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::tag_from_row).collect()
+    }
+
+    // 🎓 TEACHING: Requests carrying the tag themselves, from every collection.
+    // A tag on a collection isn't copied down to its requests.
+    pub async fn get_requests_by_tag(&self, tag_id: &str) -> Result<Vec<Request>> {
+        let rows = sqlx::query(
+            "SELECT r.* FROM requests r JOIN request_tags rt ON rt.request_id = r.id WHERE rt.tag_id = ? ORDER BY r.name"
+        )
+        .bind(tag_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut requests = Vec::new();
+        for row in rows {
+            requests.push(self.request_from_row(&row)?);
+        }
+
+        Ok(requests)
+    }
+
+    pub async fn get_collections_by_tag(&self, tag_id: &str) -> Result<Vec<Collection>> {
+        let rows = sqlx::query(
+            "SELECT c.* FROM collections c JOIN collection_tags ct ON ct.collection_id = c.id WHERE ct.tag_id = ? ORDER BY c.name"
+        )
+        .bind(tag_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut collections = Vec::new();
+        for row in rows {
+            collections.push(self.collection_from_row(&row)?);
+        }
+
+        Ok(collections)
+    }
+
+    // ============ FAVORITES AND RECENTS ============
+
+    pub async fn set_request_pinned(&self, id: &str, pinned: bool) -> Result<()> {
+        let result = sqlx::query("UPDATE requests SET pinned = ? WHERE id = ?")
+            .bind(pinned)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found(format!("Request not found: {}", id)).into());
+        }
+
+        Ok(())
+    }
+
+    pub async fn set_request_last_used(&self, id: &str, at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE requests SET last_used_at = ? WHERE id = ?")
+            .bind(at.to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_favorite_requests(&self) -> Result<Vec<Request>> {
+        let rows = sqlx::query("SELECT * FROM requests WHERE pinned ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut requests = Vec::new();
+        for row in rows {
+            requests.push(self.request_from_row(&row)?);
+        }
+
+        Ok(requests)
+    }
+
+    // Most recently sent first; requests never sent from the builder aren't listed
+    pub async fn get_recent_requests(&self, limit: u32) -> Result<Vec<Request>> {
+        let rows = sqlx::query(
+            "SELECT * FROM requests WHERE last_used_at IS NOT NULL ORDER BY last_used_at DESC LIMIT ?"
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut requests = Vec::new();
+        for row in rows {
+            requests.push(self.request_from_row(&row)?);
+        }
+
+        Ok(requests)
+    }
+
+    // ============ DOCS ============
+    // 🎓 TEACHING: Markdown docs are saved on their own, so saving a request or collection
+    // from an editor that doesn't show them never wipes them. Blank docs are stored as NULL.
+
+    pub async fn set_request_docs(&self, id: &str, docs: Option<String>) -> Result<()> {
+        let result = sqlx::query("UPDATE requests SET docs = ?, updated_at = ? WHERE id = ?")
+            .bind(docs.filter(|docs| !docs.trim().is_empty()))
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found(format!("Request not found: {}", id)).into());
+        }
+
+        Ok(())
+    }
+
+    pub async fn set_collection_docs(&self, id: &str, docs: Option<String>) -> Result<()> {
+        let result = sqlx::query("UPDATE collections SET docs = ?, updated_at = ? WHERE id = ?")
+            .bind(docs.filter(|docs| !docs.trim().is_empty()))
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found(format!("Collection not found: {}", id)).into());
+        }
+
+        Ok(())
+    }
+
+    // Like docs, saved on its own; None goes back to the parent's (or the global) settings
+    pub async fn set_collection_trace_context(&self, id: &str, context: Option<&TraceContext>) -> Result<()> {
+        let json = context.map(serde_json::to_string).transpose()?;
+        let result = sqlx::query("UPDATE collections SET trace_context = ?, updated_at = ? WHERE id = ?")
+            .bind(json)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found(format!("Collection not found: {}", id)).into());
+        }
+
+        Ok(())
+    }
+
+    // Saved on its own like the trace settings; None (or no headers) adds nothing at this level
+    pub async fn set_collection_default_headers(
+        &self,
+        id: &str,
+        headers: Option<&HashMap<String, String>>,
+    ) -> Result<()> {
+        let json = headers.filter(|headers| !headers.is_empty()).map(serde_json::to_string).transpose()?;
+        let result = sqlx::query("UPDATE collections SET default_headers = ?, updated_at = ? WHERE id = ?")
+            .bind(json)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found(format!("Collection not found: {}", id)).into());
+        }
+
+        Ok(())
+    }
+
+    // ============ BULK OPERATIONS ============
+    // 🎓 TEACHING: Each batch runs in one transaction: if any item fails, none of the
+    // batch is saved, so an import or mass edit never stops halfway.
+
+    pub async fn bulk_update_requests(&self, requests: Vec<Request>) -> Result<Vec<Request>> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let mut updated = Vec::with_capacity(requests.len());
+        for request in requests {
+            let updated_request = Request {
+                updated_at: now,
+                ..request
+            };
+            updated.push(self.write_request(&mut tx, &updated_request, false).await?);
+        }
+        tx.commit().await?;
+
+        Ok(updated)
+    }
+
+    // Everything that belongs to a request goes with it: samples, mocks, tags and scoped
+    // variables through ON DELETE CASCADE, captures and monitors here. Returns how many
+    // requests were deleted; unknown ids are skipped.
+    pub async fn bulk_delete_requests(&self, ids: &[String]) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut keychain_values = Vec::new();
+        let mut deleted = 0;
+        for id in ids {
+            let rows = sqlx::query("SELECT value FROM variables WHERE request_id = ?")
+                .bind(id)
+                .fetch_all(&mut tx)
+                .await?;
+            keychain_values.extend(rows.iter().map(|row| row.get::<String, _>("value")));
+            sqlx::query("DELETE FROM proxy_captures WHERE request_id = ?")
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+            sqlx::query("DELETE FROM monitors WHERE target_type = 'request' AND target_id = ?")
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+            deleted += sqlx::query("DELETE FROM requests WHERE id = ?")
+                .bind(id)
+                .execute(&mut tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+
+        // The keychain isn't part of the transaction, so it's only cleaned up once the rows are gone
+        for value in keychain_values {
+            self.delete_keychain_entry(&value).await;
+        }
+
+        Ok(deleted)
+    }
+
+    pub async fn bulk_create_variables(&self, variables: Vec<NewVariable>) -> Result<Vec<Variable>> {
+        let mut tx = self.pool.begin().await?;
+        let mut created = Vec::with_capacity(variables.len());
+        for variable in variables {
+            let owners = [&variable.environment_id, &variable.collection_id, &variable.request_id]
+                .iter()
+                .filter(|owner| owner.is_some())
+                .count();
+            if owners > 1 {
+                return Err(AppError::validation(format!(
+                    "Variable {} belongs to an environment, a collection, or a request, not several",
+                    variable.key
+                )).into());
+            }
+            created.push(self.insert_variable(&mut tx, variable).await?);
+        }
+        tx.commit().await?;
+
+        Ok(created)
+    }
+
+    // ============ PHASE 2: ENVIRONMENT MANAGEMENT ============
+    
+    // 🎓 TEACHING: Create a new environment
+    // Environments help organize variables for different stages (dev, prod, staging)
+    pub async fn create_environment(&self, name: String) -> Result<Environment> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let environment = Environment {
+            id: id.clone(),
+            name,
+            is_active: false, // New environments start inactive
+            host_overrides: "{}".to_string(),
+            default_headers: "{}".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        sqlx::query(
+            "INSERT INTO environments (id, name, is_active, host_overrides, default_headers, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&environment.id)
+        .bind(&environment.name)
+        .bind(environment.is_active)
+        .bind(&environment.host_overrides)
+        .bind(&environment.default_headers)
+        .bind(environment.created_at.to_rfc3339())
+        .bind(environment.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(environment)
+    }
+
+    // 🎓 TEACHING: Get all environments
+    pub async fn get_environments(&self) -> Result<Vec<Environment>> {
+        let rows = sqlx::query("SELECT * FROM environments ORDER BY name")
+            .fetch_all(&self.pool)
             .await?;
 
         let mut environments = Vec::new();
@@ -527,6 +2427,8 @@ impl Database {
                 id: row.get("id"),
                 name: row.get("name"),
                 is_active: row.get("is_active"),
+                host_overrides: row.get("host_overrides"),
+                default_headers: row.get("default_headers"),
                 created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
                     .with_timezone(&Utc),
                 updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
@@ -556,7 +2458,7 @@ impl Database {
 
         // Return the updated environment
         self.get_environment_by_id(id).await?.ok_or_else(|| {
-            anyhow::anyhow!("Environment not found after activation")
+            AppError::not_found("Environment not found after activation").into()
         })
     }
 
@@ -585,6 +2487,8 @@ impl Database {
                 id: row.get("id"),
                 name: row.get("name"),
                 is_active: row.get("is_active"),
+                host_overrides: row.get("host_overrides"),
+                default_headers: row.get("default_headers"),
                 created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
                     .with_timezone(&Utc),
                 updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
@@ -603,20 +2507,32 @@ impl Database {
             ..environment
         };
 
-        sqlx::query("UPDATE environments SET name = ?, is_active = ?, updated_at = ? WHERE id = ?")
-            .bind(&updated_environment.name)
-            .bind(updated_environment.is_active)
-            .bind(updated_environment.updated_at.to_rfc3339())
-            .bind(&updated_environment.id)
-            .execute(&self.pool)
-            .await?;
+        sqlx::query(
+            "UPDATE environments SET name = ?, is_active = ?, host_overrides = ?, default_headers = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(&updated_environment.name)
+        .bind(updated_environment.is_active)
+        .bind(&updated_environment.host_overrides)
+        .bind(&updated_environment.default_headers)
+        .bind(updated_environment.updated_at.to_rfc3339())
+        .bind(&updated_environment.id)
+        .execute(&self.pool)
+        .await?;
 
         Ok(updated_environment)
     }
 
     // 🎓 TEACHING: Delete environment (and all its variables)
     pub async fn delete_environment(&self, id: &str) -> Result<()> {
-        // First, delete all variables in this environment
+        // First, delete all variables in this environment (and any keychain entries they own)
+        let rows = sqlx::query("SELECT value FROM variables WHERE environment_id = ?")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await?;
+        for row in rows {
+            self.delete_keychain_entry(&row.get::<String, _>("value")).await;
+        }
+
         sqlx::query("DELETE FROM variables WHERE environment_id = ?")
             .bind(id)
             .execute(&self.pool)
@@ -631,41 +2547,139 @@ impl Database {
         Ok(())
     }
 
-    // ============ PHASE 2: VARIABLE MANAGEMENT ============
+    // 🎓 TEACHING: Deep-copy an environment and its variables ("copy staging to create prod").
+    // Secrets get their own copies - keychain values are stored again under the new
+    // variable's id - so deleting one environment never breaks the other.
+    pub async fn duplicate_environment(&self, id: &str, new_name: String) -> Result<Environment> {
+        if self.is_locked() {
+            return Err(AppError::auth("Workspace is locked").into());
+        }
+        let source = self
+            .get_environment_by_id(id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Environment not found"))?;
 
-    // 🎓 TEACHING: Create a new variable
-    // Variables can be global (environment_id = None) or environment-specific
-    pub async fn create_variable(
-        &self,
+        let created = self.create_environment(new_name).await?;
+        let copy = self
+            .update_environment(Environment {
+                host_overrides: source.host_overrides,
+                default_headers: source.default_headers,
+                ..created
+            })
+            .await?;
+
+        for variable in self.get_variables(Some(id)).await? {
+            // get_variables leaves the reference in place when the keychain can't be read
+            if secrets::parse_keychain_reference(&variable.value).is_some() {
+                return Err(AppError::auth(format!("Could not read secret {} from the keychain", variable.key)).into());
+            }
+            let in_keychain = self
+                .stored_variable_value(&variable.id)
+                .await?
+                .as_deref()
+                .and_then(secrets::parse_keychain_reference)
+                .is_some();
+
+            let mut new_variable = self
+                .create_variable(Some(copy.id.clone()), variable.key, variable.value.clone(), variable.is_secret)
+                .await?;
+            if variable.source.is_some() || variable.value_type != new_variable.value_type {
+                new_variable = self
+                    .update_variable(Variable {
+                        source: variable.source,
+                        value_type: variable.value_type,
+                        ..new_variable
+                    })
+                    .await?;
+            }
+            if in_keychain {
+                self.store_secret(&new_variable.id, variable.value).await?;
+            }
+        }
+
+        Ok(copy)
+    }
+
+    // ============ PHASE 2: VARIABLE MANAGEMENT ============
+
+    // 🎓 TEACHING: Create a new variable
+    // Variables can be global (environment_id = None) or environment-specific
+    pub async fn create_variable(
+        &self,
         environment_id: Option<String>,
         key: String,
         value: String,
         is_secret: bool,
     ) -> Result<Variable> {
+        let variable = NewVariable {
+            environment_id,
+            collection_id: None,
+            request_id: None,
+            key,
+            value,
+            is_secret,
+        };
+        let mut conn = self.pool.acquire().await?;
+        self.insert_variable(&mut conn, variable).await
+    }
+
+    // 🎓 TEACHING: Create a variable that lives with a collection/folder or a single request.
+    // These override environment and global variables of the same name (see resolve_variables).
+    pub async fn create_scoped_variable(
+        &self,
+        collection_id: Option<String>,
+        request_id: Option<String>,
+        key: String,
+        value: String,
+        is_secret: bool,
+    ) -> Result<Variable> {
+        if collection_id.is_some() == request_id.is_some() {
+            return Err(AppError::validation("A scoped variable needs either a collection or a request").into());
+        }
+        let variable = NewVariable {
+            environment_id: None,
+            collection_id,
+            request_id,
+            key,
+            value,
+            is_secret,
+        };
+        let mut conn = self.pool.acquire().await?;
+        self.insert_variable(&mut conn, variable).await
+    }
+
+    // Takes a connection so bulk_create_variables can insert inside its transaction
+    async fn insert_variable(&self, conn: &mut sqlx::SqliteConnection, new: NewVariable) -> Result<Variable> {
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
 
         let variable = Variable {
             id: id.clone(),
-            environment_id,
-            key,
-            value,
-            is_secret,
+            environment_id: new.environment_id,
+            key: new.key,
+            value: new.value,
+            is_secret: new.is_secret,
+            source: None,
+            collection_id: new.collection_id,
+            request_id: new.request_id,
+            value_type: default_value_type(),
             created_at: now,
             updated_at: now,
         };
 
         sqlx::query(
-            "INSERT INTO variables (id, environment_id, key, value, is_secret, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO variables (id, environment_id, collection_id, request_id, key, value, is_secret, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&variable.id)
         .bind(&variable.environment_id)
+        .bind(&variable.collection_id)
+        .bind(&variable.request_id)
         .bind(&variable.key)
-        .bind(&variable.value)
+        .bind(self.seal_variable_value(&variable.value, variable.is_secret)?)
         .bind(variable.is_secret)
         .bind(variable.created_at.to_rfc3339())
         .bind(variable.updated_at.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *conn)
         .await?;
 
         Ok(variable)
@@ -679,19 +2693,43 @@ impl Database {
                 .fetch_all(&self.pool)
                 .await?
         } else {
-            sqlx::query("SELECT * FROM variables WHERE environment_id IS NULL ORDER BY key")
+            sqlx::query("SELECT * FROM variables WHERE environment_id IS NULL AND collection_id IS NULL AND request_id IS NULL ORDER BY key")
                 .fetch_all(&self.pool)
                 .await?
         };
 
+        self.variables_from_rows(rows).await
+    }
+
+    pub async fn get_collection_variables(&self, collection_id: &str) -> Result<Vec<Variable>> {
+        let rows = sqlx::query("SELECT * FROM variables WHERE collection_id = ? ORDER BY key")
+            .bind(collection_id)
+            .fetch_all(&self.pool)
+            .await?;
+        self.variables_from_rows(rows).await
+    }
+
+    pub async fn get_request_variables(&self, request_id: &str) -> Result<Vec<Variable>> {
+        let rows = sqlx::query("SELECT * FROM variables WHERE request_id = ? ORDER BY key")
+            .bind(request_id)
+            .fetch_all(&self.pool)
+            .await?;
+        self.variables_from_rows(rows).await
+    }
+
+    async fn variables_from_rows(&self, rows: Vec<sqlx::sqlite::SqliteRow>) -> Result<Vec<Variable>> {
         let mut variables = Vec::new();
         for row in rows {
             variables.push(Variable {
                 id: row.get("id"),
                 environment_id: row.get("environment_id"),
                 key: row.get("key"),
-                value: row.get("value"),
+                value: self.reveal(row.get("value"))?,
                 is_secret: row.get("is_secret"),
+                source: row.get("source"),
+                collection_id: row.get("collection_id"),
+                request_id: row.get("request_id"),
+                value_type: row.get("value_type"),
                 created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
                     .with_timezone(&Utc),
                 updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
@@ -699,6 +2737,23 @@ impl Database {
             });
         }
 
+        // 🎓 TEACHING: Secrets kept in the OS keychain are fetched on read.
+        // If the keychain can't be reached the reference is left in place, like a locked secret.
+        // While the workspace is locked they stay hidden too.
+        let locked = self.is_locked();
+        for variable in &mut variables {
+            if locked {
+                break;
+            }
+            if let Some(key) = secrets::parse_keychain_reference(&variable.value).map(str::to_string) {
+                match self.keychain_call(move |keychain| keychain.get(&key)).await {
+                    Ok(Some(value)) => variable.value = value,
+                    Ok(None) => logging::warn(format!("Keychain entry missing for variable {}", variable.key)),
+                    Err(e) => logging::warn(format!("Failed to read variable {} from keychain: {}", variable.key, e)),
+                }
+            }
+        }
+
         Ok(variables)
     }
 
@@ -727,6 +2782,8 @@ impl Database {
                 id: row.get("id"),
                 name: row.get("name"),
                 is_active: row.get("is_active"),
+                host_overrides: row.get("host_overrides"),
+                default_headers: row.get("default_headers"),
                 created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
                     .with_timezone(&Utc),
                 updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
@@ -739,19 +2796,46 @@ impl Database {
 
     // 🎓 TEACHING: Update a variable
     pub async fn update_variable(&self, variable: Variable) -> Result<Variable> {
+        // Provider-backed values aren't known until they're fetched
+        if variable.source.is_none() {
+            placeholders::validate_typed_value(&variable.value_type, &variable.value)?;
+        }
         let now = Utc::now();
         let updated_variable = Variable {
             updated_at: now,
             ..variable
         };
 
+        // Secrets already in the keychain stay there; un-marking a secret moves it back to SQLite
+        let stored_value = self.stored_variable_value(&updated_variable.id).await?;
+        let value_to_store = match stored_value.as_deref().and_then(secrets::parse_keychain_reference) {
+            Some(keychain_key) => {
+                let keychain_key = keychain_key.to_string();
+                if updated_variable.is_secret {
+                    if secrets::parse_keychain_reference(&updated_variable.value).is_none() {
+                        let (key, value) = (keychain_key.clone(), updated_variable.value.clone());
+                        self.keychain_call(move |keychain| keychain.set(&key, &value)).await?;
+                    }
+                    secrets::keychain_reference(&keychain_key)
+                } else {
+                    self.keychain_call(move |keychain| keychain.delete(&keychain_key)).await?;
+                    updated_variable.value.clone()
+                }
+            }
+            None => self.seal_variable_value(&updated_variable.value, updated_variable.is_secret)?,
+        };
+
         sqlx::query(
-            "UPDATE variables SET environment_id = ?, key = ?, value = ?, is_secret = ?, updated_at = ? WHERE id = ?"
+            "UPDATE variables SET environment_id = ?, collection_id = ?, request_id = ?, key = ?, value = ?, is_secret = ?, source = ?, value_type = ?, updated_at = ? WHERE id = ?"
         )
         .bind(&updated_variable.environment_id)
+        .bind(&updated_variable.collection_id)
+        .bind(&updated_variable.request_id)
         .bind(&updated_variable.key)
-        .bind(&updated_variable.value)
+        .bind(value_to_store)
         .bind(updated_variable.is_secret)
+        .bind(&updated_variable.source)
+        .bind(&updated_variable.value_type)
         .bind(updated_variable.updated_at.to_rfc3339())
         .bind(&updated_variable.id)
         .execute(&self.pool)
@@ -760,8 +2844,31 @@ impl Database {
         Ok(updated_variable)
     }
 
+    // 🎓 TEACHING: Set a variable in an environment (or a global for None), creating it if
+    // needed. Response captures and scripts write their values through this.
+    pub async fn set_environment_variable(&self, environment_id: Option<&str>, key: &str, value: String) -> Result<Variable> {
+        let existing = self
+            .get_variables(environment_id)
+            .await?
+            .into_iter()
+            .find(|variable| variable.key == key);
+
+        match existing {
+            // A captured value replaces whatever provider the variable was fetched from
+            Some(variable) => self.update_variable(Variable { value, source: None, ..variable }).await,
+            None => {
+                self.create_variable(environment_id.map(str::to_string), key.to_string(), value, false)
+                    .await
+            }
+        }
+    }
+
     // 🎓 TEACHING: Delete a variable
     pub async fn delete_variable(&self, id: &str) -> Result<()> {
+        if let Some(stored_value) = self.stored_variable_value(id).await? {
+            self.delete_keychain_entry(&stored_value).await;
+        }
+
         sqlx::query("DELETE FROM variables WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
@@ -770,18 +2877,156 @@ impl Database {
         Ok(())
     }
 
+    // 🎓 TEACHING: The variables a request can see, one per key. The closest definition wins:
+    // request > folder > collection > environment > global. Folders are nested collections,
+    // so we walk up from the request's folder to the root collection.
+    pub async fn resolve_variables(&self, collection_id: Option<&str>, request_id: Option<&str>) -> Result<Vec<Variable>> {
+        let environment_id = self.get_active_environment().await?.map(|env| env.id);
+        self.resolve_variables_for(environment_id.as_deref(), collection_id, request_id).await
+    }
+
+    // Same, with a given environment instead of the active one (collection runs pick their own)
+    pub async fn resolve_variables_for(
+        &self,
+        environment_id: Option<&str>,
+        collection_id: Option<&str>,
+        request_id: Option<&str>,
+    ) -> Result<Vec<Variable>> {
+        // Lowest precedence first; later definitions replace earlier ones below
+        let mut layers = self.get_variables(None).await?;
+        if let Some(environment_id) = environment_id {
+            layers.extend(self.get_variables(Some(environment_id)).await?);
+        }
+
+        let mut chain = Vec::new();
+        let mut current = collection_id.map(str::to_string);
+        while let Some(id) = current {
+            if chain.contains(&id) {
+                break;
+            }
+            current = self.get_collection_by_id(&id).await?.and_then(|c| c.parent_id);
+            chain.push(id);
+        }
+        for id in chain.iter().rev() {
+            layers.extend(self.get_collection_variables(id).await?);
+        }
+        if let Some(request_id) = request_id {
+            layers.extend(self.get_request_variables(request_id).await?);
+        }
+
+        let mut variables: Vec<Variable> = Vec::new();
+        for variable in layers {
+            variables.retain(|v| v.key != variable.key);
+            variables.push(variable);
+        }
+        Ok(variables)
+    }
+
     // 🎓 TEACHING: Variable interpolation - replace {{variable}} syntax with actual values
     pub async fn interpolate_string(&self, input: &str) -> Result<String> {
-        let variables = self.get_active_variables().await?;
+        let variables = self.resolve_variables(None, None).await?;
+        self.interpolate_with(&variables, input).await
+    }
+
+    // Interpolate against variables already resolved for a request, so a send looks them up once
+    pub async fn interpolate_with(&self, variables: &[Variable], input: &str) -> Result<String> {
         let mut result = input.to_string();
 
         // Simple regex-like replacement for {{variable}} syntax
         for variable in variables {
             let placeholder = format!("{{{{{}}}}}", variable.key);
-            result = result.replace(&placeholder, &variable.value);
+            if result.contains(&placeholder) {
+                let value = self.resolve_variable_value(variable).await?;
+                result = result.replace(&placeholder, &value);
+            }
+        }
+
+        // Generated values last, so a variable can itself be defined as `{{$faker.email}}`
+        Ok(faker::replace_placeholders(&result))
+    }
+
+    // 🎓 TEACHING: Like interpolate_with, but for JSON bodies: typed variables keep their
+    // type and string values are escaped (see placeholders::interpolate_json)
+    pub async fn interpolate_json_with(&self, variables: &[Variable], input: &str) -> Result<String> {
+        let mut values = HashMap::new();
+        for variable in variables {
+            if input.contains(&format!("{{{{{}}}}}", variable.key)) {
+                let typed = placeholders::TypedValue {
+                    value: self.resolve_variable_value(variable).await?,
+                    value_type: variable.value_type.clone(),
+                };
+                values.insert(variable.key.clone(), typed);
+            }
+        }
+
+        let result = placeholders::interpolate_json(input, &values);
+        Ok(faker::replace_placeholders(&result))
+    }
+
+    // 🎓 TEACHING: The value to substitute for a variable that is actually used.
+    // Provider-backed variables are fetched (or served from the short-lived cache) here,
+    // so unused ones never trigger a network call.
+    async fn resolve_variable_value(&self, variable: &Variable) -> Result<String> {
+        if variable.is_secret && self.is_locked() {
+            return Err(AppError::auth(format!(
+                "Workspace is locked; unlock it to use secret variable {}",
+                variable.key
+            )).into());
+        }
+
+        if let Some(source_json) = &variable.source {
+            if let Some(value) = self.provider_cache.get(source_json) {
+                return Ok(value);
+            }
+            let source: VariableSource = serde_json::from_str(source_json)?;
+            let value = source
+                .fetch()
+                .await
+                .map_err(|e| AppError::from(e).context(format!("Failed to fetch variable {}", variable.key)))?;
+            self.provider_cache.insert(source_json, value.clone(), source.ttl());
+            return Ok(value);
+        }
+
+        if secrets::is_encrypted(&variable.value) {
+            return Err(AppError::auth(format!(
+                "Secret variable {} is locked; unlock secrets to use it",
+                variable.key
+            )).into());
+        }
+        if secrets::parse_keychain_reference(&variable.value).is_some() {
+            return Err(AppError::auth(format!(
+                "Secret variable {} could not be read from the OS keychain",
+                variable.key
+            )).into());
+        }
+        Ok(variable.value.clone())
+    }
+
+    // 🎓 TEACHING: A redactor that knows every secret value we can read right now:
+    // all stored secrets, plus the active ones resolved from the keychain.
+    pub async fn secret_redactor(&self) -> Result<Redactor> {
+        let rows = sqlx::query("SELECT value FROM variables WHERE is_secret = TRUE")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut values = Vec::new();
+        for row in rows {
+            let value = self.reveal(row.get("value"))?;
+            if !secrets::is_encrypted(&value) && secrets::parse_keychain_reference(&value).is_none() {
+                values.push(value);
+            }
         }
+        for variable in self.get_active_variables().await? {
+            if variable.is_secret {
+                values.push(variable.value);
+            }
+        }
+
+        Ok(Redactor::new(values))
+    }
 
-        Ok(result)
+    pub fn clear_variable_provider_cache(&self) {
+        self.provider_cache.clear();
     }
 
     // ============ PHASE 2: RESPONSE CACHING ============
@@ -811,6 +3056,9 @@ impl Database {
         
         let expires_at = cache_duration_seconds.map(|duration| now + chrono::Duration::seconds(duration as i64));
 
+        // The hash uses the real URL; the stored copy is only for display, so mask secrets in it
+        let url = self.secret_redactor().await?.redact(&url);
+
         let cache_entry = ResponseCache {
             id: id.clone(),
             request_hash: request_hash.clone(),
@@ -824,11 +3072,13 @@ impl Database {
         };
 
         // Use REPLACE to handle hash collisions (update existing cache)
+        let mut tx = self.pool.begin().await?;
+        let body_hash = store_blob(&mut tx, &cache_entry.response_body).await?;
         sqlx::query(
             r#"
             REPLACE INTO response_cache 
-            (id, request_hash, method, url, response_status, response_headers, response_body, cache_time, expires_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            (id, request_hash, method, url, response_status, response_headers, response_body, response_body_hash, cache_time, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?, '', ?, ?, ?)
             "#,
         )
         .bind(&cache_entry.id)
@@ -837,11 +3087,12 @@ impl Database {
         .bind(&cache_entry.url)
         .bind(cache_entry.response_status as i64)
         .bind(&cache_entry.response_headers)
-        .bind(&cache_entry.response_body)
+        .bind(&body_hash)
         .bind(cache_entry.cache_time.to_rfc3339())
         .bind(cache_entry.expires_at.as_ref().map(|dt| dt.to_rfc3339()))
-        .execute(&self.pool)
+        .execute(&mut tx)
         .await?;
+        tx.commit().await?;
 
         Ok(cache_entry)
     }
@@ -859,7 +3110,8 @@ impl Database {
 
         let row = sqlx::query(
             r#"
-            SELECT * FROM response_cache 
+            SELECT response_cache.*, blobs.data AS blob_data FROM response_cache
+            LEFT JOIN blobs ON blobs.hash = response_cache.response_body_hash
             WHERE request_hash = ? 
             AND (expires_at IS NULL OR expires_at > ?)
             "#,
@@ -877,7 +3129,7 @@ impl Database {
                 url: row.get("url"),
                 response_status: row.get::<i64, _>("response_status") as u16,
                 response_headers: row.get("response_headers"),
-                response_body: row.get("response_body"),
+                response_body: stored_body(&row, "response_body")?,
                 cache_time: DateTime::parse_from_rfc3339(&row.get::<String, _>("cache_time"))?
                     .with_timezone(&Utc),
                 expires_at: row.get::<Option<String>, _>("expires_at")
@@ -906,6 +3158,22 @@ impl Database {
         let result = sqlx::query("DELETE FROM response_cache")
             .execute(&self.pool)
             .await?;
+        self.delete_orphaned_blobs().await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Bodies no cache entry or proxy recording refers to any more
+    pub async fn delete_orphaned_blobs(&self) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM blobs
+            WHERE NOT EXISTS (SELECT 1 FROM response_cache WHERE response_body_hash = blobs.hash)
+            AND NOT EXISTS (SELECT 1 FROM proxy_captures WHERE response_body_hash = blobs.hash)
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
 
         Ok(result.rows_affected())
     }
@@ -929,9 +3197,55 @@ impl Database {
         Ok((total as u64, expired as u64))
     }
 
+    // Stored headers and bodies, the same measure the cache browser shows per entry
+    pub async fn get_cache_size_bytes(&self) -> Result<u64> {
+        let size: i64 = sqlx::query(
+            r#"
+            SELECT
+                (SELECT COALESCE(SUM(length(CAST(response_headers AS BLOB)) + length(CAST(response_body AS BLOB))), 0) FROM response_cache)
+                + (SELECT COALESCE(SUM(length(data)), 0) FROM blobs WHERE hash IN (SELECT response_body_hash FROM response_cache))
+                AS size_bytes
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .get("size_bytes");
+
+        Ok(size as u64)
+    }
+
+    // 🎓 TEACHING: Keep the cache under `max_bytes` by dropping the oldest entries first.
+    // The running total counts from the newest entry, so whatever pushes it over the limit goes.
+    // A body shared by several entries counts once, against the newest of them.
+    pub async fn trim_cache(&self, max_bytes: u64) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM response_cache WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, SUM(entry_bytes) OVER (ORDER BY cache_time DESC, id) AS running_bytes
+                    FROM (
+                        SELECT response_cache.id, response_cache.cache_time,
+                            length(CAST(response_headers AS BLOB)) + length(CAST(response_body AS BLOB))
+                            + CASE WHEN ROW_NUMBER() OVER (PARTITION BY response_body_hash ORDER BY cache_time DESC, response_cache.id) = 1
+                                THEN COALESCE(length(blobs.data), 0) ELSE 0 END AS entry_bytes
+                        FROM response_cache LEFT JOIN blobs ON blobs.hash = response_cache.response_body_hash
+                    )
+                ) WHERE running_bytes > ?
+            )
+            "#,
+        )
+        .bind(max_bytes.min(i64::MAX as u64) as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
     // 🎓 TEACHING: Get cached response by hash directly
     pub async fn get_cached_response_by_hash(&self, request_hash: &str) -> Result<Option<ResponseCache>> {
-        let row = sqlx::query("SELECT * FROM response_cache WHERE request_hash = ?")
+        let row = sqlx::query(
+            "SELECT response_cache.*, blobs.data AS blob_data FROM response_cache LEFT JOIN blobs ON blobs.hash = response_cache.response_body_hash WHERE request_hash = ?"
+        )
             .bind(request_hash)
             .fetch_optional(&self.pool)
             .await?;
@@ -944,7 +3258,7 @@ impl Database {
                 url: row.get("url"),
                 response_status: row.get::<i64, _>("response_status") as u16,
                 response_headers: row.get("response_headers"),
-                response_body: row.get("response_body"),
+                response_body: stored_body(&row, "response_body")?,
                 cache_time: DateTime::parse_from_rfc3339(&row.get::<String, _>("cache_time"))?
                     .with_timezone(&Utc),
                 expires_at: row.get::<Option<String>, _>("expires_at")
@@ -956,4 +3270,2431 @@ impl Database {
             Ok(None)
         }
     }
+
+    // 🎓 TEACHING: One page of the cache browser, newest first. Pages count from 0.
+    pub async fn list_cache_entries(&self, filter: &CacheEntryFilter, page: u32) -> Result<CacheEntryPage> {
+        let now = Utc::now();
+        let url_pattern = filter
+            .url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(|url| format!("%{}%", escape_like(url)));
+        let method = filter.method.as_deref().map(str::to_uppercase);
+        let expired = filter.expired.map(i64::from);
+        let matches = r#"
+            (? IS NULL OR url LIKE ? ESCAPE '\')
+            AND (? IS NULL OR UPPER(method) = ?)
+            AND (? IS NULL OR (expires_at IS NOT NULL AND expires_at <= ?) = ?)
+        "#;
+
+        let total: i64 = sqlx::query(&format!("SELECT COUNT(*) AS total FROM response_cache WHERE {}", matches))
+            .bind(&url_pattern)
+            .bind(&url_pattern)
+            .bind(&method)
+            .bind(&method)
+            .bind(expired)
+            .bind(now.to_rfc3339())
+            .bind(expired)
+            .fetch_one(&self.pool)
+            .await?
+            .get("total");
+
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT id, method, url, response_status, cache_time, expires_at,
+                   length(CAST(response_headers AS BLOB)) + length(CAST(response_body AS BLOB))
+                   + COALESCE((SELECT length(data) FROM blobs WHERE hash = response_body_hash), 0) AS size_bytes
+            FROM response_cache WHERE {}
+            ORDER BY cache_time DESC LIMIT ? OFFSET ?
+            "#,
+            matches
+        ))
+        .bind(&url_pattern)
+        .bind(&url_pattern)
+        .bind(&method)
+        .bind(&method)
+        .bind(expired)
+        .bind(now.to_rfc3339())
+        .bind(expired)
+        .bind(CACHE_PAGE_SIZE as i64)
+        .bind(page as i64 * CACHE_PAGE_SIZE as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let cache_time = DateTime::parse_from_rfc3339(&row.get::<String, _>("cache_time"))?.with_timezone(&Utc);
+            let expires_at = row
+                .get::<Option<String>, _>("expires_at")
+                .map(|s| DateTime::parse_from_rfc3339(&s))
+                .transpose()?
+                .map(|dt| dt.with_timezone(&Utc));
+            entries.push(CacheEntrySummary {
+                id: row.get("id"),
+                method: row.get("method"),
+                url: row.get("url"),
+                response_status: row.get::<i64, _>("response_status") as u16,
+                size_bytes: row.get::<i64, _>("size_bytes") as u64,
+                cache_time,
+                age_seconds: (now - cache_time).num_seconds().max(0),
+                expires_at,
+                expired: expires_at.is_some_and(|at| at <= now),
+            });
+        }
+
+        Ok(CacheEntryPage {
+            entries,
+            total: total as u64,
+            page,
+            page_size: CACHE_PAGE_SIZE,
+        })
+    }
+
+    pub async fn delete_cache_entry(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM response_cache WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // 🎓 TEACHING: Drop every entry whose URL matches, where `*` stands for any run of characters
+    // (`https://api.example.com/users*`). Without a `*` only that exact URL goes.
+    pub async fn invalidate_cache(&self, pattern: &str) -> Result<u64> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err(AppError::validation("A URL pattern is required").into());
+        }
+        let like = pattern.split('*').map(escape_like).collect::<Vec<_>>().join("%");
+        let result = sqlx::query("DELETE FROM response_cache WHERE url LIKE ? ESCAPE '\\'")
+            .bind(like)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // 🎓 TEACHING: After a write to a resource, its cached reads are out of date - every GET or
+    // HEAD of the same path goes, whatever query string it had
+    pub async fn invalidate_cached_reads(&self, url: &str) -> Result<u64> {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        let result = sqlx::query(
+            "DELETE FROM response_cache WHERE UPPER(method) IN ('GET', 'HEAD') AND (url = ? OR url LIKE ? ESCAPE '\\')"
+        )
+        .bind(path)
+        .bind(format!("{}?%", escape_like(path)))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ============ CACHE POLICIES ============
+
+    pub async fn create_cache_policy(
+        &self,
+        host_pattern: Option<String>,
+        collection_id: Option<String>,
+        ttl_seconds: Option<u64>,
+        bypass: bool,
+    ) -> Result<CachePolicy> {
+        let now = Utc::now();
+        let policy = self
+            .check_cache_policy(CachePolicy {
+                id: Uuid::new_v4().to_string(),
+                host_pattern,
+                collection_id,
+                enabled: true,
+                ttl_seconds,
+                bypass,
+                created_at: now,
+                updated_at: now,
+            })
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO cache_policies (id, host_pattern, collection_id, enabled, ttl_seconds, bypass, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&policy.id)
+        .bind(&policy.host_pattern)
+        .bind(&policy.collection_id)
+        .bind(policy.enabled)
+        .bind(policy.ttl_seconds.map(|ttl| ttl as i64))
+        .bind(policy.bypass)
+        .bind(policy.created_at.to_rfc3339())
+        .bind(policy.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(policy)
+    }
+
+    pub async fn get_cache_policies(&self) -> Result<Vec<CachePolicy>> {
+        let rows = sqlx::query("SELECT * FROM cache_policies ORDER BY created_at")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Self::cache_policy_from_row).collect()
+    }
+
+    pub async fn update_cache_policy(&self, policy: CachePolicy) -> Result<CachePolicy> {
+        let policy = self
+            .check_cache_policy(CachePolicy {
+                updated_at: Utc::now(),
+                ..policy
+            })
+            .await?;
+
+        let result = sqlx::query(
+            "UPDATE cache_policies SET host_pattern = ?, collection_id = ?, enabled = ?, ttl_seconds = ?, bypass = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(&policy.host_pattern)
+        .bind(&policy.collection_id)
+        .bind(policy.enabled)
+        .bind(policy.ttl_seconds.map(|ttl| ttl as i64))
+        .bind(policy.bypass)
+        .bind(policy.updated_at.to_rfc3339())
+        .bind(&policy.id)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Cache policy not found").into());
+        }
+
+        Ok(policy)
+    }
+
+    pub async fn delete_cache_policy(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM cache_policies WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // A rule targets either a host pattern or a collection that exists
+    async fn check_cache_policy(&self, policy: CachePolicy) -> Result<CachePolicy> {
+        let host_pattern = policy
+            .host_pattern
+            .as_deref()
+            .filter(|pattern| !pattern.trim().is_empty())
+            .map(cache_policy::normalize_host_pattern)
+            .transpose()?;
+        match (&host_pattern, &policy.collection_id) {
+            (Some(_), Some(_)) | (None, None) => {
+                return Err(AppError::validation("A cache policy needs either a host pattern or a collection").into())
+            }
+            (None, Some(collection_id)) => {
+                if self.get_collection_by_id(collection_id).await?.is_none() {
+                    return Err(AppError::not_found(format!("Collection not found: {}", collection_id)).into());
+                }
+            }
+            (Some(_), None) => {}
+        }
+        Ok(CachePolicy { host_pattern, ..policy })
+    }
+
+    // 🎓 TEACHING: The rule that applies to a request for `host` saved in `collection_id`, if any
+    pub async fn cache_policy_for(&self, host: &str, collection_id: Option<&str>) -> Result<Option<CachePolicy>> {
+        let policies = self.get_cache_policies().await?;
+        if policies.is_empty() {
+            return Ok(None);
+        }
+
+        // The request's collection first, then each parent up to the root
+        let mut collections: Vec<String> = Vec::new();
+        let mut current = collection_id.map(str::to_string);
+        while let Some(id) = current {
+            if collections.contains(&id) {
+                break; // Cycle guard
+            }
+            current = self.get_collection_by_id(&id).await?.and_then(|c| c.parent_id);
+            collections.push(id);
+        }
+
+        Ok(cache_policy::select(&policies, host, &collections).cloned())
+    }
+
+    fn cache_policy_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<CachePolicy> {
+        Ok(CachePolicy {
+            id: row.get("id"),
+            host_pattern: row.get("host_pattern"),
+            collection_id: row.get("collection_id"),
+            enabled: row.get("enabled"),
+            ttl_seconds: row.get::<Option<i64>, _>("ttl_seconds").map(|ttl| ttl as u64),
+            bypass: row.get("bypass"),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&Utc),
+        })
+    }
+
+    // ============ RATE LIMITS ============
+
+    pub async fn create_rate_limit(
+        &self,
+        host_pattern: String,
+        requests_per_second: Option<f64>,
+        max_concurrent: Option<u32>,
+    ) -> Result<RateLimit> {
+        let now = Utc::now();
+        let limit = Self::check_rate_limit(RateLimit {
+            id: Uuid::new_v4().to_string(),
+            host_pattern,
+            enabled: true,
+            requests_per_second,
+            max_concurrent,
+            created_at: now,
+            updated_at: now,
+        })?;
+
+        sqlx::query(
+            "INSERT INTO rate_limits (id, host_pattern, enabled, requests_per_second, max_concurrent, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&limit.id)
+        .bind(&limit.host_pattern)
+        .bind(limit.enabled)
+        .bind(limit.requests_per_second)
+        .bind(limit.max_concurrent.map(i64::from))
+        .bind(limit.created_at.to_rfc3339())
+        .bind(limit.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(limit)
+    }
+
+    pub async fn get_rate_limits(&self) -> Result<Vec<RateLimit>> {
+        let rows = sqlx::query("SELECT * FROM rate_limits ORDER BY created_at")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Self::rate_limit_from_row).collect()
+    }
+
+    pub async fn update_rate_limit(&self, limit: RateLimit) -> Result<RateLimit> {
+        let limit = Self::check_rate_limit(RateLimit {
+            updated_at: Utc::now(),
+            ..limit
+        })?;
+
+        let result = sqlx::query(
+            "UPDATE rate_limits SET host_pattern = ?, enabled = ?, requests_per_second = ?, max_concurrent = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(&limit.host_pattern)
+        .bind(limit.enabled)
+        .bind(limit.requests_per_second)
+        .bind(limit.max_concurrent.map(i64::from))
+        .bind(limit.updated_at.to_rfc3339())
+        .bind(&limit.id)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Rate limit not found").into());
+        }
+
+        Ok(limit)
+    }
+
+    pub async fn delete_rate_limit(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM rate_limits WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    fn check_rate_limit(limit: RateLimit) -> Result<RateLimit> {
+        let host_pattern = cache_policy::normalize_host_pattern(&limit.host_pattern)
+            .map_err(|e| AppError::validation(e.to_string()))?;
+        rate_limit::validate(&limit).map_err(|e| AppError::validation(e.to_string()))?;
+        Ok(RateLimit { host_pattern, ..limit })
+    }
+
+    // 🎓 TEACHING: Waits until the rate limit for `host` lets a send through. The permit must
+    // be kept until the response has been read, since it holds the concurrency slot.
+    pub async fn throttle(&self, host: &str) -> Result<Option<SendPermit>> {
+        let limits = self.get_rate_limits().await?;
+        let Some(limit) = rate_limit::select(&limits, host) else {
+            return Ok(None);
+        };
+        let permit = self.rate_limiter.acquire(limit).await;
+        if !permit.waited.is_zero() {
+            logging::debug(format!(
+                "Waited {} ms for the {} rate limit",
+                permit.waited.as_millis(),
+                limit.host_pattern
+            ));
+        }
+        Ok(Some(permit))
+    }
+
+    fn rate_limit_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<RateLimit> {
+        Ok(RateLimit {
+            id: row.get("id"),
+            host_pattern: row.get("host_pattern"),
+            enabled: row.get("enabled"),
+            requests_per_second: row.get("requests_per_second"),
+            max_concurrent: row.get::<Option<i64>, _>("max_concurrent").map(|max| max as u32),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&Utc),
+        })
+    }
+
+    // ============ COOKIE JAR ============
+
+    // 🎓 TEACHING: A cookie replaces the one with the same domain, path and name; an expired one deletes it
+    pub async fn store_cookies(&self, cookies: &[Cookie]) -> Result<()> {
+        let now = Utc::now();
+        for cookie in cookies {
+            if cookie.is_expired(now) {
+                self.delete_cookie(&cookie.domain, &cookie.path, &cookie.name).await?;
+                continue;
+            }
+            sqlx::query(
+                "INSERT OR REPLACE INTO cookies (domain, path, name, value, expires_at, secure, http_only, same_site, host_only, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&cookie.domain)
+            .bind(&cookie.path)
+            .bind(&cookie.name)
+            .bind(&cookie.value)
+            .bind(cookie.expires.map(|at| at.to_rfc3339()))
+            .bind(cookie.secure)
+            .bind(cookie.http_only)
+            .bind(&cookie.same_site)
+            .bind(cookie.host_only)
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    // Unexpired cookies, for one domain and its subdomains or all of them
+    pub async fn get_cookies(&self, domain: Option<&str>) -> Result<Vec<Cookie>> {
+        sqlx::query("DELETE FROM cookies WHERE expires_at IS NOT NULL AND expires_at <= ?")
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        let rows = sqlx::query("SELECT * FROM cookies ORDER BY domain, path, name")
+            .fetch_all(&self.pool)
+            .await?;
+        let cookies = rows.iter().map(Self::cookie_from_row).collect::<Result<Vec<_>>>()?;
+        let domain = domain.map(|domain| domain.trim().trim_start_matches('.').to_ascii_lowercase());
+        Ok(cookies
+            .into_iter()
+            .filter(|cookie| {
+                domain.as_deref().is_none_or(|domain| {
+                    cookie.domain == domain || cookie.domain.ends_with(&format!(".{}", domain))
+                })
+            })
+            .collect())
+    }
+
+    // What a request to `url` sends, most specific path first
+    pub async fn cookies_for_url(&self, url: &str) -> Result<Vec<Cookie>> {
+        let url = url::Url::parse(url)?;
+        let now = Utc::now();
+        let mut cookies: Vec<Cookie> =
+            self.get_cookies(None).await?.into_iter().filter(|cookie| cookie.matches(&url, now)).collect();
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+        Ok(cookies)
+    }
+
+    pub async fn delete_cookie(&self, domain: &str, path: &str, name: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM cookies WHERE domain = ? AND path = ? AND name = ?")
+            .bind(domain)
+            .bind(path)
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Every cookie for `domain` and its subdomains, or the whole jar
+    pub async fn clear_cookies(&self, domain: Option<&str>) -> Result<u64> {
+        let result = match domain.map(|domain| domain.trim().trim_start_matches('.').to_ascii_lowercase()) {
+            Some(domain) => {
+                sqlx::query("DELETE FROM cookies WHERE domain = ? OR domain LIKE ? ESCAPE '\\'")
+                    .bind(&domain)
+                    .bind(format!("%.{}", escape_like(&domain)))
+                    .execute(&self.pool)
+                    .await?
+            }
+            None => sqlx::query("DELETE FROM cookies").execute(&self.pool).await?,
+        };
+        Ok(result.rows_affected())
+    }
+
+    fn cookie_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Cookie> {
+        let expires_at: Option<String> = row.get("expires_at");
+        Ok(Cookie {
+            name: row.get("name"),
+            value: row.get("value"),
+            domain: row.get("domain"),
+            path: row.get("path"),
+            expires: expires_at
+                .map(|at| DateTime::parse_from_rfc3339(&at).map(|at| at.with_timezone(&Utc)))
+                .transpose()?,
+            secure: row.get("secure"),
+            http_only: row.get("http_only"),
+            same_site: row.get("same_site"),
+            host_only: row.get("host_only"),
+        })
+    }
+
+    // ============ AUTH PLUGINS ============
+
+    // 🎓 TEACHING: Install a plugin, replacing any existing plugin with the same name
+    pub async fn install_auth_plugin(&self, name: String, wasm: Vec<u8>) -> Result<AuthPlugin> {
+        let now = Utc::now();
+        let existing = sqlx::query("SELECT id, created_at FROM auth_plugins WHERE name = ?")
+            .bind(&name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let plugin = match existing {
+            Some(row) => AuthPlugin {
+                id: row.get("id"),
+                name,
+                size_bytes: wasm.len() as u64,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
+                    .with_timezone(&Utc),
+                updated_at: now,
+            },
+            None => AuthPlugin {
+                id: Uuid::new_v4().to_string(),
+                name,
+                size_bytes: wasm.len() as u64,
+                created_at: now,
+                updated_at: now,
+            },
+        };
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO auth_plugins (id, name, wasm, created_at, updated_at) VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(&plugin.id)
+        .bind(&plugin.name)
+        .bind(wasm)
+        .bind(plugin.created_at.to_rfc3339())
+        .bind(plugin.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(plugin)
+    }
+
+    pub async fn list_auth_plugins(&self) -> Result<Vec<AuthPlugin>> {
+        let rows = sqlx::query(
+            "SELECT id, name, length(wasm) as size_bytes, created_at, updated_at FROM auth_plugins ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut plugins = Vec::new();
+        for row in rows {
+            plugins.push(AuthPlugin {
+                id: row.get("id"),
+                name: row.get("name"),
+                size_bytes: row.get::<i64, _>("size_bytes") as u64,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
+                    .with_timezone(&Utc),
+                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
+                    .with_timezone(&Utc),
+            });
+        }
+
+        Ok(plugins)
+    }
+
+    pub async fn get_auth_plugin_wasm(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let row = sqlx::query("SELECT wasm FROM auth_plugins WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("wasm")))
+    }
+
+    pub async fn delete_auth_plugin(&self, name: &str) -> Result<()> {
+        sqlx::query("DELETE FROM auth_plugins WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // ============ SCRIPT MODULES ============
+
+    pub async fn create_script_module(&self, name: String, language: String, code: String) -> Result<ScriptModule> {
+        validate_module_name(&name)?;
+        let now = Utc::now();
+        let module = ScriptModule {
+            id: Uuid::new_v4().to_string(),
+            name,
+            language,
+            code,
+            created_at: now,
+            updated_at: now,
+        };
+
+        sqlx::query(
+            "INSERT INTO script_modules (id, name, language, code, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&module.id)
+        .bind(&module.name)
+        .bind(&module.language)
+        .bind(&module.code)
+        .bind(module.created_at.to_rfc3339())
+        .bind(module.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(module)
+    }
+
+    pub async fn get_script_modules(&self) -> Result<Vec<ScriptModule>> {
+        let rows = sqlx::query("SELECT * FROM script_modules ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut modules = Vec::new();
+        for row in rows {
+            modules.push(ScriptModule {
+                id: row.get("id"),
+                name: row.get("name"),
+                language: row.get("language"),
+                code: row.get("code"),
+                created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?
+                    .with_timezone(&Utc),
+                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?
+                    .with_timezone(&Utc),
+            });
+        }
+
+        Ok(modules)
+    }
+
+    pub async fn update_script_module(&self, module: ScriptModule) -> Result<ScriptModule> {
+        validate_module_name(&module.name)?;
+        let updated_module = ScriptModule {
+            updated_at: Utc::now(),
+            ..module
+        };
+
+        let result = sqlx::query(
+            "UPDATE script_modules SET name = ?, language = ?, code = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(&updated_module.name)
+        .bind(&updated_module.language)
+        .bind(&updated_module.code)
+        .bind(updated_module.updated_at.to_rfc3339())
+        .bind(&updated_module.id)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Script module not found").into());
+        }
+
+        Ok(updated_module)
+    }
+
+    pub async fn delete_script_module(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM script_modules WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // ============ SECRET ENCRYPTION ============
+
+    // 🎓 TEACHING: Encrypt a value on its way into SQLite (when encryption is enabled).
+    // Already-encrypted values pass through, so a value read while locked can be saved back safely.
+    fn seal(&self, value: &str) -> Result<String> {
+        let state = self.secrets.read().unwrap();
+        if !state.enabled || secrets::is_encrypted(value) {
+            return Ok(value.to_string());
+        }
+        match &state.cipher {
+            Some(cipher) => cipher.encrypt(value),
+            None => Err(AppError::auth("Secrets are locked; unlock them before saving secret values").into()),
+        }
+    }
+
+    fn seal_opt(&self, value: Option<&str>) -> Result<Option<String>> {
+        value.map(|v| self.seal(v)).transpose()
+    }
+
+    fn seal_variable_value(&self, value: &str, is_secret: bool) -> Result<String> {
+        if is_secret {
+            self.seal(value)
+        } else {
+            Ok(value.to_string())
+        }
+    }
+
+    // 🎓 TEACHING: Decrypt a value read from SQLite.
+    // While locked the ciphertext is returned as-is; interpolation and sending refuse to use it.
+    fn reveal(&self, value: String) -> Result<String> {
+        if !secrets::is_encrypted(&value) {
+            return Ok(value);
+        }
+        match &self.secrets.read().unwrap().cipher {
+            Some(cipher) => cipher.decrypt(&value),
+            None => Ok(value),
+        }
+    }
+
+    fn reveal_opt(&self, value: Option<String>) -> Result<Option<String>> {
+        value.map(|v| self.reveal(v)).transpose()
+    }
+
+    // Salt, verifier and (for a key kept in the OS keychain) the keychain entry
+    async fn secret_encryption_row(&self) -> Result<Option<(String, String, Option<String>)>> {
+        let row = sqlx::query("SELECT salt, verifier, keychain_key FROM secret_encryption WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| (row.get("salt"), row.get("verifier"), row.get("keychain_key"))))
+    }
+
+    // 🎓 TEACHING: "Locked" means a master password exists but its key isn't loaded
+    pub fn is_locked(&self) -> bool {
+        let state = self.secrets.read().unwrap();
+        state.enabled && state.cipher.is_none()
+    }
+
+    // 🎓 TEACHING: Lock the workspace by forgetting the key (and any fetched secrets).
+    // Encrypted values stay in the database; the master password or the OS keychain brings them back.
+    pub fn lock_workspace(&self) -> Result<()> {
+        let mut state = self.secrets.write().unwrap();
+        if !state.enabled {
+            return Err(AppError::validation(
+                "Set a master password (enable secret encryption) before locking the workspace",
+            )
+            .into());
+        }
+        state.cipher = None;
+        drop(state);
+
+        self.provider_cache.clear();
+        Ok(())
+    }
+
+    pub fn secret_encryption_status(&self) -> SecretEncryptionStatus {
+        let state = self.secrets.read().unwrap();
+        SecretEncryptionStatus {
+            enabled: state.enabled,
+            unlocked: state.cipher.is_some(),
+            in_keychain: state.in_keychain,
+        }
+    }
+
+    // 🎓 TEACHING: Turn on encryption and encrypt every existing secret in place
+    pub async fn enable_secret_encryption(&self, passphrase: &str) -> Result<()> {
+        if self.secret_encryption_row().await?.is_some() {
+            return Err(AppError::validation("Secret encryption is already enabled").into());
+        }
+        if passphrase.is_empty() {
+            return Err(AppError::validation("Passphrase cannot be empty").into());
+        }
+
+        let salt = secrets::generate_salt();
+        let cipher = SecretCipher::from_passphrase(passphrase, &salt)?;
+        self.encrypt_existing_secrets(cipher, &salt, None).await
+    }
+
+    // 🎓 TEACHING: The same, with a random key kept in the OS keychain instead of a passphrase,
+    // so secrets are encrypted at rest without asking for anything. The database only records
+    // which keychain entry holds the key.
+    pub async fn enable_keychain_secret_encryption(&self) -> Result<()> {
+        if self.secret_encryption_row().await?.is_some() {
+            return Err(AppError::validation("Secret encryption is already enabled").into());
+        }
+
+        let keychain_key = format!("secrets-key-{}", Uuid::new_v4());
+        let key = secrets::generate_key();
+        let cipher = SecretCipher::from_encoded_key(&key)?;
+        let entry = keychain_key.clone();
+        self.keychain_call(move |keychain| keychain.set(&entry, &key)).await?;
+
+        if let Err(e) = self.encrypt_existing_secrets(cipher, "", Some(&keychain_key)).await {
+            self.delete_keychain_entry(&secrets::keychain_reference(&keychain_key)).await;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // Record the key's verifier and encrypt what's stored in plain text, all in one transaction
+    async fn encrypt_existing_secrets(
+        &self,
+        cipher: SecretCipher,
+        salt: &str,
+        keychain_key: Option<&str>, // Set when the key is kept in the OS keychain
+    ) -> Result<()> {
+        let verifier = cipher.encrypt(secrets::VERIFIER_PLAINTEXT)?;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO secret_encryption (id, salt, verifier, keychain_key, created_at) VALUES (1, ?, ?, ?, ?)",
+        )
+        .bind(salt)
+        .bind(&verifier)
+        .bind(keychain_key)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut tx)
+        .await?;
+
+        let variables = sqlx::query("SELECT id, value FROM variables WHERE is_secret = TRUE")
+            .fetch_all(&mut tx)
+            .await?;
+        for row in variables {
+            let value: String = row.get("value");
+            if !secrets::is_encrypted(&value) {
+                sqlx::query("UPDATE variables SET value = ? WHERE id = ?")
+                    .bind(cipher.encrypt(&value)?)
+                    .bind(row.get::<String, _>("id"))
+                    .execute(&mut tx)
+                    .await?;
+            }
+        }
+
+        for table in ["requests", "collections"] {
+            let rows = sqlx::query(&format!("SELECT id, auth_data FROM {} WHERE auth_data IS NOT NULL", table))
+                .fetch_all(&mut tx)
+                .await?;
+            for row in rows {
+                let auth_data: String = row.get("auth_data");
+                if !secrets::is_encrypted(&auth_data) {
+                    sqlx::query(&format!("UPDATE {} SET auth_data = ? WHERE id = ?", table))
+                        .bind(cipher.encrypt(&auth_data)?)
+                        .bind(row.get::<String, _>("id"))
+                        .execute(&mut tx)
+                        .await?;
+                }
+            }
+        }
+        tx.commit().await?;
+
+        let mut state = self.secrets.write().unwrap();
+        state.enabled = true;
+        state.in_keychain = keychain_key.is_some();
+        state.cipher = Some(cipher);
+        Ok(())
+    }
+
+    // 🎓 TEACHING: Derive the key from the passphrase and check it against the stored verifier
+    pub async fn unlock_secrets(&self, passphrase: &str) -> Result<()> {
+        let (salt, verifier, keychain_key) = self
+            .secret_encryption_row()
+            .await?
+            .ok_or_else(|| AppError::validation("Secret encryption is not enabled"))?;
+        if keychain_key.is_some() {
+            return Err(
+                AppError::validation("The secrets key is kept in the OS keychain; unlock without a passphrase").into(),
+            );
+        }
+
+        let cipher = SecretCipher::from_passphrase(passphrase, &salt)?;
+        if !cipher.matches_verifier(&verifier) {
+            return Err(AppError::auth("Incorrect passphrase").into());
+        }
+
+        self.secrets.write().unwrap().cipher = Some(cipher);
+        Ok(())
+    }
+
+    // 🎓 TEACHING: Read the key back from the OS keychain (the OS may ask to allow it)
+    pub async fn unlock_secrets_from_keychain(&self) -> Result<()> {
+        let (_, verifier, keychain_key) = self
+            .secret_encryption_row()
+            .await?
+            .ok_or_else(|| AppError::validation("Secret encryption is not enabled"))?;
+        let keychain_key = keychain_key
+            .ok_or_else(|| AppError::validation("Secrets are encrypted with a passphrase; enter it to unlock"))?;
+
+        let key = self
+            .keychain_call(move |keychain| keychain.get(&keychain_key))
+            .await?
+            .ok_or_else(|| AppError::auth("The secrets key is missing from the OS keychain"))?;
+        let cipher = SecretCipher::from_encoded_key(&key)?;
+        if !cipher.matches_verifier(&verifier) {
+            return Err(AppError::auth("The secrets key in the OS keychain does not belong to this workspace").into());
+        }
+
+        self.secrets.write().unwrap().cipher = Some(cipher);
+        Ok(())
+    }
+
+    // ============ OS KEYCHAIN ============
+
+    // 🎓 TEACHING: Keychain calls can block on an OS prompt, so they run on a blocking thread
+    async fn keychain_call<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn SecretBackend) -> Result<T> + Send + 'static,
+    {
+        let keychain = self.keychain.clone();
+        tokio::task::spawn_blocking(move || f(keychain.as_ref())).await?
+    }
+
+    // The raw `value` column, without decryption or keychain lookups
+    async fn stored_variable_value(&self, id: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT value FROM variables WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("value")))
+    }
+
+    // Best effort: a leftover keychain entry shouldn't block deleting the variable
+    async fn delete_keychain_entry(&self, stored_value: &str) {
+        if let Some(key) = secrets::parse_keychain_reference(stored_value).map(str::to_string) {
+            if let Err(e) = self.keychain_call(move |keychain| keychain.delete(&key)).await {
+                logging::warn(format!("Failed to remove keychain entry: {}", e));
+            }
+        }
+    }
+
+    // 🎓 TEACHING: Store a variable's value in the OS keychain and mark it secret.
+    // The database row keeps only a reference, keyed by the variable id.
+    pub async fn store_secret(&self, variable_id: &str, value: String) -> Result<()> {
+        if self.is_locked() {
+            return Err(AppError::auth("Workspace is locked").into());
+        }
+        if self.stored_variable_value(variable_id).await?.is_none() {
+            return Err(AppError::not_found(format!("Variable {} not found", variable_id)).into());
+        }
+
+        let key = variable_id.to_string();
+        self.keychain_call(move |keychain| keychain.set(&key, &value)).await?;
+
+        sqlx::query("UPDATE variables SET value = ?, is_secret = TRUE, updated_at = ? WHERE id = ?")
+            .bind(secrets::keychain_reference(variable_id))
+            .bind(Utc::now().to_rfc3339())
+            .bind(variable_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // 🎓 TEACHING: Read a secret variable's value, wherever it is stored
+    pub async fn get_secret(&self, variable_id: &str) -> Result<Option<String>> {
+        if self.is_locked() {
+            return Err(AppError::auth("Workspace is locked").into());
+        }
+        let Some(stored_value) = self.stored_variable_value(variable_id).await? else {
+            return Ok(None);
+        };
+
+        if let Some(key) = secrets::parse_keychain_reference(&stored_value).map(str::to_string) {
+            return self.keychain_call(move |keychain| keychain.get(&key)).await;
+        }
+
+        let value = self.reveal(stored_value)?;
+        if secrets::is_encrypted(&value) {
+            return Err(AppError::auth("Secrets are locked; unlock them to read this value").into());
+        }
+        Ok(Some(value))
+    }
+
+    // 🎓 TEACHING: Move every secret variable still stored in SQLite into the keychain
+    pub async fn migrate_secrets_to_keychain(&self) -> Result<u64> {
+        let rows = sqlx::query("SELECT id, value FROM variables WHERE is_secret = TRUE")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut migrated = 0;
+        for row in rows {
+            let id: String = row.get("id");
+            let stored_value: String = row.get("value");
+            if secrets::parse_keychain_reference(&stored_value).is_some() {
+                continue;
+            }
+
+            let value = self.reveal(stored_value)?;
+            if secrets::is_encrypted(&value) {
+                return Err(AppError::auth("Secrets are locked; unlock them before migrating").into());
+            }
+            self.store_secret(&id, value).await?;
+            migrated += 1;
+        }
+
+        Ok(migrated)
+    }
+
+    // ============ WEBHOOK CAPTURES ============
+
+    pub async fn insert_webhook_capture(&self, capture: &WebhookCapture) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO webhook_captures (id, port, method, path, query, headers, body, remote_addr, received_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&capture.id)
+        .bind(capture.port as i64)
+        .bind(&capture.method)
+        .bind(&capture.path)
+        .bind(&capture.query)
+        .bind(&capture.headers)
+        .bind(&capture.body)
+        .bind(&capture.remote_addr)
+        .bind(capture.received_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Newest first, optionally only for one listener port
+    pub async fn get_webhook_captures(&self, port: Option<u16>, limit: Option<u32>) -> Result<Vec<WebhookCapture>> {
+        let rows = sqlx::query(
+            "SELECT * FROM webhook_captures WHERE (? IS NULL OR port = ?) ORDER BY received_at DESC LIMIT ?"
+        )
+        .bind(port.map(|p| p as i64))
+        .bind(port.map(|p| p as i64))
+        .bind(limit.unwrap_or(500) as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut captures = Vec::new();
+        for row in rows {
+            captures.push(WebhookCapture {
+                id: row.get("id"),
+                port: row.get::<i64, _>("port") as u16,
+                method: row.get("method"),
+                path: row.get("path"),
+                query: row.get("query"),
+                headers: row.get("headers"),
+                body: row.get("body"),
+                remote_addr: row.get("remote_addr"),
+                received_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("received_at"))?
+                    .with_timezone(&Utc),
+            });
+        }
+
+        Ok(captures)
+    }
+
+    pub async fn clear_webhook_captures(&self, port: Option<u16>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM webhook_captures WHERE (? IS NULL OR port = ?)")
+            .bind(port.map(|p| p as i64))
+            .bind(port.map(|p| p as i64))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ============ PROXY CAPTURES ============
+
+    // The capture recorded for this exact call, if any (query must match too, including "none")
+    pub async fn find_proxy_capture(
+        &self,
+        collection_id: &str,
+        method: &str,
+        path: &str,
+        query: Option<&str>,
+    ) -> Result<Option<ProxyCapture>> {
+        let row = sqlx::query(
+            "SELECT proxy_captures.*, blobs.data AS blob_data FROM proxy_captures LEFT JOIN blobs ON blobs.hash = proxy_captures.response_body_hash WHERE collection_id = ? AND method = ? AND path = ? AND query IS ?"
+        )
+        .bind(collection_id)
+        .bind(method)
+        .bind(path)
+        .bind(query)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| Self::proxy_capture_from_row(&row)).transpose()
+    }
+
+    // Insert a new capture, or overwrite the one with the same id
+    pub async fn save_proxy_capture(&self, capture: &ProxyCapture) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let body_hash = store_blob(&mut tx, &capture.response_body_base64).await?;
+        sqlx::query(
+            "INSERT OR REPLACE INTO proxy_captures (id, collection_id, request_id, method, path, query, request_headers, request_body, status, response_headers, response_body_base64, response_body_hash, duration_ms, captured_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, '', ?, ?, ?)"
+        )
+        .bind(&capture.id)
+        .bind(&capture.collection_id)
+        .bind(&capture.request_id)
+        .bind(&capture.method)
+        .bind(&capture.path)
+        .bind(&capture.query)
+        .bind(&capture.request_headers)
+        .bind(&capture.request_body)
+        .bind(capture.status as i64)
+        .bind(&capture.response_headers)
+        .bind(&body_hash)
+        .bind(capture.duration_ms as i64)
+        .bind(capture.captured_at.to_rfc3339())
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn get_proxy_captures(&self, collection_id: &str) -> Result<Vec<ProxyCapture>> {
+        let rows = sqlx::query(
+            "SELECT proxy_captures.*, blobs.data AS blob_data FROM proxy_captures LEFT JOIN blobs ON blobs.hash = proxy_captures.response_body_hash WHERE collection_id = ? ORDER BY captured_at DESC"
+        )
+            .bind(collection_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Self::proxy_capture_from_row).collect()
+    }
+
+    // Forget recorded responses; the saved requests they created stay in the collection
+    pub async fn clear_proxy_captures(&self, collection_id: &str) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM proxy_captures WHERE collection_id = ?")
+            .bind(collection_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    fn proxy_capture_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<ProxyCapture> {
+        Ok(ProxyCapture {
+            id: row.get("id"),
+            collection_id: row.get("collection_id"),
+            request_id: row.get("request_id"),
+            method: row.get("method"),
+            path: row.get("path"),
+            query: row.get("query"),
+            request_headers: row.get("request_headers"),
+            request_body: row.get("request_body"),
+            status: row.get::<i64, _>("status") as u16,
+            response_headers: row.get("response_headers"),
+            response_body_base64: stored_body(row, "response_body_base64")?,
+            duration_ms: row.get::<i64, _>("duration_ms") as u64,
+            captured_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("captured_at"))?.with_timezone(&Utc),
+        })
+    }
+
+    // ============ COLLECTION RUNS ============
+
+    pub async fn save_collection_run(&self, run: &CollectionRun) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO collection_runs (id, collection_id, environment_id, iterations, total_requests, passed_requests, failed_requests, stopped_early, results, started_at, finished_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&run.id)
+        .bind(&run.collection_id)
+        .bind(&run.environment_id)
+        .bind(run.iterations as i64)
+        .bind(run.total_requests as i64)
+        .bind(run.passed_requests as i64)
+        .bind(run.failed_requests as i64)
+        .bind(run.stopped_early)
+        .bind(&run.results)
+        .bind(run.started_at.to_rfc3339())
+        .bind(run.finished_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Newest first
+    pub async fn get_collection_runs(&self, collection_id: &str) -> Result<Vec<CollectionRun>> {
+        let rows = sqlx::query("SELECT * FROM collection_runs WHERE collection_id = ? ORDER BY started_at DESC")
+            .bind(collection_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Self::collection_run_from_row).collect()
+    }
+
+    fn collection_run_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<CollectionRun> {
+        Ok(CollectionRun {
+            id: row.get("id"),
+            collection_id: row.get("collection_id"),
+            environment_id: row.get("environment_id"),
+            iterations: row.get::<i64, _>("iterations") as u32,
+            total_requests: row.get::<i64, _>("total_requests") as u32,
+            passed_requests: row.get::<i64, _>("passed_requests") as u32,
+            failed_requests: row.get::<i64, _>("failed_requests") as u32,
+            stopped_early: row.get("stopped_early"),
+            results: row.get("results"),
+            started_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("started_at"))?.with_timezone(&Utc),
+            finished_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("finished_at"))?.with_timezone(&Utc),
+        })
+    }
+
+    pub async fn get_collection_run(&self, id: &str) -> Result<Option<CollectionRun>> {
+        let row = sqlx::query("SELECT * FROM collection_runs WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::collection_run_from_row).transpose()
+    }
+
+    pub async fn delete_collection_run(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM collection_runs WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // ============ MONITORS ============
+
+    pub async fn create_monitor(
+        &self,
+        name: String,
+        target_type: String,
+        target_id: String,
+        cron: String,
+        environment_id: Option<String>,
+    ) -> Result<Monitor> {
+        let now = Utc::now();
+        let monitor = Monitor {
+            id: Uuid::new_v4().to_string(),
+            name,
+            target_type,
+            target_id,
+            cron,
+            environment_id,
+            enabled: true,
+            notify: true,
+            webhook_url: None,
+            retry: None,
+            last_run_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        sqlx::query(
+            "INSERT INTO monitors (id, name, target_type, target_id, cron, environment_id, enabled, notify, webhook_url, retry, last_run_at, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&monitor.id)
+        .bind(&monitor.name)
+        .bind(&monitor.target_type)
+        .bind(&monitor.target_id)
+        .bind(&monitor.cron)
+        .bind(&monitor.environment_id)
+        .bind(monitor.enabled)
+        .bind(monitor.notify)
+        .bind(&monitor.webhook_url)
+        .bind(&monitor.retry)
+        .bind(monitor.last_run_at.map(|at| at.to_rfc3339()))
+        .bind(monitor.created_at.to_rfc3339())
+        .bind(monitor.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(monitor)
+    }
+
+    pub async fn get_monitors(&self) -> Result<Vec<Monitor>> {
+        let rows = sqlx::query("SELECT * FROM monitors ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Self::monitor_from_row).collect()
+    }
+
+    pub async fn get_monitor(&self, id: &str) -> Result<Option<Monitor>> {
+        let row = sqlx::query("SELECT * FROM monitors WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::monitor_from_row).transpose()
+    }
+
+    fn monitor_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Monitor> {
+        let last_run_at: Option<String> = row.get("last_run_at");
+        Ok(Monitor {
+            id: row.get("id"),
+            name: row.get("name"),
+            target_type: row.get("target_type"),
+            target_id: row.get("target_id"),
+            cron: row.get("cron"),
+            environment_id: row.get("environment_id"),
+            enabled: row.get("enabled"),
+            notify: row.get("notify"),
+            webhook_url: row.get("webhook_url"),
+            retry: row.get("retry"),
+            last_run_at: last_run_at
+                .map(|at| DateTime::parse_from_rfc3339(&at).map(|at| at.with_timezone(&Utc)))
+                .transpose()?,
+            created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&Utc),
+        })
+    }
+
+    // The scheduler owns last_run_at, so an edit never moves the schedule
+    pub async fn update_monitor(&self, monitor: Monitor) -> Result<Monitor> {
+        let updated_at = Utc::now();
+        let result = sqlx::query(
+            "UPDATE monitors SET name = ?, target_type = ?, target_id = ?, cron = ?, environment_id = ?, enabled = ?, notify = ?, webhook_url = ?, retry = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(&monitor.name)
+        .bind(&monitor.target_type)
+        .bind(&monitor.target_id)
+        .bind(&monitor.cron)
+        .bind(&monitor.environment_id)
+        .bind(monitor.enabled)
+        .bind(monitor.notify)
+        .bind(&monitor.webhook_url)
+        .bind(&monitor.retry)
+        .bind(updated_at.to_rfc3339())
+        .bind(&monitor.id)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Monitor not found").into());
+        }
+
+        self.get_monitor(&monitor.id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Monitor not found").into())
+    }
+
+    pub async fn set_monitor_last_run(&self, id: &str, at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE monitors SET last_run_at = ? WHERE id = ?")
+            .bind(at.to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_monitor(&self, id: &str) -> Result<()> {
+        self.delete_monitors_where("id = ?", id).await
+    }
+
+    // Remove monitors, with their result history, along with what they check
+    async fn delete_monitors_where(&self, condition: &str, id: &str) -> Result<()> {
+        sqlx::query(&format!(
+            "DELETE FROM monitor_results WHERE monitor_id IN (SELECT id FROM monitors WHERE {})",
+            condition
+        ))
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(&format!("DELETE FROM monitors WHERE {}", condition))
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Keeps the newest MAX_MONITOR_RESULTS per monitor, so a monitor running every minute
+    // doesn't grow the database forever
+    pub async fn save_monitor_result(&self, result: &MonitorResult) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO monitor_results (id, monitor_id, passed, total_requests, failed_requests, error, results, started_at, finished_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&result.id)
+        .bind(&result.monitor_id)
+        .bind(result.passed)
+        .bind(result.total_requests as i64)
+        .bind(result.failed_requests as i64)
+        .bind(&result.error)
+        .bind(&result.results)
+        .bind(result.started_at.to_rfc3339())
+        .bind(result.finished_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM monitor_results WHERE monitor_id = ? AND id NOT IN (SELECT id FROM monitor_results WHERE monitor_id = ? ORDER BY started_at DESC LIMIT ?)"
+        )
+        .bind(&result.monitor_id)
+        .bind(&result.monitor_id)
+        .bind(MAX_MONITOR_RESULTS)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Newest first
+    pub async fn get_monitor_results(&self, monitor_id: &str) -> Result<Vec<MonitorResult>> {
+        let rows = sqlx::query("SELECT * FROM monitor_results WHERE monitor_id = ? ORDER BY started_at DESC")
+            .bind(monitor_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(MonitorResult {
+                id: row.get("id"),
+                monitor_id: row.get("monitor_id"),
+                passed: row.get("passed"),
+                total_requests: row.get::<i64, _>("total_requests") as u32,
+                failed_requests: row.get::<i64, _>("failed_requests") as u32,
+                error: row.get("error"),
+                results: row.get("results"),
+                started_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("started_at"))?.with_timezone(&Utc),
+                finished_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("finished_at"))?.with_timezone(&Utc),
+            });
+        }
+
+        Ok(results)
+    }
+
+    // ============ REQUEST ANALYTICS ============
+
+    // Keeps the newest MAX_REQUEST_SAMPLES per request
+    pub async fn save_request_sample(&self, sample: &RequestSample) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO request_samples (id, request_id, status, duration_ms, response_size, sent_at, trace_id, correlation_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&sample.id)
+        .bind(&sample.request_id)
+        .bind(sample.status.map(|status| status as i64))
+        .bind(sample.duration_ms as i64)
+        .bind(sample.response_size as i64)
+        .bind(sample.sent_at.to_rfc3339())
+        .bind(&sample.trace_id)
+        .bind(&sample.correlation_id)
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM request_samples WHERE request_id = ? AND id NOT IN (SELECT id FROM request_samples WHERE request_id = ? ORDER BY sent_at DESC LIMIT ?)"
+        )
+        .bind(&sample.request_id)
+        .bind(&sample.request_id)
+        .bind(MAX_REQUEST_SAMPLES)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Oldest first; all of them without `since`
+    pub async fn get_request_samples(&self, request_id: &str, since: Option<DateTime<Utc>>) -> Result<Vec<RequestSample>> {
+        // RFC3339 strings in UTC sort like the times they hold
+        let since = since.map(|at| at.to_rfc3339()).unwrap_or_default();
+        let rows = sqlx::query("SELECT * FROM request_samples WHERE request_id = ? AND sent_at >= ? ORDER BY sent_at")
+            .bind(request_id)
+            .bind(since)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut samples = Vec::new();
+        for row in rows {
+            samples.push(RequestSample {
+                id: row.get("id"),
+                request_id: row.get("request_id"),
+                status: row.get::<Option<i64>, _>("status").map(|status| status as u16),
+                duration_ms: row.get::<i64, _>("duration_ms") as u64,
+                response_size: row.get::<i64, _>("response_size") as u64,
+                sent_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("sent_at"))?.with_timezone(&Utc),
+                trace_id: row.get("trace_id"),
+                correlation_id: row.get("correlation_id"),
+            });
+        }
+
+        Ok(samples)
+    }
+
+    // Newest first
+    pub async fn get_history(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
+        let rows = sqlx::query(
+            r#"
+            WITH RECURSIVE subtree(id) AS (
+                SELECT id FROM collections WHERE id = ?
+                UNION SELECT collections.id FROM collections JOIN subtree ON collections.parent_id = subtree.id
+            )
+            SELECT request_samples.*, requests.name, requests.method, requests.url, requests.collection_id
+            FROM request_samples JOIN requests ON requests.id = request_samples.request_id
+            WHERE (? IS NULL OR request_samples.request_id = ?)
+                AND (? IS NULL OR requests.collection_id IN (SELECT id FROM subtree))
+                AND (? IS NULL OR sent_at >= ?)
+                AND (? IS NULL OR sent_at < ?)
+                AND (NOT ? OR status IS NULL OR status >= 400)
+            ORDER BY sent_at DESC, request_samples.id
+            "#,
+        )
+        .bind(&filter.collection_id)
+        .bind(&filter.request_id)
+        .bind(&filter.request_id)
+        .bind(&filter.collection_id)
+        .bind(filter.from.map(|at| at.to_rfc3339()))
+        .bind(filter.from.map(|at| at.to_rfc3339()))
+        .bind(filter.to.map(|at| at.to_rfc3339()))
+        .bind(filter.to.map(|at| at.to_rfc3339()))
+        .bind(filter.failed_only)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut entries = Vec::with_capacity(rows.len());
+        for row in rows {
+            entries.push(HistoryEntry {
+                sent_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("sent_at"))?.with_timezone(&Utc),
+                request_id: row.get("request_id"),
+                request_name: row.get("name"),
+                method: row.get("method"),
+                url: row.get("url"),
+                collection_id: row.get("collection_id"),
+                status: row.get::<Option<i64>, _>("status").map(|status| status as u16),
+                duration_ms: row.get::<i64, _>("duration_ms") as u64,
+                response_size: row.get::<i64, _>("response_size") as u64,
+                trace_id: row.get("trace_id"),
+                correlation_id: row.get("correlation_id"),
+            });
+        }
+        Ok(entries)
+    }
+
+    // 🎓 TEACHING: History retention
+    // Age and entry limits apply to each history table on its own. The size limit covers
+    // them together: rows are counted newest first across all of them, and everything from
+    // the first row that no longer fits back is removed. Returns how many rows went.
+    pub async fn prune_history(
+        &self,
+        max_entries: Option<u64>,
+        max_age: Option<chrono::Duration>,
+        max_bytes: Option<u64>,
+    ) -> Result<u64> {
+        let mut removed = 0;
+        for (table, dated_by, _) in RETAINED_HISTORY {
+            if let Some(max_age) = max_age {
+                removed += sqlx::query(&format!("DELETE FROM {} WHERE {} < ?", table, dated_by))
+                    .bind((Utc::now() - max_age).to_rfc3339())
+                    .execute(&self.pool)
+                    .await?
+                    .rows_affected();
+            }
+            if let Some(max_entries) = max_entries {
+                removed += sqlx::query(&format!(
+                    "DELETE FROM {table} WHERE id NOT IN (SELECT id FROM {table} ORDER BY {dated_by} DESC, id LIMIT ?)",
+                    table = table,
+                    dated_by = dated_by
+                ))
+                .bind(max_entries.min(i64::MAX as u64) as i64)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+            }
+        }
+
+        if let Some(max_bytes) = max_bytes {
+            let rows = RETAINED_HISTORY
+                .iter()
+                .map(|(table, dated_by, bytes)| {
+                    format!("SELECT {} AS at, {} + {} AS bytes FROM {}", dated_by, HISTORY_ROW_BYTES, bytes, table)
+                })
+                .collect::<Vec<_>>()
+                .join(" UNION ALL ");
+            let cutoff: Option<String> = sqlx::query_scalar(&format!(
+                "SELECT MAX(at) FROM (SELECT at, SUM(bytes) OVER (ORDER BY at DESC) AS running_bytes FROM ({})) WHERE running_bytes > ?",
+                rows
+            ))
+            .bind(max_bytes.min(i64::MAX as u64) as i64)
+            .fetch_one(&self.pool)
+            .await?;
+            if let Some(cutoff) = cutoff {
+                for (table, dated_by, _) in RETAINED_HISTORY {
+                    removed += sqlx::query(&format!("DELETE FROM {} WHERE {} <= ?", table, dated_by))
+                        .bind(&cutoff)
+                        .execute(&self.pool)
+                        .await?
+                        .rows_affected();
+                }
+            }
+        }
+        Ok(removed)
+    }
+
+    // ============ WORKFLOWS ============
+
+    pub async fn create_workflow(&self, name: String) -> Result<Workflow> {
+        let now = Utc::now();
+        let workflow = Workflow {
+            id: Uuid::new_v4().to_string(),
+            name,
+            nodes: "[]".to_string(),
+            edges: "[]".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        sqlx::query("INSERT INTO workflows (id, name, nodes, edges, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(&workflow.id)
+            .bind(&workflow.name)
+            .bind(&workflow.nodes)
+            .bind(&workflow.edges)
+            .bind(workflow.created_at.to_rfc3339())
+            .bind(workflow.updated_at.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(workflow)
+    }
+
+    pub async fn get_workflows(&self) -> Result<Vec<Workflow>> {
+        let rows = sqlx::query("SELECT * FROM workflows ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Self::workflow_from_row).collect()
+    }
+
+    pub async fn get_workflow(&self, id: &str) -> Result<Option<Workflow>> {
+        let row = sqlx::query("SELECT * FROM workflows WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::workflow_from_row).transpose()
+    }
+
+    fn workflow_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Workflow> {
+        Ok(Workflow {
+            id: row.get("id"),
+            name: row.get("name"),
+            nodes: row.get("nodes"),
+            edges: row.get("edges"),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))?.with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&Utc),
+        })
+    }
+
+    pub async fn update_workflow(&self, workflow: Workflow) -> Result<Workflow> {
+        let updated_workflow = Workflow {
+            updated_at: Utc::now(),
+            ..workflow
+        };
+
+        let result = sqlx::query("UPDATE workflows SET name = ?, nodes = ?, edges = ?, updated_at = ? WHERE id = ?")
+            .bind(&updated_workflow.name)
+            .bind(&updated_workflow.nodes)
+            .bind(&updated_workflow.edges)
+            .bind(updated_workflow.updated_at.to_rfc3339())
+            .bind(&updated_workflow.id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Workflow not found").into());
+        }
+
+        Ok(updated_workflow)
+    }
+
+    pub async fn delete_workflow(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM workflows WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // ============ MOCK RESPONSES ============
+
+    pub async fn set_mock_response(&self, mock: MockResponse) -> Result<MockResponse> {
+        let mock = MockResponse {
+            updated_at: Utc::now(),
+            ..mock
+        };
+        sqlx::query(
+            "INSERT OR REPLACE INTO mock_responses (request_id, status, headers, body, latency_ms, updated_at) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&mock.request_id)
+        .bind(mock.status as i64)
+        .bind(&mock.headers)
+        .bind(&mock.body)
+        .bind(mock.latency_ms.map(|ms| ms as i64))
+        .bind(mock.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(mock)
+    }
+
+    pub async fn get_mock_response(&self, request_id: &str) -> Result<Option<MockResponse>> {
+        let row = sqlx::query("SELECT * FROM mock_responses WHERE request_id = ?")
+            .bind(request_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| Self::mock_response_from_row(&row)).transpose()
+    }
+
+    // Mock responses for every request in a collection, keyed by request id
+    pub async fn get_mock_responses_by_collection(&self, collection_id: &str) -> Result<HashMap<String, MockResponse>> {
+        let rows = sqlx::query(
+            "SELECT m.* FROM mock_responses m JOIN requests r ON r.id = m.request_id WHERE r.collection_id = ?"
+        )
+        .bind(collection_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut mocks = HashMap::new();
+        for row in rows {
+            let mock = Self::mock_response_from_row(&row)?;
+            mocks.insert(mock.request_id.clone(), mock);
+        }
+
+        Ok(mocks)
+    }
+
+    pub async fn delete_mock_response(&self, request_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM mock_responses WHERE request_id = ?")
+            .bind(request_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    fn mock_response_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<MockResponse> {
+        Ok(MockResponse {
+            request_id: row.get("request_id"),
+            status: row.get::<i64, _>("status") as u16,
+            headers: row.get("headers"),
+            body: row.get("body"),
+            latency_ms: row.get::<Option<i64>, _>("latency_ms").map(|ms| ms as u64),
+            updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))?.with_timezone(&Utc),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    struct TempDatabase {
+        dir: PathBuf,
+        url: String,
+    }
+
+    impl TempDatabase {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("{}-{}", name, Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let url = format!("sqlite:{}?mode=rwc", dir.join("openrequest.db").display());
+            Self { dir, url }
+        }
+
+        async fn open(&self) -> Database {
+            Database::new(&self.url, 1).await.unwrap()
+        }
+    }
+
+    impl Drop for TempDatabase {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    // The tables as the first release created them, before any column was added
+    const FIRST_RELEASE_SCHEMA: &[&str] = &[
+        "CREATE TABLE collections (id TEXT PRIMARY KEY, name TEXT NOT NULL, description TEXT,
+            parent_id TEXT REFERENCES collections(id), created_at TEXT NOT NULL, updated_at TEXT NOT NULL)",
+        "CREATE TABLE requests (id TEXT PRIMARY KEY, collection_id TEXT NOT NULL REFERENCES collections(id),
+            name TEXT NOT NULL, method TEXT NOT NULL, url TEXT NOT NULL, params TEXT NOT NULL DEFAULT '[]',
+            headers TEXT NOT NULL DEFAULT '{}', body_type TEXT NOT NULL DEFAULT 'none', body_str TEXT,
+            auth_type TEXT, auth_data TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL)",
+        "CREATE TABLE environments (id TEXT PRIMARY KEY, name TEXT NOT NULL UNIQUE,
+            is_active BOOLEAN NOT NULL DEFAULT FALSE, created_at TEXT NOT NULL, updated_at TEXT NOT NULL)",
+        "CREATE TABLE variables (id TEXT PRIMARY KEY, environment_id TEXT REFERENCES environments(id),
+            key TEXT NOT NULL, value TEXT NOT NULL, is_secret BOOLEAN NOT NULL DEFAULT FALSE,
+            created_at TEXT NOT NULL, updated_at TEXT NOT NULL, UNIQUE(environment_id, key))",
+        "CREATE TABLE response_cache (id TEXT PRIMARY KEY, request_hash TEXT UNIQUE NOT NULL,
+            method TEXT NOT NULL, url TEXT NOT NULL, response_status INTEGER NOT NULL,
+            response_headers TEXT NOT NULL, response_body TEXT NOT NULL, cache_time TEXT NOT NULL, expires_at TEXT)",
+    ];
+
+    // A database file left by the first release, holding the given rows. That release
+    // didn't enforce foreign keys, so rows may point at things that are gone.
+    async fn first_release_database(url: &str, rows: &[String]) {
+        let options = SqliteConnectOptions::from_str(url).unwrap().foreign_keys(false);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        for statement in FIRST_RELEASE_SCHEMA.iter().copied().chain(rows.iter().map(String::as_str)) {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        pool.close().await;
+    }
+
+    async fn execute(db: &Database, statements: &[&str]) {
+        for statement in statements {
+            sqlx::query(statement).execute(&db.pool).await.unwrap();
+        }
+    }
+
+    async fn columns(db: &Database, table: &str) -> Vec<String> {
+        sqlx::query(&format!("PRAGMA table_info({})", table))
+            .fetch_all(&db.pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get("name"))
+            .collect()
+    }
+
+    async fn names(db: &Database, kind: &str) -> Vec<String> {
+        sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = ? ORDER BY name")
+            .bind(kind)
+            .fetch_all(&db.pool)
+            .await
+            .unwrap()
+    }
+
+    async fn ids(db: &Database, query: &str) -> Vec<String> {
+        sqlx::query_scalar(query).fetch_all(&db.pool).await.unwrap()
+    }
+
+    // Every table with its columns, and every index, ignoring the order columns were added in
+    async fn schema(db: &Database) -> (BTreeMap<String, Vec<String>>, Vec<String>) {
+        let mut tables = BTreeMap::new();
+        for table in names(db, "table").await {
+            let mut table_columns = columns(db, &table).await;
+            table_columns.sort();
+            tables.insert(table, table_columns);
+        }
+        (tables, names(db, "index").await)
+    }
+
+    #[tokio::test]
+    async fn test_migrations_upgrade_a_first_release_database() {
+        let now = "2024-01-01T00:00:00+00:00";
+        let rows = [
+            format!("INSERT INTO collections (id, name, created_at, updated_at) VALUES ('beta', 'Beta', '{now}', '{now}'), ('alpha', 'Alpha', '{now}', '{now}')"),
+            format!("INSERT INTO collections (id, name, parent_id, created_at, updated_at) VALUES ('child', 'Child', 'beta', '{now}', '{now}')"),
+            format!("INSERT INTO requests (id, collection_id, name, method, url, created_at, updated_at) VALUES ('zed', 'beta', 'Zed', 'GET', '/z', '{now}', '{now}'), ('abc', 'beta', 'Abc', 'GET', '/a', '{now}', '{now}')"),
+            format!("INSERT INTO environments (id, name, created_at, updated_at) VALUES ('dev', 'Dev', '{now}', '{now}')"),
+            format!("INSERT INTO variables (id, environment_id, key, value, created_at, updated_at) VALUES ('host', 'dev', 'host', 'localhost', '{now}', '{now}')"),
+            format!("INSERT INTO response_cache (id, request_hash, method, url, response_status, response_headers, response_body, cache_time) VALUES ('cached', 'hash', 'GET', '/a', 200, '{{}}', 'inline', '{now}')"),
+        ];
+        let old = TempDatabase::new("migrations");
+        first_release_database(&old.url, &rows).await;
+
+        let db = old.open().await;
+        let latest = MIGRATIONS.last().map_or(BASELINE_VERSION, |migration| migration.version);
+        assert_eq!(db.schema_version().await.unwrap(), latest);
+        let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM schema_version ORDER BY version")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(applied, (BASELINE_VERSION..=latest).collect::<Vec<_>>());
+
+        // Same tables, columns and indexes as a database created from scratch
+        let fresh = TempDatabase::new("migrations");
+        let fresh_db = fresh.open().await;
+        assert_eq!(schema(&db).await, schema(&fresh_db).await);
+        fresh_db.pool.close().await;
+
+        // Existing rows were kept
+        assert_eq!(ids(&db, "SELECT id FROM collections ORDER BY id").await, ["alpha", "beta", "child"]);
+        assert_eq!(ids(&db, "SELECT id FROM requests ORDER BY id").await, ["abc", "zed"]);
+        assert_eq!(ids(&db, "SELECT id FROM variables").await, ["host"]);
+        assert_eq!(ids(&db, "SELECT response_body FROM response_cache").await, ["inline"]);
+
+        // Siblings are numbered in the order the sidebar used to show them, by name
+        assert_eq!(ids(&db, "SELECT id FROM requests ORDER BY sort_order").await, ["abc", "zed"]);
+        let top_level = ids(&db, "SELECT id FROM collections WHERE parent_id IS NULL ORDER BY sort_order").await;
+        assert_eq!(top_level, ["alpha", "beta"]);
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_reopening_applies_nothing_twice() {
+        let temp = TempDatabase::new("migrations");
+        temp.open().await.pool.close().await;
+
+        let db = temp.open().await;
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schema_version").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(count, MIGRATIONS.len() as i64 + 1);
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_newer_schema_is_refused() {
+        let temp = TempDatabase::new("migrations");
+        let db = temp.open().await;
+        db.record_schema_version(999, "From the future").await.unwrap();
+        db.pool.close().await;
+
+        let error = Database::new(&temp.url, 1).await.err().unwrap();
+        assert!(error.to_string().contains("newer than this version"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_drops_broken_references_and_cascades() {
+        let now = "2024-01-01T00:00:00+00:00";
+        let rows = [
+            format!("INSERT INTO collections (id, name, created_at, updated_at) VALUES ('beta', 'Beta', '{now}', '{now}')"),
+            format!("INSERT INTO collections (id, name, parent_id, created_at, updated_at) VALUES ('child', 'Child', 'beta', '{now}', '{now}'), ('stray', 'Stray', 'gone', '{now}', '{now}')"),
+            format!("INSERT INTO requests (id, collection_id, name, method, url, created_at, updated_at) VALUES ('kept', 'child', 'Kept', 'GET', '/k', '{now}', '{now}'), ('lost', 'gone', 'Lost', 'GET', '/l', '{now}', '{now}')"),
+            format!("INSERT INTO environments (id, name, created_at, updated_at) VALUES ('dev', 'Dev', '{now}', '{now}')"),
+            format!("INSERT INTO variables (id, environment_id, key, value, created_at, updated_at) VALUES ('host', 'dev', 'host', 'localhost', '{now}', '{now}'), ('orphan', 'deleted-env', 'host', 'x', '{now}', '{now}')"),
+        ];
+        let temp = TempDatabase::new("foreign-keys");
+        first_release_database(&temp.url, &rows).await;
+
+        // A folder whose parent is gone moves to the top level; rows that belong to nothing are dropped
+        let db = temp.open().await;
+        assert!(sqlx::query("PRAGMA foreign_key_check").fetch_all(&db.pool).await.unwrap().is_empty());
+        assert_eq!(ids(&db, "SELECT id FROM collections WHERE parent_id IS NULL ORDER BY id").await, ["beta", "stray"]);
+        assert_eq!(ids(&db, "SELECT id FROM requests").await, ["kept"]);
+        assert_eq!(ids(&db, "SELECT id FROM variables").await, ["host"]);
+
+        // The rebuilt tables cascade, and run history outlives its environment
+        execute(&db, &[&format!("INSERT INTO collection_runs (id, collection_id, environment_id, total_requests, passed_requests, failed_requests, results, started_at, finished_at) VALUES ('run', 'stray', 'dev', 1, 1, 0, '[]', '{now}', '{now}')")]).await;
+        execute(&db, &["DELETE FROM collections WHERE id = 'beta'", "DELETE FROM environments"]).await;
+        assert_eq!(ids(&db, "SELECT id FROM collections").await, ["stray"]);
+        assert!(ids(&db, "SELECT id FROM requests").await.is_empty());
+        assert!(ids(&db, "SELECT id FROM variables").await.is_empty());
+        let run_environment: Option<String> = sqlx::query_scalar("SELECT environment_id FROM collection_runs")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(run_environment, None);
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_leaving_broken_references_rolls_back() {
+        const BROKEN: Migration = Migration {
+            version: 999,
+            description: "Broken rebuild",
+            statements: &[
+                "CREATE TABLE parents (id TEXT PRIMARY KEY)",
+                "CREATE TABLE children (id TEXT PRIMARY KEY, parent_id TEXT REFERENCES parents(id))",
+                "INSERT INTO children (id, parent_id) VALUES ('child', 'missing')",
+            ],
+            rebuilds_tables: true,
+        };
+        let temp = TempDatabase::new("foreign-keys");
+        let db = temp.open().await;
+        let before = db.schema_version().await.unwrap();
+
+        let mut conn = db.pool.acquire().await.unwrap();
+        sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut conn).await.unwrap();
+        let error = Database::apply_migration(&mut conn, &BROKEN).await.err().unwrap();
+        assert!(error.to_string().contains("1 row(s) with a broken reference, first in table children"), "{}", error);
+        drop(conn);
+
+        assert!(!names(&db, "table").await.contains(&"children".to_string()));
+        assert_eq!(db.schema_version().await.unwrap(), before);
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_deleting_a_collection_takes_its_tree_or_nothing() {
+        let temp = TempDatabase::new("delete-collection");
+        let db = temp.open().await;
+        let root = db.create_collection("Root".to_string(), None, None).await.unwrap();
+        let child = db.create_collection("Child".to_string(), None, Some(root.id.clone())).await.unwrap();
+        let other = db.create_collection("Other".to_string(), None, None).await.unwrap();
+        let create = |collection: &Collection| {
+            db.create_request(collection.id.clone(), "Get".to_string(), "GET".to_string(), "/".to_string())
+        };
+        let (nested, kept) = (create(&child).await.unwrap(), create(&other).await.unwrap());
+        let variable = |key: &str, collection_id: Option<&str>, request_id: Option<&str>| NewVariable {
+            environment_id: None,
+            collection_id: collection_id.map(str::to_string),
+            request_id: request_id.map(str::to_string),
+            key: key.to_string(),
+            value: "value".to_string(),
+            is_secret: false,
+        };
+        db.bulk_create_variables(vec![
+            variable("folder", Some(&child.id), None),
+            variable("nested", None, Some(&nested.id)),
+            variable("kept", Some(&other.id), None),
+        ])
+        .await
+        .unwrap();
+
+        let now = Utc::now().to_rfc3339();
+        let monitors = format!(
+            "INSERT INTO monitors (id, name, target_type, target_id, cron, created_at, updated_at) VALUES ('folder', 'Folder', 'collection', '{}', '0 * * * *', '{now}', '{now}'), ('nested', 'Nested', 'request', '{}', '0 * * * *', '{now}', '{now}'), ('kept', 'Kept', 'collection', '{}', '0 * * * *', '{now}', '{now}')",
+            child.id, nested.id, other.id
+        );
+        let capture = format!(
+            "INSERT INTO proxy_captures (id, collection_id, request_id, method, path, request_headers, request_body, status, response_headers, response_body_base64, duration_ms, captured_at) VALUES ('capture', '{}', '{}', 'GET', '/', '{{}}', '', 200, '{{}}', '', 1, '{now}')",
+            child.id, nested.id
+        );
+        // Refuses the very last step, after the captures and monitors were deleted
+        let refuse = "CREATE TRIGGER keep_root BEFORE DELETE ON collections WHEN old.name = 'Root' BEGIN SELECT RAISE(ABORT, 'kept'); END";
+        execute(&db, &[&monitors, &capture, refuse]).await;
+
+        assert!(db.delete_collection(&root.id).await.is_err());
+        assert_eq!(ids(&db, "SELECT id FROM monitors ORDER BY id").await, ["folder", "kept", "nested"]);
+        assert_eq!(ids(&db, "SELECT id FROM proxy_captures").await, ["capture"]);
+        assert_eq!(ids(&db, "SELECT key FROM variables ORDER BY key").await, ["folder", "kept", "nested"]);
+
+        execute(&db, &["DROP TRIGGER keep_root"]).await;
+        db.delete_collection(&root.id).await.unwrap();
+        assert_eq!(ids(&db, "SELECT id FROM collections").await, [other.id.as_str()]);
+        assert_eq!(ids(&db, "SELECT id FROM requests").await, [kept.id.as_str()]);
+        assert_eq!(ids(&db, "SELECT key FROM variables").await, ["kept"]);
+        assert_eq!(ids(&db, "SELECT id FROM monitors").await, ["kept"]);
+        assert!(ids(&db, "SELECT id FROM proxy_captures").await.is_empty());
+        db.pool.close().await;
+    }
+
+    fn app_error(error: &anyhow::Error) -> &AppError {
+        error.downcast_ref::<AppError>().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_is_all_or_nothing() {
+        let temp = TempDatabase::new("bulk");
+        let db = temp.open().await;
+        let collection = db.create_collection("Bulk".to_string(), None, None).await.unwrap();
+        let create = |name: &str| {
+            db.create_request(collection.id.clone(), name.to_string(), "GET".to_string(), "/".to_string())
+        };
+        let (first, second) = (create("first").await.unwrap(), create("second").await.unwrap());
+
+        // The second request doesn't exist, so the first update is rolled back with it
+        let renamed = Request { name: "renamed".to_string(), ..first.clone() };
+        let unknown = Request { id: "unknown".to_string(), ..second.clone() };
+        let error = db.bulk_update_requests(vec![renamed.clone(), unknown]).await.err().unwrap();
+        assert_eq!(app_error(&error).kind, ErrorKind::NotFound);
+        assert_eq!(db.get_request_by_id(&first.id).await.unwrap().unwrap().name, "first");
+
+        let saved = db.bulk_update_requests(vec![renamed]).await.unwrap();
+        assert_eq!(saved[0].name, "renamed");
+        assert_eq!(db.get_request_by_id(&first.id).await.unwrap().unwrap().name, "renamed");
+
+        let ids = [first.id.clone(), "unknown".to_string(), second.id.clone()];
+        assert_eq!(db.bulk_delete_requests(&ids).await.unwrap(), 2);
+        assert!(db.get_request_by_id(&second.id).await.unwrap().is_none());
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_bulk_variables_are_all_or_nothing() {
+        let temp = TempDatabase::new("bulk");
+        let db = temp.open().await;
+        let environment = db.create_environment("Dev".to_string()).await.unwrap();
+        let collection = db.create_collection("Bulk".to_string(), None, None).await.unwrap();
+        let variable = |key: &str, environment_id: Option<&str>, collection_id: Option<&str>| NewVariable {
+            environment_id: environment_id.map(str::to_string),
+            collection_id: collection_id.map(str::to_string),
+            request_id: None,
+            key: key.to_string(),
+            value: "value".to_string(),
+            is_secret: false,
+        };
+
+        let both = variable("both", Some(&environment.id), Some(&collection.id));
+        let error = db.bulk_create_variables(vec![variable("host", Some(&environment.id), None), both]).await.err();
+        assert_eq!(app_error(&error.unwrap()).kind, ErrorKind::Validation);
+        assert!(db.get_variables(Some(&environment.id)).await.unwrap().is_empty());
+
+        let created = db
+            .bulk_create_variables(vec![
+                variable("host", Some(&environment.id), None),
+                variable("token", None, Some(&collection.id)),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(created.len(), 2);
+        assert_eq!(db.get_collection_variables(&collection.id).await.unwrap()[0].key, "token");
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_stale_saves_are_rejected_with_the_current_copy() {
+        let temp = TempDatabase::new("versions");
+        let db = temp.open().await;
+        let collection = db.create_collection("Docs".to_string(), None, None).await.unwrap();
+        let request = db
+            .create_request(collection.id.clone(), "Get".to_string(), "GET".to_string(), "/".to_string())
+            .await
+            .unwrap();
+
+        let saved = db.update_request(Request { url: "/v2".to_string(), ..request.clone() }, false).await.unwrap();
+        assert_eq!(saved.version, request.version + 1);
+        let stale = Request { url: "/v3".to_string(), ..request.clone() };
+        let error = db.update_request(stale, false).await.err().unwrap();
+        let conflict = app_error(&error);
+        assert_eq!(conflict.kind, ErrorKind::Conflict);
+        assert_eq!(conflict.details.as_ref().unwrap()["current"]["url"], "/v2");
+        // Bulk saves are checked the same way
+        let stale = Request { name: "renamed".to_string(), ..request.clone() };
+        let error = db.bulk_update_requests(vec![stale]).await.err().unwrap();
+        assert_eq!(app_error(&error).kind, ErrorKind::Conflict);
+        // Version 0 is just another stale version; only `force` overwrites
+        let unversioned = Request { url: "/v3".to_string(), version: 0, ..request };
+        let error = db.update_request(unversioned.clone(), false).await.err().unwrap();
+        assert_eq!(app_error(&error).kind, ErrorKind::Conflict);
+        let forced = db.update_request(unversioned, true).await.unwrap();
+        assert_eq!((forced.url.as_str(), forced.version), ("/v3", saved.version + 1));
+
+        let renamed = Collection { name: "API".to_string(), ..collection.clone() };
+        let renamed = db.update_collection(renamed, false).await.unwrap();
+        assert_eq!(renamed.version, collection.version + 1);
+        let error = db.update_collection(collection.clone(), false).await.err().unwrap();
+        assert_eq!(app_error(&error).details.as_ref().unwrap()["current"]["name"], "API");
+        let forced = db.update_collection(Collection { name: "Docs".to_string(), ..collection }, true).await.unwrap();
+        assert_eq!((forced.name.as_str(), forced.version), ("Docs", renamed.version + 1));
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes_wait_for_each_other() {
+        let temp = TempDatabase::new("concurrent");
+        let db = Database::new(&temp.url, 4).await.unwrap();
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(journal_mode, "wal");
+
+        let collection = db.create_collection("Writes".to_string(), None, None).await.unwrap();
+        let writers: Vec<_> = (0..20)
+            .map(|i| {
+                let (db, collection_id) = (db.clone(), collection.id.clone());
+                tokio::spawn(async move {
+                    db.create_request(collection_id, format!("request {}", i), "GET".to_string(), "/".to_string())
+                        .await
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.await.unwrap().unwrap();
+        }
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM requests").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(count, 20);
+        db.pool.close().await;
+    }
+
+    async fn cache(db: &Database, url: &str, body: &str) -> ResponseCache {
+        db.cache_response(
+            "GET".to_string(),
+            url.to_string(),
+            "{}".to_string(),
+            String::new(),
+            200,
+            "{}".to_string(),
+            body.to_string(),
+            None,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cache_listing_filters_and_pages() {
+        let temp = TempDatabase::new("cache");
+        let db = temp.open().await;
+        for i in 0..55 {
+            cache(&db, &format!("https://api.example.com/items/{}", i), "[]").await;
+        }
+        let post = |url: &str| {
+            db.cache_response(
+                "POST".to_string(),
+                url.to_string(),
+                "{}".to_string(),
+                String::new(),
+                201,
+                "{}".to_string(),
+                "{}".to_string(),
+                Some(0),
+            )
+        };
+        post("https://other.test/x_y").await.unwrap();
+        let newest = post("https://other.test/xzy").await.unwrap();
+
+        let first = db.list_cache_entries(&CacheEntryFilter::default(), 0).await.unwrap();
+        assert_eq!((first.total, first.entries.len()), (57, CACHE_PAGE_SIZE as usize));
+        assert_eq!(first.entries[0].url, "https://other.test/xzy");
+        let items = CacheEntryFilter { url: Some(" ITEMS/ ".to_string()), ..Default::default() };
+        let last = db.list_cache_entries(&items, 1).await.unwrap();
+        assert_eq!((last.total, last.entries.len()), (55, 5));
+        assert_eq!(last.entries[4].url, "https://api.example.com/items/0");
+        assert!(last.entries.iter().all(|entry| !entry.expired && entry.size_bytes > 0));
+
+        // `_` is matched literally, not as a LIKE wildcard
+        let literal = CacheEntryFilter { url: Some("x_y".to_string()), ..Default::default() };
+        let found = db.list_cache_entries(&literal, 0).await.unwrap();
+        assert_eq!(found.entries.iter().map(|entry| entry.url.as_str()).collect::<Vec<_>>(), ["https://other.test/x_y"]);
+
+        let expired_posts = CacheEntryFilter { method: Some("post".to_string()), expired: Some(true), ..Default::default() };
+        assert_eq!(db.list_cache_entries(&expired_posts, 0).await.unwrap().total, 2);
+        let fresh = CacheEntryFilter { expired: Some(false), ..Default::default() };
+        assert_eq!(db.list_cache_entries(&fresh, 0).await.unwrap().total, 55);
+
+        db.delete_cache_entry(&newest.id).await.unwrap();
+        assert_eq!(db.list_cache_entries(&expired_posts, 0).await.unwrap().total, 1);
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_cache_policy_lookup() {
+        let temp = TempDatabase::new("policies");
+        let db = temp.open().await;
+        let parent = db.create_collection("Parent".to_string(), None, None).await.unwrap();
+        let child = db.create_collection("Child".to_string(), None, Some(parent.id.clone())).await.unwrap();
+        let folder = db.create_cache_policy(None, Some(parent.id.clone()), Some(60), false).await.unwrap();
+        let wildcard = db.create_cache_policy(Some("*.Example.com".to_string()), None, None, true).await.unwrap();
+        let exact = db.create_cache_policy(Some("api.example.com".to_string()), None, Some(10), false).await.unwrap();
+        assert_eq!(wildcard.host_pattern.as_deref(), Some("*.example.com"));
+
+        let policy_for = |host: &'static str, collection_id: Option<String>| {
+            let db = db.clone();
+            async move { db.cache_policy_for(host, collection_id.as_deref()).await.unwrap().map(|policy| policy.id) }
+        };
+        assert_eq!(policy_for("api.example.com", Some(child.id.clone())).await, Some(folder.id.clone()));
+        assert_eq!(policy_for("api.example.com", None).await, Some(exact.id.clone()));
+        assert_eq!(policy_for("www.example.com", None).await, Some(wildcard.id.clone()));
+        assert_eq!(policy_for("example.org", None).await, None);
+
+        // Disabled rules are skipped
+        db.update_cache_policy(CachePolicy { enabled: false, ..exact }).await.unwrap();
+        assert_eq!(policy_for("api.example.com", None).await, Some(wildcard.id));
+
+        let neither = db.create_cache_policy(None, None, None, false).await.err().unwrap();
+        assert_eq!(app_error(&neither).kind, ErrorKind::Validation);
+        let missing = db.create_cache_policy(None, Some("gone".to_string()), None, false).await.err().unwrap();
+        assert_eq!(app_error(&missing).kind, ErrorKind::NotFound);
+
+        // A collection's rules go with it
+        db.delete_collection(&parent.id).await.unwrap();
+        assert_eq!(db.get_cache_policies().await.unwrap().len(), 2);
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_cache_by_pattern() {
+        let temp = TempDatabase::new("invalidate");
+        let db = temp.open().await;
+        for path in ["/users", "/users/1", "/users?page=2", "/users_archive", "/orders"] {
+            cache(&db, &format!("https://api.example.com{}", path), path).await;
+        }
+        let remaining = || async {
+            let page = db.list_cache_entries(&CacheEntryFilter::default(), 0).await.unwrap();
+            let mut urls: Vec<String> = page.entries.into_iter().map(|entry| entry.url).collect();
+            urls.sort();
+            urls
+        };
+
+        // Without `*` the URL has to match exactly, and `_` is not a wildcard
+        assert_eq!(db.invalidate_cache("https://api.example.com/users_1").await.unwrap(), 0);
+        assert_eq!(db.invalidate_cache(" https://api.example.com/users ").await.unwrap(), 1);
+        assert_eq!(db.invalidate_cache("https://api.example.com/users/*").await.unwrap(), 1);
+        assert_eq!(
+            remaining().await,
+            ["https://api.example.com/orders", "https://api.example.com/users?page=2", "https://api.example.com/users_archive"]
+        );
+        assert_eq!(db.invalidate_cache("*").await.unwrap(), 3);
+
+        let error = db.invalidate_cache("  ").await.err().unwrap();
+        assert_eq!(app_error(&error).kind, ErrorKind::Validation);
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_writes_invalidate_cached_reads_of_the_path() {
+        let temp = TempDatabase::new("invalidate");
+        let db = temp.open().await;
+        for path in ["/users", "/users?page=2", "/users/1"] {
+            cache(&db, &format!("https://api.example.com{}", path), path).await;
+        }
+        db.cache_response(
+            "POST".to_string(),
+            "https://api.example.com/users".to_string(),
+            "{}".to_string(),
+            String::new(),
+            201,
+            "{}".to_string(),
+            "{}".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(db.invalidate_cached_reads("https://api.example.com/users?name=ada").await.unwrap(), 2);
+        let left = db.list_cache_entries(&CacheEntryFilter::default(), 0).await.unwrap();
+        let mut left: Vec<(String, String)> = left.entries.into_iter().map(|entry| (entry.method, entry.url)).collect();
+        left.sort();
+        assert_eq!(
+            left,
+            [
+                ("GET".to_string(), "https://api.example.com/users/1".to_string()),
+                ("POST".to_string(), "https://api.example.com/users".to_string()),
+            ]
+        );
+        db.pool.close().await;
+    }
+
+    async fn blob_count(db: &Database) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM blobs").fetch_one(&db.pool).await.unwrap()
+    }
+
+    // Stored size of the body a cache entry refers to
+    async fn blob_size(db: &Database, entry_id: &str) -> u64 {
+        let size: i64 = sqlx::query_scalar(
+            "SELECT length(data) FROM blobs JOIN response_cache ON response_body_hash = hash WHERE id = ?",
+        )
+        .bind(entry_id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        size as u64
+    }
+
+    #[tokio::test]
+    async fn test_shared_body_outlives_one_of_its_entries() {
+        let temp = TempDatabase::new("blobs");
+        let db = temp.open().await;
+        let first = cache(&db, "https://api.example.com/a", "same body").await;
+        let second = cache(&db, "https://api.example.com/b", "same body").await;
+        assert_eq!(blob_count(&db).await, 1);
+
+        db.delete_cache_entry(&first.id).await.unwrap();
+        assert_eq!(db.delete_orphaned_blobs().await.unwrap(), 0);
+        let kept = db.get_cached_response_by_hash(&second.request_hash).await.unwrap().unwrap();
+        assert_eq!(kept.response_body, "same body");
+
+        db.delete_cache_entry(&second.id).await.unwrap();
+        assert_eq!(db.delete_orphaned_blobs().await.unwrap(), 1);
+        assert_eq!(blob_count(&db).await, 0);
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_proxy_capture_keeps_a_body_the_cache_dropped() {
+        let temp = TempDatabase::new("blobs");
+        let db = temp.open().await;
+        cache(&db, "https://api.example.com/a", "c2hhcmVk").await;
+        db.save_proxy_capture(&ProxyCapture {
+            id: "capture".to_string(),
+            collection_id: "recorded".to_string(),
+            request_id: "request".to_string(),
+            method: "GET".to_string(),
+            path: "/a".to_string(),
+            query: None,
+            request_headers: "{}".to_string(),
+            request_body: String::new(),
+            status: 200,
+            response_headers: "{}".to_string(),
+            response_body_base64: "c2hhcmVk".to_string(),
+            duration_ms: 5,
+            captured_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+        assert_eq!(blob_count(&db).await, 1);
+
+        // Clearing the cache cleans up orphans, but the recording still refers to the body
+        db.clear_all_cache().await.unwrap();
+        assert_eq!(blob_count(&db).await, 1);
+        let captures = db.get_proxy_captures("recorded").await.unwrap();
+        assert_eq!(captures[0].response_body_base64, "c2hhcmVk");
+
+        db.clear_proxy_captures("recorded").await.unwrap();
+        assert_eq!(db.delete_orphaned_blobs().await.unwrap(), 1);
+        db.pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_trim_counts_a_shared_body_once() {
+        let temp = TempDatabase::new("blobs");
+        let db = temp.open().await;
+        let oldest = cache(&db, "https://api.example.com/oldest", &"x".repeat(4000)).await;
+        let older = cache(&db, "https://api.example.com/older", &"y".repeat(3000)).await;
+        let newest = cache(&db, "https://api.example.com/newest", &"x".repeat(4000)).await;
+        for (entry, hours_ago) in [(&oldest, 3), (&older, 2), (&newest, 1)] {
+            sqlx::query("UPDATE response_cache SET cache_time = ? WHERE id = ?")
+                .bind((Utc::now() - chrono::Duration::hours(hours_ago)).to_rfc3339())
+                .bind(&entry.id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+
+        // Each entry has 2 bytes of headers; the x body is stored and counted once
+        let (x_bytes, y_bytes) = (blob_size(&db, &newest.id).await, blob_size(&db, &older.id).await);
+        let total = 6 + x_bytes + y_bytes;
+        assert_eq!(db.get_cache_size_bytes().await.unwrap(), total);
+        assert_eq!(db.trim_cache(total).await.unwrap(), 0);
+
+        // One byte over: only the oldest entry goes, and the newest still holds its body
+        assert_eq!(db.trim_cache(total - 1).await.unwrap(), 1);
+        assert_eq!(db.get_cache_size_bytes().await.unwrap(), total - 2);
+        assert_eq!(db.delete_orphaned_blobs().await.unwrap(), 0);
+
+        assert_eq!(db.trim_cache(2 + x_bytes).await.unwrap(), 1);
+        assert_eq!(db.get_cache_size_bytes().await.unwrap(), 2 + x_bytes);
+        assert_eq!(db.delete_orphaned_blobs().await.unwrap(), 1);
+        let left = db.get_cached_response_by_hash(&newest.request_hash).await.unwrap().unwrap();
+        assert_eq!(left.response_body, "x".repeat(4000));
+        db.pool.close().await;
+    }
+
+    // Stands in for the OS keychain, which tests can't rely on
+    #[derive(Default)]
+    struct MemoryKeychain(std::sync::Mutex<HashMap<String, String>>);
+
+    impl SecretBackend for MemoryKeychain {
+        fn get(&self, key: &str) -> Result<Option<String>> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        fn set(&self, key: &str, value: &str) -> Result<()> {
+            self.0.lock().unwrap().insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<()> {
+            self.0.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secrets_key_kept_in_the_keychain() {
+        let temp = TempDatabase::new("keychain-key");
+        let keychain = Arc::new(MemoryKeychain::default());
+        let mut db = temp.open().await;
+        db.keychain = keychain.clone();
+        let environment = db.create_environment("Dev".to_string()).await.unwrap();
+        let variable = db
+            .create_variable(Some(environment.id.clone()), "token".to_string(), "s3cret".to_string(), true)
+            .await
+            .unwrap();
+
+        db.enable_keychain_secret_encryption().await.unwrap();
+        let status = db.secret_encryption_status();
+        assert!(status.enabled && status.unlocked && status.in_keychain);
+        assert!(secrets::is_encrypted(&db.stored_variable_value(&variable.id).await.unwrap().unwrap()));
+        assert_eq!(app_error(&db.unlock_secrets("guess").await.unwrap_err()).kind, ErrorKind::Validation);
+
+        // Locking forgets the key; the keychain brings it back without a passphrase
+        db.lock_workspace().unwrap();
+        assert!(db.is_locked());
+        db.unlock_secrets_from_keychain().await.unwrap();
+        assert_eq!(db.get_variables(Some(&environment.id)).await.unwrap()[0].value, "s3cret");
+        db.pool.close().await;
+
+        // This key isn't in the real keychain, so the reopened database starts locked
+        let mut db = temp.open().await;
+        assert!(db.is_locked() && db.secret_encryption_status().in_keychain);
+        db.keychain = keychain.clone();
+        db.unlock_secrets_from_keychain().await.unwrap();
+        assert_eq!(db.get_variables(Some(&environment.id)).await.unwrap()[0].value, "s3cret");
+
+        // A key that doesn't match the verifier is refused
+        let entry = keychain.0.lock().unwrap().keys().next().unwrap().clone();
+        keychain.set(&entry, &secrets::generate_key()).unwrap();
+        db.lock_workspace().unwrap();
+        assert_eq!(app_error(&db.unlock_secrets_from_keychain().await.unwrap_err()).kind, ErrorKind::Auth);
+        db.pool.close().await;
+    }
 }
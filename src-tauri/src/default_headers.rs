@@ -0,0 +1,90 @@
+// 🎓 TEACHING: Default headers
+// Headers every request should carry, like `User-Agent: openrequest/1.0` or
+// `Accept: application/json`, can be set once instead of on each request, at three levels:
+//   - app-wide, in the settings (request_defaults.default_headers)
+//   - per environment, e.g. a different User-Agent against staging
+//   - per collection or folder, inherited by everything inside
+// For the same header the closer level wins: a folder beats its collection, which beats the
+// environment, which beats the app. The request's own headers beat them all, and a request
+// can name default headers it doesn't want sent at all. Names are compared ignoring case,
+// as HTTP does.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+// Stored as a JSON object of name -> value; nothing stored means no headers
+pub fn parse(json: Option<&str>) -> Result<HashMap<String, String>> {
+    match json.map(str::trim).filter(|json| !json.is_empty()) {
+        Some(json) => Ok(serde_json::from_str(json)?),
+        None => Ok(HashMap::new()),
+    }
+}
+
+pub fn validate(headers: &HashMap<String, String>) -> Result<()> {
+    for (name, value) in headers {
+        reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| anyhow!("Invalid default header name: {}", name))?;
+        reqwest::header::HeaderValue::from_str(value)
+            .map_err(|_| anyhow!("Invalid value for default header {}", name))?;
+    }
+    Ok(())
+}
+
+// Outermost level first; a later level replaces a header of the same name
+pub fn merge(levels: impl IntoIterator<Item = HashMap<String, String>>) -> HashMap<String, String> {
+    let mut merged: HashMap<String, String> = HashMap::new();
+    for level in levels {
+        for (name, value) in level {
+            merged.retain(|existing, _| !existing.eq_ignore_ascii_case(&name));
+            merged.insert(name, value);
+        }
+    }
+    merged
+}
+
+// Adds the defaults the request neither sets itself nor turned off
+pub fn apply(headers: &mut HashMap<String, String>, defaults: HashMap<String, String>, disabled: &[String]) {
+    for (name, value) in defaults {
+        let taken = headers.keys().chain(disabled).any(|other| other.eq_ignore_ascii_case(&name));
+        if !taken {
+            headers.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_merge_and_apply() {
+        let app = headers(&[("User-Agent", "openrequest/1.0"), ("Accept", "application/json"), ("X-Team", "core")]);
+        let environment = headers(&[("user-agent", "openrequest-staging")]);
+        let folder = headers(&[("X-Team", "billing")]);
+        let merged = merge([app, environment, folder]);
+        let expected = [("user-agent", "openrequest-staging"), ("Accept", "application/json"), ("X-Team", "billing")];
+        assert_eq!(merged, headers(&expected));
+
+        // The request's own Accept wins and X-Team is turned off
+        let mut request = headers(&[("accept", "text/csv")]);
+        apply(&mut request, merged, &["x-team".to_string()]);
+        assert_eq!(request, headers(&[("accept", "text/csv"), ("user-agent", "openrequest-staging")]));
+    }
+
+    #[test]
+    fn test_parse_and_validate() {
+        assert!(parse(None).unwrap().is_empty());
+        assert!(parse(Some(" ")).unwrap().is_empty());
+        let parsed = parse(Some(r#"{"Accept": "application/json"}"#)).unwrap();
+        assert_eq!(parsed, headers(&[("Accept", "application/json")]));
+        assert!(parse(Some("[1]")).is_err());
+
+        assert!(validate(&parsed).is_ok());
+        assert!(validate(&headers(&[("Bad Name", "x")])).is_err());
+        assert!(validate(&headers(&[("X-Ok", "line\nbreak")])).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+// 🎓 TEACHING: Connectivity diagnostics
+// When a request fails with "error sending request", the question is which layer broke:
+//   1. DNS      - the name doesn't resolve (typo, VPN not connected, split-horizon DNS)
+//   2. Network  - it resolves but connecting times out or the host is unreachable (firewall,
+//                 wrong network, server down)
+//   3. Server   - the host answers but refuses the port (nothing listening there), or the
+//                 connection works and the problem is in TLS or HTTP itself
+// resolve_host and tcp_connect_check walk those steps and say where they stopped.
+//
+// Latency is measured with TCP connects rather than ICMP ping, which needs raw sockets and
+// so admin rights; for the same reason there's no traceroute.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+pub const DEFAULT_TIMEOUT_MS: u64 = 5_000;
+pub const MAX_PROBES: u32 = 20;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    Dns,         // The name didn't resolve
+    TimedOut,    // No answer: firewall, wrong network or the host is down
+    Unreachable, // No route to the host or network
+    Refused,     // The host answered but nothing is listening on the port
+    Other,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DnsLookup {
+    pub host: String,
+    pub addresses: Vec<String>,
+    pub duration_ms: f64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConnectAttempt {
+    pub address: String,
+    pub connected: bool,
+    pub duration_ms: f64,
+    pub failure: Option<FailureKind>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TcpCheck {
+    pub host: String,
+    pub port: u16,
+    pub dns: DnsLookup,
+    pub attempts: Vec<ConnectAttempt>, // One per resolved address, until one connects
+    pub reachable: bool,
+    pub failure: Option<FailureKind>, // Where it stopped, if it did
+    pub summary: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LatencyProbe {
+    pub host: String,
+    pub port: u16,
+    pub address: String,           // The address probed, the first one resolved
+    pub samples: Vec<Option<f64>>, // Connect time of each probe in ms, None if it failed
+    pub succeeded: u32,
+    pub min_ms: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    pub jitter_ms: Option<f64>, // Mean difference between consecutive successful probes
+    pub error: Option<String>,  // Of the last failed probe
+}
+
+// `input` may be a bare host or a URL; a port given separately wins over the URL's
+pub fn target(input: &str, port: Option<u16>, default_port: u16) -> Result<(String, u16)> {
+    let input = input.trim();
+    if input.contains("://") {
+        let url = url::Url::parse(input)?;
+        let host = url.host_str().ok_or_else(|| anyhow!("The URL has no host"))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+        return Ok((host, port.or(url.port_or_known_default()).unwrap_or(default_port)));
+    }
+    if input.is_empty() {
+        return Err(anyhow!("Host is required"));
+    }
+    Ok((input.to_string(), port.unwrap_or(default_port)))
+}
+
+// A failed lookup is a result here, not an error
+pub async fn resolve_host(host: &str) -> Result<DnsLookup> {
+    let (host, _) = target(host, None, 0)?;
+    Ok(lookup(&host, 0).await.0)
+}
+
+pub async fn tcp_connect_check(host: &str, port: u16, timeout_ms: Option<u64>) -> Result<TcpCheck> {
+    let (host, port) = target(host, Some(port), port)?;
+    let host = host.as_str();
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    let (dns, addresses) = lookup(host, port).await;
+    if dns.error.is_some() {
+        return Ok(TcpCheck {
+            summary: format!("DNS lookup for {} failed: {}", host, dns.error.as_deref().unwrap_or_default()),
+            host: host.to_string(),
+            port,
+            dns,
+            attempts: Vec::new(),
+            reachable: false,
+            failure: Some(FailureKind::Dns),
+        });
+    }
+
+    let mut attempts = Vec::new();
+    for address in &addresses {
+        let attempt = connect(*address, timeout).await;
+        let connected = attempt.connected;
+        attempts.push(attempt);
+        if connected {
+            break;
+        }
+    }
+    let reachable = attempts.last().is_some_and(|attempt| attempt.connected);
+    let failure = if reachable { None } else { attempts.last().and_then(|attempt| attempt.failure) };
+    let summary = match failure {
+        None => format!(
+            "Connected to {}:{} in {:.1} ms; if requests still fail, look at TLS or the server's response",
+            host,
+            port,
+            attempts.last().map_or(0.0, |attempt| attempt.duration_ms)
+        ),
+        Some(FailureKind::Refused) => {
+            format!("{} is reachable but refused port {}: nothing is listening there", host, port)
+        }
+        Some(FailureKind::TimedOut) => format!(
+            "Connecting to {}:{} timed out: a firewall, VPN or proxy may be in the way, or the host is down",
+            host, port
+        ),
+        Some(FailureKind::Unreachable) => format!("No route to {}: check the network connection or VPN", host),
+        Some(_) => format!(
+            "Could not connect to {}:{}: {}",
+            host,
+            port,
+            attempts.last().and_then(|attempt| attempt.error.clone()).unwrap_or_default()
+        ),
+    };
+    Ok(TcpCheck { host: host.to_string(), port, dns, attempts, reachable, failure, summary })
+}
+
+// 🎓 TEACHING: "TCP ping": time `count` connects to the first resolved address, one after another
+pub async fn probe_latency(host: &str, port: u16, count: u32, timeout_ms: Option<u64>) -> Result<LatencyProbe> {
+    if count == 0 || count > MAX_PROBES {
+        return Err(anyhow!("Can send between 1 and {} probes", MAX_PROBES));
+    }
+    let (host, port) = target(host, Some(port), port)?;
+    let host = host.as_str();
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    let address = match lookup(host, port).await {
+        (_, addresses) if !addresses.is_empty() => addresses[0],
+        (dns, _) => return Err(anyhow!("DNS lookup for {} failed: {}", host, dns.error.unwrap_or_default())),
+    };
+
+    let mut samples = Vec::new();
+    let mut error = None;
+    for _ in 0..count {
+        let attempt = connect(address, timeout).await;
+        samples.push(attempt.connected.then_some(attempt.duration_ms));
+        if attempt.error.is_some() {
+            error = attempt.error;
+        }
+    }
+    let times: Vec<f64> = samples.iter().flatten().copied().collect();
+    let succeeded = times.len() as u32;
+    let jitter_ms = (times.len() > 1).then(|| {
+        let total: f64 = times.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum();
+        total / (times.len() - 1) as f64
+    });
+    Ok(LatencyProbe {
+        host: host.to_string(),
+        port,
+        address: address.to_string(),
+        min_ms: times.iter().copied().reduce(f64::min),
+        avg_ms: (succeeded > 0).then(|| times.iter().sum::<f64>() / succeeded as f64),
+        max_ms: times.iter().copied().reduce(f64::max),
+        samples,
+        succeeded,
+        jitter_ms,
+        error,
+    })
+}
+
+// The lookup as reported, and the addresses to connect to
+async fn lookup(host: &str, port: u16) -> (DnsLookup, Vec<SocketAddr>) {
+    let started = Instant::now();
+    let mut addresses: Vec<SocketAddr> = Vec::new();
+    let mut error = None;
+    match tokio::net::lookup_host((host, port)).await {
+        Ok(found) => {
+            for address in found {
+                if !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+            if addresses.is_empty() {
+                error = Some(format!("{} has no addresses", host));
+            }
+        }
+        Err(e) => error = Some(e.to_string()),
+    }
+    let dns = DnsLookup {
+        host: host.to_string(),
+        addresses: addresses.iter().map(|address| address.ip().to_string()).collect(),
+        duration_ms: elapsed_ms(started),
+        error,
+    };
+    (dns, addresses)
+}
+
+async fn connect(address: SocketAddr, timeout: Duration) -> ConnectAttempt {
+    let started = Instant::now();
+    let (failure, error) = match tokio::time::timeout(timeout, TcpStream::connect(address)).await {
+        Ok(Ok(_)) => (None, None),
+        Ok(Err(e)) => (Some(failure_kind(&e)), Some(e.to_string())),
+        Err(_) => (Some(FailureKind::TimedOut), Some(format!("No answer within {} ms", timeout.as_millis()))),
+    };
+    ConnectAttempt {
+        address: address.to_string(),
+        connected: failure.is_none(),
+        duration_ms: elapsed_ms(started),
+        failure,
+        error,
+    }
+}
+
+fn failure_kind(error: &std::io::Error) -> FailureKind {
+    use std::io::ErrorKind;
+    match error.kind() {
+        ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset => FailureKind::Refused,
+        ErrorKind::TimedOut => FailureKind::TimedOut,
+        ErrorKind::HostUnreachable | ErrorKind::NetworkUnreachable | ErrorKind::AddrNotAvailable => {
+            FailureKind::Unreachable
+        }
+        _ => FailureKind::Other,
+    }
+}
+
+fn elapsed_ms(started: Instant) -> f64 {
+    (started.elapsed().as_micros() as f64 / 1000.0 * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_target() {
+        assert_eq!(target("example.com", None, 443).unwrap(), ("example.com".to_string(), 443));
+        assert_eq!(target("https://example.com:8443/x", None, 443).unwrap(), ("example.com".to_string(), 8443));
+        assert_eq!(target("http://[::1]/", Some(9000), 443).unwrap(), ("::1".to_string(), 9000));
+        assert!(target(" ", None, 443).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connect_and_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let check = tcp_connect_check("127.0.0.1", port, None).await.unwrap();
+        assert!(check.reachable && check.failure.is_none());
+        assert_eq!(check.dns.addresses, vec!["127.0.0.1"]);
+
+        let probe = probe_latency("127.0.0.1", port, 3, None).await.unwrap();
+        assert_eq!((probe.succeeded, probe.samples.len()), (3, 3));
+        assert!(probe.min_ms.unwrap() <= probe.avg_ms.unwrap() && probe.avg_ms.unwrap() <= probe.max_ms.unwrap());
+        assert!(probe_latency("127.0.0.1", port, 0, None).await.is_err());
+
+        drop(listener);
+        let check = tcp_connect_check("127.0.0.1", port, None).await.unwrap();
+        assert!(!check.reachable);
+        assert_eq!(check.failure, Some(FailureKind::Refused));
+        assert!(check.summary.contains("nothing is listening"));
+        let probe = probe_latency("127.0.0.1", port, 2, None).await.unwrap();
+        assert_eq!((probe.succeeded, probe.avg_ms), (0, None));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_host() {
+        let lookup = resolve_host("http://127.0.0.1:8080/path").await.unwrap();
+        assert_eq!(lookup.host, "127.0.0.1");
+        assert_eq!((lookup.addresses, lookup.error), (vec!["127.0.0.1".to_string()], None));
+        assert!(resolve_host("").await.is_err());
+    }
+}
@@ -0,0 +1,268 @@
+// 🎓 TEACHING: Command errors
+// Commands used to fail with a plain string, so the UI couldn't tell a timeout from a
+// certificate problem or a missing record. An AppError says what kind of failure it was and
+// whether sending again might help, and can carry data for the UI (like the newer copy in a
+// save conflict). The frontend receives `{ kind, message, details, retryable }`.
+//
+// It also implements std::error::Error, so code that works with anyhow can return one and
+// the kind survives the trip back to the command.
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Network,    // DNS failures, refused or dropped connections
+    Timeout,    // The request (or a database lock) took too long
+    Tls,        // Certificate or handshake problems
+    Database,
+    NotFound,   // The record asked for doesn't exist
+    Validation, // The input can't be used as given
+    Conflict,   // A save based on an older copy; `details.current` is the newer one
+    Auth,       // Credentials missing, rejected, or impossible to compute
+    Offline,    // Offline mode and nothing cached for the request
+    Io,         // Reading or writing files
+    Other,
+}
+
+impl ErrorKind {
+    // Failures that have a fair chance of going away by themselves
+    fn retryable(self) -> bool {
+        matches!(self, ErrorKind::Network | ErrorKind::Timeout)
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub details: Option<Value>,
+    pub retryable: bool,
+}
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        AppError {
+            kind,
+            message: message.into(),
+            details: None,
+            retryable: kind.retryable(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Validation, message)
+    }
+
+    pub fn auth(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Auth, message)
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    // Same kind, with what was being attempted in front of the message
+    pub fn context(self, context: impl std::fmt::Display) -> Self {
+        self.map_message(|message| format!("{}: {}", context, message))
+    }
+
+    // Same error with a different message, e.g. with secrets masked
+    pub fn map_message(mut self, f: impl FnOnce(&str) -> String) -> Self {
+        self.message = f(&self.message);
+        self
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<reqwest::Error> for AppError {
+    fn from(error: reqwest::Error) -> Self {
+        classify_reqwest(&error)
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> Self {
+        classify_sqlx(&error)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        classify_io(&error)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::validation(error.to_string())
+    }
+}
+
+impl From<reqwest::header::InvalidHeaderName> for AppError {
+    fn from(error: reqwest::header::InvalidHeaderName) -> Self {
+        Self::validation(error.to_string())
+    }
+}
+
+impl From<reqwest::header::InvalidHeaderValue> for AppError {
+    fn from(error: reqwest::header::InvalidHeaderValue) -> Self {
+        Self::validation(error.to_string())
+    }
+}
+
+impl From<url::ParseError> for AppError {
+    fn from(error: url::ParseError) -> Self {
+        Self::validation(error.to_string())
+    }
+}
+
+// 🎓 TEACHING: The kind comes from the most specific cause in the chain, but the message is
+// the outermost one, since that's where the context ("Could not copy ...") was added
+impl From<anyhow::Error> for AppError {
+    fn from(error: anyhow::Error) -> Self {
+        let classified = error.chain().find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<AppError>() {
+                Some(e.clone())
+            } else if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                Some(classify_reqwest(e))
+            } else if let Some(e) = cause.downcast_ref::<sqlx::Error>() {
+                Some(classify_sqlx(e))
+            } else {
+                cause.downcast_ref::<std::io::Error>().map(classify_io)
+            }
+        });
+        let message = error.to_string();
+        match classified {
+            Some(classified) => AppError { message, ..classified },
+            None => Self::new(ErrorKind::Other, message),
+        }
+    }
+}
+
+// Messages from modules that still report errors as plain strings
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorKind::Other, message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        Self::new(ErrorKind::Other, message)
+    }
+}
+
+fn classify_reqwest(error: &reqwest::Error) -> AppError {
+    let message = error.to_string();
+    if error.is_timeout() {
+        return AppError::new(ErrorKind::Timeout, message);
+    }
+    // The TLS library's error sits somewhere below the connect error
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        let cause_text = cause.to_string().to_lowercase();
+        if ["certificate", "tls", "ssl", "handshake"].iter().any(|word| cause_text.contains(word)) {
+            return AppError::new(ErrorKind::Tls, message);
+        }
+        source = cause.source();
+    }
+    if error.is_builder() {
+        AppError::validation(message)
+    } else if error.is_decode() {
+        AppError::new(ErrorKind::Other, message)
+    } else {
+        AppError::new(ErrorKind::Network, message)
+    }
+}
+
+fn classify_sqlx(error: &sqlx::Error) -> AppError {
+    let message = error.to_string();
+    match error {
+        sqlx::Error::RowNotFound => AppError::not_found(message),
+        sqlx::Error::PoolTimedOut => AppError::new(ErrorKind::Timeout, message),
+        sqlx::Error::Database(e) => {
+            // SQLITE_BUSY and SQLITE_LOCKED (and their extended codes) clear once the other writer is done
+            let code = e.code().and_then(|code| code.parse::<i32>().ok()).unwrap_or(0);
+            AppError {
+                retryable: matches!(code & 0xff, 5 | 6),
+                ..AppError::new(ErrorKind::Database, message)
+            }
+        }
+        _ => AppError::new(ErrorKind::Database, message),
+    }
+}
+
+fn classify_io(error: &std::io::Error) -> AppError {
+    let message = error.to_string();
+    match error.kind() {
+        std::io::ErrorKind::NotFound => AppError::not_found(message),
+        std::io::ErrorKind::TimedOut => AppError::new(ErrorKind::Timeout, message),
+        _ => AppError::new(ErrorKind::Io, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_shape() {
+        let error = AppError::not_found("Request not found: 42");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "kind": "not_found",
+                "message": "Request not found: 42",
+                "details": null,
+                "retryable": false,
+            })
+        );
+        assert!(AppError::new(ErrorKind::Timeout, "slow").retryable);
+    }
+
+    #[test]
+    fn test_from_anyhow() {
+        // A typed error keeps its kind and details through anyhow, with the outer message
+        let conflict = AppError::new(ErrorKind::Conflict, "changed").with_details(serde_json::json!({"current": 1}));
+        let wrapped = anyhow::Error::from(conflict.clone()).context("Could not save 'Users'");
+        let error = AppError::from(wrapped);
+        assert_eq!(error.kind, ErrorKind::Conflict);
+        assert_eq!(error.message, "Could not save 'Users'");
+        assert_eq!(error.details, conflict.details);
+
+        let error = AppError::from(anyhow::Error::from(sqlx::Error::RowNotFound));
+        assert_eq!(error.kind, ErrorKind::NotFound);
+
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(AppError::from(anyhow::Error::from(io)).kind, ErrorKind::Io);
+
+        let error = AppError::from(anyhow::anyhow!("Something else"));
+        assert_eq!((error.kind, error.message.as_str()), (ErrorKind::Other, "Something else"));
+    }
+
+    #[tokio::test]
+    async fn test_from_reqwest() {
+        // Nothing listens on port 9 (discard) locally, so the connection is refused
+        let error = reqwest::get("http://127.0.0.1:9/").await.unwrap_err();
+        let error = AppError::from(error);
+        assert_eq!(error.kind, ErrorKind::Network);
+        assert!(error.retryable);
+
+        let error = reqwest::Client::new().get("not a url").send().await.unwrap_err();
+        assert_eq!(AppError::from(error).kind, ErrorKind::Validation);
+    }
+}
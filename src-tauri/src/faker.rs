@@ -0,0 +1,159 @@
+// 🎓 TEACHING: Faker variables
+// `{{$faker.name}}`, `{{$faker.email}}`, `{{$faker.address.city}}` ... generate realistic
+// test data on every send, which is handy for create-user style endpoints that reject
+// duplicates. Each placeholder gets a fresh value, even when the same one appears twice.
+// Names are case-insensitive and `first_name` / `firstName` both work. Unknown names are
+// left in place, so they show up as unresolved variables like any other typo.
+
+use chrono::{Duration, Utc};
+use fake::faker::{address, company, creditcard, currency, internet, job, lorem, name, phone_number};
+use fake::Fake;
+use rand::Rng;
+
+const PREFIX: &str = "{{$faker.";
+
+// Every supported name, for the UI's autocomplete
+pub const NAMES: [&str; 34] = [
+    "name",
+    "firstName",
+    "lastName",
+    "prefix",
+    "email",
+    "username",
+    "password",
+    "phone",
+    "company",
+    "catchPhrase",
+    "jobTitle",
+    "address.street",
+    "address.city",
+    "address.state",
+    "address.stateCode",
+    "address.zip",
+    "address.country",
+    "address.countryCode",
+    "address.latitude",
+    "address.longitude",
+    "internet.url",
+    "internet.domain",
+    "internet.ip",
+    "internet.ipv6",
+    "internet.userAgent",
+    "lorem.word",
+    "lorem.words",
+    "lorem.sentence",
+    "lorem.paragraph",
+    "uuid",
+    "number",
+    "boolean",
+    "date",
+    "creditCard",
+];
+
+// A fresh value for `$faker.<name>`, or None if we don't know the name
+pub fn generate(name: &str) -> Option<String> {
+    let mut rng = rand::thread_rng();
+    let key = name.trim().to_lowercase().replace('_', "");
+
+    let value = match key.as_str() {
+        "name" | "fullname" => name::en::Name().fake(),
+        "firstname" => name::en::FirstName().fake(),
+        "lastname" => name::en::LastName().fake(),
+        "prefix" => name::en::Title().fake(),
+        "email" | "internet.email" => internet::en::SafeEmail().fake(),
+        "username" | "internet.username" => internet::en::Username().fake(),
+        "password" | "internet.password" => internet::en::Password(12..20).fake(),
+        "phone" | "phonenumber" => phone_number::en::PhoneNumber().fake(),
+        "company" | "company.name" => company::en::CompanyName().fake(),
+        "catchphrase" | "company.catchphrase" => company::en::CatchPhrase().fake(),
+        "jobtitle" | "job.title" => job::en::Title().fake(),
+        "address.street" => format!(
+            "{} {}",
+            address::en::BuildingNumber().fake::<String>(),
+            address::en::StreetName().fake::<String>()
+        ),
+        "address.city" | "city" => address::en::CityName().fake(),
+        "address.state" | "state" => address::en::StateName().fake(),
+        "address.statecode" => address::en::StateAbbr().fake(),
+        "address.zip" | "address.zipcode" | "zip" => address::en::ZipCode().fake(),
+        "address.country" | "country" => address::en::CountryName().fake(),
+        "address.countrycode" => address::en::CountryCode().fake(),
+        "address.latitude" => address::en::Latitude().fake(),
+        "address.longitude" => address::en::Longitude().fake(),
+        "internet.url" | "url" => format!("https://{}", domain()),
+        "internet.domain" | "domain" => domain(),
+        "internet.ip" | "ip" => internet::en::IPv4().fake(),
+        "internet.ipv6" => internet::en::IPv6().fake(),
+        "internet.useragent" => internet::en::UserAgent().fake(),
+        "lorem.word" | "word" => lorem::en::Word().fake(),
+        "lorem.words" => lorem::en::Words(3..6).fake::<Vec<String>>().join(" "),
+        "lorem.sentence" | "sentence" => lorem::en::Sentence(4..10).fake(),
+        "lorem.paragraph" | "paragraph" => lorem::en::Paragraph(3..6).fake(),
+        "uuid" => uuid::Uuid::new_v4().to_string(),
+        "number" => rng.gen_range(0..1000).to_string(),
+        "boolean" => rng.gen_bool(0.5).to_string(),
+        // Some day in the past year
+        "date" => (Utc::now() - Duration::days(rng.gen_range(0..365))).format("%Y-%m-%d").to_string(),
+        "creditcard" => creditcard::en::CreditCardNumber().fake(),
+        "currency" | "currency.code" => currency::en::CurrencyCode().fake(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn domain() -> String {
+    format!(
+        "{}.{}",
+        lorem::en::Word().fake::<String>().to_lowercase(),
+        internet::en::DomainSuffix().fake::<String>()
+    )
+}
+
+// Replace every `{{$faker.<name>}}` in `text`
+pub fn replace_placeholders(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(PREFIX) {
+        let after = &rest[start + PREFIX.len()..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        match generate(&after[..end]) {
+            Some(value) => result.push_str(&value),
+            None => result.push_str(&rest[start..start + PREFIX.len() + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_name_generates() {
+        for name in NAMES {
+            let value = generate(name).unwrap_or_else(|| panic!("{} is not supported", name));
+            assert!(!value.is_empty(), "{} generated nothing", name);
+        }
+        assert!(generate("email").unwrap().contains('@'));
+        assert!(generate("FIRST_NAME").is_some());
+        assert!(generate("address.planet").is_none());
+    }
+
+    #[test]
+    fn test_replace_placeholders() {
+        let text = r#"{"id": "{{$faker.uuid}}", "other": "{{$faker.uuid}}", "x": "{{$faker.nope}}", "b": "{{base}}"}"#;
+        let replaced = replace_placeholders(text);
+        let json: serde_json::Value = serde_json::from_str(&replaced).unwrap();
+
+        assert_eq!(json["id"].as_str().unwrap().len(), 36);
+        assert_ne!(json["id"], json["other"]);
+        assert_eq!(json["x"], "{{$faker.nope}}");
+        assert_eq!(json["b"], "{{base}}");
+        assert_eq!(replace_placeholders("no placeholders {{$faker."), "no placeholders {{$faker.");
+    }
+}
@@ -0,0 +1,78 @@
+// 🎓 TEACHING: GraphQL over HTTP
+// A GraphQL request is a JSON POST of `{ "query", "variables", "operationName" }`.
+// We keep the three parts separate so the query editor stays plain text and the
+// variables stay a real JSON object, then assemble the payload on send.
+
+use anyhow::Result;
+use serde_json::Value;
+
+// 🎓 TEACHING: Parse the variables JSON (empty means "no variables")
+pub fn parse_variables(variables: Option<&str>) -> Result<Option<Value>> {
+    let Some(variables) = variables.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+
+    let value: Value =
+        serde_json::from_str(variables).map_err(|e| anyhow::anyhow!("GraphQL variables are not valid JSON: {}", e))?;
+    if !value.is_object() && !value.is_null() {
+        return Err(anyhow::anyhow!("GraphQL variables must be a JSON object"));
+    }
+    Ok(Some(value))
+}
+
+// Every string inside the variables, so `{{var}}` placeholders can be filled in place.
+// Interpolating strings one by one (instead of the raw JSON text) keeps quotes in values from breaking the JSON.
+pub fn string_values_mut(value: &mut Value) -> Vec<&mut String> {
+    match value {
+        Value::String(s) => vec![s],
+        Value::Array(items) => items.iter_mut().flat_map(string_values_mut).collect(),
+        Value::Object(map) => map.values_mut().flat_map(string_values_mut).collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn build_payload(query: &str, variables: Option<Value>, operation_name: Option<&str>) -> String {
+    let mut payload = serde_json::json!({ "query": query });
+    if let Some(variables) = variables {
+        payload["variables"] = variables;
+    }
+    if let Some(operation_name) = operation_name.filter(|name| !name.is_empty()) {
+        payload["operationName"] = Value::String(operation_name.to_string());
+    }
+    payload.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_payload() {
+        let variables = parse_variables(Some(r#"{"id": 1}"#)).unwrap();
+        let payload: Value =
+            serde_json::from_str(&build_payload("query User($id: ID!) { user(id: $id) { name } }", variables, Some("User")))
+                .unwrap();
+        assert_eq!(payload["variables"]["id"], 1);
+        assert_eq!(payload["operationName"], "User");
+
+        let payload: Value = serde_json::from_str(&build_payload("{ me { id } }", None, Some(""))).unwrap();
+        assert!(payload.get("variables").is_none());
+        assert!(payload.get("operationName").is_none());
+    }
+
+    #[test]
+    fn test_parse_variables_rejects_non_objects() {
+        assert!(parse_variables(Some("  ")).unwrap().is_none());
+        assert!(parse_variables(Some("[1, 2]")).is_err());
+        assert!(parse_variables(Some("{oops")).is_err());
+    }
+
+    #[test]
+    fn test_string_values_mut() {
+        let mut value = serde_json::json!({ "a": "{{x}}", "b": [1, "{{y}}"], "c": { "d": "z" } });
+        for s in string_values_mut(&mut value) {
+            *s = s.to_uppercase();
+        }
+        assert_eq!(value, serde_json::json!({ "a": "{{X}}", "b": [1, "{{Y}}"], "c": { "d": "Z" } }));
+    }
+}
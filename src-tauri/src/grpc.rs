@@ -0,0 +1,571 @@
+// 🎓 TEACHING: gRPC requests
+// gRPC calls are protobuf messages over HTTP/2. Since users bring their own services,
+// we can't generate Rust types ahead of time: instead we load the service descriptors
+// at runtime (from .proto files or the server's reflection API) and convert between
+// JSON and protobuf with `prost_reflect::DynamicMessage`.
+//
+// Saved gRPC requests live in the `requests` table with `body_type = "grpc"`:
+// `url` is the server address, `headers` is the request metadata, `body_str` is the
+// JSON message, and `grpc_config` holds the service/method and descriptor source.
+
+use anyhow::Result;
+use prost::Message;
+use prost_reflect::prost_types::{FileDescriptorProto, FileDescriptorSet};
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue, MetadataMap};
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::Status;
+use tonic_reflection::pb::v1alpha::server_reflection_client::ServerReflectionClient;
+use tonic_reflection::pb::v1alpha::server_reflection_request::MessageRequest;
+use tonic_reflection::pb::v1alpha::server_reflection_response::MessageResponse;
+use tonic_reflection::pb::v1alpha::ServerReflectionRequest;
+
+// Stored as JSON in the `requests.grpc_config` column
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GrpcConfig {
+    pub service: String, // Fully qualified, e.g. "helloworld.Greeter"
+    pub method: String,  // e.g. "SayHello"
+    #[serde(default)]
+    pub descriptor_source: DescriptorSource,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DescriptorSource {
+    #[serde(default = "default_source_kind")]
+    pub kind: String, // "reflection" (default) or "proto"
+    #[serde(default)]
+    pub proto_files: Vec<String>, // .proto files, or descriptor sets (.protoset / .pb) from `protoc -o`
+    #[serde(default)]
+    pub import_paths: Vec<String>, // Roots that imports are resolved against, like protoc's -I
+}
+
+fn default_source_kind() -> String {
+    "reflection".to_string()
+}
+
+impl Default for DescriptorSource {
+    fn default() -> Self {
+        Self {
+            kind: default_source_kind(),
+            proto_files: Vec::new(),
+            import_paths: Vec::new(),
+        }
+    }
+}
+
+// What the frontend sends to invoke a method
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GrpcRequest {
+    pub address: String, // "host:port", or with a grpc:// / grpcs:// scheme
+    pub config: GrpcConfig,
+    #[serde(default)]
+    pub body: String, // The request message as JSON
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    pub call_id: Option<String>, // Echoed in streamed message events
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GrpcService {
+    pub name: String,
+    pub methods: Vec<GrpcMethod>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GrpcMethod {
+    pub name: String,
+    pub input_type: String,
+    pub output_type: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+    pub request_template: serde_json::Value, // Every input field with its default value, to start editing from
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GrpcResponse {
+    pub status_code: i32, // 0 = OK, see https://grpc.github.io/grpc/core/md_doc_statuscodes.html
+    pub status_message: String,
+    pub metadata: HashMap<String, String>,
+    pub trailers: HashMap<String, String>,
+    pub messages: Vec<serde_json::Value>, // One for unary calls, any number for server streaming
+}
+
+// 🎓 TEACHING: `grpc://` and bare `host:port` mean plaintext HTTP/2, `grpcs://` means TLS
+pub fn normalize_address(address: &str) -> String {
+    let address = address.trim();
+    if let Some(rest) = address.strip_prefix("grpcs://") {
+        format!("https://{}", rest)
+    } else if let Some(rest) = address.strip_prefix("grpc://") {
+        format!("http://{}", rest)
+    } else if address.starts_with("http://") || address.starts_with("https://") {
+        address.to_string()
+    } else {
+        format!("http://{}", address)
+    }
+}
+
+pub async fn connect(address: &str) -> Result<Channel> {
+    let url = normalize_address(address);
+    let mut endpoint = Endpoint::from_shared(url.clone())?;
+    if url.starts_with("https://") {
+        endpoint = endpoint.tls_config(ClientTlsConfig::new().with_native_roots())?;
+    }
+    endpoint
+        .connect()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to connect to {}: {}", address, e))
+}
+
+// 🎓 TEACHING: Load service definitions from the configured source
+// Only reflection needs the server, so .proto files can be browsed while it's down.
+pub async fn load_descriptors(source: &DescriptorSource, address: &str) -> Result<DescriptorPool> {
+    match source.kind.as_str() {
+        "reflection" => reflect_descriptors(connect(address).await?).await,
+        "proto" => load_proto_files(&source.proto_files, &source.import_paths),
+        other => Err(anyhow::anyhow!("Unsupported descriptor source: {}", other)),
+    }
+}
+
+pub fn load_proto_files(files: &[String], import_paths: &[String]) -> Result<DescriptorPool> {
+    if files.is_empty() {
+        return Err(anyhow::anyhow!("No .proto files configured"));
+    }
+
+    let mut pool = DescriptorPool::new();
+    let (descriptor_sets, protos): (Vec<&String>, Vec<&String>) = files.iter().partition(|f| !f.ends_with(".proto"));
+    for path in descriptor_sets {
+        let bytes = std::fs::read(path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+        pool.add_file_descriptor_set(FileDescriptorSet::decode(bytes.as_slice())?)?;
+    }
+    if !protos.is_empty() {
+        pool.add_file_descriptor_set(compile_protos(&protos, import_paths)?)?;
+    }
+    Ok(pool)
+}
+
+// 🎓 TEACHING: .proto text is compiled in-process by `protox`, a protobuf compiler written in
+// Rust, into the same descriptor set `protoc --include_imports -o` would write. Nothing has to be
+// installed, and the well-known google/protobuf/*.proto imports are built in.
+fn compile_protos(files: &[&String], import_paths: &[String]) -> Result<FileDescriptorSet> {
+    // Without explicit import paths, each file's own directory is the root
+    let mut includes: Vec<String> = import_paths.to_vec();
+    if includes.is_empty() {
+        for file in files {
+            let dir = match std::path::Path::new(file.as_str()).parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.display().to_string(),
+                _ => ".".to_string(),
+            };
+            if !includes.contains(&dir) {
+                includes.push(dir);
+            }
+        }
+    }
+
+    protox::compile(files, includes).map_err(|e| anyhow::anyhow!("Failed to compile .proto files: {}", e))
+}
+
+// 🎓 TEACHING: Server reflection
+// Ask the server for its services, then for the file defining each one. Files can import
+// others, so keep asking for missing dependencies until the set is complete.
+async fn reflect_descriptors(channel: Channel) -> Result<DescriptorPool> {
+    let mut client = ServerReflectionClient::new(channel);
+
+    let services = match reflection_call(&mut client, MessageRequest::ListServices(String::new())).await? {
+        MessageResponse::ListServicesResponse(list) => list.service,
+        other => return Err(anyhow::anyhow!("Unexpected reflection response: {:?}", other)),
+    };
+
+    let mut files: HashMap<String, FileDescriptorProto> = HashMap::new();
+    for service in services.iter().filter(|s| s.name != "grpc.reflection.v1alpha.ServerReflection") {
+        let response = reflection_call(&mut client, MessageRequest::FileContainingSymbol(service.name.clone())).await?;
+        add_reflected_files(&mut files, response)?;
+    }
+
+    loop {
+        let missing: Vec<String> = files
+            .values()
+            .flat_map(|file| file.dependency.iter())
+            .filter(|dep| !files.contains_key(*dep))
+            .cloned()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if missing.is_empty() {
+            break;
+        }
+        for name in missing {
+            let response = reflection_call(&mut client, MessageRequest::FileByFilename(name.clone())).await?;
+            add_reflected_files(&mut files, response)?;
+            if !files.contains_key(&name) {
+                return Err(anyhow::anyhow!("Server reflection did not return {}", name));
+            }
+        }
+    }
+
+    Ok(DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: files.into_values().collect(),
+    })?)
+}
+
+async fn reflection_call(client: &mut ServerReflectionClient<Channel>, request: MessageRequest) -> Result<MessageResponse> {
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(request),
+    };
+    let mut responses = client
+        .server_reflection_info(tokio_stream::iter(vec![request]))
+        .await
+        .map_err(|status| anyhow::anyhow!("Server reflection failed: {}", status.message()))?
+        .into_inner();
+
+    let response = responses
+        .message()
+        .await?
+        .and_then(|r| r.message_response)
+        .ok_or_else(|| anyhow::anyhow!("Server reflection returned no response"))?;
+    match response {
+        MessageResponse::ErrorResponse(error) => {
+            Err(anyhow::anyhow!("Server reflection error {}: {}", error.error_code, error.error_message))
+        }
+        response => Ok(response),
+    }
+}
+
+fn add_reflected_files(files: &mut HashMap<String, FileDescriptorProto>, response: MessageResponse) -> Result<()> {
+    let MessageResponse::FileDescriptorResponse(response) = response else {
+        return Err(anyhow::anyhow!("Unexpected reflection response: {:?}", response));
+    };
+    for bytes in response.file_descriptor_proto {
+        let file = FileDescriptorProto::decode(bytes.as_slice())?;
+        files.insert(file.name().to_string(), file);
+    }
+    Ok(())
+}
+
+pub fn list_services(pool: &DescriptorPool) -> Vec<GrpcService> {
+    pool.services()
+        .map(|service| GrpcService {
+            name: service.full_name().to_string(),
+            methods: service
+                .methods()
+                .map(|method| GrpcMethod {
+                    name: method.name().to_string(),
+                    input_type: method.input().full_name().to_string(),
+                    output_type: method.output().full_name().to_string(),
+                    client_streaming: method.is_client_streaming(),
+                    server_streaming: method.is_server_streaming(),
+                    request_template: message_template(&method.input()),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn message_template(descriptor: &MessageDescriptor) -> serde_json::Value {
+    let options = prost_reflect::SerializeOptions::new().skip_default_fields(false);
+    let message = DynamicMessage::new(descriptor.clone());
+    message
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .unwrap_or(serde_json::Value::Null)
+}
+
+pub fn find_method(pool: &DescriptorPool, service: &str, method: &str) -> Result<MethodDescriptor> {
+    let service_descriptor = pool
+        .get_service_by_name(service)
+        .ok_or_else(|| anyhow::anyhow!("Service {} not found", service))?;
+    let found = service_descriptor.methods().find(|m| m.name() == method);
+    found.ok_or_else(|| anyhow::anyhow!("Method {} not found in {}", method, service))
+}
+
+pub fn message_from_json(descriptor: &MessageDescriptor, json: &str) -> Result<DynamicMessage> {
+    let json = if json.trim().is_empty() { "{}" } else { json };
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let message = DynamicMessage::deserialize(descriptor.clone(), &mut deserializer)
+        .map_err(|e| anyhow::anyhow!("Message does not match {}: {}", descriptor.full_name(), e))?;
+    deserializer.end()?;
+    Ok(message)
+}
+
+pub fn message_to_json(message: &DynamicMessage) -> serde_json::Value {
+    serde_json::to_value(message).unwrap_or(serde_json::Value::Null)
+}
+
+// 🎓 TEACHING: Invoke a unary or server-streaming method.
+// Streamed messages are passed to `on_message` as they arrive (for live display),
+// and also collected into the returned response.
+pub async fn invoke(
+    channel: Channel,
+    method: &MethodDescriptor,
+    body: &str,
+    metadata: &HashMap<String, String>,
+    mut on_message: impl FnMut(&serde_json::Value),
+) -> Result<GrpcResponse> {
+    if method.is_client_streaming() {
+        return Err(anyhow::anyhow!("Client streaming and bidirectional methods are not supported yet"));
+    }
+
+    let message = message_from_json(&method.input(), body)?;
+    let mut request = tonic::Request::new(message);
+    for (key, value) in metadata {
+        let key = AsciiMetadataKey::from_str(&key.to_lowercase())
+            .map_err(|_| anyhow::anyhow!("Invalid metadata key: {}", key))?;
+        let value = AsciiMetadataValue::from_str(value)
+            .map_err(|_| anyhow::anyhow!("Invalid metadata value for {}", key))?;
+        request.metadata_mut().insert(key, value);
+    }
+
+    let path = PathAndQuery::from_str(&format!("/{}/{}", method.parent_service().full_name(), method.name()))?;
+    let codec = DynamicCodec {
+        output: method.output(),
+    };
+    let mut client = tonic::client::Grpc::new(channel);
+    client
+        .ready()
+        .await
+        .map_err(|e| anyhow::anyhow!("gRPC channel not ready: {}", e))?;
+
+    if !method.is_server_streaming() {
+        return Ok(match client.unary(request, path, codec).await {
+            Ok(response) => {
+                let json = message_to_json(response.get_ref());
+                on_message(&json);
+                GrpcResponse {
+                    status_code: 0,
+                    status_message: String::new(),
+                    metadata: metadata_to_map(response.metadata()),
+                    trailers: HashMap::new(),
+                    messages: vec![json],
+                }
+            }
+            Err(status) => status_response(status, Vec::new()),
+        });
+    }
+
+    let response = match client.server_streaming(request, path, codec).await {
+        Ok(response) => response,
+        Err(status) => return Ok(status_response(status, Vec::new())),
+    };
+    let response_metadata = metadata_to_map(response.metadata());
+    let mut stream = response.into_inner();
+
+    let mut messages = Vec::new();
+    loop {
+        match stream.message().await {
+            Ok(Some(message)) => {
+                let json = message_to_json(&message);
+                on_message(&json);
+                messages.push(json);
+            }
+            Ok(None) => break,
+            Err(status) => return Ok(status_response(status, messages)),
+        }
+    }
+    let trailers = stream.trailers().await.ok().flatten().map(|t| metadata_to_map(&t)).unwrap_or_default();
+
+    Ok(GrpcResponse {
+        status_code: 0,
+        status_message: String::new(),
+        metadata: response_metadata,
+        trailers,
+        messages,
+    })
+}
+
+// A non-OK status is still a response worth showing, like an HTTP 500
+fn status_response(status: Status, messages: Vec<serde_json::Value>) -> GrpcResponse {
+    GrpcResponse {
+        status_code: status.code() as i32,
+        status_message: status.message().to_string(),
+        metadata: metadata_to_map(status.metadata()),
+        trailers: HashMap::new(),
+        messages,
+    }
+}
+
+fn metadata_to_map(metadata: &MetadataMap) -> HashMap<String, String> {
+    metadata
+        .clone()
+        .into_headers()
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_str().unwrap_or("").to_string()))
+        .collect()
+}
+
+// 🎓 TEACHING: tonic needs a codec to turn messages into bytes; this one works on
+// DynamicMessage, decoding responses with the method's output descriptor.
+#[derive(Clone)]
+struct DynamicCodec {
+    output: MessageDescriptor,
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        DynamicEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder(self.output.clone())
+    }
+}
+
+struct DynamicEncoder;
+
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: DynamicMessage, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        item.encode(dst).map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+struct DynamicDecoder(MessageDescriptor);
+
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<DynamicMessage>, Status> {
+        DynamicMessage::decode(self.0.clone(), src)
+            .map(Some)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, MethodDescriptorProto, ServiceDescriptorProto,
+    };
+
+    fn field(name: &str, number: i32, r#type: Type) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(r#type as i32),
+            json_name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    // helloworld.Greeter with a unary and a server-streaming method
+    fn greeter_pool() -> DescriptorPool {
+        let file = FileDescriptorProto {
+            name: Some("helloworld.proto".to_string()),
+            package: Some("helloworld".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![
+                DescriptorProto {
+                    name: Some("HelloRequest".to_string()),
+                    field: vec![field("name", 1, Type::String), field("times", 2, Type::Int32)],
+                    ..Default::default()
+                },
+                DescriptorProto {
+                    name: Some("HelloReply".to_string()),
+                    field: vec![field("message", 1, Type::String)],
+                    ..Default::default()
+                },
+            ],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Greeter".to_string()),
+                method: vec![
+                    MethodDescriptorProto {
+                        name: Some("SayHello".to_string()),
+                        input_type: Some(".helloworld.HelloRequest".to_string()),
+                        output_type: Some(".helloworld.HelloReply".to_string()),
+                        ..Default::default()
+                    },
+                    MethodDescriptorProto {
+                        name: Some("StreamHellos".to_string()),
+                        input_type: Some(".helloworld.HelloRequest".to_string()),
+                        output_type: Some(".helloworld.HelloReply".to_string()),
+                        server_streaming: Some(true),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: vec![file] }).unwrap()
+    }
+
+    #[test]
+    fn test_list_services() {
+        let services = list_services(&greeter_pool());
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].name, "helloworld.Greeter");
+
+        let stream = services[0].methods.iter().find(|m| m.name == "StreamHellos").unwrap();
+        assert!(stream.server_streaming);
+        assert!(!stream.client_streaming);
+        assert_eq!(stream.input_type, "helloworld.HelloRequest");
+        assert_eq!(stream.request_template, serde_json::json!({ "name": "", "times": 0 }));
+    }
+
+    #[test]
+    fn test_load_proto_files_without_protoc() {
+        let dir = std::env::temp_dir().join(format!("openrequest-protos-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let common = "syntax = \"proto3\";\npackage common;\nmessage Name { string value = 1; }\n";
+        std::fs::write(dir.join("common.proto"), common).unwrap();
+        std::fs::write(
+            dir.join("greeter.proto"),
+            r#"syntax = "proto3";
+package helloworld;
+import "common.proto";
+import "google/protobuf/timestamp.proto";
+message HelloRequest { common.Name name = 1; google.protobuf.Timestamp sent_at = 2; }
+message HelloReply { string message = 1; }
+service Greeter { rpc SayHello (HelloRequest) returns (HelloReply); }
+"#,
+        )
+        .unwrap();
+        let broken = "syntax = \"proto3\";\nmessage Broken { Missing field = 1; }\n";
+        std::fs::write(dir.join("broken.proto"), broken).unwrap();
+
+        let greeter = dir.join("greeter.proto").display().to_string();
+        let pool = load_proto_files(&[greeter], &[]).unwrap();
+        let method = find_method(&pool, "helloworld.Greeter", "SayHello").unwrap();
+        let json = r#"{"name": {"value": "Ada"}, "sentAt": "2024-01-01T00:00:00Z"}"#;
+        let message = message_from_json(&method.input(), json).unwrap();
+        assert_eq!(message_to_json(&message)["name"], serde_json::json!({ "value": "Ada" }));
+
+        let error = load_proto_files(&[dir.join("broken.proto").display().to_string()], &[]).unwrap_err();
+        assert!(error.to_string().contains("Missing"), "{}", error);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_json_message_roundtrip() {
+        let method = find_method(&greeter_pool(), "helloworld.Greeter", "SayHello").unwrap();
+        let message = message_from_json(&method.input(), r#"{"name": "Ada", "times": 3}"#).unwrap();
+
+        let decoded = DynamicMessage::decode(method.input(), message.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(message_to_json(&decoded), serde_json::json!({ "name": "Ada", "times": 3 }));
+
+        assert!(message_from_json(&method.input(), r#"{"nope": 1}"#).is_err());
+        assert!(find_method(&greeter_pool(), "helloworld.Greeter", "Missing").is_err());
+    }
+
+    #[test]
+    fn test_normalize_address() {
+        assert_eq!(normalize_address("localhost:50051"), "http://localhost:50051");
+        assert_eq!(normalize_address("grpc://localhost:50051"), "http://localhost:50051");
+        assert_eq!(normalize_address("grpcs://api.example.com"), "https://api.example.com");
+        assert_eq!(normalize_address("https://api.example.com"), "https://api.example.com");
+    }
+}
@@ -0,0 +1,94 @@
+// 🎓 TEACHING: History export
+// The sends of saved requests (the samples behind request analytics) can be exported as
+// "csv", one row per send for a spreadsheet, or "json", a list of objects for scripts.
+// Only what the history keeps is exported: timings, statuses, sizes and trace ids, never bodies.
+
+use crate::database::HistoryEntry;
+use anyhow::Result;
+
+const CSV_HEADERS: [&str; 11] = [
+    "sent_at",
+    "request_id",
+    "request_name",
+    "method",
+    "url",
+    "collection_id",
+    "status",
+    "duration_ms",
+    "response_size",
+    "trace_id",
+    "correlation_id",
+];
+
+pub fn export(entries: &[HistoryEntry], format: &str) -> Result<String> {
+    match format {
+        "csv" => csv(entries),
+        "json" => Ok(serde_json::to_string_pretty(entries)?),
+        other => Err(anyhow::anyhow!("Unsupported history format: {}", other)),
+    }
+}
+
+// A send with no response has an empty status
+fn csv(entries: &[HistoryEntry]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_HEADERS)?;
+    for entry in entries {
+        writer.write_record([
+            entry.sent_at.to_rfc3339(),
+            entry.request_id.clone(),
+            entry.request_name.clone(),
+            entry.method.clone(),
+            entry.url.clone(),
+            entry.collection_id.clone(),
+            entry.status.map(|status| status.to_string()).unwrap_or_default(),
+            entry.duration_ms.to_string(),
+            entry.response_size.to_string(),
+            entry.trace_id.clone().unwrap_or_default(),
+            entry.correlation_id.clone().unwrap_or_default(),
+        ])?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn entry(status: Option<u16>) -> HistoryEntry {
+        HistoryEntry {
+            sent_at: Utc.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap(),
+            request_id: "r1".to_string(),
+            request_name: "List users, paged".to_string(),
+            method: "GET".to_string(),
+            url: "{{base}}/users?page=1".to_string(),
+            collection_id: "c1".to_string(),
+            status,
+            duration_ms: 42,
+            response_size: 512,
+            trace_id: None,
+            correlation_id: Some("abc".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_csv() {
+        let text = export(&[entry(Some(200)), entry(None)], "csv").unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], CSV_HEADERS.join(","));
+        // The comma in the name gets the field quoted
+        assert_eq!(
+            lines[1],
+            r#"2026-03-01T09:30:00+00:00,r1,"List users, paged",GET,{{base}}/users?page=1,c1,200,42,512,,abc"#
+        );
+        assert!(lines[2].contains(",c1,,42,"));
+    }
+
+    #[test]
+    fn test_json_and_unknown_format() {
+        let text = export(&[entry(Some(404))], "json").unwrap();
+        let parsed: Vec<HistoryEntry> = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed, vec![entry(Some(404))]);
+        assert!(export(&[], "xml").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+// 🎓 TEACHING: HTTP caching rules
+// The "http" cache mode behaves like a browser instead of keeping every response for a
+// fixed time: the response's Cache-Control / Expires headers decide whether and how long
+// it's kept, and once it's stale it's revalidated with If-None-Match / If-Modified-Since,
+// where a 304 Not Modified means the stored copy is still good.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+fn header<'a>(headers: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value.trim()).ok().map(|date| date.with_timezone(&Utc))
+}
+
+// Directives are case-insensitive; a few carry a value (`max-age=60`)
+fn cache_control(headers: &HashMap<String, String>) -> HashMap<String, Option<String>> {
+    header(headers, "cache-control")
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| match directive.split_once('=') {
+            Some((name, value)) => (name.trim().to_lowercase(), Some(value.trim().trim_matches('"').to_string())),
+            None => (directive.to_lowercase(), None),
+        })
+        .collect()
+}
+
+// When a response stops being fresh, or None if it must not be stored at all. A response
+// that's stale straight away (`no-cache`, or only a validator) is still worth keeping,
+// since the next send can revalidate it instead of downloading it again.
+pub fn expires_at(status: u16, headers: &HashMap<String, String>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if status != 200 {
+        return None;
+    }
+    let directives = cache_control(headers);
+    if directives.contains_key("no-store") {
+        return None;
+    }
+    let has_validator = !validators(headers).is_empty();
+    if directives.contains_key("no-cache") {
+        return has_validator.then_some(now);
+    }
+
+    if let Some(max_age) = directives.get("max-age").cloned().flatten() {
+        // An invalid max-age means the response is already stale
+        let seconds = max_age.parse::<i64>().unwrap_or(0).max(0);
+        return Some(now + Duration::seconds(seconds));
+    }
+    if let Some(expires) = header(headers, "expires") {
+        // Measured against the server's clock, so a skewed local clock doesn't matter
+        let lifetime = match (http_date(expires), header(headers, "date").and_then(http_date)) {
+            (Some(expires), Some(date)) => expires - date,
+            (Some(expires), None) => expires - now,
+            (None, _) => Duration::zero(), // e.g. `Expires: 0`
+        };
+        return Some(now + lifetime.max(Duration::zero()));
+    }
+    has_validator.then_some(now)
+}
+
+// Conditional request headers that let the server answer 304 for an unchanged response
+pub fn validators(headers: &HashMap<String, String>) -> Vec<(&'static str, String)> {
+    let mut validators = Vec::new();
+    if let Some(etag) = header(headers, "etag") {
+        validators.push(("If-None-Match", etag.to_string()));
+    }
+    if let Some(last_modified) = header(headers, "last-modified") {
+        validators.push(("If-Modified-Since", last_modified.to_string()));
+    }
+    validators
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_expires_at() {
+        let now = Utc::now();
+        assert_eq!(
+            expires_at(200, &headers(&[("cache-control", "public, max-age=60")]), now),
+            Some(now + Duration::seconds(60))
+        );
+        assert_eq!(expires_at(200, &headers(&[("cache-control", "no-store, max-age=60")]), now), None);
+        assert_eq!(expires_at(404, &headers(&[("cache-control", "max-age=60")]), now), None);
+
+        // Expires counts from the server's Date header
+        let dated = headers(&[
+            ("date", "Wed, 21 Oct 2015 07:28:00 GMT"),
+            ("expires", "Wed, 21 Oct 2015 08:28:00 GMT"),
+        ]);
+        assert_eq!(expires_at(200, &dated, now), Some(now + Duration::hours(1)));
+        assert_eq!(expires_at(200, &headers(&[("expires", "0")]), now), Some(now));
+
+        // Stored stale, to be revalidated, only when there's something to revalidate with
+        assert_eq!(expires_at(200, &headers(&[("cache-control", "no-cache"), ("etag", "\"v1\"")]), now), Some(now));
+        assert_eq!(expires_at(200, &headers(&[("cache-control", "no-cache")]), now), None);
+        assert_eq!(expires_at(200, &headers(&[("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT")]), now), Some(now));
+        assert_eq!(expires_at(200, &headers(&[]), now), None);
+    }
+
+    #[test]
+    fn test_validators() {
+        let stored = headers(&[("ETag", "\"v1\""), ("Last-Modified", "Wed, 21 Oct 2015 07:28:00 GMT")]);
+        assert_eq!(
+            validators(&stored),
+            vec![
+                ("If-None-Match", "\"v1\"".to_string()),
+                ("If-Modified-Since", "Wed, 21 Oct 2015 07:28:00 GMT".to_string())
+            ]
+        );
+        assert!(validators(&headers(&[("content-type", "text/plain")])).is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+// 🎓 TEACHING: VS Code REST Client (.http / .rest) files
+// A plain-text format where requests are written the way they go over the wire:
+//
+//   @base_url = https://api.example.com
+//
+//   ### Create user
+//   # @name createUser
+//   POST {{base_url}}/users?notify=true HTTP/1.1
+//   Content-Type: application/json
+//
+//   { "name": "Ada" }
+//
+// `###` separates requests, `#` and `//` start comments, and `@name = value` lines define
+// file variables. The `{{variable}}` syntax is the same as ours, so URLs and bodies are
+// imported as-is and the file variables become an environment.
+
+use crate::importer_exporter::{CollectionImport, JsonCollection, JsonEnvironment, JsonRequest, JsonVariable, CURRENT_SCHEMA_VERSION};
+use crate::params;
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+const METHODS: [&str; 9] = ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE", "CONNECT"];
+
+pub fn parse(text: &str, collection_name: &str) -> Result<CollectionImport> {
+    let mut variables = Vec::new();
+    let mut requests = Vec::new();
+
+    for block in split_blocks(text) {
+        if let Some(request) = parse_block(&block, &mut variables)? {
+            requests.push(request);
+        }
+    }
+    if requests.is_empty() {
+        return Err(anyhow::anyhow!("No requests found in the file"));
+    }
+
+    let environments = if variables.is_empty() {
+        Vec::new()
+    } else {
+        vec![JsonEnvironment {
+            name: format!("{} variables", collection_name),
+            variables,
+        }]
+    };
+    Ok(CollectionImport {
+        collection: Some(JsonCollection {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            name: collection_name.to_string(),
+            description: None,
+            docs: None,
+            auth_type: None,
+            auth_data: None,
+            requests,
+        }),
+        environments,
+    })
+}
+
+struct Block {
+    title: Option<String>, // Text after the `###` separator
+    lines: Vec<String>,
+}
+
+fn split_blocks(text: &str) -> Vec<Block> {
+    let mut blocks = vec![Block {
+        title: None,
+        lines: Vec::new(),
+    }];
+    for line in text.lines() {
+        if let Some(title) = line.trim_start().strip_prefix("###") {
+            let title = title.trim();
+            blocks.push(Block {
+                title: (!title.is_empty()).then(|| title.to_string()),
+                lines: Vec::new(),
+            });
+        } else if let Some(block) = blocks.last_mut() {
+            block.lines.push(line.to_string());
+        }
+    }
+    blocks
+}
+
+fn is_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('#') || line.starts_with("//")
+}
+
+// `@name = value`, outside of any request
+fn parse_variable(line: &str) -> Option<JsonVariable> {
+    let (key, value) = line.trim().strip_prefix('@')?.split_once('=')?;
+    let key = key.trim();
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(JsonVariable {
+        key: key.to_string(),
+        value: value.trim().to_string(),
+        is_secret: false,
+    })
+}
+
+// `# @name createUser` / `// @name createUser`
+fn name_directive(line: &str) -> Option<String> {
+    let line = line.trim_start();
+    let rest = line.strip_prefix('#').or_else(|| line.strip_prefix("//"))?;
+    let name = rest.trim().strip_prefix("@name")?.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn parse_block(block: &Block, variables: &mut Vec<JsonVariable>) -> Result<Option<JsonRequest>> {
+    let mut lines = block.lines.iter().map(String::as_str).peekable();
+    let mut name = block.title.clone();
+
+    // Before the request line: blank lines, comments, `@name` directives and file variables
+    let request_line = loop {
+        let Some(line) = lines.next() else {
+            return Ok(None); // Only variables or comments in this block
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if is_comment(line) {
+            if let Some(directive) = name_directive(line) {
+                name = Some(directive);
+            }
+            continue;
+        }
+        if let Some(variable) = parse_variable(line) {
+            variables.retain(|v| v.key != variable.key); // Later definitions win
+            variables.push(variable);
+            continue;
+        }
+        break line.trim();
+    };
+
+    let (method, url) = parse_request_line(request_line);
+
+    // Long query strings may continue on following lines that start with `?` or `&`
+    let mut url = url;
+    while let Some(next) = lines.peek().map(|l| l.trim()).filter(|l| l.starts_with('?') || l.starts_with('&')) {
+        url.push_str(next);
+        lines.next();
+    }
+    let url = strip_http_version(&url);
+
+    let mut headers = BTreeMap::new();
+    for line in lines.by_ref() {
+        if line.trim().is_empty() {
+            break;
+        }
+        if is_comment(line) {
+            continue;
+        }
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Invalid header line in `{}`: {}", request_line, line))?;
+        headers.insert(key.trim().to_string(), value.trim().to_string());
+    }
+
+    // Everything else is the body; trailing blank lines before the next `###` aren't part of it
+    let body_lines: Vec<&str> = lines.collect();
+    let body = body_lines.join("\n").trim_end().to_string();
+
+    let (url, query_params) = params::split_query(&url);
+    let mut request = JsonRequest::new(name.unwrap_or_else(|| format!("{} {}", method, url)), method, url);
+    request.params = serde_json::to_string(&query_params)?;
+    if !body.is_empty() {
+        let content_type = headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+            .map(|(_, v)| v.to_lowercase())
+            .unwrap_or_default();
+        request.body_type = body_type_for(&content_type).to_string();
+        request.body_str = Some(body);
+    }
+    request.headers = serde_json::to_string(&headers)?;
+    Ok(Some(request))
+}
+
+// `GET url`, `GET url HTTP/1.1`, or just `url` (GET is implied)
+fn parse_request_line(line: &str) -> (String, String) {
+    let line = line.trim();
+    match line.split_once(char::is_whitespace) {
+        Some((first, rest)) if METHODS.contains(&first.to_uppercase().as_str()) => (first.to_uppercase(), rest.trim().to_string()),
+        _ => ("GET".to_string(), line.to_string()),
+    }
+}
+
+// The version comes last, after any query continuation lines
+fn strip_http_version(url: &str) -> String {
+    match url.rsplit_once(char::is_whitespace) {
+        Some((url, version)) if version.starts_with("HTTP/") => url.trim().to_string(),
+        _ => url.to_string(),
+    }
+}
+
+pub fn body_type_for(content_type: &str) -> &'static str {
+    if content_type.contains("json") {
+        "json"
+    } else if content_type.contains("x-www-form-urlencoded") {
+        "x-www-form-urlencoded"
+    } else if content_type.contains("multipart/form-data") {
+        "form-data"
+    } else if content_type.contains("xml") {
+        "xml"
+    } else if content_type.contains("html") {
+        "html"
+    } else {
+        "text"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"@base_url = https://api.example.com
+@token = abc123
+
+### List users
+GET {{base_url}}/users?page=1
+    &limit=10 HTTP/1.1
+Accept: application/json
+
+###
+# @name createUser
+POST {{base_url}}/users
+Authorization: Bearer {{token}}
+Content-Type: application/json
+
+{
+  "name": "Ada"
+}
+
+
+### Just a URL
+https://example.com/health
+"#;
+
+    #[test]
+    fn test_parse_http_file() {
+        let import = parse(FILE, "Users").unwrap();
+        let collection = import.collection.unwrap();
+        assert_eq!(collection.requests.len(), 3);
+
+        let list = &collection.requests[0];
+        assert_eq!(list.name, "List users");
+        assert_eq!(list.method, "GET");
+        assert_eq!(list.url, "{{base_url}}/users");
+        assert_eq!(
+            list.params,
+            r#"[{"key":"page","value":"1","enabled":true},{"key":"limit","value":"10","enabled":true}]"#
+        );
+        assert_eq!(list.headers, r#"{"Accept":"application/json"}"#);
+        assert_eq!(list.body_type, "none");
+
+        let create = &collection.requests[1];
+        assert_eq!(create.name, "createUser");
+        assert_eq!(create.method, "POST");
+        assert_eq!(create.body_type, "json");
+        assert_eq!(create.body_str.as_deref(), Some("{\n  \"name\": \"Ada\"\n}"));
+
+        assert_eq!(collection.requests[2].method, "GET");
+        assert_eq!(collection.requests[2].url, "https://example.com/health");
+
+        let variables: Vec<(&str, &str)> =
+            import.environments[0].variables.iter().map(|v| (v.key.as_str(), v.value.as_str())).collect();
+        assert_eq!(variables, vec![("base_url", "https://api.example.com"), ("token", "abc123")]);
+    }
+
+    #[test]
+    fn test_rejects_files_without_requests() {
+        assert!(parse("@a = 1\n# nothing here\n", "Empty").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+// 🎓 TEACHING: A tiny local HTTP server
+// Several features need to *receive* HTTP instead of sending it (webhook capture, mocks).
+// This wraps hyper so each of them only has to turn an incoming request into a response.
+
+use anyhow::Result;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct IncomingRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+    pub remote_addr: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutgoingResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl OutgoingResponse {
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: HashMap::new(),
+            body: body.into(),
+        }
+    }
+}
+
+pub type Handler =
+    Arc<dyn Fn(IncomingRequest) -> Pin<Box<dyn Future<Output = OutgoingResponse> + Send>> + Send + Sync>;
+
+// Dropping the handle (or calling `stop`) shuts the server down
+pub struct ServerHandle {
+    pub port: u16,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl ServerHandle {
+    pub fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+// 🎓 TEACHING: Bind to localhost unless asked otherwise; port 0 picks a free port
+pub async fn start(port: u16, bind_all: bool, handler: Handler) -> Result<ServerHandle> {
+    let host = if bind_all { "0.0.0.0" } else { "127.0.0.1" };
+    let listener = TcpListener::bind((host, port))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to listen on {}:{}: {}", host, port, e))?;
+    let port = listener.local_addr()?.port();
+    let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_rx => break,
+                accepted = listener.accept() => {
+                    let Ok((stream, remote_addr)) = accepted else { continue };
+                    let handler = handler.clone();
+                    tokio::spawn(async move {
+                        let service = service_fn(move |request| handle(request, remote_addr, handler.clone()));
+                        let _ = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+                    });
+                }
+            }
+        }
+    });
+
+    Ok(ServerHandle {
+        port,
+        shutdown: Some(shutdown_tx),
+    })
+}
+
+async fn handle(
+    request: hyper::Request<Incoming>,
+    remote_addr: SocketAddr,
+    handler: Handler,
+) -> Result<hyper::Response<Full<Bytes>>, Infallible> {
+    let (parts, body) = request.into_parts();
+    let body = body.collect().await.map(|b| b.to_bytes().to_vec()).unwrap_or_default();
+
+    let mut headers = HashMap::new();
+    for (key, value) in parts.headers.iter() {
+        headers.insert(key.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string());
+    }
+
+    let response = handler(IncomingRequest {
+        method: parts.method.to_string(),
+        path: parts.uri.path().to_string(),
+        query: parts.uri.query().map(str::to_string),
+        headers,
+        body,
+        remote_addr: remote_addr.to_string(),
+    })
+    .await;
+
+    let mut builder = hyper::Response::builder().status(response.status);
+    for (key, value) in &response.headers {
+        builder = builder.header(key, value);
+    }
+    Ok(builder.body(Full::new(Bytes::from(response.body))).unwrap_or_else(|e| {
+        let mut error = hyper::Response::new(Full::new(Bytes::from(format!("Invalid response: {}", e))));
+        *error.status_mut() = hyper::StatusCode::INTERNAL_SERVER_ERROR;
+        error
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_server_round_trip() {
+        let handler: Handler = Arc::new(|request: IncomingRequest| {
+            Box::pin(async move {
+                let mut response = OutgoingResponse::new(201, format!("{} {}", request.method, request.path));
+                response.headers.insert("X-Query".to_string(), request.query.unwrap_or_default());
+                response
+            })
+        });
+        let server = start(0, false, handler).await.unwrap();
+
+        let response = reqwest::Client::new()
+            .post(format!("http://127.0.0.1:{}/hooks/github?x=1", server.port))
+            .body("payload")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 201);
+        assert_eq!(response.headers()["x-query"], "x=1");
+        assert_eq!(response.text().await.unwrap(), "POST /hooks/github");
+
+        server.stop();
+    }
+}
@@ -2,7 +2,14 @@
 // We define separate structs for the JSON format to decouple it from our internal database schema.
 // This means if we change our database in the future, our import/export format can remain stable.
 
+use crate::database::Request;
+use crate::openapi;
+use crate::params::QueryParam;
+use crate::redact::Redactor;
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
 
 // The structure for a request within the JSON file.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -12,8 +19,24 @@ pub struct JsonRequest {
     pub url: String,
     pub params: String,
     pub headers: String,
+    #[serde(default = "empty_json_object")]
+    pub path_params: String,
     pub body_type: String,
     pub body_str: Option<String>,
+    #[serde(default)]
+    pub graphql_variables: Option<String>,
+    #[serde(default)]
+    pub graphql_operation_name: Option<String>,
+    #[serde(default)]
+    pub grpc_config: Option<String>,
+    #[serde(default)]
+    pub captures: Option<String>,
+    #[serde(default)]
+    pub scripts: Option<String>,
+    #[serde(default)]
+    pub settings: Option<String>,
+    #[serde(default)]
+    pub docs: Option<String>, // Markdown
     pub auth_type: Option<String>,
     pub auth_data: Option<String>,
 }
@@ -22,7 +45,817 @@ pub struct JsonRequest {
 // and a list of all its requests.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JsonCollection {
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32, // Format version; see `migrate_collection`
     pub name: String,
     pub description: Option<String>,
+    #[serde(default)]
+    pub docs: Option<String>, // Markdown
+    #[serde(default)]
+    pub auth_type: Option<String>, // Auth inherited by the collection's requests
+    #[serde(default)]
+    pub auth_data: Option<String>,
     pub requests: Vec<JsonRequest>,
 }
+
+// 🎓 TEACHING: Mask credentials and secret values in an export.
+// Auth data keeps its JSON shape so the importer can see what to fill in.
+pub fn redact_collection(collection: JsonCollection, redactor: &Redactor) -> JsonCollection {
+    let redact_auth = |auth_data: Option<String>| auth_data.map(|data| redactor.redact_auth_data(&data));
+
+    JsonCollection {
+        docs: collection.docs.map(|docs| redactor.redact(&docs)),
+        auth_data: redact_auth(collection.auth_data),
+        requests: collection
+            .requests
+            .into_iter()
+            .map(|req| JsonRequest {
+                url: redactor.redact(&req.url),
+                params: redactor.redact(&req.params),
+                headers: redactor.redact_headers_json(&req.headers),
+                path_params: redactor.redact(&req.path_params),
+                body_str: req.body_str.map(|body| redactor.redact(&body)),
+                graphql_variables: req.graphql_variables.map(|vars| redactor.redact(&vars)),
+                docs: req.docs.map(|docs| redactor.redact(&docs)),
+                auth_data: redact_auth(req.auth_data),
+                ..req
+            })
+            .collect(),
+        ..collection
+    }
+}
+
+// Older exports have no path params, so fall back to an empty JSON object
+fn empty_json_object() -> String {
+    "{}".to_string()
+}
+
+// 🎓 TEACHING: Versioned export format
+// Every export is stamped with CURRENT_SCHEMA_VERSION. On import, older files run through
+// the migrations below one version at a time until they match the current structs, so a
+// backup made years ago still imports. When the format changes: bump the version and add
+// a migration from the previous one - never edit an existing migration.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+// Files from before versioning have no `schema_version` at all
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+type Migration = fn(&mut Value) -> Result<()>;
+
+// MIGRATIONS[n] upgrades version n + 1 to version n + 2
+const MIGRATIONS: [Migration; 1] = [migrate_v1_to_v2];
+
+// v1 stored query params as a flat `{ "key": "value" }` object and had no path params
+fn migrate_v1_to_v2(collection: &mut Value) -> Result<()> {
+    let requests = collection
+        .get_mut("requests")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| anyhow::anyhow!("Export has no requests list"))?;
+
+    for request in requests.iter_mut().filter_map(Value::as_object_mut) {
+        if let Some(Value::String(params)) = request.get("params") {
+            if let Ok(Value::Object(map)) = serde_json::from_str::<Value>(params) {
+                let list: Vec<QueryParam> = map
+                    .into_iter()
+                    .map(|(key, value)| QueryParam {
+                        key,
+                        value: value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()),
+                        enabled: true,
+                    })
+                    .collect();
+                request.insert("params".to_string(), json!(serde_json::to_string(&list)?));
+            }
+        }
+        request
+            .entry("path_params")
+            .or_insert_with(|| json!(empty_json_object()));
+    }
+    Ok(())
+}
+
+pub fn migrate_collection(mut collection: Value) -> Result<Value> {
+    let version = match collection.get("schema_version") {
+        None => legacy_schema_version(),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .ok_or_else(|| anyhow::anyhow!("Invalid schema_version: {}", version))?,
+    };
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(anyhow::anyhow!(
+            "This export uses schema version {}, but this app only understands up to {}. Please update the app.",
+            version,
+            CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+        migration(&mut collection).map_err(|e| anyhow::anyhow!("Upgrading export from version {}: {}", from + 1, e))?;
+    }
+    collection["schema_version"] = json!(CURRENT_SCHEMA_VERSION);
+    Ok(collection)
+}
+
+// Parse an export of any supported version into the current structs
+pub fn parse_collection(json_str: &str) -> Result<JsonCollection> {
+    let value: Value = serde_json::from_str(json_str)?;
+    if !value.is_object() {
+        return Err(anyhow::anyhow!("Export must be a JSON object"));
+    }
+    Ok(serde_json::from_value(migrate_collection(value)?)?)
+}
+
+impl From<Request> for JsonRequest {
+    fn from(req: Request) -> Self {
+        JsonRequest {
+            name: req.name,
+            method: req.method,
+            url: req.url,
+            params: req.params,
+            headers: req.headers,
+            path_params: req.path_params,
+            body_type: req.body_type,
+            body_str: req.body_str,
+            graphql_variables: req.graphql_variables,
+            graphql_operation_name: req.graphql_operation_name,
+            grpc_config: req.grpc_config,
+            captures: req.captures,
+            scripts: req.scripts,
+            settings: req.settings,
+            docs: req.docs,
+            auth_type: req.auth_type,
+            auth_data: req.auth_data,
+        }
+    }
+}
+
+impl JsonRequest {
+    // A plain request with no params, headers, body or auth, for importers to fill in
+    pub fn new(name: impl Into<String>, method: impl Into<String>, url: impl Into<String>) -> Self {
+        JsonRequest {
+            name: name.into(),
+            method: method.into(),
+            url: url.into(),
+            params: "[]".to_string(),
+            headers: empty_json_object(),
+            path_params: empty_json_object(),
+            body_type: "none".to_string(),
+            body_str: None,
+            graphql_variables: None,
+            graphql_operation_name: None,
+            grpc_config: None,
+            captures: None,
+            scripts: None,
+            settings: None,
+            docs: None,
+            auth_type: None,
+            auth_data: None,
+        }
+    }
+
+    // Copy the exported fields onto a saved request, keeping its id, collection and timestamps
+    pub fn apply_to(self, request: Request) -> Request {
+        Request {
+            name: self.name,
+            method: self.method,
+            url: self.url,
+            params: self.params,
+            headers: self.headers,
+            path_params: self.path_params,
+            body_type: self.body_type,
+            body_str: self.body_str,
+            graphql_variables: self.graphql_variables,
+            graphql_operation_name: self.graphql_operation_name,
+            grpc_config: self.grpc_config,
+            captures: self.captures,
+            scripts: self.scripts,
+            settings: self.settings,
+            docs: self.docs,
+            auth_type: self.auth_type,
+            auth_data: self.auth_data,
+            ..request
+        }
+    }
+
+    // Names of the fields that differ, for merge reports
+    pub fn changed_fields(&self, other: &JsonRequest) -> Vec<&'static str> {
+        let fields: [(&'static str, bool); 17] = [
+            ("name", self.name == other.name),
+            ("method", self.method == other.method),
+            ("url", self.url == other.url),
+            ("params", self.params == other.params),
+            ("headers", self.headers == other.headers),
+            ("path_params", self.path_params == other.path_params),
+            ("body_type", self.body_type == other.body_type),
+            ("body_str", self.body_str == other.body_str),
+            ("graphql_variables", self.graphql_variables == other.graphql_variables),
+            ("graphql_operation_name", self.graphql_operation_name == other.graphql_operation_name),
+            ("grpc_config", self.grpc_config == other.grpc_config),
+            ("captures", self.captures == other.captures),
+            ("scripts", self.scripts == other.scripts),
+            ("settings", self.settings == other.settings),
+            ("docs", self.docs == other.docs),
+            ("auth_type", self.auth_type == other.auth_type),
+            ("auth_data", self.auth_data == other.auth_data),
+        ];
+        fields.into_iter().filter(|(_, same)| !same).map(|(name, _)| name).collect()
+    }
+}
+
+// 🎓 TEACHING: Merge mode
+// Importing into an existing collection instead of creating a new one. An incoming request
+// conflicts with a saved one when they share a name, or failing that the same method + URL.
+// Each conflict is resolved by an action: "overwrite" the saved request, "skip" the incoming
+// one, or "duplicate" (keep both). Requests without a conflict are simply added.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MergeOptions {
+    pub default_action: Option<String>, // For conflicts without their own choice; defaults to "skip"
+    #[serde(default)]
+    pub actions: HashMap<usize, String>, // Per conflict, keyed by the request's index in the file
+    #[serde(default)]
+    pub dry_run: bool, // Only report what would happen
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MergeEntry {
+    pub index: usize, // Position in the imported file
+    pub name: String,
+    pub action: String, // "added", "overwritten", "unchanged", "skipped", or "duplicated"
+    pub existing_request_id: Option<String>, // The saved request it conflicts with
+    pub request_id: Option<String>,          // The request written (None for skips and dry runs)
+    pub changed_fields: Vec<String>,         // Fields that differ from the saved request
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergeReport {
+    pub collection_id: String,
+    pub dry_run: bool,
+    pub entries: Vec<MergeEntry>,
+}
+
+// Work out what to do with each incoming request, without touching the database.
+// `existing` pairs saved request ids with their exported form.
+pub fn plan_merge(existing: &[(String, JsonRequest)], incoming: &[JsonRequest], options: &MergeOptions) -> Result<Vec<MergeEntry>> {
+    let default_action = options.default_action.as_deref().unwrap_or("skip");
+    let mut claimed = std::collections::HashSet::new();
+    let mut entries = Vec::new();
+
+    for (index, request) in incoming.iter().enumerate() {
+        // Each saved request can only be matched once, so two incoming copies don't both overwrite it
+        let unclaimed = |(id, _): &&(String, JsonRequest)| !claimed.contains(id);
+        let conflict = existing
+            .iter()
+            .filter(unclaimed)
+            .find(|(_, saved)| saved.name == request.name)
+            .or_else(|| {
+                existing
+                    .iter()
+                    .filter(unclaimed)
+                    .find(|(_, saved)| saved.method.eq_ignore_ascii_case(&request.method) && saved.url == request.url)
+            });
+
+        let Some((existing_id, saved)) = conflict else {
+            entries.push(MergeEntry {
+                index,
+                name: request.name.clone(),
+                action: "added".to_string(),
+                existing_request_id: None,
+                request_id: None,
+                changed_fields: Vec::new(),
+            });
+            continue;
+        };
+        claimed.insert(existing_id.clone());
+
+        let changed_fields: Vec<String> = saved.changed_fields(request).into_iter().map(str::to_string).collect();
+        let action = match options.actions.get(&index).map(String::as_str).unwrap_or(default_action) {
+            "overwrite" if changed_fields.is_empty() => "unchanged",
+            "overwrite" => "overwritten",
+            "skip" => "skipped",
+            "duplicate" => "duplicated",
+            other => return Err(anyhow::anyhow!("Unknown merge action for request {}: {}", index, other)),
+        };
+        entries.push(MergeEntry {
+            index,
+            name: request.name.clone(),
+            action: action.to_string(),
+            existing_request_id: Some(existing_id.clone()),
+            request_id: None,
+            changed_fields,
+        });
+    }
+    Ok(entries)
+}
+
+// An environment to create next to an imported collection
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JsonEnvironment {
+    pub name: String,
+    pub variables: Vec<JsonVariable>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JsonVariable {
+    pub key: String,
+    pub value: String,
+    #[serde(default)]
+    pub is_secret: bool,
+}
+
+// What a third-party import produces. Environment exports carry no collection.
+#[derive(Debug, Clone)]
+pub struct CollectionImport {
+    pub collection: Option<JsonCollection>,
+    pub environments: Vec<JsonEnvironment>,
+}
+
+// 🎓 TEACHING: Environment export
+// A portable file for sharing dev/staging configs. Secret values are blanked unless the
+// user opts in, but the variables stay in the file (still marked secret) so whoever
+// imports it can see what they need to fill in.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnvironmentExport {
+    pub name: String,
+    #[serde(default = "empty_json_object")]
+    pub host_overrides: String,
+    #[serde(default = "empty_json_object")]
+    pub default_headers: String,
+    #[serde(default)]
+    pub secrets_included: bool,
+    pub variables: Vec<JsonVariable>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VariableImportEntry {
+    pub key: String,
+    pub action: String, // "added", "overwritten", "unchanged", "skipped", or "kept" (blank secret)
+    pub variable_id: Option<String>, // The saved variable it collides with, or the one created
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnvironmentImportReport {
+    pub environment_id: String,
+    pub entries: Vec<VariableImportEntry>,
+}
+
+// Work out what to do with each incoming variable. Variables collide by key; `on_conflict`
+// is "overwrite" or "skip". A blanked secret never overwrites a saved value.
+// `existing` pairs saved variable ids with their exported form.
+pub fn plan_environment_import(
+    existing: &[(String, JsonVariable)],
+    incoming: &[JsonVariable],
+    on_conflict: &str,
+) -> Result<Vec<VariableImportEntry>> {
+    if !matches!(on_conflict, "overwrite" | "skip") {
+        return Err(anyhow::anyhow!("Unknown conflict action: {}", on_conflict));
+    }
+
+    let mut entries: Vec<VariableImportEntry> = Vec::new();
+    for variable in incoming {
+        // A key repeated within the file: the later definition wins
+        entries.retain(|entry| entry.key != variable.key);
+
+        let Some((existing_id, saved)) = existing.iter().find(|(_, saved)| saved.key == variable.key) else {
+            entries.push(VariableImportEntry {
+                key: variable.key.clone(),
+                action: "added".to_string(),
+                variable_id: None,
+            });
+            continue;
+        };
+
+        let action = if variable.is_secret && variable.value.is_empty() {
+            "kept"
+        } else if on_conflict == "skip" {
+            "skipped"
+        } else if saved.value == variable.value && saved.is_secret == variable.is_secret {
+            "unchanged"
+        } else {
+            "overwritten"
+        };
+        entries.push(VariableImportEntry {
+            key: variable.key.clone(),
+            action: action.to_string(),
+            variable_id: Some(existing_id.clone()),
+        });
+    }
+    Ok(entries)
+}
+
+// 🎓 TEACHING: OpenAPI/Swagger import
+// One request per operation, all under `{{base_url}}`. Each server URL becomes an
+// environment defining `base_url`, and security schemes become auth settings whose
+// credentials are left as `{{variables}}` for the user to fill in.
+pub fn collection_from_openapi(spec_text: &str) -> Result<CollectionImport> {
+    let spec = openapi::parse_spec(spec_text)?;
+    let title = openapi::title(&spec).unwrap_or_else(|| "Imported API".to_string());
+    let schemes = openapi::security_schemes(&spec);
+
+    // Credential variables used by any auth we generate, so environments can define them
+    let mut auth_variables: BTreeMap<String, bool> = BTreeMap::new();
+    let mut auth_for = |security: Option<&Value>| -> Option<(String, String)> {
+        let scheme_name = openapi::first_security_scheme(security)?;
+        let Some(scheme) = scheme_name.and_then(|name| schemes.get(&name)) else {
+            return Some(("none".to_string(), "{}".to_string()));
+        };
+        let auth = auth_from_scheme(scheme)?;
+        auth_variables.extend(auth.variables.iter().map(|(key, is_secret)| (key.to_string(), *is_secret)));
+        Some((auth.auth_type.to_string(), auth.auth_data.to_string()))
+    };
+
+    let collection_auth = auth_for(spec.get("security"));
+    let mut requests = Vec::new();
+    for operation in openapi::operations(&spec) {
+        let mut request = request_from_operation(&spec, &operation)?;
+        // Operations that override the spec-wide security get their own auth
+        if operation.security.is_some() && operation.security.as_ref() != spec.get("security") {
+            if let Some((auth_type, auth_data)) = auth_for(operation.security.as_ref()) {
+                request.auth_type = Some(auth_type);
+                request.auth_data = Some(auth_data);
+            }
+        }
+        requests.push(request);
+    }
+
+    let mut server_urls = openapi::server_urls(&spec);
+    if server_urls.is_empty() {
+        server_urls.push("http://localhost".to_string());
+    }
+    let environments = server_urls
+        .into_iter()
+        .map(|url| {
+            let mut variables = vec![JsonVariable {
+                key: "base_url".to_string(),
+                value: url.clone(),
+                is_secret: false,
+            }];
+            variables.extend(auth_variables.iter().map(|(key, is_secret)| JsonVariable {
+                key: key.clone(),
+                value: String::new(),
+                is_secret: *is_secret,
+            }));
+            JsonEnvironment {
+                name: format!("{} - {}", title, url),
+                variables,
+            }
+        })
+        .collect();
+
+    let (auth_type, auth_data) = collection_auth.unzip();
+    Ok(CollectionImport {
+        collection: Some(JsonCollection {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            name: title,
+            description: spec.pointer("/info/description").and_then(Value::as_str).map(str::to_string),
+            docs: None,
+            auth_type,
+            auth_data,
+            requests,
+        }),
+        environments,
+    })
+}
+
+fn request_from_operation(spec: &Value, operation: &openapi::Operation) -> Result<JsonRequest> {
+    let mut path_params = HashMap::new();
+    let mut query_params = Vec::new();
+    let mut headers = HashMap::new();
+    for parameter in &operation.parameters {
+        let Some(name) = parameter.get("name").and_then(Value::as_str) else { continue };
+        let value = parameter_example(spec, parameter);
+        match parameter.get("in").and_then(Value::as_str) {
+            Some("path") => {
+                path_params.insert(name.to_string(), value);
+            }
+            Some("query") => query_params.push(QueryParam {
+                key: name.to_string(),
+                value,
+                // Optional params are listed but not sent until the user turns them on
+                enabled: parameter.get("required").and_then(Value::as_bool).unwrap_or(false),
+            }),
+            Some("header") => {
+                headers.insert(name.to_string(), value);
+            }
+            _ => {}
+        }
+    }
+
+    let (body_type, body_str) = match &operation.request_body {
+        Some(body) => {
+            headers.insert("Content-Type".to_string(), body.content_type.clone());
+            let example = body
+                .example
+                .clone()
+                .or_else(|| body.schema.as_ref().map(|schema| openapi::example_from_schema(spec, schema, true)))
+                .unwrap_or(Value::Null);
+            let (body_type, body_str) = body_from_example(&body.content_type, &example)?;
+            (body_type.to_string(), Some(body_str))
+        }
+        None => ("none".to_string(), None),
+    };
+
+    Ok(JsonRequest {
+        name: operation
+            .summary
+            .clone()
+            .or_else(|| operation.operation_id.clone())
+            .unwrap_or_else(|| format!("{} {}", operation.method, operation.path)),
+        method: operation.method.clone(),
+        url: format!("{{{{base_url}}}}{}", operation.path),
+        params: serde_json::to_string(&query_params)?,
+        headers: serde_json::to_string(&headers)?,
+        path_params: serde_json::to_string(&path_params)?,
+        body_type,
+        body_str,
+        graphql_variables: None,
+        graphql_operation_name: None,
+        grpc_config: None,
+        captures: None,
+        scripts: None,
+        settings: None,
+        docs: operation.description.clone(),
+        auth_type: None, // Inherit the collection's auth
+        auth_data: None,
+    })
+}
+
+// Parameters carry their example on themselves, in their schema, or (Swagger 2) inline
+fn parameter_example(spec: &Value, parameter: &Value) -> String {
+    let value = parameter
+        .get("example")
+        .or_else(|| parameter.get("x-example"))
+        .cloned()
+        .or_else(|| parameter.get("schema").map(|schema| openapi::example_from_schema(spec, schema, true)))
+        .or_else(|| parameter.get("default").cloned())
+        .unwrap_or(Value::Null);
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text,
+        other => other.to_string(),
+    }
+}
+
+// Map a media type onto the body types the request editor knows
+fn body_from_example(content_type: &str, example: &Value) -> Result<(&'static str, String)> {
+    let content_type = content_type.to_lowercase();
+    if content_type.contains("json") {
+        return Ok(("json", serde_json::to_string_pretty(example)?));
+    }
+    if content_type.contains("x-www-form-urlencoded") {
+        let mut form = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(fields) = example.as_object() {
+            for (key, value) in fields {
+                form.append_pair(key, &value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()));
+            }
+        }
+        return Ok(("x-www-form-urlencoded", form.finish()));
+    }
+
+    let text = match example {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => serde_json::to_string_pretty(other)?,
+    };
+    let body_type = if content_type.contains("multipart/form-data") {
+        "form-data"
+    } else if content_type.contains("xml") {
+        "xml"
+    } else if content_type.contains("html") {
+        "html"
+    } else {
+        "text"
+    };
+    Ok((body_type, text))
+}
+
+// 🎓 TEACHING: Map a security scheme to one of our auth types.
+// Unknown schemes (mutual TLS, ...) are skipped.
+struct SchemeAuth {
+    auth_type: &'static str,
+    auth_data: Value,
+    variables: Vec<(&'static str, bool)>, // Credential variables it refers to, and whether they are secret
+}
+
+fn auth_from_scheme(scheme: &Value) -> Option<SchemeAuth> {
+    let kind = scheme.get("type").and_then(Value::as_str)?;
+    let http_scheme = scheme.get("scheme").and_then(Value::as_str).unwrap_or("").to_lowercase();
+
+    match (kind, http_scheme.as_str()) {
+        ("basic", _) | ("http", "basic") => Some(SchemeAuth {
+            auth_type: "basic",
+            auth_data: json!({ "username": "{{username}}", "password": "{{password}}" }),
+            variables: vec![("username", false), ("password", true)],
+        }),
+        ("http", "bearer") => Some(SchemeAuth {
+            auth_type: "bearer",
+            auth_data: json!({ "token": "{{bearer_token}}" }),
+            variables: vec![("bearer_token", true)],
+        }),
+        ("http", "digest") => Some(SchemeAuth {
+            auth_type: "digest",
+            auth_data: json!({ "username": "{{username}}", "password": "{{password}}" }),
+            variables: vec![("username", false), ("password", true)],
+        }),
+        ("apiKey", _) => {
+            let name = scheme.get("name").and_then(Value::as_str)?;
+            let auth_data = match scheme.get("in").and_then(Value::as_str) {
+                Some("query") => json!({ "key": name, "value": "{{api_key}}", "in": "query" }),
+                Some("cookie") => json!({ "key": "Cookie", "value": format!("{}={{{{api_key}}}}", name), "in": "header" }),
+                _ => json!({ "key": name, "value": "{{api_key}}", "in": "header" }),
+            };
+            Some(SchemeAuth {
+                auth_type: "api-key",
+                auth_data,
+                variables: vec![("api_key", true)],
+            })
+        }
+        ("oauth2", _) | ("openIdConnect", _) => Some(SchemeAuth {
+            auth_type: "oauth2",
+            auth_data: json!({ "access_token": "{{access_token}}" }),
+            variables: vec![("access_token", true)],
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_merge() {
+        let existing = vec![
+            ("r1".to_string(), JsonRequest::new("List users", "GET", "{{base_url}}/users")),
+            ("r2".to_string(), JsonRequest::new("Old name", "POST", "{{base_url}}/users")),
+            ("r3".to_string(), JsonRequest::new("Health", "GET", "{{base_url}}/health")),
+        ];
+        let mut renamed = JsonRequest::new("Create user", "POST", "{{base_url}}/users");
+        renamed.body_type = "json".to_string();
+        let incoming = vec![
+            JsonRequest::new("List users", "GET", "{{base_url}}/users?page=1"),
+            renamed,
+            JsonRequest::new("Health", "GET", "{{base_url}}/health"),
+            JsonRequest::new("Delete user", "DELETE", "{{base_url}}/users/:id"),
+        ];
+        let mut options = MergeOptions {
+            default_action: Some("overwrite".to_string()),
+            ..Default::default()
+        };
+        options.actions.insert(1, "duplicate".to_string());
+
+        let entries = plan_merge(&existing, &incoming, &options).unwrap();
+        let summary: Vec<(&str, Option<&str>)> =
+            entries.iter().map(|e| (e.action.as_str(), e.existing_request_id.as_deref())).collect();
+        assert_eq!(
+            summary,
+            vec![("overwritten", Some("r1")), ("duplicated", Some("r2")), ("unchanged", Some("r3")), ("added", None)]
+        );
+        assert_eq!(entries[0].changed_fields, vec!["url"]);
+        assert_eq!(entries[1].changed_fields, vec!["name", "body_type"]);
+
+        options.actions.insert(0, "replace".to_string());
+        assert!(plan_merge(&existing, &incoming, &options).is_err());
+    }
+
+    #[test]
+    fn test_plan_environment_import() {
+        let variable = |key: &str, value: &str, is_secret: bool| JsonVariable {
+            key: key.to_string(),
+            value: value.to_string(),
+            is_secret,
+        };
+        let existing = vec![
+            ("v1".to_string(), variable("base_url", "http://localhost", false)),
+            ("v2".to_string(), variable("token", "s3cret", true)),
+            ("v3".to_string(), variable("region", "eu", false)),
+        ];
+        let incoming = vec![
+            variable("base_url", "https://staging.example.com", false),
+            variable("token", "", true),
+            variable("region", "eu", false),
+            variable("timeout", "10", false),
+            variable("timeout", "30", false),
+        ];
+
+        let actions = |on_conflict: &str| -> Vec<(String, String)> {
+            plan_environment_import(&existing, &incoming, on_conflict)
+                .unwrap()
+                .into_iter()
+                .map(|entry| (entry.key, entry.action))
+                .collect()
+        };
+        let pairs = |list: &[(&str, &str)]| -> Vec<(String, String)> {
+            list.iter().map(|(k, a)| (k.to_string(), a.to_string())).collect()
+        };
+        assert_eq!(
+            actions("overwrite"),
+            pairs(&[("base_url", "overwritten"), ("token", "kept"), ("region", "unchanged"), ("timeout", "added")])
+        );
+        assert_eq!(
+            actions("skip"),
+            pairs(&[("base_url", "skipped"), ("token", "kept"), ("region", "skipped"), ("timeout", "added")])
+        );
+        assert!(plan_environment_import(&existing, &incoming, "rename").is_err());
+    }
+
+    #[test]
+    fn test_parse_collection_migrates_v1() {
+        let v1 = r#"{
+            "name": "Legacy",
+            "description": null,
+            "requests": [{
+                "name": "Search", "method": "GET", "url": "https://example.com/search",
+                "params": "{\"q\": \"rust\"}", "headers": "{}",
+                "body_type": "none", "body_str": null, "auth_type": null, "auth_data": null
+            }]
+        }"#;
+        let collection = parse_collection(v1).unwrap();
+        assert_eq!(collection.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(collection.requests[0].params, r#"[{"key":"q","value":"rust","enabled":true}]"#);
+        assert_eq!(collection.requests[0].path_params, "{}");
+
+        // Current exports pass through untouched
+        let current = serde_json::to_string(&collection).unwrap();
+        assert_eq!(parse_collection(&current).unwrap().requests[0].params, collection.requests[0].params);
+
+        let future = r#"{"schema_version": 99, "name": "Future", "description": null, "requests": []}"#;
+        assert!(parse_collection(future).unwrap_err().to_string().contains("update the app"));
+    }
+
+    const SPEC: &str = r##"
+openapi: 3.0.0
+info:
+  title: Petstore
+servers:
+  - url: https://petstore.example.com/v1
+  - url: http://localhost:8080/v1
+security:
+  - ApiKeyAuth: []
+paths:
+  /pets/{petId}:
+    get:
+      summary: Get a pet
+      parameters:
+        - { name: petId, in: path, required: true, schema: { type: integer, example: 7 } }
+        - { name: fields, in: query, schema: { type: string } }
+        - { name: X-Trace, in: header, example: abc }
+      responses:
+        '200': { description: OK }
+  /pets:
+    post:
+      operationId: createPet
+      security:
+        - BearerAuth: []
+      requestBody:
+        content:
+          application/json:
+            schema:
+              type: object
+              properties:
+                name: { type: string, example: Rex }
+      responses:
+        '201': { description: Created }
+  /health:
+    get:
+      security: []
+      responses:
+        '200': { description: OK }
+components:
+  securitySchemes:
+    ApiKeyAuth: { type: apiKey, in: header, name: X-API-Key }
+    BearerAuth: { type: http, scheme: bearer }
+"##;
+
+    #[test]
+    fn test_collection_from_openapi() {
+        let import = collection_from_openapi(SPEC).unwrap();
+        let collection = import.collection.as_ref().unwrap();
+        assert_eq!(collection.name, "Petstore");
+        assert_eq!(collection.auth_type.as_deref(), Some("api-key"));
+        assert!(collection.auth_data.as_deref().unwrap().contains("X-API-Key"));
+        assert_eq!(collection.requests.len(), 3);
+
+        let get = collection.requests.iter().find(|r| r.name == "Get a pet").unwrap();
+        assert_eq!(get.url, "{{base_url}}/pets/{petId}");
+        assert_eq!(get.path_params, r#"{"petId":"7"}"#);
+        assert_eq!(get.params, r#"[{"key":"fields","value":"string","enabled":false}]"#);
+        assert_eq!(get.headers, r#"{"X-Trace":"abc"}"#);
+        assert!(get.auth_type.is_none());
+
+        let create = collection.requests.iter().find(|r| r.name == "createPet").unwrap();
+        assert_eq!(create.body_type, "json");
+        assert_eq!(serde_json::from_str::<Value>(create.body_str.as_deref().unwrap()).unwrap(), json!({ "name": "Rex" }));
+        assert_eq!(create.auth_type.as_deref(), Some("bearer"));
+
+        let health = collection.requests.iter().find(|r| r.url.ends_with("/health")).unwrap();
+        assert_eq!(health.auth_type.as_deref(), Some("none"));
+
+        assert_eq!(import.environments.len(), 2);
+        let keys: Vec<&str> = import.environments[0].variables.iter().map(|v| v.key.as_str()).collect();
+        assert_eq!(keys, vec!["base_url", "api_key", "bearer_token"]);
+        assert_eq!(import.environments[1].variables[0].value, "http://localhost:8080/v1");
+    }
+}
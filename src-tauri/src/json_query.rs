@@ -0,0 +1,822 @@
+// 🎓 TEACHING: Querying response bodies
+// A big JSON response is slow to filter in the UI, and sending all of it to the renderer just
+// to show a few values wastes memory. So the filtering happens here, over one of the responses
+// kept for diffing (see response_diff), and only what matched goes back.
+//
+// Two languages are understood, told apart by the first character:
+//
+//   JSONPath ($...)  $.store.book[0].title, $..author, $.items[*].id, $.items[-2:],
+//                    $['odd key'], $.items[?(@.price < 10 && @.tags)]
+//   jq subset        .store.book[0].title, .items[].id, .items[] | select(.price < 10) | .name,
+//                    .items | length, .user | keys
+//
+// jq follows jq's rules where they differ: `.missing` gives null rather than nothing, and
+// `select(.flag)` keeps values where the flag is there and not null or false.
+// Every match carries its JSONPath (`$.items[3].name`), except values `length` and `keys`
+// made up, which aren't anywhere in the body.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+
+// A query matching more than this only sends back the first ones (and marks the result truncated)
+pub const MAX_MATCHES: usize = 1000;
+pub const MAX_RESULT_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct QueryMatch {
+    pub path: Option<String>,
+    pub value: Value,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct QueryResult {
+    pub total: usize, // Everything that matched, including what was cut off
+    pub matches: Vec<QueryMatch>,
+    pub truncated: bool,
+}
+
+pub fn query(body: &str, expression: &str) -> Result<QueryResult> {
+    let json: Value = serde_json::from_str(body).map_err(|e| anyhow!("The response body is not JSON: {}", e))?;
+    let expression = expression.trim();
+    let nodes = if expression.starts_with('$') {
+        let segments = Parser::new(expression, Dialect::JsonPath).json_path()?;
+        apply_segments(vec![Node::root(&json)], &segments, &json)
+    } else {
+        let stages = Parser::new(expression, Dialect::Jq).jq()?;
+        stages
+            .iter()
+            .try_fold(vec![Node::root(&json)], |nodes, stage| apply_stage(nodes, stage, &json))?
+    };
+
+    let total = nodes.len();
+    let mut matches = Vec::new();
+    let mut bytes = 0;
+    for node in nodes.into_iter().take(MAX_MATCHES) {
+        bytes += serde_json::to_vec(node.value.as_ref()).map(|json| json.len()).unwrap_or(0);
+        // The first match always goes back, however big
+        if bytes > MAX_RESULT_BYTES && !matches.is_empty() {
+            break;
+        }
+        matches.push(QueryMatch { path: node.path, value: node.value.into_owned() });
+    }
+    Ok(QueryResult { total, truncated: matches.len() < total, matches })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dialect {
+    JsonPath,
+    Jq,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Name(String),
+    Field(String), // jq's `.name`: null when missing
+    Index(i64),    // Negative counts from the end
+    Wildcard,
+    Slice(Option<i64>, Option<i64>, i64),
+    Filter(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    descendant: bool, // `..`: this node and everything below it
+    selectors: Vec<Selector>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Query {
+    relative: bool, // `@` (or jq's `.`) rather than `$`
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Literal(Value),
+    Query(Query),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, Op, Operand),
+    Exists(Query), // JSONPath `?@.tags`
+    Truthy(Query), // jq `select(.tags)`
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stage {
+    Path(Vec<Segment>),
+    Length,
+    Keys,
+    Select(Expr),
+}
+
+struct Parser<'s> {
+    text: &'s str,
+    chars: Vec<char>,
+    pos: usize,
+    dialect: Dialect,
+}
+
+impl<'s> Parser<'s> {
+    fn new(text: &'s str, dialect: Dialect) -> Self {
+        Parser { text, chars: text.chars().collect(), pos: 0, dialect }
+    }
+
+    fn error(&self, message: &str) -> anyhow::Error {
+        let language = match self.dialect {
+            Dialect::JsonPath => "JSONPath",
+            Dialect::Jq => "jq",
+        };
+        anyhow!("{} at position {} of the {} expression {}", message, self.pos + 1, language, self.text)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let found = s.chars().enumerate().all(|(i, c)| self.peek_at(i) == Some(c));
+        if found {
+            self.pos += s.chars().count();
+        }
+        found
+    }
+
+    // A keyword like `and`, not the start of a longer name
+    fn eat_word(&mut self, word: &str) -> bool {
+        let len = word.chars().count();
+        let boundary = !self.peek_at(len).is_some_and(is_name_char);
+        boundary && self.eat_str(word)
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", c)))
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let start = self.pos;
+        if !self.peek().is_some_and(|c| is_name_char(c) && !c.is_ascii_digit()) {
+            return Err(self.error("Expected a name"));
+        }
+        while self.peek().is_some_and(is_name_char) {
+            self.pos += 1;
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let quote = self.peek().filter(|c| *c == '\'' || *c == '"').ok_or_else(|| self.error("Expected a string"))?;
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated string")),
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('u') => {
+                            let hex: String = self.chars.iter().skip(self.pos + 1).take(4).collect();
+                            let code = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                            self.pos += 4;
+                            code.ok_or_else(|| self.error("Invalid \\u escape"))?
+                        }
+                        Some(c) => c,
+                        None => return Err(self.error("Unterminated string")),
+                    };
+                    text.push(escaped);
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn int(&mut self) -> Result<Option<i64>> {
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Ok(None);
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits.parse().map(Some).map_err(|_| self.error("Expected a number"))
+    }
+
+    fn literal(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('\'') | Some('"') => Ok(Value::String(self.string()?)),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c)) {
+                    self.pos += 1;
+                }
+                let number: String = self.chars[start..self.pos].iter().collect();
+                serde_json::from_str(&number).map_err(|_| self.error("Invalid number"))
+            }
+            _ if self.eat_word("true") => Ok(Value::Bool(true)),
+            _ if self.eat_word("false") => Ok(Value::Bool(false)),
+            _ if self.eat_word("null") => Ok(Value::Null),
+            _ => Err(self.error("Expected a value")),
+        }
+    }
+
+    // ---- JSONPath ----
+
+    fn json_path(mut self) -> Result<Vec<Segment>> {
+        self.expect('$')?;
+        let segments = self.segments()?;
+        self.skip_ws();
+        if !self.at_end() {
+            return Err(self.error("Unexpected character"));
+        }
+        Ok(segments)
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>> {
+        let mut segments = Vec::new();
+        loop {
+            let descendant = self.eat_str("..");
+            let selectors = if descendant || self.eat('.') {
+                if self.eat('*') {
+                    vec![Selector::Wildcard]
+                } else if descendant && self.peek() == Some('[') {
+                    self.bracket()?
+                } else {
+                    vec![Selector::Name(self.name()?)]
+                }
+            } else if self.peek() == Some('[') {
+                self.bracket()?
+            } else {
+                return Ok(segments);
+            };
+            segments.push(Segment { descendant, selectors });
+        }
+    }
+
+    // `[...]` with one or more comma-separated selectors
+    fn bracket(&mut self) -> Result<Vec<Selector>> {
+        self.expect('[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_ws();
+            let selector = match self.peek() {
+                Some('\'') | Some('"') => Selector::Name(self.string()?),
+                Some('*') => {
+                    self.pos += 1;
+                    Selector::Wildcard
+                }
+                Some('?') => {
+                    self.pos += 1;
+                    self.skip_ws();
+                    Selector::Filter(Box::new(self.or()?))
+                }
+                _ => self.index_or_slice()?,
+            };
+            selectors.push(selector);
+            self.skip_ws();
+            if self.eat(']') {
+                return Ok(selectors);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn index_or_slice(&mut self) -> Result<Selector> {
+        let start = self.int()?;
+        self.skip_ws();
+        if !self.eat(':') {
+            return start.map(Selector::Index).ok_or_else(|| self.error("Expected an index, name or filter"));
+        }
+        self.skip_ws();
+        let end = self.int()?;
+        self.skip_ws();
+        let step = if self.eat(':') {
+            self.skip_ws();
+            self.int()?.unwrap_or(1)
+        } else {
+            1
+        };
+        Ok(Selector::Slice(start, end, step))
+    }
+
+    // ---- Filter expressions, shared by JSONPath `?` and jq `select` ----
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut terms = vec![self.and()?];
+        loop {
+            self.skip_ws();
+            let found = match self.dialect {
+                Dialect::JsonPath => self.eat_str("||"),
+                Dialect::Jq => self.eat_word("or"),
+            };
+            if !found {
+                break;
+            }
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Expr::Or(terms) })
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut terms = vec![self.unary()?];
+        loop {
+            self.skip_ws();
+            let found = match self.dialect {
+                Dialect::JsonPath => self.eat_str("&&"),
+                Dialect::Jq => self.eat_word("and"),
+            };
+            if !found {
+                break;
+            }
+            terms.push(self.unary()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Expr::And(terms) })
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        self.skip_ws();
+        if self.dialect == Dialect::JsonPath && self.peek() == Some('!') && self.peek_at(1) != Some('=') {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat('(') {
+            let expr = self.or()?;
+            self.skip_ws();
+            self.expect(')')?;
+            return Ok(expr);
+        }
+        let left = self.operand()?;
+        self.skip_ws();
+        let op = if self.eat_str("==") {
+            Op::Eq
+        } else if self.eat_str("!=") {
+            Op::Ne
+        } else if self.eat_str("<=") {
+            Op::Le
+        } else if self.eat_str(">=") {
+            Op::Ge
+        } else if self.eat('<') {
+            Op::Lt
+        } else if self.eat('>') {
+            Op::Gt
+        } else {
+            return match (left, self.dialect) {
+                (Operand::Query(query), Dialect::JsonPath) => Ok(Expr::Exists(query)),
+                (Operand::Query(query), Dialect::Jq) => Ok(Expr::Truthy(query)),
+                (Operand::Literal(_), _) => Err(self.error("Expected a comparison")),
+            };
+        };
+        self.skip_ws();
+        Ok(Expr::Compare(left, op, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        match (self.peek(), self.dialect) {
+            (Some('@'), Dialect::JsonPath) => {
+                self.pos += 1;
+                Ok(Operand::Query(Query { relative: true, segments: self.segments()? }))
+            }
+            (Some('$'), Dialect::JsonPath) => {
+                self.pos += 1;
+                Ok(Operand::Query(Query { relative: false, segments: self.segments()? }))
+            }
+            (Some('.'), Dialect::Jq) => Ok(Operand::Query(Query { relative: true, segments: self.jq_path()? })),
+            _ => Ok(Operand::Literal(self.literal()?)),
+        }
+    }
+
+    // ---- jq ----
+
+    fn jq(mut self) -> Result<Vec<Stage>> {
+        let mut stages = Vec::new();
+        loop {
+            self.skip_ws();
+            let stage = if self.peek() == Some('.') {
+                Stage::Path(self.jq_path()?)
+            } else {
+                match self.name()?.as_str() {
+                    "length" => Stage::Length,
+                    "keys" => Stage::Keys,
+                    "select" => {
+                        self.skip_ws();
+                        self.expect('(')?;
+                        let expr = self.or()?;
+                        self.skip_ws();
+                        self.expect(')')?;
+                        Stage::Select(expr)
+                    }
+                    other => return Err(self.error(&format!("Unsupported jq filter '{}'", other))),
+                }
+            };
+            stages.push(stage);
+            self.skip_ws();
+            if self.at_end() {
+                return Ok(stages);
+            }
+            self.expect('|')?;
+        }
+    }
+
+    // `.`, `.a.b`, `.a[0]`, `.items[]`, `.["odd key"]`, `.[2:4]`
+    fn jq_path(&mut self) -> Result<Vec<Segment>> {
+        self.expect('.')?;
+        let mut segments = Vec::new();
+        let segment = |selector| Segment { descendant: false, selectors: vec![selector] };
+        if self.peek().is_some_and(|c| is_name_char(c) && !c.is_ascii_digit()) {
+            segments.push(segment(Selector::Field(self.name()?)));
+        }
+        loop {
+            self.eat('?'); // jq's "no error if it can't be indexed", which is how this always behaves
+            if self.eat('[') {
+                self.skip_ws();
+                let selector = match self.peek() {
+                    Some(']') => Selector::Wildcard,
+                    Some('"') => Selector::Field(self.string()?),
+                    _ => self.index_or_slice()?,
+                };
+                self.skip_ws();
+                self.expect(']')?;
+                segments.push(segment(selector));
+            } else if self.peek() == Some('.') && self.peek_at(1).is_some_and(|c| is_name_char(c) || c == '[') {
+                self.pos += 1;
+                if self.peek() != Some('[') {
+                    segments.push(segment(Selector::Field(self.name()?)));
+                }
+            } else {
+                return Ok(segments);
+            }
+        }
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || !c.is_ascii()
+}
+
+// Names that can be written `.name`; the rest need `["odd key"]`
+fn is_plain_name(name: &str) -> bool {
+    name.chars().all(is_name_char) && name.starts_with(|c: char| !c.is_ascii_digit())
+}
+
+// ---- Evaluation ----
+
+struct Node<'a> {
+    path: Option<String>, // None for values made up by `length` and `keys`
+    value: Cow<'a, Value>,
+}
+
+impl<'a> Node<'a> {
+    fn root(value: &'a Value) -> Self {
+        Node { path: Some("$".to_string()), value: Cow::Borrowed(value) }
+    }
+}
+
+enum Step {
+    Name(String),
+    Index(usize),
+}
+
+fn child_path(path: &Option<String>, step: &Step) -> Option<String> {
+    let path = path.as_ref()?;
+    Some(match step {
+        Step::Name(name) if is_plain_name(name) => format!("{}.{}", path, name),
+        Step::Name(name) => format!("{}[{}]", path, Value::String(name.clone())),
+        Step::Index(i) => format!("{}[{}]", path, i),
+    })
+}
+
+fn apply_segments<'a>(nodes: Vec<Node<'a>>, segments: &[Segment], root: &Value) -> Vec<Node<'a>> {
+    segments.iter().fold(nodes, |nodes, segment| {
+        let mut next = Vec::new();
+        for node in nodes {
+            let targets = if segment.descendant { descendants(node) } else { vec![node] };
+            for target in &targets {
+                for selector in &segment.selectors {
+                    select(target, selector, root, &mut next);
+                }
+            }
+        }
+        next
+    })
+}
+
+// The node and everything below it, parents before children
+fn descendants(node: Node<'_>) -> Vec<Node<'_>> {
+    let mut out = Vec::new();
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        let mut children = Vec::new();
+        select(&node, &Selector::Wildcard, &Value::Null, &mut children);
+        stack.extend(children.into_iter().rev());
+        out.push(node);
+    }
+    out
+}
+
+fn select<'a>(node: &Node<'a>, selector: &Selector, root: &Value, out: &mut Vec<Node<'a>>) {
+    match &node.value {
+        Cow::Borrowed(value) => {
+            for (step, child) in children(value, selector, root) {
+                out.push(Node { path: child_path(&node.path, &step), value: child });
+            }
+        }
+        // Values `length` or `keys` made up; rare enough that copying them is fine
+        Cow::Owned(value) => {
+            for (step, child) in children(value, selector, root) {
+                out.push(Node { path: child_path(&node.path, &step), value: Cow::Owned(child.into_owned()) });
+            }
+        }
+    }
+}
+
+fn children<'v>(value: &'v Value, selector: &Selector, root: &Value) -> Vec<(Step, Cow<'v, Value>)> {
+    match (selector, value) {
+        (Selector::Name(name), Value::Object(map)) => {
+            map.get(name).map(|child| (Step::Name(name.clone()), Cow::Borrowed(child))).into_iter().collect()
+        }
+        (Selector::Field(name), Value::Object(map)) => {
+            let child = map.get(name).map(Cow::Borrowed).unwrap_or(Cow::Owned(Value::Null));
+            vec![(Step::Name(name.clone()), child)]
+        }
+        (Selector::Field(name), Value::Null) => vec![(Step::Name(name.clone()), Cow::Owned(Value::Null))],
+        (Selector::Index(i), Value::Array(items)) => {
+            let i = if *i < 0 { items.len() as i64 + i } else { *i };
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| items.get(i).map(|child| (Step::Index(i), Cow::Borrowed(child))))
+                .into_iter()
+                .collect()
+        }
+        (Selector::Slice(start, end, step), Value::Array(items)) => slice_indices(items.len() as i64, *start, *end, *step)
+            .into_iter()
+            .map(|i| (Step::Index(i), Cow::Borrowed(&items[i])))
+            .collect(),
+        (Selector::Wildcard, _) => all_children(value),
+        (Selector::Filter(expr), _) => all_children(value)
+            .into_iter()
+            .filter(|(_, child)| expr.matches(child, root))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn all_children(value: &Value) -> Vec<(Step, Cow<'_, Value>)> {
+    match value {
+        Value::Array(items) => items.iter().enumerate().map(|(i, child)| (Step::Index(i), Cow::Borrowed(child))).collect(),
+        Value::Object(map) => map.iter().map(|(name, child)| (Step::Name(name.clone()), Cow::Borrowed(child))).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// 🎓 TEACHING: Python-style slices: `[1:3]`, `[-2:]` (the last two), `[::-1]` (reversed)
+fn slice_indices(len: i64, start: Option<i64>, end: Option<i64>, step: i64) -> Vec<usize> {
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+    let mut indices = Vec::new();
+    if step > 0 {
+        let mut i = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        while i < upper {
+            indices.push(i as usize);
+            i += step;
+        }
+    } else if step < 0 {
+        let mut i = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = end.map(normalize).unwrap_or(-1).clamp(-1, len - 1);
+        while i > lower {
+            indices.push(i as usize);
+            i += step;
+        }
+    }
+    indices
+}
+
+impl Expr {
+    fn matches(&self, current: &Value, root: &Value) -> bool {
+        match self {
+            Expr::Or(terms) => terms.iter().any(|term| term.matches(current, root)),
+            Expr::And(terms) => terms.iter().all(|term| term.matches(current, root)),
+            Expr::Not(expr) => !expr.matches(current, root),
+            Expr::Compare(left, op, right) => {
+                let (left, right) = (left.value(current, root), right.value(current, root));
+                compare(left.as_deref(), *op, right.as_deref())
+            }
+            Expr::Exists(query) => !query.nodes(current, root).is_empty(),
+            Expr::Truthy(query) => query
+                .nodes(current, root)
+                .iter()
+                .any(|node| !matches!(node.value.as_ref(), Value::Null | Value::Bool(false))),
+        }
+    }
+}
+
+impl Query {
+    fn nodes<'a>(&self, current: &'a Value, root: &'a Value) -> Vec<Node<'a>> {
+        let start = if self.relative { current } else { root };
+        apply_segments(vec![Node::root(start)], &self.segments, root)
+    }
+}
+
+impl Operand {
+    // A query only has a value if it matches exactly one thing
+    fn value<'a>(&'a self, current: &'a Value, root: &'a Value) -> Option<Cow<'a, Value>> {
+        match self {
+            Operand::Literal(value) => Some(Cow::Borrowed(value)),
+            Operand::Query(query) => {
+                let mut nodes = query.nodes(current, root);
+                (nodes.len() == 1).then(|| nodes.remove(0).value)
+            }
+        }
+    }
+}
+
+// Numbers compare by value (1 == 1.0); only numbers with numbers and strings with strings are ordered
+fn compare(left: Option<&Value>, op: Op, right: Option<&Value>) -> bool {
+    let equal = |a: Option<&Value>, b: Option<&Value>| match (a, b) {
+        (Some(Value::Number(x)), Some(Value::Number(y))) => x.as_f64() == y.as_f64(),
+        (a, b) => a == b,
+    };
+    let less = |a: Option<&Value>, b: Option<&Value>| match (a, b) {
+        (Some(Value::Number(x)), Some(Value::Number(y))) => x.as_f64() < y.as_f64(),
+        (Some(Value::String(x)), Some(Value::String(y))) => x < y,
+        _ => false,
+    };
+    match op {
+        Op::Eq => equal(left, right),
+        Op::Ne => !equal(left, right),
+        Op::Lt => less(left, right),
+        Op::Le => less(left, right) || equal(left, right),
+        Op::Gt => less(right, left),
+        Op::Ge => less(right, left) || equal(left, right),
+    }
+}
+
+fn apply_stage<'a>(nodes: Vec<Node<'a>>, stage: &Stage, root: &'a Value) -> Result<Vec<Node<'a>>> {
+    match stage {
+        Stage::Path(segments) => Ok(apply_segments(nodes, segments, root)),
+        Stage::Select(expr) => Ok(nodes.into_iter().filter(|node| expr.matches(&node.value, root)).collect()),
+        Stage::Length => nodes
+            .iter()
+            .map(|node| {
+                let length = match node.value.as_ref() {
+                    Value::Null => Value::from(0),
+                    Value::String(text) => Value::from(text.chars().count()),
+                    Value::Array(items) => Value::from(items.len()),
+                    Value::Object(map) => Value::from(map.len()),
+                    Value::Number(n) => n.as_f64().map(f64::abs).map(Value::from).unwrap_or(Value::Null),
+                    Value::Bool(_) => return Err(anyhow!("A boolean has no length")),
+                };
+                Ok(Node { path: None, value: Cow::Owned(length) })
+            })
+            .collect(),
+        Stage::Keys => nodes
+            .iter()
+            .map(|node| {
+                let keys = match node.value.as_ref() {
+                    Value::Object(map) => {
+                        let mut keys: Vec<&String> = map.keys().collect();
+                        keys.sort();
+                        Value::from(keys.into_iter().cloned().collect::<Vec<_>>())
+                    }
+                    Value::Array(items) => Value::from((0..items.len()).collect::<Vec<_>>()),
+                    other => return Err(anyhow!("Only objects and arrays have keys, not {}", other)),
+                };
+                Ok(Node { path: None, value: Cow::Owned(keys) })
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const BODY: &str = r#"{
+        "store": {
+            "book": [
+                {"title": "Sayings", "author": "Rees", "price": 8.95, "tags": ["quotes"]},
+                {"title": "Sword", "author": "Waugh", "price": 12.99},
+                {"title": "Moby Dick", "author": "Melville", "price": 8.99, "isbn": "0-553"},
+                {"title": "Rings", "author": "Tolkien", "price": 22.99, "isbn": "0-395"}
+            ],
+            "bicycle": {"color": "red", "price": 19.95},
+            "odd key": true
+        }
+    }"#;
+
+    fn values(expression: &str) -> Vec<Value> {
+        query(BODY, expression).unwrap().matches.into_iter().map(|m| m.value).collect()
+    }
+
+    fn paths(expression: &str) -> Vec<String> {
+        query(BODY, expression).unwrap().matches.into_iter().filter_map(|m| m.path).collect()
+    }
+
+    #[test]
+    fn test_json_path() {
+        assert_eq!(values("$.store.book[0].title"), vec![json!("Sayings")]);
+        assert_eq!(values("$.store.book[-1].author"), vec![json!("Tolkien")]);
+        assert_eq!(values("$['store']['odd key']"), vec![json!(true)]);
+        assert_eq!(paths("$.store['odd key']"), vec!["$.store[\"odd key\"]"]);
+        assert_eq!(values("$.store.book[*].author").len(), 4);
+        assert_eq!(values("$.store.book[1:3].title"), vec![json!("Sword"), json!("Moby Dick")]);
+        assert_eq!(values("$.store.book[::-2].title"), vec![json!("Rings"), json!("Sword")]);
+        assert_eq!(values("$.store.book[0,2].price"), vec![json!(8.95), json!(8.99)]);
+        assert_eq!(values("$..price").len(), 5);
+        assert_eq!(paths("$..bicycle.color"), vec!["$.store.bicycle.color"]);
+        assert!(values("$.store.missing").is_empty());
+    }
+
+    #[test]
+    fn test_json_path_filters() {
+        assert_eq!(
+            paths("$.store.book[?(@.price < 10)]"),
+            vec!["$.store.book[0]", "$.store.book[2]"]
+        );
+        assert_eq!(values("$.store.book[?@.isbn && @.price > 20].title"), vec![json!("Rings")]);
+        assert_eq!(values("$.store.book[?(!@.isbn)].title"), vec![json!("Sayings"), json!("Sword")]);
+        assert_eq!(values("$.store.book[?(@.author == 'Waugh' || @.tags)].title"), vec![json!("Sayings"), json!("Sword")]);
+        // `$` inside a filter is the whole body
+        assert_eq!(values("$.store.book[?(@.price > $.store.bicycle.price)].title"), vec![json!("Rings")]);
+    }
+
+    #[test]
+    fn test_jq() {
+        assert_eq!(values(".store.book[0].title"), vec![json!("Sayings")]);
+        assert_eq!(values(".store.book[] | select(.price < 10) | .title"), vec![json!("Sayings"), json!("Moby Dick")]);
+        assert_eq!(values(".store.book[] | select(.isbn and .price < 10) | .author"), vec![json!("Melville")]);
+        assert_eq!(values(".store.book | length"), vec![json!(4)]);
+        assert_eq!(values(".store.bicycle | keys"), vec![json!(["color", "price"])]);
+        assert_eq!(values(".[\"store\"].bicycle.color"), vec![json!("red")]);
+        // Missing fields are null, as in jq
+        assert_eq!(values(".store.book[].isbn").iter().filter(|v| v.is_null()).count(), 2);
+        assert_eq!(paths(".store.book[] | select(.tags) | .title"), vec!["$.store.book[0].title"]);
+        assert_eq!(query(BODY, ".store | length").unwrap().matches[0].path, None);
+    }
+
+    #[test]
+    fn test_errors_and_limits() {
+        assert!(query("not json", "$.a").is_err());
+        assert!(query(BODY, "$.store[").is_err());
+        assert!(query(BODY, ".store | sort").unwrap_err().to_string().contains("Unsupported jq filter 'sort'"));
+        assert!(query(BODY, "$.store.book[?(@.price <)]").is_err());
+
+        let many = serde_json::to_string(&(0..MAX_MATCHES + 5).collect::<Vec<_>>()).unwrap();
+        let result = query(&many, "$[*]").unwrap();
+        assert_eq!((result.total, result.matches.len(), result.truncated), (MAX_MATCHES + 5, MAX_MATCHES, true));
+    }
+}
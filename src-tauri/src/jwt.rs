@@ -0,0 +1,239 @@
+// 🎓 TEACHING: JWT inspector
+// Paste a token (with or without "Bearer ") and get back its decoded header and claims,
+// the algorithm it was signed with, and whether it's expired, not valid yet or fine, read
+// from `exp`/`nbf` against the clock here.
+//
+// Given a key, the signature is checked too. The key can be:
+//   - a shared secret, for HS256/384/512
+//   - a PEM public key, for RSA, ECDSA and EdDSA tokens
+//   - a JWK, or a JWKS (`{"keys": [...]}`), where the token's `kid` picks the key
+//   - a JWKS URL, fetched here
+// Only the signature is checked; expiry is reported separately, and issuer and audience
+// are left to the reader. A token that fails verification is still decoded.
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryState {
+    Valid,
+    Expired,
+    NotYetValid, // `nbf` is still in the future
+    NoExpiry,    // No `exp` claim
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Expiry {
+    pub state: ExpiryState,
+    pub expires_at: Option<String>, // RFC 3339, from `exp`
+    pub not_before: Option<String>,
+    pub issued_at: Option<String>,
+    pub expires_in_secs: Option<i64>, // Negative once expired
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SignatureCheck {
+    pub verified: bool,
+    pub error: Option<String>, // Why it wasn't verified
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct JwtInfo {
+    pub header: Value,
+    pub claims: Value, // A string if the payload isn't JSON
+    pub algorithm: Option<String>,
+    pub expiry: Expiry,
+    pub signature: Option<SignatureCheck>, // Only when a key was given
+}
+
+// Where the verification key comes from
+pub enum VerifyKey<'a> {
+    Key(&'a str), // Secret, PEM, JWK or JWKS
+    Jwks(JwkSet),
+}
+
+pub async fn fetch_jwks(url: &str) -> Result<JwkSet> {
+    let response = reqwest::get(url).await?.error_for_status()?;
+    Ok(response.json().await?)
+}
+
+pub fn inspect(token: &str, key: Option<VerifyKey>) -> Result<JwtInfo> {
+    inspect_at(token, key, chrono::Utc::now().timestamp())
+}
+
+fn inspect_at(token: &str, key: Option<VerifyKey>, now: i64) -> Result<JwtInfo> {
+    let token = strip_bearer(token);
+    let parts: Vec<&str> = token.split('.').collect();
+    match parts.len() {
+        3 => {}
+        5 => return Err(anyhow!("This is an encrypted token (JWE); only signed tokens can be decoded")),
+        _ => return Err(anyhow!("Not a JWT: expected three dot-separated parts, found {}", parts.len())),
+    }
+
+    let header: Value = serde_json::from_slice(&decode_part(parts[0], "header")?)
+        .map_err(|e| anyhow!("The JWT header is not JSON: {}", e))?;
+    let payload = decode_part(parts[1], "payload")?;
+    let claims = serde_json::from_slice(&payload)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&payload).into_owned()));
+    let algorithm = header.get("alg").and_then(Value::as_str).map(str::to_string);
+    let expiry = expiry(&claims, now);
+    let signature = key.map(|key| match verify(token, &header, key) {
+        Ok(()) => SignatureCheck { verified: true, error: None },
+        Err(e) => SignatureCheck { verified: false, error: Some(e.to_string()) },
+    });
+    Ok(JwtInfo { header, claims, algorithm, expiry, signature })
+}
+
+fn strip_bearer(token: &str) -> &str {
+    let token = token.trim();
+    match token.get(..7) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("bearer ") => token[7..].trim(),
+        _ => token,
+    }
+}
+
+// Tokens are meant to be unpadded base64url, but padded ones turn up too
+fn decode_part(part: &str, name: &str) -> Result<Vec<u8>> {
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(part.trim_end_matches('='))
+        .map_err(|e| anyhow!("The JWT {} is not valid base64url: {}", name, e))
+}
+
+fn expiry(claims: &Value, now: i64) -> Expiry {
+    let time = |name: &str| claims.get(name).and_then(Value::as_f64).map(|secs| secs as i64);
+    let rfc3339 = |secs: Option<i64>| {
+        secs.and_then(|secs| chrono::DateTime::from_timestamp(secs, 0)).map(|at| at.to_rfc3339())
+    };
+    let (exp, nbf) = (time("exp"), time("nbf"));
+    let state = match (exp, nbf) {
+        (_, Some(nbf)) if now < nbf => ExpiryState::NotYetValid,
+        (Some(exp), _) if now >= exp => ExpiryState::Expired,
+        (Some(_), _) => ExpiryState::Valid,
+        (None, _) => ExpiryState::NoExpiry,
+    };
+    Expiry {
+        state,
+        expires_at: rfc3339(exp),
+        not_before: rfc3339(nbf),
+        issued_at: rfc3339(time("iat")),
+        expires_in_secs: exp.map(|exp| exp - now),
+    }
+}
+
+fn verify(token: &str, header: &Value, key: VerifyKey) -> Result<()> {
+    let alg = header.get("alg").and_then(Value::as_str).unwrap_or_default();
+    if alg.eq_ignore_ascii_case("none") {
+        return Err(anyhow!("The token is unsigned (alg \"none\")"));
+    }
+    let algorithm: Algorithm = alg.parse().map_err(|_| anyhow!("Unsupported algorithm {}", alg))?;
+    let kid = header.get("kid").and_then(Value::as_str);
+    let decoding_key = match key {
+        VerifyKey::Jwks(set) => DecodingKey::from_jwk(select_jwk(&set, kid)?)?,
+        VerifyKey::Key(key) => decoding_key(key.trim(), algorithm, kid)?,
+    };
+
+    let mut validation = Validation::new(algorithm);
+    validation.required_spec_claims.clear();
+    validation.validate_exp = false;
+    validation.validate_nbf = false;
+    validation.validate_aud = false;
+    jsonwebtoken::decode::<Value>(token, &decoding_key, &validation)?;
+    Ok(())
+}
+
+fn decoding_key(key: &str, algorithm: Algorithm, kid: Option<&str>) -> Result<DecodingKey> {
+    if key.starts_with('{') {
+        if let Ok(set) = serde_json::from_str::<JwkSet>(key) {
+            return Ok(DecodingKey::from_jwk(select_jwk(&set, kid)?)?);
+        }
+        let jwk: Jwk = serde_json::from_str(key).map_err(|e| anyhow!("The key is not a JWK or JWKS: {}", e))?;
+        return Ok(DecodingKey::from_jwk(&jwk)?);
+    }
+    let pem = key.as_bytes();
+    Ok(match algorithm {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => DecodingKey::from_secret(pem),
+        Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_pem(pem)?,
+        Algorithm::EdDSA => DecodingKey::from_ed_pem(pem)?,
+        _ => DecodingKey::from_rsa_pem(pem)?,
+    })
+}
+
+// The key named by the token's `kid`, or the only key if it names none
+fn select_jwk<'a>(set: &'a JwkSet, kid: Option<&str>) -> Result<&'a Jwk> {
+    match kid {
+        Some(kid) => set.find(kid).ok_or_else(|| anyhow!("No key in the JWKS has kid {}", kid)),
+        None if set.keys.len() == 1 => Ok(&set.keys[0]),
+        None => Err(anyhow!("The token has no kid to pick one of the {} JWKS keys", set.keys.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn token(claims: Value, kid: Option<&str>) -> String {
+        let header = Header { kid: kid.map(str::to_string), ..Header::new(Algorithm::HS256) };
+        encode(&header, &claims, &EncodingKey::from_secret(b"secret")).unwrap()
+    }
+
+    #[test]
+    fn test_decode_and_expiry() {
+        let jwt = token(serde_json::json!({"sub": "ada", "exp": NOW + 60, "iat": NOW - 60}), None);
+        let info = inspect_at(&format!("Bearer {}", jwt), None, NOW).unwrap();
+        assert_eq!(info.algorithm.as_deref(), Some("HS256"));
+        assert_eq!(info.claims["sub"], "ada");
+        assert_eq!(info.header["typ"], "JWT");
+        assert_eq!(info.expiry.state, ExpiryState::Valid);
+        assert_eq!(info.expiry.expires_in_secs, Some(60));
+        assert_eq!(info.expiry.expires_at.as_deref(), Some("2023-11-14T22:14:20+00:00"));
+        assert_eq!(info.signature, None);
+
+        assert_eq!(inspect_at(&jwt, None, NOW + 60).unwrap().expiry.state, ExpiryState::Expired);
+        let later = token(serde_json::json!({"nbf": NOW + 10}), None);
+        assert_eq!(inspect_at(&later, None, NOW).unwrap().expiry.state, ExpiryState::NotYetValid);
+        let forever = token(serde_json::json!({"sub": "x"}), None);
+        assert_eq!(inspect_at(&forever, None, NOW).unwrap().expiry.state, ExpiryState::NoExpiry);
+    }
+
+    #[test]
+    fn test_verify() {
+        // Expired, but the signature is still checked
+        let jwt = token(serde_json::json!({"exp": NOW - 10}), Some("k1"));
+        let check = |key| inspect_at(&jwt, Some(key), NOW).unwrap().signature.unwrap();
+        assert!(check(VerifyKey::Key("secret")).verified);
+        let wrong = check(VerifyKey::Key("not the secret"));
+        assert!(!wrong.verified && wrong.error.is_some());
+
+        // "secret" as a symmetric JWK
+        let jwks = r#"{"keys": [
+            {"kty": "oct", "kid": "k0", "k": "b3RoZXI"},
+            {"kty": "oct", "kid": "k1", "k": "c2VjcmV0"}
+        ]}"#;
+        assert!(check(VerifyKey::Key(jwks)).verified);
+        let set: JwkSet = serde_json::from_str(jwks).unwrap();
+        assert!(check(VerifyKey::Jwks(set.clone())).verified);
+        let unnamed = token(serde_json::json!({}), None);
+        let result = inspect_at(&unnamed, Some(VerifyKey::Jwks(set)), NOW).unwrap().signature.unwrap();
+        assert!(result.error.unwrap().contains("no kid"));
+    }
+
+    #[test]
+    fn test_not_a_jwt() {
+        assert!(inspect_at("abc", None, NOW).is_err());
+        assert!(inspect_at("a.b.c.d.e", None, NOW).unwrap_err().to_string().contains("JWE"));
+        assert!(inspect_at("e30.!!.x", None, NOW).is_err());
+        // Unsigned tokens decode but never verify
+        let unsigned = "eyJhbGciOiJub25lIn0.eyJzdWIiOiJ4In0.";
+        let info = inspect_at(unsigned, Some(VerifyKey::Key("secret")), NOW).unwrap();
+        assert_eq!(info.algorithm.as_deref(), Some("none"));
+        assert!(!info.signature.unwrap().verified);
+    }
+}
@@ -0,0 +1,309 @@
+// 🎓 TEACHING: Large responses
+// A 500 MB body returned inline has to go through the IPC bridge and into the editor in one
+// piece, which freezes the webview. So above `large_response_bytes` (10 MiB by default) the
+// body is written to a file in the app cache directory instead, and the response carries
+// only its first PREVIEW_BYTES plus `large_body` saying how big the whole thing is. The UI
+// then pages through it with get_response_chunk and finds things with search_response_body,
+// both of which read the file here rather than holding it in memory.
+//
+// The files belong to the responses kept for diffing (see response_diff) and are deleted
+// once those are dropped; anything left over from the last run is cleared at startup.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// What the response carries inline when its body went to a file
+pub const PREVIEW_BYTES: usize = 64 * 1024;
+pub const MAX_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+pub const MAX_SEARCH_MATCHES: usize = 1000;
+const SEARCH_BLOCK_BYTES: usize = 1024 * 1024;
+// How much of the line around a search match is sent back with it
+const CONTEXT_BYTES: usize = 60;
+
+// On a response whose body was too big to send inline
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LargeBody {
+    pub size: u64,          // The whole body, in bytes
+    pub preview_bytes: u64, // How much of it `body` holds
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BodyFile {
+    path: PathBuf,
+    size: u64,
+}
+
+impl Drop for BodyFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// A kept response body: in memory, or in a file that goes away with the last copy of it
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoredBody {
+    Inline(String),
+    File(Arc<BodyFile>),
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BodyChunk {
+    pub offset: u64,      // Where the text starts; moved forward if the asked offset was mid-character
+    pub next_offset: u64, // Where the next chunk starts
+    pub total: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SearchMatch {
+    pub offset: u64, // Byte offset of the match, for get_response_chunk
+    pub line: u64,   // 1-based
+    pub context: String, // The match with some of its line either side
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SearchResult {
+    pub total: u64, // Every match, including those past MAX_SEARCH_MATCHES
+    pub matches: Vec<SearchMatch>,
+    pub truncated: bool,
+}
+
+// The longest prefix of `body` within `max_bytes` that doesn't split a character
+pub fn preview(body: &str, max_bytes: usize) -> &str {
+    let mut end = max_bytes.min(body.len());
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    &body[..end]
+}
+
+// Leftovers from the last run; nothing refers to them any more
+pub fn clear_dir(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+impl StoredBody {
+    pub fn spill(dir: &Path, body: &str) -> Result<StoredBody> {
+        std::fs::create_dir_all(dir)?;
+        // Execution ids come from the frontend, so they don't name the file
+        let path = dir.join(format!("{}.body", uuid::Uuid::new_v4()));
+        std::fs::write(&path, body)?;
+        Ok(StoredBody::File(Arc::new(BodyFile { path, size: body.len() as u64 })))
+    }
+
+    pub fn size(&self) -> u64 {
+        match self {
+            StoredBody::Inline(text) => text.len() as u64,
+            StoredBody::File(file) => file.size,
+        }
+    }
+
+    // What keeping it costs in memory
+    pub fn memory_size(&self) -> usize {
+        match self {
+            StoredBody::Inline(text) => text.len(),
+            StoredBody::File(_) => 0,
+        }
+    }
+
+    pub fn text(&self) -> Result<Cow<'_, str>> {
+        match self {
+            StoredBody::Inline(text) => Ok(Cow::Borrowed(text)),
+            StoredBody::File(file) => Ok(Cow::Owned(std::fs::read_to_string(&file.path)?)),
+        }
+    }
+
+    pub fn chunk(&self, offset: u64, length: u64) -> Result<BodyChunk> {
+        let total = self.size();
+        let start = offset.min(total);
+        // At least one whole character, so paging with next_offset always moves forward
+        let end = start.saturating_add(length.clamp(4, MAX_CHUNK_BYTES)).min(total);
+        let bytes = match self {
+            StoredBody::Inline(text) => Cow::Borrowed(&text.as_bytes()[start as usize..end as usize]),
+            StoredBody::File(file) => {
+                let mut reader = File::open(&file.path)?;
+                reader.seek(SeekFrom::Start(start))?;
+                let mut bytes = vec![0; (end - start) as usize];
+                reader.read_exact(&mut bytes)?;
+                Cow::Owned(bytes)
+            }
+        };
+
+        // Skip the tail of a character the offset landed in, and leave off one cut at the end
+        let skip = bytes.iter().take(3).take_while(|byte| is_continuation(**byte)).count();
+        let text = match std::str::from_utf8(&bytes[skip..]) {
+            Ok(text) => text,
+            Err(e) => std::str::from_utf8(&bytes[skip..skip + e.valid_up_to()])?,
+        };
+        Ok(BodyChunk {
+            offset: start + skip as u64,
+            next_offset: start + (skip + text.len()) as u64,
+            total,
+            text: text.to_string(),
+        })
+    }
+
+    // 🎓 TEACHING: Plain text search, read a block at a time so a file body never has to be
+    // in memory at once. Case-insensitive matching follows Unicode case folding.
+    pub fn search(&self, query: &str, case_sensitive: bool) -> Result<SearchResult> {
+        if query.is_empty() {
+            return Err(anyhow::anyhow!("Search text can't be empty"));
+        }
+        let pattern = regex::bytes::RegexBuilder::new(&regex::escape(query))
+            .case_insensitive(!case_sensitive)
+            .build()?;
+        match self {
+            StoredBody::Inline(text) => search_reader(text.as_bytes(), &pattern, query.len()),
+            StoredBody::File(file) => search_reader(File::open(&file.path)?, &pattern, query.len()),
+        }
+    }
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+fn search_reader(mut reader: impl Read, pattern: &regex::bytes::Regex, query_len: usize) -> Result<SearchResult> {
+    // A match (or its context) can run past the end of a block; those are left for the next
+    // round, which keeps this much of the previous block
+    let overlap = query_len * 4 + CONTEXT_BYTES;
+    let mut buffer = Vec::new();
+    let mut buffer_offset = 0u64; // Of buffer[0] in the body
+    let mut buffer_line = 1u64; // The line buffer[0] is on
+    let mut search_from = 0;
+    let mut result = SearchResult { total: 0, matches: Vec::new(), truncated: false };
+    let mut block = vec![0; SEARCH_BLOCK_BYTES];
+    loop {
+        let read = reader.read(&mut block)?;
+        buffer.extend_from_slice(&block[..read]);
+        let at_end = read == 0;
+        let limit = if at_end { buffer.len() } else { buffer.len().saturating_sub(overlap) };
+
+        let (mut counted_to, mut line) = (0, buffer_line);
+        for found in pattern.find_iter(&buffer[search_from..]) {
+            let (start, end) = (search_from + found.start(), search_from + found.end());
+            if start >= limit {
+                break;
+            }
+            result.total += 1;
+            if result.matches.len() < MAX_SEARCH_MATCHES {
+                line += count_lines(&buffer[counted_to..start]);
+                counted_to = start;
+                result.matches.push(SearchMatch {
+                    offset: buffer_offset + start as u64,
+                    line,
+                    context: context(&buffer, start, end),
+                });
+            }
+        }
+        if at_end {
+            result.truncated = result.total > result.matches.len() as u64;
+            return Ok(result);
+        }
+
+        // Keep enough before `limit` for the context of the next round's first match
+        let drop = limit.saturating_sub(CONTEXT_BYTES);
+        buffer_line += count_lines(&buffer[..drop]);
+        buffer_offset += drop as u64;
+        buffer.drain(..drop);
+        search_from = limit - drop;
+    }
+}
+
+fn count_lines(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|byte| **byte == b'\n').count() as u64
+}
+
+// The match and up to CONTEXT_BYTES either side of it, without crossing a line end
+fn context(buffer: &[u8], start: usize, end: usize) -> String {
+    let mut from = start.saturating_sub(CONTEXT_BYTES);
+    if let Some(newline) = buffer[from..start].iter().rposition(|byte| *byte == b'\n') {
+        from += newline + 1;
+    }
+    let mut to = (end + CONTEXT_BYTES).min(buffer.len());
+    if let Some(newline) = buffer[end..to].iter().position(|byte| *byte == b'\n') {
+        to = end + newline;
+    }
+    while from < start && is_continuation(buffer[from]) {
+        from += 1;
+    }
+    while to > end && to < buffer.len() && is_continuation(buffer[to]) {
+        to -= 1;
+    }
+    String::from_utf8_lossy(&buffer[from..to]).trim_end_matches('\r').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spill_chunk_and_cleanup() {
+        let dir = std::env::temp_dir().join(format!("openrequest-bodies-{}", uuid::Uuid::new_v4()));
+        let body = StoredBody::spill(&dir, "héllo wörld").unwrap();
+        assert_eq!(body.size(), 13);
+        assert_eq!(body.memory_size(), 0);
+        assert_eq!(body.text().unwrap(), "héllo wörld");
+
+        // 5..9 ends inside "ö", so the chunk stops before it
+        let chunk = body.chunk(5, 4).unwrap();
+        assert_eq!((chunk.text.as_str(), chunk.next_offset), ("o w", 8));
+        // Starting inside "é" skips to the next character
+        let chunk = body.chunk(2, 6).unwrap();
+        assert_eq!((chunk.offset, chunk.text.as_str(), chunk.next_offset), (3, "llo w", 8));
+        let chunk = body.chunk(8, 100).unwrap();
+        assert_eq!((chunk.text.as_str(), chunk.next_offset, chunk.total), ("örld", 13, 13));
+
+        let copy = body.clone();
+        drop(body);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        drop(copy);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        clear_dir(&dir).unwrap();
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_search() {
+        let body = StoredBody::Inline("first line\nsecond LINE here\n  third line".to_string());
+        let result = body.search("line", false).unwrap();
+        let found: Vec<(u64, u64, &str)> =
+            result.matches.iter().map(|m| (m.offset, m.line, m.context.as_str())).collect();
+        assert_eq!(
+            found,
+            vec![(6, 1, "first line"), (18, 2, "second LINE here"), (36, 3, "  third line")]
+        );
+        assert_eq!(body.search("line", true).unwrap().total, 2);
+        assert!(body.search("", true).is_err());
+    }
+
+    #[test]
+    fn test_search_across_blocks() {
+        // Matches straddling the block boundaries are found once each, with the right lines
+        let line = "x".repeat(1000) + "needle\n";
+        let text = line.repeat(3000);
+        let result = search_reader(text.as_bytes(), &regex::bytes::Regex::new("needle").unwrap(), 6).unwrap();
+        assert_eq!(result.total, 3000);
+        assert_eq!(result.matches.len(), MAX_SEARCH_MATCHES);
+        assert!(result.truncated);
+        let last = result.matches.last().unwrap();
+        assert_eq!(last.line, MAX_SEARCH_MATCHES as u64);
+        assert_eq!(last.offset, (MAX_SEARCH_MATCHES - 1) as u64 * line.len() as u64 + 1000);
+        assert!(last.context.ends_with("xneedle"));
+    }
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(preview("héllo", 2), "h");
+        assert_eq!(preview("héllo", 3), "hé");
+        assert_eq!(preview("hi", 10), "hi");
+    }
+}
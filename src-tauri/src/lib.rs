@@ -5,30 +5,158 @@ use tauri::State;
 
 // Import our database module
 mod database;
+mod default_headers;
+mod diagnostics;
+mod error;
+mod faker;
+mod graphql;
+mod history;
+mod grpc;
+mod http_cache;
+mod http_file;
+mod http_server;
 mod importer_exporter;
+mod json_query;
+mod jwt;
+mod large_body;
+mod logging;
+mod matrix;
+mod mock;
+mod monitor;
+mod mqtt;
 mod oauth; // Phase 2: OAuth 2.0 support
+mod oidc;
+mod openapi;
+mod analytics;
 mod auth;  // Phase 2: Advanced authentication
+mod body_format;
+mod bru;
+mod cache_maintenance;
+mod cache_policy;
+mod compression;
+mod capture;
+mod contract;
+mod cookies;
+mod params;
+mod placeholders;
+mod plugin;
+mod pm_shim;
+mod progress;
+mod providers;
+mod proxy;
+mod queue;
+mod rate_limit;
+mod raw_socket;
+mod redact;
+mod report;
+mod request_settings;
+mod response_diff;
+mod runner;
+mod scripting;
+mod secrets;
+mod session;
+mod settings;
+mod streaming;
+mod sync;
+mod thunder;
+mod tls_inspect;
+mod trace;
+mod utilities;
+mod webhook;
+mod wire_log;
+mod workflow;
 use database::Database;
+use error::{AppError, ErrorKind};
 
 // 🎓 TEACHING: This is our application state
 // The Mutex ensures thread safety (only one thread can access it at a time)
 type DatabaseState = Mutex<Option<Database>>;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 struct ApiRequest {
     method: String,
     url: String,
-    params: HashMap<String, String>,
+    // Ordered list of query params (a legacy `{ key: value }` object is also accepted)
+    #[serde(deserialize_with = "params::deserialize_query_params")]
+    params: Vec<params::QueryParam>,
+    // "encode" (default) encodes params on send, "raw" sends them as typed
+    param_encoding: Option<String>,
+    // Values for `:id` / `{id}` placeholders in the URL path
+    #[serde(default)]
+    path_params: HashMap<String, String>,
     headers: HashMap<String, String>,
     body: Option<String>,
-    auth_type: Option<String>,
+    // "graphql" sends `body` as the query, assembled into a JSON payload with the fields below
+    body_type: Option<String>,
+    graphql_variables: Option<String>, // JSON object
+    graphql_operation_name: Option<String>,
+    auth_type: Option<String>, // None or "inherit" falls back to the folder/collection auth
     auth_data: Option<String>,
+    // Collection (or folder) the request lives in, used for auth and variable inheritance
+    collection_id: Option<String>,
+    // Saved request this was sent from, for request-level variables
+    request_id: Option<String>,
+    // One-off values for this send only (never saved); they beat every stored variable
+    #[serde(default)]
+    variable_overrides: HashMap<String, String>,
+    // Use this environment instead of the active one (collection runs pick their own)
+    environment_id: Option<String>,
+    // Fail the send if any `{{variable}}` is left unresolved (otherwise they come back as warnings)
+    strict_variables: Option<bool>,
+    // Return a wire log of exactly what was sent and received
+    debug: Option<bool>,
+    // Values to pull out of the response into variables
+    #[serde(default)]
+    captures: Vec<capture::CaptureRule>,
+    // Rhai pre-request and tests scripts
+    #[serde(default)]
+    scripts: scripting::RequestScripts,
     // Phase 2: Cache options
     use_cache: Option<bool>,
     cache_duration: Option<u64>, // Cache duration in seconds
+    // Header names left out of the cache key (defaults to DEFAULT_CACHE_IGNORED_HEADERS)
+    cache_ignore_headers: Option<Vec<String>>,
+    // "fixed" keeps responses for cache_duration; "http" follows Cache-Control and revalidates
+    cache_mode: Option<String>,
+    // Answer from the cache only; set from the global offline setting, never by the caller
+    #[serde(skip)]
+    offline: bool,
+    // Drop cached reads of the path after a successful write; also set from the global settings
+    #[serde(skip)]
+    invalidate_cache_on_write: bool,
+    // Trace headers to add unless the request's folders set their own; from the global settings
+    #[serde(skip)]
+    trace_context: trace::TraceContext,
+    // The app-wide default headers, from the global settings; merged with the environment's and folders'
+    #[serde(skip)]
+    default_headers: HashMap<String, String>,
+    // Default headers (from any level) not to send with this request
+    #[serde(default)]
+    disabled_default_headers: Vec<String>,
+    // HTTP protocol version: "auto", "http1", "http2" (defaults to "auto")
+    http_version: Option<String>,
+    // Send through a Unix domain socket (or a Windows named pipe like `\\.\pipe\docker_engine`)
+    // instead of TCP. The URL host is ignored, but its path and query are still used.
+    socket_path: Option<String>,
+    // Map hostnames to fixed IPs, like curl's `--resolve` (merged over the active environment's overrides)
+    #[serde(default)]
+    host_overrides: HashMap<String, String>,
+    // Give up after this many milliseconds (no limit by default)
+    timeout_ms: Option<u64>,
+    // Redirects are followed (up to 10) unless turned off here
+    follow_redirects: Option<bool>,
+    max_redirects: Option<usize>,
+    // Send through this proxy instead of the system one
+    proxy_url: Option<String>,
+    // false accepts invalid certificates (self-signed, expired, wrong host)
+    verify_tls: Option<bool>,
+    // Keep the response's cookies in the cookie jar and send the jar's matching cookies
+    use_cookie_jar: Option<bool>,
+    // Send a GET or HEAD again when it fails to connect or gets a retryable status
+    retry: Option<runner::RetryPolicy>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 struct ApiResponse {
     status: u16,
     headers: HashMap<String, String>,
@@ -36,48 +164,683 @@ struct ApiResponse {
     // Phase 2: Cache metadata
     from_cache: Option<bool>,
     cache_time: Option<String>,
+    // A cached copy past its expiry, which only offline mode serves
+    #[serde(default)]
+    cache_stale: Option<bool>,
+    // Negotiated protocol version, e.g. "HTTP/1.1" or "HTTP/2.0"
+    http_version: Option<String>,
+    // `{{variables}}` that were sent verbatim because nothing defines them
+    #[serde(default)]
+    unresolved_variables: Vec<placeholders::UnresolvedVariable>,
+    // What the request's capture rules found (and wrote into variables)
+    #[serde(default)]
+    captured: Vec<capture::CaptureResult>,
+    // Checks recorded by the tests script, and anything the scripts printed
+    #[serde(default)]
+    test_results: Vec<scripting::TestResult>,
+    #[serde(default)]
+    script_logs: Vec<String>,
+    // What went over the wire, for sends with `debug` set (a cached answer sent nothing)
+    #[serde(default)]
+    wire_log: Option<wire_log::WireLog>,
+    // The trace and correlation ids added to the send, if trace headers are turned on
+    #[serde(default)]
+    trace: Option<trace::TraceIds>,
+    // Set when the body was too big to return inline; `body` is then only its start
+    #[serde(default)]
+    large_body: Option<large_body::LargeBody>,
+    // The Set-Cookie headers, parsed (`headers` only holds the last one)
+    #[serde(default)]
+    cookies: Vec<cookies::Cookie>,
+    // How long the send waited for its host's rate limit, if it had to
+    #[serde(default)]
+    throttled_ms: Option<u64>,
+    // Sends made, retries included; 0 for an answer from the cache
+    #[serde(default)]
+    attempts: u32,
+}
+
+// 🎓 TEACHING: Build an HTTP client configured for this request's transport options.
+// For the protocol version, "auto" lets reqwest negotiate (HTTP/2 via ALPN over TLS, otherwise HTTP/1.1).
+fn build_http_client(request: &ApiRequest) -> Result<reqwest::Client, AppError> {
+    let mut builder = reqwest::Client::builder();
+
+    if let Some(socket_path) = request.socket_path.as_deref().filter(|p| !p.is_empty()) {
+        #[cfg(unix)]
+        {
+            builder = builder.unix_socket(socket_path);
+        }
+        #[cfg(windows)]
+        {
+            builder = builder.windows_named_pipe(socket_path);
+        }
+    }
+
+    // 🎓 TEACHING: Pin hostnames to specific IPs without touching /etc/hosts.
+    // The URL's port (or the scheme default) is still used, so only the IP is needed here.
+    for (host, ip) in &request.host_overrides {
+        let ip: std::net::IpAddr = ip
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse()
+            .map_err(|_| AppError::validation(format!("Invalid IP address for host override {}: {}", host, ip)))?;
+        builder = builder.resolve(host, std::net::SocketAddr::new(ip, 0));
+    }
+
+    if let Some(timeout_ms) = request.timeout_ms {
+        builder = builder.timeout(std::time::Duration::from_millis(timeout_ms));
+    }
+    builder = builder.redirect(match (request.follow_redirects, request.max_redirects) {
+        (Some(false), _) => reqwest::redirect::Policy::none(),
+        (_, Some(max)) => reqwest::redirect::Policy::limited(max),
+        _ => reqwest::redirect::Policy::default(),
+    });
+    if let Some(proxy_url) = request.proxy_url.as_deref().filter(|url| !url.is_empty()) {
+        let proxy = reqwest::Proxy::all(proxy_url)
+            .map_err(|e| AppError::validation(format!("Invalid proxy URL {}: {}", proxy_url, e)))?;
+        builder = builder.proxy(proxy);
+    }
+    if request.verify_tls == Some(false) {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    let builder = match request.http_version.as_deref().unwrap_or("auto") {
+        "auto" => builder,
+        "http1" => builder.http1_only(),
+        "http2" => builder.http2_prior_knowledge(),
+        "http3" => return Err(AppError::validation("HTTP/3 is not supported yet")),
+        other => return Err(AppError::validation(format!("Unsupported HTTP version: {}", other))),
+    };
+
+    builder.build().map_err(AppError::from)
+}
+
+// Headers that change from one send to the next without changing the response
+const DEFAULT_CACHE_IGNORED_HEADERS: &[&str] = &[
+    "authorization",
+    "date",
+    "user-agent",
+    "traceparent",
+    "tracestate",
+    "x-request-id",
+    "x-correlation-id",
+    "x-amzn-trace-id",
+    "x-cloud-trace-context",
+    "x-b3-traceid",
+    "x-b3-spanid",
+    "x-b3-parentspanid",
+    "x-b3-sampled",
+    "b3",
+    "uber-trace-id",
+];
+
+// 🎓 TEACHING: The headers part of a cache key. Names are compared case-insensitively and
+// sorted, so the same headers always give the same key whatever order they were added in.
+fn cache_key_headers(headers: &HashMap<String, String>, ignored: Option<&[String]>) -> String {
+    let is_ignored = |name: &str| match ignored {
+        Some(ignored) => ignored.iter().any(|ignored| ignored.eq_ignore_ascii_case(name)),
+        None => DEFAULT_CACHE_IGNORED_HEADERS.iter().any(|ignored| ignored.eq_ignore_ascii_case(name)),
+    };
+    let kept: std::collections::BTreeMap<String, &str> = headers
+        .iter()
+        .filter(|(name, _)| !is_ignored(name))
+        .map(|(name, value)| (name.to_lowercase(), value.as_str()))
+        .collect();
+    serde_json::to_string(&kept).unwrap_or_default()
+}
+
+fn format_http_version(version: reqwest::Version) -> String {
+    format!("{:?}", version)
+}
+
+fn cached_api_response(
+    cached: database::ResponseCache,
+    unresolved_variables: Vec<placeholders::UnresolvedVariable>,
+    script_logs: Vec<String>,
+) -> Result<ApiResponse, AppError> {
+    let cached_headers: HashMap<String, String> =
+        serde_json::from_str(&cached.response_headers).map_err(AppError::from)?;
+    Ok(ApiResponse {
+        status: cached.response_status,
+        headers: cached_headers,
+        body: cached.response_body,
+        from_cache: Some(true),
+        cache_time: Some(cached.cache_time.to_rfc3339()),
+        cache_stale: Some(cached.expires_at.is_some_and(|at| at <= chrono::Utc::now())),
+        http_version: None,
+        unresolved_variables,
+        captured: Vec::new(),
+        test_results: Vec::new(), // Tests run on fresh responses only
+        script_logs,
+        wire_log: None,
+        trace: None,
+        large_body: None,
+        cookies: Vec::new(),
+        throttled_ms: None,
+        attempts: 0,
+    })
+}
+
+impl ApiRequest {
+    // 🎓 TEACHING: Turn a saved request row back into something we can send
+    fn from_saved(saved: &database::Request) -> Result<Self, AppError> {
+        let params: Vec<params::QueryParam> = params::parse_query_params(&saved.params).map_err(AppError::from)?;
+        let headers: HashMap<String, String> = serde_json::from_str(&saved.headers).map_err(AppError::from)?;
+        let path_params: HashMap<String, String> =
+            serde_json::from_str(&saved.path_params).map_err(AppError::from)?;
+        let settings = request_settings::parse_settings(saved.settings.as_deref()).map_err(AppError::from)?;
+
+        Ok(ApiRequest {
+            method: saved.method.clone(),
+            url: saved.url.clone(),
+            params,
+            path_params,
+            headers,
+            body: saved.body_str.clone(),
+            body_type: Some(saved.body_type.clone()),
+            graphql_variables: saved.graphql_variables.clone(),
+            graphql_operation_name: saved.graphql_operation_name.clone(),
+            auth_type: saved.auth_type.clone(),
+            auth_data: saved.auth_data.clone(),
+            collection_id: Some(saved.collection_id.clone()),
+            request_id: Some(saved.id.clone()),
+            captures: capture::parse_rules(saved.captures.as_deref()).map_err(AppError::from)?,
+            scripts: scripting::parse_scripts(saved.scripts.as_deref()).map_err(AppError::from)?,
+            use_cache: settings.use_cache,
+            cache_duration: settings.cache_duration,
+            cache_ignore_headers: settings.cache_ignore_headers,
+            cache_mode: settings.cache_mode,
+            http_version: settings.http_version,
+            timeout_ms: settings.timeout_ms,
+            follow_redirects: settings.follow_redirects,
+            max_redirects: settings.max_redirects,
+            proxy_url: settings.proxy_url,
+            verify_tls: settings.verify_tls,
+            use_cookie_jar: settings.use_cookie_jar,
+            disabled_default_headers: settings.disabled_default_headers,
+            retry: settings.retry,
+            ..Default::default()
+        })
+    }
+
+    // The app settings that apply to every send
+    fn apply_app_settings(&mut self, app_settings: &settings::AppSettings) {
+        self.apply_defaults(&app_settings.request_defaults);
+        self.offline = app_settings.offline;
+        self.invalidate_cache_on_write = app_settings.invalidate_cache_on_write;
+        self.trace_context = app_settings.trace_context.clone();
+    }
+
+    // Fill in whatever the request leaves unset from the app-wide defaults
+    fn apply_defaults(&mut self, defaults: &settings::RequestDefaults) {
+        self.timeout_ms = self.timeout_ms.or(defaults.timeout_ms);
+        self.follow_redirects = self.follow_redirects.or(defaults.follow_redirects);
+        self.max_redirects = self.max_redirects.or(defaults.max_redirects);
+        self.proxy_url = self.proxy_url.take().or_else(|| defaults.proxy_url.clone());
+        self.verify_tls = self.verify_tls.or(defaults.verify_tls);
+        self.http_version = self.http_version.take().or_else(|| defaults.http_version.clone());
+        self.use_cookie_jar = self.use_cookie_jar.or(defaults.use_cookie_jar);
+        self.retry = self.retry.take().or_else(|| defaults.retry.clone());
+        self.default_headers = defaults.default_headers.clone();
+    }
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)] // Tauri passes the managed state in as arguments
 async fn send_api_request(
     request: ApiRequest,
+    stream_id: Option<String>, // Set to receive NDJSON/chunked bodies incrementally as `response-stream` events
+    execution_id: Option<String>, // Id for the `request:*` progress events; made up if not given
+    app: tauri::AppHandle,
     db_state: State<'_, DatabaseState>,
-) -> Result<ApiResponse, String> {
+    session_cache: State<'_, session::SessionCache>,
+    streams: State<'_, streaming::StreamRegistry>,
+    responses: State<'_, response_diff::ResponseStore>,
+) -> Result<ApiResponse, AppError> {
+    use tauri::Emitter;
+
     // 🎓 TEACHING: Now we support variable interpolation in requests
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
+    let app_settings = app_dirs(&app)
+        .ok()
+        .and_then(|(config_dir, _)| settings::AppSettings::load(&config_dir).ok())
+        .unwrap_or_default();
+    let (large_response_bytes, body_dir) = (app_settings.large_response_bytes(), response_body_dir(&app));
+
+    let progress_app = app.clone();
+    let progress = progress::ProgressReporter::new(
+        execution_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        std::sync::Arc::new(move |event| {
+            let _ = progress_app.emit(event.name(), &event);
+        }),
+    );
+    let stream = stream_id.as_ref().map(|stream_id| streaming::StreamTarget {
+        stream_id: stream_id.clone(),
+        sink: std::sync::Arc::new(move |event| {
+            let _ = app.emit("response-stream", event);
+        }),
+        stop: streams.start(stream_id),
+    });
+    let after_response = AfterResponse::new(&request);
+    let request_id = request.request_id.clone();
+    let (method, url) = (request.method.to_uppercase(), request.url.clone());
+    progress.emit(progress::ProgressEvent::Started {
+        execution_id: progress.execution_id.clone(),
+        request_id: request_id.clone(),
+        method: method.clone(),
+        url: url.clone(),
+    });
+    let started = std::time::Instant::now();
+    let result = execute_request(&db, &session_cache, request, stream.as_ref(), Some(&progress)).await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    if let Some(stream_id) = &stream_id {
+        streams.finish(stream_id);
+    }
+    if let Some(request_id) = &request_id {
+        analytics::record(&db, request_id, duration_ms, result.as_ref().ok()).await;
+        if let Err(e) = db.set_request_last_used(request_id, chrono::Utc::now()).await {
+            logging::warn(format!("Failed to update recently used requests: {}", e));
+        }
+    }
+
+    // Errors can echo the interpolated URL or headers, so keep secrets out of them
+    let result = match result {
+        Ok(response) => Ok(response),
+        Err(e) => {
+            let redactor = db.secret_redactor().await.unwrap_or_default();
+            Err(e.map_message(|message| redactor.redact(message)))
+        }
+    };
+    let response = result.as_ref().ok();
+    progress.emit(progress::ProgressEvent::Finished {
+        execution_id: progress.execution_id.clone(),
+        status: response.map(|response| response.status),
+        duration_ms,
+        received_bytes: response.map(|response| response.body.len() as u64),
+        from_cache: response.is_some_and(|response| response.from_cache == Some(true)),
+        error: result.as_ref().err().cloned(),
+    });
+    if logging::enabled() {
+        let redactor = db.secret_redactor().await.unwrap_or_default();
+        logging::send(&logging::SendRecord {
+            method,
+            url: redactor.redact(&url),
+            request_id,
+            status: response.map(|response| response.status),
+            duration_ms,
+            response_bytes: response.map(|response| response.body.len() as u64),
+            from_cache: response.is_some_and(|response| response.from_cache == Some(true)),
+            error: result.as_ref().err().cloned(),
+        });
+    }
+
+    let mut response = result?;
+    after_response.apply(&db, &mut response).await?;
+
+    // Big bodies go to a file and only their start goes back; the UI pages through the rest
+    let large = response.body.len() as u64 > large_response_bytes;
+    let spilled = match body_dir {
+        Ok(dir) if large => large_body::StoredBody::spill(&dir, &response.body)
+            .map_err(|e| logging::warn(format!("Could not write a large response body to disk: {}", e)))
+            .ok(),
+        _ => None,
+    };
+    let body = match spilled {
+        Some(stored) => {
+            let preview = large_body::preview(&response.body, large_body::PREVIEW_BYTES).to_string();
+            response.large_body = Some(large_body::LargeBody {
+                size: stored.size(),
+                preview_bytes: preview.len() as u64,
+            });
+            response.body = preview;
+            stored
+        }
+        None => large_body::StoredBody::Inline(response.body.clone()),
+    };
+    responses.keep(
+        &progress.execution_id,
+        response_diff::ResponseSnapshot {
+            status: response.status,
+            headers: response.headers.clone(),
+            body,
+        },
+    );
+    Ok(response)
+}
+
+// 🎓 TEACHING: The environment a send uses: the active one, unless the request names another
+async fn send_environment(
+    db: &Database,
+    environment_id: Option<&str>,
+) -> Result<Option<database::Environment>, AppError> {
+    match environment_id {
+        Some(id) => match db.get_environment_by_id(id).await.map_err(AppError::from)? {
+            Some(environment) => Ok(Some(environment)),
+            None => Err(AppError::not_found(format!("Environment not found: {}", id))),
+        },
+        None => db.get_active_environment().await.map_err(AppError::from),
+    }
+}
+
+// 🎓 TEACHING: What runs once a response is back: capture rules, then the tests script.
+// Taken from the request up front, since sending consumes it.
+struct AfterResponse {
+    captures: Vec<capture::CaptureRule>,
+    tests_script: Option<scripting::Script>,
+    collection_id: Option<String>,
+    request_id: Option<String>,
+    environment_id: Option<String>,
+    variable_overrides: HashMap<String, String>,
+}
+
+impl AfterResponse {
+    fn new(request: &ApiRequest) -> Self {
+        AfterResponse {
+            captures: request.captures.clone(),
+            tests_script: request.scripts.tests.clone(),
+            collection_id: request.collection_id.clone(),
+            request_id: request.request_id.clone(),
+            environment_id: request.environment_id.clone(),
+            variable_overrides: request.variable_overrides.clone(),
+        }
+    }
+
+    async fn apply(self, db: &Database, response: &mut ApiResponse) -> Result<(), AppError> {
+        let environment_id = send_environment(db, self.environment_id.as_deref()).await?.map(|env| env.id);
+
+        // Captured values go into the environment, ready for the next request
+        response.captured = capture::apply(&self.captures, &response.headers, &response.body);
+        for result in response.captured.iter_mut() {
+            if let Some(value) = &result.value {
+                if let Err(e) = db
+                    .set_environment_variable(environment_id.as_deref(), &result.variable, value.clone())
+                    .await
+                {
+                    result.error = Some(e.to_string());
+                }
+            }
+        }
+
+        // The tests script runs last, so `get_var` already sees captured values
+        if let Some(script) = &self.tests_script {
+            let mut variables: HashMap<String, String> = db
+                .resolve_variables_for(environment_id.as_deref(), self.collection_id.as_deref(), self.request_id.as_deref())
+                .await
+                .map_err(AppError::from)?
+                .into_iter()
+                .map(|variable| (variable.key, variable.value))
+                .collect();
+            variables.extend(self.variable_overrides);
+            let modules = db.get_script_modules().await.map_err(AppError::from)?;
+            let outcome = scripting::run_tests(
+                script,
+                response.status,
+                &response.headers,
+                &response.body,
+                &variables,
+                &modules,
+            );
+            for (key, value) in outcome.variables_set {
+                if let Err(e) = db.set_environment_variable(environment_id.as_deref(), &key, value).await {
+                    response.script_logs.push(format!("Could not set {}: {}", key, e));
+                }
+            }
+            response.test_results = outcome.tests;
+            response.script_logs.extend(outcome.logs);
+        }
+        Ok(())
+    }
+}
+
+// 🎓 TEACHING: The actual send pipeline, separate from the command so that
+// session auth can run a saved login request through the exact same steps.
+async fn execute_request(
+    db: &Database,
+    session_cache: &session::SessionCache,
+    mut request: ApiRequest,
+    stream: Option<&streaming::StreamTarget>,
+    progress: Option<&progress::ProgressReporter>,
+) -> Result<ApiResponse, AppError> {
+    // 🎓 TEACHING: Every send comes through here, from the editor or not (collection runs,
+    // monitors, workflows, session logins), so this is where the app settings are applied
+    let app_settings = db
+        .settings_dir()
+        .and_then(|config_dir| settings::AppSettings::load(config_dir).ok())
+        .unwrap_or_default();
+    request.apply_app_settings(&app_settings);
+
+    // 🎓 TEACHING: Requests without their own auth inherit it from their folder, then collection
+    if matches!(request.auth_type.as_deref(), None | Some("inherit")) {
+        if let Some(collection_id) = &request.collection_id {
+            if let Some((auth_type, auth_data)) = db
+                .resolve_inherited_auth(collection_id)
+                .await
+                .map_err(AppError::from)?
+            {
+                request.auth_type = Some(auth_type);
+                request.auth_data = auth_data;
+            }
+        }
+    }
+
+    if request.auth_data.as_deref().is_some_and(secrets::is_encrypted) {
+        return Err(AppError::auth("Auth data is locked; unlock the workspace to send this request"));
+    }
+
+    // Host overrides from the environment apply unless the request sets its own
+    let environment = send_environment(db, request.environment_id.as_deref()).await?;
+    if let Some(environment) = &environment {
+        let env_overrides: HashMap<String, String> =
+            serde_json::from_str(&environment.host_overrides).map_err(AppError::from)?;
+        for (host, ip) in env_overrides {
+            request.host_overrides.entry(host).or_insert(ip);
+        }
+    }
+
+    // 🎓 TEACHING: Default headers from the app, the environment and the folders go in before
+    // the pre-request script and interpolation, so they can use variables like any header
+    let mut header_levels = vec![std::mem::take(&mut request.default_headers)];
+    if let Some(environment) = &environment {
+        header_levels.push(default_headers::parse(Some(&environment.default_headers)).map_err(AppError::from)?);
+    }
+    if let Some(collection_id) = &request.collection_id {
+        header_levels.push(db.resolve_default_headers(collection_id).await.map_err(AppError::from)?);
+    }
+    let defaults = default_headers::merge(header_levels);
+    default_headers::apply(&mut request.headers, defaults, &request.disabled_default_headers);
+    let environment_id = environment.map(|env| env.id);
+
+    // 🎓 TEACHING: Look variables up once for the whole send, honoring request/folder/collection scopes
+    let mut resolved = db
+        .resolve_variables_for(
+            environment_id.as_deref(),
+            request.collection_id.as_deref(),
+            request.request_id.as_deref(),
+        )
+        .await
+        .map_err(AppError::from)?;
+    for (key, value) in &request.variable_overrides {
+        resolved.retain(|variable| &variable.key != key);
+        resolved.push(database::Variable::ephemeral(key, value));
+    }
+
+    // 🎓 TEACHING: The pre-request script sees the request before interpolation, and the
+    // variables it sets are saved and used for this very send
+    let mut script_logs = Vec::new();
+    if let Some(script) = &request.scripts.pre_request {
+        let variables: HashMap<String, String> =
+            resolved.iter().map(|variable| (variable.key.clone(), variable.value.clone())).collect();
+        let mut script_request = scripting::ScriptRequest {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            headers: request.headers.clone(),
+            body: request.body.clone(),
+        };
+        let modules = db.get_script_modules().await.map_err(AppError::from)?;
+        let outcome = scripting::run_pre_request(script, &mut script_request, &variables, &modules)
+            .map_err(|e| AppError::validation(format!("Pre-request script failed: {}", e)))?;
+        request.method = script_request.method;
+        request.url = script_request.url;
+        request.headers = script_request.headers;
+        request.body = script_request.body;
+
+        for (key, value) in outcome.variables_set {
+            db.set_environment_variable(environment_id.as_deref(), &key, value.clone())
+                .await
+                .map_err(AppError::from)?;
+            resolved.retain(|variable| variable.key != key);
+            resolved.push(database::Variable::ephemeral(&key, &value));
+        }
+        script_logs = outcome.logs;
+    }
+
+    let mut unresolved = Vec::new();
 
     // Interpolate variables in the URL
-    let interpolated_url = db.interpolate_string(&request.url).await.map_err(|e| e.to_string())?;
+    let interpolated_url = db.interpolate_with(&resolved, &request.url).await.map_err(AppError::from)?;
+    placeholders::note_unresolved(&mut unresolved, "URL", &interpolated_url);
+
+    // 🎓 TEACHING: Fill path params after variables, so `{{base_url}}/users/:id` works
+    let mut path_params = HashMap::new();
+    for (key, value) in &request.path_params {
+        let interpolated_value = db.interpolate_with(&resolved, value).await.map_err(AppError::from)?;
+        placeholders::note_unresolved(&mut unresolved, &format!("path param {}", key), &interpolated_value);
+        path_params.insert(key.clone(), interpolated_value);
+    }
+    let interpolated_url = params::substitute_path_params(&interpolated_url, &path_params)
+        .map_err(AppError::from)?;
+
+    // 🎓 TEACHING: Interpolate query params, then append the enabled ones to the URL
+    let mut query_params = Vec::with_capacity(request.params.len());
+    for param in &request.params {
+        let key = db.interpolate_with(&resolved, &param.key).await.map_err(AppError::from)?;
+        let value = db.interpolate_with(&resolved, &param.value).await.map_err(AppError::from)?;
+        if param.enabled {
+            let location = format!("query param {}", key);
+            placeholders::note_unresolved(&mut unresolved, &location, &key);
+            placeholders::note_unresolved(&mut unresolved, &location, &value);
+        }
+        query_params.push(params::QueryParam {
+            key,
+            value,
+            enabled: param.enabled,
+        });
+    }
+    let request_url = params::append_query_params(
+        &interpolated_url,
+        &query_params,
+        request.param_encoding.as_deref(),
+    )
+    .map_err(AppError::from)?;
+
+    // 🎓 TEACHING: Offline mode never touches the network - any cached copy is used, however old
+    if request.offline {
+        let headers_json = cache_key_headers(&request.headers, request.cache_ignore_headers.as_deref());
+        let body_content = request.body.as_deref().unwrap_or("");
+        let request_hash = Database::generate_request_hash(&request.method, &request_url, &headers_json, body_content);
+        return match db.get_cached_response_by_hash(&request_hash).await.map_err(AppError::from)? {
+            Some(cached) => cached_api_response(cached, unresolved, script_logs),
+            None => Err(AppError::new(
+                ErrorKind::Offline,
+                format!("No cached copy of {} {} to use offline", request.method, request_url),
+            )),
+        };
+    }
+
+    // 🎓 TEACHING: Cache policies decide for requests that don't choose caching themselves
+    if request.use_cache.is_none() {
+        if let Some(host) = reqwest::Url::parse(&request_url).ok().and_then(|url| url.host_str().map(str::to_string)) {
+            let policy = db
+                .cache_policy_for(&host, request.collection_id.as_deref())
+                .await
+                .map_err(AppError::from)?;
+            if let Some(policy) = policy {
+                request.use_cache = Some(!policy.bypass);
+                match policy.ttl_seconds {
+                    Some(ttl) => {
+                        request.cache_duration.get_or_insert(ttl);
+                    }
+                    None => {
+                        request.cache_mode.get_or_insert_with(|| "http".to_string());
+                    }
+                }
+            }
+        }
+    }
 
     // 🎓 TEACHING: Check cache first if caching is enabled
-    let use_cache = request.use_cache.unwrap_or(false);
+    let http_caching = match request.cache_mode.as_deref() {
+        None | Some("fixed") => false,
+        Some("http") => true,
+        Some(other) => return Err(AppError::validation(format!("Unknown cache mode: {}", other))),
+    };
+    // Only GET and HEAD responses are cacheable by HTTP's rules
+    let use_cache = request.use_cache.unwrap_or(false)
+        && (!http_caching || matches!(request.method.to_uppercase().as_str(), "GET" | "HEAD"));
+    // A stale "http" entry, sent back to the server to check whether it's still current
+    let mut revalidating: Option<database::ResponseCache> = None;
     if use_cache {
-        let headers_json = serde_json::to_string(&request.headers).map_err(|e| e.to_string())?;
+        let headers_json = cache_key_headers(&request.headers, request.cache_ignore_headers.as_deref());
         let body_content = request.body.as_deref().unwrap_or("");
-        
-        if let Ok(Some(cached)) = db.get_cached_response(
-            &request.method,
-            &interpolated_url,
-            &headers_json,
-            body_content,
-        ).await {
-            let cached_headers: HashMap<String, String> = 
-                serde_json::from_str(&cached.response_headers).map_err(|e| e.to_string())?;
-            
-            return Ok(ApiResponse {
-                status: cached.response_status,
-                headers: cached_headers,
-                body: cached.response_body,
-                from_cache: Some(true),
-                cache_time: Some(cached.cache_time.to_rfc3339()),
-            });
+
+        let cached = if http_caching {
+            let request_hash =
+                Database::generate_request_hash(&request.method, &request_url, &headers_json, body_content);
+            match db.get_cached_response_by_hash(&request_hash).await {
+                Ok(Some(cached)) if cached.expires_at.is_some_and(|at| at <= chrono::Utc::now()) => {
+                    revalidating = Some(cached);
+                    None
+                }
+                Ok(cached) => cached,
+                Err(_) => None,
+            }
+        } else {
+            db.get_cached_response(&request.method, &request_url, &headers_json, body_content)
+                .await
+                .ok()
+                .flatten()
+        };
+        if let Some(cached) = cached {
+            // Only the URL has been checked for unresolved variables at this point
+            return cached_api_response(cached, unresolved, script_logs);
+        }
+    }
+
+    // 🎓 TEACHING: Build the final body up front, so signing auth (AWS, plugins) sees exactly what is sent
+    let request_body = if request.body_type.as_deref() == Some("graphql") {
+        if !request.headers.keys().any(|k| k.eq_ignore_ascii_case("content-type")) {
+            request.headers.insert("Content-Type".to_string(), "application/json".to_string());
+        }
+        let query = db
+            .interpolate_with(&resolved, request.body.as_deref().unwrap_or(""))
+            .await
+            .map_err(AppError::from)?;
+        let mut variables =
+            graphql::parse_variables(request.graphql_variables.as_deref()).map_err(AppError::from)?;
+        if let Some(variables) = variables.as_mut() {
+            for value in graphql::string_values_mut(variables) {
+                *value = db.interpolate_with(&resolved, value).await.map_err(AppError::from)?;
+            }
+        }
+        Some(graphql::build_payload(&query, variables, request.graphql_operation_name.as_deref()))
+    } else {
+        match &request.body {
+            Some(body) if request.body_type.as_deref() == Some("json") => {
+                Some(db.interpolate_json_with(&resolved, body).await.map_err(AppError::from)?)
+            }
+            Some(body) => Some(db.interpolate_with(&resolved, body).await.map_err(AppError::from)?),
+            None => None,
         }
+    };
+    if let Some(body) = &request_body {
+        placeholders::note_unresolved(&mut unresolved, "body", body);
     }
 
-    let client = reqwest::Client::new();
+    let client = build_http_client(&request)?;
 
     let method = match request.method.to_uppercase().as_str() {
         "GET" => reqwest::Method::GET,
@@ -87,17 +850,38 @@ async fn send_api_request(
         "PATCH" => reqwest::Method::PATCH,
         "HEAD" => reqwest::Method::HEAD,
         "OPTIONS" => reqwest::Method::OPTIONS,
-        _ => return Err("Unsupported HTTP method".to_string()),
+        _ => return Err(AppError::validation("Unsupported HTTP method")),
     };
 
-    let mut req_builder = client.request(method, &interpolated_url).query(&request.params);
+    let mut req_builder = client.request(method, &request_url);
+
+    // Cookies from the jar join a Cookie header the request sets itself, rather than adding a second one
+    let use_cookie_jar = request.use_cookie_jar.unwrap_or(false);
+    let mut jar_cookies = if use_cookie_jar {
+        db.cookies_for_url(&request_url).await.map_err(AppError::from)?
+    } else {
+        Vec::new()
+    };
 
     // 🎓 TEACHING: Interpolate variables in headers
     for (key, value) in &request.headers {
-        let interpolated_value = db.interpolate_string(value).await.map_err(|e| e.to_string())?;
+        let mut interpolated_value = db.interpolate_with(&resolved, value).await.map_err(AppError::from)?;
+        placeholders::note_unresolved(&mut unresolved, &format!("header {}", key), &interpolated_value);
+        if key.eq_ignore_ascii_case("cookie") && !jar_cookies.is_empty() {
+            interpolated_value = cookies::merge_cookie_header(&interpolated_value, &std::mem::take(&mut jar_cookies));
+        }
         req_builder = req_builder.header(key, &interpolated_value);
     }
+    if !jar_cookies.is_empty() {
+        req_builder = req_builder.header("Cookie", cookies::merge_cookie_header("", &jar_cookies));
+    }
+
+    // 🎓 TEACHING: Strict mode stops here, before anything goes over the wire
+    if request.strict_variables.unwrap_or(false) && !unresolved.is_empty() {
+        return Err(AppError::validation(placeholders::describe(&unresolved)));
+    }
 
+    let mut pending_digest: Option<auth::DigestAuthConfig> = None;
     if let Some(auth_type) = request.auth_type {
         match auth_type.as_str() {
             "basic" => {
@@ -105,7 +889,7 @@ async fn send_api_request(
                     // 🎓 TEACHING: For Basic Auth, we expect a JSON string with "username" and "password" fields.
                     // We need to parse this JSON and then apply the basic authentication to the request.
                     let auth: HashMap<String, String> =
-                        serde_json::from_str(&auth_data).map_err(|e| e.to_string())?;
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
                     let username = auth
                         .get("username")
                         .ok_or("Username not found in auth_data")?;
@@ -119,7 +903,7 @@ async fn send_api_request(
                 if let Some(auth_data) = request.auth_data {
                     // 🎓 TEACHING: For Bearer Auth, we expect the token to be in the "token" field of the JSON string.
                     let auth: HashMap<String, String> =
-                        serde_json::from_str(&auth_data).map_err(|e| e.to_string())?;
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
                     let token = auth.get("token").ok_or("Token not found in auth_data")?;
                     req_builder = req_builder.bearer_auth(token);
                 }
@@ -129,7 +913,7 @@ async fn send_api_request(
                     // 🎓 TEACHING: For API Key Auth, we expect "key", "value", and "in" fields.
                     // The "in" field can be either "header" or "query".
                     let auth: HashMap<String, String> =
-                        serde_json::from_str(&auth_data).map_err(|e| e.to_string())?;
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
                     let key = auth.get("key").ok_or("Key not found in auth_data")?;
                     let value = auth.get("value").ok_or("Value not found in auth_data")?;
                     let in_ = auth.get("in").ok_or("In not found in auth_data")?;
@@ -146,31 +930,57 @@ async fn send_api_request(
                 // We expect the auth_data to contain an access_token field
                 if let Some(auth_data) = request.auth_data {
                     let auth: HashMap<String, String> =
-                        serde_json::from_str(&auth_data).map_err(|e| e.to_string())?;
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
                     let access_token = auth.get("access_token").ok_or("Access token not found in auth_data")?;
                     req_builder = req_builder.bearer_auth(access_token);
                 }
             }
+            "jwt-bearer" => {
+                // 🎓 TEACHING: JWT Bearer grant - sign an assertion and trade it for an access token
+                if let Some(auth_data) = request.auth_data {
+                    let jwt_config: oauth::JwtBearerConfig =
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
+                    // Reuse the access token until it expires instead of signing a new assertion per send
+                    let cache_key = jwt_config.cache_key();
+                    let access_token = match session_cache.get(&cache_key) {
+                        Some(access_token) => access_token,
+                        None => {
+                            let token = jwt_config.request_token().await.map_err(AppError::from)?;
+                            if let Some(ttl) = token.reuse_for() {
+                                session_cache.insert(&cache_key, token.access_token.clone(), ttl);
+                            }
+                            token.access_token
+                        }
+                    };
+                    req_builder = req_builder.bearer_auth(access_token);
+                }
+            }
             "digest" => {
                 // 🎓 TEACHING: Digest Authentication
+                // Without a nonce we negotiate it from the server's 401 challenge when sending
                 if let Some(auth_data) = request.auth_data {
                     let digest_config: auth::DigestAuthConfig =
-                        serde_json::from_str(&auth_data).map_err(|e| e.to_string())?;
-                    let auth_header = digest_config.generate_authorization_header()
-                        .map_err(|e| e.to_string())?;
-                    req_builder = req_builder.header("Authorization", auth_header);
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
+                    if digest_config.needs_challenge() {
+                        pending_digest = Some(digest_config);
+                    } else {
+                        let body = request_body.as_deref().unwrap_or_default().as_bytes();
+                        let auth_header = digest_config.generate_authorization_header(Some(body))
+                            .map_err(AppError::from)?;
+                        req_builder = req_builder.header("Authorization", auth_header);
+                    }
                 }
             }
             "oauth1" => {
                 // 🎓 TEACHING: OAuth 1.0 Authentication
                 if let Some(auth_data) = request.auth_data {
                     let oauth1_config: auth::OAuth1Config =
-                        serde_json::from_str(&auth_data).map_err(|e| e.to_string())?;
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
                     let auth_header = oauth1_config.generate_authorization_header(
                         &request.method,
                         &interpolated_url,
-                        &request.params
-                    ).map_err(|e| e.to_string())?;
+                        &params::enabled_param_pairs(&query_params)
+                    ).map_err(AppError::from)?;
                     req_builder = req_builder.header("Authorization", auth_header);
                 }
             }
@@ -178,27 +988,25 @@ async fn send_api_request(
                 // 🎓 TEACHING: AWS Signature V4 Authentication
                 if let Some(auth_data) = request.auth_data {
                     let aws_config: auth::AwsSignatureConfig =
-                        serde_json::from_str(&auth_data).map_err(|e| e.to_string())?;
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
+                    let aws_config = aws_config.resolve_credentials().map_err(AppError::from)?;
                     
                     // Get current headers from the request builder
                     let mut headers = reqwest::header::HeaderMap::new();
                     for (key, value) in &request.headers {
-                        let interpolated_value = db.interpolate_string(value).await.map_err(|e| e.to_string())?;
+                        let interpolated_value = db.interpolate_with(&resolved, value).await.map_err(AppError::from)?;
                         headers.insert(
-                            reqwest::header::HeaderName::from_bytes(key.as_bytes()).map_err(|e| e.to_string())?,
-                            reqwest::header::HeaderValue::from_str(&interpolated_value).map_err(|e| e.to_string())?
+                            reqwest::header::HeaderName::from_bytes(key.as_bytes()).map_err(AppError::from)?,
+                            reqwest::header::HeaderValue::from_str(&interpolated_value).map_err(AppError::from)?
                         );
                     }
                     
-                    let body_content = request.body.as_deref().unwrap_or("");
-                    let interpolated_body = db.interpolate_string(body_content).await.map_err(|e| e.to_string())?;
-                    
                     let signed_headers = aws_config.generate_authorization_header(
                         &request.method,
-                        &interpolated_url,
+                        &request_url,
                         &headers,
-                        &interpolated_body
-                    ).map_err(|e| e.to_string())?;
+                        request_body.as_deref().unwrap_or("")
+                    ).map_err(AppError::from)?;
                     
                     // Apply the signed headers to the request
                     for (name, value) in signed_headers.iter() {
@@ -206,42 +1014,267 @@ async fn send_api_request(
                     }
                 }
             }
+            "plugin" => {
+                // 🎓 TEACHING: Custom auth plugin - a WASM module computes the headers to add
+                if let Some(auth_data) = request.auth_data {
+                    let plugin_config: plugin::PluginAuthConfig =
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
+                    let wasm = db
+                        .get_auth_plugin_wasm(&plugin_config.plugin)
+                        .await
+                        .map_err(AppError::from)?
+                        .ok_or_else(|| {
+                            AppError::not_found(format!("Auth plugin {} is not installed", plugin_config.plugin))
+                        })?;
+
+                    let mut headers = HashMap::new();
+                    for (key, value) in &request.headers {
+                        let interpolated_value = db.interpolate_with(&resolved, value).await.map_err(AppError::from)?;
+                        headers.insert(key.clone(), interpolated_value);
+                    }
+                    let plugin_request = plugin::PluginRequest {
+                        method: request.method.clone(),
+                        url: request_url.clone(),
+                        headers,
+                        body: request_body.clone().unwrap_or_default(),
+                        config: plugin_config.config,
+                    };
+                    let added_headers = plugin::run(&wasm, &plugin_request).map_err(AppError::from)?;
+                    for (name, value) in added_headers {
+                        req_builder = req_builder.header(name, value);
+                    }
+                }
+            }
+            "session" => {
+                // 🎓 TEACHING: Session auth - reuse the cached value or run the login request first
+                if let Some(auth_data) = request.auth_data {
+                    let session_config: session::SessionAuthConfig =
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
+                    let cache_key = session_config.cache_key(environment_id.as_deref());
+                    let value = match session_cache.get(&cache_key) {
+                        Some(value) => value,
+                        None => {
+                            let value =
+                                run_session_login(db, session_cache, &session_config, environment_id.clone()).await?;
+                            session_cache.insert(&cache_key, value.clone(), session_config.ttl());
+                            value
+                        }
+                    };
+                    req_builder = session_config.inject(req_builder, &value).map_err(AppError::from)?;
+                }
+            }
             _ => {} // No other auth types are supported yet
         }
     }
 
-    // 🎓 TEACHING: Interpolate variables in request body
-    if let Some(ref body) = request.body {
-        let interpolated_body = db.interpolate_string(body).await.map_err(|e| e.to_string())?;
-        req_builder = req_builder.body(interpolated_body);
+    // 🎓 TEACHING: Trace headers go on last, so they're never part of a cache key or a signature
+    let trace_context = match &request.collection_id {
+        Some(collection_id) => db.resolve_trace_context(collection_id).await.map_err(AppError::from)?,
+        None => None,
+    }
+    .unwrap_or_else(|| request.trace_context.clone());
+    let (trace_headers, trace) = trace::headers(&trace_context, &request.headers);
+    for (name, value) in trace_headers {
+        req_builder = req_builder.header(name, value);
+    }
+
+    if let Some(body) = request_body {
+        req_builder = req_builder.body(body);
+    }
+
+    if let Some(cached) = &revalidating {
+        let cached_headers: HashMap<String, String> =
+            serde_json::from_str(&cached.response_headers).map_err(AppError::from)?;
+        for (name, value) in http_cache::validators(&cached_headers) {
+            req_builder = req_builder.header(name, value);
+        }
     }
 
-    let res = req_builder.send().await.map_err(|e| e.to_string())?;
+    // 🎓 TEACHING: Each attempt waits here if the host has a rate limit, so retries can't
+    // outpace it; the last permit is kept until the body has been read, so a concurrency cap
+    // counts the whole exchange
+    let host = reqwest::Url::parse(&request_url).ok().and_then(|url| url.host_str().map(str::to_string));
+    let mut send_permit = None;
+    let mut throttled = std::time::Duration::ZERO;
+
+    // 🎓 TEACHING: With a retry policy, a GET or HEAD that fails to connect or gets a retryable
+    // status is sent again after the backoff or the response's Retry-After. Other methods might
+    // not be safe to repeat, so they're sent once whatever the policy says.
+    let debug = request.debug.unwrap_or(false);
+    let retry = request
+        .retry
+        .as_ref()
+        .filter(|_| request.method.eq_ignore_ascii_case("GET") || request.method.eq_ignore_ascii_case("HEAD"));
+    let mut attempts = 0;
+    let (res, sent_request) = loop {
+        attempts += 1;
+        drop(send_permit.take()); // Frees the concurrency slot before queueing for another
+        send_permit = match &host {
+            Some(host) => db.throttle(host).await.map_err(AppError::from)?,
+            None => None,
+        };
+        throttled += send_permit.as_ref().map_or(std::time::Duration::ZERO, |permit| permit.waited);
+        let next_builder = retry.and_then(|_| req_builder.try_clone());
+        let sent = match &pending_digest {
+            Some(digest_config) => {
+                send_with_digest_challenge(req_builder, digest_config, &request.method, &request_url, debug).await
+            }
+            None => {
+                let sent_request = debug.then(|| wire_log::capture_request(&req_builder)).flatten();
+                req_builder.send().await.map(|res| (res, sent_request)).map_err(AppError::from)
+            }
+        };
+        let status = match &sent {
+            Ok((res, _)) => Some(res.status().as_u16()),
+            Err(e) if e.retryable => None,
+            Err(_) => break sent?, // Not something a retry would fix
+        };
+        let wait = match (retry, next_builder) {
+            (Some(policy), Some(next_builder)) if policy.should_retry(attempts, status) => {
+                let retry_after = sent
+                    .as_ref()
+                    .ok()
+                    .and_then(|(res, _)| res.headers().get(reqwest::header::RETRY_AFTER))
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| runner::parse_retry_after(value, chrono::Utc::now()));
+                let jitter = rand::Rng::gen::<f64>(&mut rand::thread_rng());
+                policy.wait_before_retry(attempts, jitter, retry_after).map(|wait| (wait, next_builder))
+            }
+            _ => None,
+        };
+        match wait {
+            Some((wait, next_builder)) => {
+                let next = attempts + 1;
+                logging::debug(format!("Retrying {} after {} ms (attempt {})", request.method, wait.as_millis(), next));
+                tokio::time::sleep(wait).await;
+                req_builder = next_builder;
+            }
+            None => break sent?,
+        }
+    };
+    let wire_log = sent_request.map(|sent| wire_log::finish(sent, &res));
+    let throttled_ms = Some(throttled.as_millis() as u64).filter(|waited| *waited > 0);
 
     let status = res.status().as_u16();
+    let http_version = format_http_version(res.version());
     let mut headers = HashMap::new();
     for (key, value) in res.headers().iter() {
         headers.insert(key.to_string(), value.to_str().unwrap_or("").to_string());
     }
 
-    let body = res.text().await.map_err(|e| e.to_string())?;
+    // 🎓 TEACHING: Every Set-Cookie header, read against the URL that finally answered (after redirects)
+    let now = chrono::Utc::now();
+    let response_cookies: Vec<cookies::Cookie> = res
+        .headers()
+        .get_all(reqwest::header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| cookies::parse_set_cookie(value, res.url(), now))
+        .collect();
+    if use_cookie_jar {
+        // Best effort, like caching: the response is still good if the jar can't be written
+        let _ = db.store_cookies(&response_cookies).await;
+    }
+
+    if let Some(progress) = progress {
+        progress.headers_received(status, &http_version, &headers, res.content_length());
+    }
+
+    let content_type = headers.get("content-type").map(String::as_str);
+    let transfer_encoding = headers.get("transfer-encoding").map(String::as_str);
+    let mut stopped = false;
+    let body = match stream {
+        // 🎓 TEACHING: Forward the body as it arrives, until it ends or the user stops it
+        Some(target) if streaming::should_stream(content_type, transfer_encoding) => {
+            let mut res = res;
+            let mut decoder = streaming::StreamDecoder::new(streaming::is_ndjson(content_type));
+            let mut received = Vec::new();
+            let mut body_progress = progress.map(|progress| progress.body(res.content_length()));
+            loop {
+                let chunk = tokio::select! {
+                    chunk = res.chunk() => chunk.map_err(AppError::from)?,
+                    _ = target.stop.wait() => None,
+                };
+                let Some(chunk) = chunk else { break };
+                received.extend_from_slice(&chunk);
+                if let Some(body_progress) = body_progress.as_mut() {
+                    body_progress.add(chunk.len());
+                }
+                for event in decoder.push(&target.stream_id, &chunk) {
+                    (target.sink)(event);
+                }
+            }
+            if let Some(body_progress) = body_progress {
+                body_progress.finish();
+            }
+            for event in decoder.finish(&target.stream_id) {
+                (target.sink)(event);
+            }
+            stopped = target.stop.is_stopped();
+            (target.sink)(streaming::StreamEvent::End {
+                stream_id: target.stream_id.clone(),
+                stopped,
+            });
+            String::from_utf8_lossy(&received).to_string()
+        }
+        _ => match progress {
+            Some(progress) => progress::read_text(res, progress).await.map_err(AppError::from)?,
+            None => res.text().await.map_err(AppError::from)?,
+        },
+    };
+
+    // 🎓 TEACHING: A 304 means the stale copy is still current - keep it for as long as the server now says
+    if let Some(cached) = revalidating.filter(|_| status == 304) {
+        let now = chrono::Utc::now();
+        let fresh_for =
+            http_cache::expires_at(200, &headers, now).map_or(0, |at| (at - now).num_seconds().max(0) as u64);
+        let refreshed = db
+            .cache_response(
+                cached.method.clone(),
+                cached.url.clone(),
+                cache_key_headers(&request.headers, request.cache_ignore_headers.as_deref()),
+                request.body.clone().unwrap_or_default(),
+                cached.response_status,
+                cached.response_headers.clone(),
+                cached.response_body.clone(),
+                Some(fresh_for),
+            )
+            .await
+            .unwrap_or(cached);
+        return cached_api_response(refreshed, unresolved, script_logs);
+    }
+
+    // 🎓 TEACHING: A write that went through makes cached reads of the same resource stale
+    if request.invalidate_cache_on_write
+        && matches!(request.method.to_uppercase().as_str(), "POST" | "PUT" | "PATCH" | "DELETE")
+        && (200..300).contains(&status)
+    {
+        // Like storing, this is best effort
+        let _ = db.invalidate_cached_reads(&request_url).await;
+    }
 
-    // 🎓 TEACHING: Store response in cache if caching is enabled
-    if use_cache && request.cache_duration.is_some() {
-        let headers_json = serde_json::to_string(&request.headers).map_err(|e| e.to_string())?;
-        let response_headers_json = serde_json::to_string(&headers).map_err(|e| e.to_string())?;
+    // 🎓 TEACHING: Store response in cache if caching is enabled (a stopped stream is only part of the body)
+    let cache_duration = if http_caching {
+        let now = chrono::Utc::now();
+        http_cache::expires_at(status, &headers, now).map(|at| (at - now).num_seconds().max(0) as u64)
+    } else {
+        request.cache_duration
+    };
+    if use_cache && cache_duration.is_some() && !stopped {
+        let headers_json = cache_key_headers(&request.headers, request.cache_ignore_headers.as_deref());
+        let response_headers_json = serde_json::to_string(&headers).map_err(AppError::from)?;
         let body_content = request.body.as_deref().unwrap_or("");
         
         // Attempt to cache the response, but don't fail if caching fails
         let _ = db.cache_response(
             request.method,
-            interpolated_url,
+            request_url,
             headers_json,
             body_content.to_string(),
             status,
             response_headers_json,
             body.clone(),
-            request.cache_duration,
+            cache_duration,
         ).await;
     }
 
@@ -251,593 +1284,3590 @@ async fn send_api_request(
         body,
         from_cache: Some(false),
         cache_time: None,
+        cache_stale: None,
+        http_version: Some(http_version),
+        unresolved_variables: unresolved,
+        captured: Vec::new(),
+        test_results: Vec::new(),
+        script_logs,
+        wire_log,
+        trace,
+        large_body: None,
+        cookies: response_cookies,
+        throttled_ms,
+        attempts,
     })
 }
 
+// 🎓 TEACHING: Send the saved login request and extract the session value from its response.
+// The login itself can't use session auth, otherwise two requests could log each other in forever.
+async fn run_session_login(
+    db: &Database,
+    session_cache: &session::SessionCache,
+    session_config: &session::SessionAuthConfig,
+    environment_id: Option<String>, // Log in against the same environment as the request
+) -> Result<String, AppError> {
+    let saved = db
+        .get_request_by_id(&session_config.login_request_id)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::not_found(format!("Login request {} not found", session_config.login_request_id)))?;
+
+    let mut login_request = ApiRequest::from_saved(&saved)?;
+    login_request.environment_id = environment_id;
+    if login_request.auth_type.as_deref() == Some("session") {
+        return Err(AppError::validation("A session login request cannot itself use session auth"));
+    }
+
+    // Boxed because execute_request is (indirectly) recursive
+    let response = Box::pin(execute_request(db, session_cache, login_request, None, None)).await?;
+    session_config
+        .extract(response.status, &response.headers, &response.body)
+        .map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Digest auth needs a round trip: send without credentials, read the
+// server's 401 challenge, then retry once with the computed response.
+async fn send_with_digest_challenge(
+    req_builder: reqwest::RequestBuilder,
+    digest_config: &auth::DigestAuthConfig,
+    method: &str,
+    url: &str,
+    debug: bool, // Also capture the request that produced the final response
+) -> Result<(reqwest::Response, Option<wire_log::SentRequest>), AppError> {
+    let retry_builder = req_builder
+        .try_clone()
+        .ok_or("Request body cannot be replayed for Digest authentication")?;
+
+    let first_request = debug.then(|| wire_log::capture_request(&req_builder)).flatten();
+    let first_response = req_builder.send().await.map_err(AppError::from)?;
+    if first_response.status() != reqwest::StatusCode::UNAUTHORIZED {
+        return Ok((first_response, first_request));
+    }
+
+    let challenge = auth::DigestChallenge::from_headers(
+        first_response
+            .headers()
+            .get_all(reqwest::header::WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok()),
+    )
+    .map_err(AppError::from)?;
+
+    // The digest URI is the request target: path plus query
+    let parsed_url = url::Url::parse(url).map_err(AppError::from)?;
+    let uri = match parsed_url.query() {
+        Some(query) => format!("{}?{}", parsed_url.path(), query),
+        None => parsed_url.path().to_string(),
+    };
+
+    // Only auth-int signs the body, so it's read from a copy of the request just for that
+    let body = retry_builder
+        .try_clone()
+        .and_then(|builder| builder.build().ok())
+        .map(|request| request.body().and_then(|body| body.as_bytes()).unwrap_or_default().to_vec());
+    let auth_header = digest_config
+        .with_challenge(&challenge, method, &uri)
+        .generate_authorization_header(body.as_deref())
+        .map_err(AppError::from)?;
+
+    let retry_builder = retry_builder.header("Authorization", auth_header);
+    let retry_request = debug.then(|| wire_log::capture_request(&retry_builder)).flatten();
+    let response = retry_builder.send().await.map_err(AppError::from)?;
+    Ok((response, retry_request))
+}
+
 // #[tauri::command]
 // fn greet(name: &str) -> String {
 //     format!("Hello, {}! You've been greeted from Rust!", name);
 // }
 
+// Settings file and data directory for this platform, from Tauri's path resolver
+fn app_dirs(app: &tauri::AppHandle) -> Result<(std::path::PathBuf, std::path::PathBuf), AppError> {
+    use tauri::Manager;
+    let config_dir = app.path().app_config_dir().map_err(|e| AppError::new(ErrorKind::Io, e.to_string()))?;
+    let data_dir = app.path().app_data_dir().map_err(|e| AppError::new(ErrorKind::Io, e.to_string()))?;
+    Ok((config_dir, data_dir))
+}
+
 // 🎓 TEACHING: This command initializes our database
 #[tauri::command]
-async fn init_database(db_state: State<'_, DatabaseState>) -> Result<String, String> {
-    println!("🚀 Starting database initialization...");
+async fn init_database(app: tauri::AppHandle, db_state: State<'_, DatabaseState>) -> Result<String, AppError> {
+    logging::info("Starting database initialization");
+
+    // 🎓 TEACHING: The database lives in the platform app data directory unless the user
+    // moved it; the working directory may be read-only (e.g. when launched from /Applications)
+    let (config_dir, data_dir) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    let database_path = settings.database_path(&data_dir);
+    if let Some(parent) = database_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| AppError::from(e).context(format!("Cannot create {}", parent.display())))?;
+    }
 
-    // 🎓 TEACHING: Use a more explicit database path
-    // This creates the database file in the current directory
-    let database_url = "sqlite:./openrequest.db?mode=rwc";
+    // Bring over the database older releases kept in the working directory
+    let legacy_path = std::path::Path::new(settings::LEGACY_DATABASE_PATH);
+    let is_default_database =
+        settings.active_workspace_id() == settings::DEFAULT_WORKSPACE_ID && settings.database_path.is_none();
+    if is_default_database && !database_path.exists() && legacy_path.is_file() {
+        logging::info(format!("Copying database from {} to {}", legacy_path.display(), database_path.display()));
+        let legacy = Database::new(&settings::database_url(legacy_path), 1)
+            .await
+            .map_err(|e| AppError::from(e).context("Could not open the previous database"))?;
+        let copied = legacy.backup_to(&database_path).await;
+        legacy.close().await;
+        copied.map_err(|e| AppError::from(e).context("Could not copy the previous database"))?;
+    }
 
-    let database = Database::new(database_url).await.map_err(|e| {
-        let error_msg = format!("Database initialization failed: {}", e);
-        println!("❌ {}", error_msg);
-        error_msg
+    let max_connections = settings.database_max_connections();
+    let mut database = Database::new(&settings::database_url(&database_path), max_connections).await.map_err(|e| {
+        let error = AppError::from(e).context("Database initialization failed");
+        logging::error(&error);
+        error
     })?;
+    database.set_settings_dir(config_dir);
+    database.set_workspace_id(settings.active_workspace_id());
 
     // Store the database in our application state
     *db_state.lock().unwrap() = Some(database);
 
     let success_msg = "✅ Database initialized successfully".to_string();
-    println!("{}", success_msg);
+    logging::info("Database initialized");
     Ok(success_msg)
 }
 
-// 🎓 TEACHING: Fixed version - extract database before await
 #[tauri::command]
-async fn create_collection(
-    name: String,
-    description: Option<String>,
-    parent_id: Option<String>,
+async fn get_database_location(app: tauri::AppHandle) -> Result<settings::DatabaseLocation, AppError> {
+    let (config_dir, data_dir) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(settings.database_location(&data_dir))
+}
+
+// 🎓 TEACHING: Moving the database
+// Copy first, open the copy (which also checks it), record the new location, and only
+// then switch over and delete the old file. Any failure before the switch removes the
+// partial copy and leaves the current database untouched.
+#[tauri::command]
+async fn migrate_database_location(
+    new_path: String,
+    app: tauri::AppHandle,
     db_state: State<'_, DatabaseState>,
-) -> Result<database::Collection, String> {
-    // 🎯 KEY FIX: Extract database from the guard immediately
+) -> Result<settings::DatabaseLocation, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let (config_dir, data_dir) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    let current_path = app_settings.database_path(&data_dir);
+    let target_path = settings::target_database_path(&new_path).map_err(AppError::from)?;
+
+    let moved = async {
+        db.backup_to(&target_path).await?;
+        let mut moved =
+            Database::new(&settings::database_url(&target_path), app_settings.database_max_connections()).await?;
+        moved.carry_over_from(&db);
+        app_settings.set_database_path(&data_dir, &target_path);
+        if let Err(e) = app_settings.save(&config_dir) {
+            moved.close().await;
+            return Err(e);
+        }
+        Ok(moved)
+    }
+    .await;
+    let moved = match moved {
+        Ok(moved) => moved,
+        Err(e) => {
+            if target_path.exists() {
+                let _ = settings::remove_database_files(&target_path);
+            }
+            return Err(AppError::from(e).context("Could not move the database"));
+        }
+    };
+
+    *db_state.lock().unwrap() = Some(moved);
+    db.close().await;
+    if let Err(e) = settings::remove_database_files(&current_path) {
+        // The move itself succeeded; the old copy is just left behind
+        logging::warn(format!("Could not remove old database {}: {}", current_path.display(), e));
+    }
+
+    Ok(app_settings.database_location(&data_dir))
+}
+
+#[tauri::command]
+async fn get_database_max_connections(app: tauri::AppHandle) -> Result<u32, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(settings.database_max_connections())
+}
+
+// 🎓 TEACHING: The pool size is fixed when a pool opens, so the open database is reopened
+// with the new size (keeping its unlocked secrets) before the setting is saved.
+// None goes back to the default.
+#[tauri::command]
+async fn set_database_max_connections(
+    max_connections: Option<u32>,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+) -> Result<u32, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let (config_dir, data_dir) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    app_settings.set_database_max_connections(max_connections).map_err(AppError::from)?;
+    let database_path = app_settings.database_path(&data_dir);
+
+    let mut reopened = Database::new(&settings::database_url(&database_path), app_settings.database_max_connections())
+        .await
+        .map_err(|e| AppError::from(e).context("Could not reopen the database"))?;
+    reopened.carry_over_from(&db);
+    if let Err(e) = app_settings.save(&config_dir) {
+        reopened.close().await;
+        return Err(e.into());
+    }
+
+    *db_state.lock().unwrap() = Some(reopened);
+    db.close().await;
+
+    Ok(app_settings.database_max_connections())
+}
+
+#[tauri::command]
+async fn get_storage_stats(db_state: State<'_, DatabaseState>) -> Result<database::StorageStats, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_storage_stats().await.map_err(AppError::from)
+}
+
+// Gives back the space left by deleted rows (clear the cache or history first); returns
+// how many bytes were freed. Can take a while on a large database.
+#[tauri::command]
+async fn compact_database(db_state: State<'_, DatabaseState>) -> Result<i64, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.compact_database().await.map_err(AppError::from)
+}
+
+// ============ WORKSPACE COMMANDS ============
+
+#[tauri::command]
+async fn list_workspaces(app: tauri::AppHandle) -> Result<Vec<settings::WorkspaceInfo>, AppError> {
+    let (config_dir, data_dir) = app_dirs(&app)?;
+    let app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(app_settings.workspace_list(&data_dir))
+}
+
+// A new workspace starts with an empty database, created the first time it's opened.
+// `database_path` places it somewhere other than the app data directory.
+#[tauri::command]
+async fn create_workspace(
+    name: String,
+    database_path: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<settings::WorkspaceInfo, AppError> {
+    let (config_dir, data_dir) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    let database_path = database_path
+        .map(|path| settings::target_database_path(&path))
+        .transpose()
+        .map_err(AppError::from)?;
+    let workspace = app_settings.add_workspace(&name, database_path).map_err(AppError::from)?;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+    app_settings.workspace(&workspace.id, &data_dir).map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Switching workspaces
+// The other workspace's database is opened (and migrated) before anything changes, so a
+// database that can't be opened leaves the current workspace in place. Session logins are
+// dropped too: a token from one client's API shouldn't be sent from another workspace.
+#[tauri::command]
+async fn switch_workspace(
+    id: String,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    session_cache: State<'_, session::SessionCache>,
+) -> Result<settings::WorkspaceInfo, AppError> {
+    let (config_dir, data_dir) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    let workspace = app_settings.workspace(&id, &data_dir).map_err(AppError::from)?;
+    let database_path = std::path::PathBuf::from(&workspace.database_path);
+    if let Some(parent) = database_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| AppError::from(e).context(format!("Cannot create {}", parent.display())))?;
+    }
+
+    let mut database = Database::new(&settings::database_url(&database_path), app_settings.database_max_connections())
+        .await
+        .map_err(|e| AppError::from(e).context(format!("Could not open workspace {}", workspace.name)))?;
+    database.set_settings_dir(config_dir.clone());
+    database.set_workspace_id(&id);
+    app_settings.active_workspace = (id != settings::DEFAULT_WORKSPACE_ID).then(|| id.clone());
+    if let Err(e) = app_settings.save(&config_dir) {
+        database.close().await;
+        return Err(e.into());
+    }
+
+    let previous = db_state.lock().unwrap().replace(database);
+    if let Some(previous) = previous {
+        previous.close().await;
+    }
+    session_cache.clear();
+
+    app_settings.workspace(&id, &data_dir).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn rename_workspace(
+    id: String,
+    name: String,
+    app: tauri::AppHandle,
+) -> Result<settings::WorkspaceInfo, AppError> {
+    let (config_dir, data_dir) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    app_settings.rename_workspace(&id, &name).map_err(AppError::from)?;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+    app_settings.workspace(&id, &data_dir).map_err(AppError::from)
+}
+
+// The open workspace can't be removed. Its database is kept unless `delete_database` is set.
+#[tauri::command]
+async fn delete_workspace(
+    id: String,
+    delete_database: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<(), AppError> {
+    let (config_dir, data_dir) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    let database_path = app_settings.workspace_database_path(&id, &data_dir);
+    app_settings.remove_workspace(&id).map_err(AppError::from)?;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+
+    if delete_database.unwrap_or(false) && database_path.exists() {
+        settings::remove_database_files(&database_path)
+            .map_err(|e| {
+                let context = format!("Removed the workspace but not its database {}", database_path.display());
+                AppError::from(e).context(context)
+            })?;
+    }
+    Ok(())
+}
+
+// Preferences kept per workspace as a JSON object; `id` defaults to the open workspace
+#[tauri::command]
+async fn get_workspace_settings(
+    id: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    let id = id.unwrap_or_else(|| app_settings.active_workspace_id().to_string());
+    if !app_settings.has_workspace(&id) {
+        return Err(AppError::not_found(format!("Workspace not found: {}", id)));
+    }
+    Ok(app_settings
+        .workspace_settings
+        .get(&id)
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({})))
+}
+
+#[tauri::command]
+async fn update_workspace_settings(
+    id: Option<String>,
+    workspace_settings: serde_json::Value,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, AppError> {
+    if !workspace_settings.is_object() {
+        return Err(AppError::validation("Workspace settings must be a JSON object"));
+    }
+    let (config_dir, _) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    let id = id.unwrap_or_else(|| app_settings.active_workspace_id().to_string());
+    if !app_settings.has_workspace(&id) {
+        return Err(AppError::not_found(format!("Workspace not found: {}", id)));
+    }
+    app_settings.workspace_settings.insert(id, workspace_settings.clone());
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+    Ok(workspace_settings)
+}
+
+// 🎓 TEACHING: Fixed version - extract database before await
+#[tauri::command]
+async fn create_collection(
+    name: String,
+    description: Option<String>,
+    parent_id: Option<String>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Collection, AppError> {
+    // 🎯 KEY FIX: Extract database from the guard immediately
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        // Clone the database (it's cheap - just a connection pool)
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    }; // Lock is released here!
+
+    // Now we can safely await without holding the lock
+    db.create_collection(name, description, parent_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_collections(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Collection>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_collections().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_collection_tree(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::CollectionNode>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_collection_tree().await.map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Saves are checked against `collection.version`; a stale one is a Conflict error
+// carrying the current copy. `force` overwrites it anyway, e.g. after the user chose "keep mine".
+#[tauri::command]
+async fn update_collection(
+    collection: database::Collection,
+    force: Option<bool>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Collection, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.update_collection(collection, force.unwrap_or(false)).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn move_collection(
+    collection_id: String,
+    target_parent_id: Option<String>,
+    position: usize,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Collection, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.move_collection(&collection_id, target_parent_id.as_deref(), position)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_collection(id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_collection(&id).await.map_err(|e| {
+        logging::error(format!("Could not delete collection {}: {}", id, e));
+        AppError::from(e)
+    })
+}
+
+#[tauri::command]
+async fn get_collection_by_id(
+    id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Option<database::Collection>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_collection_by_id(&id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn create_request(
+    collection_id: String,
+    name: String,
+    method: String,
+    url: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Request, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.create_request(collection_id, name, method, url)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_requests_by_collection(
+    collection_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Request>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_requests_by_collection(&collection_id)
+        .await
+        .map_err(AppError::from)
+}
+
+// Checked against `request.version` like update_collection, with the same `force` override
+#[tauri::command]
+async fn update_request(
+    request: database::Request,
+    force: Option<bool>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Request, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.update_request(request, force.unwrap_or(false)).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn move_request(
+    request_id: String,
+    target_collection_id: String,
+    position: usize,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Request, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.move_request(&request_id, &target_collection_id, position)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_request(id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_request(&id).await.map_err(|e| {
+        logging::error(format!("Could not delete request {}: {}", id, e));
+        AppError::from(e)
+    })
+}
+
+#[tauri::command]
+async fn get_request_by_id(
+    id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Option<database::Request>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_request_by_id(&id).await.map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Shared by every exporter - load a collection into the export structure
+async fn build_json_collection(
+    db: &Database,
+    collection_id: &str,
+    include_secrets: bool,
+) -> Result<importer_exporter::JsonCollection, AppError> {
+    // 1. Fetch the collection from the database
+    let collection = db
+        .get_collection_by_id(collection_id)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| "Collection not found".to_string())?;
+
+    // 2. Fetch all requests for that collection
+    let requests = db
+        .get_requests_by_collection(collection_id)
+        .await
+        .map_err(AppError::from)?;
+
+    // 3. Convert database requests to JSON requests
+    let json_requests = requests.into_iter().map(importer_exporter::JsonRequest::from).collect();
+
+    // 4. Create the final JSON collection structure
+    let mut json_collection = importer_exporter::JsonCollection {
+        schema_version: importer_exporter::CURRENT_SCHEMA_VERSION,
+        name: collection.name,
+        description: collection.description,
+        docs: collection.docs,
+        auth_type: collection.auth_type,
+        auth_data: collection.auth_data,
+        requests: json_requests,
+    };
+
+    // 🎓 TEACHING: Exports get shared, so mask secrets unless the user explicitly opts in
+    if !include_secrets {
+        let redactor = db.secret_redactor().await.map_err(AppError::from)?;
+        json_collection = importer_exporter::redact_collection(json_collection, &redactor);
+    }
+
+    Ok(json_collection)
+}
+
+#[tauri::command]
+async fn export_collection_to_json(
+    collection_id: String,
+    include_secrets: Option<bool>, // Secrets and credentials are masked unless this is true
+    db_state: State<'_, DatabaseState>,
+) -> Result<String, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let json_collection = build_json_collection(&db, &collection_id, include_secrets.unwrap_or(false)).await?;
+
+    // 5. Serialize the structure to a JSON string
+    serde_json::to_string_pretty(&json_collection).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn import_collection_from_json(
+    json_str: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Collection, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    // 1. Deserialize the JSON string into our import structure, upgrading older exports
+    let json_collection = importer_exporter::parse_collection(&json_str).map_err(AppError::from)?;
+
+    create_collection_from_json(&db, json_collection).await
+}
+
+// 🎓 TEACHING: Shared by every importer - they all produce a JsonCollection first
+async fn create_collection_from_json(
+    db: &Database,
+    json_collection: importer_exporter::JsonCollection,
+) -> Result<database::Collection, AppError> {
+    // 2. Create the new collection in the database
+    let mut new_collection = db
+        .create_collection(json_collection.name, json_collection.description, None)
+        .await
+        .map_err(AppError::from)?;
+
+    if json_collection.auth_type.is_some() {
+        new_collection.auth_type = json_collection.auth_type;
+        new_collection.auth_data = json_collection.auth_data;
+        new_collection = db
+            .update_collection(new_collection, false)
+            .await
+            .map_err(AppError::from)?;
+    }
+    if json_collection.docs.is_some() {
+        db.set_collection_docs(&new_collection.id, json_collection.docs.clone())
+            .await
+            .map_err(AppError::from)?;
+        new_collection.docs = json_collection.docs;
+    }
+
+    // 3. Iterate over the requests from the JSON and create them
+    for json_req in json_collection.requests {
+        let new_req = db
+            .create_request(
+                new_collection.id.clone(),
+                json_req.name.clone(),
+                json_req.method.clone(),
+                json_req.url.clone(),
+            )
+            .await
+            .map_err(AppError::from)?;
+
+        // 4. Update the request with the additional details from the JSON
+        save_imported_request(db, json_req, new_req).await?;
+    }
+
+    Ok(new_collection)
+}
+
+// Docs aren't part of a normal save, so they're written separately
+async fn save_imported_request(
+    db: &Database,
+    json_req: importer_exporter::JsonRequest,
+    target: database::Request,
+) -> Result<database::Request, AppError> {
+    let request = json_req.apply_to(target);
+    db.set_request_docs(&request.id, request.docs.clone())
+        .await
+        .map_err(AppError::from)?;
+    db.update_request(request, false).await.map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Import into an existing collection instead of creating a new one.
+// Run with `dry_run` first to see the conflicts, then again with an action per conflict.
+#[tauri::command]
+async fn merge_collection_from_json(
+    json_str: String,
+    collection_id: String,
+    options: Option<importer_exporter::MergeOptions>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<importer_exporter::MergeReport, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let json_collection = importer_exporter::parse_collection(&json_str).map_err(AppError::from)?;
+    let options = options.unwrap_or_default();
+
+    let saved_requests = db
+        .get_requests_by_collection(&collection_id)
+        .await
+        .map_err(AppError::from)?;
+    let existing: Vec<(String, importer_exporter::JsonRequest)> = saved_requests
+        .iter()
+        .map(|req| (req.id.clone(), importer_exporter::JsonRequest::from(req.clone())))
+        .collect();
+
+    let mut entries = importer_exporter::plan_merge(&existing, &json_collection.requests, &options)
+        .map_err(AppError::from)?;
+
+    if !options.dry_run {
+        for (entry, json_req) in entries.iter_mut().zip(json_collection.requests) {
+            let target = match entry.action.as_str() {
+                "added" | "duplicated" => Some(
+                    db.create_request(collection_id.clone(), json_req.name.clone(), json_req.method.clone(), json_req.url.clone())
+                        .await
+                        .map_err(AppError::from)?,
+                ),
+                "overwritten" => saved_requests
+                    .iter()
+                    .find(|req| Some(&req.id) == entry.existing_request_id.as_ref())
+                    .cloned(),
+                _ => None,
+            };
+            if let Some(target) = target {
+                let updated = save_imported_request(&db, json_req, target).await?;
+                entry.request_id = Some(updated.id);
+            }
+        }
+    }
+
+    Ok(importer_exporter::MergeReport {
+        collection_id,
+        dry_run: options.dry_run,
+        entries,
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct ImportResult {
+    collection: Option<database::Collection>,
+    environments: Vec<database::Environment>,
+}
+
+// Create whatever a third-party importer produced
+async fn save_import(db: &Database, import: importer_exporter::CollectionImport) -> Result<ImportResult, AppError> {
+    let collection = match import.collection {
+        Some(json_collection) => Some(create_collection_from_json(db, json_collection).await?),
+        None => None,
+    };
+
+    let mut environments = Vec::new();
+    for json_env in import.environments {
+        let environment = db.create_environment(json_env.name).await.map_err(AppError::from)?;
+        for variable in json_env.variables {
+            db.create_variable(Some(environment.id.clone()), variable.key, variable.value, variable.is_secret)
+                .await
+                .map_err(AppError::from)?;
+        }
+        environments.push(environment);
+    }
+
+    Ok(ImportResult { collection, environments })
+}
+
+// 🎓 TEACHING: Import an OpenAPI 3.x or Swagger 2.0 spec (JSON or YAML).
+// Each server URL becomes an environment.
+#[tauri::command]
+async fn import_openapi(
+    spec_text: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<ImportResult, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let import = importer_exporter::collection_from_openapi(&spec_text).map_err(AppError::from)?;
+    save_import(&db, import).await
+}
+
+// 🎓 TEACHING: Import a VS Code REST Client `.http` / `.rest` file.
+// `@name = value` file variables become an environment.
+#[tauri::command]
+async fn import_http_file(
+    file_text: String,
+    collection_name: Option<String>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<ImportResult, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let name = collection_name.unwrap_or_else(|| "REST Client import".to_string());
+    let import = http_file::parse(&file_text, &name).map_err(AppError::from)?;
+    save_import(&db, import).await
+}
+
+// Thunder Client collection or environment export
+#[tauri::command]
+async fn import_thunder_client(
+    json_str: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<ImportResult, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let import = thunder::parse(&json_str).map_err(AppError::from)?;
+    save_import(&db, import).await
+}
+
+// 🎓 TEACHING: Write a collection as a folder of .bru files (one per request) for git
+#[tauri::command]
+async fn export_collection_to_directory(
+    collection_id: String,
+    dir_path: String,
+    include_secrets: Option<bool>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<usize, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let json_collection = build_json_collection(&db, &collection_id, include_secrets.unwrap_or(false)).await?;
+    bru::export_to_directory(&json_collection, std::path::Path::new(&dir_path)).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn import_collection_from_directory(
+    dir_path: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Collection, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let json_collection = bru::import_from_directory(std::path::Path::new(&dir_path)).map_err(AppError::from)?;
+    create_collection_from_json(&db, json_collection).await
+}
+
+// ============ TAG COMMANDS ============
+
+// 🎓 TEACHING: Tags group requests and collections across folders ("smoke-test", "deprecated")
+#[tauri::command]
+async fn create_tag(
+    name: String,
+    color: Option<String>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Tag, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.create_tag(name, color).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_tags(db_state: State<'_, DatabaseState>) -> Result<Vec<database::Tag>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_tags().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn update_tag(
+    tag: database::Tag,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Tag, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.update_tag(tag).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_tag(id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_tag(&id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn tag_request(
+    request_id: String,
+    tag_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.tag_request(&request_id, &tag_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn untag_request(
+    request_id: String,
+    tag_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.untag_request(&request_id, &tag_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn tag_collection(
+    collection_id: String,
+    tag_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.tag_collection(&collection_id, &tag_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn untag_collection(
+    collection_id: String,
+    tag_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.untag_collection(&collection_id, &tag_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_request_tags(
+    request_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Tag>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_request_tags(&request_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_collection_tags(
+    collection_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Tag>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_collection_tags(&collection_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_requests_by_tag(
+    tag_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Request>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_requests_by_tag(&tag_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_collections_by_tag(
+    tag_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Collection>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_collections_by_tag(&tag_id).await.map_err(AppError::from)
+}
+
+// ============ BULK COMMANDS ============
+
+// 🎓 TEACHING: One round trip and one transaction for a whole batch; if any item fails,
+// nothing in the batch is saved
+#[tauri::command]
+async fn bulk_update_requests(
+    requests: Vec<database::Request>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Request>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.bulk_update_requests(requests).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn bulk_delete_requests(ids: Vec<String>, db_state: State<'_, DatabaseState>) -> Result<u64, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.bulk_delete_requests(&ids).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn bulk_create_variables(
+    variables: Vec<database::NewVariable>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Variable>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.bulk_create_variables(variables).await.map_err(AppError::from)
+}
+
+// ============ FAVORITES AND RECENTS COMMANDS ============
+
+#[tauri::command]
+async fn set_request_pinned(
+    request_id: String,
+    pinned: bool,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.set_request_pinned(&request_id, pinned).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_favorite_requests(db_state: State<'_, DatabaseState>) -> Result<Vec<database::Request>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_favorite_requests().await.map_err(AppError::from)
+}
+
+// Requests most recently sent from the request builder, newest first (10 by default)
+#[tauri::command]
+async fn get_recent_requests(
+    limit: Option<u32>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Request>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_recent_requests(limit.unwrap_or(10)).await.map_err(AppError::from)
+}
+
+// ============ DOCS COMMANDS ============
+
+// Markdown docs; an empty string clears them
+#[tauri::command]
+async fn update_request_docs(
+    request_id: String,
+    docs: Option<String>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.set_request_docs(&request_id, docs).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn update_collection_docs(
+    collection_id: String,
+    docs: Option<String>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.set_collection_docs(&collection_id, docs).await.map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Trace headers for the requests in a collection or folder; None inherits again
+#[tauri::command]
+async fn update_collection_trace_context(
+    collection_id: String,
+    trace_context: Option<trace::TraceContext>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.set_collection_trace_context(&collection_id, trace_context.as_ref())
+        .await
+        .map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Headers sent by every request in a collection or folder; None removes them
+#[tauri::command]
+async fn update_collection_default_headers(
+    collection_id: String,
+    default_headers: Option<HashMap<String, String>>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    if let Some(headers) = &default_headers {
+        default_headers::validate(headers).map_err(|e| AppError::validation(e.to_string()))?;
+    }
+    db.set_collection_default_headers(&collection_id, default_headers.as_ref())
+        .await
+        .map_err(AppError::from)
+}
+
+// ============ PHASE 2: ENVIRONMENT MANAGEMENT COMMANDS ============
+
+#[tauri::command]
+async fn create_environment(
+    name: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Environment, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.create_environment(name).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_environments(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Environment>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_environments().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn set_active_environment(
+    id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Environment, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.set_active_environment(&id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn clear_active_environment(
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.clear_active_environment().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_active_environment(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Option<database::Environment>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_active_environment().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn update_environment(
+    environment: database::Environment,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Environment, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let headers = default_headers::parse(Some(&environment.default_headers))
+        .map_err(|e| AppError::validation(format!("Default headers must be a JSON object of strings: {}", e)))?;
+    default_headers::validate(&headers).map_err(|e| AppError::validation(e.to_string()))?;
+    db.update_environment(environment).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_environment(
+    id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_environment(&id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn duplicate_environment(
+    id: String,
+    new_name: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Environment, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.duplicate_environment(&id, new_name).await.map_err(AppError::from)
+}
+
+// Shared by the environment export and workspace sync
+async fn build_environment_export(
+    db: &Database,
+    id: &str,
+    include_secrets: bool,
+) -> Result<importer_exporter::EnvironmentExport, AppError> {
+    let environment = db
+        .get_environment_by_id(id)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| "Environment not found".to_string())?;
+
+    let mut variables = Vec::new();
+    for variable in db.get_variables(Some(id)).await.map_err(AppError::from)? {
+        // Secrets are read explicitly so a locked workspace fails instead of exporting ciphertext
+        let value = match (variable.is_secret, include_secrets) {
+            (false, _) => variable.value,
+            (true, true) => db
+                .get_secret(&variable.id)
+                .await
+                .map_err(AppError::from)?
+                .unwrap_or_default(),
+            (true, false) => String::new(),
+        };
+        variables.push(importer_exporter::JsonVariable {
+            key: variable.key,
+            value,
+            is_secret: variable.is_secret,
+        });
+    }
+
+    Ok(importer_exporter::EnvironmentExport {
+        name: environment.name,
+        host_overrides: environment.host_overrides,
+        default_headers: environment.default_headers,
+        secrets_included: include_secrets,
+        variables,
+    })
+}
+
+// 🎓 TEACHING: Export an environment as portable JSON for sharing dev/staging configs.
+// Secret values are left blank unless `include_secrets` is true.
+#[tauri::command]
+async fn export_environment(
+    id: String,
+    include_secrets: Option<bool>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<String, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let export = build_environment_export(&db, &id, include_secrets.unwrap_or(false)).await?;
+    serde_json::to_string_pretty(&export).map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Import an environment export.
+// It goes into `environment_id` if given, else into an environment with the same name, else
+// a new one. Variables collide by key and `on_conflict` ("skip" by default, or "overwrite")
+// decides which value wins.
+#[tauri::command]
+async fn import_environment(
+    json_str: String,
+    environment_id: Option<String>,
+    on_conflict: Option<String>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<importer_exporter::EnvironmentImportReport, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let export: importer_exporter::EnvironmentExport = serde_json::from_str(&json_str).map_err(AppError::from)?;
+
+    let target = match environment_id {
+        Some(id) => Some(
+            db.get_environment_by_id(&id)
+                .await
+                .map_err(AppError::from)?
+                .ok_or_else(|| "Environment not found".to_string())?,
+        ),
+        None => db
+            .get_environments()
+            .await
+            .map_err(AppError::from)?
+            .into_iter()
+            .find(|env| env.name == export.name),
+    };
+    let environment = match target {
+        Some(environment) => environment,
+        None => {
+            let created = db.create_environment(export.name.clone()).await.map_err(AppError::from)?;
+            db.update_environment(database::Environment {
+                host_overrides: export.host_overrides.clone(),
+                default_headers: export.default_headers.clone(),
+                ..created
+            })
+            .await
+            .map_err(AppError::from)?
+        }
+    };
+
+    let saved_variables = db.get_variables(Some(&environment.id)).await.map_err(AppError::from)?;
+    let existing: Vec<(String, importer_exporter::JsonVariable)> = saved_variables
+        .iter()
+        .map(|variable| {
+            (
+                variable.id.clone(),
+                importer_exporter::JsonVariable {
+                    key: variable.key.clone(),
+                    value: variable.value.clone(),
+                    is_secret: variable.is_secret,
+                },
+            )
+        })
+        .collect();
+
+    let mut entries = importer_exporter::plan_environment_import(
+        &existing,
+        &export.variables,
+        on_conflict.as_deref().unwrap_or("skip"),
+    )
+    .map_err(AppError::from)?;
+
+    for entry in entries.iter_mut() {
+        // The plan keeps the last definition of each key
+        let Some(incoming) = export.variables.iter().rev().find(|v| v.key == entry.key) else {
+            continue;
+        };
+        match entry.action.as_str() {
+            "added" => {
+                let created = db
+                    .create_variable(Some(environment.id.clone()), incoming.key.clone(), incoming.value.clone(), incoming.is_secret)
+                    .await
+                    .map_err(AppError::from)?;
+                entry.variable_id = Some(created.id);
+            }
+            "overwritten" => {
+                if let Some(saved) = saved_variables.iter().find(|v| Some(&v.id) == entry.variable_id.as_ref()) {
+                    db.update_variable(database::Variable {
+                        value: incoming.value.clone(),
+                        is_secret: incoming.is_secret,
+                        ..saved.clone()
+                    })
+                    .await
+                    .map_err(AppError::from)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(importer_exporter::EnvironmentImportReport {
+        environment_id: environment.id,
+        entries,
+    })
+}
+
+// ============ PHASE 2: VARIABLE MANAGEMENT COMMANDS ============
+
+// 🎓 TEACHING: Pass `collection_id` (a collection or folder) or `request_id` instead of an
+// environment to create a scoped variable that overrides environment and global ones
+#[tauri::command]
+async fn create_variable(
+    environment_id: Option<String>,
+    collection_id: Option<String>,
+    request_id: Option<String>,
+    key: String,
+    value: String,
+    is_secret: bool,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Variable, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    if collection_id.is_some() || request_id.is_some() {
+        if environment_id.is_some() {
+            return Err(AppError::validation(
+                "A variable belongs to an environment, a collection, or a request, not several",
+            ));
+        }
+        return db
+            .create_scoped_variable(collection_id, request_id, key, value, is_secret)
+            .await
+            .map_err(AppError::from);
+    }
+
+    db.create_variable(environment_id, key, value, is_secret)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_variables(
+    environment_id: Option<String>,
+    collection_id: Option<String>,
+    request_id: Option<String>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Variable>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let variables = match (collection_id, request_id) {
+        (Some(collection_id), _) => db.get_collection_variables(&collection_id).await,
+        (None, Some(request_id)) => db.get_request_variables(&request_id).await,
+        (None, None) => db.get_variables(environment_id.as_deref()).await,
+    };
+    variables.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_active_variables(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::Variable>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_active_variables().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn update_variable(
+    variable: database::Variable,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Variable, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.update_variable(variable).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_variable(
+    id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_variable(&id).await.map_err(AppError::from)
+}
+
+// Give the request's collection and id to preview with its scoped variables
+#[tauri::command]
+async fn interpolate_string(
+    input: String,
+    collection_id: Option<String>,
+    request_id: Option<String>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<String, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let variables = db
+        .resolve_variables(collection_id.as_deref(), request_id.as_deref())
+        .await
+        .map_err(AppError::from)?;
+    db.interpolate_with(&variables, &input).await.map_err(AppError::from)
+}
+
+#[derive(Debug, Serialize)]
+struct VariableReference {
+    collection_id: String,
+    request_id: Option<String>, // None for a collection's own auth
+    name: String,               // Request (or collection) name
+    location: String,           // e.g. "URL", "header Authorization", "auth"
+}
+
+// 🎓 TEACHING: Find every saved request (and collection auth) that uses `{{key}}`,
+// so a variable can be renamed or deleted safely
+#[tauri::command]
+async fn find_variable_references(
+    key: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<VariableReference>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let placeholder = format!("{{{{{}}}}}", key);
+    let mut references = Vec::new();
+    for collection in db.get_collections().await.map_err(AppError::from)? {
+        if collection.auth_data.as_deref().is_some_and(|auth| auth.contains(&placeholder)) {
+            references.push(VariableReference {
+                collection_id: collection.id.clone(),
+                request_id: None,
+                name: collection.name.clone(),
+                location: "auth".to_string(),
+            });
+        }
+        for request in db.get_requests_by_collection(&collection.id).await.map_err(AppError::from)? {
+            for location in placeholders::request_references(&request, &key) {
+                references.push(VariableReference {
+                    collection_id: collection.id.clone(),
+                    request_id: Some(request.id.clone()),
+                    name: request.name.clone(),
+                    location,
+                });
+            }
+        }
+    }
+
+    Ok(references)
+}
+
+// Names usable as `{{$faker.<name>}}`, for autocomplete
+#[tauri::command]
+fn list_faker_variables() -> Vec<&'static str> {
+    faker::NAMES.to_vec()
+}
+
+// ============ PHASE 2: OAUTH 2.0 COMMANDS ============
+
+#[tauri::command]
+async fn oauth_get_authorization_url(config: oauth::OAuthConfig) -> Result<String, AppError> {
+    let mut oauth_manager = oauth::OAuthManager::new(config);
+    oauth_manager.get_authorization_url().map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn oauth_exchange_code_for_token(
+    config: oauth::OAuthConfig,
+    authorization_code: String,
+    csrf_token: String,
+) -> Result<oauth::OAuthToken, AppError> {
+    let mut oauth_manager = oauth::OAuthManager::new(config);
+    // Generate the authorization URL first to set up PKCE and CSRF
+    oauth_manager.get_authorization_url().map_err(AppError::from)?;
+    
+    oauth_manager
+        .exchange_code_for_token(&authorization_code, &csrf_token)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn oauth_client_credentials_flow(
+    config: oauth::OAuthConfig,
+) -> Result<oauth::OAuthToken, AppError> {
+    let oauth_manager = oauth::OAuthManager::new(config);
+    oauth_manager
+        .client_credentials_flow()
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn oauth_password_flow(
+    config: oauth::OAuthConfig,
+    username: String,
+    password: String,
+) -> Result<oauth::OAuthToken, AppError> {
+    let oauth_manager = oauth::OAuthManager::new(config);
+    oauth_manager
+        .password_flow(&username, &password)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn oauth_jwt_bearer_flow(
+    config: oauth::JwtBearerConfig,
+) -> Result<oauth::OAuthToken, AppError> {
+    config.request_token().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn oauth_service_account_config(
+    service_account_json: String,
+    scope: Option<String>,
+) -> Result<oauth::JwtBearerConfig, AppError> {
+    oauth::JwtBearerConfig::from_service_account_json(&service_account_json, scope)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn oauth_refresh_token(
+    config: oauth::OAuthConfig,
+    refresh_token: String,
+) -> Result<oauth::OAuthToken, AppError> {
+    let oauth_manager = oauth::OAuthManager::new(config);
+    oauth_manager
+        .refresh_token(&refresh_token)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn oauth_parse_callback_url(callback_url: String) -> Result<(String, String), AppError> {
+    oauth::parse_callback_url(&callback_url).map_err(AppError::from)
+}
+
+// ============ AWS COMMANDS ============
+
+#[tauri::command]
+async fn aws_presign_url(
+    config: auth::AwsSignatureConfig,
+    method: String,
+    url: String,
+    expires_in: u64,
+) -> Result<String, AppError> {
+    config
+        .resolve_credentials()
+        .and_then(|config| config.generate_presigned_url(&method, &url, expires_in))
+        .map_err(AppError::from)
+}
+
+// ============ OPENID CONNECT COMMANDS ============
+
+#[tauri::command]
+async fn oidc_discover(
+    issuer_url: String,
+    config: oauth::OAuthConfig,
+) -> Result<oidc::OidcDiscoveryResult, AppError> {
+    let metadata = oidc::discover(&issuer_url).await.map_err(AppError::from)?;
+    let config = metadata.apply_to_config(config);
+    Ok(oidc::OidcDiscoveryResult { metadata, config })
+}
+
+#[tauri::command]
+async fn oidc_validate_id_token(
+    issuer_url: String,
+    client_id: String,
+    id_token: String,
+    nonce: Option<String>,
+) -> Result<oidc::IdTokenInfo, AppError> {
+    let metadata = oidc::discover(&issuer_url).await.map_err(AppError::from)?;
+    oidc::validate_id_token(&metadata, &client_id, &id_token, nonce.as_deref())
+        .await
+        .map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Decode any JWT for the inspector. With a key (secret, PEM, JWK or JWKS) or a
+// JWKS URL the signature is checked as well; a bad signature is reported, not an error.
+#[tauri::command]
+async fn decode_jwt(token: String, key: Option<String>, jwks_url: Option<String>) -> Result<jwt::JwtInfo, AppError> {
+    let key = match (key.as_deref().filter(|key| !key.trim().is_empty()), jwks_url) {
+        (Some(key), _) => Some(jwt::VerifyKey::Key(key)),
+        (None, Some(url)) if !url.trim().is_empty() => {
+            Some(jwt::VerifyKey::Jwks(jwt::fetch_jwks(url.trim()).await.map_err(AppError::from)?))
+        }
+        _ => None,
+    };
+    jwt::inspect(&token, key).map_err(|e| AppError::validation(e.to_string()))
+}
+
+// ============ SECRET ENCRYPTION COMMANDS ============
+
+#[tauri::command]
+async fn get_secret_encryption_status(
+    db_state: State<'_, DatabaseState>,
+) -> Result<secrets::SecretEncryptionStatus, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    Ok(db.secret_encryption_status())
+}
+
+// 🎓 TEACHING: Set a passphrase and encrypt all existing secrets with it
+#[tauri::command]
+async fn enable_secret_encryption(passphrase: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.enable_secret_encryption(&passphrase).await.map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Encrypt secrets with a random key kept in the OS keychain, so there's no passphrase to enter
+#[tauri::command]
+async fn enable_keychain_secret_encryption(db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.enable_keychain_secret_encryption().await.map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Lock the workspace for shared machines or screen sharing.
+// Secret values, auth data and OAuth tokens become unreadable, and requests that need them refuse to send.
+#[tauri::command]
+async fn lock_workspace(
+    db_state: State<'_, DatabaseState>,
+    session_cache: State<'_, session::SessionCache>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.lock_workspace().map_err(AppError::from)?;
+    // Session tokens came from logins that used secrets, so drop them too
+    session_cache.clear();
+    Ok(())
+}
+
+// 🎓 TEACHING: The workspace also starts locked after a restart, until the master password is entered.
+// Without a password the key is read back from the OS keychain instead.
+#[tauri::command]
+async fn unlock_workspace(master_password: Option<String>, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    match master_password {
+        Some(master_password) => db.unlock_secrets(&master_password).await.map_err(AppError::from),
+        None => db.unlock_secrets_from_keychain().await.map_err(AppError::from),
+    }
+}
+
+// ============ OS KEYCHAIN COMMANDS ============
+
+// 🎓 TEACHING: Move a variable's value into the OS keychain (macOS Keychain,
+// Windows Credential Manager or libsecret) so it never touches the SQLite file
+#[tauri::command]
+async fn store_secret(
+    variable_id: String,
+    value: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.store_secret(&variable_id, value).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_secret(variable_id: String, db_state: State<'_, DatabaseState>) -> Result<Option<String>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_secret(&variable_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn migrate_secrets_to_keychain(db_state: State<'_, DatabaseState>) -> Result<u64, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.migrate_secrets_to_keychain().await.map_err(AppError::from)
+}
+
+// ============ VARIABLE PROVIDER COMMANDS ============
+
+// 🎓 TEACHING: Fetch a provider-backed value without saving anything, so the UI can test a config
+#[tauri::command]
+async fn fetch_variable_source(source: providers::VariableSource) -> Result<String, AppError> {
+    source.fetch().await.map_err(AppError::from)
+}
+
+// Forget cached provider values, e.g. right after rotating a secret
+#[tauri::command]
+async fn clear_variable_provider_cache(db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.clear_variable_provider_cache();
+    Ok(())
+}
+
+// ============ AUTH PLUGIN COMMANDS ============
+
+// 🎓 TEACHING: Install a WASM auth plugin from a file on disk.
+// The module is validated first and stored in the database, so the original file can be removed.
+#[tauri::command]
+async fn install_auth_plugin(
+    name: String,
+    path: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::AuthPlugin, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let wasm = std::fs::read(&path).map_err(|e| AppError::from(e).context(format!("Failed to read {}", path)))?;
+    plugin::validate(&wasm).map_err(AppError::from)?;
+
+    db.install_auth_plugin(name, wasm).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn list_auth_plugins(db_state: State<'_, DatabaseState>) -> Result<Vec<database::AuthPlugin>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.list_auth_plugins().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn uninstall_auth_plugin(name: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_auth_plugin(&name).await.map_err(AppError::from)
+}
+
+// ============ COLLECTION RUNNER COMMANDS ============
+
+// 🎓 TEACHING: Run every request in a collection, reporting each one as `runner-progress` events
+#[tauri::command]
+async fn run_collection(
+    collection_id: String,
+    options: Option<runner::RunOptions>,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    session_cache: State<'_, session::SessionCache>,
+) -> Result<database::CollectionRun, AppError> {
+    use tauri::Emitter;
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let progress: runner::ProgressSink = std::sync::Arc::new(move |event| {
+        let _ = app.emit("runner-progress", event);
+    });
+    runner::run_collection(&db, &session_cache, &collection_id, options.unwrap_or_default(), progress, None).await
+}
+
+// 🎓 TEACHING: Run a collection once per environment and compare the results side by side.
+// Every run reports `runner-progress` events under its own run id.
+#[tauri::command]
+async fn run_collection_matrix(
+    collection_id: String,
+    environment_ids: Vec<String>,
+    options: Option<runner::RunOptions>,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    session_cache: State<'_, session::SessionCache>,
+) -> Result<matrix::MatrixRun, AppError> {
+    use tauri::Emitter;
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let progress: runner::ProgressSink = std::sync::Arc::new(move |event| {
+        let _ = app.emit("runner-progress", event);
+    });
+    matrix::run_matrix(
+        &db,
+        &session_cache,
+        &collection_id,
+        &environment_ids,
+        options.unwrap_or_default(),
+        progress,
+    )
+    .await
+}
+
+// 🎓 TEACHING: Run a collection and check every response against an OpenAPI spec
+// (documented status, required headers, content type and body schema). Progress is
+// reported as `runner-progress` events, like a plain run.
+#[tauri::command]
+async fn run_contract_test(
+    collection_id: String,
+    spec_text: String,
+    options: Option<runner::RunOptions>,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    session_cache: State<'_, session::SessionCache>,
+) -> Result<contract::ContractReport, AppError> {
+    use tauri::Emitter;
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let progress: runner::ProgressSink = std::sync::Arc::new(move |event| {
+        let _ = app.emit("runner-progress", event);
+    });
+    contract::run(&db, &session_cache, &collection_id, &spec_text, options.unwrap_or_default(), progress).await
+}
+
+#[tauri::command]
+async fn get_collection_runs(
+    collection_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::CollectionRun>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_collection_runs(&collection_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_collection_run(id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_collection_run(&id).await.map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Export a finished run as "junit" (XML for CI) or "html" (a standalone page)
+#[tauri::command]
+async fn export_run_report(
+    run_id: String,
+    format: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<String, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let run = db
+        .get_collection_run(&run_id)
+        .await
+        .map_err(AppError::from)?
+        .ok_or("Run not found")?;
+    // The collection may have been renamed (or deleted) since the run
+    let collection_name = db
+        .get_collection_by_id(&run.collection_id)
+        .await
+        .map_err(AppError::from)?
+        .map(|collection| collection.name)
+        .unwrap_or_else(|| "Collection run".to_string());
+    report::export(&run, &collection_name, &format).map_err(AppError::from)
+}
+
+// ============ MONITOR COMMANDS ============
+
+// 🎓 TEACHING: Requests or collections the background scheduler checks on a cron schedule
+#[tauri::command]
+async fn create_monitor(
+    name: String,
+    target_type: String,
+    target_id: String,
+    cron: String,
+    environment_id: Option<String>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Monitor, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    monitor::validate(&target_type, &cron, None, None).map_err(AppError::from)?;
+    db.create_monitor(name, target_type, target_id, cron, environment_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_monitors(db_state: State<'_, DatabaseState>) -> Result<Vec<database::Monitor>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_monitors().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn update_monitor(
+    monitor: database::Monitor,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Monitor, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    monitor::validate(
+        &monitor.target_type,
+        &monitor.cron,
+        monitor.webhook_url.as_deref(),
+        monitor.retry.as_deref(),
+    )
+    .map_err(AppError::from)?;
+    db.update_monitor(monitor).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_monitor(id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_monitor(&id).await.map_err(AppError::from)
+}
+
+// Check now, outside the schedule; the result goes into the history like any other
+#[tauri::command]
+async fn run_monitor(
+    id: String,
+    db_state: State<'_, DatabaseState>,
+    session_cache: State<'_, session::SessionCache>,
+) -> Result<database::MonitorResult, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let monitor = db
+        .get_monitor(&id)
+        .await
+        .map_err(AppError::from)?
+        .ok_or("Monitor not found")?;
+    monitor::run_monitor(&db, &session_cache, &monitor)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_monitor_results(
+    monitor_id: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<database::MonitorResult>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_monitor_results(&monitor_id).await.map_err(AppError::from)
+}
+
+// ============ WORKFLOW COMMANDS ============
+
+// 🎓 TEACHING: Saved requests wired into a graph, passing values along the edges
+#[tauri::command]
+async fn create_workflow(name: String, db_state: State<'_, DatabaseState>) -> Result<database::Workflow, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.create_workflow(name).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_workflows(db_state: State<'_, DatabaseState>) -> Result<Vec<database::Workflow>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_workflows().await.map_err(AppError::from)
+}
+
+// The graph is checked (known nodes, valid conditions, no cycles) before it's saved
+#[tauri::command]
+async fn update_workflow(
+    workflow: database::Workflow,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::Workflow, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let (nodes, edges) = workflow::parse_graph(&workflow).map_err(AppError::from)?;
+    workflow::run_order(&nodes, &edges).map_err(AppError::from)?;
+    db.update_workflow(workflow).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_workflow(id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_workflow(&id).await.map_err(AppError::from)
+}
+
+// Each node's result is also sent as a `workflow-progress` event as soon as it's known
+#[tauri::command]
+async fn run_workflow(
+    id: String,
+    environment_id: Option<String>,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    session_cache: State<'_, session::SessionCache>,
+) -> Result<workflow::WorkflowRun, AppError> {
+    use tauri::Emitter;
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let saved = db
+        .get_workflow(&id)
+        .await
+        .map_err(AppError::from)?
+        .ok_or("Workflow not found")?;
+    let progress: workflow::NodeSink = std::sync::Arc::new(move |node| {
+        let _ = app.emit("workflow-progress", node);
+    });
+    workflow::run_workflow(&db, &session_cache, &saved, environment_id, progress).await
+}
+
+// ============ REQUEST ANALYTICS COMMANDS ============
+
+// 🎓 TEACHING: Latency percentiles, error rate and payload size for a saved request over a
+// range like "24h" or "7d", with a trend to spot regressions
+#[tauri::command]
+async fn get_request_analytics(
+    request_id: String,
+    range: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<analytics::RequestAnalytics, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    analytics::request_analytics(&db, &request_id, &range)
+        .await
+        .map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Export sends of saved requests as "csv" or "json"; no filter exports everything
+#[tauri::command]
+async fn export_history(
+    format: String,
+    filter: Option<database::HistoryFilter>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<String, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let entries = db.get_history(&filter.unwrap_or_default()).await.map_err(AppError::from)?;
+    history::export(&entries, &format).map_err(|e| AppError::validation(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_history_retention(app: tauri::AppHandle) -> Result<settings::HistoryRetention, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(settings.history_retention)
+}
+
+// The background maintenance keeps history within these limits; new limits apply straight away.
+// Returns how many history rows were pruned.
+#[tauri::command]
+async fn set_history_retention(
+    retention: settings::HistoryRetention,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+) -> Result<u64, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    app_settings.set_history_retention(retention).map_err(AppError::from)?;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().cloned()
+    };
+    match db {
+        Some(db) => {
+            let retention = &app_settings.history_retention;
+            db.prune_history(retention.max_entries, retention.max_age(), retention.max_bytes)
+                .await
+                .map_err(AppError::from)
+        }
+        None => Ok(0),
+    }
+}
+
+// 🎓 TEACHING: Compare two recent responses by the execution ids their sends used
+#[tauri::command]
+async fn diff_responses(
+    execution_id_a: String,
+    execution_id_b: String,
+    responses: State<'_, response_diff::ResponseStore>,
+) -> Result<response_diff::ResponseDiff, AppError> {
+    let (a, b) = (responses.kept(&execution_id_a)?, responses.kept(&execution_id_b)?);
+    response_diff::diff(&a, &b).map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Filter a recent response with JSONPath (`$.items[*].id`) or a jq subset
+// (`.items[] | select(.price < 10)`), so only the matches go to the UI
+#[tauri::command]
+async fn query_response_body(
+    execution_id: String,
+    expression: String,
+    responses: State<'_, response_diff::ResponseStore>,
+) -> Result<json_query::QueryResult, AppError> {
+    let body = responses.kept(&execution_id)?.body;
+    let text = body.text().map_err(AppError::from)?;
+    json_query::query(&text, &expression).map_err(|e| AppError::validation(e.to_string()))
+}
+
+// 🎓 TEACHING: A page of a recent response's body, by byte offset; for bodies too big to
+// return inline (see large_body), but any kept response works
+#[tauri::command]
+async fn get_response_chunk(
+    execution_id: String,
+    offset: u64,
+    length: u64,
+    responses: State<'_, response_diff::ResponseStore>,
+) -> Result<large_body::BodyChunk, AppError> {
+    let body = responses.kept(&execution_id)?.body;
+    body.chunk(offset, length).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn search_response_body(
+    execution_id: String,
+    query: String,
+    case_sensitive: Option<bool>, // Defaults to false
+    responses: State<'_, response_diff::ResponseStore>,
+) -> Result<large_body::SearchResult, AppError> {
+    let body = responses.kept(&execution_id)?.body;
+    body.search(&query, case_sensitive.unwrap_or(false))
+        .map_err(|e| AppError::validation(e.to_string()))
+}
+
+// 🎓 TEACHING: Pretty-print, minify or convert a body; see body_format for what converts to what
+#[tauri::command]
+async fn format_body(content: String, from: String, to: String) -> Result<String, AppError> {
+    body_format::format(&content, &from, &to).map_err(|e| AppError::validation(e.to_string()))
+}
+
+// ============ UTILITY COMMANDS ============
+
+#[tauri::command]
+async fn encode_text(input: String, encoding: utilities::Encoding) -> Result<String, AppError> {
+    Ok(utilities::encode(&input, encoding))
+}
+
+#[tauri::command]
+async fn decode_text(input: String, encoding: utilities::Encoding) -> Result<String, AppError> {
+    utilities::decode(&input, encoding).map_err(|e| AppError::validation(e.to_string()))
+}
+
+#[tauri::command]
+async fn hash_text(
+    input: String,
+    algorithm: utilities::HashAlgorithm,
+    output: Option<utilities::Encoding>, // Defaults to hex
+) -> Result<String, AppError> {
+    utilities::digest(&input, algorithm, output.unwrap_or(utilities::Encoding::Hex))
+        .map_err(|e| AppError::validation(e.to_string()))
+}
+
+#[tauri::command]
+async fn hmac_text(
+    input: String,
+    key: String,
+    key_encoding: Option<utilities::Encoding>, // The key is plain text if not given
+    algorithm: utilities::HashAlgorithm,
+    output: Option<utilities::Encoding>,
+) -> Result<String, AppError> {
+    utilities::hmac(&input, &key, key_encoding, algorithm, output.unwrap_or(utilities::Encoding::Hex))
+        .map_err(|e| AppError::validation(e.to_string()))
+}
+
+#[tauri::command]
+async fn generate_uuids(count: Option<usize>) -> Result<Vec<String>, AppError> {
+    utilities::uuids(count.unwrap_or(1)).map_err(|e| AppError::validation(e.to_string()))
+}
+
+// ============ SCRIPT LIBRARY COMMANDS ============
+
+// 🎓 TEACHING: Shared helpers that request scripts pull in with `import "name" as alias;`
+#[tauri::command]
+async fn create_script_module(
+    name: String,
+    language: Option<String>, // Defaults to "rhai"
+    code: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::ScriptModule, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let language = language.unwrap_or_else(|| "rhai".to_string());
+    scripting::check(&language, &code).map_err(AppError::from)?;
+    db.create_script_module(name, language, code).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_script_modules(db_state: State<'_, DatabaseState>) -> Result<Vec<database::ScriptModule>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_script_modules().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn update_script_module(
+    module: database::ScriptModule,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::ScriptModule, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    scripting::check(&module.language, &module.code).map_err(AppError::from)?;
+    db.update_script_module(module).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_script_module(id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_script_module(&id).await.map_err(AppError::from)
+}
+
+// ============ SESSION AUTH COMMANDS ============
+
+// 🎓 TEACHING: Forget cached session values so the next request logs in again
+#[tauri::command]
+async fn clear_session_cache(session_cache: State<'_, session::SessionCache>) -> Result<usize, AppError> {
+    Ok(session_cache.clear())
+}
+
+// ============ GRPC COMMANDS ============
+
+// 🎓 TEACHING: List services and methods, with a JSON template for each request message
+#[tauri::command]
+async fn list_grpc_services(
+    address: String,
+    descriptor_source: grpc::DescriptorSource,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<grpc::GrpcService>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let address = db.interpolate_string(&address).await.map_err(AppError::from)?;
+    let pool = grpc::load_descriptors(&descriptor_source, &address)
+        .await
+        .map_err(AppError::from)?;
+    Ok(grpc::list_services(&pool))
+}
+
+#[derive(Clone, Serialize)]
+struct GrpcMessageEvent {
+    call_id: Option<String>,
+    message: serde_json::Value,
+}
+
+// 🎓 TEACHING: Invoke a unary or server-streaming method.
+// Each received message is also emitted as a `grpc-message` event, so streams show up live.
+#[tauri::command]
+async fn send_grpc_request(
+    request: grpc::GrpcRequest,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+) -> Result<grpc::GrpcResponse, AppError> {
+    use tauri::Emitter;
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let result = async {
+        let address = db.interpolate_string(&request.address).await.map_err(AppError::from)?;
+        let body = db.interpolate_string(&request.body).await.map_err(AppError::from)?;
+        let mut metadata = HashMap::new();
+        for (key, value) in &request.metadata {
+            let interpolated_value = db.interpolate_string(value).await.map_err(AppError::from)?;
+            metadata.insert(key.clone(), interpolated_value);
+        }
+
+        let pool = grpc::load_descriptors(&request.config.descriptor_source, &address)
+            .await
+            .map_err(AppError::from)?;
+        let method =
+            grpc::find_method(&pool, &request.config.service, &request.config.method).map_err(AppError::from)?;
+        let channel = grpc::connect(&address).await.map_err(AppError::from)?;
+
+        grpc::invoke(channel, &method, &body, &metadata, |message| {
+            let _ = app.emit(
+                "grpc-message",
+                GrpcMessageEvent {
+                    call_id: request.call_id.clone(),
+                    message: message.clone(),
+                },
+            );
+        })
+        .await
+        .map_err(AppError::from)
+    }
+    .await;
+
+    match result {
+        Ok(response) => Ok(response),
+        Err(e) => {
+            let redactor = db.secret_redactor().await.unwrap_or_default();
+            Err(e.map_message(|message| redactor.redact(message)))
+        }
+    }
+}
+
+// ============ MQTT COMMANDS ============
+
+// 🎓 TEACHING: Open a broker connection. Received messages (and the connection closing)
+// arrive as `mqtt-event` events tagged with the returned connection id.
+#[tauri::command]
+async fn mqtt_connect(
+    mut config: mqtt::MqttConnectConfig,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    mqtt_manager: State<'_, mqtt::MqttManager>,
+) -> Result<String, AppError> {
+    use tauri::Emitter;
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    // Credentials can come from (secret) variables like any other request field
+    config.broker_url = db.interpolate_string(&config.broker_url).await.map_err(AppError::from)?;
+    for field in [&mut config.client_id, &mut config.username, &mut config.password] {
+        if let Some(value) = field.as_mut() {
+            *value = db.interpolate_string(value).await.map_err(AppError::from)?;
+        }
+    }
+
+    let sink: mqtt::EventSink = std::sync::Arc::new(move |event| {
+        let _ = app.emit("mqtt-event", event);
+    });
+    match mqtt_manager.connect(&config, sink).await {
+        Ok(connection_id) => Ok(connection_id),
+        Err(e) => {
+            let redactor = db.secret_redactor().await.unwrap_or_default();
+            Err(AppError::from(e).map_message(|message| redactor.redact(message)))
+        }
+    }
+}
+
+#[tauri::command]
+async fn mqtt_publish(
+    connection_id: String,
+    topic: String,
+    payload: String,
+    qos: Option<u8>,
+    retain: Option<bool>,
+    db_state: State<'_, DatabaseState>,
+    mqtt_manager: State<'_, mqtt::MqttManager>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let topic = db.interpolate_string(&topic).await.map_err(AppError::from)?;
+    let payload = db.interpolate_string(&payload).await.map_err(AppError::from)?;
+    mqtt_manager
+        .publish(&connection_id, &topic, payload, qos.unwrap_or(0), retain.unwrap_or(false))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn mqtt_subscribe(
+    connection_id: String,
+    topic: String,
+    qos: Option<u8>,
+    mqtt_manager: State<'_, mqtt::MqttManager>,
+) -> Result<(), AppError> {
+    mqtt_manager
+        .subscribe(&connection_id, &topic, qos.unwrap_or(0))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn mqtt_unsubscribe(
+    connection_id: String,
+    topic: String,
+    mqtt_manager: State<'_, mqtt::MqttManager>,
+) -> Result<(), AppError> {
+    mqtt_manager.unsubscribe(&connection_id, &topic).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn mqtt_disconnect(connection_id: String, mqtt_manager: State<'_, mqtt::MqttManager>) -> Result<(), AppError> {
+    mqtt_manager.disconnect(&connection_id).await.map_err(AppError::from)
+}
+
+// ============ RAW SOCKET COMMANDS ============
+
+// 🎓 TEACHING: Send arbitrary bytes to host:port and return the raw reply
+#[tauri::command]
+async fn send_raw_socket(
+    mut request: raw_socket::RawSocketRequest,
+    db_state: State<'_, DatabaseState>,
+) -> Result<raw_socket::RawSocketResponse, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    request.host = db.interpolate_string(&request.host).await.map_err(AppError::from)?;
+    request.payload = db.interpolate_string(&request.payload).await.map_err(AppError::from)?;
+    match raw_socket::send(&request).await {
+        Ok(response) => Ok(response),
+        Err(e) => {
+            let redactor = db.secret_redactor().await.unwrap_or_default();
+            Err(AppError::from(e).map_message(|message| redactor.redact(message)))
+        }
+    }
+}
+
+// ============ TLS COMMANDS ============
+
+// 🎓 TEACHING: Show the certificate chain a server sends and why it would (not) be trusted
+#[tauri::command]
+async fn inspect_tls(host: String, port: Option<u16>) -> Result<tls_inspect::TlsInspection, AppError> {
+    tls_inspect::inspect(&host, port).await.map_err(AppError::from)
+}
+
+// ============ CONNECTIVITY DIAGNOSTICS COMMANDS ============
+
+// 🎓 TEACHING: For a failed request: does the name resolve, does the port answer, how fast?
+#[tauri::command]
+async fn resolve_host(host: String) -> Result<diagnostics::DnsLookup, AppError> {
+    diagnostics::resolve_host(&host).await.map_err(|e| AppError::validation(e.to_string()))
+}
+
+#[tauri::command]
+async fn tcp_connect_check(
+    host: String,
+    port: u16,
+    timeout_ms: Option<u64>,
+) -> Result<diagnostics::TcpCheck, AppError> {
+    diagnostics::tcp_connect_check(&host, port, timeout_ms)
+        .await
+        .map_err(|e| AppError::validation(e.to_string()))
+}
+
+#[tauri::command]
+async fn probe_latency(
+    host: String,
+    port: u16,
+    count: Option<u32>, // Defaults to 5
+    timeout_ms: Option<u64>,
+) -> Result<diagnostics::LatencyProbe, AppError> {
+    diagnostics::probe_latency(&host, port, count.unwrap_or(5), timeout_ms)
+        .await
+        .map_err(AppError::from)
+}
+
+// ============ REQUEST QUEUE COMMANDS ============
+
+// 🎓 TEACHING: Queue a send instead of making it now; higher priorities go first (default 0).
+// The response arrives in a `queue-result` event.
+#[tauri::command]
+async fn enqueue_request(
+    request: ApiRequest,
+    label: Option<String>,
+    priority: Option<i32>,
+    app: tauri::AppHandle,
+    queue: State<'_, queue::RequestQueue>,
+) -> Result<queue::QueueEntry, AppError> {
+    let entry = queue.enqueue(request, label, priority.unwrap_or(0));
+    queue::emit_status(&app);
+    Ok(entry)
+}
+
+#[tauri::command]
+async fn get_queue_status(queue: State<'_, queue::RequestQueue>) -> Result<queue::QueueStatus, AppError> {
+    Ok(queue.status())
+}
+
+// Pausing lets the send in flight finish and starts no new one until resumed
+#[tauri::command]
+async fn set_queue_paused(
+    paused: bool,
+    app: tauri::AppHandle,
+    queue: State<'_, queue::RequestQueue>,
+) -> Result<queue::QueueStatus, AppError> {
+    queue.set_paused(paused);
+    queue::emit_status(&app);
+    Ok(queue.status())
+}
+
+#[tauri::command]
+async fn set_queue_priority(
+    id: String,
+    priority: i32,
+    app: tauri::AppHandle,
+    queue: State<'_, queue::RequestQueue>,
+) -> Result<(), AppError> {
+    if !queue.set_priority(&id, priority) {
+        return Err(AppError::not_found(format!("No queued send with id {}", id)));
+    }
+    queue::emit_status(&app);
+    Ok(())
+}
+
+// Returns false if the send isn't waiting any more (it already started, or never existed)
+#[tauri::command]
+async fn cancel_queued_request(
+    id: String,
+    app: tauri::AppHandle,
+    queue: State<'_, queue::RequestQueue>,
+) -> Result<bool, AppError> {
+    let cancelled = queue.cancel(&id);
+    queue::emit_status(&app);
+    Ok(cancelled)
+}
+
+// Drops every waiting send and the finished ones; returns how many were waiting
+#[tauri::command]
+async fn clear_queue(app: tauri::AppHandle, queue: State<'_, queue::RequestQueue>) -> Result<usize, AppError> {
+    let cleared = queue.clear();
+    queue::emit_status(&app);
+    Ok(cleared)
+}
+
+// ============ RATE LIMIT COMMANDS ============
+
+#[tauri::command]
+async fn get_rate_limits(db_state: State<'_, DatabaseState>) -> Result<Vec<database::RateLimit>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_rate_limits().await.map_err(AppError::from)
+}
+
+// 🎓 TEACHING: Throttle sends to hosts matching `host_pattern`; give a rate, a concurrency
+// cap or both. Sends over the limit wait their turn rather than fail.
+#[tauri::command]
+async fn create_rate_limit(
+    host_pattern: String,
+    requests_per_second: Option<f64>,
+    max_concurrent: Option<u32>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::RateLimit, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.create_rate_limit(host_pattern, requests_per_second, max_concurrent)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn update_rate_limit(
+    limit: database::RateLimit,
+    db_state: State<'_, DatabaseState>,
+) -> Result<database::RateLimit, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.update_rate_limit(limit).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_rate_limit(id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.delete_rate_limit(&id).await.map_err(AppError::from)
+}
+
+// ============ WORKSPACE SYNC COMMANDS ============
+
+// The open workspace's sync folder, if it has one
+#[tauri::command]
+async fn get_sync_config(app: tauri::AppHandle) -> Result<Option<sync::SyncConfig>, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(app_settings.workspace_sync.get(app_settings.active_workspace_id()).cloned())
+}
+
+// 🎓 TEACHING: Mirror the open workspace to `config.dir` (see sync.rs); None stops syncing.
+// The folder itself is left as it is either way.
+#[tauri::command]
+async fn set_sync_config(
+    config: Option<sync::SyncConfig>,
+    app: tauri::AppHandle,
+) -> Result<Option<sync::SyncConfig>, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    let id = app_settings.active_workspace_id().to_string();
+    match &config {
+        Some(config) => {
+            sync::validate(config).map_err(|e| AppError::validation(e.to_string()))?;
+            let config = sync::SyncConfig { dir: config.dir.trim().to_string(), ..config.clone() };
+            app_settings.workspace_sync.insert(id.clone(), config);
+        }
+        None => {
+            app_settings.workspace_sync.remove(&id);
+        }
+    }
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+    Ok(app_settings.workspace_sync.get(&id).cloned())
+}
+
+// Sync now, even with the background sync turned off
+#[tauri::command]
+async fn sync_workspace(
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    workspace_sync: State<'_, sync::WorkspaceSync>,
+) -> Result<sync::SyncReport, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    let (config_dir, _) = app_dirs(&app)?;
+    let app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    let workspace_id = app_settings.active_workspace_id();
+    let config = app_settings
+        .workspace_sync
+        .get(workspace_id)
+        .ok_or_else(|| AppError::validation("This workspace has no sync folder"))?;
+    Ok(workspace_sync.run(&db, workspace_id, std::path::Path::new(&config.dir)).await)
+}
+
+// ============ COOKIE JAR COMMANDS ============
+
+// 🎓 TEACHING: Cookies kept by requests with `use_cookie_jar`, optionally for one domain and its subdomains
+#[tauri::command]
+async fn get_cookies(
+    domain: Option<String>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<cookies::Cookie>, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.get_cookies(domain.as_deref()).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_cookie(
+    domain: String,
+    path: String,
+    name: String,
+    db_state: State<'_, DatabaseState>,
+) -> Result<(), AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    if !db.delete_cookie(&domain, &path, &name).await.map_err(AppError::from)? {
+        return Err(AppError::not_found(format!("No cookie {} for {}{}", name, domain, path)));
+    }
+    Ok(())
+}
+
+// Returns how many cookies were removed
+#[tauri::command]
+async fn clear_cookies(domain: Option<String>, db_state: State<'_, DatabaseState>) -> Result<u64, AppError> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+    };
+
+    db.clear_cookies(domain.as_deref()).await.map_err(AppError::from)
+}
+
+// ============ RESPONSE STREAMING COMMANDS ============
+
+// 🎓 TEACHING: Stop a streamed response; the send returns with what arrived so far
+#[tauri::command]
+async fn stop_response_stream(
+    stream_id: String,
+    streams: State<'_, streaming::StreamRegistry>,
+) -> Result<bool, AppError> {
+    Ok(streams.stop(&stream_id))
+}
+
+// ============ WEBHOOK LISTENER COMMANDS ============
+
+// 🎓 TEACHING: Start recording requests sent to localhost:<port>.
+// Each capture is saved and also emitted as a `webhook-captured` event.
+#[tauri::command]
+async fn start_webhook_listener(
+    port: u16,
+    config: Option<webhook::WebhookListenerConfig>,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    webhooks: State<'_, webhook::WebhookManager>,
+) -> Result<u16, AppError> {
+    use tauri::Emitter;
+
     let db = {
         let db_guard = db_state.lock().unwrap();
-        // Clone the database (it's cheap - just a connection pool)
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
-    }; // Lock is released here!
+    };
 
-    // Now we can safely await without holding the lock
-    db.create_collection(name, description, parent_id)
+    let sink: webhook::CaptureSink = std::sync::Arc::new(move |capture| {
+        let _ = app.emit("webhook-captured", capture);
+    });
+    webhooks
+        .start(db, port, config.unwrap_or_default(), sink)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn get_collections(
+async fn stop_webhook_listener(port: u16, webhooks: State<'_, webhook::WebhookManager>) -> Result<(), AppError> {
+    webhooks.stop(port).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn list_webhook_listeners(webhooks: State<'_, webhook::WebhookManager>) -> Result<Vec<u16>, AppError> {
+    Ok(webhooks.ports())
+}
+
+#[tauri::command]
+async fn get_webhook_captures(
+    port: Option<u16>,
+    limit: Option<u32>,
     db_state: State<'_, DatabaseState>,
-) -> Result<Vec<database::Collection>, String> {
+) -> Result<Vec<database::WebhookCapture>, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.get_collections().await.map_err(|e| e.to_string())
+    db.get_webhook_captures(port, limit).await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn update_collection(
-    collection: database::Collection,
-    db_state: State<'_, DatabaseState>,
-) -> Result<database::Collection, String> {
+async fn clear_webhook_captures(port: Option<u16>, db_state: State<'_, DatabaseState>) -> Result<u64, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.update_collection(collection)
-        .await
-        .map_err(|e| e.to_string())
+    db.clear_webhook_captures(port).await.map_err(AppError::from)
 }
 
+// ============ MOCK SERVER COMMANDS ============
+
+// 🎓 TEACHING: Serve a collection's saved requests as fake endpoints on localhost:<port>
 #[tauri::command]
-async fn delete_collection(id: String, db_state: State<'_, DatabaseState>) -> Result<(), String> {
-    println!("🗑️ Rust: delete_collection called with id: {}", id);
-    
+async fn start_mock_server(
+    config: mock::MockServerConfig,
+    db_state: State<'_, DatabaseState>,
+    mocks: State<'_, mock::MockManager>,
+) -> Result<mock::MockServerInfo, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    println!("🔄 Rust: Calling database delete_collection...");
-    let result = db.delete_collection(&id).await.map_err(|e| {
-        println!("❌ Rust: Database delete_collection failed: {}", e);
-        e.to_string()
-    });
-    
-    if result.is_ok() {
-        println!("✅ Rust: delete_collection completed successfully");
-    }
-    
-    result
+    mocks.start(db, config).await.map_err(AppError::from)
 }
 
+// Same, but with routes generated from an OpenAPI/Swagger spec instead of a collection
 #[tauri::command]
-async fn get_collection_by_id(
-    id: String,
+async fn start_openapi_mock_server(
+    config: mock::OpenApiMockConfig,
     db_state: State<'_, DatabaseState>,
-) -> Result<Option<database::Collection>, String> {
+    mocks: State<'_, mock::MockManager>,
+) -> Result<mock::MockServerInfo, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.get_collection_by_id(&id)
-        .await
-        .map_err(|e| e.to_string())
+    mocks.start_openapi(db, config).await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn create_request(
-    collection_id: String,
-    name: String,
-    method: String,
-    url: String,
+async fn stop_mock_server(port: u16, mocks: State<'_, mock::MockManager>) -> Result<(), AppError> {
+    mocks.stop(port).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn list_mock_servers(mocks: State<'_, mock::MockManager>) -> Result<Vec<mock::MockServerInfo>, AppError> {
+    Ok(mocks.list())
+}
+
+// Re-read the collection after requests or mock responses were edited
+#[tauri::command]
+async fn reload_mock_server(
+    port: u16,
     db_state: State<'_, DatabaseState>,
-) -> Result<database::Request, String> {
+    mocks: State<'_, mock::MockManager>,
+) -> Result<mock::MockServerInfo, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.create_request(collection_id, name, method, url)
-        .await
-        .map_err(|e| e.to_string())
+    mocks.reload(&db, port).await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn get_requests_by_collection(
-    collection_id: String,
+async fn set_mock_response(
+    request_id: String,
+    status: u16,
+    headers: Option<String>,
+    body: String,
+    latency_ms: Option<u64>,
     db_state: State<'_, DatabaseState>,
-) -> Result<Vec<database::Request>, String> {
+) -> Result<database::MockResponse, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.get_requests_by_collection(&collection_id)
-        .await
-        .map_err(|e| e.to_string())
+    let headers = headers.unwrap_or_else(|| "{}".to_string());
+    serde_json::from_str::<std::collections::HashMap<String, String>>(&headers)
+        .map_err(|e| AppError::validation(format!("Mock headers must be a JSON object of strings: {}", e)))?;
+
+    db.set_mock_response(database::MockResponse {
+        request_id,
+        status,
+        headers,
+        body,
+        latency_ms,
+        updated_at: chrono::Utc::now(),
+    })
+    .await
+    .map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn update_request(
-    request: database::Request,
+async fn get_mock_response(
+    request_id: String,
     db_state: State<'_, DatabaseState>,
-) -> Result<database::Request, String> {
+) -> Result<Option<database::MockResponse>, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.update_request(request).await.map_err(|e| e.to_string())
+    db.get_mock_response(&request_id).await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn delete_request(id: String, db_state: State<'_, DatabaseState>) -> Result<(), String> {
-    println!("🗑️ Rust: delete_request called with id: {}", id);
-    
+async fn delete_mock_response(request_id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    println!("🔄 Rust: Calling database delete_request...");
-    let result = db.delete_request(&id).await.map_err(|e| {
-        println!("❌ Rust: Database delete_request failed: {}", e);
-        e.to_string()
-    });
-    
-    if result.is_ok() {
-        println!("✅ Rust: delete_request completed successfully");
-    }
-    
-    result
+    db.delete_mock_response(&request_id).await.map_err(AppError::from)
 }
 
+// ============ RECORD/REPLAY PROXY COMMANDS ============
+
+// 🎓 TEACHING: Start a proxy on localhost:<port> in front of `target_url`.
+// While recording, each capture is saved and also emitted as a `proxy-captured` event.
 #[tauri::command]
-async fn get_request_by_id(
-    id: String,
+async fn start_proxy(
+    config: proxy::ProxyConfig,
+    app: tauri::AppHandle,
     db_state: State<'_, DatabaseState>,
-) -> Result<Option<database::Request>, String> {
+    proxies: State<'_, proxy::ProxyManager>,
+) -> Result<proxy::ProxyInfo, AppError> {
+    use tauri::Emitter;
+
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.get_request_by_id(&id).await.map_err(|e| e.to_string())
+    let sink: proxy::CaptureSink = std::sync::Arc::new(move |capture| {
+        let _ = app.emit("proxy-captured", capture);
+    });
+    proxies.start(db, config, sink).await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn export_collection_to_json(
+async fn stop_proxy(port: u16, proxies: State<'_, proxy::ProxyManager>) -> Result<(), AppError> {
+    proxies.stop(port).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn list_proxies(proxies: State<'_, proxy::ProxyManager>) -> Result<Vec<proxy::ProxyInfo>, AppError> {
+    Ok(proxies.list())
+}
+
+#[tauri::command]
+async fn get_proxy_captures(
     collection_id: String,
     db_state: State<'_, DatabaseState>,
-) -> Result<String, String> {
+) -> Result<Vec<database::ProxyCapture>, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    // 1. Fetch the collection from the database
-    let collection = db
-        .get_collection_by_id(&collection_id)
-        .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "Collection not found".to_string())?;
-
-    // 2. Fetch all requests for that collection
-    let requests = db
-        .get_requests_by_collection(&collection_id)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 3. Convert database requests to JSON requests
-    let json_requests = requests
-        .into_iter()
-        .map(|req| importer_exporter::JsonRequest {
-            name: req.name,
-            method: req.method,
-            url: req.url,
-            params: req.params,
-            headers: req.headers,
-            body_type: req.body_type,
-            body_str: req.body_str,
-            auth_type: req.auth_type,
-            auth_data: req.auth_data,
-        })
-        .collect();
-
-    // 4. Create the final JSON collection structure
-    let json_collection = importer_exporter::JsonCollection {
-        name: collection.name,
-        description: collection.description,
-        requests: json_requests,
-    };
-
-    // 5. Serialize the structure to a JSON string
-    serde_json::to_string_pretty(&json_collection).map_err(|e| e.to_string())
+    db.get_proxy_captures(&collection_id).await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn import_collection_from_json(
-    json_str: String,
-    db_state: State<'_, DatabaseState>,
-) -> Result<database::Collection, String> {
+async fn clear_proxy_captures(collection_id: String, db_state: State<'_, DatabaseState>) -> Result<u64, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    // 1. Deserialize the JSON string into our import structure
-    let json_collection: importer_exporter::JsonCollection =
-        serde_json::from_str(&json_str).map_err(|e| e.to_string())?;
-
-    // 2. Create the new collection in the database
-    let new_collection = db
-        .create_collection(json_collection.name, json_collection.description, None)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 3. Iterate over the requests from the JSON and create them
-    for json_req in json_collection.requests {
-        let mut new_req = db
-            .create_request(
-                new_collection.id.clone(),
-                json_req.name,
-                json_req.method,
-                json_req.url,
-            )
-            .await
-            .map_err(|e| e.to_string())?;
-
-        // 4. Update the request with the additional details from the JSON
-        new_req.params = json_req.params;
-        new_req.headers = json_req.headers;
-        new_req.body_type = json_req.body_type;
-        new_req.body_str = json_req.body_str;
-        new_req.auth_type = json_req.auth_type;
-        new_req.auth_data = json_req.auth_data;
-
-        db.update_request(new_req)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
-
-    Ok(new_collection)
+    db.clear_proxy_captures(&collection_id).await.map_err(AppError::from)
 }
 
-// ============ PHASE 2: ENVIRONMENT MANAGEMENT COMMANDS ============
+// ============ PHASE 2: RESPONSE CACHING COMMANDS ============
 
 #[tauri::command]
-async fn create_environment(
-    name: String,
-    db_state: State<'_, DatabaseState>,
-) -> Result<database::Environment, String> {
+async fn get_cache_stats(db_state: State<'_, DatabaseState>) -> Result<(u64, u64), AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.create_environment(name).await.map_err(|e| e.to_string())
+    db.get_cache_stats().await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn get_environments(
-    db_state: State<'_, DatabaseState>,
-) -> Result<Vec<database::Environment>, String> {
+async fn clear_expired_cache(db_state: State<'_, DatabaseState>) -> Result<u64, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.get_environments().await.map_err(|e| e.to_string())
+    db.clear_expired_cache().await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn set_active_environment(
-    id: String,
-    db_state: State<'_, DatabaseState>,
-) -> Result<database::Environment, String> {
+async fn clear_all_cache(db_state: State<'_, DatabaseState>) -> Result<u64, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.set_active_environment(&id).await.map_err(|e| e.to_string())
+    db.clear_all_cache().await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn clear_active_environment(
+async fn get_cached_response_by_hash(
+    request_hash: String,
     db_state: State<'_, DatabaseState>,
-) -> Result<(), String> {
+) -> Result<Option<database::ResponseCache>, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.clear_active_environment().await.map_err(|e| e.to_string())
+    db.get_cached_response_by_hash(&request_hash).await.map_err(AppError::from)
 }
 
+// 🎓 TEACHING: The cache browser - entries a page at a time, and removing one at a time
 #[tauri::command]
-async fn get_active_environment(
+async fn list_cache_entries(
+    filter: Option<database::CacheEntryFilter>,
+    page: Option<u32>,
     db_state: State<'_, DatabaseState>,
-) -> Result<Option<database::Environment>, String> {
+) -> Result<database::CacheEntryPage, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.get_active_environment().await.map_err(|e| e.to_string())
+    db.list_cache_entries(&filter.unwrap_or_default(), page.unwrap_or(0))
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn update_environment(
-    environment: database::Environment,
-    db_state: State<'_, DatabaseState>,
-) -> Result<database::Environment, String> {
+async fn delete_cache_entry(id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.update_environment(environment).await.map_err(|e| e.to_string())
+    db.delete_cache_entry(&id).await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn delete_environment(
-    id: String,
-    db_state: State<'_, DatabaseState>,
-) -> Result<(), String> {
+async fn invalidate_cache(pattern: String, db_state: State<'_, DatabaseState>) -> Result<u64, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.delete_environment(&id).await.map_err(|e| e.to_string())
+    db.invalidate_cache(&pattern).await.map_err(AppError::from)
 }
 
-// ============ PHASE 2: VARIABLE MANAGEMENT COMMANDS ============
+#[tauri::command]
+async fn get_invalidate_cache_on_write(app: tauri::AppHandle) -> Result<bool, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(settings.invalidate_cache_on_write)
+}
 
 #[tauri::command]
-async fn create_variable(
-    environment_id: Option<String>,
-    key: String,
-    value: String,
-    is_secret: bool,
-    db_state: State<'_, DatabaseState>,
-) -> Result<database::Variable, String> {
-    let db = {
-        let db_guard = db_state.lock().unwrap();
-        db_guard.as_ref().ok_or("Database not initialized")?.clone()
-    };
+async fn set_invalidate_cache_on_write(enabled: bool, app: tauri::AppHandle) -> Result<bool, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    app_settings.invalidate_cache_on_write = enabled;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+    Ok(enabled)
+}
 
-    db.create_variable(environment_id, key, value, is_secret)
-        .await
-        .map_err(|e| e.to_string())
+#[tauri::command]
+async fn get_cache_max_bytes(app: tauri::AppHandle) -> Result<u64, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(settings.cache_max_bytes())
 }
 
+// 🎓 TEACHING: The background maintenance keeps the cache under this size; a smaller limit
+// applies straight away. None goes back to the default.
 #[tauri::command]
-async fn get_variables(
-    environment_id: Option<String>,
+async fn set_cache_max_bytes(
+    max_bytes: Option<u64>,
+    app: tauri::AppHandle,
     db_state: State<'_, DatabaseState>,
-) -> Result<Vec<database::Variable>, String> {
+) -> Result<u64, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    app_settings.set_cache_max_bytes(max_bytes).map_err(AppError::from)?;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+
     let db = {
         let db_guard = db_state.lock().unwrap();
-        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+        db_guard.as_ref().cloned()
     };
-
-    let env_id_ref = environment_id.as_deref();
-    db.get_variables(env_id_ref).await.map_err(|e| e.to_string())
+    if let Some(db) = db {
+        db.trim_cache(app_settings.cache_max_bytes()).await.map_err(AppError::from)?;
+    }
+    Ok(app_settings.cache_max_bytes())
 }
 
 #[tauri::command]
-async fn get_active_variables(
-    db_state: State<'_, DatabaseState>,
-) -> Result<Vec<database::Variable>, String> {
+async fn get_cache_policies(db_state: State<'_, DatabaseState>) -> Result<Vec<database::CachePolicy>, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.get_active_variables().await.map_err(|e| e.to_string())
+    db.get_cache_policies().await.map_err(AppError::from)
 }
 
+// 🎓 TEACHING: A rule for a host pattern or a collection (pass exactly one). Without a TTL,
+// matching responses are kept for as long as their caching headers allow.
 #[tauri::command]
-async fn update_variable(
-    variable: database::Variable,
+async fn create_cache_policy(
+    host_pattern: Option<String>,
+    collection_id: Option<String>,
+    ttl_seconds: Option<u64>,
+    bypass: Option<bool>,
     db_state: State<'_, DatabaseState>,
-) -> Result<database::Variable, String> {
+) -> Result<database::CachePolicy, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.update_variable(variable).await.map_err(|e| e.to_string())
+    db.create_cache_policy(host_pattern, collection_id, ttl_seconds, bypass.unwrap_or(false))
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn delete_variable(
-    id: String,
+async fn update_cache_policy(
+    policy: database::CachePolicy,
     db_state: State<'_, DatabaseState>,
-) -> Result<(), String> {
+) -> Result<database::CachePolicy, AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.delete_variable(&id).await.map_err(|e| e.to_string())
+    db.update_cache_policy(policy).await.map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn interpolate_string(
-    input: String,
-    db_state: State<'_, DatabaseState>,
-) -> Result<String, String> {
+async fn delete_cache_policy(id: String, db_state: State<'_, DatabaseState>) -> Result<(), AppError> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.as_ref().ok_or("Database not initialized")?.clone()
     };
 
-    db.interpolate_string(&input).await.map_err(|e| e.to_string())
+    db.delete_cache_policy(&id).await.map_err(AppError::from)
 }
 
-// ============ PHASE 2: OAUTH 2.0 COMMANDS ============
-
 #[tauri::command]
-async fn oauth_get_authorization_url(config: oauth::OAuthConfig) -> Result<String, String> {
-    let mut oauth_manager = oauth::OAuthManager::new(config);
-    oauth_manager.get_authorization_url().map_err(|e| e.to_string())
+async fn get_offline_mode(app: tauri::AppHandle) -> Result<bool, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(settings.offline)
 }
 
+// 🎓 TEACHING: While offline, sends are answered from the response cache (stale copies included)
+// or fail with an "offline" error, so demos keep working without a network
 #[tauri::command]
-async fn oauth_exchange_code_for_token(
-    config: oauth::OAuthConfig,
-    authorization_code: String,
-    csrf_token: String,
-) -> Result<oauth::OAuthToken, String> {
-    let mut oauth_manager = oauth::OAuthManager::new(config);
-    // Generate the authorization URL first to set up PKCE and CSRF
-    oauth_manager.get_authorization_url().map_err(|e| e.to_string())?;
-    
-    oauth_manager
-        .exchange_code_for_token(&authorization_code, &csrf_token)
-        .await
-        .map_err(|e| e.to_string())
+async fn set_offline_mode(enabled: bool, app: tauri::AppHandle) -> Result<bool, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    app_settings.offline = enabled;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+    Ok(enabled)
 }
 
 #[tauri::command]
-async fn oauth_client_credentials_flow(
-    config: oauth::OAuthConfig,
-) -> Result<oauth::OAuthToken, String> {
-    let oauth_manager = oauth::OAuthManager::new(config);
-    oauth_manager
-        .client_credentials_flow()
-        .await
-        .map_err(|e| e.to_string())
+async fn get_trace_context(app: tauri::AppHandle) -> Result<trace::TraceContext, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(settings.trace_context)
 }
 
+// 🎓 TEACHING: Add traceparent and/or a correlation id header to every send, so backend logs
+// and traces can be matched to calls made here. Collections can override it.
 #[tauri::command]
-async fn oauth_refresh_token(
-    config: oauth::OAuthConfig,
-    refresh_token: String,
-) -> Result<oauth::OAuthToken, String> {
-    let oauth_manager = oauth::OAuthManager::new(config);
-    oauth_manager
-        .refresh_token(&refresh_token)
-        .await
-        .map_err(|e| e.to_string())
+async fn set_trace_context(
+    trace_context: trace::TraceContext,
+    app: tauri::AppHandle,
+) -> Result<trace::TraceContext, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    app_settings.trace_context = trace_context;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+    Ok(app_settings.trace_context)
 }
 
 #[tauri::command]
-async fn oauth_parse_callback_url(callback_url: String) -> Result<(String, String), String> {
-    oauth::parse_callback_url(&callback_url).map_err(|e| e.to_string())
+async fn get_settings(app: tauri::AppHandle) -> Result<settings::GeneralSettings, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(settings.general_settings())
 }
 
-// ============ PHASE 2: RESPONSE CACHING COMMANDS ============
-
+// 🎓 TEACHING: Saves the settings screen in one go: request defaults (timeout, redirects,
+// proxy, TLS checks, default headers) plus the cache, offline and trace options. Sends
+// read them as they start, so there's nothing to restart.
 #[tauri::command]
-async fn get_cache_stats(db_state: State<'_, DatabaseState>) -> Result<(u64, u64), String> {
+async fn update_settings(
+    settings: settings::GeneralSettings,
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+) -> Result<settings::GeneralSettings, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    app_settings.set_general_settings(settings).map_err(AppError::from)?;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+
     let db = {
         let db_guard = db_state.lock().unwrap();
-        db_guard.as_ref().ok_or("Database not initialized")?.clone()
+        db_guard.as_ref().cloned()
     };
+    if let Some(db) = db {
+        db.trim_cache(app_settings.cache_max_bytes()).await.map_err(AppError::from)?;
+    }
+    Ok(app_settings.general_settings())
+}
 
-    db.get_cache_stats().await.map_err(|e| e.to_string())
+// Where bodies too big to return inline are kept while their responses are (see large_body)
+fn response_body_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
+    use tauri::Manager;
+    let cache_dir = app.path().app_cache_dir().map_err(|e| AppError::new(ErrorKind::Io, e.to_string()))?;
+    Ok(cache_dir.join("responses"))
 }
 
 #[tauri::command]
-async fn clear_expired_cache(db_state: State<'_, DatabaseState>) -> Result<u64, String> {
-    let db = {
-        let db_guard = db_state.lock().unwrap();
-        db_guard.as_ref().ok_or("Database not initialized")?.clone()
-    };
-
-    db.clear_expired_cache().await.map_err(|e| e.to_string())
+async fn get_large_response_bytes(app: tauri::AppHandle) -> Result<u64, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    Ok(settings.large_response_bytes())
 }
 
+// Bodies bigger than this are written to disk and paged; None goes back to the default
 #[tauri::command]
-async fn clear_all_cache(db_state: State<'_, DatabaseState>) -> Result<u64, String> {
-    let db = {
-        let db_guard = db_state.lock().unwrap();
-        db_guard.as_ref().ok_or("Database not initialized")?.clone()
-    };
+async fn set_large_response_bytes(max_bytes: Option<u64>, app: tauri::AppHandle) -> Result<u64, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    app_settings.set_large_response_bytes(max_bytes).map_err(AppError::from)?;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+    Ok(app_settings.large_response_bytes())
+}
 
-    db.clear_all_cache().await.map_err(|e| e.to_string())
+// 🎓 TEACHING: The activity log lives in the platform log directory and is off until turned on
+fn configure_logging(app: &tauri::AppHandle, config: &logging::LoggingConfig) -> Result<logging::LoggingStatus, AppError> {
+    use tauri::Manager;
+    let log_dir = app.path().app_log_dir().map_err(|e| AppError::new(ErrorKind::Io, e.to_string()))?;
+    logging::configure(&log_dir, config);
+    Ok(logging::status(&log_dir, config))
 }
 
 #[tauri::command]
-async fn get_cached_response_by_hash(
-    request_hash: String,
-    db_state: State<'_, DatabaseState>,
-) -> Result<Option<database::ResponseCache>, String> {
-    let db = {
-        let db_guard = db_state.lock().unwrap();
-        db_guard.as_ref().ok_or("Database not initialized")?.clone()
-    };
+async fn get_logging_config(app: tauri::AppHandle) -> Result<logging::LoggingStatus, AppError> {
+    use tauri::Manager;
+    let (config_dir, _) = app_dirs(&app)?;
+    let settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    let log_dir = app.path().app_log_dir().map_err(|e| AppError::new(ErrorKind::Io, e.to_string()))?;
+    Ok(logging::status(&log_dir, &settings.logging))
+}
 
-    db.get_cached_response_by_hash(&request_hash).await.map_err(|e| e.to_string())
+// Takes effect straight away; turning the log off leaves the files that were written
+#[tauri::command]
+async fn set_logging_config(
+    config: logging::LoggingConfig,
+    app: tauri::AppHandle,
+) -> Result<logging::LoggingStatus, AppError> {
+    let (config_dir, _) = app_dirs(&app)?;
+    let mut app_settings = settings::AppSettings::load(&config_dir).map_err(AppError::from)?;
+    app_settings.set_logging_config(config).map_err(AppError::from)?;
+    app_settings.save(&config_dir).map_err(AppError::from)?;
+    configure_logging(&app, &app_settings.logging)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(DatabaseState::default())
+        .manage(session::SessionCache::default())
+        .manage(mqtt::MqttManager::default())
+        .manage(streaming::StreamRegistry::default())
+        .manage(response_diff::ResponseStore::default())
+        .manage(webhook::WebhookManager::default())
+        .manage(mock::MockManager::default())
+        .manage(proxy::ProxyManager::default())
+        .manage(queue::RequestQueue::default())
+        .manage(sync::WorkspaceSync::default())
+        .setup(|app| {
+            // A settings file that can't be read just means no log, not no app
+            let logging_config = app_dirs(app.handle())
+                .ok()
+                .and_then(|(config_dir, _)| settings::AppSettings::load(&config_dir).ok())
+                .unwrap_or_default()
+                .logging;
+            if let Err(e) = configure_logging(app.handle(), &logging_config) {
+                logging::warn(format!("Could not start the activity log: {}", e));
+            }
+            let body_dir = response_body_dir(app.handle());
+            if let Err(e) = body_dir.and_then(|dir| large_body::clear_dir(&dir).map_err(AppError::from)) {
+                logging::warn(format!("Could not clear large response bodies from the last run: {}", e));
+            }
+            monitor::start_scheduler(app.handle().clone());
+            queue::start_worker(app.handle().clone());
+            cache_maintenance::start(app.handle().clone());
+            sync::start(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             init_database,
+            get_database_location,
+            migrate_database_location,
+            get_database_max_connections,
+            set_database_max_connections,
+            get_storage_stats,
+            compact_database,
+            // Workspaces
+            list_workspaces,
+            create_workspace,
+            switch_workspace,
+            rename_workspace,
+            delete_workspace,
+            get_workspace_settings,
+            update_workspace_settings,
             create_collection,
             get_collections,
+            get_collection_tree,
             update_collection,
+            move_collection,
             delete_collection,
             get_collection_by_id,
             create_request,
             get_requests_by_collection,
             update_request,
+            move_request,
             delete_request,
             get_request_by_id,
             send_api_request,
             export_collection_to_json,
             import_collection_from_json,
+            merge_collection_from_json,
+            import_openapi,
+            import_http_file,
+            import_thunder_client,
+            export_collection_to_directory,
+            import_collection_from_directory,
+            // Tags
+            create_tag,
+            get_tags,
+            update_tag,
+            delete_tag,
+            tag_request,
+            untag_request,
+            tag_collection,
+            untag_collection,
+            get_request_tags,
+            get_collection_tags,
+            get_requests_by_tag,
+            get_collections_by_tag,
+            // Bulk operations
+            bulk_update_requests,
+            bulk_delete_requests,
+            bulk_create_variables,
+            // Favorites and recents
+            set_request_pinned,
+            get_favorite_requests,
+            get_recent_requests,
+            // Docs
+            update_request_docs,
+            update_collection_docs,
+            update_collection_trace_context,
+            update_collection_default_headers,
             // Phase 2: Environment Management
             create_environment,
             get_environments,
@@ -846,6 +4876,9 @@ pub fn run() {
             get_active_environment,
             update_environment,
             delete_environment,
+            duplicate_environment,
+            export_environment,
+            import_environment,
             // Phase 2: Variable Management
             create_variable,
             get_variables,
@@ -853,17 +4886,171 @@ pub fn run() {
             update_variable,
             delete_variable,
             interpolate_string,
+            find_variable_references,
+            list_faker_variables,
             // Phase 2: OAuth 2.0
             oauth_get_authorization_url,
             oauth_exchange_code_for_token,
             oauth_client_credentials_flow,
+            oauth_password_flow,
+            oauth_jwt_bearer_flow,
+            oauth_service_account_config,
             oauth_refresh_token,
             oauth_parse_callback_url,
+            // AWS
+            aws_presign_url,
+            // OpenID Connect
+            oidc_discover,
+            oidc_validate_id_token,
+            decode_jwt,
+            // Secret encryption
+            get_secret_encryption_status,
+            enable_secret_encryption,
+            enable_keychain_secret_encryption,
+            lock_workspace,
+            unlock_workspace,
+            // OS keychain
+            store_secret,
+            get_secret,
+            migrate_secrets_to_keychain,
+            // Variable providers
+            fetch_variable_source,
+            clear_variable_provider_cache,
+            // Auth plugins
+            install_auth_plugin,
+            list_auth_plugins,
+            uninstall_auth_plugin,
+            // Collection runner
+            run_collection,
+            run_collection_matrix,
+            run_contract_test,
+            get_collection_runs,
+            delete_collection_run,
+            export_run_report,
+            // Monitors
+            create_monitor,
+            get_monitors,
+            update_monitor,
+            delete_monitor,
+            run_monitor,
+            get_monitor_results,
+            // Workflows
+            create_workflow,
+            get_workflows,
+            update_workflow,
+            delete_workflow,
+            run_workflow,
+            // Request analytics
+            get_request_analytics,
+            // History
+            export_history,
+            get_history_retention,
+            set_history_retention,
+            diff_responses,
+            query_response_body,
+            get_response_chunk,
+            search_response_body,
+            format_body,
+            // Utilities
+            encode_text,
+            decode_text,
+            hash_text,
+            hmac_text,
+            generate_uuids,
+            // Script library
+            create_script_module,
+            get_script_modules,
+            update_script_module,
+            delete_script_module,
+            // Session auth
+            clear_session_cache,
+            // gRPC
+            list_grpc_services,
+            send_grpc_request,
+            // MQTT
+            mqtt_connect,
+            mqtt_publish,
+            mqtt_subscribe,
+            mqtt_unsubscribe,
+            mqtt_disconnect,
+            // Raw sockets
+            send_raw_socket,
+            // TLS
+            inspect_tls,
+            // Connectivity diagnostics
+            resolve_host,
+            tcp_connect_check,
+            probe_latency,
+            // Cookie jar
+            get_cookies,
+            delete_cookie,
+            clear_cookies,
+            // Response streaming
+            stop_response_stream,
+            // Webhook listener
+            start_webhook_listener,
+            stop_webhook_listener,
+            list_webhook_listeners,
+            get_webhook_captures,
+            clear_webhook_captures,
+            // Mock server
+            start_mock_server,
+            start_openapi_mock_server,
+            stop_mock_server,
+            list_mock_servers,
+            reload_mock_server,
+            set_mock_response,
+            get_mock_response,
+            delete_mock_response,
+            // Record/replay proxy
+            start_proxy,
+            stop_proxy,
+            list_proxies,
+            get_proxy_captures,
+            clear_proxy_captures,
             // Phase 2: Response Caching
             get_cache_stats,
             clear_expired_cache,
             clear_all_cache,
-            get_cached_response_by_hash
+            get_cached_response_by_hash,
+            list_cache_entries,
+            delete_cache_entry,
+            invalidate_cache,
+            get_invalidate_cache_on_write,
+            set_invalidate_cache_on_write,
+            get_cache_max_bytes,
+            set_cache_max_bytes,
+            get_large_response_bytes,
+            set_large_response_bytes,
+            get_cache_policies,
+            create_cache_policy,
+            update_cache_policy,
+            delete_cache_policy,
+            get_offline_mode,
+            set_offline_mode,
+            get_trace_context,
+            set_trace_context,
+            get_settings,
+            update_settings,
+            // Request queue
+            enqueue_request,
+            get_queue_status,
+            set_queue_paused,
+            set_queue_priority,
+            cancel_queued_request,
+            clear_queue,
+            // Rate limits
+            get_rate_limits,
+            create_rate_limit,
+            update_rate_limit,
+            delete_rate_limit,
+            // Workspace sync
+            get_sync_config,
+            set_sync_config,
+            sync_workspace,
+            // Activity log
+            get_logging_config,
+            set_logging_config
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -871,11 +5058,11 @@ pub fn run() {
 
 // 🎓 TEACHING: Test-only version of send_api_request without database dependency
 #[cfg(test)]
-async fn send_api_request_test_only(request: ApiRequest) -> Result<ApiResponse, String> {
+async fn send_api_request_test_only(request: ApiRequest) -> Result<ApiResponse, AppError> {
     // For tests, we'll skip the database/caching functionality
     // and only test the HTTP request parts
     
-    let client = reqwest::Client::new();
+    let client = build_http_client(&request)?;
 
     let method = match request.method.to_uppercase().as_str() {
         "GET" => reqwest::Method::GET,
@@ -885,10 +5072,19 @@ async fn send_api_request_test_only(request: ApiRequest) -> Result<ApiResponse,
         "PATCH" => reqwest::Method::PATCH,
         "HEAD" => reqwest::Method::HEAD,
         "OPTIONS" => reqwest::Method::OPTIONS,
-        _ => return Err("Unsupported HTTP method".to_string()),
+        _ => return Err(AppError::validation("Unsupported HTTP method")),
     };
 
-    let mut req_builder = client.request(method, &request.url).query(&request.params);
+    let request_url = params::substitute_path_params(&request.url, &request.path_params)
+        .map_err(AppError::from)?;
+    let request_url = params::append_query_params(
+        &request_url,
+        &request.params,
+        request.param_encoding.as_deref(),
+    )
+    .map_err(AppError::from)?;
+
+    let mut req_builder = client.request(method, &request_url);
 
     // Add headers (no interpolation in tests)
     for (key, value) in &request.headers {
@@ -901,7 +5097,7 @@ async fn send_api_request_test_only(request: ApiRequest) -> Result<ApiResponse,
             "basic" => {
                 if let Some(auth_data) = request.auth_data {
                     let auth: HashMap<String, String> =
-                        serde_json::from_str(&auth_data).map_err(|e| e.to_string())?;
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
                     let username = auth
                         .get("username")
                         .ok_or("Username not found in auth_data")?;
@@ -914,7 +5110,7 @@ async fn send_api_request_test_only(request: ApiRequest) -> Result<ApiResponse,
             "bearer" => {
                 if let Some(auth_data) = request.auth_data {
                     let auth: HashMap<String, String> =
-                        serde_json::from_str(&auth_data).map_err(|e| e.to_string())?;
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
                     let token = auth.get("token").ok_or("Token not found in auth_data")?;
                     req_builder = req_builder.bearer_auth(token);
                 }
@@ -922,7 +5118,7 @@ async fn send_api_request_test_only(request: ApiRequest) -> Result<ApiResponse,
             "api-key" => {
                 if let Some(auth_data) = request.auth_data {
                     let auth: HashMap<String, String> =
-                        serde_json::from_str(&auth_data).map_err(|e| e.to_string())?;
+                        serde_json::from_str(&auth_data).map_err(AppError::from)?;
                     let key = auth.get("key").ok_or("Key not found in auth_data")?;
                     let value = auth.get("value").ok_or("Value not found in auth_data")?;
                     let in_ = auth.get("in").ok_or("In not found in auth_data")?;
@@ -943,15 +5139,16 @@ async fn send_api_request_test_only(request: ApiRequest) -> Result<ApiResponse,
         req_builder = req_builder.body(body.clone());
     }
 
-    let res = req_builder.send().await.map_err(|e| e.to_string())?;
+    let res = req_builder.send().await.map_err(AppError::from)?;
 
     let status = res.status().as_u16();
+    let http_version = format_http_version(res.version());
     let mut headers = HashMap::new();
     for (key, value) in res.headers().iter() {
         headers.insert(key.to_string(), value.to_str().unwrap_or("").to_string());
     }
 
-    let body = res.text().await.map_err(|e| e.to_string())?;
+    let body = res.text().await.map_err(AppError::from)?;
 
     Ok(ApiResponse {
         status,
@@ -959,6 +5156,18 @@ async fn send_api_request_test_only(request: ApiRequest) -> Result<ApiResponse,
         body,
         from_cache: Some(false),
         cache_time: None,
+        cache_stale: None,
+        http_version: Some(http_version),
+        unresolved_variables: Vec::new(),
+        captured: Vec::new(),
+        test_results: Vec::new(),
+        script_logs: Vec::new(),
+        wire_log: None,
+        trace: None,
+        large_body: None,
+        cookies: Vec::new(),
+        throttled_ms: None,
+        attempts: 0,
     })
 }
 
@@ -975,20 +5184,59 @@ mod tests {
         let mut headers = HashMap::new();
         headers.insert("X-Test-Header".to_string(), "gemini-test".to_string());
 
-        let mut params = HashMap::new();
-        params.insert("param1".to_string(), "value1".to_string());
-        params.insert("param2".to_string(), "value with spaces".to_string());
+        let params = vec![
+            params::QueryParam {
+                key: "param1".to_string(),
+                value: "value1".to_string(),
+                enabled: true,
+            },
+            params::QueryParam {
+                key: "param2".to_string(),
+                value: "value with spaces".to_string(),
+                enabled: true,
+            },
+        ];
 
         let api_request = ApiRequest {
             method: "GET".to_string(),
             url: "https://httpbin.org/get".to_string(),
             params,
+            param_encoding: None,
+            path_params: HashMap::new(),
             headers,
             body: None,
+            body_type: None,
+            graphql_variables: None,
+            graphql_operation_name: None,
             auth_type: None,
             auth_data: None,
+            collection_id: None,
+            request_id: None,
+            variable_overrides: HashMap::new(),
+            environment_id: None,
+            strict_variables: None,
+            debug: None,
+            captures: Vec::new(),
+            scripts: Default::default(),
             use_cache: Some(false),
             cache_duration: None,
+            cache_ignore_headers: None,
+            cache_mode: None,
+            offline: false,
+            invalidate_cache_on_write: false,
+            trace_context: Default::default(),
+            default_headers: HashMap::new(),
+            disabled_default_headers: Vec::new(),
+            http_version: None,
+            socket_path: None,
+            host_overrides: HashMap::new(),
+            timeout_ms: None,
+            follow_redirects: None,
+            max_redirects: None,
+            proxy_url: None,
+            verify_tls: None,
+            use_cookie_jar: None,
+            retry: None,
         };
 
         // 2. Execute: Call our test-only function
@@ -1023,13 +5271,43 @@ mod tests {
         let api_request = ApiRequest {
             method: "GET".to_string(),
             url: "https://httpbin.org/bearer".to_string(),
-            params: HashMap::new(),
+            params: Vec::new(),
+            param_encoding: None,
+            path_params: HashMap::new(),
             headers,
             body: None,
+            body_type: None,
+            graphql_variables: None,
+            graphql_operation_name: None,
             auth_type: Some("bearer".to_string()),
             auth_data: Some(serde_json::to_string(&auth_data).unwrap()),
+            collection_id: None,
+            request_id: None,
+            variable_overrides: HashMap::new(),
+            environment_id: None,
+            strict_variables: None,
+            debug: None,
+            captures: Vec::new(),
+            scripts: Default::default(),
             use_cache: Some(false),
             cache_duration: None,
+            cache_ignore_headers: None,
+            cache_mode: None,
+            offline: false,
+            invalidate_cache_on_write: false,
+            trace_context: Default::default(),
+            default_headers: HashMap::new(),
+            disabled_default_headers: Vec::new(),
+            http_version: None,
+            socket_path: None,
+            host_overrides: HashMap::new(),
+            timeout_ms: None,
+            follow_redirects: None,
+            max_redirects: None,
+            proxy_url: None,
+            verify_tls: None,
+            use_cookie_jar: None,
+            retry: None,
         };
 
         // 2. Execute: Call our test-only function
@@ -1061,13 +5339,43 @@ mod tests {
             method: "GET".to_string(),
             // This httpbin endpoint validates the user/pass in the URL
             url: "https://httpbin.org/basic-auth/testuser/testpass".to_string(),
-            params: HashMap::new(),
+            params: Vec::new(),
+            param_encoding: None,
+            path_params: HashMap::new(),
             headers: HashMap::new(),
             body: None,
+            body_type: None,
+            graphql_variables: None,
+            graphql_operation_name: None,
             auth_type: Some("basic".to_string()),
             auth_data: Some(serde_json::to_string(&auth_data).unwrap()),
+            collection_id: None,
+            request_id: None,
+            variable_overrides: HashMap::new(),
+            environment_id: None,
+            strict_variables: None,
+            debug: None,
+            captures: Vec::new(),
+            scripts: Default::default(),
             use_cache: Some(false),
             cache_duration: None,
+            cache_ignore_headers: None,
+            cache_mode: None,
+            offline: false,
+            invalidate_cache_on_write: false,
+            trace_context: Default::default(),
+            default_headers: HashMap::new(),
+            disabled_default_headers: Vec::new(),
+            http_version: None,
+            socket_path: None,
+            host_overrides: HashMap::new(),
+            timeout_ms: None,
+            follow_redirects: None,
+            max_redirects: None,
+            proxy_url: None,
+            verify_tls: None,
+            use_cookie_jar: None,
+            retry: None,
         };
 
         // 2. Execute
@@ -1100,13 +5408,43 @@ mod tests {
         let api_request = ApiRequest {
             method: "GET".to_string(),
             url: "https://httpbin.org/headers".to_string(),
-            params: HashMap::new(),
+            params: Vec::new(),
+            param_encoding: None,
+            path_params: HashMap::new(),
             headers: HashMap::new(),
             body: None,
+            body_type: None,
+            graphql_variables: None,
+            graphql_operation_name: None,
             auth_type: Some("api-key".to_string()),
             auth_data: Some(serde_json::to_string(&auth_data).unwrap()),
+            collection_id: None,
+            request_id: None,
+            variable_overrides: HashMap::new(),
+            environment_id: None,
+            strict_variables: None,
+            debug: None,
+            captures: Vec::new(),
+            scripts: Default::default(),
             use_cache: Some(false),
             cache_duration: None,
+            cache_ignore_headers: None,
+            cache_mode: None,
+            offline: false,
+            invalidate_cache_on_write: false,
+            trace_context: Default::default(),
+            default_headers: HashMap::new(),
+            disabled_default_headers: Vec::new(),
+            http_version: None,
+            socket_path: None,
+            host_overrides: HashMap::new(),
+            timeout_ms: None,
+            follow_redirects: None,
+            max_redirects: None,
+            proxy_url: None,
+            verify_tls: None,
+            use_cookie_jar: None,
+            retry: None,
         };
 
         // 2. Execute
@@ -1140,13 +5478,43 @@ mod tests {
         let api_request = ApiRequest {
             method: "GET".to_string(),
             url: "https://httpbin.org/get".to_string(),
-            params: HashMap::new(),
+            params: Vec::new(),
+            param_encoding: None,
+            path_params: HashMap::new(),
             headers: HashMap::new(),
             body: None,
+            body_type: None,
+            graphql_variables: None,
+            graphql_operation_name: None,
             auth_type: Some("api-key".to_string()),
             auth_data: Some(serde_json::to_string(&auth_data).unwrap()),
+            collection_id: None,
+            request_id: None,
+            variable_overrides: HashMap::new(),
+            environment_id: None,
+            strict_variables: None,
+            debug: None,
+            captures: Vec::new(),
+            scripts: Default::default(),
             use_cache: Some(false),
             cache_duration: None,
+            cache_ignore_headers: None,
+            cache_mode: None,
+            offline: false,
+            invalidate_cache_on_write: false,
+            trace_context: Default::default(),
+            default_headers: HashMap::new(),
+            disabled_default_headers: Vec::new(),
+            http_version: None,
+            socket_path: None,
+            host_overrides: HashMap::new(),
+            timeout_ms: None,
+            follow_redirects: None,
+            max_redirects: None,
+            proxy_url: None,
+            verify_tls: None,
+            use_cookie_jar: None,
+            retry: None,
         };
 
         // 2. Execute
@@ -1166,4 +5534,149 @@ mod tests {
             assert!(response.body.contains("\"api_key\": \"my-secret-api-key\""));
         }
     }
+
+    fn request_with_version(http_version: Option<&str>) -> ApiRequest {
+        ApiRequest {
+            method: "GET".to_string(),
+            http_version: http_version.map(|v| v.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_http_client_versions() {
+        assert!(build_http_client(&request_with_version(None)).is_ok());
+        assert!(build_http_client(&request_with_version(Some("http1"))).is_ok());
+        assert!(build_http_client(&request_with_version(Some("http2"))).is_ok());
+        assert!(build_http_client(&request_with_version(Some("http3"))).is_err());
+        assert!(build_http_client(&request_with_version(Some("spdy"))).is_err());
+    }
+
+    #[test]
+    fn test_build_http_client_host_overrides() {
+        let mut request = request_with_version(None);
+        request.host_overrides.insert("api.example.com".to_string(), "127.0.0.1".to_string());
+        request.host_overrides.insert("v6.example.com".to_string(), "[::1]".to_string());
+        assert!(build_http_client(&request).is_ok());
+
+        request.host_overrides.insert("bad.example.com".to_string(), "not-an-ip".to_string());
+        let err = build_http_client(&request).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Validation);
+        assert!(err.message.contains("bad.example.com"));
+    }
+
+    #[test]
+    fn test_build_http_client_settings() {
+        let mut request = request_with_version(None);
+        request.timeout_ms = Some(2500);
+        request.follow_redirects = Some(false);
+        request.verify_tls = Some(false);
+        request.proxy_url = Some("http://127.0.0.1:8888".to_string());
+        assert!(build_http_client(&request).is_ok());
+
+        request.proxy_url = Some("not a proxy".to_string());
+        assert!(build_http_client(&request).unwrap_err().message.contains("Invalid proxy URL"));
+    }
+
+    // 🎓 TEACHING: Spin up a tiny HTTP server on a Unix socket and talk to it over that socket
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_send_over_unix_socket() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let socket_path = std::env::temp_dir().join(format!("openrequest-{}.sock", uuid::Uuid::new_v4()));
+        let listener = tokio::net::UnixListener::bind(&socket_path).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .await
+                .unwrap();
+        });
+
+        let api_request = ApiRequest {
+            method: "GET".to_string(),
+            url: "http://localhost/_ping".to_string(),
+            socket_path: Some(socket_path.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        let response = send_api_request_test_only(api_request).await.unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "ok");
+    }
+
+    #[test]
+    fn test_cache_key_headers() {
+        let mut headers = HashMap::new();
+        headers.insert("Accept".to_string(), "application/json".to_string());
+        headers.insert("X-Tenant".to_string(), "acme".to_string());
+        headers.insert("Authorization".to_string(), "Bearer one".to_string());
+        headers.insert("traceparent".to_string(), "00-abc-01".to_string());
+        let key = cache_key_headers(&headers, None);
+        assert_eq!(key, r#"{"accept":"application/json","x-tenant":"acme"}"#);
+
+        // A different token and trace id still hit the same entry
+        let mut other = headers.clone();
+        other.insert("Authorization".to_string(), "Bearer two".to_string());
+        other.remove("traceparent");
+        assert_eq!(cache_key_headers(&other, None), key);
+
+        // A custom list replaces the defaults
+        let ignored = vec!["x-tenant".to_string()];
+        assert_eq!(
+            cache_key_headers(&headers, Some(&ignored)),
+            r#"{"accept":"application/json","authorization":"Bearer one","traceparent":"00-abc-01"}"#
+        );
+    }
+
+    #[test]
+    fn test_format_http_version() {
+        assert_eq!(format_http_version(reqwest::Version::HTTP_11), "HTTP/1.1");
+        assert_eq!(format_http_version(reqwest::Version::HTTP_2), "HTTP/2.0");
+    }
+
+    // A server that always answers 503 and asks for an immediate retry
+    async fn unavailable_server() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buffer = [0u8; 4096];
+                let _ = socket.read(&mut buffer).await;
+                let response = "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{}/busy", address)
+    }
+
+    #[tokio::test]
+    async fn test_retries_wait_for_the_rate_limit() {
+        let dir = std::env::temp_dir().join(format!("retry-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(&settings::database_url(&dir.join("retry.db")), 1).await.unwrap();
+        db.create_rate_limit("127.0.0.1".to_string(), Some(2.0), None).await.unwrap();
+
+        let request = ApiRequest {
+            method: "GET".to_string(),
+            url: unavailable_server().await,
+            retry: Some(runner::RetryPolicy { max_attempts: 3, jitter: false, ..Default::default() }),
+            ..Default::default()
+        };
+        let started = std::time::Instant::now();
+        let response = execute_request(&db, &session::SessionCache::default(), request, None, None).await.unwrap();
+        // Two sends a second: the third attempt waits for a token although Retry-After is 0
+        assert_eq!((response.status, response.attempts), (503, 3));
+        assert!(response.throttled_ms.unwrap() >= 400);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(400));
+
+        db.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
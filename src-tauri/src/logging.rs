@@ -0,0 +1,312 @@
+// 🎓 TEACHING: Activity log
+// With logging turned on, every send and every warning the app used to print to the console
+// goes into a JSON lines file in the platform log directory, one object per line:
+//
+//   {"time":"2026-01-05T10:12:03.120Z","level":"info","event":"send","method":"GET","url":"...","status":200,...}
+//   {"time":"2026-01-05T10:12:04.001Z","level":"warn","event":"message","message":"Keychain entry missing ..."}
+//
+// When the file would grow past `max_file_bytes` it's renamed to openrequest.log.1 (the older
+// ones shift up to .2, .3, ...) and a fresh one is started, keeping at most `max_files` old
+// ones. That gives users a file to attach to a bug report without it filling the disk.
+// URLs and error messages pass through the secret redactor before they're written.
+//
+// The log is process-wide rather than Tauri state, so the database layer and background tasks
+// can write to it without an AppHandle. Debug builds also echo messages to the console.
+
+use crate::error::AppError;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const LOG_FILE: &str = "openrequest.log";
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+pub const DEFAULT_MAX_FILES: u32 = 5;
+pub const MAX_MAX_FILES: u32 = 50;
+// Below this a single send could rotate the file on every line
+pub const MIN_MAX_FILE_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LoggingConfig {
+    pub enabled: bool,
+    pub level: Level, // Entries below this level are left out
+    pub max_file_bytes: u64,
+    pub max_files: u32, // Rotated files kept besides the current one
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            enabled: false,
+            level: Level::Info,
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.max_file_bytes < MIN_MAX_FILE_BYTES {
+            return Err(anyhow::anyhow!("Log files must be allowed at least {} bytes", MIN_MAX_FILE_BYTES));
+        }
+        if self.max_files > MAX_MAX_FILES {
+            return Err(anyhow::anyhow!("At most {} old log files can be kept", MAX_MAX_FILES));
+        }
+        Ok(())
+    }
+}
+
+// What the settings screen shows: the configuration and where the current file is
+#[derive(Debug, Serialize, Clone)]
+pub struct LoggingStatus {
+    #[serde(flatten)]
+    pub config: LoggingConfig,
+    pub log_file: String,
+}
+
+pub fn status(log_dir: &Path, config: &LoggingConfig) -> LoggingStatus {
+    LoggingStatus {
+        config: config.clone(),
+        log_file: log_dir.join(LOG_FILE).display().to_string(),
+    }
+}
+
+// One send, as the log records it
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SendRecord {
+    pub method: String,
+    pub url: String,
+    pub request_id: Option<String>,
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    pub response_bytes: Option<u64>,
+    pub from_cache: bool,
+    pub error: Option<AppError>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    Message { message: &'a str },
+    Send(&'a SendRecord),
+}
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    time: String,
+    level: Level,
+    #[serde(flatten)]
+    event: Event<'a>,
+}
+
+struct LogFile {
+    path: PathBuf,
+    config: LoggingConfig,
+    file: Option<File>, // Opened on the first write
+    size: u64,
+}
+
+impl LogFile {
+    fn new(log_dir: &Path, config: LoggingConfig) -> Self {
+        LogFile {
+            path: log_dir.join(LOG_FILE),
+            config,
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.file.is_none() {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            self.size = file.metadata()?.len();
+            self.file = Some(file);
+        }
+        // A line bigger than the limit still goes in, on its own in a fresh file
+        if self.size > 0 && self.size + len > self.config.max_file_bytes {
+            self.rotate()?;
+        }
+        let file = self.file.as_mut().expect("log file is open");
+        writeln!(file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    // openrequest.log -> .1 -> .2 ...; whatever would go past max_files is overwritten
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file = None;
+        for n in (1..self.config.max_files).rev() {
+            let from = self.rotated_path(n);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(n + 1))?;
+            }
+        }
+        if self.config.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = Some(File::create(&self.path)?);
+        self.size = 0;
+        Ok(())
+    }
+
+    fn rotated_path(&self, n: u32) -> PathBuf {
+        let mut path = self.path.as_os_str().to_owned();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+}
+
+static LOG: Mutex<Option<LogFile>> = Mutex::new(None);
+
+fn log_file() -> std::sync::MutexGuard<'static, Option<LogFile>> {
+    // A panic mid-write leaves nothing half-done that matters, so keep logging
+    LOG.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Start, stop or reconfigure the log; called at startup and when the settings change
+pub fn configure(log_dir: &Path, config: &LoggingConfig) {
+    *log_file() = config.enabled.then(|| LogFile::new(log_dir, config.clone()));
+}
+
+pub fn enabled() -> bool {
+    log_file().is_some()
+}
+
+fn write(level: Level, event: Event) {
+    let mut log = log_file();
+    let Some(log) = log.as_mut().filter(|log| level >= log.config.level) else {
+        return;
+    };
+    let entry = Entry {
+        time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        level,
+        event,
+    };
+    let Ok(line) = serde_json::to_string(&entry) else { return };
+    if let Err(e) = log.write_line(&line) {
+        // Not much else can be done; the console is where messages went before
+        eprintln!("Could not write to {}: {}", log.path.display(), e);
+    }
+}
+
+fn message(level: Level, message: &str) {
+    if cfg!(debug_assertions) {
+        println!("[{:?}] {}", level, message);
+    }
+    write(level, Event::Message { message });
+}
+
+pub fn debug(text: impl std::fmt::Display) {
+    message(Level::Debug, &text.to_string());
+}
+
+pub fn info(text: impl std::fmt::Display) {
+    message(Level::Info, &text.to_string());
+}
+
+pub fn warn(text: impl std::fmt::Display) {
+    message(Level::Warn, &text.to_string());
+}
+
+pub fn error(text: impl std::fmt::Display) {
+    message(Level::Error, &text.to_string());
+}
+
+// Failed sends are warnings, so a log kept at "warn" still shows them
+pub fn send(record: &SendRecord) {
+    let level = if record.error.is_some() { Level::Warn } else { Level::Info };
+    write(level, Event::Send(record));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("openrequest-logging-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = temp_dir();
+        let config = LoggingConfig {
+            enabled: true,
+            max_file_bytes: 20,
+            max_files: 2,
+            ..LoggingConfig::default()
+        };
+        let mut log = LogFile::new(&dir, config);
+        for line in ["first line", "second line", "third line", "fourth line"] {
+            log.write_line(line).unwrap();
+        }
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read(LOG_FILE), "fourth line\n");
+        assert_eq!(read("openrequest.log.1"), "third line\n");
+        assert_eq!(read("openrequest.log.2"), "second line\n");
+        assert!(!dir.join("openrequest.log.3").exists());
+
+        // Reopening picks up the existing size instead of starting over
+        let mut log = LogFile::new(&dir, LoggingConfig { max_file_bytes: 30, ..log.config.clone() });
+        log.write_line("fifth").unwrap();
+        assert_eq!(read(LOG_FILE), "fourth line\nfifth\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_entry_shape() {
+        let record = SendRecord {
+            method: "GET".to_string(),
+            url: "https://api.example.com/users".to_string(),
+            request_id: None,
+            status: None,
+            duration_ms: 12,
+            response_bytes: None,
+            from_cache: false,
+            error: Some(AppError::new(crate::error::ErrorKind::Timeout, "timed out")),
+        };
+        let entry = Entry { time: "t".to_string(), level: Level::Warn, event: Event::Send(&record) };
+        let value = serde_json::to_value(&entry).unwrap();
+        assert_eq!(value["event"], "send");
+        assert_eq!(value["level"], "warn");
+        assert_eq!(value["url"], "https://api.example.com/users");
+        assert_eq!(value["error"]["kind"], "timeout");
+
+        let entry = Entry { time: "t".to_string(), level: Level::Info, event: Event::Message { message: "hi" } };
+        assert_eq!(
+            serde_json::to_string(&entry).unwrap(),
+            r#"{"time":"t","level":"info","event":"message","message":"hi"}"#
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(LoggingConfig::default().validate().is_ok());
+        assert!(LoggingConfig { max_file_bytes: 10, ..LoggingConfig::default() }.validate().is_err());
+        assert!(LoggingConfig { max_files: MAX_MAX_FILES + 1, ..LoggingConfig::default() }.validate().is_err());
+        // Old settings files without the section get the defaults
+        let config: LoggingConfig = serde_json::from_str(r#"{"enabled":true}"#).unwrap();
+        assert_eq!(config, LoggingConfig { enabled: true, ..LoggingConfig::default() });
+    }
+}
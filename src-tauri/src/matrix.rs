@@ -0,0 +1,200 @@
+// 🎓 TEACHING: Environment matrix runs
+// Runs one collection once per selected environment (dev, staging, prod...) and lines the
+// results up side by side: a row per request, a column per environment. A row whose
+// outcome differs between environments is flagged, which is usually the interesting part:
+// "passes on staging, 500 on prod". Each environment's run is also saved to the normal run
+// history, so it can be opened or exported on its own.
+
+use crate::database::{CollectionRun, Database};
+use crate::error::AppError;
+use crate::runner::{self, ProgressSink, RunOptions, RunRequestResult};
+use crate::session::SessionCache;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MatrixEnvironment {
+    pub environment_id: String,
+    pub name: String,
+    pub run_id: String,
+    pub passed_requests: u32,
+    pub failed_requests: u32,
+    pub avg_duration_ms: Option<u64>, // Over requests that got a response
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MatrixCell {
+    pub passed: bool,
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MatrixRow {
+    pub iteration: usize,
+    pub request_id: String,
+    pub name: String,
+    pub method: String,
+    pub cells: Vec<Option<MatrixCell>>, // One per environment, None if it wasn't sent there
+    pub consistent: bool,               // Same pass/fail and status everywhere it was sent
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MatrixRun {
+    pub collection_id: String,
+    pub environments: Vec<MatrixEnvironment>,
+    pub rows: Vec<MatrixRow>,
+}
+
+pub async fn run_matrix(
+    db: &Database,
+    session_cache: &SessionCache,
+    collection_id: &str,
+    environment_ids: &[String],
+    options: RunOptions,
+    progress: ProgressSink,
+) -> Result<MatrixRun, AppError> {
+    if environment_ids.is_empty() {
+        return Err(AppError::validation("Select at least one environment"));
+    }
+    // Check them all up front rather than failing after the first few runs
+    let mut environments = Vec::new();
+    for id in environment_ids {
+        match db.get_environment_by_id(id).await? {
+            Some(environment) => environments.push(environment),
+            None => return Err(AppError::not_found(format!("Environment not found: {}", id))),
+        }
+    }
+
+    // One environment at a time, so the runs don't compete for the same servers
+    let mut runs = Vec::new();
+    for environment in environments {
+        let options = RunOptions {
+            environment_id: Some(environment.id.clone()),
+            ..options.clone()
+        };
+        let run = runner::run_collection(db, session_cache, collection_id, options, progress.clone(), None).await?;
+        runs.push((environment.name, run));
+    }
+    build_matrix(collection_id, &runs)
+}
+
+pub fn build_matrix(collection_id: &str, runs: &[(String, CollectionRun)]) -> Result<MatrixRun, AppError> {
+    let mut environments = Vec::new();
+    let mut rows: Vec<MatrixRow> = Vec::new();
+    for (column, (name, run)) in runs.iter().enumerate() {
+        let results: Vec<RunRequestResult> = serde_json::from_str(&run.results)?;
+        let answered: Vec<u64> = results
+            .iter()
+            .filter(|result| result.status.is_some())
+            .map(|result| result.duration_ms)
+            .collect();
+        environments.push(MatrixEnvironment {
+            environment_id: run.environment_id.clone().unwrap_or_default(),
+            name: name.clone(),
+            run_id: run.id.clone(),
+            passed_requests: run.passed_requests,
+            failed_requests: run.failed_requests,
+            avg_duration_ms: (!answered.is_empty()).then(|| answered.iter().sum::<u64>() / answered.len() as u64),
+        });
+
+        for result in results {
+            let position = match rows
+                .iter()
+                .position(|row| row.request_id == result.request_id && row.iteration == result.iteration)
+            {
+                Some(position) => position,
+                None => {
+                    rows.push(MatrixRow {
+                        iteration: result.iteration,
+                        request_id: result.request_id.clone(),
+                        name: result.name.clone(),
+                        method: result.method.clone(),
+                        cells: vec![None; runs.len()],
+                        consistent: true,
+                    });
+                    rows.len() - 1
+                }
+            };
+            rows[position].cells[column] = Some(MatrixCell {
+                passed: result.passed,
+                status: result.status,
+                duration_ms: result.duration_ms,
+                error: result.error,
+            });
+        }
+    }
+
+    for row in &mut rows {
+        let mut outcomes = row.cells.iter().flatten().map(|cell| (cell.passed, cell.status));
+        let first = outcomes.next();
+        row.consistent = outcomes.all(|outcome| Some(outcome) == first);
+    }
+    Ok(MatrixRun {
+        collection_id: collection_id.to_string(),
+        environments,
+        rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(id: &str, results: Vec<RunRequestResult>) -> CollectionRun {
+        let now = chrono::Utc::now();
+        let passed = results.iter().filter(|result| result.passed).count() as u32;
+        CollectionRun {
+            id: id.to_string(),
+            collection_id: "c".to_string(),
+            environment_id: Some(format!("env-{}", id)),
+            iterations: 1,
+            total_requests: results.len() as u32,
+            passed_requests: passed,
+            failed_requests: results.len() as u32 - passed,
+            stopped_early: false,
+            results: serde_json::to_string(&results).unwrap(),
+            started_at: now,
+            finished_at: now,
+        }
+    }
+
+    fn result(request_id: &str, status: Option<u16>, duration_ms: u64) -> RunRequestResult {
+        RunRequestResult {
+            iteration: 0,
+            request_id: request_id.to_string(),
+            name: request_id.to_uppercase(),
+            method: "GET".to_string(),
+            status,
+            duration_ms,
+            error: None,
+            tests: Vec::new(),
+            passed: status == Some(200),
+            attempts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_build_matrix() {
+        let runs = vec![
+            ("Staging".to_string(), run("a", vec![result("list", Some(200), 100), result("get", Some(200), 50)])),
+            ("Prod".to_string(), run("b", vec![result("list", Some(200), 300), result("get", Some(500), 10)])),
+            ("Dev".to_string(), run("c", vec![result("list", Some(200), 20)])), // Stopped early
+        ];
+        let matrix = build_matrix("c", &runs).unwrap();
+
+        assert_eq!(matrix.environments.len(), 3);
+        assert_eq!(matrix.environments[0].avg_duration_ms, Some(75));
+        assert_eq!(matrix.environments[1].failed_requests, 1);
+        assert_eq!(matrix.environments[2].environment_id, "env-c");
+
+        assert_eq!(matrix.rows.len(), 2);
+        let list = &matrix.rows[0];
+        assert!(list.consistent);
+        assert_eq!(list.cells[1].as_ref().unwrap().duration_ms, 300);
+        let get = &matrix.rows[1];
+        assert!(!get.consistent);
+        assert_eq!(get.cells[1].as_ref().unwrap().status, Some(500));
+        assert!(get.cells[2].is_none());
+    }
+}
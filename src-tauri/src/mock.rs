@@ -0,0 +1,537 @@
+// 🎓 TEACHING: Mock server
+// Serves a collection's saved requests as fake endpoints on a local port, so a frontend
+// can be built before the real API exists. Each request's method + URL path becomes a
+// route (`:id` / `{id}` segments match anything), and its mock response decides the
+// status, headers, body, and an optional delay. Routes can also be generated from an
+// OpenAPI spec, for backends that so far exist only on paper.
+//
+// Response bodies and headers are templates: `{{variables}}` come from the active
+// environment, and `{{request.*}}` placeholders echo parts of the incoming call:
+//   {{request.method}} {{request.path}} {{request.body}}
+//   {{request.params.id}} {{request.query.page}} {{request.headers.x-user}}
+
+use crate::database::Database;
+use crate::http_server::{self, IncomingRequest, OutgoingResponse, ServerHandle};
+use crate::openapi;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MockRoute {
+    pub name: String,
+    pub method: String,
+    pub path: String, // Pattern like /users/:id
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+    pub latency_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MockServerConfig {
+    pub collection_id: String,
+    pub port: u16, // 0 picks a free port
+    pub latency_ms: Option<u64>, // Default delay for routes without their own
+    #[serde(default)]
+    pub bind_all: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OpenApiMockConfig {
+    pub spec_text: String, // OpenAPI 3.x or Swagger 2.0, JSON or YAML
+    pub port: u16,
+    pub latency_ms: Option<u64>,
+    #[serde(default)]
+    pub bind_all: bool,
+    pub response_mode: Option<String>, // "example" (default) uses documented examples, "schema" fakes everything from schemas
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MockServerInfo {
+    pub port: u16,
+    pub collection_id: Option<String>,
+    pub routes: Vec<MockRoute>,
+}
+
+struct RunningMock {
+    server: ServerHandle,
+    collection_id: Option<String>,
+    routes: Arc<RwLock<Vec<MockRoute>>>,
+}
+
+// Running mock servers, keyed by port
+#[derive(Default)]
+pub struct MockManager {
+    servers: Mutex<HashMap<u16, RunningMock>>,
+}
+
+// 🎓 TEACHING: Saved URLs usually look like `{{base_url}}/users/:id?x=1`.
+// Only the path matters for routing, so drop the host part and the query.
+pub fn route_path(url: &str) -> String {
+    let url = url.trim();
+    let without_query = url.split(['?', '#']).next().unwrap_or("");
+
+    let path = if let Some(rest) = without_query.strip_prefix("{{") {
+        // A leading variable stands for scheme + host
+        rest.split_once("}}").map(|(_, path)| path).unwrap_or("")
+    } else if let Some((_, after_scheme)) = without_query.split_once("://") {
+        after_scheme.find('/').map(|i| &after_scheme[i..]).unwrap_or("")
+    } else if without_query.starts_with('/') {
+        without_query
+    } else {
+        // host/path without a scheme
+        without_query.find('/').map(|i| &without_query[i..]).unwrap_or("")
+    };
+
+    let path = path.trim_end_matches('/');
+    if path.is_empty() {
+        "/".to_string()
+    } else if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    }
+}
+
+fn param_name(segment: &str) -> Option<&str> {
+    segment
+        .strip_prefix(':')
+        .or_else(|| segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')))
+        .filter(|name| !name.is_empty() && !name.starts_with('{'))
+}
+
+// 🎓 TEACHING: Literal segments beat parameters, so `/users/me` wins over `/users/:id`
+pub fn match_route<'a>(routes: &'a [MockRoute], method: &str, path: &str) -> Option<(&'a MockRoute, HashMap<String, String>)> {
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    let mut best: Option<(&MockRoute, HashMap<String, String>, usize)> = None;
+
+    for route in routes.iter().filter(|r| r.method.eq_ignore_ascii_case(method)) {
+        let pattern: Vec<&str> = route.path.trim_end_matches('/').split('/').collect();
+        if pattern.len() != segments.len() {
+            continue;
+        }
+
+        let mut params = HashMap::new();
+        let mut literal_matches = 0;
+        let matched = pattern.iter().zip(&segments).all(|(expected, actual)| match param_name(expected) {
+            Some(name) => {
+                params.insert(name.to_string(), actual.to_string());
+                true
+            }
+            None if expected == actual => {
+                literal_matches += 1;
+                true
+            }
+            None => false,
+        });
+
+        if matched && best.as_ref().is_none_or(|(_, _, score)| literal_matches > *score) {
+            best = Some((route, params, literal_matches));
+        }
+    }
+
+    best.map(|(route, params, _)| (route, params))
+}
+
+// Fill `{{request.*}}` placeholders; other `{{...}}` are left for variable interpolation
+pub fn render_request_placeholders(template: &str, request: &IncomingRequest, params: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{request.") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            result.push_str(&rest[start..]);
+            return result;
+        };
+
+        let key = after[..end].trim();
+        match request_value(key, request, params) {
+            Some(value) => result.push_str(&value),
+            None => result.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn request_value(key: &str, request: &IncomingRequest, params: &HashMap<String, String>) -> Option<String> {
+    match key {
+        "request.method" => return Some(request.method.clone()),
+        "request.path" => return Some(request.path.clone()),
+        "request.body" => return Some(String::from_utf8_lossy(&request.body).to_string()),
+        _ => {}
+    }
+    if let Some(name) = key.strip_prefix("request.params.") {
+        return Some(params.get(name).cloned().unwrap_or_default());
+    }
+    if let Some(name) = key.strip_prefix("request.query.") {
+        let query = request.query.as_deref().unwrap_or("");
+        let value = url::form_urlencoded::parse(query.as_bytes())
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.to_string());
+        return Some(value.unwrap_or_default());
+    }
+    if let Some(name) = key.strip_prefix("request.headers.") {
+        let value = request.headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.clone());
+        return Some(value.unwrap_or_default());
+    }
+    None
+}
+
+// 🎓 TEACHING: One route per saved request; requests without a mock response answer 200 with an empty body
+pub async fn routes_from_collection(db: &Database, collection_id: &str) -> Result<Vec<MockRoute>> {
+    let requests = db.get_requests_by_collection(collection_id).await?;
+    let mocks = db.get_mock_responses_by_collection(collection_id).await?;
+
+    let mut routes = Vec::new();
+    for request in requests {
+        let mock = mocks.get(&request.id);
+        routes.push(MockRoute {
+            name: request.name.clone(),
+            method: request.method.to_uppercase(),
+            path: route_path(&request.url),
+            status: mock.map(|m| m.status).unwrap_or(200),
+            headers: mock
+                .map(|m| serde_json::from_str(&m.headers))
+                .transpose()?
+                .unwrap_or_default(),
+            body: mock.map(|m| m.body.clone()).unwrap_or_default(),
+            latency_ms: mock.and_then(|m| m.latency_ms),
+        });
+    }
+    Ok(routes)
+}
+
+// 🎓 TEACHING: One route per operation, answering with its first success response.
+// The body is the documented example if there is one, otherwise a value faked from the schema.
+pub fn routes_from_openapi(spec_text: &str, use_examples: bool) -> Result<Vec<MockRoute>> {
+    let spec = openapi::parse_spec(spec_text)?;
+    let base_path = openapi::base_path(&spec);
+
+    let mut routes = Vec::new();
+    for operation in openapi::operations(&spec) {
+        let (status, response) = pick_response(&operation.responses);
+        let body = response.and_then(|response| openapi::response_body(&spec, response));
+
+        let mut headers = HashMap::new();
+        let body = match body {
+            Some(body) => {
+                headers.insert("Content-Type".to_string(), body.content_type.clone());
+                let value = match (body.example, &body.schema) {
+                    (Some(example), _) if use_examples => example,
+                    (_, Some(schema)) => openapi::example_from_schema(&spec, schema, use_examples),
+                    (example, None) => example.unwrap_or(serde_json::Value::Null),
+                };
+                match value {
+                    serde_json::Value::String(text) if !body.content_type.contains("json") => text,
+                    value => serde_json::to_string_pretty(&value)?,
+                }
+            }
+            None => String::new(),
+        };
+
+        routes.push(MockRoute {
+            name: operation
+                .operation_id
+                .or(operation.summary)
+                .unwrap_or_else(|| format!("{} {}", operation.method, operation.path)),
+            method: operation.method,
+            path: route_path(&format!("{}{}", base_path, operation.path)),
+            status,
+            headers,
+            body,
+            latency_ms: None,
+        });
+    }
+    Ok(routes)
+}
+
+// Lowest 2xx wins, then `default`, then whatever is documented first
+fn pick_response(responses: &[(String, serde_json::Value)]) -> (u16, Option<&serde_json::Value>) {
+    let success = responses
+        .iter()
+        .filter_map(|(code, response)| code.parse::<u16>().ok().map(|code| (code, response)))
+        .filter(|(code, _)| (200..300).contains(code))
+        .min_by_key(|(code, _)| *code);
+    if let Some((code, response)) = success {
+        return (code, Some(response));
+    }
+    if let Some((_, response)) = responses.iter().find(|(code, _)| code == "default") {
+        return (200, Some(response));
+    }
+    match responses.first() {
+        Some((code, response)) => (code.parse().unwrap_or(200), Some(response)),
+        None => (200, None),
+    }
+}
+
+impl MockManager {
+    pub async fn start(&self, db: Database, config: MockServerConfig) -> Result<MockServerInfo> {
+        let routes = routes_from_collection(&db, &config.collection_id).await?;
+        self.serve(db, config.port, config.bind_all, config.latency_ms, Some(config.collection_id), routes)
+            .await
+    }
+
+    pub async fn start_openapi(&self, db: Database, config: OpenApiMockConfig) -> Result<MockServerInfo> {
+        let use_examples = match config.response_mode.as_deref().unwrap_or("example") {
+            "example" => true,
+            "schema" => false,
+            other => return Err(anyhow::anyhow!("Unknown response mode: {}", other)),
+        };
+        let routes = routes_from_openapi(&config.spec_text, use_examples)?;
+        self.serve(db, config.port, config.bind_all, config.latency_ms, None, routes).await
+    }
+
+    // Serve a fixed set of routes, whether built from a collection or a spec
+    pub async fn serve(
+        &self,
+        db: Database,
+        port: u16,
+        bind_all: bool,
+        default_latency_ms: Option<u64>,
+        collection_id: Option<String>,
+        routes: Vec<MockRoute>,
+    ) -> Result<MockServerInfo> {
+        if port != 0 && self.servers.lock().unwrap().contains_key(&port) {
+            return Err(anyhow::anyhow!("A mock server is already running on port {}", port));
+        }
+
+        let routes = Arc::new(RwLock::new(routes));
+        let handler_routes = routes.clone();
+        let handler: http_server::Handler = Arc::new(move |request: IncomingRequest| {
+            let db = db.clone();
+            let routes = handler_routes.clone();
+            Box::pin(async move { respond(&db, &routes, default_latency_ms, request).await })
+        });
+
+        let server = http_server::start(port, bind_all, handler).await?;
+        let info = MockServerInfo {
+            port: server.port,
+            collection_id: collection_id.clone(),
+            routes: routes.read().unwrap().clone(),
+        };
+        self.servers.lock().unwrap().insert(
+            server.port,
+            RunningMock {
+                server,
+                collection_id,
+                routes,
+            },
+        );
+        Ok(info)
+    }
+
+    // Pick up edits to the collection without restarting the server
+    pub async fn reload(&self, db: &Database, port: u16) -> Result<MockServerInfo> {
+        let (collection_id, routes) = {
+            let servers = self.servers.lock().unwrap();
+            let running = servers
+                .get(&port)
+                .ok_or_else(|| anyhow::anyhow!("No mock server on port {}", port))?;
+            (running.collection_id.clone(), running.routes.clone())
+        };
+        let collection_id = collection_id
+            .ok_or_else(|| anyhow::anyhow!("The mock server on port {} is not backed by a collection", port))?;
+
+        let new_routes = routes_from_collection(db, &collection_id).await?;
+        *routes.write().unwrap() = new_routes.clone();
+        Ok(MockServerInfo {
+            port,
+            collection_id: Some(collection_id),
+            routes: new_routes,
+        })
+    }
+
+    pub fn stop(&self, port: u16) -> Result<()> {
+        let running = self
+            .servers
+            .lock()
+            .unwrap()
+            .remove(&port)
+            .ok_or_else(|| anyhow::anyhow!("No mock server on port {}", port))?;
+        running.server.stop();
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<MockServerInfo> {
+        let servers = self.servers.lock().unwrap();
+        let mut list: Vec<MockServerInfo> = servers
+            .iter()
+            .map(|(port, running)| MockServerInfo {
+                port: *port,
+                collection_id: running.collection_id.clone(),
+                routes: running.routes.read().unwrap().clone(),
+            })
+            .collect();
+        list.sort_by_key(|info| info.port);
+        list
+    }
+}
+
+async fn respond(
+    db: &Database,
+    routes: &RwLock<Vec<MockRoute>>,
+    default_latency_ms: Option<u64>,
+    request: IncomingRequest,
+) -> OutgoingResponse {
+    // Browsers preflight cross-origin calls; a mock should never be the thing blocking them
+    if request.method == "OPTIONS" {
+        let mut response = OutgoingResponse::new(204, Vec::new());
+        add_cors_headers(&mut response);
+        response.headers.insert("Access-Control-Allow-Methods".to_string(), "*".to_string());
+        response.headers.insert("Access-Control-Allow-Headers".to_string(), "*".to_string());
+        return response;
+    }
+
+    let matched = {
+        let routes = routes.read().unwrap();
+        match_route(&routes, &request.method, &request.path).map(|(route, params)| (route.clone(), params))
+    };
+    let Some((route, params)) = matched else {
+        let body = serde_json::json!({ "error": format!("No mock route for {} {}", request.method, request.path) });
+        let mut response = OutgoingResponse::new(404, body.to_string());
+        response.headers.insert("Content-Type".to_string(), "application/json".to_string());
+        add_cors_headers(&mut response);
+        return response;
+    };
+
+    if let Some(latency_ms) = route.latency_ms.or(default_latency_ms).filter(|ms| *ms > 0) {
+        tokio::time::sleep(Duration::from_millis(latency_ms)).await;
+    }
+
+    let body = render_request_placeholders(&route.body, &request, &params);
+    let body = match db.interpolate_string(&body).await {
+        Ok(body) => body,
+        Err(e) => return OutgoingResponse::new(500, format!("Failed to render mock response: {}", e)),
+    };
+
+    let mut response = OutgoingResponse::new(route.status, body);
+    for (key, value) in &route.headers {
+        let value = render_request_placeholders(value, &request, &params);
+        let value = db.interpolate_string(&value).await.unwrap_or(value);
+        response.headers.insert(key.clone(), value);
+    }
+    add_cors_headers(&mut response);
+    response
+}
+
+fn add_cors_headers(response: &mut OutgoingResponse) {
+    if !response.headers.keys().any(|k| k.eq_ignore_ascii_case("access-control-allow-origin")) {
+        response.headers.insert("Access-Control-Allow-Origin".to_string(), "*".to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(method: &str, path: &str) -> MockRoute {
+        MockRoute {
+            name: path.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            status: 200,
+            headers: HashMap::new(),
+            body: String::new(),
+            latency_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_route_path() {
+        assert_eq!(route_path("{{base_url}}/users/:id?expand=1"), "/users/:id");
+        assert_eq!(route_path("https://api.example.com/v1/items/"), "/v1/items");
+        assert_eq!(route_path("api.example.com/health"), "/health");
+        assert_eq!(route_path("https://api.example.com"), "/");
+        assert_eq!(route_path("/orders/{orderId}"), "/orders/{orderId}");
+    }
+
+    #[test]
+    fn test_match_route_prefers_literal_segments() {
+        let routes = vec![route("GET", "/users/:id"), route("GET", "/users/me"), route("POST", "/users")];
+
+        let (matched, params) = match_route(&routes, "GET", "/users/42").unwrap();
+        assert_eq!(matched.path, "/users/:id");
+        assert_eq!(params["id"], "42");
+
+        let (matched, _) = match_route(&routes, "get", "/users/me/").unwrap();
+        assert_eq!(matched.path, "/users/me");
+
+        assert!(match_route(&routes, "DELETE", "/users").is_none());
+        assert!(match_route(&routes, "GET", "/users/1/posts").is_none());
+    }
+
+    #[test]
+    fn test_render_request_placeholders() {
+        let mut headers = HashMap::new();
+        headers.insert("x-user".to_string(), "ada".to_string());
+        let request = IncomingRequest {
+            method: "GET".to_string(),
+            path: "/users/42".to_string(),
+            query: Some("page=2".to_string()),
+            headers,
+            ..Default::default()
+        };
+        let mut params = HashMap::new();
+        params.insert("id".to_string(), "42".to_string());
+
+        let rendered = render_request_placeholders(
+            r#"{"id": "{{request.params.id}}", "page": "{{request.query.page}}", "user": "{{request.headers.X-User}}", "env": "{{base_url}}"}"#,
+            &request,
+            &params,
+        );
+        assert_eq!(rendered, r#"{"id": "42", "page": "2", "user": "ada", "env": "{{base_url}}"}"#);
+    }
+
+    #[test]
+    fn test_routes_from_openapi() {
+        let spec = r#"
+openapi: 3.0.0
+servers:
+  - url: https://api.example.com/v1
+paths:
+  /users/{id}:
+    get:
+      operationId: getUser
+      responses:
+        '404': { description: Not found }
+        '200':
+          description: OK
+          content:
+            application/json:
+              schema:
+                type: object
+                properties:
+                  id: { type: integer, example: 42 }
+                  email: { type: string, format: email }
+    delete:
+      responses:
+        '204': { description: Deleted }
+"#;
+        let routes = routes_from_openapi(spec, true).unwrap();
+        let get = routes.iter().find(|r| r.method == "GET").unwrap();
+        assert_eq!(get.name, "getUser");
+        assert_eq!(get.path, "/v1/users/{id}");
+        assert_eq!(get.status, 200);
+        assert_eq!(get.headers["Content-Type"], "application/json");
+        let body: serde_json::Value = serde_json::from_str(&get.body).unwrap();
+        assert_eq!(body, serde_json::json!({ "id": 42, "email": "user@example.com" }));
+
+        let delete = routes.iter().find(|r| r.method == "DELETE").unwrap();
+        assert_eq!(delete.status, 204);
+        assert!(delete.body.is_empty());
+
+        let (matched, params) = match_route(&routes, "GET", "/v1/users/9").unwrap();
+        assert_eq!(matched.name, "getUser");
+        assert_eq!(params["id"], "9");
+
+        let faked = routes_from_openapi(spec, false).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&faked[0].body).unwrap();
+        assert_eq!(body["id"], serde_json::json!(1));
+    }
+}
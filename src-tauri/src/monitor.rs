@@ -0,0 +1,313 @@
+// 🎓 TEACHING: Monitors
+// A monitor sends a saved request, or runs a whole collection, on a cron schedule for as
+// long as the app is open. Every check goes into the monitor's result history, and a failed
+// check can raise a system notification and POST a JSON summary to a webhook (Slack, a
+// pager, a CI job...).
+//
+// Schedules use the five usual cron fields (minute, hour, day of month, month, day of week)
+// in local time. The scheduler wakes up every SCHEDULER_TICK and runs each monitor that came
+// due since it last ran; checks missed while the app was closed are caught up once, not once
+// per missed slot.
+//
+// A monitor can carry a retry policy (see runner.rs), so one dropped connection doesn't
+// raise an alert.
+
+use crate::database::{Database, Monitor, MonitorResult};
+use crate::logging;
+use crate::runner::{self, RetryPolicy, RunOptions, RunRequestResult};
+use crate::session::SessionCache;
+use crate::DatabaseState;
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use cron::Schedule;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use uuid::Uuid;
+
+const SCHEDULER_TICK: Duration = Duration::from_secs(30);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The cron crate wants a seconds field first; monitors run on the minute
+pub fn parse_schedule(expression: &str) -> Result<Schedule> {
+    if expression.split_whitespace().count() != 5 {
+        return Err(anyhow::anyhow!(
+            "A schedule needs five fields (minute hour day-of-month month day-of-week): {}",
+            expression
+        ));
+    }
+    Schedule::from_str(&format!("0 {}", expression))
+        .map_err(|e| anyhow::anyhow!("Invalid schedule '{}': {}", expression, e))
+}
+
+// Checked before a monitor is saved
+pub fn validate(target_type: &str, cron: &str, webhook_url: Option<&str>, retry: Option<&str>) -> Result<()> {
+    if !matches!(target_type, "request" | "collection") {
+        return Err(anyhow::anyhow!("Unsupported monitor target: {}", target_type));
+    }
+    if let Some(url) = webhook_url.filter(|url| !url.is_empty()) {
+        url::Url::parse(url).map_err(|e| anyhow::anyhow!("Invalid webhook URL: {}", e))?;
+    }
+    parse_retry(retry)?;
+    parse_schedule(cron).map(|_| ())
+}
+
+fn parse_retry(retry: Option<&str>) -> Result<Option<RetryPolicy>> {
+    match retry.map(str::trim).filter(|retry| !retry.is_empty()) {
+        Some(retry) => serde_json::from_str(retry)
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("Invalid retry policy: {}", e)),
+        None => Ok(None),
+    }
+}
+
+// The first scheduled time after the monitor last ran (or was created)
+pub fn next_run(monitor: &Monitor) -> Option<DateTime<Utc>> {
+    let schedule = parse_schedule(&monitor.cron).ok()?;
+    let since = monitor.last_run_at.unwrap_or(monitor.created_at).with_timezone(&Local);
+    schedule.after(&since).next().map(|at| at.with_timezone(&Utc))
+}
+
+pub fn is_due(monitor: &Monitor, now: DateTime<Utc>) -> bool {
+    monitor.enabled && next_run(monitor).is_some_and(|at| at <= now)
+}
+
+// Start the background scheduler; called once from the app's setup
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticks = tokio::time::interval(SCHEDULER_TICK);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = run_due_monitors(&app).await {
+                logging::warn(format!("Monitor scheduler: {}", e));
+            }
+        }
+    });
+}
+
+// Due monitors run one after another, so a slow collection delays the next check rather
+// than piling up sends against the same servers
+async fn run_due_monitors(app: &AppHandle) -> Result<()> {
+    let db = {
+        let db_state = app.state::<DatabaseState>();
+        let db_guard = db_state.lock().unwrap();
+        match db_guard.as_ref() {
+            Some(db) => db.clone(),
+            None => return Ok(()), // Not opened yet
+        }
+    };
+    let session_cache = app.state::<SessionCache>();
+
+    for monitor in db.get_monitors().await? {
+        let now = Utc::now();
+        if !is_due(&monitor, now) {
+            continue;
+        }
+        // Recorded before the check, so a failing save can't make it run every tick
+        db.set_monitor_last_run(&monitor.id, now).await?;
+        logging::info(format!("Running monitor: {}", monitor.name));
+        let result = run_monitor(&db, &session_cache, &monitor).await?;
+        let _ = app.emit("monitor-result", &result);
+        if !result.passed {
+            notify_failure(app, &monitor, &result).await;
+        }
+    }
+    Ok(())
+}
+
+// Run one check and save it to the monitor's history. A target that can't be run is a
+// failed check, not an error; only failing to save it is.
+pub async fn run_monitor(db: &Database, session_cache: &SessionCache, monitor: &Monitor) -> Result<MonitorResult> {
+    let started_at = Utc::now();
+    let outcome = match parse_retry(monitor.retry.as_deref()) {
+        Ok(retry) => {
+            let options = RunOptions {
+                environment_id: monitor.environment_id.clone(),
+                retry,
+                ..Default::default()
+            };
+            run_target(db, session_cache, monitor, options).await
+        }
+        Err(e) => Err(e.to_string()),
+    };
+
+    let (results, error) = match outcome {
+        Ok(results) => (results, None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    let failed_requests = results.iter().filter(|result| !result.passed).count() as u32;
+    let result = MonitorResult {
+        id: Uuid::new_v4().to_string(),
+        monitor_id: monitor.id.clone(),
+        passed: error.is_none() && failed_requests == 0,
+        total_requests: results.len() as u32,
+        failed_requests,
+        error,
+        results: serde_json::to_string(&results)?,
+        started_at,
+        finished_at: Utc::now(),
+    };
+    db.save_monitor_result(&result).await?;
+    Ok(result)
+}
+
+async fn run_target(
+    db: &Database,
+    session_cache: &SessionCache,
+    monitor: &Monitor,
+    options: RunOptions,
+) -> Result<Vec<RunRequestResult>, String> {
+    match monitor.target_type.as_str() {
+        "request" => match db.get_request_by_id(&monitor.target_id).await {
+            Ok(Some(request)) => Ok(vec![
+                runner::run_request(db, session_cache, &request, &options, 0, &HashMap::new(), None).await,
+            ]),
+            Ok(None) => Err(format!("Request not found: {}", monitor.target_id)),
+            Err(e) => Err(e.to_string()),
+        },
+        "collection" => {
+            let progress: runner::ProgressSink = Arc::new(|_| {});
+            let run = runner::run_collection(db, session_cache, &monitor.target_id, options, progress, None)
+                .await
+                .map_err(|e| e.message)?;
+            serde_json::from_str(&run.results).map_err(|e| e.to_string())
+        }
+        other => Err(format!("Unsupported monitor target: {}", other)),
+    }
+}
+
+// One line for the notification and the webhook's `text`
+pub fn failure_summary(monitor: &Monitor, result: &MonitorResult) -> String {
+    match &result.error {
+        Some(error) => format!("{} could not run: {}", monitor.name, error),
+        None => format!(
+            "{}: {} of {} request(s) failed",
+            monitor.name, result.failed_requests, result.total_requests
+        ),
+    }
+}
+
+// Best effort: a notification or webhook that doesn't go through is only logged
+async fn notify_failure(app: &AppHandle, monitor: &Monitor, result: &MonitorResult) {
+    let summary = failure_summary(monitor, result);
+    if monitor.notify {
+        if let Err(e) = app
+            .notification()
+            .builder()
+            .title("Monitor failed")
+            .body(summary.clone())
+            .show()
+        {
+            logging::warn(format!("Failed to show monitor notification: {}", e));
+        }
+    }
+
+    if let Some(url) = monitor.webhook_url.as_deref().filter(|url| !url.is_empty()) {
+        // `text` makes the payload usable as-is by Slack-style incoming webhooks
+        let payload = serde_json::json!({
+            "text": summary,
+            "monitor": {
+                "id": monitor.id,
+                "name": monitor.name,
+                "target_type": monitor.target_type,
+                "target_id": monitor.target_id,
+            },
+            "result": result,
+        });
+        let sent = reqwest::Client::new()
+            .post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = sent {
+            logging::warn(format!("Failed to call monitor webhook: {}", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn monitor(cron: &str) -> Monitor {
+        let created_at = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 30).unwrap();
+        Monitor {
+            id: "m".to_string(),
+            name: "Health".to_string(),
+            target_type: "request".to_string(),
+            target_id: "r".to_string(),
+            cron: cron.to_string(),
+            environment_id: None,
+            enabled: true,
+            notify: true,
+            webhook_url: None,
+            retry: None,
+            last_run_at: None,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    #[test]
+    fn test_parse_schedule() {
+        assert!(parse_schedule("*/5 * * * *").is_ok());
+        assert!(parse_schedule("0 9 * * MON-FRI").is_ok());
+        assert!(parse_schedule("0 */5 * * * *").is_err()); // Seconds aren't supported
+        assert!(parse_schedule("61 * * * *").is_err());
+        assert!(parse_schedule("every minute").is_err());
+    }
+
+    #[test]
+    fn test_is_due() {
+        let mut check = monitor("* * * * *");
+        let at = |h, m, s| Utc.with_ymd_and_hms(2024, 3, 1, h, m, s).unwrap();
+        assert_eq!(next_run(&check), Some(at(10, 1, 0)));
+        assert!(!is_due(&check, at(10, 0, 50)));
+        assert!(is_due(&check, at(10, 1, 5)));
+
+        check.last_run_at = Some(at(10, 1, 5));
+        assert!(!is_due(&check, at(10, 1, 40)));
+        assert!(is_due(&check, at(10, 2, 0)));
+
+        check.enabled = false;
+        assert!(!is_due(&check, at(10, 2, 0)));
+        check.enabled = true;
+        check.cron = "not a schedule".to_string();
+        assert!(!is_due(&check, at(10, 2, 0)));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("request", "0 * * * *", None, None).is_ok());
+        assert!(validate("collection", "0 * * * *", Some("https://hooks.example.com/T1"), Some(r#"{"max_attempts": 2}"#)).is_ok());
+        assert!(validate("request", "0 * * * *", Some("hooks.example.com"), None).is_err());
+        assert!(validate("folder", "0 * * * *", None, None).is_err());
+        assert!(validate("request", "hourly", None, None).is_err());
+        assert!(validate("request", "0 * * * *", None, Some(r#"{"max_attempts": "many"}"#)).is_err());
+    }
+
+    #[test]
+    fn test_failure_summary() {
+        let check = monitor("* * * * *");
+        let mut result = MonitorResult {
+            id: "x".to_string(),
+            monitor_id: check.id.clone(),
+            passed: false,
+            total_requests: 3,
+            failed_requests: 1,
+            error: None,
+            results: "[]".to_string(),
+            started_at: check.created_at,
+            finished_at: check.created_at,
+        };
+        assert_eq!(failure_summary(&check, &result), "Health: 1 of 3 request(s) failed");
+        result.error = Some("Request not found: r".to_string());
+        assert_eq!(failure_summary(&check, &result), "Health could not run: Request not found: r");
+    }
+}
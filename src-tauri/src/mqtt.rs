@@ -0,0 +1,279 @@
+// 🎓 TEACHING: MQTT client
+// IoT APIs often speak MQTT instead of HTTP: clients keep one connection to a broker,
+// publish messages to topics, and subscribe to topic filters like `sensors/+/temp`.
+// Unlike a request/response call, messages arrive whenever the broker sends them,
+// so each connection runs a background task that forwards them as events.
+
+use anyhow::Result;
+use rumqttc::v5::mqttbytes::v5::Packet as PacketV5;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MqttConnectConfig {
+    pub broker_url: String, // mqtt://host:1883, mqtts://host:8883, or host:port
+    pub client_id: Option<String>, // Random if empty
+    pub protocol_version: Option<String>, // "3.1.1" (default) or "5"
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub keep_alive_secs: Option<u64>,
+    pub clean_session: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MqttEvent {
+    Message {
+        connection_id: String,
+        topic: String,
+        payload: String, // UTF-8, lossy for binary payloads
+        qos: u8,
+        retain: bool,
+    },
+    Disconnected {
+        connection_id: String,
+        error: Option<String>,
+    },
+}
+
+pub type EventSink = Arc<dyn Fn(MqttEvent) + Send + Sync>;
+
+#[derive(Clone)]
+enum MqttClient {
+    V4(rumqttc::AsyncClient),
+    V5(rumqttc::v5::AsyncClient),
+}
+
+// 🎓 TEACHING: Open connections, keyed by an id the frontend holds on to
+#[derive(Default)]
+pub struct MqttManager {
+    connections: Mutex<HashMap<String, MqttClient>>,
+}
+
+// Returns (host, port, use_tls)
+pub fn parse_broker_url(url: &str) -> Result<(String, u16, bool)> {
+    let url = url.trim();
+    let (tls, rest) = if let Some(rest) = url.strip_prefix("mqtts://").or_else(|| url.strip_prefix("ssl://")) {
+        (true, rest)
+    } else if let Some(rest) = url.strip_prefix("mqtt://").or_else(|| url.strip_prefix("tcp://")) {
+        (false, rest)
+    } else if url.contains("://") {
+        return Err(anyhow::anyhow!("Unsupported broker URL scheme: {}", url));
+    } else {
+        (false, url)
+    };
+
+    let rest = rest.trim_end_matches('/');
+    let (host, port) = match rest.strip_prefix('[') {
+        // IPv6 literal, e.g. [::1]:1883
+        Some(bracketed) => {
+            let (host, after) = bracketed
+                .split_once(']')
+                .ok_or_else(|| anyhow::anyhow!("Invalid broker URL: {}", url))?;
+            (host, after.strip_prefix(':'))
+        }
+        None => match rest.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (rest, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| anyhow::anyhow!("Invalid broker port: {}", port))?,
+        None if tls => 8883,
+        None => 1883,
+    };
+    if host.is_empty() {
+        return Err(anyhow::anyhow!("Broker URL has no host"));
+    }
+    Ok((host.to_string(), port, tls))
+}
+
+fn qos_v4(qos: u8) -> Result<rumqttc::QoS> {
+    rumqttc::qos(qos).map_err(|_| anyhow::anyhow!("Invalid QoS {} (expected 0, 1 or 2)", qos))
+}
+
+fn qos_v5(qos: u8) -> Result<rumqttc::v5::mqttbytes::QoS> {
+    rumqttc::v5::mqttbytes::qos(qos).ok_or_else(|| anyhow::anyhow!("Invalid QoS {} (expected 0, 1 or 2)", qos))
+}
+
+impl MqttManager {
+    // 🎓 TEACHING: Connect, wait for the broker's CONNACK (so bad credentials fail here),
+    // then hand the event loop to a background task.
+    pub async fn connect(&self, config: &MqttConnectConfig, sink: EventSink) -> Result<String> {
+        let (host, port, tls) = parse_broker_url(&config.broker_url)?;
+        let client_id = config
+            .client_id
+            .clone()
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| format!("openrequest-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]));
+        let keep_alive = Duration::from_secs(config.keep_alive_secs.unwrap_or(30));
+        let connection_id = uuid::Uuid::new_v4().to_string();
+
+        let client = match config.protocol_version.as_deref().unwrap_or("3.1.1") {
+            "3.1.1" | "4" => {
+                let mut options = rumqttc::MqttOptions::new(client_id, host, port);
+                options.set_keep_alive(keep_alive);
+                options.set_clean_session(config.clean_session.unwrap_or(true));
+                if let Some(username) = config.username.as_deref().filter(|u| !u.is_empty()) {
+                    options.set_credentials(username, config.password.clone().unwrap_or_default());
+                }
+                if tls {
+                    options.set_transport(rumqttc::Transport::tls_with_default_config());
+                }
+
+                let (client, mut eventloop) = rumqttc::AsyncClient::new(options, 64);
+                tokio::time::timeout(CONNECT_TIMEOUT, async {
+                    loop {
+                        match eventloop.poll().await {
+                            Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => return Ok(()),
+                            Ok(_) => {}
+                            Err(e) => return Err(anyhow::anyhow!("MQTT connection failed: {}", e)),
+                        }
+                    }
+                })
+                .await
+                .map_err(|_| anyhow::anyhow!("Timed out connecting to {}", config.broker_url))??;
+
+                let id = connection_id.clone();
+                tokio::spawn(async move {
+                    let error = loop {
+                        match eventloop.poll().await {
+                            Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(publish))) => sink(MqttEvent::Message {
+                                connection_id: id.clone(),
+                                topic: publish.topic,
+                                payload: String::from_utf8_lossy(&publish.payload).to_string(),
+                                qos: publish.qos as u8,
+                                retain: publish.retain,
+                            }),
+                            Ok(rumqttc::Event::Outgoing(rumqttc::Outgoing::Disconnect)) => break None,
+                            Ok(_) => {}
+                            Err(e) => break Some(e.to_string()),
+                        }
+                    };
+                    sink(MqttEvent::Disconnected { connection_id: id, error });
+                });
+                MqttClient::V4(client)
+            }
+            "5" | "5.0" => {
+                let mut options = rumqttc::v5::MqttOptions::new(client_id, host, port);
+                options.set_keep_alive(keep_alive);
+                options.set_clean_start(config.clean_session.unwrap_or(true));
+                if let Some(username) = config.username.as_deref().filter(|u| !u.is_empty()) {
+                    options.set_credentials(username, config.password.clone().unwrap_or_default());
+                }
+                if tls {
+                    options.set_transport(rumqttc::Transport::tls_with_default_config());
+                }
+
+                let (client, mut eventloop) = rumqttc::v5::AsyncClient::new(options, 64);
+                tokio::time::timeout(CONNECT_TIMEOUT, async {
+                    loop {
+                        match eventloop.poll().await {
+                            Ok(rumqttc::v5::Event::Incoming(PacketV5::ConnAck(_))) => return Ok(()),
+                            Ok(_) => {}
+                            Err(e) => return Err(anyhow::anyhow!("MQTT connection failed: {}", e)),
+                        }
+                    }
+                })
+                .await
+                .map_err(|_| anyhow::anyhow!("Timed out connecting to {}", config.broker_url))??;
+
+                let id = connection_id.clone();
+                tokio::spawn(async move {
+                    let error = loop {
+                        match eventloop.poll().await {
+                            Ok(rumqttc::v5::Event::Incoming(PacketV5::Publish(publish))) => sink(MqttEvent::Message {
+                                connection_id: id.clone(),
+                                topic: String::from_utf8_lossy(&publish.topic).to_string(),
+                                payload: String::from_utf8_lossy(&publish.payload).to_string(),
+                                qos: publish.qos as u8,
+                                retain: publish.retain,
+                            }),
+                            Ok(rumqttc::v5::Event::Outgoing(rumqttc::Outgoing::Disconnect)) => break None,
+                            Ok(_) => {}
+                            Err(e) => break Some(e.to_string()),
+                        }
+                    };
+                    sink(MqttEvent::Disconnected { connection_id: id, error });
+                });
+                MqttClient::V5(client)
+            }
+            other => return Err(anyhow::anyhow!("Unsupported MQTT protocol version: {}", other)),
+        };
+
+        self.connections.lock().unwrap().insert(connection_id.clone(), client);
+        Ok(connection_id)
+    }
+
+    fn client(&self, connection_id: &str) -> Result<MqttClient> {
+        self.connections
+            .lock()
+            .unwrap()
+            .get(connection_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("MQTT connection {} is not open", connection_id))
+    }
+
+    pub async fn publish(&self, connection_id: &str, topic: &str, payload: String, qos: u8, retain: bool) -> Result<()> {
+        match self.client(connection_id)? {
+            MqttClient::V4(client) => client.publish(topic, qos_v4(qos)?, retain, payload).await?,
+            MqttClient::V5(client) => client.publish(topic, qos_v5(qos)?, retain, payload).await?,
+        }
+        Ok(())
+    }
+
+    pub async fn subscribe(&self, connection_id: &str, topic: &str, qos: u8) -> Result<()> {
+        match self.client(connection_id)? {
+            MqttClient::V4(client) => client.subscribe(topic, qos_v4(qos)?).await?,
+            MqttClient::V5(client) => client.subscribe(topic, qos_v5(qos)?).await?,
+        }
+        Ok(())
+    }
+
+    pub async fn unsubscribe(&self, connection_id: &str, topic: &str) -> Result<()> {
+        match self.client(connection_id)? {
+            MqttClient::V4(client) => client.unsubscribe(topic).await?,
+            MqttClient::V5(client) => client.unsubscribe(topic).await?,
+        }
+        Ok(())
+    }
+
+    pub async fn disconnect(&self, connection_id: &str) -> Result<()> {
+        let client = self.client(connection_id)?;
+        self.connections.lock().unwrap().remove(connection_id);
+        // The event loop may already be gone (e.g. the broker dropped us), which is fine here
+        let _ = match client {
+            MqttClient::V4(client) => client.disconnect().await.map_err(|e| e.to_string()),
+            MqttClient::V5(client) => client.disconnect().await.map_err(|e| e.to_string()),
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_broker_url() {
+        assert_eq!(parse_broker_url("mqtt://broker.local").unwrap(), ("broker.local".to_string(), 1883, false));
+        assert_eq!(parse_broker_url("mqtts://broker.local").unwrap(), ("broker.local".to_string(), 8883, true));
+        assert_eq!(parse_broker_url("10.0.0.5:1884").unwrap(), ("10.0.0.5".to_string(), 1884, false));
+        assert_eq!(parse_broker_url("ssl://[::1]:8884/").unwrap(), ("::1".to_string(), 8884, true));
+        assert_eq!(parse_broker_url("[::1]").unwrap(), ("::1".to_string(), 1883, false));
+        assert!(parse_broker_url("ws://broker.local").is_err());
+        assert!(parse_broker_url("mqtt://broker.local:abc").is_err());
+    }
+
+    #[test]
+    fn test_qos_validation() {
+        assert!(qos_v4(1).is_ok());
+        assert!(qos_v5(2).is_ok());
+        assert!(qos_v4(3).is_err());
+        assert!(qos_v5(3).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+// 🎓 TEACHING: OpenID Connect Discovery
+// OIDC providers publish their endpoints at `{issuer}/.well-known/openid-configuration`,
+// so users only need the issuer URL instead of copying auth and token URLs by hand.
+
+use crate::oauth::OAuthConfig;
+use anyhow::Result;
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+
+// 🎓 TEACHING: The subset of provider metadata we care about
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OidcProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+    pub userinfo_endpoint: Option<String>,
+    pub end_session_endpoint: Option<String>,
+    #[serde(default)]
+    pub scopes_supported: Vec<String>,
+    #[serde(default)]
+    pub response_types_supported: Vec<String>,
+    #[serde(default)]
+    pub id_token_signing_alg_values_supported: Vec<String>,
+}
+
+// What the discovery command hands back to the frontend
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OidcDiscoveryResult {
+    pub metadata: OidcProviderMetadata,
+    pub config: OAuthConfig, // The caller's config with endpoints (and default scopes) filled in
+}
+
+// A verified ID token, split into its decoded parts
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IdTokenInfo {
+    pub header: serde_json::Value,
+    pub claims: serde_json::Value,
+}
+
+impl OidcProviderMetadata {
+    // 🎓 TEACHING: Fill in an OAuthConfig from the discovered endpoints.
+    // If the user hasn't chosen scopes yet, request "openid" plus the common profile scopes.
+    pub fn apply_to_config(&self, config: OAuthConfig) -> OAuthConfig {
+        let scope = config.scope.clone().or_else(|| {
+            let mut scopes = vec!["openid"];
+            for extra in ["profile", "email"] {
+                if self.scopes_supported.iter().any(|s| s == extra) {
+                    scopes.push(extra);
+                }
+            }
+            Some(scopes.join(" "))
+        });
+
+        OAuthConfig {
+            authorization_url: self.authorization_endpoint.clone(),
+            token_url: self.token_endpoint.clone(),
+            scope,
+            ..config
+        }
+    }
+}
+
+// 🎓 TEACHING: Fetch and sanity-check the provider's discovery document
+pub async fn discover(issuer_url: &str) -> Result<OidcProviderMetadata> {
+    let issuer = issuer_url.trim_end_matches('/');
+    let discovery_url = format!("{}/.well-known/openid-configuration", issuer);
+
+    let response = reqwest::get(&discovery_url).await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Discovery request to {} failed with status {}",
+            discovery_url,
+            response.status()
+        ));
+    }
+
+    let metadata: OidcProviderMetadata = response.json().await?;
+
+    // The spec requires the advertised issuer to match the one we asked for
+    if metadata.issuer.trim_end_matches('/') != issuer {
+        return Err(anyhow::anyhow!(
+            "Issuer mismatch: expected {}, provider reported {}",
+            issuer,
+            metadata.issuer
+        ));
+    }
+
+    Ok(metadata)
+}
+
+// 🎓 TEACHING: Verify an ID token's signature against the provider's JWKS,
+// then check issuer, audience (our client ID), expiry and the optional nonce.
+pub async fn validate_id_token(
+    metadata: &OidcProviderMetadata,
+    client_id: &str,
+    id_token: &str,
+    nonce: Option<&str>,
+) -> Result<IdTokenInfo> {
+    let jwks: JwkSet = reqwest::get(&metadata.jwks_uri).await?.json().await?;
+    verify_id_token(&jwks, &metadata.issuer, client_id, id_token, nonce)
+}
+
+fn verify_id_token(
+    jwks: &JwkSet,
+    issuer: &str,
+    client_id: &str,
+    id_token: &str,
+    nonce: Option<&str>,
+) -> Result<IdTokenInfo> {
+    let header = decode_header(id_token)?;
+
+    // Symmetric algorithms would let anyone holding the client secret forge tokens
+    if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+        return Err(anyhow::anyhow!("Unsupported ID token algorithm: {:?}", header.alg));
+    }
+
+    let jwk = match &header.kid {
+        Some(kid) => jwks
+            .find(kid)
+            .ok_or_else(|| anyhow::anyhow!("No signing key found for kid {}", kid))?,
+        None => jwks
+            .keys
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Provider JWKS contains no keys"))?,
+    };
+    let key = DecodingKey::from_jwk(jwk)?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[client_id]);
+    validation.set_issuer(&[issuer]);
+
+    let token = decode::<serde_json::Value>(id_token, &key, &validation)?;
+
+    if let Some(expected_nonce) = nonce {
+        let actual_nonce = token.claims.get("nonce").and_then(|n| n.as_str());
+        if actual_nonce != Some(expected_nonce) {
+            return Err(anyhow::anyhow!("ID token nonce does not match"));
+        }
+    }
+
+    Ok(IdTokenInfo {
+        header: serde_json::to_value(&token.header)?,
+        claims: token.claims,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> OidcProviderMetadata {
+        serde_json::from_str(
+            r#"{
+                "issuer": "https://id.example.com",
+                "authorization_endpoint": "https://id.example.com/authorize",
+                "token_endpoint": "https://id.example.com/token",
+                "jwks_uri": "https://id.example.com/jwks",
+                "scopes_supported": ["openid", "email", "offline_access"]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn base_config() -> OAuthConfig {
+        OAuthConfig {
+            client_id: "client".to_string(),
+            client_secret: None,
+            authorization_url: String::new(),
+            token_url: String::new(),
+            redirect_uri: "http://localhost:8080/callback".to_string(),
+            scope: None,
+            use_pkce: true,
+        }
+    }
+
+    #[test]
+    fn test_apply_to_config_fills_endpoints_and_scopes() {
+        let config = metadata().apply_to_config(base_config());
+        assert_eq!(config.authorization_url, "https://id.example.com/authorize");
+        assert_eq!(config.token_url, "https://id.example.com/token");
+        assert_eq!(config.scope.as_deref(), Some("openid email"));
+        assert_eq!(config.client_id, "client");
+    }
+
+    #[test]
+    fn test_apply_to_config_keeps_user_scopes() {
+        let mut config = base_config();
+        config.scope = Some("openid custom".to_string());
+        let config = metadata().apply_to_config(config);
+        assert_eq!(config.scope.as_deref(), Some("openid custom"));
+    }
+
+    #[test]
+    fn test_verify_rejects_hmac_tokens() {
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({ "iss": "https://id.example.com", "aud": "client", "exp": 9999999999u64 }),
+            &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        let jwks = JwkSet { keys: Vec::new() };
+        let err = verify_id_token(&jwks, "https://id.example.com", "client", &token, None).unwrap_err();
+        assert!(err.to_string().contains("Unsupported ID token algorithm"));
+    }
+}
@@ -0,0 +1,499 @@
+// 🎓 TEACHING: OpenAPI / Swagger documents
+// A spec describes every endpoint of an API: its path, parameters, request body and
+// possible responses, with JSON Schemas for the payloads. This module reads OpenAPI 3.x
+// and Swagger 2.0 (JSON or YAML) into one flat list of operations and can invent an
+// example value for any schema, which is what the mock server and importer build on.
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+const METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+// Deep enough for real specs, shallow enough to stop self-referencing schemas
+const MAX_SCHEMA_DEPTH: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub method: String, // Upper case
+    pub path: String,   // As written in the spec, e.g. /users/{id}
+    pub operation_id: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>, // Markdown (CommonMark) per the spec
+    pub parameters: Vec<Value>, // Path-level and operation-level, with $refs resolved
+    pub request_body: Option<Body>,
+    pub responses: Vec<(String, Value)>, // Status code (or "default") and response object
+    pub security: Option<Value>,         // Operation-level requirements, if overridden
+}
+
+#[derive(Debug, Clone)]
+pub struct Body {
+    pub content_type: String,
+    pub schema: Option<Value>,
+    pub example: Option<Value>,
+}
+
+// JSON is valid YAML, but parsing JSON directly gives better error messages
+pub fn parse_spec(text: &str) -> Result<Value> {
+    let spec: Value = if text.trim_start().starts_with('{') {
+        serde_json::from_str(text).map_err(|e| anyhow::anyhow!("Invalid OpenAPI JSON: {}", e))?
+    } else {
+        serde_yaml::from_str(text).map_err(|e| anyhow::anyhow!("Invalid OpenAPI YAML: {}", e))?
+    };
+
+    if spec.get("openapi").is_none() && spec.get("swagger").is_none() {
+        return Err(anyhow::anyhow!("Not an OpenAPI 3.x or Swagger 2.0 document"));
+    }
+    if spec.get("paths").and_then(Value::as_object).is_none() {
+        return Err(anyhow::anyhow!("The spec has no paths"));
+    }
+    Ok(spec)
+}
+
+pub fn is_swagger2(spec: &Value) -> bool {
+    spec.get("swagger").is_some()
+}
+
+pub fn title(spec: &Value) -> Option<String> {
+    spec.pointer("/info/title").and_then(Value::as_str).map(str::to_string)
+}
+
+// Named security schemes (`components.securitySchemes`, or `securityDefinitions` in Swagger 2)
+pub fn security_schemes(spec: &Value) -> Map<String, Value> {
+    spec.pointer("/components/securitySchemes")
+        .or_else(|| spec.get("securityDefinitions"))
+        .and_then(Value::as_object)
+        .map(|schemes| {
+            schemes
+                .iter()
+                .map(|(name, scheme)| (name.clone(), resolve(spec, scheme).clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// 🎓 TEACHING: `security` is a list of alternatives, each naming one or more schemes.
+// Returns the first scheme of the first alternative; `Some(None)` means "explicitly no auth".
+pub fn first_security_scheme(security: Option<&Value>) -> Option<Option<String>> {
+    let alternatives = security?.as_array()?;
+    Some(
+        alternatives
+            .iter()
+            .filter_map(Value::as_object)
+            .find_map(|requirement| requirement.keys().next().cloned()),
+    )
+}
+
+// Follow local `$ref`s like `#/components/schemas/User` (external files are not supported)
+pub fn resolve<'a>(spec: &'a Value, value: &'a Value) -> &'a Value {
+    let mut current = value;
+    for _ in 0..MAX_SCHEMA_DEPTH {
+        let Some(reference) = current.get("$ref").and_then(Value::as_str) else {
+            break;
+        };
+        match reference.strip_prefix('#').and_then(|pointer| spec.pointer(pointer)) {
+            Some(target) => current = target,
+            None => break,
+        }
+    }
+    current
+}
+
+// Server URLs with their `{variables}` filled with defaults; Swagger 2 has a single host
+pub fn server_urls(spec: &Value) -> Vec<String> {
+    if is_swagger2(spec) {
+        let Some(host) = spec.get("host").and_then(Value::as_str) else {
+            return Vec::new();
+        };
+        let scheme = spec
+            .get("schemes")
+            .and_then(Value::as_array)
+            .and_then(|schemes| schemes.first())
+            .and_then(Value::as_str)
+            .unwrap_or("https");
+        let base_path = spec.get("basePath").and_then(Value::as_str).unwrap_or("");
+        return vec![format!("{}://{}{}", scheme, host, base_path.trim_end_matches('/'))];
+    }
+
+    spec.get("servers")
+        .and_then(Value::as_array)
+        .map(|servers| {
+            servers
+                .iter()
+                .filter_map(|server| {
+                    let mut url = server.get("url")?.as_str()?.to_string();
+                    if let Some(variables) = server.get("variables").and_then(Value::as_object) {
+                        for (name, variable) in variables {
+                            let default = variable.get("default").and_then(Value::as_str).unwrap_or("");
+                            url = url.replace(&format!("{{{}}}", name), default);
+                        }
+                    }
+                    Some(url.trim_end_matches('/').to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// The path prefix every operation lives under, e.g. /v1 for https://api.example.com/v1
+pub fn base_path(spec: &Value) -> String {
+    let Some(url) = server_urls(spec).into_iter().next() else {
+        return String::new();
+    };
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|i| &rest[i..]).unwrap_or(""),
+        None if url.starts_with('/') => url.as_str(),
+        None => "",
+    };
+    path.trim_end_matches('/').to_string()
+}
+
+pub fn operations(spec: &Value) -> Vec<Operation> {
+    let swagger2 = is_swagger2(spec);
+    let mut operations = Vec::new();
+    let Some(paths) = spec.get("paths").and_then(Value::as_object) else {
+        return operations;
+    };
+
+    for (path, item) in paths {
+        let item = resolve(spec, item);
+        let shared_parameters = parameter_list(spec, item.get("parameters"));
+
+        for method in METHODS {
+            let Some(operation) = item.get(method) else { continue };
+
+            // Operation parameters override path-level ones with the same name and location
+            let mut parameters = parameter_list(spec, operation.get("parameters"));
+            for shared in &shared_parameters {
+                let overridden = parameters
+                    .iter()
+                    .any(|p| p.get("name") == shared.get("name") && p.get("in") == shared.get("in"));
+                if !overridden {
+                    parameters.push(shared.clone());
+                }
+            }
+
+            let request_body = if swagger2 {
+                swagger2_body(spec, operation, &parameters)
+            } else {
+                operation
+                    .get("requestBody")
+                    .and_then(|body| pick_content(spec, resolve(spec, body)))
+            };
+
+            let responses = operation
+                .get("responses")
+                .and_then(Value::as_object)
+                .map(|responses| {
+                    responses
+                        .iter()
+                        .map(|(status, response)| (status.clone(), resolve(spec, response).clone()))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            operations.push(Operation {
+                method: method.to_uppercase(),
+                path: path.clone(),
+                operation_id: operation.get("operationId").and_then(Value::as_str).map(str::to_string),
+                summary: operation.get("summary").and_then(Value::as_str).map(str::to_string),
+                description: operation.get("description").and_then(Value::as_str).map(str::to_string),
+                parameters: parameters.into_iter().filter(|p| p.get("in") != Some(&json!("body"))).collect(),
+                request_body,
+                responses,
+                security: operation.get("security").cloned(),
+            });
+        }
+    }
+    operations
+}
+
+fn parameter_list(spec: &Value, parameters: Option<&Value>) -> Vec<Value> {
+    parameters
+        .and_then(Value::as_array)
+        .map(|list| list.iter().map(|p| resolve(spec, p).clone()).collect())
+        .unwrap_or_default()
+}
+
+// Swagger 2 puts the body in an `in: body` parameter and the media type in `consumes`
+fn swagger2_body(spec: &Value, operation: &Value, parameters: &[Value]) -> Option<Body> {
+    let parameter = parameters.iter().find(|p| p.get("in") == Some(&json!("body")))?;
+    let consumes = operation.get("consumes").or_else(|| spec.get("consumes"));
+    let content_type = consumes
+        .and_then(Value::as_array)
+        .and_then(|types| types.first())
+        .and_then(Value::as_str)
+        .unwrap_or("application/json");
+    Some(Body {
+        content_type: content_type.to_string(),
+        schema: parameter.get("schema").cloned(),
+        example: parameter.get("x-example").cloned(),
+    })
+}
+
+// OpenAPI 3 bodies are keyed by media type; prefer JSON when there is a choice
+pub fn pick_content(spec: &Value, holder: &Value) -> Option<Body> {
+    let content = holder.get("content")?.as_object()?;
+    let (content_type, media) = content
+        .iter()
+        .find(|(content_type, _)| content_type.contains("json"))
+        .or_else(|| content.iter().next())?;
+
+    let example = media.get("example").cloned().or_else(|| {
+        media
+            .get("examples")
+            .and_then(Value::as_object)
+            .and_then(|examples| examples.values().next())
+            .map(|example| resolve(spec, example))
+            .and_then(|example| example.get("value").cloned())
+    });
+    Some(Body {
+        content_type: content_type.clone(),
+        schema: media.get("schema").cloned(),
+        example,
+    })
+}
+
+// The documented body of a response object, in either spec version
+pub fn response_body(spec: &Value, response: &Value) -> Option<Body> {
+    if is_swagger2(spec) {
+        let examples = response.get("examples").and_then(Value::as_object);
+        let (content_type, example) = match examples.and_then(|e| e.iter().next()) {
+            Some((content_type, example)) => (content_type.clone(), Some(example.clone())),
+            None => ("application/json".to_string(), None),
+        };
+        let schema = response.get("schema").cloned();
+        if schema.is_none() && example.is_none() {
+            return None;
+        }
+        return Some(Body {
+            content_type,
+            schema,
+            example,
+        });
+    }
+    pick_content(spec, response)
+}
+
+// 🎓 TEACHING: Build a plausible value from a schema.
+// Documented examples win (unless `use_examples` is off); otherwise values are made up
+// from the type and format, so `format: email` gives an address rather than "string".
+pub fn example_from_schema(spec: &Value, schema: &Value, use_examples: bool) -> Value {
+    example_at_depth(spec, schema, use_examples, 0, None)
+}
+
+fn example_at_depth(spec: &Value, schema: &Value, use_examples: bool, depth: usize, name: Option<&str>) -> Value {
+    if depth > MAX_SCHEMA_DEPTH {
+        return Value::Null;
+    }
+    let schema = resolve(spec, schema);
+
+    if use_examples {
+        if let Some(example) = schema.get("example") {
+            return example.clone();
+        }
+        if let Some(example) = schema.get("examples").and_then(Value::as_array).and_then(|e| e.first()) {
+            return example.clone();
+        }
+        if let Some(default) = schema.get("default") {
+            return default.clone();
+        }
+    }
+    if let Some(first) = schema.get("enum").and_then(Value::as_array).and_then(|e| e.first()) {
+        return first.clone();
+    }
+    if let Some(constant) = schema.get("const") {
+        return constant.clone();
+    }
+
+    if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = Map::new();
+        for part in parts {
+            match example_at_depth(spec, part, use_examples, depth + 1, name) {
+                Value::Object(fields) => merged.extend(fields),
+                other if parts.len() == 1 => return other,
+                _ => {}
+            }
+        }
+        return Value::Object(merged);
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(first) = schema.get(key).and_then(Value::as_array).and_then(|options| options.first()) {
+            return example_at_depth(spec, first, use_examples, depth + 1, name);
+        }
+    }
+
+    match schema_type(schema).as_deref() {
+        Some("object") => {
+            let mut object = Map::new();
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (key, property) in properties {
+                    object.insert(key.clone(), example_at_depth(spec, property, use_examples, depth + 1, Some(key)));
+                }
+            }
+            Value::Object(object)
+        }
+        Some("array") => match schema.get("items") {
+            Some(items) => json!([example_at_depth(spec, items, use_examples, depth + 1, name)]),
+            None => json!([]),
+        },
+        Some("integer") => json!(schema.get("minimum").and_then(Value::as_i64).unwrap_or(1)),
+        Some("number") => json!(schema.get("minimum").and_then(Value::as_f64).unwrap_or(1.5)),
+        Some("boolean") => json!(true),
+        Some("null") => Value::Null,
+        Some("string") => json!(fake_string(schema.get("format").and_then(Value::as_str), name)),
+        _ => Value::Null,
+    }
+}
+
+// `type` may be missing (implied by `properties`), or a list in OpenAPI 3.1
+pub fn schema_type(schema: &Value) -> Option<String> {
+    match schema.get("type") {
+        Some(Value::String(kind)) => Some(kind.clone()),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null")
+            .or(Some("null"))
+            .map(str::to_string),
+        _ if schema.get("properties").is_some() => Some("object".to_string()),
+        _ if schema.get("items").is_some() => Some("array".to_string()),
+        _ => None,
+    }
+}
+
+fn fake_string(format: Option<&str>, name: Option<&str>) -> String {
+    match format {
+        Some("date-time") => return "2024-01-15T09:30:00Z".to_string(),
+        Some("date") => return "2024-01-15".to_string(),
+        Some("time") => return "09:30:00".to_string(),
+        Some("email") => return "user@example.com".to_string(),
+        Some("uuid") => return "3fa85f64-5717-4562-b3fc-2c963f66afa6".to_string(),
+        Some("uri") | Some("url") => return "https://example.com".to_string(),
+        Some("hostname") => return "example.com".to_string(),
+        Some("ipv4") => return "192.0.2.1".to_string(),
+        Some("ipv6") => return "2001:db8::1".to_string(),
+        Some("byte") => return "ZXhhbXBsZQ==".to_string(),
+        Some("password") => return "********".to_string(),
+        _ => {}
+    }
+
+    // Property names often say more than the schema does
+    let name = name.unwrap_or("").to_lowercase();
+    if name.contains("email") {
+        "user@example.com".to_string()
+    } else if name == "id" || name.ends_with("_id") {
+        "abc123".to_string()
+    } else if name.contains("name") {
+        "Jane Doe".to_string()
+    } else if name.contains("url") {
+        "https://example.com".to_string()
+    } else {
+        "string".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PETSTORE_YAML: &str = r##"
+openapi: 3.0.3
+servers:
+  - url: https://{env}.example.com/v1
+    variables:
+      env:
+        default: api
+paths:
+  /pets/{petId}:
+    parameters:
+      - name: petId
+        in: path
+        required: true
+        schema: { type: string }
+    get:
+      operationId: getPet
+      responses:
+        200:
+          description: A pet
+          content:
+            application/json:
+              schema:
+                $ref: '#/components/schemas/Pet'
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        id: { type: integer, example: 7 }
+        name: { type: string }
+        owner_email: { type: string, format: email }
+        status: { type: string, enum: [available, sold] }
+        tags:
+          type: array
+          items: { type: string }
+"##;
+
+    #[test]
+    fn test_parse_yaml_and_operations() {
+        let spec = parse_spec(PETSTORE_YAML).unwrap();
+        assert_eq!(server_urls(&spec), vec!["https://api.example.com/v1"]);
+        assert_eq!(base_path(&spec), "/v1");
+
+        let ops = operations(&spec);
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].method, "GET");
+        assert_eq!(ops[0].path, "/pets/{petId}");
+        assert_eq!(ops[0].parameters.len(), 1);
+        assert_eq!(ops[0].responses[0].0, "200");
+    }
+
+    #[test]
+    fn test_example_from_schema() {
+        let spec = parse_spec(PETSTORE_YAML).unwrap();
+        let pet = json!({ "$ref": "#/components/schemas/Pet" });
+
+        assert_eq!(
+            example_from_schema(&spec, &pet, true),
+            json!({ "id": 7, "name": "Jane Doe", "owner_email": "user@example.com", "status": "available", "tags": ["string"] })
+        );
+        // Without examples the id is made up from the type
+        assert_eq!(example_from_schema(&spec, &pet, false)["id"], json!(1));
+    }
+
+    #[test]
+    fn test_swagger2_body_and_response() {
+        let spec = parse_spec(
+            r##"{
+                "swagger": "2.0",
+                "host": "api.example.com",
+                "basePath": "/v2",
+                "schemes": ["http"],
+                "paths": {
+                    "/users": {
+                        "post": {
+                            "parameters": [{ "name": "user", "in": "body", "schema": { "type": "object", "properties": { "name": { "type": "string" } } } }],
+                            "responses": { "201": { "description": "Created", "examples": { "application/json": { "id": "u1" } } } }
+                        }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(server_urls(&spec), vec!["http://api.example.com/v2"]);
+
+        let ops = operations(&spec);
+        assert!(ops[0].parameters.is_empty());
+        let body = ops[0].request_body.as_ref().unwrap();
+        assert_eq!(body.content_type, "application/json");
+        assert_eq!(example_from_schema(&spec, body.schema.as_ref().unwrap(), true), json!({ "name": "Jane Doe" }));
+
+        let response = response_body(&spec, &ops[0].responses[0].1).unwrap();
+        assert_eq!(response.example, Some(json!({ "id": "u1" })));
+    }
+
+    #[test]
+    fn test_rejects_non_specs() {
+        assert!(parse_spec("{\"info\": {}}").is_err());
+        assert!(parse_spec("openapi: 3.0.0\ninfo: {}").is_err());
+    }
+}
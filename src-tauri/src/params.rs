@@ -0,0 +1,310 @@
+// 🎓 TEACHING: Query and path parameter handling
+// Query params are an ordered list (so duplicate keys and insertion order survive) where
+// each entry can be toggled off without deleting it. The same JSON shape is stored
+// in the `requests.params` column.
+// Path params fill `:id` / `{id}` placeholders in the URL path.
+
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QueryParam {
+    pub key: String,
+    pub value: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool, // Disabled params are kept but not sent
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+// 🎓 TEACHING: Older clients send params as a flat `{ "key": "value" }` object.
+// We accept both shapes so nothing breaks while the frontend catches up.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QueryParamsInput {
+    List(Vec<QueryParam>),
+    Map(HashMap<String, String>),
+}
+
+impl From<QueryParamsInput> for Vec<QueryParam> {
+    fn from(input: QueryParamsInput) -> Self {
+        match input {
+            QueryParamsInput::List(params) => params,
+            QueryParamsInput::Map(map) => map
+                .into_iter()
+                .map(|(key, value)| QueryParam { key, value, enabled: true })
+                .collect(),
+        }
+    }
+}
+
+pub fn deserialize_query_params<'de, D>(deserializer: D) -> std::result::Result<Vec<QueryParam>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(QueryParamsInput::deserialize(deserializer)?.into())
+}
+
+// 🎓 TEACHING: Parse the `requests.params` column, which may hold either shape
+pub fn parse_query_params(json: &str) -> Result<Vec<QueryParam>> {
+    let input: QueryParamsInput = serde_json::from_str(json)?;
+    Ok(input.into())
+}
+
+// 🎓 TEACHING: Append enabled params to a URL.
+// "encode" (the default) percent-encodes keys and values on send,
+// "raw" assumes the user already encoded them and sends them untouched.
+pub fn append_query_params(url: &str, params: &[QueryParam], encoding: Option<&str>) -> Result<String> {
+    let encode = match encoding.unwrap_or("encode") {
+        "encode" => true,
+        "raw" => false,
+        other => return Err(anyhow::anyhow!("Unsupported param encoding: {}", other)),
+    };
+
+    let pairs: Vec<String> = params
+        .iter()
+        .filter(|p| p.enabled && !p.key.is_empty())
+        .map(|p| {
+            if encode {
+                format!("{}={}", encode_component(&p.key), encode_component(&p.value))
+            } else {
+                format!("{}={}", p.key, p.value)
+            }
+        })
+        .collect();
+
+    if pairs.is_empty() {
+        return Ok(url.to_string());
+    }
+
+    // Keep any fragment at the very end of the URL
+    let (base, fragment) = match url.split_once('#') {
+        Some((base, fragment)) => (base, Some(fragment)),
+        None => (url, None),
+    };
+
+    let separator = if !base.contains('?') {
+        "?"
+    } else if base.ends_with('?') || base.ends_with('&') {
+        ""
+    } else {
+        "&"
+    };
+
+    let mut result = format!("{}{}{}", base, separator, pairs.join("&"));
+    if let Some(fragment) = fragment {
+        result.push('#');
+        result.push_str(fragment);
+    }
+
+    Ok(result)
+}
+
+// 🎓 TEACHING: Enabled params as key/value pairs, for signers (like OAuth 1.0) that sign every
+// param. A list, not a map: `?tag=a&tag=b` sends both values, so both must be signed.
+pub fn enabled_param_pairs(params: &[QueryParam]) -> Vec<(String, String)> {
+    params
+        .iter()
+        .filter(|p| p.enabled)
+        .map(|p| (p.key.clone(), p.value.clone()))
+        .collect()
+}
+
+// 🎓 TEACHING: Importers get URLs with the query typed inline; move it into the params
+// list so it shows up (decoded) in the params editor instead of being sent twice.
+pub fn split_query(url: &str) -> (String, Vec<QueryParam>) {
+    let (without_fragment, fragment) = match url.find('#') {
+        Some(i) => (&url[..i], &url[i..]),
+        None => (url, ""),
+    };
+    let Some((base, query)) = without_fragment.split_once('?') else {
+        return (url.to_string(), Vec::new());
+    };
+    let params = url::form_urlencoded::parse(query.as_bytes())
+        .map(|(key, value)| QueryParam {
+            key: key.to_string(),
+            value: value.to_string(),
+            enabled: true,
+        })
+        .collect();
+    (format!("{}{}", base, fragment), params)
+}
+
+fn encode_component(input: &str) -> String {
+    url::form_urlencoded::byte_serialize(input.as_bytes()).collect()
+}
+
+// 🎓 TEACHING: Substitute path params into the URL path.
+// Only the path is touched, so `host:8080` or `?a=:b` are left alone.
+// A `:name` placeholder must fill a whole segment; `{name}` can appear anywhere in the path.
+// Placeholders without a value produce an error listing every missing name.
+pub fn substitute_path_params(url: &str, path_params: &HashMap<String, String>) -> Result<String> {
+    let path_start = match url.find("://") {
+        Some(scheme_end) => match url[scheme_end + 3..].find('/') {
+            Some(offset) => scheme_end + 3 + offset,
+            None => return Ok(url.to_string()),
+        },
+        None => url.find('/').unwrap_or(url.len()),
+    };
+    let path_end = url[path_start..]
+        .find(['?', '#'])
+        .map(|offset| path_start + offset)
+        .unwrap_or(url.len());
+
+    let mut missing = Vec::new();
+    let segments: Vec<String> = url[path_start..path_end]
+        .split('/')
+        .map(|segment| substitute_segment(segment, path_params, &mut missing))
+        .collect();
+
+    if !missing.is_empty() {
+        return Err(anyhow::anyhow!("Unresolved path parameter(s): {}", missing.join(", ")));
+    }
+
+    Ok(format!("{}{}{}", &url[..path_start], segments.join("/"), &url[path_end..]))
+}
+
+fn substitute_segment(segment: &str, path_params: &HashMap<String, String>, missing: &mut Vec<String>) -> String {
+    if let Some(name) = segment.strip_prefix(':').filter(|name| is_param_name(name)) {
+        return match path_params.get(name) {
+            Some(value) => encode_path_segment(value),
+            None => {
+                missing.push(name.to_string());
+                segment.to_string()
+            }
+        };
+    }
+
+    let mut result = String::new();
+    let mut rest = segment;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|offset| open + offset) else {
+            break;
+        };
+        let name = &rest[open + 1..close];
+        result.push_str(&rest[..open]);
+        if is_param_name(name) {
+            match path_params.get(name) {
+                Some(value) => result.push_str(&encode_path_segment(value)),
+                None => {
+                    missing.push(name.to_string());
+                    result.push_str(&rest[open..=close]);
+                }
+            }
+        } else {
+            result.push_str(&rest[open..=close]);
+        }
+        rest = &rest[close + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn is_param_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+// Percent-encode everything except RFC 3986 unreserved characters
+fn encode_path_segment(input: &str) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(key: &str, value: &str, enabled: bool) -> QueryParam {
+        QueryParam {
+            key: key.to_string(),
+            value: value.to_string(),
+            enabled,
+        }
+    }
+
+    #[test]
+    fn test_split_query() {
+        let (url, params) = split_query("{{base}}/search?q=a%20b&tag=x&tag=y#results");
+        assert_eq!(url, "{{base}}/search#results");
+        assert_eq!(params, vec![param("q", "a b", true), param("tag", "x", true), param("tag", "y", true)]);
+        assert_eq!(split_query("https://example.com/x"), ("https://example.com/x".to_string(), vec![]));
+    }
+
+    #[test]
+    fn test_append_keeps_order_and_duplicates() {
+        let params = vec![param("b", "2", true), param("a", "1", true), param("a", "3", true)];
+        let url = append_query_params("https://example.com/x", &params, None).unwrap();
+        assert_eq!(url, "https://example.com/x?b=2&a=1&a=3");
+    }
+
+    #[test]
+    fn test_append_skips_disabled_params() {
+        let params = vec![param("a", "1", false), param("b", "2", true)];
+        let url = append_query_params("https://example.com/x?z=0#top", &params, None).unwrap();
+        assert_eq!(url, "https://example.com/x?z=0&b=2#top");
+    }
+
+    #[test]
+    fn test_enabled_param_pairs_keep_duplicates() {
+        let params = vec![param("tag", "b", true), param("off", "1", false), param("tag", "a", true)];
+        let pair = |key: &str, value: &str| (key.to_string(), value.to_string());
+        assert_eq!(enabled_param_pairs(&params), vec![pair("tag", "b"), pair("tag", "a")]);
+    }
+
+    #[test]
+    fn test_encoding_modes() {
+        let params = vec![param("q", "a b&c", true)];
+        let encoded = append_query_params("https://example.com", &params, Some("encode")).unwrap();
+        assert_eq!(encoded, "https://example.com?q=a+b%26c");
+
+        let params = vec![param("q", "a%20b", true)];
+        let raw = append_query_params("https://example.com", &params, Some("raw")).unwrap();
+        assert_eq!(raw, "https://example.com?q=a%20b");
+
+        assert!(append_query_params("https://example.com", &params, Some("weird")).is_err());
+    }
+
+    #[test]
+    fn test_substitute_path_params() {
+        let mut path_params = HashMap::new();
+        path_params.insert("id".to_string(), "42".to_string());
+        path_params.insert("name".to_string(), "a b/c".to_string());
+
+        let url = substitute_path_params("http://localhost:8080/users/:id/files/{name}.json?x=:id", &path_params)
+            .unwrap();
+        assert_eq!(url, "http://localhost:8080/users/42/files/a%20b%2Fc.json?x=:id");
+    }
+
+    #[test]
+    fn test_unresolved_path_params_error() {
+        let err = substitute_path_params("https://example.com/orgs/:org/repos/{repo}", &HashMap::new())
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "Unresolved path parameter(s): org, repo");
+    }
+
+    #[test]
+    fn test_deserialize_list_and_legacy_map() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            #[serde(deserialize_with = "deserialize_query_params")]
+            params: Vec<QueryParam>,
+        }
+
+        let list: Wrapper =
+            serde_json::from_str(r#"{"params":[{"key":"a","value":"1"},{"key":"b","value":"2","enabled":false}]}"#)
+                .unwrap();
+        assert_eq!(list.params, vec![param("a", "1", true), param("b", "2", false)]);
+
+        let map: Wrapper = serde_json::from_str(r#"{"params":{"a":"1"}}"#).unwrap();
+        assert_eq!(map.params, vec![param("a", "1", true)]);
+    }
+}
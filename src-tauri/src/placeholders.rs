@@ -0,0 +1,283 @@
+// 🎓 TEACHING: Unresolved variables
+// Interpolation leaves unknown `{{placeholders}}` in place, so a typo or the wrong active
+// environment means the literal text `{{token}}` goes over the wire. After interpolating,
+// anything still shaped like `{{name}}` is reported with where it appeared; strict mode
+// turns those reports into a failed send.
+
+use crate::database::Request;
+use crate::params;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UnresolvedVariable {
+    pub name: String,
+    pub location: String, // e.g. "URL", "header Authorization", "body"
+}
+
+// Variable names as we allow them; `{{` followed by anything else (a template
+// in a body, say) isn't treated as a placeholder
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '$'))
+}
+
+// Names of the `{{placeholders}}` left in `text`, each once, in order of appearance
+pub fn find_placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let name = after[..end].trim();
+        if is_variable_name(name) && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+    names
+}
+
+pub fn note_unresolved(found: &mut Vec<UnresolvedVariable>, location: &str, text: &str) {
+    for name in find_placeholders(text) {
+        found.push(UnresolvedVariable {
+            name,
+            location: location.to_string(),
+        });
+    }
+}
+
+// "Unresolved variables: {{token}} in header Authorization, {{id}} in URL"
+pub fn describe(unresolved: &[UnresolvedVariable]) -> String {
+    let list: Vec<String> = unresolved
+        .iter()
+        .map(|u| format!("{{{{{}}}}} in {}", u.name, u.location))
+        .collect();
+    format!("Unresolved variables: {}", list.join(", "))
+}
+
+// 🎓 TEACHING: "Where is this variable used?"
+// Every place in a saved request that mentions `{{key}}`, so a variable can be renamed
+// or deleted without quietly breaking requests.
+pub fn request_references(request: &Request, key: &str) -> Vec<String> {
+    let placeholder = format!("{{{{{}}}}}", key);
+    let mut locations = Vec::new();
+    let mut check = |location: String, text: &str| {
+        if text.contains(&placeholder) {
+            locations.push(location);
+        }
+    };
+
+    check("URL".to_string(), &request.url);
+    for param in params::parse_query_params(&request.params).unwrap_or_default() {
+        check(format!("query param {}", param.key), &format!("{} {}", param.key, param.value));
+    }
+    let path_params: BTreeMap<String, String> = serde_json::from_str(&request.path_params).unwrap_or_default();
+    for (name, value) in path_params {
+        check(format!("path param {}", name), &value);
+    }
+    let headers: BTreeMap<String, String> = serde_json::from_str(&request.headers).unwrap_or_default();
+    for (name, value) in headers {
+        check(format!("header {}", name), &format!("{} {}", name, value));
+    }
+    check("body".to_string(), request.body_str.as_deref().unwrap_or(""));
+    check("GraphQL variables".to_string(), request.graphql_variables.as_deref().unwrap_or(""));
+    check("gRPC config".to_string(), request.grpc_config.as_deref().unwrap_or(""));
+    check("auth".to_string(), request.auth_data.as_deref().unwrap_or(""));
+    locations
+}
+
+// 🎓 TEACHING: Typed variables in JSON bodies
+// A variable's `value_type` ("string", "number", "boolean", or "json") matters where it
+// lands in a JSON body. A placeholder that is a whole string, like `"count": "{{max}}"`,
+// becomes a bare `10` / `true` / `{...}` for typed variables. Inside a longer string the
+// value is JSON-escaped, so quotes and newlines can't break the payload. Bare placeholders
+// (`"count": {{max}}`) are inserted as-is, as they always were.
+#[derive(Debug, Clone)]
+pub struct TypedValue {
+    pub value: String,
+    pub value_type: String,
+}
+
+pub fn validate_typed_value(value_type: &str, value: &str) -> Result<()> {
+    let valid = match value_type {
+        "string" => true,
+        "number" => serde_json::from_str::<serde_json::Number>(value.trim()).is_ok(),
+        "boolean" => matches!(value.trim(), "true" | "false"),
+        "json" => serde_json::from_str::<serde_json::Value>(value).is_ok(),
+        other => return Err(anyhow::anyhow!("Unknown variable type: {}", other)),
+    };
+    if !valid {
+        return Err(anyhow::anyhow!("Value is not a valid {}: {}", value_type, value));
+    }
+    Ok(())
+}
+
+// `{{name}}` placeholders with a value in `values` are replaced; others are left alone
+pub fn interpolate_json(text: &str, values: &HashMap<String, TypedValue>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut string_start = 0; // Position of the opening quote in `out`
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+        if let Some((name, token_len)) = rest
+            .strip_prefix("{{")
+            .and_then(|after| after.find("}}").map(|end| (&after[..end], end + 4)))
+        {
+            if let Some(typed) = values.get(name) {
+                if !in_string {
+                    out.push_str(&typed.value);
+                    i += token_len;
+                    continue;
+                }
+                let whole_string = out.len() == string_start + 1 && text[i + token_len..].starts_with('"');
+                if whole_string && typed.value_type != "string" && validate_typed_value(&typed.value_type, &typed.value).is_ok() {
+                    out.pop(); // The opening quote
+                    out.push_str(typed.value.trim());
+                    i += token_len + 1; // And the closing one
+                    in_string = false;
+                    continue;
+                }
+                let quoted = serde_json::Value::String(typed.value.clone()).to_string();
+                out.push_str(&quoted[1..quoted.len() - 1]);
+                i += token_len;
+                continue;
+            }
+        }
+
+        let c = rest.chars().next().unwrap_or_default();
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+            string_start = out.len();
+        }
+        out.push(c);
+        i += c.len_utf8();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_placeholders() {
+        assert_eq!(
+            find_placeholders("{{base_url}}/users/{{ id }}?q={{base_url}}"),
+            vec!["base_url", "id"]
+        );
+        assert!(find_placeholders(r#"{"a": {"b": 1}}"#).is_empty());
+        assert!(find_placeholders("{{#each items}}{{/each}} {{unclosed").is_empty());
+    }
+
+    #[test]
+    fn test_interpolate_json() {
+        let typed = |value: &str, value_type: &str| TypedValue {
+            value: value.to_string(),
+            value_type: value_type.to_string(),
+        };
+        let values: HashMap<String, TypedValue> = [
+            ("max", typed("10", "number")),
+            ("flag", typed("true", "boolean")),
+            ("filter", typed(r#"{"a": [1, 2]}"#, "json")),
+            ("name", typed("Ada \"The\" Countess", "string")),
+            ("bad", typed("ten", "number")),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+
+        let body = r#"{"count": "{{max}}", "bare": {{max}}, "on": "{{flag}}", "filter": "{{filter}}",
+            "name": "{{name}}", "greeting": "Hi {{name}} x{{max}}", "bad": "{{bad}}", "other": "{{unknown}}",
+            "escaped": "\"{{max}}\""}"#;
+        let json: serde_json::Value = serde_json::from_str(&interpolate_json(body, &values)).unwrap();
+
+        assert_eq!(json["count"], 10);
+        assert_eq!(json["bare"], 10);
+        assert_eq!(json["on"], true);
+        assert_eq!(json["filter"]["a"][1], 2);
+        assert_eq!(json["name"], "Ada \"The\" Countess");
+        assert_eq!(json["greeting"], "Hi Ada \"The\" Countess x10");
+        assert_eq!(json["bad"], "ten");
+        assert_eq!(json["other"], "{{unknown}}");
+        assert_eq!(json["escaped"], "\"10\"");
+    }
+
+    #[test]
+    fn test_request_references() {
+        let now = chrono::Utc::now();
+        let request = Request {
+            id: "r".to_string(),
+            collection_id: "c".to_string(),
+            name: "Get user".to_string(),
+            method: "GET".to_string(),
+            url: "{{base_url}}/users/:id".to_string(),
+            params: r#"[{"key":"token","value":"{{token}}","enabled":false}]"#.to_string(),
+            headers: r#"{"X-Api-Key":"{{token}}","Accept":"application/json"}"#.to_string(),
+            path_params: r#"{"id":"{{user_id}}"}"#.to_string(),
+            body_type: "none".to_string(),
+            body_str: None,
+            graphql_variables: None,
+            graphql_operation_name: None,
+            grpc_config: None,
+            captures: None,
+            scripts: None,
+            settings: None,
+            docs: None,
+            auth_type: Some("bearer".to_string()),
+            auth_data: Some(r#"{"token":"{{token}}"}"#.to_string()),
+            sort_order: 0,
+            pinned: false,
+            last_used_at: None,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+        };
+
+        assert_eq!(
+            request_references(&request, "token"),
+            vec!["query param token", "header X-Api-Key", "auth"]
+        );
+        assert_eq!(request_references(&request, "base_url"), vec!["URL"]);
+        assert_eq!(request_references(&request, "user_id"), vec!["path param id"]);
+        assert!(request_references(&request, "base").is_empty());
+    }
+
+    #[test]
+    fn test_validate_typed_value() {
+        assert!(validate_typed_value("number", "-1.5e3").is_ok());
+        assert!(validate_typed_value("number", "12px").is_err());
+        assert!(validate_typed_value("boolean", "false").is_ok());
+        assert!(validate_typed_value("boolean", "yes").is_err());
+        assert!(validate_typed_value("json", "[1, {}]").is_ok());
+        assert!(validate_typed_value("json", "{").is_err());
+        assert!(validate_typed_value("date", "2024").is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        let mut found = Vec::new();
+        note_unresolved(&mut found, "URL", "{{host}}/users");
+        note_unresolved(&mut found, "header Authorization", "Bearer {{token}}");
+        assert_eq!(
+            describe(&found),
+            "Unresolved variables: {{host}} in URL, {{token}} in header Authorization"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+// 🎓 TEACHING: Custom auth plugins (WebAssembly)
+// Teams with proprietary signing schemes can ship a small WASM module instead of forking the app.
+// The module runs in a sandboxed interpreter with no imports (no network, no filesystem)
+// and a fuel limit, so a broken plugin can't hang the app.
+//
+// Plugin ABI:
+// - export `memory`
+// - export `alloc(len: i32) -> i32`: reserve `len` bytes and return a pointer to them
+// - export `sign(ptr: i32, len: i32) -> i64`: read the JSON input at `ptr`, return
+//   `(out_ptr << 32) | out_len` pointing at the JSON output
+//
+// Input:  { "method", "url", "headers": {..}, "body", "config": <from auth_data> }
+// Output: { "headers": { "Name": "value", .. } }
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasmi::{Engine, Instance, Linker, Memory, Module, Store};
+
+// Enough for real signing work, small enough to stop an infinite loop quickly
+const FUEL_LIMIT: u64 = 50_000_000;
+
+// What the request's auth_data holds for `auth_type = "plugin"`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PluginAuthConfig {
+    pub plugin: String, // Name of an installed plugin
+    #[serde(default)]
+    pub config: serde_json::Value, // Passed to the plugin untouched
+}
+
+// The prepared request handed to the plugin
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PluginRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+    pub config: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PluginResponse {
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+// 🎓 TEACHING: Check a module at install time, so a bad upload fails early
+pub fn validate(wasm: &[u8]) -> Result<()> {
+    let (mut store, instance) = instantiate(wasm)?;
+    exports(&mut store, &instance)?;
+    Ok(())
+}
+
+// 🎓 TEACHING: Run the plugin's `sign` function and return the headers it wants added
+pub fn run(wasm: &[u8], request: &PluginRequest) -> Result<HashMap<String, String>> {
+    let (mut store, instance) = instantiate(wasm)?;
+    let (memory, alloc, sign) = exports(&mut store, &instance)?;
+
+    let input = serde_json::to_vec(request)?;
+    let input_len = i32::try_from(input.len()).map_err(|_| anyhow::anyhow!("Plugin input is too large"))?;
+    let input_ptr = alloc.call(&mut store, input_len)?;
+    memory
+        .write(&mut store, input_ptr as u32 as usize, &input)
+        .map_err(|e| anyhow::anyhow!("Plugin returned an invalid input buffer: {}", e))?;
+
+    let packed = sign.call(&mut store, (input_ptr, input_len))? as u64;
+    let output_ptr = (packed >> 32) as usize;
+    let output_len = (packed & 0xffff_ffff) as usize;
+
+    let mut output = vec![0u8; output_len];
+    memory
+        .read(&store, output_ptr, &mut output)
+        .map_err(|e| anyhow::anyhow!("Plugin returned an invalid output buffer: {}", e))?;
+
+    let response: PluginResponse = serde_json::from_slice(&output)
+        .map_err(|e| anyhow::anyhow!("Plugin output is not valid JSON: {}", e))?;
+    Ok(response.headers)
+}
+
+fn instantiate(wasm: &[u8]) -> Result<(Store<()>, Instance)> {
+    let mut config = wasmi::Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+
+    let module = Module::new(&engine, wasm).map_err(|e| anyhow::anyhow!("Invalid WASM module: {}", e))?;
+    let mut store = Store::new(&engine, ());
+    store.set_fuel(FUEL_LIMIT).map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    // No host functions are linked: plugins only see the bytes we give them
+    let linker = <Linker<()>>::new(&engine);
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .and_then(|pre| pre.start(&mut store))
+        .map_err(|e| anyhow::anyhow!("Failed to instantiate plugin: {}", e))?;
+
+    Ok((store, instance))
+}
+
+type PluginExports = (Memory, wasmi::TypedFunc<i32, i32>, wasmi::TypedFunc<(i32, i32), i64>);
+
+fn exports(store: &mut Store<()>, instance: &Instance) -> Result<PluginExports> {
+    let memory = instance
+        .get_memory(&*store, "memory")
+        .ok_or_else(|| anyhow::anyhow!("Plugin must export `memory`"))?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&*store, "alloc")
+        .map_err(|e| anyhow::anyhow!("Plugin must export `alloc(i32) -> i32`: {}", e))?;
+    let sign = instance
+        .get_typed_func::<(i32, i32), i64>(&*store, "sign")
+        .map_err(|e| anyhow::anyhow!("Plugin must export `sign(i32, i32) -> i64`: {}", e))?;
+    Ok((memory, alloc, sign))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Ignores its input and always adds `X-Signed: yes`
+    const STATIC_PLUGIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 0) "{\"headers\":{\"X-Signed\":\"yes\"}}")
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "sign") (param $ptr i32) (param $len i32) (result i64)
+            (i64.const 30)))
+    "#;
+
+    const LOOPING_PLUGIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "sign") (param i32 i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0)))
+    "#;
+
+    fn request() -> PluginRequest {
+        PluginRequest {
+            method: "GET".to_string(),
+            url: "https://example.com".to_string(),
+            headers: HashMap::new(),
+            body: String::new(),
+            config: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_run_plugin_returns_headers() {
+        let wasm = wat::parse_str(STATIC_PLUGIN).unwrap();
+        validate(&wasm).unwrap();
+        let headers = run(&wasm, &request()).unwrap();
+        assert_eq!(headers.get("X-Signed").map(String::as_str), Some("yes"));
+    }
+
+    #[test]
+    fn test_validate_rejects_missing_exports() {
+        let wasm = wat::parse_str(r#"(module (memory (export "memory") 1))"#).unwrap();
+        assert!(validate(&wasm).is_err());
+        assert!(validate(b"not wasm").is_err());
+    }
+
+    #[test]
+    fn test_runaway_plugin_runs_out_of_fuel() {
+        let wasm = wat::parse_str(LOOPING_PLUGIN).unwrap();
+        assert!(run(&wasm, &request()).is_err());
+    }
+}
@@ -0,0 +1,440 @@
+// 🎓 TEACHING: Postman `pm.*` compatibility for Rhai scripts
+// Postman scripts are built around a global `pm` object. This module gives Rhai scripts the
+// same object, covering the calls collections use most, so a script ported from Postman only
+// needs its JavaScript syntax changed (`function () {}` becomes `|| {}`, `const` becomes `let`):
+//
+//   pm.environment.get("token"), pm.environment.set("token", value)
+//   pm.variables.get("base_url"), pm.variables.set("id", value)
+//   pm.response.json(), pm.response.text(), pm.response.code, pm.response.headers.get("ETag")
+//   pm.response.to.have.status(200), pm.response.to.have.header("ETag"), pm.response.to.be.ok
+//   pm.test("status is 200", || { pm.expect(pm.response.code).to.eql(200); });
+//   pm.expect(value).to.be.above(3), .to.not.include("x"), .to.have.property("id"), ...
+//
+// There's one variable store here, not Postman's layered scopes, so `pm.variables.set` writes
+// to the active environment just like `pm.environment.set`.
+
+use crate::scripting::{ScriptState, TestResult};
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, NativeCallContext};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+type Check<T> = Result<T, Box<EvalAltResult>>;
+
+// The `pm` global. Pre-request scripts get one without a response.
+// 🎓 TEACHING: `pm` comes from a variable resolver rather than the scope. A closure that
+// captures a scope variable turns it into a shared, locked value, and `pm.test(name, || ...)`
+// would then hold that lock while the closure tried to read `pm` again.
+#[derive(Clone)]
+pub struct Pm {
+    response: Option<PmResponse>,
+}
+
+impl Pm {
+    pub fn new(response: Option<PmResponse>) -> Self {
+        Self { response }
+    }
+}
+
+#[derive(Clone)]
+pub struct PmResponse {
+    code: u16,
+    headers: HashMap<String, String>,
+    body: String,
+    json: Option<Dynamic>,
+}
+
+impl PmResponse {
+    pub fn new(code: u16, headers: &HashMap<String, String>, body: &str, json: Option<Dynamic>) -> Self {
+        Self {
+            code,
+            headers: headers.clone(),
+            body: body.to_string(),
+            json,
+        }
+    }
+
+    // Header names are case-insensitive, as in Postman
+    fn header(&self, name: &str) -> Option<&String> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+}
+
+// `pm.environment` and `pm.variables`
+#[derive(Clone)]
+pub struct PmVariables;
+
+#[derive(Clone)]
+pub struct PmHeaders(PmResponse);
+
+// `pm.response.to...`
+#[derive(Clone)]
+pub struct ResponseAssertion(PmResponse);
+
+// `pm.expect(value)...`, Chai style: the chain words return the same expectation, `not`
+// flips it, and the final call throws when the check fails
+#[derive(Clone)]
+pub struct Expectation {
+    actual: Dynamic,
+    negated: bool,
+}
+
+impl Expectation {
+    fn check(&self, passed: bool, what: String) -> Check<Self> {
+        if passed != self.negated {
+            return Ok(self.clone());
+        }
+        let not = if self.negated { "not " } else { "" };
+        Err(format!("expected {} to {}{}", describe(&self.actual), not, what).into())
+    }
+}
+
+pub fn register(engine: &mut Engine, state: &Rc<RefCell<ScriptState>>, pm: Pm) {
+    #[allow(deprecated)] // Rhai marks variable resolvers as volatile, not as going away
+    engine.on_var(move |name, _, _| match name {
+        "pm" => Ok(Some(Dynamic::from(pm.clone()))),
+        _ => Ok(None),
+    });
+    engine.register_type_with_name::<Pm>("Pm");
+    engine.register_type_with_name::<PmVariables>("PmVariables");
+    engine.register_type_with_name::<PmResponse>("PmResponse");
+    engine.register_type_with_name::<PmHeaders>("PmHeaders");
+    engine.register_type_with_name::<ResponseAssertion>("ResponseAssertion");
+    engine.register_type_with_name::<Expectation>("Expectation");
+
+    engine.register_get("environment", |_: &mut Pm| PmVariables);
+    engine.register_get("variables", |_: &mut Pm| PmVariables);
+    engine.register_get("response", |pm: &mut Pm| -> Check<PmResponse> {
+        pm.response.clone().ok_or_else(|| "pm.response is only available in tests scripts".into())
+    });
+    engine.register_fn("expect", |_: &mut Pm, actual: Dynamic| Expectation { actual, negated: false });
+    let test_state = state.clone();
+    engine.register_fn(
+        "test",
+        move |context: NativeCallContext, _: &mut Pm, name: &str, check: FnPtr| -> Check<()> {
+            let error = match check.call_within_context::<Dynamic>(&context, ()) {
+                Ok(_) => None,
+                Err(e) => Some(failure(e)?),
+            };
+            test_state.borrow_mut().outcome.tests.push(TestResult {
+                name: name.to_string(),
+                passed: error.is_none(),
+                error,
+            });
+            Ok(())
+        },
+    );
+
+    let get_state = state.clone();
+    engine.register_fn("get", move |_: &mut PmVariables, key: &str| get_state.borrow().get(key));
+    let has_state = state.clone();
+    engine.register_fn("has", move |_: &mut PmVariables, key: &str| has_state.borrow().variables.contains_key(key));
+    let set_state = state.clone();
+    engine.register_fn("set", move |_: &mut PmVariables, key: &str, value: Dynamic| {
+        set_state.borrow_mut().set(key, value)
+    });
+
+    engine.register_get("code", |response: &mut PmResponse| response.code as i64);
+    engine.register_get("status", |response: &mut PmResponse| {
+        let code = reqwest::StatusCode::from_u16(response.code).ok();
+        code.and_then(|code| code.canonical_reason()).unwrap_or_default().to_string()
+    });
+    engine.register_get("headers", |response: &mut PmResponse| PmHeaders(response.clone()));
+    engine.register_get("to", |response: &mut PmResponse| ResponseAssertion(response.clone()));
+    engine.register_fn("text", |response: &mut PmResponse| response.body.clone());
+    engine.register_fn("json", |response: &mut PmResponse| -> Check<Dynamic> {
+        response.json.clone().ok_or_else(|| "the response body is not JSON".into())
+    });
+    engine.register_fn("get", |headers: &mut PmHeaders, name: &str| -> Dynamic {
+        headers.0.header(name).map_or(Dynamic::UNIT, |value| value.clone().into())
+    });
+    engine.register_fn("has", |headers: &mut PmHeaders, name: &str| headers.0.header(name).is_some());
+
+    for word in ["have", "be", "and"] {
+        engine.register_get(word, |assertion: &mut ResponseAssertion| assertion.clone());
+    }
+    engine.register_fn("status", |assertion: &mut ResponseAssertion, code: i64| -> Check<()> {
+        match assertion.0.code as i64 == code {
+            true => Ok(()),
+            false => Err(format!("expected response to have status code {} but got {}", code, assertion.0.code).into()),
+        }
+    });
+    engine.register_fn("header", |assertion: &mut ResponseAssertion, name: &str| -> Check<()> {
+        match assertion.0.header(name) {
+            Some(_) => Ok(()),
+            None => Err(format!("expected response to have header {}", name).into()),
+        }
+    });
+    engine.register_get("ok", |assertion: &mut ResponseAssertion| -> Check<ResponseAssertion> {
+        match (200..300).contains(&assertion.0.code) {
+            true => Ok(assertion.clone()),
+            false => Err(format!("expected response code to be 2XX but got {}", assertion.0.code).into()),
+        }
+    });
+
+    register_expectation(engine);
+}
+
+fn register_expectation(engine: &mut Engine) {
+    for word in ["to", "be", "been", "have", "has", "and", "that", "which", "deep"] {
+        engine.register_get(word, |expectation: &mut Expectation| expectation.clone());
+    }
+    engine.register_get("not", |expectation: &mut Expectation| Expectation {
+        negated: !expectation.negated,
+        ..expectation.clone()
+    });
+
+    for name in ["eql", "equal", "equals", "eq"] {
+        engine.register_fn(name, |expectation: &mut Expectation, expected: Dynamic| {
+            let passed = same(&to_json(&expectation.actual), &to_json(&expected));
+            expectation.check(passed, format!("equal {}", describe(&expected)))
+        });
+    }
+    engine.register_fn("include", |expectation: &mut Expectation, expected: Dynamic| {
+        let passed = includes(&to_json(&expectation.actual), &to_json(&expected));
+        expectation.check(passed, format!("include {}", describe(&expected)))
+    });
+    engine.register_fn("oneOf", |expectation: &mut Expectation, options: rhai::Array| {
+        let actual = to_json(&expectation.actual);
+        let passed = options.iter().any(|option| same(&actual, &to_json(option)));
+        expectation.check(passed, format!("be one of {}", describe(&options.into())))
+    });
+    engine.register_fn("above", |expectation: &mut Expectation, bound: Dynamic| {
+        let passed = matches!(compare(&expectation.actual, &bound), Some(std::cmp::Ordering::Greater));
+        expectation.check(passed, format!("be above {}", describe(&bound)))
+    });
+    engine.register_fn("below", |expectation: &mut Expectation, bound: Dynamic| {
+        let passed = matches!(compare(&expectation.actual, &bound), Some(std::cmp::Ordering::Less));
+        expectation.check(passed, format!("be below {}", describe(&bound)))
+    });
+    for name in ["a", "an"] {
+        engine.register_fn(name, |expectation: &mut Expectation, kind: &str| {
+            let passed = type_name(&to_json(&expectation.actual)) == kind.to_lowercase();
+            expectation.check(passed, format!("be a {}", kind))
+        });
+    }
+    for name in ["lengthOf", "length"] {
+        engine.register_fn(name, |expectation: &mut Expectation, length: i64| {
+            let passed = length_of(&to_json(&expectation.actual)) == Some(length as usize);
+            expectation.check(passed, format!("have length {}", length))
+        });
+    }
+    // Like Chai, `property` moves the expectation on to the property's value
+    engine.register_fn("property", |expectation: &mut Expectation, name: &str| -> Check<Expectation> {
+        let value = expectation.actual.clone().try_cast::<rhai::Map>().and_then(|map| map.get(name).cloned());
+        expectation.check(value.is_some(), format!("have property '{}'", name))?;
+        Ok(Expectation {
+            actual: value.unwrap_or_default(),
+            negated: false,
+        })
+    });
+    engine.register_fn(
+        "property",
+        |expectation: &mut Expectation, name: &str, expected: Dynamic| -> Check<Expectation> {
+            let value = expectation.actual.clone().try_cast::<rhai::Map>().and_then(|map| map.get(name).cloned());
+            let passed = value.as_ref().is_some_and(|value| same(&to_json(value), &to_json(&expected)));
+            let what = format!("have property '{}' of {}", name, describe(&expected));
+            expectation.check(passed, what)
+        },
+    );
+    engine.register_get("ok", |expectation: &mut Expectation| {
+        let passed = match to_json(&expectation.actual) {
+            Value::Null | Value::Bool(false) => false,
+            Value::String(text) => !text.is_empty(),
+            Value::Number(number) => number.as_f64() != Some(0.0),
+            _ => true,
+        };
+        expectation.check(passed, "be ok".to_string())
+    });
+    engine.register_get("empty", |expectation: &mut Expectation| {
+        let passed = length_of(&to_json(&expectation.actual)) == Some(0);
+        expectation.check(passed, "be empty".to_string())
+    });
+}
+
+// A failed check becomes the test's error message. Hitting the operation limit still stops
+// the whole script, so a runaway loop inside pm.test can't be swallowed.
+fn failure(error: Box<EvalAltResult>) -> Check<String> {
+    match *error {
+        EvalAltResult::ErrorInFunctionCall(_, _, inner, _) => failure(inner),
+        EvalAltResult::ErrorRuntime(message, _) => Ok(message.to_string()),
+        EvalAltResult::ErrorTooManyOperations(_) | EvalAltResult::ErrorTerminated(..) => Err(error),
+        other => Ok(other.to_string()),
+    }
+}
+
+fn to_json(value: &Dynamic) -> Value {
+    rhai::serde::from_dynamic(value).unwrap_or_else(|_| Value::String(value.to_string()))
+}
+
+fn describe(value: &Dynamic) -> String {
+    to_json(value).to_string()
+}
+
+// Deep equality, where 42 and 42.0 are the same number as they are in JavaScript
+fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b)),
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| same(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+// Substring, array element, or (for objects) every expected key with the same value
+fn includes(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::String(actual), Value::String(expected)) => actual.contains(expected.as_str()),
+        (Value::Array(items), expected) => items.iter().any(|item| same(item, expected)),
+        (Value::Object(actual), Value::Object(expected)) => expected
+            .iter()
+            .all(|(key, expected)| actual.get(key).is_some_and(|actual| same(actual, expected))),
+        _ => false,
+    }
+}
+
+fn compare(a: &Dynamic, b: &Dynamic) -> Option<std::cmp::Ordering> {
+    to_json(a).as_f64()?.partial_cmp(&to_json(b).as_f64()?)
+}
+
+fn length_of(value: &Value) -> Option<usize> {
+    match value {
+        Value::String(text) => Some(text.chars().count()),
+        Value::Array(items) => Some(items.len()),
+        Value::Object(fields) => Some(fields.len()),
+        _ => None,
+    }
+}
+
+// The names Chai uses for `a(...)`
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scripting::{run_pre_request, run_tests, Script, ScriptRequest};
+    use std::collections::HashMap;
+
+    fn rhai(code: &str) -> Script {
+        Script {
+            language: "rhai".to_string(),
+            code: code.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_postman_tests_script() {
+        let headers = HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
+        let body = r#"{"data": {"id": 42, "name": "Ada", "tags": ["a", "b"]}}"#;
+        let variables = HashMap::from([("expected_name".to_string(), "Ada".to_string())]);
+        let script = rhai(
+            r#"
+            let data = pm.response.json().data;
+            pm.test("status is 200", || { pm.response.to.have.status(200); });
+            pm.test("is ok with JSON", || {
+                pm.response.to.be.ok;
+                pm.response.to.have.header("content-type");
+                pm.expect(pm.response.headers.get("CONTENT-TYPE")).to.include("json");
+            });
+            pm.test("user", || {
+                pm.expect(data.id).to.eql(42.0);
+                pm.expect(data.name).to.equal(pm.environment.get("expected_name"));
+                pm.expect(data).to.have.property("tags").that.has.lengthOf(2);
+                pm.expect(data.tags).to.include("b").and.not.include("c");
+                pm.expect(data).to.include(#{ id: 42 });
+                pm.expect(data.name).to.be.a("string");
+                pm.expect(data.id).to.be.above(40).and.below(50);
+                pm.expect(pm.response.status).to.be.oneOf(["OK", "Created"]);
+            });
+            pm.test("wrong id", || pm.expect(data.id).to.eql(7));
+            pm.test("unexpected header", || pm.response.to.have.header("ETag"));
+            pm.environment.set("user_id", data.id);
+            pm.variables.set("user_name", data.name);
+            "#,
+        );
+
+        let outcome = run_tests(&script, 200, &headers, body, &variables, &[]);
+        let results: Vec<(&str, bool)> = outcome.tests.iter().map(|t| (t.name.as_str(), t.passed)).collect();
+        assert_eq!(
+            results,
+            vec![
+                ("status is 200", true),
+                ("is ok with JSON", true),
+                ("user", true),
+                ("wrong id", false),
+                ("unexpected header", false),
+            ]
+        );
+        assert_eq!(outcome.tests[3].error.as_deref(), Some("expected 42 to equal 7"));
+        assert_eq!(outcome.tests[4].error.as_deref(), Some("expected response to have header ETag"));
+        assert_eq!(
+            outcome.variables_set,
+            vec![("user_id".to_string(), "42".to_string()), ("user_name".to_string(), "Ada".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_failed_checks_and_limits() {
+        let headers = HashMap::new();
+        let script = rhai(
+            r#"
+            pm.test("not json", || pm.response.json());
+            pm.test("status", || pm.response.to.have.status(200));
+            pm.test("negated", || pm.expect("").to.not.be.ok);
+            pm.test("empty", || pm.expect([]).to.be.empty);
+            "#,
+        );
+        let outcome = run_tests(&script, 404, &headers, "Not here", &HashMap::new(), &[]);
+        let errors: Vec<Option<&str>> = outcome.tests.iter().map(|t| t.error.as_deref()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                Some("the response body is not JSON"),
+                Some("expected response to have status code 200 but got 404"),
+                None,
+                None,
+            ]
+        );
+
+        // A runaway check ends the script instead of counting as one failed test
+        let outcome = run_tests(&rhai(r#"pm.test("spin", || { loop {} });"#), 200, &headers, "", &HashMap::new(), &[]);
+        assert_eq!(outcome.tests.len(), 1);
+        assert_eq!(outcome.tests[0].name, "Test script");
+    }
+
+    #[test]
+    fn test_postman_pre_request_script() {
+        let mut request = ScriptRequest {
+            method: "GET".to_string(),
+            url: "{{base_url}}/users".to_string(),
+            headers: HashMap::new(),
+            body: None,
+        };
+        let variables = HashMap::from([("base_url".to_string(), "https://api.example.com".to_string())]);
+        let script = rhai(
+            r#"
+            if !pm.variables.has("token") { pm.environment.set("token", "abc"); }
+            request.headers["Authorization"] = `Bearer ${pm.environment.get("token")}`;
+            "#,
+        );
+        let outcome = run_pre_request(&script, &mut request, &variables, &[]).unwrap();
+        assert_eq!(request.headers["Authorization"], "Bearer abc");
+        assert_eq!(outcome.variables_set, vec![("token".to_string(), "abc".to_string())]);
+
+        assert!(run_pre_request(&rhai("pm.response.code"), &mut request, &variables, &[]).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+// 🎓 TEACHING: Send progress events
+// A slow call used to leave the UI with nothing to show until the whole response was in.
+// Each send now reports its lifecycle as Tauri events, all carrying the same execution id
+// (the frontend passes one in, or one is made up and announced by `request:started`):
+//
+//   request:started           the send is on its way
+//   request:headers_received  status line and headers are back, body still coming
+//   request:progress          bytes of the body received so far (and the total, if known)
+//   request:finished          done, with the status or the error
+//
+// Progress is throttled to one event per PROGRESS_INTERVAL, plus one at the end, so a fast
+// download doesn't flood the event bus.
+
+use crate::error::AppError;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ProgressEvent {
+    Started {
+        execution_id: String,
+        request_id: Option<String>,
+        method: String,
+        url: String, // As written in the editor, before variables are filled in
+    },
+    HeadersReceived {
+        execution_id: String,
+        status: u16,
+        http_version: String,
+        headers: HashMap<String, String>,
+        content_length: Option<u64>,
+    },
+    Progress {
+        execution_id: String,
+        received_bytes: u64,
+        total_bytes: Option<u64>, // None for chunked bodies and streams
+    },
+    Finished {
+        execution_id: String,
+        status: Option<u16>,
+        duration_ms: u64,
+        received_bytes: Option<u64>,
+        from_cache: bool,
+        error: Option<AppError>,
+    },
+}
+
+impl ProgressEvent {
+    // The Tauri event it's emitted as
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProgressEvent::Started { .. } => "request:started",
+            ProgressEvent::HeadersReceived { .. } => "request:headers_received",
+            ProgressEvent::Progress { .. } => "request:progress",
+            ProgressEvent::Finished { .. } => "request:finished",
+        }
+    }
+}
+
+pub type ProgressSink = Arc<dyn Fn(ProgressEvent) + Send + Sync>;
+
+// Where one send reports its progress to
+#[derive(Clone)]
+pub struct ProgressReporter {
+    pub execution_id: String,
+    sink: ProgressSink,
+}
+
+impl ProgressReporter {
+    pub fn new(execution_id: String, sink: ProgressSink) -> Self {
+        ProgressReporter { execution_id, sink }
+    }
+
+    pub fn emit(&self, event: ProgressEvent) {
+        (self.sink)(event);
+    }
+
+    pub fn headers_received(
+        &self,
+        status: u16,
+        http_version: &str,
+        headers: &HashMap<String, String>,
+        content_length: Option<u64>,
+    ) {
+        self.emit(ProgressEvent::HeadersReceived {
+            execution_id: self.execution_id.clone(),
+            status,
+            http_version: http_version.to_string(),
+            headers: headers.clone(),
+            content_length,
+        });
+    }
+
+    pub fn body(&self, total_bytes: Option<u64>) -> BodyProgress<'_> {
+        BodyProgress {
+            reporter: self,
+            received_bytes: 0,
+            total_bytes,
+            last_sent: None,
+        }
+    }
+}
+
+// Counts body bytes as they arrive and reports them every PROGRESS_INTERVAL
+pub struct BodyProgress<'a> {
+    reporter: &'a ProgressReporter,
+    received_bytes: u64,
+    total_bytes: Option<u64>,
+    last_sent: Option<(Instant, u64)>,
+}
+
+impl BodyProgress<'_> {
+    pub fn add(&mut self, bytes: usize) {
+        self.received_bytes += bytes as u64;
+        if self.last_sent.is_none_or(|(at, _)| at.elapsed() >= PROGRESS_INTERVAL) {
+            self.send();
+        }
+    }
+
+    // The final count, unless the last event already had it
+    pub fn finish(mut self) {
+        if self.last_sent.is_none_or(|(_, sent)| sent != self.received_bytes) {
+            self.send();
+        }
+    }
+
+    fn send(&mut self) {
+        self.last_sent = Some((Instant::now(), self.received_bytes));
+        self.reporter.emit(ProgressEvent::Progress {
+            execution_id: self.reporter.execution_id.clone(),
+            received_bytes: self.received_bytes,
+            total_bytes: self.total_bytes,
+        });
+    }
+}
+
+// 🎓 TEACHING: `text()` reads the body in one go, so to report progress it's read chunk by
+// chunk instead. The bytes are then put back into a response with the same headers, so the
+// charset is decoded exactly as `text()` would have.
+pub async fn read_text(mut response: reqwest::Response, reporter: &ProgressReporter) -> Result<String, reqwest::Error> {
+    let headers = response.headers().clone();
+    let mut progress = reporter.body(response.content_length());
+    let mut received = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        received.extend_from_slice(&chunk);
+        progress.add(chunk.len());
+    }
+    progress.finish();
+
+    let mut buffered = hyper::Response::new(received);
+    *buffered.headers_mut() = headers;
+    reqwest::Response::from(buffered).text().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn recorder() -> (ProgressReporter, Arc<Mutex<Vec<ProgressEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink_events = events.clone();
+        let reporter = ProgressReporter::new(
+            "exec-1".to_string(),
+            Arc::new(move |event| sink_events.lock().unwrap().push(event)),
+        );
+        (reporter, events)
+    }
+
+    #[test]
+    fn test_body_progress_is_throttled() {
+        let (reporter, events) = recorder();
+        let mut progress = reporter.body(Some(30));
+        for _ in 0..3 {
+            progress.add(10);
+        }
+        progress.finish();
+
+        // The first chunk is reported straight away, the rest together at the end
+        let events = events.lock().unwrap();
+        let counts: Vec<u64> = events
+            .iter()
+            .map(|event| match event {
+                ProgressEvent::Progress { received_bytes, total_bytes, .. } => {
+                    assert_eq!(*total_bytes, Some(30));
+                    *received_bytes
+                }
+                other => panic!("unexpected event {:?}", other),
+            })
+            .collect();
+        assert_eq!(counts, vec![10, 30]);
+        assert_eq!(events[0].name(), "request:progress");
+    }
+
+    #[tokio::test]
+    async fn test_read_text() {
+        let (reporter, events) = recorder();
+        let response = hyper::Response::builder()
+            .header("Content-Type", "text/plain; charset=iso-8859-1")
+            .body(vec![b'c', b'a', b'f', 0xe9])
+            .unwrap();
+        let text = read_text(reqwest::Response::from(response), &reporter).await.unwrap();
+        assert_eq!(text, "café");
+        assert_eq!(
+            events.lock().unwrap().last(),
+            Some(&ProgressEvent::Progress {
+                execution_id: "exec-1".to_string(),
+                received_bytes: 4,
+                total_bytes: Some(4),
+            })
+        );
+    }
+
+    #[test]
+    fn test_event_payload() {
+        let event = ProgressEvent::Finished {
+            execution_id: "exec-1".to_string(),
+            status: Some(200),
+            duration_ms: 5,
+            received_bytes: Some(2),
+            from_cache: false,
+            error: None,
+        };
+        assert_eq!(event.name(), "request:finished");
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "execution_id": "exec-1",
+                "status": 200,
+                "duration_ms": 5,
+                "received_bytes": 2,
+                "from_cache": false,
+                "error": null,
+            })
+        );
+    }
+}
@@ -0,0 +1,191 @@
+// 🎓 TEACHING: External variable providers
+// Instead of copying production secrets into the local database, a variable can point at
+// AWS Secrets Manager or SSM Parameter Store. The value is fetched when a request uses it,
+// signed with the same AWS credential sources as AWS Signature auth (environment or profile),
+// and kept in memory for a short time so a burst of requests doesn't hammer the API.
+
+use crate::auth::AwsSignatureConfig;
+use anyhow::Result;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, HOST};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Stored as JSON in the `variables.source` column
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VariableSource {
+    pub provider: String,                  // "aws-secrets-manager" or "aws-ssm"
+    pub name: String,                      // Secret ID/ARN, or SSM parameter name
+    pub region: String,
+    pub json_key: Option<String>,          // Pick a single field out of a JSON secret
+    pub version_stage: Option<String>,     // Secrets Manager only (defaults to AWSCURRENT)
+    pub credential_source: Option<String>, // "profile" (default) or "environment"
+    pub profile: Option<String>,
+    pub cache_ttl_secs: Option<u64>,       // How long a fetched value is reused (defaults to 60s)
+}
+
+// 🎓 TEACHING: In-memory cache of fetched values, keyed by the source definition
+#[derive(Default)]
+pub struct ProviderCache {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl ProviderCache {
+    pub fn get(&self, key: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > Instant::now())
+            .map(|(value, _)| value.clone())
+    }
+
+    pub fn insert(&self, key: &str, value: String, ttl: Duration) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (value, Instant::now() + ttl));
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+impl VariableSource {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.cache_ttl_secs.unwrap_or(60))
+    }
+
+    // 🎓 TEACHING: Both services speak the AWS JSON 1.1 protocol: a signed POST to the
+    // regional endpoint with the operation named in the `X-Amz-Target` header.
+    pub async fn fetch(&self) -> Result<String> {
+        let (service, target, payload) = match self.provider.as_str() {
+            "aws-secrets-manager" => {
+                let mut payload = serde_json::json!({ "SecretId": self.name });
+                if let Some(stage) = &self.version_stage {
+                    payload["VersionStage"] = serde_json::json!(stage);
+                }
+                ("secretsmanager", "secretsmanager.GetSecretValue", payload)
+            }
+            "aws-ssm" => (
+                "ssm",
+                "AmazonSSM.GetParameter",
+                serde_json::json!({ "Name": self.name, "WithDecryption": true }),
+            ),
+            other => return Err(anyhow::anyhow!("Unsupported variable provider: {}", other)),
+        };
+
+        let host = format!("{}.{}.amazonaws.com", service, self.region);
+        let url = format!("https://{}/", host);
+        let body = payload.to_string();
+
+        let credentials = AwsSignatureConfig {
+            access_key: String::new(),
+            secret_key: String::new(),
+            region: self.region.clone(),
+            service: service.to_string(),
+            session_token: None,
+            credential_source: Some(self.credential_source.clone().unwrap_or_else(|| "profile".to_string())),
+            profile: self.profile.clone(),
+            signing_algorithm: None,
+        }
+        .resolve_credentials()?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, HeaderValue::from_str(&host)?);
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/x-amz-json-1.1"));
+        headers.insert("x-amz-target", HeaderValue::from_static(target));
+        let signed_headers = credentials.generate_authorization_header("POST", &url, &headers, &body)?;
+
+        let response = reqwest::Client::new()
+            .post(&url)
+            .headers(signed_headers)
+            .body(body)
+            .send()
+            .await?;
+        let status = response.status();
+        let response_body = response.text().await?;
+
+        if !status.is_success() {
+            let message = serde_json::from_str::<serde_json::Value>(&response_body)
+                .ok()
+                .and_then(|json| {
+                    json.get("message")
+                        .or_else(|| json.get("Message"))
+                        .and_then(|m| m.as_str())
+                        .map(str::to_string)
+                })
+                .unwrap_or(response_body);
+            return Err(anyhow::anyhow!("{} request for {} failed ({}): {}", service, self.name, status, message));
+        }
+
+        self.parse_response(&response_body)
+    }
+
+    fn parse_response(&self, body: &str) -> Result<String> {
+        let json: serde_json::Value = serde_json::from_str(body)?;
+        let value = match self.provider.as_str() {
+            "aws-secrets-manager" => json.get("SecretString").and_then(|v| v.as_str()).ok_or_else(|| {
+                anyhow::anyhow!("Secret {} has no string value (binary secrets are not supported)", self.name)
+            })?,
+            _ => json
+                .pointer("/Parameter/Value")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Parameter {} has no value", self.name))?,
+        };
+
+        let Some(json_key) = &self.json_key else {
+            return Ok(value.to_string());
+        };
+
+        let secret: serde_json::Value = serde_json::from_str(value)
+            .map_err(|_| anyhow::anyhow!("Secret {} is not JSON, so json_key cannot be used", self.name))?;
+        match secret.get(json_key) {
+            Some(serde_json::Value::String(s)) => Ok(s.clone()),
+            Some(other) => Ok(other.to_string()),
+            None => Err(anyhow::anyhow!("Key {} not found in secret {}", json_key, self.name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(provider: &str, json_key: Option<&str>) -> VariableSource {
+        VariableSource {
+            provider: provider.to_string(),
+            name: "prod/db".to_string(),
+            region: "us-east-1".to_string(),
+            json_key: json_key.map(str::to_string),
+            version_stage: None,
+            credential_source: None,
+            profile: None,
+            cache_ttl_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_secrets_manager_response() {
+        let body = r#"{"Name":"prod/db","SecretString":"{\"password\":\"hunter2\",\"port\":5432}"}"#;
+        assert_eq!(source("aws-secrets-manager", Some("password")).parse_response(body).unwrap(), "hunter2");
+        assert_eq!(source("aws-secrets-manager", Some("port")).parse_response(body).unwrap(), "5432");
+        assert!(source("aws-secrets-manager", Some("missing")).parse_response(body).is_err());
+    }
+
+    #[test]
+    fn test_parse_ssm_response() {
+        let body = r#"{"Parameter":{"Name":"prod/db","Type":"SecureString","Value":"s3cret"}}"#;
+        assert_eq!(source("aws-ssm", None).parse_response(body).unwrap(), "s3cret");
+    }
+
+    #[test]
+    fn test_provider_cache_expiry() {
+        let cache = ProviderCache::default();
+        cache.insert("a", "1".to_string(), Duration::from_secs(60));
+        cache.insert("b", "2".to_string(), Duration::from_secs(0));
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        assert_eq!(cache.get("b"), None);
+    }
+}
@@ -0,0 +1,367 @@
+// 🎓 TEACHING: Record-and-replay proxy
+// Point an app at localhost:<port> instead of its real backend. In "record" mode every
+// call is forwarded to the target, and the request/response pair is saved: the request
+// as a normal saved request in a collection, the response as a proxy capture. In
+// "replay" mode the proxy answers from those captures without touching the network,
+// so the app keeps working offline or against a backend that no longer exists.
+
+use crate::database::{Database, ProxyCapture, Request};
+use crate::http_server::{self, IncomingRequest, OutgoingResponse, ServerHandle};
+use crate::params::QueryParam;
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProxyConfig {
+    pub port: u16,               // 0 picks a free port
+    pub target_url: String,      // Where traffic is forwarded, e.g. https://api.example.com
+    pub collection_id: String,   // Where recordings are saved (and replayed from)
+    pub mode: String,            // "record" or "replay"
+    #[serde(default)]
+    pub bind_all: bool,
+    #[serde(default)]
+    pub replay_passthrough: bool, // In replay mode, forward calls that were never recorded
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProxyInfo {
+    pub port: u16,
+    pub config: ProxyConfig,
+}
+
+pub type CaptureSink = Arc<dyn Fn(ProxyCapture) + Send + Sync>;
+
+struct RunningProxy {
+    server: ServerHandle,
+    config: ProxyConfig,
+}
+
+// Running proxies, keyed by port
+#[derive(Default)]
+pub struct ProxyManager {
+    proxies: Mutex<HashMap<u16, RunningProxy>>,
+}
+
+// Connection-level headers describe one hop, so they are never forwarded or recorded
+const HOP_BY_HOP: [&str; 9] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "content-length",
+];
+
+fn is_hop_by_hop(name: &str) -> bool {
+    HOP_BY_HOP.iter().any(|h| name.eq_ignore_ascii_case(h))
+}
+
+impl ProxyManager {
+    pub async fn start(&self, db: Database, config: ProxyConfig, sink: CaptureSink) -> Result<ProxyInfo> {
+        if !matches!(config.mode.as_str(), "record" | "replay") {
+            return Err(anyhow::anyhow!("Unknown proxy mode: {}", config.mode));
+        }
+        if config.port != 0 && self.proxies.lock().unwrap().contains_key(&config.port) {
+            return Err(anyhow::anyhow!("A proxy is already running on port {}", config.port));
+        }
+        url::Url::parse(&config.target_url).map_err(|e| anyhow::anyhow!("Invalid target URL: {}", e))?;
+
+        // Redirects go back to the app untouched, like any other response
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+
+        let handler_config = config.clone();
+        let handler: http_server::Handler = Arc::new(move |request: IncomingRequest| {
+            let db = db.clone();
+            let client = client.clone();
+            let config = handler_config.clone();
+            let sink = sink.clone();
+            Box::pin(async move {
+                let result = if config.mode == "replay" {
+                    replay(&db, &client, &config, request).await
+                } else {
+                    record(&db, &client, &config, request, &sink).await
+                };
+                result.unwrap_or_else(|e| OutgoingResponse::new(502, format!("Proxy error: {}", e)))
+            })
+        });
+
+        let server = http_server::start(config.port, config.bind_all, handler).await?;
+        let info = ProxyInfo {
+            port: server.port,
+            config: config.clone(),
+        };
+        self.proxies
+            .lock()
+            .unwrap()
+            .insert(server.port, RunningProxy { server, config });
+        Ok(info)
+    }
+
+    pub fn stop(&self, port: u16) -> Result<()> {
+        let running = self
+            .proxies
+            .lock()
+            .unwrap()
+            .remove(&port)
+            .ok_or_else(|| anyhow::anyhow!("No proxy on port {}", port))?;
+        running.server.stop();
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<ProxyInfo> {
+        let mut list: Vec<ProxyInfo> = self
+            .proxies
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(port, running)| ProxyInfo {
+                port: *port,
+                config: running.config.clone(),
+            })
+            .collect();
+        list.sort_by_key(|info| info.port);
+        list
+    }
+}
+
+pub fn target_url(target: &str, path: &str, query: Option<&str>) -> String {
+    let mut url = format!("{}{}", target.trim_end_matches('/'), path);
+    if let Some(query) = query.filter(|q| !q.is_empty()) {
+        url.push('?');
+        url.push_str(query);
+    }
+    url
+}
+
+struct Forwarded {
+    status: u16,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+    duration_ms: u64,
+}
+
+async fn forward(client: &reqwest::Client, config: &ProxyConfig, request: &IncomingRequest) -> Result<Forwarded> {
+    let method = reqwest::Method::from_bytes(request.method.as_bytes())?;
+    let mut builder = client.request(method, target_url(&config.target_url, &request.path, request.query.as_deref()));
+    for (key, value) in &request.headers {
+        // reqwest sets Host from the target URL
+        if !is_hop_by_hop(key) && !key.eq_ignore_ascii_case("host") {
+            builder = builder.header(key, value);
+        }
+    }
+
+    let started = Instant::now();
+    let response = builder.body(request.body.clone()).send().await?;
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter(|(key, _)| !is_hop_by_hop(key.as_str()))
+        .map(|(key, value)| (key.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+        .collect();
+    let body = response.bytes().await?.to_vec();
+
+    Ok(Forwarded {
+        status,
+        headers,
+        body,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+async fn record(
+    db: &Database,
+    client: &reqwest::Client,
+    config: &ProxyConfig,
+    request: IncomingRequest,
+    sink: &CaptureSink,
+) -> Result<OutgoingResponse> {
+    let forwarded = forward(client, config, &request).await?;
+
+    let existing = db
+        .find_proxy_capture(&config.collection_id, &request.method, &request.path, request.query.as_deref())
+        .await?;
+    let (id, request_id) = match existing {
+        Some(capture) => (capture.id, capture.request_id),
+        None => {
+            let saved = save_as_request(db, config, &request).await?;
+            (Uuid::new_v4().to_string(), saved.id)
+        }
+    };
+
+    let request_headers: HashMap<&String, &String> = request
+        .headers
+        .iter()
+        .filter(|(key, _)| !is_hop_by_hop(key))
+        .collect();
+    let capture = ProxyCapture {
+        id,
+        collection_id: config.collection_id.clone(),
+        request_id,
+        method: request.method.clone(),
+        path: request.path.clone(),
+        query: request.query.clone(),
+        request_headers: serde_json::to_string(&request_headers)?,
+        request_body: String::from_utf8_lossy(&request.body).to_string(),
+        status: forwarded.status,
+        response_headers: serde_json::to_string(&forwarded.headers)?,
+        response_body_base64: general_purpose::STANDARD.encode(&forwarded.body),
+        duration_ms: forwarded.duration_ms,
+        captured_at: Utc::now(),
+    };
+    db.save_proxy_capture(&capture).await?;
+    sink(capture);
+
+    Ok(OutgoingResponse {
+        status: forwarded.status,
+        headers: forwarded.headers,
+        body: forwarded.body,
+    })
+}
+
+// 🎓 TEACHING: The first time a call is seen it becomes a saved request, so the
+// recorded traffic can be browsed, edited, and re-sent like anything else.
+async fn save_as_request(db: &Database, config: &ProxyConfig, request: &IncomingRequest) -> Result<Request> {
+    let saved = db
+        .create_request(
+            config.collection_id.clone(),
+            format!("{} {}", request.method, request.path),
+            request.method.clone(),
+            target_url(&config.target_url, &request.path, None),
+        )
+        .await?;
+
+    let params: Vec<QueryParam> = url::form_urlencoded::parse(request.query.as_deref().unwrap_or("").as_bytes())
+        .map(|(key, value)| QueryParam {
+            key: key.to_string(),
+            value: value.to_string(),
+            enabled: true,
+        })
+        .collect();
+    let headers: HashMap<&String, &String> = request
+        .headers
+        .iter()
+        .filter(|(key, _)| !is_hop_by_hop(key) && !key.eq_ignore_ascii_case("host"))
+        .collect();
+    let content_type = request
+        .headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.to_lowercase())
+        .unwrap_or_default();
+    let (body_type, body_str) = if request.body.is_empty() {
+        ("none", None)
+    } else if content_type.contains("json") {
+        ("json", Some(String::from_utf8_lossy(&request.body).to_string()))
+    } else {
+        ("raw", Some(String::from_utf8_lossy(&request.body).to_string()))
+    };
+
+    let updated = Request {
+        params: serde_json::to_string(&params)?,
+        headers: serde_json::to_string(&headers)?,
+        body_type: body_type.to_string(),
+        body_str,
+        ..saved
+    };
+    db.update_request(updated, false).await
+}
+
+// Exact method + path + query first, then the same call with any query
+pub fn find_recording<'a>(captures: &'a [ProxyCapture], request: &IncomingRequest) -> Option<&'a ProxyCapture> {
+    let same_call = |c: &&ProxyCapture| c.method.eq_ignore_ascii_case(&request.method) && c.path == request.path;
+    captures
+        .iter()
+        .filter(same_call)
+        .find(|c| c.query.as_deref().unwrap_or("") == request.query.as_deref().unwrap_or(""))
+        .or_else(|| captures.iter().find(same_call))
+}
+
+async fn replay(
+    db: &Database,
+    client: &reqwest::Client,
+    config: &ProxyConfig,
+    request: IncomingRequest,
+) -> Result<OutgoingResponse> {
+    let captures = db.get_proxy_captures(&config.collection_id).await?;
+    let Some(capture) = find_recording(&captures, &request) else {
+        if config.replay_passthrough {
+            let forwarded = forward(client, config, &request).await?;
+            return Ok(OutgoingResponse {
+                status: forwarded.status,
+                headers: forwarded.headers,
+                body: forwarded.body,
+            });
+        }
+        let body = serde_json::json!({ "error": format!("No recording for {} {}", request.method, request.path) });
+        let mut response = OutgoingResponse::new(404, body.to_string());
+        response.headers.insert("Content-Type".to_string(), "application/json".to_string());
+        return Ok(response);
+    };
+
+    Ok(OutgoingResponse {
+        status: capture.status,
+        headers: serde_json::from_str(&capture.response_headers)?,
+        body: general_purpose::STANDARD.decode(&capture.response_body_base64)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(method: &str, path: &str, query: Option<&str>, status: u16) -> ProxyCapture {
+        ProxyCapture {
+            id: Uuid::new_v4().to_string(),
+            collection_id: "c".to_string(),
+            request_id: "r".to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query.map(str::to_string),
+            request_headers: "{}".to_string(),
+            request_body: String::new(),
+            status,
+            response_headers: "{}".to_string(),
+            response_body_base64: String::new(),
+            duration_ms: 0,
+            captured_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_target_url() {
+        assert_eq!(target_url("https://api.example.com/", "/users", Some("page=2")), "https://api.example.com/users?page=2");
+        assert_eq!(target_url("https://api.example.com/v1", "/users", Some("")), "https://api.example.com/v1/users");
+    }
+
+    #[test]
+    fn test_find_recording_prefers_exact_query() {
+        let captures = vec![
+            capture("GET", "/users", Some("page=1"), 200),
+            capture("GET", "/users", Some("page=2"), 201),
+            capture("POST", "/users", None, 202),
+        ];
+        let request = |method: &str, query: Option<&str>| IncomingRequest {
+            method: method.to_string(),
+            path: "/users".to_string(),
+            query: query.map(str::to_string),
+            ..Default::default()
+        };
+
+        assert_eq!(find_recording(&captures, &request("GET", Some("page=2"))).unwrap().status, 201);
+        assert_eq!(find_recording(&captures, &request("GET", Some("page=9"))).unwrap().status, 200);
+        assert_eq!(find_recording(&captures, &request("POST", None)).unwrap().status, 202);
+        assert!(find_recording(&captures, &request("DELETE", None)).is_none());
+    }
+}
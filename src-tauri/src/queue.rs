@@ -0,0 +1,300 @@
+// 🎓 TEACHING: Request queue
+// Instead of clicking send dozens of times against a slow or rate-limited service, sends can
+// be queued and left to go out one after another. Each queued send has a priority (higher
+// goes first, ties in the order they were queued), and the queue can be paused and resumed:
+// pausing lets the send in flight finish and starts no new one.
+//
+// Queued sends go through send_api_request like any other, so they get the same settings,
+// history, progress events (with the queue entry's id as the execution id) and rate limits.
+// The frontend follows along with two events:
+//   queue-status   the whole queue, after anything changes
+//   queue-result   a send finished, with its response or error
+
+use crate::error::AppError;
+use crate::{ApiRequest, ApiResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+// Finished sends kept for the queue view, newest first
+pub const MAX_FINISHED: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QueueEntry {
+    pub id: String,
+    pub label: String, // Defaults to "METHOD url"
+    pub priority: i32,
+    pub state: EntryState,
+    pub enqueued_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct QueueStatus {
+    pub paused: bool,
+    pub running: Option<QueueEntry>,
+    pub queued: Vec<QueueEntry>, // In the order they'll be sent
+    pub finished: Vec<QueueEntry>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QueueResult {
+    pub entry: QueueEntry,
+    pub response: Option<ApiResponse>,
+    pub error: Option<AppError>,
+}
+
+struct Waiting {
+    entry: QueueEntry,
+    sequence: u64,
+    request: ApiRequest,
+}
+
+#[derive(Default)]
+struct State {
+    paused: bool,
+    next_sequence: u64,
+    waiting: Vec<Waiting>,
+    running: Option<QueueEntry>,
+    finished: VecDeque<QueueEntry>,
+}
+
+impl State {
+    fn retire(&mut self, entry: QueueEntry) {
+        self.finished.push_front(entry);
+        self.finished.truncate(MAX_FINISHED);
+    }
+}
+
+// 🎓 TEACHING: Managed as Tauri state; the worker started at setup takes sends from it
+#[derive(Default)]
+pub struct RequestQueue {
+    state: Mutex<State>,
+    wakeup: Notify,
+}
+
+impl RequestQueue {
+    pub fn enqueue(&self, request: ApiRequest, label: Option<String>, priority: i32) -> QueueEntry {
+        let label = label
+            .filter(|label| !label.trim().is_empty())
+            .unwrap_or_else(|| format!("{} {}", request.method.to_uppercase(), request.url));
+        let entry = QueueEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            label,
+            priority,
+            state: EntryState::Queued,
+            enqueued_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            status: None,
+            error: None,
+        };
+        let mut state = self.state.lock().unwrap();
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        state.waiting.push(Waiting { entry: entry.clone(), sequence, request });
+        drop(state);
+        self.wakeup.notify_one();
+        entry
+    }
+
+    pub fn status(&self) -> QueueStatus {
+        let state = self.state.lock().unwrap();
+        let mut waiting: Vec<&Waiting> = state.waiting.iter().collect();
+        waiting.sort_by_key(|waiting| (std::cmp::Reverse(waiting.entry.priority), waiting.sequence));
+        QueueStatus {
+            paused: state.paused,
+            running: state.running.clone(),
+            queued: waiting.into_iter().map(|waiting| waiting.entry.clone()).collect(),
+            finished: state.finished.iter().cloned().collect(),
+        }
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.state.lock().unwrap().paused = paused;
+        if !paused {
+            self.wakeup.notify_one();
+        }
+    }
+
+    // Only sends still waiting can be moved; returns false for any other id
+    pub fn set_priority(&self, id: &str, priority: i32) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.waiting.iter_mut().find(|waiting| waiting.entry.id == id) {
+            Some(waiting) => {
+                waiting.entry.priority = priority;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Takes a waiting send off the queue; one already in flight runs to the end
+    pub fn cancel(&self, id: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let Some(index) = state.waiting.iter().position(|waiting| waiting.entry.id == id) else {
+            return false;
+        };
+        let mut entry = state.waiting.remove(index).entry;
+        entry.state = EntryState::Cancelled;
+        entry.finished_at = Some(Utc::now());
+        state.retire(entry);
+        true
+    }
+
+    // Cancels everything waiting and forgets the finished sends; returns how many were cancelled
+    pub fn clear(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        state.finished.clear();
+        std::mem::take(&mut state.waiting).len()
+    }
+
+    // The next send to make, unless paused or one is already in flight
+    fn start_next(&self) -> Option<(QueueEntry, ApiRequest)> {
+        let mut state = self.state.lock().unwrap();
+        if state.paused || state.running.is_some() {
+            return None;
+        }
+        let index = state
+            .waiting
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, waiting)| (std::cmp::Reverse(waiting.entry.priority), waiting.sequence))
+            .map(|(index, _)| index)?;
+        let Waiting { mut entry, request, .. } = state.waiting.remove(index);
+        entry.state = EntryState::Running;
+        entry.started_at = Some(Utc::now());
+        state.running = Some(entry.clone());
+        Some((entry, request))
+    }
+
+    fn finish(&self, mut entry: QueueEntry, result: &Result<ApiResponse, AppError>) -> QueueEntry {
+        entry.finished_at = Some(Utc::now());
+        match result {
+            Ok(response) => {
+                entry.state = EntryState::Succeeded;
+                entry.status = Some(response.status);
+            }
+            Err(e) => {
+                entry.state = EntryState::Failed;
+                entry.error = Some(e.message.clone());
+            }
+        }
+        let mut state = self.state.lock().unwrap();
+        state.running = None;
+        state.retire(entry.clone());
+        entry
+    }
+}
+
+pub fn emit_status(app: &AppHandle) {
+    let _ = app.emit("queue-status", app.state::<RequestQueue>().status());
+}
+
+// Start the worker that sends queued requests; called once from the app's setup
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let queue = app.state::<RequestQueue>();
+            let Some((entry, request)) = queue.start_next() else {
+                queue.wakeup.notified().await;
+                continue;
+            };
+            emit_status(&app);
+            let result = crate::send_api_request(
+                request,
+                None,
+                Some(entry.id.clone()),
+                app.clone(),
+                app.state(),
+                app.state(),
+                app.state(),
+                app.state(),
+            )
+            .await;
+            let entry = queue.finish(entry, &result);
+            let (response, error) = match result {
+                Ok(response) => (Some(response), None),
+                Err(e) => (None, Some(e)),
+            };
+            let _ = app.emit("queue-result", QueueResult { entry, response, error });
+            emit_status(&app);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str) -> ApiRequest {
+        ApiRequest {
+            method: "get".to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn labels(entries: &[QueueEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let queue = RequestQueue::default();
+        let first = queue.enqueue(request("http://a.test/1"), None, 0);
+        queue.enqueue(request("http://a.test/2"), Some("urgent".to_string()), 5);
+        queue.enqueue(request("http://a.test/3"), None, 0);
+        assert_eq!(first.label, "GET http://a.test/1");
+        assert_eq!(labels(&queue.status().queued), ["urgent", "GET http://a.test/1", "GET http://a.test/3"]);
+
+        assert!(queue.set_priority(&first.id, -1));
+        let (running, _) = queue.start_next().unwrap();
+        assert_eq!(running.label, "urgent");
+        // One at a time
+        assert!(queue.start_next().is_none());
+
+        let finished = queue.finish(running, &Err(AppError::validation("no")));
+        assert_eq!((finished.state, finished.error.as_deref()), (EntryState::Failed, Some("no")));
+        assert_eq!(queue.start_next().unwrap().0.label, "GET http://a.test/3");
+        assert_eq!(labels(&queue.status().finished), ["urgent"]);
+    }
+
+    #[test]
+    fn test_pause_and_cancel() {
+        let queue = RequestQueue::default();
+        let entry = queue.enqueue(request("http://a.test/"), None, 0);
+        queue.set_paused(true);
+        assert!(queue.start_next().is_none());
+        assert!(queue.status().paused);
+
+        assert!(queue.cancel(&entry.id));
+        assert!(!queue.cancel(&entry.id));
+        assert!(!queue.set_priority(&entry.id, 1));
+        let status = queue.status();
+        assert!(status.queued.is_empty());
+        assert_eq!(status.finished[0].state, EntryState::Cancelled);
+
+        queue.enqueue(request("http://a.test/"), None, 0);
+        queue.set_paused(false);
+        assert_eq!(queue.clear(), 1);
+        assert!(queue.start_next().is_none());
+        assert!(queue.status().finished.is_empty());
+    }
+}
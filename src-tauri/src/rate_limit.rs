@@ -0,0 +1,169 @@
+// 🎓 TEACHING: Outbound rate limits
+// Rules per host pattern (the same patterns as cache policies: api.example.com, *.example.com
+// or *) that slow sends down instead of failing them, so a collection run can't hammer a
+// production API or get the app's IP banned by a WAF. A rule can cap:
+//   - requests per second, with a token bucket: it holds up to one second's worth of sends,
+//     so a short burst goes straight out and a steady stream is spaced evenly
+//   - concurrency: how many sends to matching hosts may be in flight at once
+// A rule's budget is shared by every host it matches and every send in the app (the editor,
+// collection runs, monitors, workflows). Answers from the response cache don't count. When
+// several rules match, the most specific pattern wins, as with cache policies.
+
+use crate::cache_policy;
+use crate::database::RateLimit;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+pub fn validate(limit: &RateLimit) -> Result<()> {
+    if limit.requests_per_second.is_none() && limit.max_concurrent.is_none() {
+        return Err(anyhow!("A rate limit needs requests per second, a concurrency cap or both"));
+    }
+    if limit.requests_per_second.is_some_and(|rate| !rate.is_finite() || rate <= 0.0) {
+        return Err(anyhow!("Requests per second must be more than 0"));
+    }
+    if limit.max_concurrent == Some(0) {
+        return Err(anyhow!("The concurrency cap must be at least 1"));
+    }
+    Ok(())
+}
+
+pub fn select<'a>(limits: &'a [RateLimit], host: &str) -> Option<&'a RateLimit> {
+    limits
+        .iter()
+        .filter(|limit| limit.enabled && cache_policy::host_matches(&limit.host_pattern, host))
+        .max_by_key(|limit| cache_policy::specificity(&limit.host_pattern))
+}
+
+struct Bucket {
+    tokens: f64, // Below 0 when sends are queued up waiting for tokens
+    refilled: Instant,
+}
+
+// 🎓 TEACHING: Shared by every clone of the database, like the provider cache, so all sends
+// draw from the same buckets. Buckets and slots are kept by rule id.
+#[derive(Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+    slots: Mutex<HashMap<String, (u32, Arc<Semaphore>)>>,
+}
+
+// Held for the whole send; its concurrency slot frees up when it's dropped
+pub struct SendPermit {
+    _slot: Option<OwnedSemaphorePermit>,
+    pub waited: Duration,
+}
+
+impl RateLimiter {
+    pub async fn acquire(&self, limit: &RateLimit) -> SendPermit {
+        let started = Instant::now();
+        let slot = match limit.max_concurrent {
+            Some(max) => self.semaphore(&limit.id, max).acquire_owned().await.ok(),
+            None => None,
+        };
+        if let Some(rate) = limit.requests_per_second {
+            let wait = self.reserve(&limit.id, rate, Instant::now());
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+        }
+        SendPermit { _slot: slot, waited: started.elapsed() }
+    }
+
+    // A changed cap gets a fresh semaphore; sends holding the old one finish undisturbed
+    fn semaphore(&self, id: &str, max: u32) -> Arc<Semaphore> {
+        let mut slots = self.slots.lock().unwrap();
+        match slots.get(id) {
+            Some((size, semaphore)) if *size == max => semaphore.clone(),
+            _ => {
+                let semaphore = Arc::new(Semaphore::new(max as usize));
+                slots.insert(id.to_string(), (max, semaphore.clone()));
+                semaphore
+            }
+        }
+    }
+
+    // Takes a token even when there's none left; the debt says how long this send must wait,
+    // and queues every later send behind it
+    fn reserve(&self, id: &str, rate: f64, now: Instant) -> Duration {
+        let capacity = rate.max(1.0);
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(id.to_string()).or_insert(Bucket { tokens: capacity, refilled: now });
+        let elapsed = now.saturating_duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity) - 1.0;
+        bucket.refilled = now;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn limit(host_pattern: &str, requests_per_second: Option<f64>, max_concurrent: Option<u32>) -> RateLimit {
+        RateLimit {
+            id: host_pattern.to_string(),
+            host_pattern: host_pattern.to_string(),
+            enabled: true,
+            requests_per_second,
+            max_concurrent,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_validate_and_select() {
+        assert!(validate(&limit("*", Some(0.5), None)).is_ok());
+        assert!(validate(&limit("*", None, None)).is_err());
+        assert!(validate(&limit("*", Some(0.0), None)).is_err());
+        assert!(validate(&limit("*", Some(f64::NAN), None)).is_err());
+        assert!(validate(&limit("*", None, Some(0))).is_err());
+
+        let mut off = limit("api.example.com", Some(1.0), None);
+        off.enabled = false;
+        let limits = vec![limit("*", Some(50.0), None), limit("*.example.com", Some(5.0), None), off];
+        assert_eq!(select(&limits, "api.example.com").unwrap().host_pattern, "*.example.com");
+        assert_eq!(select(&limits, "other.org").unwrap().host_pattern, "*");
+        assert!(select(&limits[1..], "other.org").is_none());
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // Two per second: a burst of two, then sends are spaced half a second apart
+        assert_eq!(limiter.reserve("a", 2.0, at(0)), Duration::ZERO);
+        assert_eq!(limiter.reserve("a", 2.0, at(0)), Duration::ZERO);
+        assert_eq!(limiter.reserve("a", 2.0, at(0)), Duration::from_millis(500));
+        assert_eq!(limiter.reserve("a", 2.0, at(0)), Duration::from_millis(1000));
+        // Once the queue has drained and the bucket refilled, bursts are allowed again
+        assert_eq!(limiter.reserve("a", 2.0, at(3000)), Duration::ZERO);
+        assert_eq!(limiter.reserve("b", 2.0, at(3000)), Duration::ZERO);
+
+        // Slower than one a second still lets a single send through at once
+        assert_eq!(limiter.reserve("slow", 0.5, at(0)), Duration::ZERO);
+        assert_eq!(limiter.reserve("slow", 0.5, at(1000)), Duration::from_millis(1000));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_cap() {
+        let limiter = RateLimiter::default();
+        let one_at_a_time = limit("api.example.com", None, Some(1));
+        let first = limiter.acquire(&one_at_a_time).await;
+        let second = tokio::time::timeout(Duration::from_millis(50), limiter.acquire(&one_at_a_time)).await;
+        assert!(second.is_err());
+        drop(first);
+        let second = tokio::time::timeout(Duration::from_millis(50), limiter.acquire(&one_at_a_time)).await;
+        assert!(second.is_ok());
+    }
+}
@@ -0,0 +1,287 @@
+// 🎓 TEACHING: Raw TCP/TLS sockets
+// Sometimes HTTP is the wrong tool: text protocols like Redis or SMTP, or a server so
+// broken that reqwest refuses to talk to it. This mode writes exactly the bytes the
+// user typed to host:port (optionally over TLS) and shows whatever comes back.
+
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RawSocketRequest {
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub tls: bool,
+    #[serde(default)]
+    pub insecure: bool, // Skip TLS certificate verification (self-signed or broken servers)
+    pub payload: String,
+    pub encoding: Option<String>, // "text" (default), "escaped" (\r\n, \xHH, ...), "hex", or "base64"
+    pub connect_timeout_ms: Option<u64>, // Defaults to 10s
+    pub read_timeout_ms: Option<u64>,    // Stop reading after this long without data (defaults to 2s)
+    pub max_bytes: Option<usize>,        // Stop reading after this many bytes (defaults to 1 MiB)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RawSocketResponse {
+    pub bytes_sent: usize,
+    pub text: String, // The reply as UTF-8 (invalid sequences replaced)
+    pub hex: String,
+    pub base64: String,
+    pub closed_by_peer: bool, // The server closed the connection
+    pub timed_out: bool,      // Reading stopped because the server went quiet
+    pub truncated: bool,      // Reading stopped at max_bytes
+    pub duration_ms: u64,
+}
+
+// 🎓 TEACHING: Turn what the user typed into the bytes to send
+pub fn decode_payload(payload: &str, encoding: Option<&str>) -> Result<Vec<u8>> {
+    match encoding.unwrap_or("text") {
+        "text" => Ok(payload.as_bytes().to_vec()),
+        "escaped" => unescape(payload),
+        "hex" => {
+            let compact: String = payload.chars().filter(|c| !c.is_whitespace()).collect();
+            hex::decode(compact).map_err(|e| anyhow::anyhow!("Invalid hex payload: {}", e))
+        }
+        "base64" => general_purpose::STANDARD
+            .decode(payload.trim())
+            .map_err(|e| anyhow::anyhow!("Invalid base64 payload: {}", e)),
+        other => Err(anyhow::anyhow!("Unsupported payload encoding: {}", other)),
+    }
+}
+
+// C-style escapes, so `PING\r\n` can be typed on one line
+fn unescape(input: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('r') => bytes.push(b'\r'),
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('0') => bytes.push(0),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == 2)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid escape \\x{}", digits))?;
+                bytes.push(byte);
+            }
+            Some(other) => return Err(anyhow::anyhow!("Unknown escape \\{}", other)),
+            None => return Err(anyhow::anyhow!("Payload ends with a lone backslash")),
+        }
+    }
+    Ok(bytes)
+}
+
+pub async fn send(request: &RawSocketRequest) -> Result<RawSocketResponse> {
+    let payload = decode_payload(&request.payload, request.encoding.as_deref())?;
+    let connect_timeout = Duration::from_millis(request.connect_timeout_ms.unwrap_or(10_000));
+    let read_timeout = Duration::from_millis(request.read_timeout_ms.unwrap_or(2_000));
+    let max_bytes = request.max_bytes.unwrap_or(1024 * 1024);
+    let started = Instant::now();
+
+    let stream = tokio::time::timeout(connect_timeout, TcpStream::connect((request.host.as_str(), request.port)))
+        .await
+        .map_err(|_| anyhow::anyhow!("Timed out connecting to {}:{}", request.host, request.port))?
+        .map_err(|e| anyhow::anyhow!("Failed to connect to {}:{}: {}", request.host, request.port, e))?;
+
+    let exchange = if request.tls {
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(tls_config(request.insecure)?));
+        let server_name = rustls::pki_types::ServerName::try_from(request.host.clone())
+            .map_err(|_| anyhow::anyhow!("Invalid TLS server name: {}", request.host))?;
+        let stream = tokio::time::timeout(connect_timeout, connector.connect(server_name, stream))
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out during the TLS handshake"))?
+            .map_err(|e| anyhow::anyhow!("TLS handshake failed: {}", e))?;
+        exchange(stream, &payload, read_timeout, max_bytes).await?
+    } else {
+        exchange(stream, &payload, read_timeout, max_bytes).await?
+    };
+
+    Ok(RawSocketResponse {
+        bytes_sent: payload.len(),
+        text: String::from_utf8_lossy(&exchange.received).to_string(),
+        hex: hex::encode(&exchange.received),
+        base64: general_purpose::STANDARD.encode(&exchange.received),
+        closed_by_peer: exchange.closed_by_peer,
+        timed_out: exchange.timed_out,
+        truncated: exchange.truncated,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+struct Exchange {
+    received: Vec<u8>,
+    closed_by_peer: bool,
+    timed_out: bool,
+    truncated: bool,
+}
+
+// 🎓 TEACHING: There is no "end of response" in a raw socket, so read until the
+// server closes, goes quiet for `read_timeout`, or we have `max_bytes`.
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    payload: &[u8],
+    read_timeout: Duration,
+    max_bytes: usize,
+) -> Result<Exchange> {
+    stream.write_all(payload).await?;
+    stream.flush().await?;
+
+    let mut result = Exchange {
+        received: Vec::new(),
+        closed_by_peer: false,
+        timed_out: false,
+        truncated: false,
+    };
+    let mut buf = [0u8; 8192];
+    loop {
+        match tokio::time::timeout(read_timeout, stream.read(&mut buf)).await {
+            Err(_) => {
+                result.timed_out = true;
+                break;
+            }
+            Ok(Ok(0)) => {
+                result.closed_by_peer = true;
+                break;
+            }
+            Ok(Ok(n)) => {
+                let room = max_bytes - result.received.len();
+                result.received.extend_from_slice(&buf[..n.min(room)]);
+                if n >= room {
+                    result.truncated = true;
+                    break;
+                }
+            }
+            // Servers that reset the connection still sent a reply worth showing
+            Ok(Err(_)) if !result.received.is_empty() => {
+                result.closed_by_peer = true;
+                break;
+            }
+            Ok(Err(e)) => return Err(anyhow::anyhow!("Read failed: {}", e)),
+        }
+    }
+    Ok(result)
+}
+
+fn tls_config(insecure: bool) -> Result<rustls::ClientConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+
+    if insecure {
+        return Ok(builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+            .with_no_client_auth());
+    }
+
+    Ok(builder.with_root_certificates(native_roots()).with_no_client_auth())
+}
+
+// The OS trust store; certificates rustls can't use are skipped
+pub fn native_roots() -> rustls::RootCertStore {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in rustls_native_certs::load_native_certs().certs {
+        let _ = roots.add(cert);
+    }
+    roots
+}
+
+// Accepts any certificate, for the `insecure` option only
+#[derive(Debug)]
+struct NoVerification(Arc<rustls::crypto::CryptoProvider>);
+
+impl rustls::client::danger::ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_decode_payload() {
+        assert_eq!(decode_payload("PING\\r\\n", Some("escaped")).unwrap(), b"PING\r\n");
+        assert_eq!(decode_payload("\\x00\\xff", Some("escaped")).unwrap(), vec![0, 255]);
+        assert_eq!(decode_payload("PING\\r\\n", None).unwrap(), b"PING\\r\\n");
+        assert_eq!(decode_payload("de ad\nbe ef", Some("hex")).unwrap(), vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(decode_payload("aGk=", Some("base64")).unwrap(), b"hi");
+        assert!(decode_payload("\\q", Some("escaped")).is_err());
+        assert!(decode_payload("\\x4", Some("escaped")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_reads_until_close() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 64];
+            let n = socket.read(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"PING\r\n");
+            socket.write_all(b"+PONG\r\n").await.unwrap();
+        });
+
+        let response = send(&RawSocketRequest {
+            host: "127.0.0.1".to_string(),
+            port,
+            payload: "PING\\r\\n".to_string(),
+            encoding: Some("escaped".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(response.bytes_sent, 6);
+        assert_eq!(response.text, "+PONG\r\n");
+        assert_eq!(response.hex, "2b504f4e470d0a");
+        assert!(response.closed_by_peer);
+        assert!(!response.timed_out);
+    }
+}
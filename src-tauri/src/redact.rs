@@ -0,0 +1,157 @@
+// 🎓 TEACHING: Secret redaction
+// Anything that leaves the app (exports, error messages, stored history) goes through here,
+// so secret variable values and credentials show up as `*****` instead of in plain text.
+
+use std::collections::HashMap;
+
+pub const MASK: &str = "*****";
+
+// Headers that carry credentials no matter what their value looks like
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "x-auth-token",
+    "x-amz-security-token",
+];
+
+// auth_data fields that hold credentials (structural fields like "in" or "username" are kept)
+const SENSITIVE_AUTH_FIELDS: &[&str] = &[
+    "password",
+    "token",
+    "value",
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "client_secret",
+    "secret_key",
+    "session_token",
+    "private_key",
+    "consumer_secret",
+    "token_secret",
+];
+
+// 🎓 TEACHING: Replaces known secret values wherever they appear in a string
+#[derive(Debug, Default, Clone)]
+pub struct Redactor {
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new(secrets: impl IntoIterator<Item = String>) -> Self {
+        // Secrets also get masked in their percent-encoded form, as they appear in query strings
+        let mut secrets: Vec<String> = secrets
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .flat_map(|s| {
+                let encoded: String = url::form_urlencoded::byte_serialize(s.as_bytes()).collect();
+                [s, encoded]
+            })
+            .collect();
+        // Longest first, so a secret containing another secret is masked whole
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        secrets.dedup();
+        Self { secrets }
+    }
+
+    pub fn redact(&self, input: &str) -> String {
+        self.secrets
+            .iter()
+            .fold(input.to_string(), |result, secret| result.replace(secret.as_str(), MASK))
+    }
+
+    pub fn redact_headers(&self, headers: &HashMap<String, String>) -> HashMap<String, String> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if is_sensitive_header(name) {
+                    MASK.to_string()
+                } else {
+                    self.redact(value)
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    // Headers stored as a JSON object string (the `requests.headers` column)
+    pub fn redact_headers_json(&self, headers_json: &str) -> String {
+        match serde_json::from_str::<HashMap<String, String>>(headers_json) {
+            Ok(headers) => serde_json::to_string(&self.redact_headers(&headers))
+                .unwrap_or_else(|_| self.redact(headers_json)),
+            Err(_) => self.redact(headers_json),
+        }
+    }
+
+    // 🎓 TEACHING: auth_data is JSON, so mask credential fields but keep its shape,
+    // which lets an importer see which auth type was used and fill in their own values.
+    pub fn redact_auth_data(&self, auth_data: &str) -> String {
+        match serde_json::from_str::<serde_json::Value>(auth_data) {
+            Ok(mut json) => {
+                mask_auth_fields(&mut json);
+                self.redact(&json.to_string())
+            }
+            Err(_) => MASK.to_string(),
+        }
+    }
+}
+
+pub fn is_sensitive_header(name: &str) -> bool {
+    SENSITIVE_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(name))
+}
+
+fn mask_auth_fields(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if SENSITIVE_AUTH_FIELDS.contains(&key.to_lowercase().as_str()) && field.is_string() {
+                    *field = serde_json::Value::String(MASK.to_string());
+                } else {
+                    mask_auth_fields(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(mask_auth_fields),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_secret_values() {
+        let redactor = Redactor::new(vec!["abc".to_string(), "abcdef".to_string(), String::new()]);
+        assert_eq!(redactor.redact("key=abcdef&other=abc"), "key=*****&other=*****");
+        assert_eq!(redactor.redact("nothing here"), "nothing here");
+    }
+
+    #[test]
+    fn test_redact_headers() {
+        let redactor = Redactor::new(vec!["s3cret".to_string()]);
+        let mut headers = HashMap::new();
+        headers.insert("Authorization".to_string(), "Bearer xyz".to_string());
+        headers.insert("X-Trace".to_string(), "id-s3cret".to_string());
+        headers.insert("Accept".to_string(), "application/json".to_string());
+
+        let redacted = redactor.redact_headers(&headers);
+        assert_eq!(redacted["Authorization"], MASK);
+        assert_eq!(redacted["X-Trace"], "id-*****");
+        assert_eq!(redacted["Accept"], "application/json");
+    }
+
+    #[test]
+    fn test_redact_auth_data_keeps_shape() {
+        let redactor = Redactor::default();
+        let redacted = redactor.redact_auth_data(r#"{"key":"X-API-Key","value":"abc123","in":"header"}"#);
+        let json: serde_json::Value = serde_json::from_str(&redacted).unwrap();
+        assert_eq!(json["key"], "X-API-Key");
+        assert_eq!(json["value"], MASK);
+        assert_eq!(json["in"], "header");
+
+        assert_eq!(redactor.redact_auth_data("not json"), MASK);
+    }
+}
@@ -0,0 +1,263 @@
+// 🎓 TEACHING: Run reports
+// A finished collection run can be exported as JUnit XML, which CI systems (Jenkins,
+// GitLab, GitHub Actions reporters) understand, or as a standalone HTML page to share.
+// In the JUnit report each sent request is a test suite and each of its tests a test
+// case, the same layout Newman uses; a request that couldn't be sent is an error.
+
+use crate::database::CollectionRun;
+use crate::runner::RunRequestResult;
+use anyhow::Result;
+use std::fmt::Write;
+
+pub fn export(run: &CollectionRun, collection_name: &str, format: &str) -> Result<String> {
+    let results: Vec<RunRequestResult> = serde_json::from_str(&run.results)?;
+    match format {
+        "junit" => Ok(junit(run, collection_name, &results)),
+        "html" => Ok(html(run, collection_name, &results)),
+        other => Err(anyhow::anyhow!("Unsupported report format: {}", other)),
+    }
+}
+
+// Good for both XML and HTML text and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+// "Create user" or "Create user (iteration 2)" when the run had a data file
+fn display_name(run: &CollectionRun, result: &RunRequestResult) -> String {
+    if run.iterations > 1 {
+        format!("{} (iteration {})", result.name, result.iteration + 1)
+    } else {
+        result.name.clone()
+    }
+}
+
+fn junit(run: &CollectionRun, collection_name: &str, results: &[RunRequestResult]) -> String {
+    let cases = |result: &RunRequestResult| result.tests.len().max(1);
+    let tests: usize = results.iter().map(cases).sum();
+    let failures: usize = results.iter().map(|r| r.tests.iter().filter(|t| !t.passed).count()).sum();
+    let errors = results.iter().filter(|r| r.error.is_some()).count();
+    let total_ms: u64 = results.iter().map(|r| r.duration_ms).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\">",
+        escape(collection_name),
+        tests,
+        failures,
+        errors,
+        seconds(total_ms)
+    );
+    for result in results {
+        let name = display_name(run, result);
+        let suite_failures = result.tests.iter().filter(|t| !t.passed).count();
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\" timestamp=\"{}\">",
+            escape(&name),
+            cases(result),
+            suite_failures,
+            usize::from(result.error.is_some()),
+            seconds(result.duration_ms),
+            run.started_at.to_rfc3339()
+        );
+        let classname = escape(&format!("{}.{}", collection_name, result.name));
+
+        if let Some(error) = &result.error {
+            let _ = writeln!(
+                xml,
+                "    <testcase name=\"{} {}\" classname=\"{}\" time=\"{}\">",
+                escape(&result.method),
+                escape(&name),
+                classname,
+                seconds(result.duration_ms)
+            );
+            let _ = writeln!(xml, "      <error message=\"{}\" type=\"RequestError\"/>", escape(error));
+            xml.push_str("    </testcase>\n");
+        } else if result.tests.is_empty() {
+            let status = result.status.map(|s| s.to_string()).unwrap_or_default();
+            let _ = writeln!(
+                xml,
+                "    <testcase name=\"{} {} responded {}\" classname=\"{}\" time=\"{}\"/>",
+                escape(&result.method),
+                escape(&name),
+                status,
+                classname,
+                seconds(result.duration_ms)
+            );
+        }
+        for test in &result.tests {
+            let _ = write!(xml, "    <testcase name=\"{}\" classname=\"{}\" time=\"0.000\"", escape(&test.name), classname);
+            if test.passed {
+                xml.push_str("/>\n");
+            } else {
+                let message = test.error.as_deref().unwrap_or("Assertion failed");
+                let _ = writeln!(xml, ">\n      <failure message=\"{}\" type=\"AssertionFailure\"/>", escape(message));
+                xml.push_str("    </testcase>\n");
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2rem;color:#222}\
+table{border-collapse:collapse;width:100%}th,td{text-align:left;padding:.4rem .6rem;border-bottom:1px solid #ddd;vertical-align:top}\
+.pass{color:#1a7f37}.fail{color:#cf222e}.muted{color:#666}ul{margin:.2rem 0;padding-left:1.2rem}code{font-size:.9em}";
+
+fn html(run: &CollectionRun, collection_name: &str, results: &[RunRequestResult]) -> String {
+    let duration = (run.finished_at - run.started_at).num_milliseconds().max(0) as u64;
+    let mut page = String::new();
+    let _ = write!(
+        page,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{} - run report</title>\n<style>{}</style>\n</head>\n<body>\n",
+        escape(collection_name),
+        STYLE
+    );
+    let _ = writeln!(page, "<h1>{}</h1>", escape(collection_name));
+    let _ = writeln!(
+        page,
+        "<p class=\"muted\">Started {} &middot; {} s &middot; {} iteration(s)</p>",
+        run.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        seconds(duration),
+        run.iterations
+    );
+    let _ = writeln!(
+        page,
+        "<p><strong class=\"pass\">{} passed</strong> &middot; <strong class=\"fail\">{} failed</strong> &middot; {} of {} requests sent{}</p>",
+        run.passed_requests,
+        run.failed_requests,
+        results.len(),
+        run.total_requests,
+        if run.stopped_early { " (stopped on failure)" } else { "" }
+    );
+
+    page.push_str("<table>\n<tr><th>#</th><th>Request</th><th>Status</th><th>Time</th><th>Result</th></tr>\n");
+    for (position, result) in results.iter().enumerate() {
+        let (class, verdict) = if result.passed { ("pass", "Passed") } else { ("fail", "Failed") };
+        let status = result.status.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+        let _ = write!(
+            page,
+            "<tr><td>{}</td><td><code>{}</code> {}</td><td>{}</td><td>{} ms</td><td><span class=\"{}\">{}</span>",
+            position + 1,
+            escape(&result.method),
+            escape(&display_name(run, result)),
+            status,
+            result.duration_ms,
+            class,
+            verdict
+        );
+        if result.attempts.len() > 1 {
+            let _ = write!(page, " <span class=\"muted\">after {} attempts</span>", result.attempts.len());
+        }
+        if let Some(error) = &result.error {
+            let _ = write!(page, "<div class=\"fail\">{}</div>", escape(error));
+        }
+        if !result.tests.is_empty() {
+            page.push_str("<ul>");
+            for test in &result.tests {
+                let (class, mark) = if test.passed { ("pass", "&#10003;") } else { ("fail", "&#10007;") };
+                let _ = write!(page, "<li class=\"{}\">{} {}", class, mark, escape(&test.name));
+                if let Some(error) = &test.error {
+                    let _ = write!(page, ": {}", escape(error));
+                }
+                page.push_str("</li>");
+            }
+            page.push_str("</ul>");
+        }
+        page.push_str("</td></tr>\n");
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scripting::TestResult;
+
+    fn sample_run() -> CollectionRun {
+        let test = |name: &str, passed: bool| TestResult {
+            name: name.to_string(),
+            passed,
+            error: None,
+        };
+        let results = vec![
+            RunRequestResult {
+                iteration: 0,
+                request_id: "r1".to_string(),
+                name: "Login".to_string(),
+                method: "POST".to_string(),
+                status: Some(200),
+                duration_ms: 120,
+                error: None,
+                tests: vec![test("status is 200", true), test("has <token>", false)],
+                passed: false,
+                attempts: Vec::new(),
+            },
+            RunRequestResult {
+                iteration: 0,
+                request_id: "r2".to_string(),
+                name: "Health".to_string(),
+                method: "GET".to_string(),
+                status: None,
+                duration_ms: 5,
+                error: Some("Connection refused".to_string()),
+                tests: Vec::new(),
+                passed: false,
+                attempts: Vec::new(),
+            },
+        ];
+        let now = chrono::Utc::now();
+        CollectionRun {
+            id: "run".to_string(),
+            collection_id: "c".to_string(),
+            environment_id: None,
+            iterations: 1,
+            total_requests: 2,
+            passed_requests: 0,
+            failed_requests: 2,
+            stopped_early: false,
+            results: serde_json::to_string(&results).unwrap(),
+            started_at: now,
+            finished_at: now,
+        }
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let xml = export(&sample_run(), "Shop & Co", "junit").unwrap();
+        assert!(xml.contains("<testsuites name=\"Shop &amp; Co\" tests=\"3\" failures=\"1\" errors=\"1\" time=\"0.125\">"));
+        assert!(xml.contains("<testcase name=\"status is 200\" classname=\"Shop &amp; Co.Login\" time=\"0.000\"/>"));
+        assert!(xml.contains("<testcase name=\"has &lt;token&gt;\""));
+        assert!(xml.contains("<failure message=\"Assertion failed\" type=\"AssertionFailure\"/>"));
+        assert!(xml.contains("<error message=\"Connection refused\" type=\"RequestError\"/>"));
+        assert_eq!(xml.matches("<testsuite ").count(), 2);
+    }
+
+    #[test]
+    fn test_html_report() {
+        let page = export(&sample_run(), "Shop", "html").unwrap();
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains("has &lt;token&gt;"));
+        assert!(page.contains("Connection refused"));
+        assert!(export(&sample_run(), "Shop", "pdf").is_err());
+    }
+}
@@ -0,0 +1,53 @@
+// 🎓 TEACHING: Per-request settings
+// How a saved request is sent, as opposed to what it sends: timeout, redirects, proxy, TLS
+// checks, response caching, the cookie jar and which default headers to leave out. Anything
+// left unset uses the app's default, so an empty object behaves exactly like a request saved
+// before these settings existed.
+
+use crate::runner::RetryPolicy;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct RequestSettings {
+    pub timeout_ms: Option<u64>,        // Give up after this long (no limit by default)
+    pub follow_redirects: Option<bool>, // Defaults to true
+    pub max_redirects: Option<usize>,   // Defaults to 10
+    pub proxy_url: Option<String>,      // Send through this proxy instead of the system one
+    pub verify_tls: Option<bool>,       // false accepts self-signed and expired certificates
+    pub http_version: Option<String>,   // "auto", "http1" or "http2"
+    pub use_cache: Option<bool>,
+    pub cache_duration: Option<u64>,    // Seconds
+    pub cache_ignore_headers: Option<Vec<String>>, // Left out of the cache key; None uses the defaults
+    pub cache_mode: Option<String>,     // "fixed" (cache_duration) or "http" (the response's caching headers)
+    pub use_cookie_jar: Option<bool>,   // Keep and send cookies like a browser; off by default
+    pub disabled_default_headers: Vec<String>, // Default headers (app, environment or folder) not to send
+    pub retry: Option<RetryPolicy>,     // Resend a failed GET or HEAD; max_attempts 1 turns the app default off
+}
+
+// Stored as JSON in the `requests.settings` column
+pub fn parse_settings(json: Option<&str>) -> Result<RequestSettings> {
+    match json.map(str::trim).filter(|json| !json.is_empty()) {
+        Some(json) => Ok(serde_json::from_str(json)?),
+        None => Ok(RequestSettings::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_settings() {
+        assert_eq!(parse_settings(None).unwrap(), RequestSettings::default());
+        assert_eq!(parse_settings(Some("  ")).unwrap(), RequestSettings::default());
+
+        let settings = parse_settings(Some(r#"{"timeout_ms":5000,"follow_redirects":false}"#)).unwrap();
+        assert_eq!(settings.timeout_ms, Some(5000));
+        assert_eq!(settings.follow_redirects, Some(false));
+        assert_eq!(settings.proxy_url, None);
+
+        assert!(parse_settings(Some(r#"{"timeout_ms":"soon"}"#)).is_err());
+    }
+}
@@ -0,0 +1,359 @@
+// 🎓 TEACHING: Response diff
+// To compare the same request across environments, or before and after a deploy, the last
+// few responses are kept in memory under the execution id their send used (the one the
+// `request:*` progress events carry), and any two of them can be diffed:
+//
+//   status   both codes, so the UI can show "200 -> 500"
+//   headers  added, removed and changed headers, by lowercase name
+//   body     for two JSON bodies, every changed value with its path (`$.items[2].price`);
+//            otherwise the lines that were added or removed
+//
+// Arrays are compared index by index, so an item inserted at the front shows up as every
+// later item changing. That's what actually changed in the document, and what a script
+// reading `items[0]` would see.
+
+use crate::error::AppError;
+use crate::large_body::StoredBody;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::Mutex;
+
+// Kept responses; the oldest go first when either limit is passed
+pub const MAX_KEPT: usize = 50;
+pub const MAX_KEPT_BYTES: usize = 64 * 1024 * 1024;
+// A diff past this many changes is cut short (and marked truncated)
+const MAX_CHANGES: usize = 500;
+// Above this many line pairs, text bodies are only reported as different, not line by line
+const MAX_LINE_PAIRS: usize = 4_000_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseSnapshot {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: StoredBody, // Large bodies stay in their file
+}
+
+impl ResponseSnapshot {
+    fn size(&self) -> usize {
+        self.body.memory_size() + self.headers.iter().map(|(name, value)| name.len() + value.len()).sum::<usize>()
+    }
+}
+
+// 🎓 TEACHING: Managed as Tauri state, like the session cache
+#[derive(Default)]
+pub struct ResponseStore {
+    kept: Mutex<VecDeque<(String, ResponseSnapshot)>>,
+}
+
+impl ResponseStore {
+    pub fn keep(&self, execution_id: &str, snapshot: ResponseSnapshot) {
+        let mut kept = self.kept.lock().unwrap();
+        kept.retain(|(id, _)| id != execution_id);
+        kept.push_back((execution_id.to_string(), snapshot));
+        let mut bytes: usize = kept.iter().map(|(_, snapshot)| snapshot.size()).sum();
+        // The newest one stays even if it's bigger than the whole budget
+        while kept.len() > 1 && (kept.len() > MAX_KEPT || bytes > MAX_KEPT_BYTES) {
+            if let Some((_, dropped)) = kept.pop_front() {
+                bytes -= dropped.size();
+            }
+        }
+    }
+
+    pub fn get(&self, execution_id: &str) -> Option<ResponseSnapshot> {
+        let kept = self.kept.lock().unwrap();
+        kept.iter().find(|(id, _)| id == execution_id).map(|(_, snapshot)| snapshot.clone())
+    }
+
+    pub fn kept(&self, execution_id: &str) -> Result<ResponseSnapshot, AppError> {
+        self.get(execution_id).ok_or_else(|| {
+            AppError::not_found(format!(
+                "No response kept for execution {}; only the last {} are",
+                execution_id, MAX_KEPT
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,   // Only in the second response
+    Removed, // Only in the first
+    Changed,
+}
+
+// One header or JSON value that differs; `a` and `b` are None where it's missing
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Change {
+    pub path: String, // Lowercase header name, or a JSON path like `$.data.items[0].id`
+    pub kind: ChangeKind,
+    pub a: Option<Value>,
+    pub b: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct LineChange {
+    pub kind: ChangeKind, // Added or Removed
+    pub line_a: Option<usize>, // 1-based line in the first body (for removed lines)
+    pub line_b: Option<usize>, // and in the second (for added ones)
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BodyDiff {
+    Identical,
+    Json {
+        changes: Vec<Change>,
+        truncated: bool,
+    },
+    Text {
+        lines: Option<Vec<LineChange>>, // None if the bodies were too big to diff line by line
+        truncated: bool,
+    },
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ResponseDiff {
+    pub identical: bool,
+    pub status_a: u16,
+    pub status_b: u16,
+    pub headers: Vec<Change>,
+    pub body: BodyDiff,
+}
+
+pub fn diff(a: &ResponseSnapshot, b: &ResponseSnapshot) -> Result<ResponseDiff> {
+    let headers = diff_headers(&a.headers, &b.headers);
+    let body = diff_bodies(&a.body.text()?, &b.body.text()?);
+    Ok(ResponseDiff {
+        identical: a.status == b.status && headers.is_empty() && body == BodyDiff::Identical,
+        status_a: a.status,
+        status_b: b.status,
+        headers,
+        body,
+    })
+}
+
+fn diff_headers(a: &HashMap<String, String>, b: &HashMap<String, String>) -> Vec<Change> {
+    let lower = |headers: &HashMap<String, String>| -> HashMap<String, String> {
+        headers.iter().map(|(name, value)| (name.to_ascii_lowercase(), value.clone())).collect()
+    };
+    let (a, b) = (lower(a), lower(b));
+    let names: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let (value_a, value_b) = (a.get(name), b.get(name));
+            let kind = match (value_a, value_b) {
+                (Some(x), Some(y)) if x == y => return None,
+                (Some(_), Some(_)) => ChangeKind::Changed,
+                (Some(_), None) => ChangeKind::Removed,
+                _ => ChangeKind::Added,
+            };
+            Some(Change {
+                path: name.clone(),
+                kind,
+                a: value_a.map(|value| Value::String(value.clone())),
+                b: value_b.map(|value| Value::String(value.clone())),
+            })
+        })
+        .collect()
+}
+
+fn diff_bodies(a: &str, b: &str) -> BodyDiff {
+    if a == b {
+        return BodyDiff::Identical;
+    }
+    match (serde_json::from_str::<Value>(a), serde_json::from_str::<Value>(b)) {
+        (Ok(a), Ok(b)) if a == b => BodyDiff::Identical, // Only the formatting differs
+        (Ok(a), Ok(b)) => {
+            let mut changes = Vec::new();
+            let truncated = !diff_json(&a, &b, "$", &mut changes);
+            BodyDiff::Json { changes, truncated }
+        }
+        _ => diff_lines(a, b),
+    }
+}
+
+// Returns false once MAX_CHANGES is reached
+fn diff_json(a: &Value, b: &Value, at: &str, changes: &mut Vec<Change>) -> bool {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            keys.into_iter().all(|key| {
+                let key_at = format!("{}.{}", at, key);
+                diff_member(a.get(key), b.get(key), &key_at, changes)
+            })
+        }
+        (Value::Array(a), Value::Array(b)) => (0..a.len().max(b.len())).all(|i| {
+            let item_at = format!("{}[{}]", at, i);
+            diff_member(a.get(i), b.get(i), &item_at, changes)
+        }),
+        _ if a == b => true,
+        _ => push(changes, at, ChangeKind::Changed, Some(a), Some(b)),
+    }
+}
+
+fn diff_member(a: Option<&Value>, b: Option<&Value>, at: &str, changes: &mut Vec<Change>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => diff_json(a, b, at, changes),
+        (Some(a), None) => push(changes, at, ChangeKind::Removed, Some(a), None),
+        (None, Some(b)) => push(changes, at, ChangeKind::Added, None, Some(b)),
+        (None, None) => true,
+    }
+}
+
+fn push(changes: &mut Vec<Change>, at: &str, kind: ChangeKind, a: Option<&Value>, b: Option<&Value>) -> bool {
+    if changes.len() >= MAX_CHANGES {
+        return false;
+    }
+    changes.push(Change { path: at.to_string(), kind, a: a.cloned(), b: b.cloned() });
+    true
+}
+
+// 🎓 TEACHING: A plain longest-common-subsequence line diff. Lines both bodies share at the
+// start and end are skipped first, which for two versions of the same page is most of them.
+// Where a line was replaced, the removed one is listed before the one that took its place.
+fn diff_lines(a: &str, b: &str) -> BodyDiff {
+    let a: Vec<&str> = a.lines().collect();
+    let b: Vec<&str> = b.lines().collect();
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (rest_a, rest_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    if rest_a.len().saturating_mul(rest_b.len()) > MAX_LINE_PAIRS {
+        return BodyDiff::Text { lines: None, truncated: false };
+    }
+
+    // common[i][j]: the longest common run of rest_a[i..] and rest_b[j..]
+    let width = rest_b.len() + 1;
+    let mut common = vec![0u32; (rest_a.len() + 1) * width];
+    for i in (0..rest_a.len()).rev() {
+        for j in (0..rest_b.len()).rev() {
+            common[i * width + j] = if rest_a[i] == rest_b[j] {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while (i < rest_a.len() || j < rest_b.len()) && lines.len() < MAX_CHANGES {
+        if i < rest_a.len() && j < rest_b.len() && rest_a[i] == rest_b[j] {
+            i += 1;
+            j += 1;
+        } else if j < rest_b.len() && (i == rest_a.len() || common[i * width + j + 1] > common[(i + 1) * width + j]) {
+            lines.push(LineChange {
+                kind: ChangeKind::Added,
+                line_a: None,
+                line_b: Some(prefix + j + 1),
+                text: rest_b[j].to_string(),
+            });
+            j += 1;
+        } else {
+            lines.push(LineChange {
+                kind: ChangeKind::Removed,
+                line_a: Some(prefix + i + 1),
+                line_b: None,
+                text: rest_a[i].to_string(),
+            });
+            i += 1;
+        }
+    }
+    let truncated = i < rest_a.len() || j < rest_b.len();
+    BodyDiff::Text { lines: Some(lines), truncated }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn snapshot(status: u16, headers: &[(&str, &str)], body: &str) -> ResponseSnapshot {
+        ResponseSnapshot {
+            status,
+            headers: headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+            body: StoredBody::Inline(body.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_json_diff() {
+        let a = snapshot(
+            200,
+            &[("Content-Type", "application/json"), ("ETag", "\"1\"")],
+            r#"{"user":{"id":1,"name":"Ada"},"items":[1,2],"old":true}"#,
+        );
+        let b = snapshot(
+            500,
+            &[("content-type", "application/json"), ("etag", "\"2\""), ("Retry-After", "5")],
+            r#"{"user":{"id":1,"name":"Grace"},"items":[1,2,3],"new":null}"#,
+        );
+        let result = diff(&a, &b).unwrap();
+        assert!(!result.identical);
+        assert_eq!((result.status_a, result.status_b), (200, 500));
+
+        let headers: Vec<(&str, ChangeKind)> = result.headers.iter().map(|c| (c.path.as_str(), c.kind)).collect();
+        assert_eq!(headers, vec![("etag", ChangeKind::Changed), ("retry-after", ChangeKind::Added)]);
+
+        let BodyDiff::Json { changes, truncated } = result.body else { panic!("expected a JSON diff") };
+        assert!(!truncated);
+        let paths: Vec<(&str, ChangeKind)> = changes.iter().map(|c| (c.path.as_str(), c.kind)).collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("$.items[2]", ChangeKind::Added),
+                ("$.new", ChangeKind::Added),
+                ("$.old", ChangeKind::Removed),
+                ("$.user.name", ChangeKind::Changed),
+            ]
+        );
+        assert_eq!((changes[3].a.clone(), changes[3].b.clone()), (Some(json!("Ada")), Some(json!("Grace"))));
+    }
+
+    #[test]
+    fn test_identical_and_reformatted() {
+        let a = snapshot(200, &[("X-Id", "1")], r#"{"a": [1, 2]}"#);
+        let b = snapshot(200, &[("x-id", "1")], "{\n  \"a\": [1,2]\n}");
+        let result = diff(&a, &b).unwrap();
+        assert!(result.identical);
+        assert_eq!(result.body, BodyDiff::Identical);
+    }
+
+    #[test]
+    fn test_text_diff() {
+        let a = snapshot(200, &[], "<html>\n<h1>Old</h1>\n<p>same</p>\n</html>");
+        let b = snapshot(200, &[], "<html>\n<h1>New</h1>\n<p>same</p>\n<p>more</p>\n</html>");
+        let BodyDiff::Text { lines: Some(lines), truncated: false } = diff(&a, &b).unwrap().body else {
+            panic!("expected a line diff")
+        };
+        let summary: Vec<(ChangeKind, Option<usize>, Option<usize>, &str)> =
+            lines.iter().map(|l| (l.kind, l.line_a, l.line_b, l.text.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (ChangeKind::Removed, Some(2), None, "<h1>Old</h1>"),
+                (ChangeKind::Added, None, Some(2), "<h1>New</h1>"),
+                (ChangeKind::Added, None, Some(4), "<p>more</p>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_store_keeps_the_latest() {
+        let store = ResponseStore::default();
+        for i in 0..MAX_KEPT + 1 {
+            store.keep(&format!("exec-{}", i), snapshot(200, &[], "ok"));
+        }
+        assert_eq!(store.get("exec-0"), None);
+        assert!(store.get("exec-1").is_some());
+
+        // One too big for the budget pushes out everything older
+        store.keep("big", snapshot(200, &[], &"x".repeat(MAX_KEPT_BYTES + 1)));
+        assert_eq!(store.get(&format!("exec-{}", MAX_KEPT)), None);
+        assert!(store.get("big").is_some());
+    }
+}
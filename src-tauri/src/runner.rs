@@ -0,0 +1,697 @@
+// 🎓 TEACHING: Collection runner
+// Sends every request in a collection, folders included, one after another through the
+// normal send pipeline (scripts, captures and all), so a value captured by the login
+// request is there for the next one. Progress is reported per request as it happens, and
+// the run's summary, with every test result, is saved for the run history.
+//
+// A request fails when it can't be sent or any of its tests fail; the status code alone
+// doesn't fail it, since some suites expect a 404.
+//
+// With an iteration data file (CSV or JSON) the whole collection runs once per row, with
+// the row's values as one-off variables, for table-driven tests of the same requests.
+//
+// With `concurrency` above 1, independent requests are sent in parallel. A request that
+// writes variables (capture rules, or a script calling `set_var`) may feed the requests
+// after it, so it waits for everything before it and runs on its own. Results are always
+// reported in run order, however the sends interleave.
+//
+// An optional response check (contract testing uses one) adds its own test results to
+// every response, after the request's tests script.
+//
+// A retry policy resends a request that couldn't be sent or got a retryable status (429,
+// 503...), waiting longer after each attempt, or as long as a Retry-After header asks. Every
+// attempt is kept in the result.
+
+use crate::analytics;
+use crate::capture;
+use crate::database::{Collection, CollectionRun, Database, Request};
+use crate::error::AppError;
+use crate::scripting::{self, TestResult};
+use crate::session::SessionCache;
+use crate::{execute_request, send_environment, AfterResponse, ApiRequest, ApiResponse};
+use anyhow::Result;
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use rand::Rng;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RunOptions {
+    pub environment_id: Option<String>, // Defaults to the active environment
+    #[serde(default)]
+    pub delay_ms: u64, // Pause between requests (per worker when running in parallel)
+    #[serde(default)]
+    pub stop_on_failure: bool,
+    // A .csv (with a header row) or .json (array of objects) file; one iteration per row
+    pub data_file: Option<String>,
+    // How many requests may be in flight at once; 1 (the default) runs them one by one
+    pub concurrency: Option<usize>,
+    // None sends each request once
+    pub retry: Option<RetryPolicy>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RetryPolicy {
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32, // Including the first send
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64, // Wait after the first attempt; doubles after each one after that
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    #[serde(default = "default_true")]
+    pub jitter: bool, // Wait a random 50-100% of the backoff, so clients don't retry in lockstep
+    #[serde(default = "default_retry_statuses")]
+    pub retry_on_status: Vec<u16>,
+    #[serde(default = "default_true")]
+    pub retry_on_network_error: bool, // Retry sends that got no response at all
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_retry_statuses() -> Vec<u16> {
+    vec![429, 502, 503, 504]
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: default_max_attempts(),
+            backoff_ms: default_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            jitter: true,
+            retry_on_status: default_retry_statuses(),
+            retry_on_network_error: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // `status` is None when the send failed without a response
+    pub fn should_retry(&self, attempt: u32, status: Option<u16>) -> bool {
+        attempt < self.max_attempts
+            && match status {
+                Some(status) => self.retry_on_status.contains(&status),
+                None => self.retry_on_network_error,
+            }
+    }
+
+    // 🎓 TEACHING: Exponential backoff: base, 2x base, 4x base... up to the cap. `jitter` is
+    // a random number in 0..1 that, with jitter on, trims the wait by up to half.
+    pub fn delay(&self, attempt: u32, jitter: f64) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(20);
+        let backoff = self.backoff_ms.saturating_mul(1 << exponent).min(self.max_backoff_ms);
+        let backoff = if self.jitter {
+            (backoff as f64 * (1.0 - jitter.clamp(0.0, 1.0) / 2.0)) as u64
+        } else {
+            backoff
+        };
+        Duration::from_millis(backoff)
+    }
+
+    // 🎓 TEACHING: A response's Retry-After replaces the backoff. One asking for longer than
+    // max_backoff_ms isn't waited out: there's no retry and the response stands.
+    pub fn wait_before_retry(&self, attempt: u32, jitter: f64, retry_after: Option<Duration>) -> Option<Duration> {
+        match retry_after {
+            Some(wait) if wait > Duration::from_millis(self.max_backoff_ms) => None,
+            Some(wait) => Some(wait),
+            None => Some(self.delay(attempt, jitter)),
+        }
+    }
+}
+
+// Retry-After is a number of seconds or an HTTP date; a date in the past means now
+pub fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&chrono::Utc);
+    Some((at - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Attempt {
+    pub status: Option<u16>, // None if this attempt got no response
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunRequestResult {
+    pub iteration: usize, // Row of the iteration data, 0 without a data file
+    pub request_id: String,
+    pub name: String,
+    pub method: String,
+    pub status: Option<u16>, // None if the request couldn't be sent
+    pub duration_ms: u64, // All attempts, including the waits between them
+    pub error: Option<String>,
+    pub tests: Vec<TestResult>,
+    pub passed: bool,
+    #[serde(default)]
+    pub attempts: Vec<Attempt>, // Every send, the last one being the result above
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunProgress {
+    Started {
+        run_id: String,
+        total: usize,
+    },
+    RequestStarted {
+        run_id: String,
+        index: usize, // Position in the whole run, across iterations
+        iteration: usize,
+        request_id: String,
+        name: String,
+    },
+    RequestFinished {
+        run_id: String,
+        index: usize,
+        result: RunRequestResult,
+    },
+    Finished {
+        run: CollectionRun,
+    },
+}
+
+pub type ProgressSink = Arc<dyn Fn(RunProgress) + Send + Sync>;
+
+// Extra tests for each response, beyond the request's own script
+pub type ResponseCheck = Arc<dyn Fn(&Request, &ApiResponse) -> Vec<TestResult> + Send + Sync>;
+
+// Folders in run order: the collection itself, then each subfolder depth-first,
+// in the same order as the sidebar
+pub fn run_order(root_id: &str, collections: &[Collection]) -> Vec<String> {
+    let mut order = Vec::new();
+    let mut pending = vec![root_id.to_string()];
+    while let Some(id) = pending.pop() {
+        if order.contains(&id) {
+            continue; // A parent loop in the data; don't run anything twice
+        }
+        let mut children: Vec<&Collection> = collections
+            .iter()
+            .filter(|collection| collection.parent_id.as_deref() == Some(id.as_str()))
+            .collect();
+        children.sort_by(|a, b| (a.sort_order, &a.name).cmp(&(b.sort_order, &b.name)));
+        pending.extend(children.into_iter().rev().map(|child| child.id.clone()));
+        order.push(id);
+    }
+    order
+}
+
+// 🎓 TEACHING: Iteration data. Each row's columns (CSV) or keys (JSON) become variables for
+// that iteration; JSON values that aren't strings are used as their JSON text.
+pub fn parse_iteration_data(text: &str, format: &str) -> Result<Vec<HashMap<String, String>>> {
+    let rows = match format {
+        "csv" => {
+            let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::Headers).from_reader(text.as_bytes());
+            let headers = reader.headers()?.clone();
+            let mut rows = Vec::new();
+            for record in reader.records() {
+                let record = record?;
+                rows.push(headers.iter().map(str::to_string).zip(record.iter().map(str::to_string)).collect());
+            }
+            rows
+        }
+        "json" => {
+            let values: Vec<serde_json::Value> = serde_json::from_str(text)?;
+            let mut rows = Vec::new();
+            for (index, value) in values.into_iter().enumerate() {
+                let serde_json::Value::Object(object) = value else {
+                    return Err(anyhow::anyhow!("Row {} of the iteration data is not an object", index + 1));
+                };
+                let row = object
+                    .into_iter()
+                    .map(|(key, value)| match value {
+                        serde_json::Value::String(text) => (key, text),
+                        serde_json::Value::Null => (key, String::new()),
+                        other => (key, other.to_string()),
+                    })
+                    .collect();
+                rows.push(row);
+            }
+            rows
+        }
+        other => return Err(anyhow::anyhow!("Unsupported iteration data format: {}", other)),
+    };
+    if rows.is_empty() {
+        return Err(anyhow::anyhow!("The iteration data has no rows"));
+    }
+    Ok(rows)
+}
+
+fn load_iteration_data(path: &str) -> Result<Vec<HashMap<String, String>>> {
+    let text = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+    let format = if path.to_lowercase().ends_with(".json") { "json" } else { "csv" };
+    parse_iteration_data(&text, format)
+}
+
+// Whether a request can change variables that later requests read
+fn writes_variables(request: &Request) -> bool {
+    let captures = capture::parse_rules(request.captures.as_deref()).unwrap_or_default();
+    let scripts = scripting::parse_scripts(request.scripts.as_deref()).unwrap_or_default();
+    captures.iter().any(|rule| rule.enabled)
+        || [scripts.pre_request, scripts.tests]
+            .iter()
+            .flatten()
+            .any(|script| script_writes_variables(&script.code))
+}
+
+// A module from the script library can call set_var as well, so a script that imports one
+// counts as a writer; modules aren't resolved here, and a false positive only costs parallelism
+fn script_writes_variables(code: &str) -> bool {
+    code.contains("set_var") || code.contains("import")
+}
+
+// 🎓 TEACHING: Split the run into batches that are safe to send in parallel. Requests that
+// write variables get a batch of their own, so they start after everything before them
+// has finished and finish before anything after them starts. Returns the batch sizes.
+pub fn parallel_batches(writes_variables: &[bool]) -> Vec<usize> {
+    let mut batches = Vec::new();
+    let mut pending = 0; // Independent requests since the last batch
+    for &writes in writes_variables {
+        if writes {
+            if pending > 0 {
+                batches.push(pending);
+            }
+            batches.push(1);
+            pending = 0;
+        } else {
+            pending += 1;
+        }
+    }
+    if pending > 0 {
+        batches.push(pending);
+    }
+    batches
+}
+
+// One send in the run: which request, in which iteration
+struct Job<'a> {
+    index: usize,
+    iteration: usize,
+    request: &'a Request,
+    data: &'a HashMap<String, String>,
+}
+
+// Everything a job needs besides itself
+struct RunContext<'a> {
+    db: &'a Database,
+    session_cache: &'a SessionCache,
+    options: &'a RunOptions,
+    run_id: &'a str,
+    progress: &'a ProgressSink,
+    check: Option<&'a ResponseCheck>,
+    stop: AtomicBool, // Set on the first failure with stop_on_failure
+}
+
+pub async fn run_collection(
+    db: &Database,
+    session_cache: &SessionCache,
+    collection_id: &str,
+    mut options: RunOptions,
+    progress: ProgressSink,
+    check: Option<ResponseCheck>,
+) -> Result<CollectionRun, AppError> {
+    if db.get_collection_by_id(collection_id).await?.is_none() {
+        return Err(AppError::not_found(format!("Collection not found: {}", collection_id)));
+    }
+    // Pin the environment now, so switching the active one mid-run doesn't affect it
+    options.environment_id = send_environment(db, options.environment_id.as_deref()).await?.map(|env| env.id);
+
+    let collections = db.get_collections().await?;
+    let mut requests = Vec::new();
+    for folder_id in run_order(collection_id, &collections) {
+        requests.extend(db.get_requests_by_collection(&folder_id).await?);
+    }
+
+    let iterations = match options.data_file.as_deref() {
+        Some(path) => load_iteration_data(path)?,
+        None => vec![HashMap::new()],
+    };
+    let total = requests.len() * iterations.len();
+
+    let run_id = Uuid::new_v4().to_string();
+    let started_at = chrono::Utc::now();
+    progress(RunProgress::Started {
+        run_id: run_id.clone(),
+        total,
+    });
+
+    let mut jobs = Vec::with_capacity(total);
+    for (iteration, data) in iterations.iter().enumerate() {
+        for request in &requests {
+            jobs.push(Job {
+                index: jobs.len(),
+                iteration,
+                request,
+                data,
+            });
+        }
+    }
+    let concurrency = options.concurrency.unwrap_or(1).max(1);
+    let batches = if concurrency == 1 {
+        vec![jobs.len()]
+    } else {
+        parallel_batches(&jobs.iter().map(|job| writes_variables(job.request)).collect::<Vec<_>>())
+    };
+
+    let context = RunContext {
+        db,
+        session_cache,
+        options: &options,
+        run_id: &run_id,
+        progress: &progress,
+        check: check.as_ref(),
+        stop: AtomicBool::new(false),
+    };
+    let mut jobs = jobs.into_iter();
+    let mut results: Vec<RunRequestResult> = Vec::with_capacity(total);
+    for batch in batches {
+        // `buffered` keeps results in job order while up to `concurrency` run at once.
+        // The futures are collected first: a closure over borrowed jobs inside the stream
+        // type would keep the run's future from being Send.
+        let sends: Vec<_> = jobs.by_ref().take(batch).map(|job| run_job(&context, job)).collect();
+        let batch_results: Vec<Option<RunRequestResult>> =
+            stream::iter(sends).buffered(concurrency).collect().await;
+        results.extend(batch_results.into_iter().flatten());
+        if context.stop.load(Ordering::Relaxed) {
+            break;
+        }
+    }
+    let stopped_early = results.len() < total;
+
+    let passed_requests = results.iter().filter(|result| result.passed).count() as u32;
+    let run = CollectionRun {
+        id: run_id,
+        collection_id: collection_id.to_string(),
+        environment_id: options.environment_id,
+        iterations: iterations.len() as u32,
+        total_requests: total as u32,
+        passed_requests,
+        failed_requests: results.len() as u32 - passed_requests,
+        stopped_early,
+        results: serde_json::to_string(&results)?,
+        started_at,
+        finished_at: chrono::Utc::now(),
+    };
+    db.save_collection_run(&run).await?;
+    progress(RunProgress::Finished { run: run.clone() });
+    Ok(run)
+}
+
+// None if the run was stopped before this job started
+async fn run_job(context: &RunContext<'_>, job: Job<'_>) -> Option<RunRequestResult> {
+    if job.index > 0 && context.options.delay_ms > 0 && !context.stop.load(Ordering::Relaxed) {
+        tokio::time::sleep(Duration::from_millis(context.options.delay_ms)).await;
+    }
+    if context.stop.load(Ordering::Relaxed) {
+        return None;
+    }
+    (context.progress)(RunProgress::RequestStarted {
+        run_id: context.run_id.to_string(),
+        index: job.index,
+        iteration: job.iteration,
+        request_id: job.request.id.clone(),
+        name: job.request.name.clone(),
+    });
+
+    let result = run_request(
+        context.db,
+        context.session_cache,
+        job.request,
+        context.options,
+        job.iteration,
+        job.data,
+        context.check,
+    )
+    .await;
+    (context.progress)(RunProgress::RequestFinished {
+        run_id: context.run_id.to_string(),
+        index: job.index,
+        result: result.clone(),
+    });
+    if !result.passed && context.options.stop_on_failure {
+        context.stop.store(true, Ordering::Relaxed);
+    }
+    Some(result)
+}
+
+pub async fn run_request(
+    db: &Database,
+    session_cache: &SessionCache,
+    saved: &Request,
+    options: &RunOptions,
+    iteration: usize,
+    data: &HashMap<String, String>,
+    check: Option<&ResponseCheck>,
+) -> RunRequestResult {
+    send_request(db, session_cache, saved, options, iteration, data, check).await.0
+}
+
+// Like run_request, but also hands back the response (None if the send failed) for callers
+// that read it, like workflows passing values along their edges
+pub async fn send_request(
+    db: &Database,
+    session_cache: &SessionCache,
+    saved: &Request,
+    options: &RunOptions,
+    iteration: usize,
+    data: &HashMap<String, String>,
+    check: Option<&ResponseCheck>,
+) -> (RunRequestResult, Option<ApiResponse>) {
+    let policy = options.retry.as_ref();
+    let prepare = || {
+        let mut request = ApiRequest::from_saved(saved)?;
+        request.environment_id = options.environment_id.clone();
+        request.variable_overrides.extend(data.clone());
+        if policy.is_some() {
+            // The run's policy replaces the request's own, so retries don't nest
+            request.retry = None;
+        }
+        Ok::<_, AppError>(request)
+    };
+    let started = Instant::now();
+    let mut attempts = Vec::new();
+    let sent = async {
+        let first = prepare()?;
+        let after_response = AfterResponse::new(&first);
+        let mut next = Some(first);
+        let response = loop {
+            // Each attempt is a fresh send: the pre-request script and variables run again
+            let request = match next.take() {
+                Some(request) => request,
+                None => prepare()?,
+            };
+            let attempt_started = Instant::now();
+            let response = execute_request(db, session_cache, request, None, None).await;
+            let sent_ms = attempt_started.elapsed().as_millis() as u64;
+            analytics::record(db, &saved.id, sent_ms, response.as_ref().ok()).await;
+            let status = response.as_ref().ok().map(|response| response.status);
+            attempts.push(Attempt {
+                status,
+                duration_ms: sent_ms,
+                error: response.as_ref().err().map(|e| e.message.clone()),
+            });
+
+            match policy {
+                Some(policy) if policy.should_retry(attempts.len() as u32, status) => {
+                    let jitter = rand::thread_rng().gen::<f64>();
+                    let retry_after = response
+                        .as_ref()
+                        .ok()
+                        .and_then(|response| response.headers.get("retry-after"))
+                        .and_then(|value| parse_retry_after(value, chrono::Utc::now()));
+                    match policy.wait_before_retry(attempts.len() as u32, jitter, retry_after) {
+                        Some(wait) => tokio::time::sleep(wait).await,
+                        None => break response,
+                    }
+                }
+                _ => break response,
+            }
+        };
+        let mut response = response?;
+        after_response.apply(db, &mut response).await?;
+        if let Some(check) = check {
+            let checks = check(saved, &response);
+            response.test_results.extend(checks);
+        }
+        Ok::<_, AppError>(response)
+    }
+    .await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    // Errors can echo the interpolated URL or headers, so keep secrets out of them
+    let redactor = db.secret_redactor().await.unwrap_or_default();
+    for attempt in &mut attempts {
+        attempt.error = attempt.error.as_deref().map(|error| redactor.redact(error));
+    }
+
+    let mut result = RunRequestResult {
+        iteration,
+        request_id: saved.id.clone(),
+        name: saved.name.clone(),
+        method: saved.method.clone(),
+        status: None,
+        duration_ms,
+        error: None,
+        tests: Vec::new(),
+        passed: false,
+        attempts,
+    };
+    match sent {
+        Ok(response) => {
+            result.passed = response.test_results.iter().all(|test| test.passed);
+            result.status = Some(response.status);
+            result.tests = response.test_results.clone();
+            (result, Some(response))
+        }
+        Err(e) => {
+            result.error = Some(redactor.redact(&e.message));
+            (result, None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_order() {
+        let now = chrono::Utc::now();
+        let folder = |id: &str, name: &str, parent_id: Option<&str>| Collection {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: None,
+            docs: None,
+            parent_id: parent_id.map(str::to_string),
+            auth_type: None,
+            auth_data: None,
+            sort_order: 0,
+            version: 1,
+            trace_context: None,
+            default_headers: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let collections = vec![
+            folder("root", "API", None),
+            folder("users", "Users", Some("root")),
+            folder("auth", "Auth", Some("root")),
+            folder("tokens", "Tokens", Some("auth")),
+            folder("other", "Other", None),
+            folder("admin", "Admin", Some("users")),
+        ];
+
+        assert_eq!(run_order("root", &collections), vec!["root", "auth", "tokens", "users", "admin"]);
+        assert_eq!(run_order("other", &collections), vec!["other"]);
+    }
+
+    #[test]
+    fn test_parallel_batches() {
+        assert_eq!(parallel_batches(&[false, false, false]), vec![3]);
+        assert_eq!(parallel_batches(&[true, false, false, true, true, false]), vec![1, 2, 1, 1, 1]);
+        assert!(parallel_batches(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_script_writes_variables() {
+        assert!(script_writes_variables(r#"set_var("token", response.json.token);"#));
+        assert!(script_writes_variables(r#"import "auth/login" as login; login::store(response);"#));
+        assert!(!script_writes_variables(r#"test("ok", response.status == 200); print(get_var("token"));"#));
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy: RetryPolicy = serde_json::from_str(r#"{"max_attempts": 4, "jitter": false}"#).unwrap();
+        assert!(policy.should_retry(1, None));
+        assert!(policy.should_retry(3, Some(503)));
+        assert!(!policy.should_retry(4, Some(503))); // Out of attempts
+        assert!(!policy.should_retry(1, Some(500)));
+        assert!(!policy.should_retry(1, Some(200)));
+
+        assert_eq!(policy.delay(1, 0.9), Duration::from_millis(500));
+        assert_eq!(policy.delay(3, 0.9), Duration::from_millis(2000));
+        assert_eq!(policy.delay(40, 0.9), Duration::from_millis(30_000));
+
+        let jittered = RetryPolicy::default();
+        assert_eq!(jittered.delay(2, 0.0), Duration::from_millis(1000));
+        assert_eq!(jittered.delay(2, 1.0), Duration::from_millis(500));
+
+        // Retry-After wins over the backoff, unless it asks for more than the cap
+        assert_eq!(policy.wait_before_retry(3, 0.9, None), Some(Duration::from_millis(2000)));
+        assert_eq!(policy.wait_before_retry(3, 0.9, Some(Duration::from_secs(2))), Some(Duration::from_secs(2)));
+        assert_eq!(policy.wait_before_retry(1, 0.9, Some(Duration::from_secs(31))), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = "2026-01-01T00:00:00Z".parse().unwrap();
+        assert_eq!(parse_retry_after(" 120 ", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Thu, 01 Jan 2026 00:00:30 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Wed, 31 Dec 2025 23:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_parse_iteration_data() {
+        let csv = "user, password\nada,\"p,1\"\ngrace,\n";
+        let rows = parse_iteration_data(csv, "csv").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["user"], "ada");
+        assert_eq!(rows[0]["password"], "p,1");
+        assert_eq!(rows[1]["password"], "");
+
+        let json = r#"[{"id": 1, "name": "Ada", "tags": ["a"], "note": null}]"#;
+        let rows = parse_iteration_data(json, "json").unwrap();
+        assert_eq!(rows[0]["id"], "1");
+        assert_eq!(rows[0]["name"], "Ada");
+        assert_eq!(rows[0]["tags"], r#"["a"]"#);
+        assert_eq!(rows[0]["note"], "");
+
+        assert!(parse_iteration_data("[1, 2]", "json").is_err());
+        assert!(parse_iteration_data("[]", "json").is_err());
+        assert!(parse_iteration_data("a,b\n1,2,3\n", "csv").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_runs_use_app_settings() {
+        let dir = std::env::temp_dir().join(format!("runner-{}", Uuid::new_v4()));
+        crate::settings::AppSettings { offline: true, ..Default::default() }.save(&dir).unwrap();
+        let mut db = Database::new(&crate::settings::database_url(&dir.join("runner.db")), 1).await.unwrap();
+        db.set_settings_dir(dir.clone());
+        let collection = db.create_collection("API".to_string(), None, None).await.unwrap();
+        // Nothing listens on the discard port; offline mode must not even try it
+        let url = "http://127.0.0.1:9/ping".to_string();
+        let saved = db.create_request(collection.id, "Ping".to_string(), "GET".to_string(), url).await.unwrap();
+
+        let options = RunOptions::default();
+        let (result, response) =
+            send_request(&db, &SessionCache::default(), &saved, &options, 0, &HashMap::new(), None).await;
+        assert!(response.is_none());
+        assert!(result.error.unwrap().contains("to use offline"));
+
+        db.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
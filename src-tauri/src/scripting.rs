@@ -0,0 +1,387 @@
+// 🎓 TEACHING: Request scripts (Rhai)
+// A request can carry two scripts: `pre_request` runs before variables are interpolated and
+// may edit the request or set variables, `tests` runs on the response and records checks.
+// Scripts are Rhai (https://rhai.rs), a small embedded language written in Rust, so there's
+// no JavaScript runtime to ship. Each script says which language it is in, so others can
+// be added later without touching saved requests.
+//
+// What a script sees:
+//   request  - { method, url, headers, body } (pre-request: changes are sent)
+//   response - { status, headers, body, json } (tests; `json` is () unless the body is JSON)
+//   get_var("name"), set_var("name", value)  - set_var writes to the active environment
+//   test("status is 200", response.status == 200)
+//   print(...)  - collected into the script log
+//   import "signing" as signing;  - a shared module from the script library (our `require()`)
+//   pm.environment.set(...), pm.test(...), pm.expect(...)  - Postman's API, see pm_shim.rs
+//
+// Scripts run with no filesystem or network access and an operation limit, like plugins.
+
+use crate::database::ScriptModule;
+use crate::pm_shim::{self, Pm, PmResponse};
+use anyhow::Result;
+use rhai::module_resolvers::ModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Module, Position, Scope, Shared};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// Plenty for assertions and signing helpers, small enough to stop a runaway loop quickly
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Script {
+    #[serde(default = "default_language")]
+    pub language: String, // Only "rhai" for now
+    pub code: String,
+}
+
+fn default_language() -> String {
+    "rhai".to_string()
+}
+
+// Stored as JSON in the `requests.scripts` column
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RequestScripts {
+    pub pre_request: Option<Script>,
+    pub tests: Option<Script>,
+}
+
+// The editable part of a request, as pre-request scripts see it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScriptRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    pub passed: bool,
+    pub error: Option<String>, // Set when the script or a pm.test check failed
+}
+
+#[derive(Debug, Default)]
+pub struct ScriptOutcome {
+    pub variables_set: Vec<(String, String)>, // In the order the script set them
+    pub tests: Vec<TestResult>,
+    pub logs: Vec<String>,
+}
+
+pub fn parse_scripts(json: Option<&str>) -> Result<RequestScripts> {
+    match json.map(str::trim).filter(|json| !json.is_empty()) {
+        Some(json) => Ok(serde_json::from_str(json)?),
+        None => Ok(RequestScripts::default()),
+    }
+}
+
+pub fn run_pre_request(
+    script: &Script,
+    request: &mut ScriptRequest,
+    variables: &HashMap<String, String>,
+    modules: &[ScriptModule],
+) -> Result<ScriptOutcome> {
+    let mut scope = Scope::new();
+    scope.push("request", to_dynamic(&*request)?);
+
+    let (mut outcome, result) = run(script, &mut scope, Pm::new(None), variables, modules);
+    result?;
+    let edited = scope.get_value::<Dynamic>("request").unwrap_or_default();
+    *request = rhai::serde::from_dynamic(&edited)
+        .map_err(|e| anyhow::anyhow!("Pre-request script left an invalid request: {}", e))?;
+    outcome.tests.clear(); // Checks belong in the tests script
+    Ok(outcome)
+}
+
+// A script error is reported as a failed test rather than an error, so the results
+// of checks that already ran aren't lost
+pub fn run_tests(
+    script: &Script,
+    status: u16,
+    headers: &HashMap<String, String>,
+    body: &str,
+    variables: &HashMap<String, String>,
+    modules: &[ScriptModule],
+) -> ScriptOutcome {
+    let json = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .map(|value| to_dynamic(&value))
+        .transpose();
+    let response = json.and_then(|json| {
+        let mut response = rhai::Map::new();
+        response.insert("status".into(), Dynamic::from(status as i64));
+        response.insert("headers".into(), to_dynamic(headers)?);
+        response.insert("body".into(), Dynamic::from(body.to_string()));
+        response.insert("json".into(), json.clone().unwrap_or_default());
+        Ok((response, PmResponse::new(status, headers, body, json)))
+    });
+
+    let (mut outcome, result) = match response {
+        Ok((response, pm_response)) => {
+            let mut scope = Scope::new();
+            scope.push("response", response);
+            run(script, &mut scope, Pm::new(Some(pm_response)), variables, modules)
+        }
+        Err(e) => (ScriptOutcome::default(), Err(e)),
+    };
+    if let Err(e) = result {
+        outcome.tests.push(TestResult {
+            name: "Test script".to_string(),
+            passed: false,
+            error: Some(e.to_string()),
+        });
+    }
+    outcome
+}
+
+// Syntax check for the script library, so a broken module is caught when it's saved
+pub fn check(language: &str, code: &str) -> Result<()> {
+    if language != "rhai" {
+        return Err(anyhow::anyhow!("Unsupported script language: {}", language));
+    }
+    Engine::new()
+        .compile(code)
+        .map(|_| ())
+        .map_err(|e| anyhow::anyhow!("Script error: {}", e))
+}
+
+fn to_dynamic<T: Serialize + ?Sized>(value: &T) -> Result<Dynamic> {
+    rhai::serde::to_dynamic(value).map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+#[derive(Default)]
+pub struct ScriptState {
+    pub variables: HashMap<String, String>,
+    pub outcome: ScriptOutcome,
+}
+
+impl ScriptState {
+    pub fn get(&self, key: &str) -> Dynamic {
+        match self.variables.get(key) {
+            Some(value) => value.clone().into(),
+            None => Dynamic::UNIT,
+        }
+    }
+
+    // Writes to the active environment once the script is done
+    pub fn set(&mut self, key: &str, value: Dynamic) {
+        let value = value.to_string();
+        self.variables.insert(key.to_string(), value.clone());
+        self.outcome.variables_set.push((key.to_string(), value));
+    }
+}
+
+// The outcome holds whatever the script did before it failed
+fn run(
+    script: &Script,
+    scope: &mut Scope,
+    pm: Pm,
+    variables: &HashMap<String, String>,
+    modules: &[ScriptModule],
+) -> (ScriptOutcome, Result<()>) {
+    if script.language != "rhai" {
+        let error = anyhow::anyhow!("Unsupported script language: {}", script.language);
+        return (ScriptOutcome::default(), Err(error));
+    }
+
+    let state = Rc::new(RefCell::new(ScriptState {
+        variables: variables.clone(),
+        ..Default::default()
+    }));
+    let mut engine = engine(&state, pm);
+    engine.set_module_resolver(LibraryResolver {
+        modules: modules
+            .iter()
+            .filter(|module| module.language == script.language)
+            .map(|module| (module.name.clone(), module.code.clone()))
+            .collect(),
+        loading: RefCell::new(Vec::new()),
+    });
+    let result = engine.run_with_scope(scope, &script.code);
+
+    let outcome = std::mem::take(&mut state.borrow_mut().outcome);
+    (outcome, result.map_err(|e| anyhow::anyhow!("Script error: {}", e)))
+}
+
+fn engine(state: &Rc<RefCell<ScriptState>>, pm: Pm) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_string_size(10 * 1024 * 1024);
+    engine.set_max_array_size(100_000);
+    engine.set_max_map_size(100_000);
+    engine.disable_symbol("eval");
+
+    let print_state = state.clone();
+    engine.on_print(move |text| print_state.borrow_mut().outcome.logs.push(text.to_string()));
+    let debug_state = state.clone();
+    engine.on_debug(move |text, _, _| debug_state.borrow_mut().outcome.logs.push(text.to_string()));
+
+    let get_state = state.clone();
+    engine.register_fn("get_var", move |key: &str| get_state.borrow().get(key));
+    let set_state = state.clone();
+    engine.register_fn("set_var", move |key: &str, value: Dynamic| set_state.borrow_mut().set(key, value));
+    let test_state = state.clone();
+    engine.register_fn("test", move |name: &str, passed: bool| {
+        test_state.borrow_mut().outcome.tests.push(TestResult {
+            name: name.to_string(),
+            passed,
+            error: None,
+        });
+    });
+    pm_shim::register(&mut engine, state, pm);
+    engine
+}
+
+// 🎓 TEACHING: `import "name"` looks the module up in the script library and compiles it
+// on demand, so a broken module only affects the scripts that use it
+struct LibraryResolver {
+    modules: HashMap<String, String>, // Name -> code
+    loading: RefCell<Vec<String>>,    // Modules being imported right now, to catch cycles
+}
+
+impl ModuleResolver for LibraryResolver {
+    fn resolve(
+        &self,
+        engine: &Engine,
+        _source: Option<&str>,
+        path: &str,
+        pos: Position,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        let code = self
+            .modules
+            .get(path)
+            .ok_or_else(|| Box::new(EvalAltResult::ErrorModuleNotFound(path.to_string(), pos)))?;
+        if self.loading.borrow().iter().any(|name| name == path) {
+            let cycle = format!("{} imports itself", path);
+            return Err(Box::new(EvalAltResult::ErrorInModule(path.to_string(), cycle.into(), pos)));
+        }
+
+        self.loading.borrow_mut().push(path.to_string());
+        let module = engine
+            .compile(code)
+            .map_err(|e| e.into())
+            .and_then(|ast| Module::eval_ast_as_new(Scope::new(), &ast, engine));
+        self.loading.borrow_mut().pop();
+        module
+            .map(Shared::new)
+            .map_err(|e| Box::new(EvalAltResult::ErrorInModule(path.to_string(), e, pos)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rhai(code: &str) -> Script {
+        Script {
+            language: "rhai".to_string(),
+            code: code.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_run_pre_request() {
+        let mut request = ScriptRequest {
+            method: "GET".to_string(),
+            url: "{{base_url}}/users".to_string(),
+            headers: HashMap::new(),
+            body: None,
+        };
+        let variables = HashMap::from([("base_url".to_string(), "https://api.example.com".to_string())]);
+        let script = rhai(
+            r#"
+            let stamp = 1700000000;
+            request.headers["X-Timestamp"] = `${stamp}`;
+            request.method = "POST";
+            set_var("request_count", 3);
+            print(`sending to ${get_var("base_url")}`);
+            if get_var("missing") == () { print("no missing"); }
+            "#,
+        );
+
+        let outcome = run_pre_request(&script, &mut request, &variables, &[]).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.url, "{{base_url}}/users");
+        assert_eq!(request.headers["X-Timestamp"], "1700000000");
+        assert_eq!(outcome.variables_set, vec![("request_count".to_string(), "3".to_string())]);
+        assert_eq!(outcome.logs, vec!["sending to https://api.example.com", "no missing"]);
+
+        assert!(run_pre_request(&rhai("request.headers = 42;"), &mut request, &variables, &[]).is_err());
+        assert!(run_pre_request(&rhai("loop {}"), &mut request, &variables, &[]).is_err());
+        let javascript = Script {
+            language: "javascript".to_string(),
+            code: "pm.environment.set('a', 1)".to_string(),
+        };
+        assert!(run_pre_request(&javascript, &mut request, &variables, &[]).is_err());
+    }
+
+    #[test]
+    fn test_run_tests() {
+        let headers = HashMap::from([("content-type".to_string(), "application/json".to_string())]);
+        let body = r#"{"data": {"id": 42, "tags": ["a", "b"]}}"#;
+        let script = rhai(
+            r#"
+            test("status is 200", response.status == 200);
+            test("has two tags", response.json.data.tags.len() == 2);
+            test("is html", response.headers["content-type"] == "text/html");
+            set_var("user_id", response.json.data.id);
+            "#,
+        );
+
+        let outcome = run_tests(&script, 200, &headers, body, &HashMap::new(), &[]);
+        let results: Vec<(&str, bool)> = outcome.tests.iter().map(|t| (t.name.as_str(), t.passed)).collect();
+        assert_eq!(results, vec![("status is 200", true), ("has two tags", true), ("is html", false)]);
+        assert_eq!(outcome.variables_set, vec![("user_id".to_string(), "42".to_string())]);
+
+        let broken = run_tests(&rhai("test(\"x\", true); response.json.nope.deeper"), 500, &headers, "oops", &HashMap::new(), &[]);
+        assert_eq!(broken.tests.len(), 2);
+        assert!(broken.tests[0].passed);
+        assert!(!broken.tests[1].passed);
+        assert!(broken.tests[1].error.is_some());
+    }
+
+    #[test]
+    fn test_import_modules() {
+        let now = chrono::Utc::now();
+        let module = |name: &str, code: &str| ScriptModule {
+            id: name.to_string(),
+            name: name.to_string(),
+            language: "rhai".to_string(),
+            code: code.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        let modules = vec![
+            module("strings", r#"fn shout(text) { text.to_upper() + "!" }"#),
+            module("auth/sign", r#"import "strings" as s; fn sign(body) { s::shout(body) + get_var("key") }"#),
+            module("loop", r#"import "loop" as l;"#),
+        ];
+        let variables = HashMap::from([("key".to_string(), "k1".to_string())]);
+        let mut request = ScriptRequest {
+            method: "POST".to_string(),
+            url: "/".to_string(),
+            headers: HashMap::new(),
+            body: Some("hi".to_string()),
+        };
+
+        let script = rhai(r#"import "auth/sign" as auth; request.headers["X-Sig"] = auth::sign(request.body);"#);
+        run_pre_request(&script, &mut request, &variables, &modules).unwrap();
+        assert_eq!(request.headers["X-Sig"], "HI!k1");
+
+        assert!(run_pre_request(&rhai(r#"import "missing" as m;"#), &mut request, &variables, &modules).is_err());
+        assert!(run_pre_request(&rhai(r#"import "loop" as l;"#), &mut request, &variables, &modules).is_err());
+        assert!(check("rhai", "fn broken( {").is_err());
+        assert!(check("rhai", "fn ok(a) { a + 1 }").is_ok());
+    }
+
+    #[test]
+    fn test_parse_scripts() {
+        assert_eq!(parse_scripts(None).unwrap(), RequestScripts::default());
+        let scripts = parse_scripts(Some(r#"{"tests": {"code": "test(\"ok\", true);"}}"#)).unwrap();
+        assert_eq!(scripts.tests.unwrap().language, "rhai");
+        assert!(scripts.pre_request.is_none());
+    }
+}
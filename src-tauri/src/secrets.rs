@@ -0,0 +1,206 @@
+// 🎓 TEACHING: Encryption at rest for secrets
+// Secret variable values and auth_data blobs are encrypted with AES-256-GCM before they
+// reach SQLite. The key is derived from the user's passphrase with Argon2id, or is a random
+// key kept in the OS keychain, so the database file alone is not enough to read them.
+//
+// Stored format: `enc:v1:<base64(nonce || ciphertext)>`. Plain values have no prefix,
+// which lets old databases keep working until encryption is enabled.
+//
+// Secret variables can also live outside SQLite entirely, in an OS secret store
+// (macOS Keychain, Windows Credential Manager, libsecret). The row then only holds
+// a `keychain:<variable id>` reference.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+const PREFIX: &str = "enc:v1:";
+const KEYCHAIN_PREFIX: &str = "keychain:";
+const KEYCHAIN_SERVICE: &str = "openrequest";
+const NONCE_LEN: usize = 12;
+
+// Encrypted alongside the salt so a wrong passphrase is detected on unlock
+pub const VERIFIER_PLAINTEXT: &str = "openrequest-secrets";
+
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SecretEncryptionStatus {
+    pub enabled: bool,     // A passphrase or keychain key has been set up
+    pub unlocked: bool,    // The key is loaded for this session
+    pub in_keychain: bool, // The key lives in the OS keychain, so no passphrase is asked for
+}
+
+impl SecretCipher {
+    pub fn from_key(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(key.into()),
+        }
+    }
+
+    // 🎓 TEACHING: Argon2id is deliberately slow, which makes guessing passphrases expensive
+    pub fn from_passphrase(passphrase: &str, salt: &str) -> Result<Self> {
+        let salt = general_purpose::STANDARD.decode(salt)?;
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+        Ok(Self::from_key(&key))
+    }
+
+    // A key from `generate_key`, as stored in the OS keychain
+    pub fn from_encoded_key(encoded: &str) -> Result<Self> {
+        let key: [u8; 32] = general_purpose::STANDARD
+            .decode(encoded)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Secrets key has the wrong length"))?;
+        Ok(Self::from_key(&key))
+    }
+
+    // True when this key decrypts the verifier stored when encryption was enabled
+    pub fn matches_verifier(&self, verifier: &str) -> bool {
+        self.decrypt(verifier).is_ok_and(|plaintext| plaintext == VERIFIER_PLAINTEXT)
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", PREFIX, general_purpose::STANDARD.encode(payload)))
+    }
+
+    pub fn decrypt(&self, value: &str) -> Result<String> {
+        let encoded = value
+            .strip_prefix(PREFIX)
+            .ok_or_else(|| anyhow::anyhow!("Value is not encrypted"))?;
+        let payload = general_purpose::STANDARD.decode(encoded)?;
+        if payload.len() < NONCE_LEN {
+            return Err(anyhow::anyhow!("Encrypted value is truncated"));
+        }
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt secret (wrong key or corrupted data)"))?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+pub fn generate_salt() -> String {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    general_purpose::STANDARD.encode(salt)
+}
+
+// A random 256-bit key, base64 encoded, for workspaces that keep their key in the OS keychain
+pub fn generate_key() -> String {
+    let mut key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    general_purpose::STANDARD.encode(key)
+}
+
+// 🎓 TEACHING: Where secret values can be stored outside the database.
+// Calls may block (the OS can show an unlock prompt), so run them off the async runtime.
+pub trait SecretBackend: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<String>>;
+    fn set(&self, key: &str, value: &str) -> Result<()>;
+    fn delete(&self, key: &str) -> Result<()>;
+}
+
+// The platform credential store, via the `keyring` crate
+pub struct KeychainBackend;
+
+impl SecretBackend for KeychainBackend {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        match keyring::Entry::new(KEYCHAIN_SERVICE, key)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(anyhow::anyhow!("Keychain read failed: {}", e)),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, key)?
+            .set_password(value)
+            .map_err(|e| anyhow::anyhow!("Keychain write failed: {}", e))
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        match keyring::Entry::new(KEYCHAIN_SERVICE, key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(anyhow::anyhow!("Keychain delete failed: {}", e)),
+        }
+    }
+}
+
+pub fn keychain_reference(key: &str) -> String {
+    format!("{}{}", KEYCHAIN_PREFIX, key)
+}
+
+// Returns the keychain key if the stored value is a reference rather than the secret itself
+pub fn parse_keychain_reference(value: &str) -> Option<&str> {
+    value.strip_prefix(KEYCHAIN_PREFIX).filter(|key| !key.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let cipher = SecretCipher::from_key(&[7u8; 32]);
+        let sealed = cipher.encrypt("s3cret").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("s3cret"));
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), "s3cret");
+
+        // Fresh nonce every time
+        assert_ne!(sealed, cipher.encrypt("s3cret").unwrap());
+    }
+
+    #[test]
+    fn test_generated_key_roundtrip() {
+        let key = generate_key();
+        let sealed = SecretCipher::from_encoded_key(&key).unwrap().encrypt("s3cret").unwrap();
+        assert_eq!(SecretCipher::from_encoded_key(&key).unwrap().decrypt(&sealed).unwrap(), "s3cret");
+        assert!(SecretCipher::from_encoded_key(&generate_key()).unwrap().decrypt(&sealed).is_err());
+        assert!(SecretCipher::from_encoded_key("c2hvcnQ=").is_err());
+    }
+
+    #[test]
+    fn test_keychain_reference_roundtrip() {
+        let reference = keychain_reference("var-1");
+        assert_eq!(reference, "keychain:var-1");
+        assert_eq!(parse_keychain_reference(&reference), Some("var-1"));
+        assert_eq!(parse_keychain_reference("keychain:"), None);
+        assert_eq!(parse_keychain_reference("plain"), None);
+    }
+
+    #[test]
+    fn test_wrong_passphrase_fails_to_decrypt() {
+        let salt = generate_salt();
+        let right = SecretCipher::from_passphrase("correct horse", &salt).unwrap();
+        let wrong = SecretCipher::from_passphrase("battery staple", &salt).unwrap();
+        let sealed = right.encrypt(VERIFIER_PLAINTEXT).unwrap();
+        assert_eq!(right.decrypt(&sealed).unwrap(), VERIFIER_PLAINTEXT);
+        assert!(wrong.decrypt(&sealed).is_err());
+    }
+}
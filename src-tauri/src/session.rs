@@ -0,0 +1,210 @@
+// 🎓 TEACHING: Session auth - "log in once, reuse the token"
+// A request with `auth_type = "session"` points at another saved request (the login step).
+// The backend sends the login, pulls a token or cookie out of the response, caches it for
+// a while, and injects it into every request that uses the session.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionAuthConfig {
+    pub login_request_id: String, // Saved request that performs the login
+    pub extract_from: String,     // "body" (JSON path), "header", or "cookie"
+    pub extract_path: String,     // JSON path like `$.data.token`, or a header/cookie name
+    pub inject_as: String,        // "bearer", "header", or "cookie"
+    pub inject_name: Option<String>, // Header or cookie name to inject (not needed for "bearer")
+    pub ttl_secs: Option<u64>,    // How long the extracted value stays valid (defaults to 5 minutes)
+}
+
+impl SessionAuthConfig {
+    // 🎓 TEACHING: Pull the session value out of the login response
+    pub fn extract(&self, status: u16, headers: &HashMap<String, String>, body: &str) -> Result<String> {
+        if !(200..300).contains(&status) {
+            return Err(anyhow::anyhow!("Login request failed with status {}", status));
+        }
+
+        let value = match self.extract_from.as_str() {
+            "body" => {
+                let json: serde_json::Value = serde_json::from_str(body)
+                    .map_err(|e| anyhow::anyhow!("Login response is not JSON: {}", e))?;
+                match extract_json_path(&json, &self.extract_path) {
+                    Some(serde_json::Value::String(s)) => Some(s.clone()),
+                    Some(other) => Some(other.to_string()),
+                    None => None,
+                }
+            }
+            "header" => find_header(headers, &self.extract_path).map(|v| v.to_string()),
+            "cookie" => find_cookie(headers, &self.extract_path),
+            other => return Err(anyhow::anyhow!("Unsupported session extract source: {}", other)),
+        };
+
+        value.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not find {} in the login response {}",
+                self.extract_path,
+                self.extract_from
+            )
+        })
+    }
+
+    // 🎓 TEACHING: Attach the session value to the outgoing request
+    pub fn inject(&self, req_builder: reqwest::RequestBuilder, value: &str) -> Result<reqwest::RequestBuilder> {
+        let name = || {
+            self.inject_name
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("inject_name is required for {} injection", self.inject_as))
+        };
+
+        match self.inject_as.as_str() {
+            "bearer" => Ok(req_builder.bearer_auth(value)),
+            "header" => Ok(req_builder.header(name()?, value)),
+            "cookie" => Ok(req_builder.header("Cookie", format!("{}={}", name()?, value))),
+            other => Err(anyhow::anyhow!("Unsupported session inject target: {}", other)),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_secs.unwrap_or(300))
+    }
+
+    // 🎓 TEACHING: The login runs against the request's environment, so a token from staging
+    // must never be reused for a send against production. Each environment gets its own entry.
+    pub fn cache_key(&self, environment_id: Option<&str>) -> String {
+        serde_json::json!(["session", self.login_request_id, environment_id]).to_string()
+    }
+}
+
+// 🎓 TEACHING: In-memory cache of session values, keyed by login request and environment.
+// JWT bearer access tokens share it, keyed by `JwtBearerConfig::cache_key`.
+// Managed as Tauri state next to the database, so it lives as long as the app does.
+#[derive(Default)]
+pub struct SessionCache {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl SessionCache {
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((value, expires_at)) if *expires_at > Instant::now() => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: &str, value: String, ttl: Duration) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (value, Instant::now() + ttl));
+    }
+
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let count = entries.len();
+        entries.clear();
+        count
+    }
+}
+
+// 🎓 TEACHING: Minimal JSON path: `$.data.items[0].token` or `data.token`
+pub fn extract_json_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let path = path.trim().trim_start_matches('$').trim_start_matches('.');
+    let mut current = value;
+
+    for part in path.split('.').filter(|p| !p.is_empty()) {
+        let (key, indexes) = match part.find('[') {
+            Some(pos) => (&part[..pos], &part[pos..]),
+            None => (part, ""),
+        };
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indexes.split('[').filter(|i| !i.is_empty()) {
+            let index: usize = index.trim_end_matches(']').parse().ok()?;
+            current = current.get(index)?;
+        }
+    }
+
+    Some(current)
+}
+
+pub fn find_header<'a>(headers: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+// The value of cookie `name` from the response's Set-Cookie header(s)
+pub fn find_cookie(headers: &HashMap<String, String>, name: &str) -> Option<String> {
+    find_header(headers, "set-cookie").and_then(|set_cookie| {
+        set_cookie.split(&[',', '\n'][..]).find_map(|cookie| {
+            let (cookie_name, rest) = cookie.trim().split_once('=')?;
+            (cookie_name == name).then(|| rest.split(';').next().unwrap_or("").to_string())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(extract_from: &str, extract_path: &str) -> SessionAuthConfig {
+        SessionAuthConfig {
+            login_request_id: "login".to_string(),
+            extract_from: extract_from.to_string(),
+            extract_path: extract_path.to_string(),
+            inject_as: "bearer".to_string(),
+            inject_name: None,
+            ttl_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_extract_json_path() {
+        let json = serde_json::json!({ "data": { "items": [{ "token": "abc" }] } });
+        assert_eq!(extract_json_path(&json, "$.data.items[0].token"), Some(&serde_json::json!("abc")));
+        assert_eq!(extract_json_path(&json, "data.missing"), None);
+    }
+
+    #[test]
+    fn test_extract_from_body_header_and_cookie() {
+        let mut headers = HashMap::new();
+        headers.insert("X-Session".to_string(), "hdr-token".to_string());
+        headers.insert("set-cookie".to_string(), "sid=cookie-token; Path=/; HttpOnly".to_string());
+
+        let body = r#"{"access_token":"body-token"}"#;
+        assert_eq!(config("body", "$.access_token").extract(200, &headers, body).unwrap(), "body-token");
+        assert_eq!(config("header", "x-session").extract(200, &headers, body).unwrap(), "hdr-token");
+        assert_eq!(config("cookie", "sid").extract(200, &headers, body).unwrap(), "cookie-token");
+        assert!(config("body", "$.access_token").extract(401, &headers, body).is_err());
+    }
+
+    #[test]
+    fn test_session_cache_expiry() {
+        let cache = SessionCache::default();
+        cache.insert("a", "value".to_string(), Duration::from_secs(60));
+        cache.insert("b", "stale".to_string(), Duration::from_secs(0));
+        assert_eq!(cache.get("a").as_deref(), Some("value"));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.clear(), 1);
+    }
+
+    #[test]
+    fn test_session_cache_key_is_per_environment() {
+        let config = config("body", "$.token");
+        let cache = SessionCache::default();
+        cache.insert(&config.cache_key(Some("staging")), "staging-token".to_string(), Duration::from_secs(60));
+
+        assert_eq!(cache.get(&config.cache_key(Some("staging"))).as_deref(), Some("staging-token"));
+        assert_eq!(cache.get(&config.cache_key(Some("production"))), None);
+        assert_eq!(cache.get(&config.cache_key(None)), None);
+    }
+}
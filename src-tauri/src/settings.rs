@@ -0,0 +1,619 @@
+// 🎓 TEACHING: App settings
+// A few settings have to be known before the database is open, most importantly where the
+// database itself lives, so they're kept in a small JSON file in the platform config
+// directory (~/.config/<app> on Linux, %APPDATA% on Windows, ~/Library/Application Support
+// on macOS) instead of in the database. By default the database sits in the app data
+// directory, which is always writable, unlike wherever the app happened to be launched from.
+//
+// 🎓 TEACHING: Workspaces
+// Each workspace is a separate database file, so data for one client never shows up in
+// another. The settings file is the registry: it lists the workspaces and which one is open.
+// The "Default" workspace is the database the app has always used and isn't listed there.
+
+use crate::logging::LoggingConfig;
+use crate::runner::RetryPolicy;
+use crate::sync::SyncConfig;
+use crate::trace::TraceContext;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const SETTINGS_FILE: &str = "settings.json";
+pub const DATABASE_FILE: &str = "openrequest.db";
+// Where releases before the app data directory was used kept the database
+pub const LEGACY_DATABASE_PATH: &str = "./openrequest.db";
+pub const DEFAULT_WORKSPACE_ID: &str = "default";
+pub const DEFAULT_WORKSPACE_NAME: &str = "Default";
+// Under the app data directory, one database per workspace
+pub const WORKSPACES_DIR: &str = "workspaces";
+// SQLite runs one write at a time whatever the pool size, so extra connections only help reads
+pub const DEFAULT_DATABASE_MAX_CONNECTIONS: u32 = 8;
+pub const MAX_DATABASE_MAX_CONNECTIONS: u32 = 64;
+pub const DEFAULT_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+pub const DEFAULT_HISTORY_MAX_BYTES: u64 = 512 * 1024 * 1024;
+// Response bodies above this are written to disk and paged instead of returned whole
+pub const DEFAULT_LARGE_RESPONSE_BYTES: u64 = 10 * 1024 * 1024;
+pub const MIN_LARGE_RESPONSE_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct AppSettings {
+    #[serde(default)]
+    pub database_path: Option<String>, // Default workspace's custom database file; None means the default location
+    #[serde(default)]
+    pub workspaces: Vec<Workspace>, // Workspaces added besides the default one
+    #[serde(default)]
+    pub active_workspace: Option<String>, // None means the default workspace
+    #[serde(default)]
+    pub workspace_settings: HashMap<String, serde_json::Value>, // Per-workspace preferences, by workspace id
+    #[serde(default)]
+    pub database_max_connections: Option<u32>, // Database pool size; None means the default
+    #[serde(default)]
+    pub offline: bool, // Answer sends from the response cache instead of the network
+    #[serde(default)]
+    pub invalidate_cache_on_write: bool, // A successful POST/PUT/PATCH/DELETE drops cached GETs of its path
+    #[serde(default)]
+    pub cache_max_bytes: Option<u64>, // Response cache size limit; None means the default
+    #[serde(default)]
+    pub logging: LoggingConfig, // The activity log written to the platform log directory
+    #[serde(default)]
+    pub history_retention: HistoryRetention,
+    #[serde(default)]
+    pub trace_context: TraceContext, // Trace headers for sends outside collections that set their own
+    #[serde(default)]
+    pub large_response_bytes: Option<u64>, // None means the default
+    #[serde(default)]
+    pub request_defaults: RequestDefaults,
+    #[serde(default)]
+    pub workspace_sync: HashMap<String, SyncConfig>, // Folder each workspace is mirrored to, by workspace id
+}
+
+// 🎓 TEACHING: Defaults for every send. A request's own setting always wins; these only
+// fill in what it leaves unset, and default headers are added unless the request already
+// has a header with the same name.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct RequestDefaults {
+    pub timeout_ms: Option<u64>,
+    pub follow_redirects: Option<bool>,
+    pub max_redirects: Option<usize>,
+    pub proxy_url: Option<String>,
+    pub verify_tls: Option<bool>,
+    pub http_version: Option<String>, // "auto", "http1" or "http2"
+    pub use_cookie_jar: Option<bool>,
+    pub default_headers: HashMap<String, String>,
+    pub retry: Option<RetryPolicy>, // Only GET and HEAD are ever retried
+}
+
+impl RequestDefaults {
+    pub fn validate(&self) -> Result<()> {
+        if self.timeout_ms == Some(0) {
+            return Err(anyhow::anyhow!("The default timeout must be more than 0 ms; leave it empty for no limit"));
+        }
+        if let Some(proxy_url) = &self.proxy_url {
+            url::Url::parse(proxy_url).map_err(|e| anyhow::anyhow!("Invalid proxy URL {}: {}", proxy_url, e))?;
+        }
+        if let Some(version) = &self.http_version {
+            if !matches!(version.as_str(), "auto" | "http1" | "http2") {
+                return Err(anyhow::anyhow!("HTTP version must be auto, http1 or http2, not {}", version));
+            }
+        }
+        crate::default_headers::validate(&self.default_headers)
+    }
+}
+
+// 🎓 TEACHING: The settings screen's view of the app-wide options, read and saved in one go.
+// Workspaces, the database location and logging keep their own commands.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GeneralSettings {
+    pub request_defaults: RequestDefaults,
+    pub offline: bool,
+    pub invalidate_cache_on_write: bool,
+    pub cache_max_bytes: u64,
+    pub large_response_bytes: u64,
+    pub trace_context: TraceContext,
+}
+
+// 🎓 TEACHING: How much run, monitor, send and webhook history to keep. Each limit is
+// optional; the background maintenance prunes whatever goes past any of them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct HistoryRetention {
+    pub max_entries: Option<u64>, // Per kind of history, newest kept
+    pub max_age_days: Option<u32>,
+    pub max_bytes: Option<u64>, // All history together, roughly
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        HistoryRetention {
+            max_entries: None,
+            max_age_days: None,
+            max_bytes: Some(DEFAULT_HISTORY_MAX_BYTES),
+        }
+    }
+}
+
+impl HistoryRetention {
+    pub fn max_age(&self) -> Option<chrono::Duration> {
+        self.max_age_days.map(|days| chrono::Duration::days(days.into()))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub database_path: Option<String>, // Custom database file; None means workspaces/<id>.db
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WorkspaceInfo {
+    pub id: String,
+    pub name: String,
+    pub database_path: String,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DatabaseLocation {
+    pub path: String,
+    pub default_path: String,
+    pub is_custom: bool,
+}
+
+impl AppSettings {
+    // A missing file just means nothing has been changed yet
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = config_dir.join(SETTINGS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&text).map_err(|e| anyhow::anyhow!("Invalid settings file {}: {}", path.display(), e))
+    }
+
+    // 🎓 TEACHING: Write to a temporary file and rename it over the old one, so a crash
+    // mid-write can't leave a half-written settings file behind
+    pub fn save(&self, config_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(config_dir)?;
+        let path = config_dir.join(SETTINGS_FILE);
+        let temp = config_dir.join(format!("{}.tmp", SETTINGS_FILE));
+        std::fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temp, &path)?;
+        Ok(())
+    }
+
+    // The open workspace's database
+    pub fn database_path(&self, data_dir: &Path) -> PathBuf {
+        self.workspace_database_path(self.active_workspace_id(), data_dir)
+    }
+
+    pub fn database_location(&self, data_dir: &Path) -> DatabaseLocation {
+        let id = self.active_workspace_id();
+        DatabaseLocation {
+            path: self.database_path(data_dir).display().to_string(),
+            default_path: default_database_path(id, data_dir).display().to_string(),
+            is_custom: self.custom_database_path(id).is_some(),
+        }
+    }
+
+    // Record where the open workspace's database now lives
+    pub fn set_database_path(&mut self, data_dir: &Path, path: &Path) {
+        let id = self.active_workspace_id().to_string();
+        let custom = (path != default_database_path(&id, data_dir)).then(|| path.display().to_string());
+        match self.workspaces.iter_mut().find(|workspace| workspace.id == id) {
+            Some(workspace) => workspace.database_path = custom,
+            None => self.database_path = custom,
+        }
+    }
+
+    pub fn database_max_connections(&self) -> u32 {
+        self.database_max_connections
+            .unwrap_or(DEFAULT_DATABASE_MAX_CONNECTIONS)
+            .clamp(1, MAX_DATABASE_MAX_CONNECTIONS)
+    }
+
+    pub fn set_database_max_connections(&mut self, max_connections: Option<u32>) -> Result<()> {
+        if let Some(max_connections) = max_connections {
+            if !(1..=MAX_DATABASE_MAX_CONNECTIONS).contains(&max_connections) {
+                return Err(anyhow::anyhow!(
+                    "Database connections must be between 1 and {}",
+                    MAX_DATABASE_MAX_CONNECTIONS
+                ));
+            }
+        }
+        self.database_max_connections = max_connections;
+        Ok(())
+    }
+
+    pub fn cache_max_bytes(&self) -> u64 {
+        self.cache_max_bytes.unwrap_or(DEFAULT_CACHE_MAX_BYTES)
+    }
+
+    pub fn set_cache_max_bytes(&mut self, max_bytes: Option<u64>) -> Result<()> {
+        if max_bytes == Some(0) {
+            return Err(anyhow::anyhow!("The cache size limit must be more than 0 bytes"));
+        }
+        self.cache_max_bytes = max_bytes;
+        Ok(())
+    }
+
+    pub fn large_response_bytes(&self) -> u64 {
+        self.large_response_bytes.unwrap_or(DEFAULT_LARGE_RESPONSE_BYTES)
+    }
+
+    pub fn set_large_response_bytes(&mut self, max_bytes: Option<u64>) -> Result<()> {
+        if max_bytes.is_some_and(|bytes| bytes < MIN_LARGE_RESPONSE_BYTES) {
+            return Err(anyhow::anyhow!("The large response size must be at least {} bytes", MIN_LARGE_RESPONSE_BYTES));
+        }
+        self.large_response_bytes = max_bytes;
+        Ok(())
+    }
+
+    pub fn general_settings(&self) -> GeneralSettings {
+        GeneralSettings {
+            request_defaults: self.request_defaults.clone(),
+            offline: self.offline,
+            invalidate_cache_on_write: self.invalidate_cache_on_write,
+            cache_max_bytes: self.cache_max_bytes(),
+            large_response_bytes: self.large_response_bytes(),
+            trace_context: self.trace_context.clone(),
+        }
+    }
+
+    // Checks everything before changing anything, so a bad value leaves the settings as they were
+    pub fn set_general_settings(&mut self, general: GeneralSettings) -> Result<()> {
+        general.request_defaults.validate()?;
+        let mut updated = self.clone();
+        updated.set_cache_max_bytes(Some(general.cache_max_bytes))?;
+        updated.set_large_response_bytes(Some(general.large_response_bytes))?;
+        updated.request_defaults = general.request_defaults;
+        updated.offline = general.offline;
+        updated.invalidate_cache_on_write = general.invalidate_cache_on_write;
+        updated.trace_context = general.trace_context;
+        *self = updated;
+        Ok(())
+    }
+
+    pub fn set_history_retention(&mut self, retention: HistoryRetention) -> Result<()> {
+        if retention.max_entries == Some(0) || retention.max_age_days == Some(0) || retention.max_bytes == Some(0) {
+            return Err(anyhow::anyhow!("History limits must be more than 0; leave a limit empty to turn it off"));
+        }
+        self.history_retention = retention;
+        Ok(())
+    }
+
+    pub fn set_logging_config(&mut self, config: LoggingConfig) -> Result<()> {
+        config.validate()?;
+        self.logging = config;
+        Ok(())
+    }
+
+    // A workspace that has since been removed falls back to the default one
+    pub fn active_workspace_id(&self) -> &str {
+        match &self.active_workspace {
+            Some(id) if self.workspaces.iter().any(|workspace| &workspace.id == id) => id.as_str(),
+            _ => DEFAULT_WORKSPACE_ID,
+        }
+    }
+
+    pub fn workspace_database_path(&self, id: &str, data_dir: &Path) -> PathBuf {
+        match self.custom_database_path(id) {
+            Some(path) => PathBuf::from(path),
+            None => default_database_path(id, data_dir),
+        }
+    }
+
+    fn custom_database_path(&self, id: &str) -> Option<&str> {
+        if id == DEFAULT_WORKSPACE_ID {
+            return self.database_path.as_deref();
+        }
+        self.workspaces
+            .iter()
+            .find(|workspace| workspace.id == id)
+            .and_then(|workspace| workspace.database_path.as_deref())
+    }
+
+    // The default workspace first, then the others in the order they were added
+    pub fn workspace_list(&self, data_dir: &Path) -> Vec<WorkspaceInfo> {
+        let active = self.active_workspace_id();
+        std::iter::once((DEFAULT_WORKSPACE_ID, DEFAULT_WORKSPACE_NAME))
+            .chain(self.workspaces.iter().map(|workspace| (workspace.id.as_str(), workspace.name.as_str())))
+            .map(|(id, name)| WorkspaceInfo {
+                id: id.to_string(),
+                name: name.to_string(),
+                database_path: self.workspace_database_path(id, data_dir).display().to_string(),
+                is_active: id == active,
+            })
+            .collect()
+    }
+
+    pub fn has_workspace(&self, id: &str) -> bool {
+        id == DEFAULT_WORKSPACE_ID || self.workspaces.iter().any(|workspace| workspace.id == id)
+    }
+
+    pub fn workspace(&self, id: &str, data_dir: &Path) -> Result<WorkspaceInfo> {
+        self.workspace_list(data_dir)
+            .into_iter()
+            .find(|workspace| workspace.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Workspace not found: {}", id))
+    }
+
+    // Names are how workspaces are told apart in the switcher, so they must be unique
+    pub fn add_workspace(&mut self, name: &str, database_path: Option<PathBuf>) -> Result<Workspace> {
+        let name = self.check_workspace_name(None, name)?;
+        let workspace = Workspace {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            database_path: database_path.map(|path| path.display().to_string()),
+        };
+        self.workspaces.push(workspace.clone());
+        Ok(workspace)
+    }
+
+    pub fn rename_workspace(&mut self, id: &str, name: &str) -> Result<()> {
+        if id == DEFAULT_WORKSPACE_ID {
+            return Err(anyhow::anyhow!("The default workspace can't be renamed"));
+        }
+        let name = self.check_workspace_name(Some(id), name)?;
+        let workspace = self
+            .workspaces
+            .iter_mut()
+            .find(|workspace| workspace.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Workspace not found: {}", id))?;
+        workspace.name = name;
+        Ok(())
+    }
+
+    // Only forgets the workspace; the caller decides what happens to its database
+    pub fn remove_workspace(&mut self, id: &str) -> Result<Workspace> {
+        if id == DEFAULT_WORKSPACE_ID {
+            return Err(anyhow::anyhow!("The default workspace can't be removed"));
+        }
+        if self.active_workspace_id() == id {
+            return Err(anyhow::anyhow!("Switch to another workspace before removing this one"));
+        }
+        let index = self
+            .workspaces
+            .iter()
+            .position(|workspace| workspace.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Workspace not found: {}", id))?;
+        self.workspace_settings.remove(id);
+        self.workspace_sync.remove(id);
+        Ok(self.workspaces.remove(index))
+    }
+
+    fn check_workspace_name(&self, id: Option<&str>, name: &str) -> Result<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("Workspace name cannot be empty"));
+        }
+        let taken = std::iter::once((DEFAULT_WORKSPACE_ID, DEFAULT_WORKSPACE_NAME))
+            .chain(self.workspaces.iter().map(|workspace| (workspace.id.as_str(), workspace.name.as_str())))
+            .any(|(other_id, other_name)| Some(other_id) != id && other_name.eq_ignore_ascii_case(name));
+        if taken {
+            return Err(anyhow::anyhow!("A workspace named {} already exists", name));
+        }
+        Ok(name.to_string())
+    }
+}
+
+fn default_database_path(workspace_id: &str, data_dir: &Path) -> PathBuf {
+    if workspace_id == DEFAULT_WORKSPACE_ID {
+        data_dir.join(DATABASE_FILE)
+    } else {
+        data_dir.join(WORKSPACES_DIR).join(format!("{}.db", workspace_id))
+    }
+}
+
+pub fn database_url(path: &Path) -> String {
+    format!("sqlite:{}?mode=rwc", path.display())
+}
+
+// Where a database moved to `requested` should end up. A directory gets the default file
+// name; an existing file is refused rather than overwritten.
+pub fn target_database_path(requested: &str) -> Result<PathBuf> {
+    let requested = requested.trim();
+    if requested.is_empty() {
+        return Err(anyhow::anyhow!("Choose a location for the database"));
+    }
+    let mut path = PathBuf::from(requested);
+    if !path.is_absolute() {
+        return Err(anyhow::anyhow!("The database location must be an absolute path"));
+    }
+    if path.is_dir() {
+        path.push(DATABASE_FILE);
+    }
+    if path.exists() {
+        return Err(anyhow::anyhow!("A file already exists at {}", path.display()));
+    }
+    match path.parent() {
+        Some(parent) if parent.is_dir() => Ok(path),
+        _ => Err(anyhow::anyhow!("Folder does not exist: {}", path.display())),
+    }
+}
+
+// SQLite keeps recent writes in -wal/-shm files next to the database
+pub fn remove_database_files(path: &Path) -> std::io::Result<()> {
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let sidecar = PathBuf::from(sidecar);
+        if sidecar.exists() {
+            std::fs::remove_file(sidecar)?;
+        }
+    }
+    std::fs::remove_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("openrequest-settings-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let dir = temp_dir();
+        assert_eq!(AppSettings::load(&dir).unwrap(), AppSettings::default());
+
+        let data_dir = dir.join("data");
+        let settings = AppSettings::default();
+        assert_eq!(settings.database_path(&data_dir), data_dir.join(DATABASE_FILE));
+        assert!(!settings.database_location(&data_dir).is_custom);
+
+        let custom = AppSettings {
+            database_path: Some("/srv/openrequest/work.db".to_string()),
+            ..AppSettings::default()
+        };
+        custom.save(&dir).unwrap();
+        let loaded = AppSettings::load(&dir).unwrap();
+        assert_eq!(loaded, custom);
+        assert_eq!(loaded.database_path(&data_dir), PathBuf::from("/srv/openrequest/work.db"));
+        assert!(loaded.database_location(&data_dir).is_custom);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_database_max_connections() {
+        let mut settings = AppSettings::default();
+        assert_eq!(settings.database_max_connections(), DEFAULT_DATABASE_MAX_CONNECTIONS);
+        settings.set_database_max_connections(Some(2)).unwrap();
+        assert_eq!(settings.database_max_connections(), 2);
+        assert!(settings.set_database_max_connections(Some(0)).is_err());
+        assert!(settings.set_database_max_connections(Some(MAX_DATABASE_MAX_CONNECTIONS + 1)).is_err());
+        assert_eq!(settings.database_max_connections(), 2);
+        settings.set_database_max_connections(None).unwrap();
+        assert_eq!(settings.database_max_connections(), DEFAULT_DATABASE_MAX_CONNECTIONS);
+
+        // A hand-edited file can't turn the pool off
+        settings.database_max_connections = Some(0);
+        assert_eq!(settings.database_max_connections(), 1);
+    }
+
+    #[test]
+    fn test_cache_max_bytes() {
+        let mut settings = AppSettings::default();
+        assert_eq!(settings.cache_max_bytes(), DEFAULT_CACHE_MAX_BYTES);
+        settings.set_cache_max_bytes(Some(1024)).unwrap();
+        assert_eq!(settings.cache_max_bytes(), 1024);
+        assert!(settings.set_cache_max_bytes(Some(0)).is_err());
+        settings.set_cache_max_bytes(None).unwrap();
+        assert_eq!(settings.cache_max_bytes(), DEFAULT_CACHE_MAX_BYTES);
+    }
+
+    #[test]
+    fn test_history_retention() {
+        let mut settings = AppSettings::default();
+        assert_eq!(settings.history_retention.max_bytes, Some(DEFAULT_HISTORY_MAX_BYTES));
+        let retention = HistoryRetention { max_entries: Some(100), max_age_days: Some(30), max_bytes: None };
+        settings.set_history_retention(retention.clone()).unwrap();
+        assert_eq!(settings.history_retention.max_age(), Some(chrono::Duration::days(30)));
+        assert!(settings.set_history_retention(HistoryRetention { max_entries: Some(0), ..retention }).is_err());
+
+        // Limits missing from the file get their defaults
+        let loaded: AppSettings = serde_json::from_str(r#"{"history_retention":{"max_age_days":7}}"#).unwrap();
+        assert_eq!(loaded.history_retention.max_age_days, Some(7));
+        assert_eq!(loaded.history_retention.max_bytes, Some(DEFAULT_HISTORY_MAX_BYTES));
+    }
+
+    #[test]
+    fn test_large_response_bytes() {
+        let mut settings = AppSettings::default();
+        assert_eq!(settings.large_response_bytes(), DEFAULT_LARGE_RESPONSE_BYTES);
+        settings.set_large_response_bytes(Some(1024 * 1024)).unwrap();
+        assert_eq!(settings.large_response_bytes(), 1024 * 1024);
+        assert!(settings.set_large_response_bytes(Some(100)).is_err());
+        settings.set_large_response_bytes(None).unwrap();
+        assert_eq!(settings.large_response_bytes(), DEFAULT_LARGE_RESPONSE_BYTES);
+    }
+
+    #[test]
+    fn test_general_settings() {
+        let mut settings = AppSettings::default();
+        let mut general = settings.general_settings();
+        assert_eq!(general.cache_max_bytes, DEFAULT_CACHE_MAX_BYTES);
+
+        general.request_defaults.timeout_ms = Some(30_000);
+        general.request_defaults.default_headers.insert("User-Agent".to_string(), "openrequest".to_string());
+        general.offline = true;
+        settings.set_general_settings(general.clone()).unwrap();
+        assert_eq!(settings.general_settings(), general);
+        assert_eq!(settings.request_defaults.timeout_ms, Some(30_000));
+
+        // One bad value and nothing is saved
+        let mut bad = general.clone();
+        bad.offline = false;
+        bad.request_defaults.http_version = Some("http3".to_string());
+        assert!(settings.set_general_settings(bad).is_err());
+        let mut bad = general.clone();
+        bad.request_defaults.default_headers.insert("Bad Header".to_string(), "x".to_string());
+        assert!(settings.set_general_settings(bad).is_err());
+        let mut bad = general.clone();
+        bad.request_defaults.proxy_url = Some("not a url".to_string());
+        assert!(settings.set_general_settings(bad).is_err());
+        assert!(settings.set_general_settings(GeneralSettings { cache_max_bytes: 0, ..general.clone() }).is_err());
+        assert_eq!(settings.general_settings(), general);
+    }
+
+    #[test]
+    fn test_workspaces() {
+        let data_dir = PathBuf::from("/data");
+        let mut settings = AppSettings::default();
+        let work = settings.add_workspace(" Work ", None).unwrap();
+        assert_eq!(work.name, "Work");
+        assert!(settings.add_workspace("work", None).is_err());
+        assert!(settings.add_workspace("default", None).is_err());
+        assert!(settings.add_workspace("  ", None).is_err());
+
+        // Nothing changes until the workspace is switched to
+        assert_eq!(settings.database_path(&data_dir), data_dir.join(DATABASE_FILE));
+        settings.active_workspace = Some(work.id.clone());
+        let work_path = data_dir.join(WORKSPACES_DIR).join(format!("{}.db", work.id));
+        assert_eq!(settings.database_path(&data_dir), work_path);
+        assert!(!settings.database_location(&data_dir).is_custom);
+
+        settings.set_database_path(&data_dir, Path::new("/srv/work.db"));
+        assert_eq!(settings.database_path(&data_dir), PathBuf::from("/srv/work.db"));
+        assert_eq!(settings.database_path, None);
+        settings.set_database_path(&data_dir, &work_path);
+        assert!(!settings.database_location(&data_dir).is_custom);
+
+        let list = settings.workspace_list(&data_dir);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].name, DEFAULT_WORKSPACE_NAME);
+        assert!(!list[0].is_active && list[1].is_active);
+
+        assert!(settings.remove_workspace(&work.id).is_err());
+        settings.active_workspace = None;
+        settings.rename_workspace(&work.id, "Client A").unwrap();
+        assert_eq!(settings.remove_workspace(&work.id).unwrap().name, "Client A");
+
+        // A workspace that no longer exists falls back to the default one
+        settings.active_workspace = Some(work.id);
+        assert_eq!(settings.active_workspace_id(), DEFAULT_WORKSPACE_ID);
+    }
+
+    #[test]
+    fn test_target_database_path() {
+        let dir = temp_dir();
+        assert_eq!(target_database_path(dir.to_str().unwrap()).unwrap(), dir.join(DATABASE_FILE));
+        let file = dir.join("work.db");
+        assert_eq!(target_database_path(file.to_str().unwrap()).unwrap(), file);
+
+        std::fs::write(&file, "").unwrap();
+        assert!(target_database_path(file.to_str().unwrap()).is_err());
+        assert!(target_database_path("relative.db").is_err());
+        assert!(target_database_path(dir.join("missing/work.db").to_str().unwrap()).is_err());
+
+        remove_database_files(&file).unwrap();
+        assert!(!file.exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+// 🎓 TEACHING: Streaming responses
+// Some APIs never "finish" a response quickly: NDJSON feeds send one JSON document per
+// line, and chunked responses trickle data over minutes. Instead of waiting for the whole
+// body, the send pipeline can forward each line/chunk to the frontend as it arrives,
+// and the user can stop the stream halfway.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    // One NDJSON line; `json` is None if the line isn't valid JSON
+    Line {
+        stream_id: String,
+        line: String,
+        json: Option<serde_json::Value>,
+    },
+    // A piece of any other streamed body
+    Chunk {
+        stream_id: String,
+        data: String,
+    },
+    End {
+        stream_id: String,
+        stopped: bool, // True if the user stopped the stream
+    },
+}
+
+pub type EventSink = Arc<dyn Fn(StreamEvent) + Send + Sync>;
+
+// Where a streamed send reports to, and how it learns it should stop
+#[derive(Clone)]
+pub struct StreamTarget {
+    pub stream_id: String,
+    pub sink: EventSink,
+    pub stop: Arc<StopSignal>,
+}
+
+// 🎓 TEACHING: A flag plus a wakeup, so a stream waiting on a slow server stops right away
+#[derive(Default)]
+pub struct StopSignal {
+    stopped: AtomicBool,
+    notify: Notify,
+}
+
+impl StopSignal {
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.notify.notify_one();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    pub async fn wait(&self) {
+        while !self.is_stopped() {
+            self.notify.notified().await;
+        }
+    }
+}
+
+// 🎓 TEACHING: Stop flags for streams in flight, keyed by the frontend's stream id
+#[derive(Default)]
+pub struct StreamRegistry {
+    active: Mutex<HashMap<String, Arc<StopSignal>>>,
+}
+
+impl StreamRegistry {
+    pub fn start(&self, stream_id: &str) -> Arc<StopSignal> {
+        let stop = Arc::new(StopSignal::default());
+        self.active.lock().unwrap().insert(stream_id.to_string(), stop.clone());
+        stop
+    }
+
+    // Returns false if no such stream is running
+    pub fn stop(&self, stream_id: &str) -> bool {
+        match self.active.lock().unwrap().get(stream_id) {
+            Some(stop) => {
+                stop.stop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn finish(&self, stream_id: &str) {
+        self.active.lock().unwrap().remove(stream_id);
+    }
+}
+
+// Only bodies that arrive over time are streamed; everything else is read in one go as before
+pub fn should_stream(content_type: Option<&str>, transfer_encoding: Option<&str>) -> bool {
+    is_ndjson(content_type)
+        || content_type.is_some_and(|ct| ct.to_lowercase().starts_with("text/event-stream"))
+        || transfer_encoding.is_some_and(|te| te.to_lowercase().contains("chunked"))
+}
+
+// NDJSON (and its JSON Lines alias) is split into lines; anything else is passed through as chunks
+pub fn is_ndjson(content_type: Option<&str>) -> bool {
+    let mime = content_type.unwrap_or("").split(';').next().unwrap_or("").trim().to_lowercase();
+    matches!(mime.as_str(), "application/x-ndjson" | "application/ndjson" | "application/jsonl" | "application/x-jsonlines")
+}
+
+// 🎓 TEACHING: Network chunks don't line up with lines or even UTF-8 characters,
+// so keep the incomplete tail around until the next chunk completes it.
+pub struct StreamDecoder {
+    ndjson: bool,
+    pending: Vec<u8>,
+}
+
+impl StreamDecoder {
+    pub fn new(ndjson: bool) -> Self {
+        Self {
+            ndjson,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, stream_id: &str, bytes: &[u8]) -> Vec<StreamEvent> {
+        self.pending.extend_from_slice(bytes);
+        if self.ndjson {
+            let Some(last_newline) = self.pending.iter().rposition(|b| *b == b'\n') else {
+                return Vec::new();
+            };
+            let complete: Vec<u8> = self.pending.drain(..=last_newline).collect();
+            String::from_utf8_lossy(&complete)
+                .lines()
+                .filter_map(|line| line_event(stream_id, line))
+                .collect()
+        } else {
+            let valid_up_to = match std::str::from_utf8(&self.pending) {
+                Ok(_) => self.pending.len(),
+                // An incomplete character at the end waits for more bytes; anything else is just invalid
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => self.pending.len(),
+            };
+            if valid_up_to == 0 {
+                return Vec::new();
+            }
+            let complete: Vec<u8> = self.pending.drain(..valid_up_to).collect();
+            vec![StreamEvent::Chunk {
+                stream_id: stream_id.to_string(),
+                data: String::from_utf8_lossy(&complete).to_string(),
+            }]
+        }
+    }
+
+    // Whatever is left when the body ends (a last line without a trailing newline)
+    pub fn finish(&mut self, stream_id: &str) -> Vec<StreamEvent> {
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+        let rest = String::from_utf8_lossy(&std::mem::take(&mut self.pending)).to_string();
+        if self.ndjson {
+            line_event(stream_id, &rest).into_iter().collect()
+        } else {
+            vec![StreamEvent::Chunk {
+                stream_id: stream_id.to_string(),
+                data: rest,
+            }]
+        }
+    }
+}
+
+fn line_event(stream_id: &str, line: &str) -> Option<StreamEvent> {
+    let line = line.trim_end_matches('\r');
+    if line.trim().is_empty() {
+        return None;
+    }
+    Some(StreamEvent::Line {
+        stream_id: stream_id.to_string(),
+        line: line.to_string(),
+        json: serde_json::from_str(line).ok(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(events: &[StreamEvent]) -> Vec<(String, bool)> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                StreamEvent::Line { line, json, .. } => Some((line.clone(), json.is_some())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ndjson_lines_across_chunks() {
+        let mut decoder = StreamDecoder::new(true);
+        let events = decoder.push("s", b"{\"a\":1}\n{\"b\"");
+        assert_eq!(lines(&events), vec![("{\"a\":1}".to_string(), true)]);
+        let events = decoder.push("s", b":2}\r\n\nnot json\n{\"c\":");
+        assert_eq!(lines(&events), vec![("{\"b\":2}".to_string(), true), ("not json".to_string(), false)]);
+        assert_eq!(lines(&decoder.finish("s")), vec![("{\"c\":".to_string(), false)]);
+    }
+
+    #[test]
+    fn test_chunks_keep_utf8_characters_whole() {
+        let mut decoder = StreamDecoder::new(false);
+        let euro = "€".as_bytes();
+        assert!(decoder.push("s", &euro[..1]).is_empty());
+        let events = decoder.push("s", &euro[1..]);
+        assert!(matches!(&events[..], [StreamEvent::Chunk { data, .. }] if data == "€"));
+    }
+
+    #[test]
+    fn test_is_ndjson() {
+        assert!(is_ndjson(Some("application/x-ndjson; charset=utf-8")));
+        assert!(is_ndjson(Some("application/jsonl")));
+        assert!(!is_ndjson(Some("application/json")));
+        assert!(!is_ndjson(None));
+
+        assert!(should_stream(Some("text/plain"), Some("chunked")));
+        assert!(should_stream(Some("application/x-ndjson"), None));
+        assert!(!should_stream(Some("application/json"), None));
+    }
+
+    #[tokio::test]
+    async fn test_registry_stop() {
+        let registry = StreamRegistry::default();
+        let stop = registry.start("s1");
+        assert!(registry.stop("s1"));
+        assert!(stop.is_stopped());
+        stop.wait().await;
+        registry.finish("s1");
+        assert!(!registry.stop("s1"));
+    }
+}
@@ -0,0 +1,698 @@
+// 🎓 TEACHING: Workspace sync
+// A workspace can be mirrored to a folder of small text files, so a team can keep its API
+// workspace in git and review changes to it in pull requests:
+//
+//   <folder>/
+//     collections/Users API/collection.bru     one folder per collection, .bru files as in
+//     collections/Users API/Get user.bru       bru.rs, with a subfolder per folder
+//     collections/Users API/Admin/collection.bru
+//     environments/Staging.json                one environment export per file
+//
+// While sync is on, a background task compares the workspace, the folder and what it last
+// synced every SYNC_TICK, one collection folder or environment file at a time:
+//   - only the workspace changed: its version is written to the folder
+//   - only the folder changed (an edit, a `git pull`): the files are read into the workspace
+//   - both changed: the workspace wins and the clash is reported
+// Deleting a collection's folder or an environment's file deletes it from the workspace,
+// except when the whole folder is missing: then everything is written out again.
+//
+// Secrets never go to the folder. Credentials are masked as in exports and secret variables
+// are left blank, and reading a file back keeps the stored value of anything still exactly
+// as it was written, so the masks don't overwrite the real secrets.
+
+use crate::bru;
+use crate::database::{Collection, Database};
+use crate::error::AppError;
+use crate::importer_exporter::{EnvironmentExport, JsonCollection};
+use crate::settings::AppSettings;
+use crate::DatabaseState;
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const COLLECTIONS_DIR: &str = "collections";
+pub const ENVIRONMENTS_DIR: &str = "environments";
+const SYNC_TICK: Duration = Duration::from_secs(2);
+
+// Kept per workspace in the settings file
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct SyncConfig {
+    pub dir: String,
+    #[serde(default)]
+    pub enabled: bool, // Off keeps the folder but only syncs when asked to
+}
+
+pub fn validate(config: &SyncConfig) -> Result<()> {
+    let dir = Path::new(config.dir.trim());
+    if !dir.is_absolute() {
+        return Err(anyhow!("The sync folder must be an absolute path"));
+    }
+    if dir.exists() && !dir.is_dir() {
+        return Err(anyhow!("{} is not a folder", dir.display()));
+    }
+    Ok(())
+}
+
+// Path under the sync folder ("collections/Users API/Get user.bru") -> file contents
+pub type Files = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    InSync,
+    Write,    // The workspace changed
+    Import,   // The folder changed
+    Conflict, // Both changed
+}
+
+// 🎓 TEACHING: A three-way comparison; no files means the collection or environment doesn't
+// exist on that side, and no `synced` means it has never been synced
+pub fn decide(app: &Files, disk: &Files, synced: Option<&Files>) -> Action {
+    if app == disk {
+        Action::InSync
+    } else if synced.map_or(disk.is_empty(), |synced| synced == disk) {
+        Action::Write
+    } else if synced.map_or(app.is_empty(), |synced| synced == app) {
+        Action::Import
+    } else {
+        Action::Conflict
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct SyncReport {
+    pub written: Vec<String>,   // Collection folders and environment files written from the workspace
+    pub imported: Vec<String>,  // ...and read into it
+    pub conflicts: Vec<String>, // Changed on both sides; the workspace's version was written
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Collection(String),
+    Environment(String),
+}
+
+#[derive(Default)]
+struct SyncState {
+    target: Option<(String, String)>, // Workspace id and folder the baselines below are for
+    synced: HashMap<String, Files>,   // What each collection folder or environment file held when last in sync
+}
+
+// 🎓 TEACHING: Managed as Tauri state; the lock keeps the background task and `sync_workspace`
+// from syncing at the same time
+#[derive(Default)]
+pub struct WorkspaceSync {
+    state: tokio::sync::Mutex<SyncState>,
+}
+
+impl WorkspaceSync {
+    pub async fn run(&self, db: &Database, workspace_id: &str, dir: &Path) -> SyncReport {
+        let mut state = self.state.lock().await;
+        let target = (workspace_id.to_string(), dir.display().to_string());
+        if state.target.as_ref() != Some(&target) || !dir.exists() {
+            *state = SyncState { target: Some(target), ..Default::default() };
+        }
+        let mut report = SyncReport::default();
+        if let Err(e) = sync(db, dir, &mut state.synced, &mut report).await {
+            report.errors.push(e.message);
+        }
+        report
+    }
+}
+
+async fn sync(
+    db: &Database,
+    dir: &Path,
+    synced: &mut HashMap<String, Files>,
+    report: &mut SyncReport,
+) -> Result<(), AppError> {
+    std::fs::create_dir_all(dir)?;
+    let app = render(db).await?;
+    let mut disk = scan(dir)?;
+    let no_files = Files::new();
+
+    let keys: Vec<String> = app.keys().chain(disk.keys()).cloned().collect::<BTreeSet<_>>().into_iter().collect();
+    let mut imports = Vec::new();
+    for key in keys {
+        let app_files = app.get(&key).map_or(&no_files, |(_, files)| files);
+        let disk_files = disk.get(&key).unwrap_or(&no_files);
+        match decide(app_files, disk_files, synced.get(&key)) {
+            Action::InSync => {}
+            Action::Import => {
+                imports.push(key);
+                continue;
+            }
+            action => {
+                if let Err(e) = write_unit(dir, &key, disk_files, app_files) {
+                    report.errors.push(format!("{}: {}", key, e));
+                    continue;
+                }
+                match action {
+                    Action::Conflict => report.conflicts.push(key.clone()),
+                    _ => report.written.push(key.clone()),
+                }
+            }
+        }
+        remember(synced, &key, app_files);
+    }
+
+    // Parents are created before their folders, and folders deleted before their parents
+    imports.sort();
+    let (deletions, mut updates): (Vec<String>, Vec<String>) =
+        imports.into_iter().partition(|key| disk.get(key).is_none_or(|files| files.is_empty()));
+    updates.extend(deletions.into_iter().rev());
+    let mut created: HashMap<String, String> = HashMap::new();
+    for key in updates {
+        let current = app.get(&key);
+        let disk_files = disk.get(&key).unwrap_or(&no_files);
+        let written = current.map(|(_, files)| files);
+        let parent_id = parent_key(&key).and_then(|parent| match app.get(parent) {
+            Some((Item::Collection(id), _)) => Some(id.clone()),
+            _ => created.get(parent).cloned(),
+        });
+        let result = match (current.map(|(item, _)| item), key.starts_with(COLLECTIONS_DIR)) {
+            (Some(Item::Collection(id)), _) if disk_files.is_empty() => {
+                db.delete_collection(id).await.map_err(AppError::from)
+            }
+            (Some(Item::Environment(id)), _) if disk_files.is_empty() => {
+                db.delete_environment(id).await.map_err(AppError::from)
+            }
+            (Some(Item::Collection(id)), _) => {
+                import_collection(db, &key, Some(id), None, disk_files, written).await.map(|_| ())
+            }
+            (Some(Item::Environment(id)), _) => import_environment(db, &key, Some(id), disk_files, written).await,
+            (None, true) => import_collection(db, &key, None, parent_id, disk_files, None).await.map(|id| {
+                created.insert(key.clone(), id);
+            }),
+            (None, false) => import_environment(db, &key, None, disk_files, None).await,
+        };
+        match result {
+            Ok(()) => report.imported.push(key),
+            Err(e) => report.errors.push(format!("{}: {}", key, e.message)),
+        }
+    }
+
+    // What was read in is now what the workspace has; files written in a different layout
+    // (spacing, order) are rewritten the way the app writes them
+    if !report.imported.is_empty() {
+        let app = render(db).await?;
+        disk = scan(dir)?;
+        for key in &report.imported {
+            let app_files = app.get(key).map_or(&no_files, |(_, files)| files);
+            let disk_files = disk.get(key).unwrap_or(&no_files);
+            if app_files != disk_files {
+                write_unit(dir, key, disk_files, app_files)?;
+            }
+            remember(synced, key, app_files);
+        }
+    }
+    Ok(())
+}
+
+fn remember(synced: &mut HashMap<String, Files>, key: &str, files: &Files) {
+    if files.is_empty() {
+        synced.remove(key);
+    } else {
+        synced.insert(key.to_string(), files.clone());
+    }
+}
+
+// The workspace as files, by collection folder or environment file
+async fn render(db: &Database) -> Result<BTreeMap<String, (Item, Files)>, AppError> {
+    let mut units = BTreeMap::new();
+    let collections = db.get_collections().await?;
+    for (id, key) in collection_paths(&collections) {
+        let collection = crate::build_json_collection(db, &id, false).await?;
+        let files = bru::collection_files(&collection)?
+            .into_iter()
+            .map(|(name, text)| (format!("{}/{}", key, name), text))
+            .collect();
+        units.insert(key, (Item::Collection(id), files));
+    }
+
+    let mut used = HashSet::new();
+    for environment in db.get_environments().await? {
+        let key = format!("{}/{}.json", ENVIRONMENTS_DIR, entry_name(&environment.name, "environment", &mut used));
+        let export = crate::build_environment_export(db, &environment.id, false).await?;
+        let text = serde_json::to_string_pretty(&export)? + "\n";
+        units.insert(key.clone(), (Item::Environment(environment.id), Files::from([(key, text)])));
+    }
+    Ok(units)
+}
+
+// Folder of each collection, nested like the sidebar: "collections/Users API/Admin"
+pub fn collection_paths(collections: &[Collection]) -> BTreeMap<String, String> {
+    let mut paths = BTreeMap::new();
+    let mut pending = vec![(None, COLLECTIONS_DIR.to_string())];
+    while let Some((parent_id, parent_path)) = pending.pop() {
+        let mut used = HashSet::new();
+        for collection in collections.iter().filter(|collection| collection.parent_id == parent_id) {
+            let path = format!("{}/{}", parent_path, entry_name(&collection.name, "collection", &mut used));
+            paths.insert(collection.id.clone(), path.clone());
+            pending.push((Some(collection.id.clone()), path));
+        }
+    }
+    paths
+}
+
+// Names only need to be readable and unique among their siblings
+fn entry_name(name: &str, fallback: &str, used: &mut HashSet<String>) -> String {
+    let mut stem: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | ' ' | '.') { c } else { '-' })
+        .collect::<String>()
+        .trim()
+        .trim_matches('.')
+        .to_string();
+    if stem.is_empty() {
+        stem = fallback.to_string();
+    }
+    let mut candidate = stem.clone();
+    let mut counter = 2;
+    while !used.insert(candidate.to_lowercase()) {
+        candidate = format!("{} {}", stem, counter);
+        counter += 1;
+    }
+    candidate
+}
+
+// "collections/Users API/Admin" -> "collections/Users API"; None for a top-level collection
+fn parent_key(key: &str) -> Option<&str> {
+    key.rsplit_once('/').map(|(parent, _)| parent).filter(|parent| *parent != COLLECTIONS_DIR)
+}
+
+// The folder's files, by collection folder or environment file. Hidden files and folders
+// (.git) are skipped, and Windows line endings read as plain newlines.
+pub fn scan(dir: &Path) -> Result<BTreeMap<String, Files>> {
+    let mut units = BTreeMap::new();
+    scan_collections(dir, COLLECTIONS_DIR, &mut units)?;
+    for (name, path) in entries(&dir.join(ENVIRONMENTS_DIR))? {
+        if path.is_file() && name.ends_with(".json") {
+            let key = format!("{}/{}", ENVIRONMENTS_DIR, name);
+            units.insert(key.clone(), Files::from([(key, read_text(&path)?)]));
+        }
+    }
+    Ok(units)
+}
+
+fn scan_collections(root: &Path, key: &str, units: &mut BTreeMap<String, Files>) -> Result<()> {
+    let mut files = Files::new();
+    for (name, path) in entries(&root.join(key))? {
+        let child = format!("{}/{}", key, name);
+        if path.is_dir() {
+            scan_collections(root, &child, units)?;
+        } else if name.ends_with(".bru") {
+            files.insert(child, read_text(&path)?);
+        }
+    }
+    if !files.is_empty() {
+        units.insert(key.to_string(), files);
+    }
+    Ok(())
+}
+
+fn entries(dir: &Path) -> Result<Vec<(String, std::path::PathBuf)>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with('.') {
+            entries.push((name, entry.path()));
+        }
+    }
+    Ok(entries)
+}
+
+fn read_text(path: &Path) -> Result<String> {
+    Ok(std::fs::read_to_string(path)?.replace("\r\n", "\n"))
+}
+
+// Replace what's on disk for one collection folder or environment file; only the unit's own
+// files are touched, so subfolders and anything that isn't .bru or .json stay
+pub fn write_unit(dir: &Path, key: &str, on_disk: &Files, files: &Files) -> Result<()> {
+    for path in on_disk.keys().filter(|path| !files.contains_key(*path)) {
+        std::fs::remove_file(dir.join(path))?;
+    }
+    for (path, text) in files {
+        if on_disk.get(path) != Some(text) {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, text)?;
+        }
+    }
+    if files.is_empty() {
+        let _ = std::fs::remove_dir(dir.join(key)); // Only goes if nothing else is left in it
+    }
+    Ok(())
+}
+
+// 🎓 TEACHING: Fields the file still has exactly as the app wrote them keep their stored
+// value: masked credentials stay real and whatever .bru can't hold isn't cleared
+fn keep_unchanged<T: Serialize + DeserializeOwned>(incoming: T, written: &T, stored: &T) -> Result<T> {
+    let (written, stored) = (serde_json::to_value(written)?, serde_json::to_value(stored)?);
+    let mut incoming = serde_json::to_value(incoming)?;
+    if let Some(fields) = incoming.as_object_mut() {
+        for (name, value) in fields.iter_mut() {
+            if let (Some(written), Some(stored)) = (written.get(name), stored.get(name)) {
+                if written == value {
+                    *value = stored.clone();
+                }
+            }
+        }
+    }
+    Ok(serde_json::from_value(incoming)?)
+}
+
+fn unit_files(key: &str, files: &Files) -> Files {
+    let prefix = format!("{}/", key);
+    files.iter().filter_map(|(path, text)| Some((path.strip_prefix(&prefix)?.to_string(), text.clone()))).collect()
+}
+
+// Creates the collection when there's no `id`; returns its id
+async fn import_collection(
+    db: &Database,
+    key: &str,
+    id: Option<&String>,
+    parent_id: Option<String>,
+    files: &Files,
+    written: Option<&Files>,
+) -> Result<String, AppError> {
+    let fallback_name = key.rsplit('/').next().unwrap_or(key);
+    let incoming = bru::parse_collection_files(&unit_files(key, files), fallback_name)?;
+    let id = match id {
+        Some(id) => id.clone(),
+        None => db.create_collection(incoming.name.clone(), incoming.description.clone(), parent_id).await?.id,
+    };
+    let written = match written {
+        Some(written) => Some(bru::parse_collection_files(&unit_files(key, written), fallback_name)?),
+        None => None,
+    };
+    let stored = crate::build_json_collection(db, &id, true).await?;
+
+    let without_requests = |collection: &JsonCollection| JsonCollection { requests: Vec::new(), ..collection.clone() };
+    let settings = match &written {
+        Some(written) => {
+            keep_unchanged(without_requests(&incoming), &without_requests(written), &without_requests(&stored))?
+        }
+        None => without_requests(&incoming),
+    };
+    let collection = db.get_collection_by_id(&id).await?.ok_or_else(|| "Collection not found".to_string())?;
+    let changed = (collection.name.as_str(), &collection.description, &collection.auth_type, &collection.auth_data)
+        != (settings.name.as_str(), &settings.description, &settings.auth_type, &settings.auth_data);
+    if changed {
+        let updated = Collection {
+            name: settings.name,
+            description: settings.description,
+            auth_type: settings.auth_type,
+            auth_data: settings.auth_data,
+            ..collection
+        };
+        db.update_collection(updated, false).await?;
+    }
+
+    // Requests are matched by name, so one edited in the folder keeps its history
+    let saved = db.get_requests_by_collection(&id).await?;
+    let written_requests = written.map(|written| written.requests).filter(|requests| requests.len() == saved.len());
+    let mut matched = vec![false; saved.len()];
+    for request in incoming.requests {
+        let index = (0..saved.len()).find(|&index| !matched[index] && saved[index].name == request.name);
+        let Some(index) = index else {
+            let target =
+                db.create_request(id.clone(), request.name.clone(), request.method.clone(), request.url.clone()).await?;
+            crate::save_imported_request(db, request, target).await?;
+            continue;
+        };
+        matched[index] = true;
+        let stored = &stored.requests[index];
+        let request = match &written_requests {
+            Some(written) => keep_unchanged(request, &written[index], stored)?,
+            None => request,
+        };
+        if serde_json::to_value(&request)? != serde_json::to_value(stored)? {
+            crate::save_imported_request(db, request, saved[index].clone()).await?;
+        }
+    }
+    for (request, _) in saved.iter().zip(&matched).filter(|(_, matched)| !**matched) {
+        db.delete_request(&request.id).await?;
+    }
+    Ok(id)
+}
+
+// Creates the environment when there's no `id`
+async fn import_environment(
+    db: &Database,
+    key: &str,
+    id: Option<&String>,
+    files: &Files,
+    written: Option<&Files>,
+) -> Result<(), AppError> {
+    let text = files.get(key).ok_or_else(|| format!("{} is missing", key))?;
+    let export: EnvironmentExport = serde_json::from_str(text)?;
+    let headers = crate::default_headers::parse(Some(&export.default_headers))
+        .map_err(|e| AppError::validation(format!("Default headers must be a JSON object of strings: {}", e)))?;
+    crate::default_headers::validate(&headers).map_err(|e| AppError::validation(e.to_string()))?;
+    let written: Option<EnvironmentExport> = match written.and_then(|written| written.get(key)) {
+        Some(text) => Some(serde_json::from_str(text)?),
+        None => None,
+    };
+
+    let environment = match id {
+        Some(id) => db.get_environment_by_id(id).await?.ok_or_else(|| "Environment not found".to_string())?,
+        None => db.create_environment(export.name.clone()).await?,
+    };
+    let changed = (&environment.name, &environment.host_overrides, &environment.default_headers)
+        != (&export.name, &export.host_overrides, &export.default_headers);
+    let environment_id = environment.id.clone();
+    if changed {
+        db.update_environment(crate::database::Environment {
+            name: export.name,
+            host_overrides: export.host_overrides,
+            default_headers: export.default_headers,
+            ..environment
+        })
+        .await?;
+    }
+
+    let saved = db.get_variables(Some(&environment_id)).await?;
+    let mut kept = HashSet::new();
+    for variable in export.variables {
+        let unchanged = written.as_ref().is_some_and(|written| {
+            written.variables.iter().any(|other| {
+                (&other.key, &other.value, other.is_secret) == (&variable.key, &variable.value, variable.is_secret)
+            })
+        });
+        let existing = saved.iter().find(|existing| existing.key == variable.key && !kept.contains(&existing.id));
+        let Some(existing) = existing else {
+            db.create_variable(Some(environment_id.clone()), variable.key, variable.value, variable.is_secret).await?;
+            continue;
+        };
+        kept.insert(existing.id.clone());
+        // A secret is written out blank, so a blank one keeps its value
+        let blank_secret = variable.is_secret && variable.value.is_empty();
+        if unchanged || (blank_secret && existing.is_secret) {
+            continue;
+        }
+        let value = if blank_secret { existing.value.clone() } else { variable.value };
+        db.update_variable(crate::database::Variable { value, is_secret: variable.is_secret, ..existing.clone() })
+            .await?;
+    }
+    for variable in saved.iter().filter(|variable| !kept.contains(&variable.id)) {
+        db.delete_variable(&variable.id).await?;
+    }
+    Ok(())
+}
+
+// Start the background task; called once from the app's setup
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticks = tokio::time::interval(SYNC_TICK);
+        let mut last_errors: Vec<String> = Vec::new();
+        loop {
+            ticks.tick().await;
+            // 🎓 TEACHING: Settings first, database second. Switching workspaces saves the new
+            // active workspace before it swaps the database, so in between the two disagree;
+            // syncing then would write the old workspace into the new one's folder.
+            let settings = crate::app_dirs(&app)
+                .ok()
+                .and_then(|(config_dir, _)| AppSettings::load(&config_dir).ok())
+                .unwrap_or_default();
+            let workspace_id = settings.active_workspace_id();
+            let db = {
+                let db_state = app.state::<DatabaseState>();
+                let db_guard = db_state.lock().unwrap();
+                db_guard.as_ref().cloned()
+            };
+            let Some(db) = db else { continue }; // Not opened yet
+            if db.workspace_id() != Some(workspace_id) {
+                continue; // A switch is under way; the next tick sees both sides agree
+            }
+
+            let Some(config) = settings.workspace_sync.get(workspace_id).filter(|config| config.enabled) else {
+                continue;
+            };
+            let report = app.state::<WorkspaceSync>().run(&db, workspace_id, Path::new(config.dir.trim())).await;
+
+            // The same errors come back every tick until they're fixed; they're only told once
+            let changed = !(report.written.is_empty() && report.imported.is_empty() && report.conflicts.is_empty());
+            if changed || report.errors != last_errors {
+                for error in report.errors.iter().filter(|error| !last_errors.contains(error)) {
+                    crate::logging::warn(format!("Workspace sync: {}", error));
+                }
+                let _ = app.emit("workspace-sync", &report);
+                last_errors = report.errors;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importer_exporter::JsonRequest;
+    use chrono::Utc;
+
+    fn files(pairs: &[(&str, &str)]) -> Files {
+        pairs.iter().map(|(path, text)| (path.to_string(), text.to_string())).collect()
+    }
+
+    fn collection(id: &str, name: &str, parent_id: Option<&str>) -> Collection {
+        Collection {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: None,
+            docs: None,
+            parent_id: parent_id.map(str::to_string),
+            auth_type: None,
+            auth_data: None,
+            sort_order: 0,
+            version: 0,
+            trace_context: None,
+            default_headers: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_decide() {
+        let (old, new, other) = (files(&[("a", "1")]), files(&[("a", "2")]), files(&[("a", "3")]));
+        let none = Files::new();
+        assert_eq!(decide(&old, &old, None), Action::InSync);
+        assert_eq!(decide(&new, &old, Some(&old)), Action::Write);
+        assert_eq!(decide(&old, &new, Some(&old)), Action::Import);
+        assert_eq!(decide(&new, &other, Some(&old)), Action::Conflict);
+        // Never synced: new on one side only
+        assert_eq!(decide(&new, &none, None), Action::Write);
+        assert_eq!(decide(&none, &new, None), Action::Import);
+        assert_eq!(decide(&new, &other, None), Action::Conflict);
+        // Deleted on one side
+        assert_eq!(decide(&none, &old, Some(&old)), Action::Write);
+        assert_eq!(decide(&old, &none, Some(&old)), Action::Import);
+    }
+
+    #[test]
+    fn test_collection_paths() {
+        let collections = vec![
+            collection("1", "Users API", None),
+            collection("2", "users api", None),
+            collection("3", "Admin/Ops", Some("1")),
+            collection("4", "..", Some("3")),
+        ];
+        let paths = collection_paths(&collections);
+        assert_eq!(paths["1"], "collections/Users API");
+        assert_eq!(paths["2"], "collections/users api 2");
+        assert_eq!(paths["3"], "collections/Users API/Admin-Ops");
+        assert_eq!(paths["4"], "collections/Users API/Admin-Ops/collection");
+        assert_eq!(parent_key(&paths["3"]), Some("collections/Users API"));
+        assert_eq!(parent_key(&paths["1"]), None);
+    }
+
+    #[test]
+    fn test_keep_unchanged() {
+        let request = |url: &str, auth_data: &str| JsonRequest {
+            name: "Get user".to_string(),
+            method: "GET".to_string(),
+            url: url.to_string(),
+            params: "[]".to_string(),
+            headers: "{}".to_string(),
+            path_params: "{}".to_string(),
+            body_type: "none".to_string(),
+            body_str: None,
+            graphql_variables: None,
+            graphql_operation_name: None,
+            grpc_config: None,
+            captures: None,
+            scripts: None,
+            settings: None,
+            docs: None,
+            auth_type: Some("bearer".to_string()),
+            auth_data: Some(auth_data.to_string()),
+        };
+        let stored = request("https://api.test/users/1", r#"{"token":"s3cret"}"#);
+        let written = request("https://api.test/users/1", r#"{"token":"****"}"#);
+        let edited = request("https://api.test/users/2", r#"{"token":"****"}"#);
+        let merged = keep_unchanged(edited, &written, &stored).unwrap();
+        assert_eq!(merged.url, "https://api.test/users/2");
+        assert_eq!(merged.auth_data.as_deref(), Some(r#"{"token":"s3cret"}"#));
+    }
+
+    #[test]
+    fn test_scan_and_write() {
+        let dir = std::env::temp_dir().join(format!("sync-{}", uuid::Uuid::new_v4()));
+        let users = files(&[
+            ("collections/Users/collection.bru", "meta {\n  name: Users\n}\n"),
+            ("collections/Users/Get user.bru", "a"),
+        ]);
+        let admin = files(&[("collections/Users/Admin/collection.bru", "b")]);
+        let staging = files(&[("environments/Staging.json", "{}\n")]);
+        let units = [
+            ("collections/Users", &users),
+            ("collections/Users/Admin", &admin),
+            ("environments/Staging.json", &staging),
+        ];
+        for (key, unit) in units {
+            write_unit(&dir, key, &Files::new(), unit).unwrap();
+        }
+        std::fs::write(dir.join("collections/Users/README.md"), "notes").unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join("environments/Prod.json"), "{\r\n}\r\n").unwrap();
+
+        let units = scan(&dir).unwrap();
+        assert_eq!(units.keys().collect::<Vec<_>>(), [
+            "collections/Users",
+            "collections/Users/Admin",
+            "environments/Prod.json",
+            "environments/Staging.json"
+        ]);
+        assert_eq!(units["collections/Users"], users);
+        assert_eq!(units["environments/Prod.json"]["environments/Prod.json"], "{\n}\n");
+
+        // Removing a folder's own files leaves its subfolder and other files alone
+        write_unit(&dir, "collections/Users", &users, &Files::new()).unwrap();
+        let units = scan(&dir).unwrap();
+        assert!(!units.contains_key("collections/Users"));
+        assert!(units.contains_key("collections/Users/Admin"));
+        assert!(dir.join("collections/Users/README.md").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&SyncConfig { dir: "relative/path".to_string(), enabled: true }).is_err());
+        let file = std::env::temp_dir().join(format!("sync-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&file, "x").unwrap();
+        assert!(validate(&SyncConfig { dir: file.display().to_string(), enabled: true }).is_err());
+        assert!(validate(&SyncConfig { dir: std::env::temp_dir().display().to_string(), enabled: true }).is_ok());
+        std::fs::remove_file(file).unwrap();
+    }
+}
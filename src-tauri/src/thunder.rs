@@ -0,0 +1,331 @@
+// 🎓 TEACHING: Thunder Client exports
+// Thunder Client (a VS Code extension) exports collections and environments as JSON.
+// Collections list their requests flat, each pointing at an optional folder, with
+// headers/params as `{ name, value, isDisabled }` lists. Environments are a list of
+// `{ name, value }` pairs. Both use the same `{{variable}}` syntax as we do.
+
+use crate::http_file::body_type_for;
+use crate::importer_exporter::{CollectionImport, JsonCollection, JsonEnvironment, JsonRequest, JsonVariable, CURRENT_SCHEMA_VERSION};
+use crate::params::{self, QueryParam};
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThunderCollection {
+    collection_name: String,
+    #[serde(default)]
+    folders: Vec<ThunderFolder>,
+    #[serde(default)]
+    requests: Vec<ThunderRequest>,
+    #[serde(default)]
+    settings: Option<ThunderSettings>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThunderFolder {
+    #[serde(rename = "_id")]
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct ThunderSettings {
+    auth: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThunderRequest {
+    name: String,
+    url: String,
+    method: String,
+    #[serde(default)]
+    container_id: String, // Folder id, empty at the top level
+    #[serde(default)]
+    sort_num: f64,
+    #[serde(default)]
+    headers: Vec<ThunderPair>,
+    #[serde(default)]
+    params: Vec<ThunderParam>,
+    body: Option<ThunderBody>,
+    auth: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThunderPair {
+    name: String,
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    is_disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThunderParam {
+    name: String,
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    is_disabled: bool,
+    #[serde(default)]
+    is_path: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThunderBody {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    raw: Option<String>,
+    #[serde(default)]
+    form: Vec<ThunderPair>,
+    #[serde(default)]
+    graphql: Option<ThunderGraphql>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThunderGraphql {
+    query: String,
+    variables: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThunderEnvironment {
+    environment_name: String,
+    #[serde(default)]
+    data: Vec<ThunderPair>,
+}
+
+// Accepts either kind of export and tells them apart by their name field
+pub fn parse(json_str: &str) -> Result<CollectionImport> {
+    let value: Value = serde_json::from_str(json_str)?;
+    if value.get("environmentName").is_some() {
+        let environment: ThunderEnvironment = serde_json::from_value(value)?;
+        return Ok(CollectionImport {
+            collection: None,
+            environments: vec![JsonEnvironment {
+                name: environment.environment_name,
+                variables: environment
+                    .data
+                    .into_iter()
+                    .filter(|v| !v.is_disabled)
+                    .map(|v| JsonVariable {
+                        key: v.name,
+                        value: v.value,
+                        is_secret: false,
+                    })
+                    .collect(),
+            }],
+        });
+    }
+    if value.get("collectionName").is_none() {
+        return Err(anyhow::anyhow!("Not a Thunder Client collection or environment export"));
+    }
+
+    let collection: ThunderCollection = serde_json::from_value(value)?;
+    let folders: HashMap<&str, &str> = collection.folders.iter().map(|f| (f.id.as_str(), f.name.as_str())).collect();
+
+    let mut thunder_requests: Vec<&ThunderRequest> = collection.requests.iter().collect();
+    thunder_requests.sort_by(|a, b| a.sort_num.total_cmp(&b.sort_num));
+    let requests = thunder_requests
+        .into_iter()
+        .map(|request| convert_request(request, &folders))
+        .collect::<Result<Vec<_>>>()?;
+
+    let (auth_type, auth_data) = collection
+        .settings
+        .and_then(|settings| settings.auth)
+        .and_then(|auth| convert_auth(&auth))
+        .unzip();
+    Ok(CollectionImport {
+        collection: Some(JsonCollection {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            name: collection.collection_name,
+            description: None,
+            docs: None,
+            auth_type,
+            auth_data,
+            requests,
+        }),
+        environments: Vec::new(),
+    })
+}
+
+fn convert_request(request: &ThunderRequest, folders: &HashMap<&str, &str>) -> Result<JsonRequest> {
+    // We have no folders, so keep the folder as part of the name
+    let name = match folders.get(request.container_id.as_str()) {
+        Some(folder) => format!("{} / {}", folder, request.name),
+        None => request.name.clone(),
+    };
+    // The params list mirrors the URL's query string, so the list wins
+    let (url, _) = params::split_query(&request.url);
+    let mut json_request = JsonRequest::new(name, request.method.to_uppercase(), url);
+
+    let query: Vec<QueryParam> = request
+        .params
+        .iter()
+        .filter(|p| !p.is_path)
+        .map(|p| QueryParam {
+            key: p.name.clone(),
+            value: p.value.clone(),
+            enabled: !p.is_disabled,
+        })
+        .collect();
+    let path: BTreeMap<&str, &str> = request
+        .params
+        .iter()
+        .filter(|p| p.is_path)
+        .map(|p| (p.name.as_str(), p.value.as_str()))
+        .collect();
+    let mut headers: BTreeMap<String, String> = request
+        .headers
+        .iter()
+        .filter(|h| !h.is_disabled)
+        .map(|h| (h.name.clone(), h.value.clone()))
+        .collect();
+
+    if let Some(body) = &request.body {
+        match body.kind.as_str() {
+            "none" | "" => {}
+            "formencoded" => {
+                let mut form = url::form_urlencoded::Serializer::new(String::new());
+                for field in body.form.iter().filter(|f| !f.is_disabled) {
+                    form.append_pair(&field.name, &field.value);
+                }
+                json_request.body_type = "x-www-form-urlencoded".to_string();
+                json_request.body_str = Some(form.finish());
+            }
+            "graphql" => {
+                if let Some(graphql) = &body.graphql {
+                    json_request.body_type = "graphql".to_string();
+                    json_request.body_str = Some(graphql.query.clone());
+                    json_request.graphql_variables = graphql.variables.clone().filter(|v| !v.trim().is_empty());
+                }
+            }
+            kind => {
+                let content_type = headers
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+                    .map(|(_, v)| v.to_lowercase())
+                    .unwrap_or_else(|| kind.to_string());
+                json_request.body_type = match kind {
+                    "formdata" => "form-data",
+                    "json" | "xml" | "text" => kind,
+                    _ => body_type_for(&content_type),
+                }
+                .to_string();
+                json_request.body_str = body.raw.clone();
+            }
+        }
+    }
+
+    if let Some((auth_type, auth_data)) = request.auth.as_ref().and_then(convert_auth) {
+        json_request.auth_type = Some(auth_type);
+        json_request.auth_data = Some(auth_data);
+    }
+    // Thunder adds Content-Type from the body type, so it's often not in the list
+    if json_request.body_type == "json" && !headers.keys().any(|k| k.eq_ignore_ascii_case("content-type")) {
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+    }
+
+    json_request.params = serde_json::to_string(&query)?;
+    json_request.path_params = serde_json::to_string(&path)?;
+    json_request.headers = serde_json::to_string(&headers)?;
+    Ok(json_request)
+}
+
+// 🎓 TEACHING: Thunder keeps each auth type's settings under a key named after the type,
+// e.g. `{ "type": "bearer", "bearer": "{{token}}" }`. "inherit" and "none" map directly.
+fn convert_auth(auth: &Value) -> Option<(String, String)> {
+    let kind = auth.get("type")?.as_str()?;
+    let text = |pointer: &str| auth.pointer(pointer).and_then(Value::as_str).unwrap_or("").to_string();
+
+    let (auth_type, auth_data) = match kind {
+        "inherit" => return None, // Requests inherit the collection's auth by default
+        "none" => ("none", json!({})),
+        "basic" => (
+            "basic",
+            json!({ "username": text("/basic/username"), "password": text("/basic/password") }),
+        ),
+        "bearer" => ("bearer", json!({ "token": text("/bearer") })),
+        "oauth2" => ("oauth2", json!({ "access_token": text("/oauth2/accessToken") })),
+        "digest" => ("digest", json!({ "username": text("/digest/username"), "password": text("/digest/password") })),
+        _ => return None,
+    };
+    Some((auth_type.to_string(), auth_data.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_collection() {
+        let export = r#"{
+            "client": "Thunder Client",
+            "collectionName": "Shop",
+            "version": "1.1",
+            "folders": [{ "_id": "f1", "name": "Orders", "containerId": "" }],
+            "requests": [
+                {
+                    "_id": "r2", "name": "Create order", "url": "{{base}}/orders/:shop?dry=1", "method": "POST",
+                    "containerId": "f1", "sortNum": 20,
+                    "headers": [{ "name": "X-Debug", "value": "1", "isDisabled": true }],
+                    "params": [
+                        { "name": "dry", "value": "1", "isDisabled": false },
+                        { "name": "shop", "value": "main", "isPath": true }
+                    ],
+                    "body": { "type": "json", "raw": "{\"qty\": 2}", "form": [] },
+                    "auth": { "type": "bearer", "bearer": "{{token}}" }
+                },
+                {
+                    "_id": "r1", "name": "Login", "url": "{{base}}/login", "method": "post", "containerId": "", "sortNum": 10,
+                    "body": { "type": "formencoded", "form": [{ "name": "user", "value": "ada" }, { "name": "x", "value": "y", "isDisabled": true }] }
+                }
+            ],
+            "settings": { "auth": { "type": "basic", "basic": { "username": "u", "password": "p" } } }
+        }"#;
+
+        let collection = parse(export).unwrap().collection.unwrap();
+        assert_eq!(collection.name, "Shop");
+        assert_eq!(collection.auth_type.as_deref(), Some("basic"));
+
+        let login = &collection.requests[0];
+        assert_eq!(login.name, "Login");
+        assert_eq!(login.method, "POST");
+        assert_eq!(login.body_type, "x-www-form-urlencoded");
+        assert_eq!(login.body_str.as_deref(), Some("user=ada"));
+        assert!(login.auth_type.is_none());
+
+        let order = &collection.requests[1];
+        assert_eq!(order.name, "Orders / Create order");
+        assert_eq!(order.url, "{{base}}/orders/:shop");
+        assert_eq!(order.params, r#"[{"key":"dry","value":"1","enabled":true}]"#);
+        assert_eq!(order.path_params, r#"{"shop":"main"}"#);
+        assert_eq!(order.headers, r#"{"Content-Type":"application/json"}"#);
+        assert_eq!(order.body_type, "json");
+        assert_eq!(order.auth_data.as_deref(), Some(r#"{"token":"{{token}}"}"#));
+    }
+
+    #[test]
+    fn test_parse_environment() {
+        let export = r#"{
+            "client": "Thunder Client",
+            "environmentName": "staging",
+            "data": [{ "name": "base", "value": "https://staging.example.com" }]
+        }"#;
+        let import = parse(export).unwrap();
+        assert!(import.collection.is_none());
+        assert_eq!(import.environments[0].name, "staging");
+        assert_eq!(import.environments[0].variables[0].value, "https://staging.example.com");
+
+        assert!(parse(r#"{"name": "postman?"}"#).is_err());
+    }
+}
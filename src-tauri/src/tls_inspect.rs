@@ -0,0 +1,491 @@
+// 🎓 TEACHING: TLS certificate inspection
+// "certificate verify failed" says nothing about *why*. This connects to host:port, completes
+// the handshake whatever the certificate looks like, and returns the chain the server sent:
+// subjects, issuers, SANs, validity and fingerprints, plus the reason the OS trust store
+// would have rejected it (unknown issuer, expired, wrong name...) if it would have.
+//
+// Certificates are read with the small DER reader below rather than a full X.509 crate;
+// only the fields shown here are picked out, and anything unexpected is left blank.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls;
+use tokio_rustls::rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TlsInspection {
+    pub host: String,
+    pub port: u16,
+    pub protocol_version: Option<String>, // e.g. "TLSv1_3"
+    pub cipher_suite: Option<String>,
+    pub trusted: bool,                      // Whether a normal request would accept the chain
+    pub verification_error: Option<String>, // Why not
+    pub certificates: Vec<CertificateInfo>, // Server certificate first, as sent
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct CertificateInfo {
+    pub subject: String, // e.g. "C=GB, O=Example, CN=example.com"
+    pub issuer: String,
+    pub serial_number: String, // Hex
+    pub not_before: Option<String>, // RFC 3339
+    pub not_after: Option<String>,
+    pub expired: bool,
+    pub days_remaining: Option<i64>, // Negative once expired
+    pub subject_alt_names: Vec<String>, // "DNS:example.com", "IP:10.0.0.1", ...
+    pub signature_algorithm: String,
+    pub public_key: String, // e.g. "RSA 2048", "EC P-256"
+    pub is_ca: bool,
+    pub self_signed: bool,
+    pub sha256_fingerprint: String, // Colon-separated, as browsers show it
+    pub sha1_fingerprint: String,
+}
+
+// `host` may also be a URL, whose host and port are used
+pub async fn inspect(host: &str, port: Option<u16>) -> Result<TlsInspection> {
+    let (host, port) = crate::diagnostics::target(host, port, 443)?;
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = Arc::new(RecordingVerifier {
+        inner: rustls::client::WebPkiServerVerifier::builder_with_provider(
+            Arc::new(crate::raw_socket::native_roots()),
+            provider.clone(),
+        )
+        .build()?,
+        error: Mutex::new(None),
+    });
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier.clone())
+        .with_no_client_auth();
+
+    let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host.as_str(), port)))
+        .await
+        .map_err(|_| anyhow!("Timed out connecting to {}:{}", host, port))?
+        .map_err(|e| anyhow!("Failed to connect to {}:{}: {}", host, port, e))?;
+    let server_name =
+        ServerName::try_from(host.clone()).map_err(|_| anyhow!("Invalid TLS server name: {}", host))?;
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+    let stream = tokio::time::timeout(CONNECT_TIMEOUT, connector.connect(server_name, stream))
+        .await
+        .map_err(|_| anyhow!("Timed out during the TLS handshake"))?
+        .map_err(|e| anyhow!("TLS handshake failed: {}", e))?;
+
+    let (_, connection) = stream.get_ref();
+    let now = chrono::Utc::now();
+    let certificates = connection
+        .peer_certificates()
+        .unwrap_or_default()
+        .iter()
+        .map(|cert| certificate_info(cert, now))
+        .collect();
+    let verification_error = verifier.error.lock().unwrap().take();
+    Ok(TlsInspection {
+        host,
+        port,
+        protocol_version: connection.protocol_version().map(|version| format!("{:?}", version)),
+        cipher_suite: connection.negotiated_cipher_suite().map(|suite| format!("{:?}", suite.suite())),
+        trusted: verification_error.is_none(),
+        verification_error,
+        certificates,
+    })
+}
+
+// 🎓 TEACHING: Runs the normal checks but only notes the result, so the handshake goes
+// ahead and the chain can be shown even when it's the thing that's broken
+#[derive(Debug)]
+struct RecordingVerifier {
+    inner: Arc<rustls::client::WebPkiServerVerifier>,
+    error: Mutex<Option<String>>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if let Err(e) = self.inner.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now) {
+            *self.error.lock().unwrap() = Some(e.to_string());
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+// Fingerprints always come out; the rest is blank if the certificate can't be read
+fn certificate_info(der: &[u8], now: chrono::DateTime<chrono::Utc>) -> CertificateInfo {
+    let mut info = parse_certificate(der, now).unwrap_or_default();
+    info.sha256_fingerprint = fingerprint(&Sha256::digest(der));
+    info.sha1_fingerprint = fingerprint(&sha1::Sha1::digest(der));
+    info
+}
+
+fn fingerprint(hash: &[u8]) -> String {
+    let bytes: Vec<String> = hash.iter().map(|byte| format!("{:02X}", byte)).collect();
+    bytes.join(":")
+}
+
+// 🎓 TEACHING: X.509, just enough of it
+//   Certificate  ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signature }
+//   TBSCertificate ::= SEQUENCE { [0] version OPTIONAL, serialNumber, signature, issuer,
+//                                 validity, subject, subjectPublicKeyInfo, ..., [3] extensions }
+fn parse_certificate(der: &[u8], now: chrono::DateTime<chrono::Utc>) -> Result<CertificateInfo> {
+    let mut certificate = Der::read(&mut &*der)?.expect(SEQUENCE)?.content;
+    let mut tbs = Der::read(&mut certificate)?.expect(SEQUENCE)?.content;
+    let signature_algorithm = algorithm_name(Der::read(&mut certificate)?.expect(SEQUENCE)?.content);
+
+    let mut field = Der::read(&mut tbs)?;
+    if field.tag == 0xA0 {
+        field = Der::read(&mut tbs)?; // Skip the version
+    }
+    let serial_number = hex::encode(field.expect(INTEGER)?.content);
+    Der::read(&mut tbs)?; // Signature algorithm again
+    let issuer = name(Der::read(&mut tbs)?.expect(SEQUENCE)?.content)?;
+    let mut validity = Der::read(&mut tbs)?.expect(SEQUENCE)?.content;
+    let not_before = time(&Der::read(&mut validity)?);
+    let not_after = time(&Der::read(&mut validity)?);
+    let subject = name(Der::read(&mut tbs)?.expect(SEQUENCE)?.content)?;
+    let public_key = public_key(Der::read(&mut tbs)?.expect(SEQUENCE)?.content).unwrap_or_default();
+
+    let mut info = CertificateInfo {
+        self_signed: subject == issuer,
+        subject,
+        issuer,
+        serial_number,
+        not_before: not_before.map(|at| at.to_rfc3339()),
+        not_after: not_after.map(|at| at.to_rfc3339()),
+        expired: not_after.is_some_and(|at| at < now),
+        days_remaining: not_after.map(|at| (at - now).num_days()),
+        signature_algorithm,
+        public_key,
+        ..Default::default()
+    };
+    while !tbs.is_empty() {
+        let field = Der::read(&mut tbs)?;
+        if field.tag == 0xA3 {
+            read_extensions(Der::read(&mut &*field.content)?.expect(SEQUENCE)?.content, &mut info)?;
+        }
+    }
+    Ok(info)
+}
+
+fn read_extensions(mut extensions: &[u8], info: &mut CertificateInfo) -> Result<()> {
+    while !extensions.is_empty() {
+        let mut extension = Der::read(&mut extensions)?.expect(SEQUENCE)?.content;
+        let id = oid(Der::read(&mut extension)?.expect(OID)?.content);
+        let mut value = Der::read(&mut extension)?;
+        if value.tag == BOOLEAN {
+            value = Der::read(&mut extension)?; // Skip `critical`
+        }
+        let mut value = Der::read(&mut value.expect(OCTET_STRING)?.content)?.content;
+        match id.as_str() {
+            "2.5.29.17" => {
+                while !value.is_empty() {
+                    let general_name = Der::read(&mut value)?;
+                    if let Some(name) = alt_name(&general_name) {
+                        info.subject_alt_names.push(name);
+                    }
+                }
+            }
+            // basicConstraints: SEQUENCE { cA BOOLEAN DEFAULT FALSE, ... }
+            "2.5.29.19" if !value.is_empty() => {
+                let first = Der::read(&mut value)?;
+                info.is_ca = first.tag == BOOLEAN && first.content.first().is_some_and(|byte| *byte != 0);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn alt_name(general_name: &Der) -> Option<String> {
+    let text = || String::from_utf8_lossy(general_name.content);
+    match general_name.tag {
+        0x81 => Some(format!("email:{}", text())),
+        0x82 => Some(format!("DNS:{}", text())),
+        0x86 => Some(format!("URI:{}", text())),
+        0x87 => match general_name.content.len() {
+            4 => Some(format!("IP:{}", std::net::Ipv4Addr::from(<[u8; 4]>::try_from(general_name.content).ok()?))),
+            16 => Some(format!("IP:{}", std::net::Ipv6Addr::from(<[u8; 16]>::try_from(general_name.content).ok()?))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Name ::= SEQUENCE OF SET OF SEQUENCE { type OID, value }, shown in the order it's stored
+fn name(mut sequence: &[u8]) -> Result<String> {
+    let mut parts = Vec::new();
+    while !sequence.is_empty() {
+        let mut set = Der::read(&mut sequence)?.content;
+        while !set.is_empty() {
+            let mut attribute = Der::read(&mut set)?.expect(SEQUENCE)?.content;
+            let id = oid(Der::read(&mut attribute)?.expect(OID)?.content);
+            let value = Der::read(&mut attribute)?;
+            let label = match id.as_str() {
+                "2.5.4.3" => "CN",
+                "2.5.4.5" => "serialNumber",
+                "2.5.4.6" => "C",
+                "2.5.4.7" => "L",
+                "2.5.4.8" => "ST",
+                "2.5.4.10" => "O",
+                "2.5.4.11" => "OU",
+                "1.2.840.113549.1.9.1" => "emailAddress",
+                "0.9.2342.19200300.100.1.25" => "DC",
+                other => other,
+            };
+            parts.push(format!("{}={}", label, string(&value)));
+        }
+    }
+    Ok(parts.join(", "))
+}
+
+fn string(value: &Der) -> String {
+    match value.tag {
+        // BMPString is UTF-16
+        0x1E => {
+            let units: Vec<u16> =
+                value.content.chunks(2).map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(value.content).into_owned(),
+    }
+}
+
+fn time(value: &Der) -> Option<chrono::DateTime<chrono::Utc>> {
+    let text = std::str::from_utf8(value.content).ok()?.trim_end_matches('Z');
+    let text = match value.tag {
+        // UTCTime has a two-digit year: 50-99 are 19xx
+        0x17 => format!("{}{}", if text.get(..2)? >= "50" { "19" } else { "20" }, text),
+        0x18 => text.to_string(),
+        _ => return None,
+    };
+    let at = chrono::NaiveDateTime::parse_from_str(&text, "%Y%m%d%H%M%S").ok()?;
+    Some(at.and_utc())
+}
+
+// SubjectPublicKeyInfo ::= SEQUENCE { algorithm SEQUENCE { OID, parameters }, BIT STRING }
+fn public_key(mut info: &[u8]) -> Result<String> {
+    let mut algorithm = Der::read(&mut info)?.expect(SEQUENCE)?.content;
+    let id = oid(Der::read(&mut algorithm)?.expect(OID)?.content);
+    Ok(match id.as_str() {
+        "1.2.840.113549.1.1.1" => {
+            // The key is SEQUENCE { modulus INTEGER, exponent INTEGER } after the unused-bits byte
+            let bits = Der::read(&mut info)?.expect(BIT_STRING)?.content;
+            let mut key = Der::read(&mut bits.get(1..).unwrap_or_default())?.expect(SEQUENCE)?.content;
+            let modulus = Der::read(&mut key)?.expect(INTEGER)?.content;
+            let modulus = &modulus[modulus.iter().take_while(|byte| **byte == 0).count()..];
+            let size = modulus.len() * 8 - modulus.first().map_or(0, |byte| byte.leading_zeros() as usize);
+            format!("RSA {}", size)
+        }
+        "1.2.840.10045.2.1" => {
+            let curve = Der::read(&mut algorithm).ok().map(|curve| oid(curve.content));
+            match curve.as_deref() {
+                Some("1.2.840.10045.3.1.7") => "EC P-256".to_string(),
+                Some("1.3.132.0.34") => "EC P-384".to_string(),
+                Some("1.3.132.0.35") => "EC P-521".to_string(),
+                Some(other) => format!("EC {}", other),
+                None => "EC".to_string(),
+            }
+        }
+        "1.3.101.112" => "Ed25519".to_string(),
+        "1.3.101.113" => "Ed448".to_string(),
+        other => other.to_string(),
+    })
+}
+
+// AlgorithmIdentifier ::= SEQUENCE { algorithm OID, parameters ANY OPTIONAL }
+fn algorithm_name(mut identifier: &[u8]) -> String {
+    let id = match Der::read(&mut identifier) {
+        Ok(id) => oid(id.content),
+        Err(_) => return String::new(),
+    };
+    match id.as_str() {
+        "1.2.840.113549.1.1.5" => "sha1WithRSAEncryption",
+        "1.2.840.113549.1.1.10" => "RSASSA-PSS",
+        "1.2.840.113549.1.1.11" => "sha256WithRSAEncryption",
+        "1.2.840.113549.1.1.12" => "sha384WithRSAEncryption",
+        "1.2.840.113549.1.1.13" => "sha512WithRSAEncryption",
+        "1.2.840.10045.4.3.2" => "ecdsa-with-SHA256",
+        "1.2.840.10045.4.3.3" => "ecdsa-with-SHA384",
+        "1.2.840.10045.4.3.4" => "ecdsa-with-SHA512",
+        "1.3.101.112" => "Ed25519",
+        other => return other.to_string(),
+    }
+    .to_string()
+}
+
+fn oid(content: &[u8]) -> String {
+    let mut arcs = Vec::new();
+    let mut value: u64 = 0;
+    for byte in content {
+        value = (value << 7) | u64::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                // The first byte packs two arcs as 40 * first + second
+                let first = (value / 40).min(2);
+                arcs.push(first);
+                arcs.push(value - first * 40);
+            } else {
+                arcs.push(value);
+            }
+            value = 0;
+        }
+    }
+    let arcs: Vec<String> = arcs.iter().map(u64::to_string).collect();
+    arcs.join(".")
+}
+
+const BOOLEAN: u8 = 0x01;
+const INTEGER: u8 = 0x02;
+const BIT_STRING: u8 = 0x03;
+const OCTET_STRING: u8 = 0x04;
+const OID: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+
+// One DER tag-length-value; certificates only use single-byte tags
+struct Der<'a> {
+    tag: u8,
+    content: &'a [u8],
+}
+
+impl<'a> Der<'a> {
+    fn read(input: &mut &'a [u8]) -> Result<Der<'a>> {
+        let truncated = || anyhow!("Truncated certificate");
+        let (&tag, rest) = input.split_first().ok_or_else(truncated)?;
+        let (&first, mut rest) = rest.split_first().ok_or_else(truncated)?;
+        let length = if first < 0x80 {
+            first as usize
+        } else {
+            let count = (first & 0x7F) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                return Err(anyhow!("Unsupported DER length"));
+            }
+            let length = rest[..count].iter().fold(0usize, |length, byte| (length << 8) | *byte as usize);
+            rest = &rest[count..];
+            length
+        };
+        if rest.len() < length {
+            return Err(truncated());
+        }
+        let (content, rest) = rest.split_at(length);
+        *input = rest;
+        Ok(Der { tag, content })
+    }
+
+    fn expect(self, tag: u8) -> Result<Der<'a>> {
+        if self.tag == tag {
+            Ok(self)
+        } else {
+            Err(anyhow!("Unexpected DER tag {:#04x}, wanted {:#04x}", self.tag, tag))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose, Engine as _};
+    use tokio::net::TcpListener;
+
+    // Self-signed P-256, CN=localhost, valid 2026-10-17 to 2126-09-23
+    const CERT: &str = "\
+        MIIB6TCCAY+gAwIBAgICEjQwCgYIKoZIzj0EAwIwPDELMAkGA1UEBhMCR0IxGTAX\
+        BgNVBAoMEE9wZW5SZXF1ZXN0IFRlc3QxEjAQBgNVBAMMCWxvY2FsaG9zdDAgFw0y\
+        NjEwMTcwMjI4MjRaGA8yMTI2MDkyMzAyMjgyNFowPDELMAkGA1UEBhMCR0IxGTAX\
+        BgNVBAoMEE9wZW5SZXF1ZXN0IFRlc3QxEjAQBgNVBAMMCWxvY2FsaG9zdDBZMBMG\
+        ByqGSM49AgEGCCqGSM49AwEHA0IABE0csBdBK0xv78N8rcy/EIBwidfvwqVxoAhz\
+        0rQgXDK9YUESVV09D9ueYVMqtwS6Yo0jMSnd/xeSdIV3v/g9KeOjfzB9MB0GA1Ud\
+        DgQWBBS3jDSpZn6dIAdb4amhd0HqHGbVkDAfBgNVHSMEGDAWgBS3jDSpZn6dIAdb\
+        4amhd0HqHGbVkDAPBgNVHRMBAf8EBTADAQH/MCoGA1UdEQQjMCGCCWxvY2FsaG9z\
+        dIIOKi5leGFtcGxlLnRlc3SHBH8AAAEwCgYIKoZIzj0EAwIDSAAwRQIgd+ReU4UQ\
+        5MnEcpeBrICBtOL26FGaK4K4TZynxOje1SwCIQDZiXnP3kFHojXRfhC3sS9Km7Iq\
+        GWNc2UG+i8VNChtGQQ==";
+    const KEY: &str = "\
+        MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgy36d+gkgb7VNjUA3\
+        s7VdXhuU7cGgu32t8F6Fwnx1aQuhRANCAARNHLAXQStMb+/DfK3MvxCAcInX78Kl\
+        caAIc9K0IFwyvWFBElVdPQ/bnmFTKrcEumKNIzEp3f8XknSFd7/4PSnj";
+
+    fn cert_der() -> Vec<u8> {
+        general_purpose::STANDARD.decode(CERT.replace(' ', "")).unwrap()
+    }
+
+    #[test]
+    fn test_parse_certificate() {
+        let now = "2027-01-01T00:00:00Z".parse().unwrap();
+        let info = certificate_info(&cert_der(), now);
+        assert_eq!(info.subject, "C=GB, O=OpenRequest Test, CN=localhost");
+        assert!(info.self_signed && info.is_ca && !info.expired);
+        assert_eq!(info.serial_number, "1234");
+        assert_eq!(info.not_before.as_deref(), Some("2026-10-17T02:28:24+00:00"));
+        assert_eq!(info.not_after.as_deref(), Some("2126-09-23T02:28:24+00:00"));
+        assert_eq!(info.subject_alt_names, vec!["DNS:localhost", "DNS:*.example.test", "IP:127.0.0.1"]);
+        assert_eq!(info.signature_algorithm, "ecdsa-with-SHA256");
+        assert_eq!(info.public_key, "EC P-256");
+        assert!(info.sha256_fingerprint.starts_with("80:EA:FE:19:27:6D"));
+
+        let later = "2127-01-01T00:00:00Z".parse().unwrap();
+        assert!(certificate_info(&cert_der(), later).expired);
+        // Garbage still gets fingerprints
+        let garbage = certificate_info(b"not a certificate", now);
+        assert_eq!((garbage.subject.as_str(), garbage.sha256_fingerprint.len()), ("", 95));
+    }
+
+    #[tokio::test]
+    async fn test_inspect_untrusted_server() {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let key = general_purpose::STANDARD.decode(KEY.replace(' ', "")).unwrap();
+        let config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert_der().into()], rustls::pki_types::PrivateKeyDer::try_from(key).unwrap())
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let _ = acceptor.accept(stream).await;
+        });
+
+        let result = inspect("localhost", Some(port)).await.unwrap();
+        assert!(!result.trusted);
+        assert!(result.verification_error.is_some());
+        assert_eq!(result.certificates.len(), 1);
+        assert_eq!(result.certificates[0].subject, "C=GB, O=OpenRequest Test, CN=localhost");
+        assert!(result.protocol_version.is_some() && result.cipher_suite.is_some());
+    }
+}
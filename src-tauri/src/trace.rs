@@ -0,0 +1,123 @@
+// 🎓 TEACHING: Trace context and correlation ids
+// With this turned on, every send carries a W3C `traceparent` header with a fresh trace id
+// and/or a generated correlation id (`X-Request-Id` unless another header is named). A
+// backend that logs or traces these can then be searched for exactly the call made here;
+// the ids come back on the response and are kept in the send history.
+//
+// The setting is global, and a collection or folder can set its own, which wins for the
+// requests inside (the nearest folder with one set, like auth). Headers the request already
+// sets itself are left alone. They're added after the cache key is computed, so a fresh id
+// on every send doesn't stop caching from working.
+
+use anyhow::Result;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const DEFAULT_REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct TraceContext {
+    pub traceparent: bool, // Add a W3C traceparent header
+    pub request_id: bool,  // Add a generated correlation id
+    pub request_id_header: Option<String>, // Defaults to X-Request-Id
+}
+
+// What one send carried; None for headers that weren't added
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TraceIds {
+    pub traceparent: Option<String>,
+    pub trace_id: Option<String>, // The part of traceparent tracing backends search by
+    pub request_id: Option<String>,
+}
+
+impl TraceContext {
+    pub fn request_id_header(&self) -> &str {
+        self.request_id_header
+            .as_deref()
+            .map(str::trim)
+            .filter(|header| !header.is_empty())
+            .unwrap_or(DEFAULT_REQUEST_ID_HEADER)
+    }
+}
+
+// Stored as JSON in the `collections.trace_context` column
+pub fn parse_trace_context(json: Option<&str>) -> Result<Option<TraceContext>> {
+    match json.map(str::trim).filter(|json| !json.is_empty()) {
+        Some(json) => Ok(Some(serde_json::from_str(json)?)),
+        None => Ok(None),
+    }
+}
+
+// 🎓 TEACHING: traceparent is "00-<trace id>-<parent id>-01": version 00, 16 random bytes
+// naming the whole trace, 8 naming this call, and the "sampled" flag. All-zero ids are invalid.
+fn new_traceparent() -> (String, String) {
+    let mut rng = rand::thread_rng();
+    let trace_id = format!("{:032x}", rng.gen::<u128>().max(1));
+    let parent_id = format!("{:016x}", rng.gen::<u64>().max(1));
+    (format!("00-{}-{}-01", trace_id, parent_id), trace_id)
+}
+
+// The headers to add to a send with `existing` headers, and the ids they carry
+pub fn headers(context: &TraceContext, existing: &HashMap<String, String>) -> (Vec<(String, String)>, Option<TraceIds>) {
+    let has = |name: &str| existing.keys().any(|key| key.eq_ignore_ascii_case(name));
+    let mut added = Vec::new();
+    let mut ids = TraceIds::default();
+    if context.traceparent && !has("traceparent") {
+        let (traceparent, trace_id) = new_traceparent();
+        added.push(("traceparent".to_string(), traceparent.clone()));
+        ids.traceparent = Some(traceparent);
+        ids.trace_id = Some(trace_id);
+    }
+    let request_id_header = context.request_id_header();
+    if context.request_id && !has(request_id_header) {
+        let request_id = uuid::Uuid::new_v4().to_string();
+        added.push((request_id_header.to_string(), request_id.clone()));
+        ids.request_id = Some(request_id);
+    }
+    let ids = (!added.is_empty()).then_some(ids);
+    (added, ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headers() {
+        let context = TraceContext { traceparent: true, request_id: true, request_id_header: None };
+        let (added, ids) = headers(&context, &HashMap::new());
+        let ids = ids.unwrap();
+        assert_eq!(added.len(), 2);
+        assert_eq!(added[1].0, DEFAULT_REQUEST_ID_HEADER);
+
+        let traceparent = ids.traceparent.unwrap();
+        let parts: Vec<&str> = traceparent.split('-').collect();
+        assert_eq!(parts.len(), 4);
+        assert_eq!((parts[0], parts[3]), ("00", "01"));
+        assert_eq!((parts[1].len(), parts[2].len()), (32, 16));
+        assert_eq!(ids.trace_id.as_deref(), Some(parts[1]));
+        assert_eq!(ids.request_id.as_deref(), Some(added[1].1.as_str()));
+
+        // A header the request sets itself wins
+        let existing = HashMap::from([("X-Correlation-Id".to_string(), "mine".to_string())]);
+        let context = TraceContext { request_id_header: Some("x-correlation-id".to_string()), ..context };
+        let (added, ids) = headers(&context, &existing);
+        assert_eq!(added.len(), 1);
+        assert_eq!(ids.unwrap().request_id, None);
+
+        let (added, ids) = headers(&TraceContext::default(), &HashMap::new());
+        assert!(added.is_empty() && ids.is_none());
+    }
+
+    #[test]
+    fn test_parse_trace_context() {
+        assert_eq!(parse_trace_context(None).unwrap(), None);
+        assert_eq!(parse_trace_context(Some(" ")).unwrap(), None);
+        let context = parse_trace_context(Some(r#"{"request_id":true,"request_id_header":" "}"#)).unwrap().unwrap();
+        assert!(context.request_id && !context.traceparent);
+        assert_eq!(context.request_id_header(), DEFAULT_REQUEST_ID_HEADER);
+        assert!(parse_trace_context(Some("yes")).is_err());
+    }
+}
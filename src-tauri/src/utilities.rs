@@ -0,0 +1,203 @@
+// 🎓 TEACHING: Encoding and crypto utilities
+// The "tools" panel: encode and decode text, hash it, sign it with an HMAC and generate
+// UUIDs, without leaving the app for a website that sees your tokens. The primitives are
+// the ones auth.rs already signs requests with, so a digest here matches what a Digest or
+// AWS signature would have used.
+//
+// Encodings:
+//   base64      standard alphabet, padded
+//   base64url   URL-safe alphabet, unpadded (as in JWTs); decoding accepts padding too
+//   url         percent-encoding of everything but unreserved characters, spaces as %20
+//   form        application/x-www-form-urlencoded, spaces as +
+//   hex         lowercase; decoding accepts either case and ignores whitespace
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const MAX_UUIDS: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    Base64,
+    Base64url,
+    Url,
+    Form,
+    Hex,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+pub fn encode(input: &str, encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Url => crate::auth::aws_uri_encode(input),
+        Encoding::Form => crate::auth::percent_encode(input),
+        _ => encode_binary(input.as_bytes(), encoding).unwrap_or_default(),
+    }
+}
+
+pub fn decode(input: &str, encoding: Encoding) -> Result<String> {
+    String::from_utf8(decode_bytes(input, encoding)?)
+        .map_err(|_| anyhow!("The decoded bytes are not UTF-8 text; decode to hex to see them"))
+}
+
+// Hashes are binary, so they're shown as hex, base64 or base64url
+pub fn digest(input: &str, algorithm: HashAlgorithm, output: Encoding) -> Result<String> {
+    let hash = match algorithm {
+        HashAlgorithm::Md5 => md5::compute(input.as_bytes()).0.to_vec(),
+        HashAlgorithm::Sha1 => sha1::Sha1::digest(input.as_bytes()).to_vec(),
+        HashAlgorithm::Sha256 => Sha256::digest(input.as_bytes()).to_vec(),
+    };
+    encode_binary(&hash, output)
+}
+
+// The key is text unless `key_encoding` says it's hex or base64, as secrets often are
+pub fn hmac(
+    input: &str,
+    key: &str,
+    key_encoding: Option<Encoding>,
+    algorithm: HashAlgorithm,
+    output: Encoding,
+) -> Result<String> {
+    let key = match key_encoding {
+        Some(encoding) => decode_bytes(key, encoding).map_err(|e| anyhow!("Invalid key: {}", e))?,
+        None => key.as_bytes().to_vec(),
+    };
+    let signature = match algorithm {
+        HashAlgorithm::Md5 => return Err(anyhow!("HMAC-MD5 isn't supported; use SHA-1 or SHA-256")),
+        HashAlgorithm::Sha1 => {
+            let mut mac = Hmac::<sha1::Sha1>::new_from_slice(&key)?;
+            mac.update(input.as_bytes());
+            mac.finalize().into_bytes().to_vec()
+        }
+        HashAlgorithm::Sha256 => crate::auth::hmac_sha256(&key, input.as_bytes())?,
+    };
+    encode_binary(&signature, output)
+}
+
+pub fn uuids(count: usize) -> Result<Vec<String>> {
+    if count == 0 || count > MAX_UUIDS {
+        return Err(anyhow!("Can generate between 1 and {} UUIDs at a time", MAX_UUIDS));
+    }
+    Ok((0..count).map(|_| uuid::Uuid::new_v4().to_string()).collect())
+}
+
+fn encode_binary(bytes: &[u8], encoding: Encoding) -> Result<String> {
+    match encoding {
+        Encoding::Base64 => Ok(general_purpose::STANDARD.encode(bytes)),
+        Encoding::Base64url => Ok(general_purpose::URL_SAFE_NO_PAD.encode(bytes)),
+        Encoding::Hex => Ok(hex::encode(bytes)),
+        Encoding::Url | Encoding::Form => Err(anyhow!("Binary output can be hex, base64 or base64url")),
+    }
+}
+
+fn decode_bytes(input: &str, encoding: Encoding) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::Base64 => Ok(general_purpose::STANDARD.decode(input.trim())?),
+        Encoding::Base64url => Ok(general_purpose::URL_SAFE_NO_PAD.decode(input.trim().trim_end_matches('='))?),
+        Encoding::Hex => {
+            let digits: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+            Ok(hex::decode(digits)?)
+        }
+        Encoding::Url => percent_decode(input, false),
+        Encoding::Form => percent_decode(input, true),
+    }
+}
+
+fn percent_decode(input: &str, plus_is_space: bool) -> Result<Vec<u8>> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let byte = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|digits| std::str::from_utf8(digits).ok())
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .ok_or_else(|| anyhow!("Invalid percent-escape at position {}", i))?;
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            b'+' if plus_is_space => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let text = "a b/c?d=é&f~";
+        let cases = [
+            (Encoding::Base64, "YSBiL2M/ZD3DqSZmfg=="),
+            (Encoding::Base64url, "YSBiL2M_ZD3DqSZmfg"),
+            (Encoding::Url, "a%20b%2Fc%3Fd%3D%C3%A9%26f~"),
+            (Encoding::Form, "a+b%2Fc%3Fd%3D%C3%A9%26f%7E"),
+            (Encoding::Hex, "6120622f633f643dc3a926667e"),
+        ];
+        for (encoding, encoded) in cases {
+            assert_eq!(encode(text, encoding), encoded, "{:?}", encoding);
+            assert_eq!(decode(encoded, encoding).unwrap(), text, "{:?}", encoding);
+        }
+        assert_eq!(decode("YSBiL2M_ZD3DqSZmfg==", Encoding::Base64url).unwrap(), text);
+        assert_eq!(decode("61 62\n6A", Encoding::Hex).unwrap(), "abj");
+        assert_eq!(decode("a+b%20c", Encoding::Url).unwrap(), "a+b c");
+        assert!(decode("100%", Encoding::Url).is_err());
+        assert!(decode("ff", Encoding::Hex).unwrap_err().to_string().contains("UTF-8"));
+    }
+
+    #[test]
+    fn test_digest() {
+        assert_eq!(digest("abc", HashAlgorithm::Md5, Encoding::Hex).unwrap(), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            digest("abc", HashAlgorithm::Sha1, Encoding::Hex).unwrap(),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            digest("abc", HashAlgorithm::Sha256, Encoding::Base64).unwrap(),
+            "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="
+        );
+        assert!(digest("abc", HashAlgorithm::Sha256, Encoding::Url).is_err());
+    }
+
+    #[test]
+    fn test_hmac() {
+        // RFC 4231 test case 2 and its SHA-1 counterpart from RFC 2202
+        let data = "what do ya want for nothing?";
+        assert_eq!(
+            hmac(data, "Jefe", None, HashAlgorithm::Sha256, Encoding::Hex).unwrap(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hmac(data, "4a656665", Some(Encoding::Hex), HashAlgorithm::Sha1, Encoding::Hex).unwrap(),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+        assert!(hmac(data, "zz", Some(Encoding::Hex), HashAlgorithm::Sha256, Encoding::Hex).is_err());
+        assert!(hmac(data, "Jefe", None, HashAlgorithm::Md5, Encoding::Hex).is_err());
+    }
+
+    #[test]
+    fn test_uuids() {
+        let ids = uuids(3).unwrap();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.iter().all(|id| uuid::Uuid::parse_str(id).is_ok()));
+        assert_ne!(ids[0], ids[1]);
+        assert!(uuids(0).is_err() && uuids(MAX_UUIDS + 1).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+// 🎓 TEACHING: Webhook listener
+// Third-party services (GitHub, Stripe, ...) call *you* when something happens. To test that
+// locally, start a listener on a port, point the service (or a tunnel like ngrok) at it,
+// and every request it receives is saved to `webhook_captures` and shown live in the UI.
+
+use crate::database::{Database, WebhookCapture};
+use crate::http_server::{self, IncomingRequest, OutgoingResponse, ServerHandle};
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WebhookListenerConfig {
+    pub response_status: Option<u16>, // What the caller gets back (defaults to 200)
+    pub response_body: Option<String>,
+    #[serde(default)]
+    pub bind_all: bool, // Listen on every interface instead of only localhost
+}
+
+pub type CaptureSink = Arc<dyn Fn(WebhookCapture) + Send + Sync>;
+
+// Running listeners, keyed by port
+#[derive(Default)]
+pub struct WebhookManager {
+    listeners: Mutex<HashMap<u16, ServerHandle>>,
+}
+
+impl WebhookManager {
+    // Returns the port actually used (useful when asking for port 0)
+    pub async fn start(&self, db: Database, port: u16, config: WebhookListenerConfig, sink: CaptureSink) -> Result<u16> {
+        if port != 0 && self.listeners.lock().unwrap().contains_key(&port) {
+            return Err(anyhow::anyhow!("A webhook listener is already running on port {}", port));
+        }
+
+        let bind_all = config.bind_all;
+        let listener_port = Arc::new(std::sync::OnceLock::new());
+        let handler_port = listener_port.clone();
+        let handler: http_server::Handler = Arc::new(move |request: IncomingRequest| {
+            let db = db.clone();
+            let sink = sink.clone();
+            let config = config.clone();
+            let port = handler_port.get().copied().unwrap_or(port);
+            Box::pin(async move {
+                let capture = to_capture(port, request);
+                if let Err(e) = db.insert_webhook_capture(&capture).await {
+                    return OutgoingResponse::new(500, format!("Failed to record webhook: {}", e));
+                }
+                sink(capture);
+
+                let mut response = OutgoingResponse::new(
+                    config.response_status.unwrap_or(200),
+                    config.response_body.unwrap_or_default(),
+                );
+                response.headers.insert("Content-Type".to_string(), "text/plain".to_string());
+                response
+            })
+        });
+
+        let server = http_server::start(port, bind_all, handler).await?;
+        let port = server.port;
+        let _ = listener_port.set(port);
+        self.listeners.lock().unwrap().insert(port, server);
+        Ok(port)
+    }
+
+    pub fn stop(&self, port: u16) -> Result<()> {
+        let server = self
+            .listeners
+            .lock()
+            .unwrap()
+            .remove(&port)
+            .ok_or_else(|| anyhow::anyhow!("No webhook listener on port {}", port))?;
+        server.stop();
+        Ok(())
+    }
+
+    pub fn ports(&self) -> Vec<u16> {
+        let mut ports: Vec<u16> = self.listeners.lock().unwrap().keys().copied().collect();
+        ports.sort();
+        ports
+    }
+}
+
+fn to_capture(port: u16, request: IncomingRequest) -> WebhookCapture {
+    WebhookCapture {
+        id: Uuid::new_v4().to_string(),
+        port,
+        method: request.method,
+        path: request.path,
+        query: request.query,
+        headers: serde_json::to_string(&request.headers).unwrap_or_else(|_| "{}".to_string()),
+        body: String::from_utf8_lossy(&request.body).to_string(),
+        remote_addr: request.remote_addr,
+        received_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_listener_records_and_answers() {
+        let dir = std::env::temp_dir().join(format!("webhook-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(&crate::settings::database_url(&dir.join("webhook.db")), 1).await.unwrap();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink_seen = seen.clone();
+        let sink: CaptureSink = Arc::new(move |capture| sink_seen.lock().unwrap().push(capture));
+        let config = WebhookListenerConfig {
+            response_status: Some(202),
+            response_body: Some("queued".to_string()),
+            bind_all: false,
+        };
+        let manager = WebhookManager::default();
+        let port = manager.start(db.clone(), 0, config, sink.clone()).await.unwrap();
+        assert_eq!(manager.ports(), [port]);
+        let duplicate = manager.start(db.clone(), port, WebhookListenerConfig::default(), sink).await;
+        assert!(duplicate.unwrap_err().to_string().contains("already running"));
+
+        let response = reqwest::Client::new()
+            .post(format!("http://127.0.0.1:{}/hooks/github?delivery=1", port))
+            .header("X-GitHub-Event", "push")
+            .body(r#"{"ref":"main"}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 202);
+        assert_eq!(response.text().await.unwrap(), "queued");
+
+        // Saved with the port actually used, and handed to the UI as it came in
+        let captures = db.get_webhook_captures(Some(port), None).await.unwrap();
+        assert_eq!(captures.len(), 1);
+        let capture = &captures[0];
+        assert_eq!((capture.port, capture.method.as_str(), capture.path.as_str()), (port, "POST", "/hooks/github"));
+        assert_eq!(capture.query.as_deref(), Some("delivery=1"));
+        assert_eq!(capture.body, r#"{"ref":"main"}"#);
+        let headers: HashMap<String, String> = serde_json::from_str(&capture.headers).unwrap();
+        assert!(headers.iter().any(|(name, value)| name.eq_ignore_ascii_case("x-github-event") && value == "push"));
+        assert_eq!(seen.lock().unwrap()[0].id, capture.id);
+
+        manager.stop(port).unwrap();
+        assert!(manager.ports().is_empty());
+        assert!(manager.stop(port).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
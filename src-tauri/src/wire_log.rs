@@ -0,0 +1,148 @@
+// 🎓 TEACHING: Wire log
+// A send with `debug` set also returns what actually went over the wire: the request line,
+// every header after auth and interpolation, the body, and the response's status line and
+// raw headers (repeated headers like Set-Cookie stay separate). When a server rejects a
+// request, this shows exactly what it was given rather than what the editor holds.
+
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WireHeader {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WireLog {
+    pub request_line: String, // e.g. "POST /users?page=2 HTTP/1.1"
+    pub request_headers: Vec<WireHeader>,
+    pub request_body: Option<String>,
+    pub status_line: String, // e.g. "HTTP/1.1 201 Created"
+    pub response_headers: Vec<WireHeader>,
+}
+
+// The request half, taken just before sending; the protocol version is only known once
+// the response is back
+#[derive(Debug, Clone)]
+pub struct SentRequest {
+    method: String,
+    target: String,
+    headers: Vec<WireHeader>,
+    body: Option<String>,
+}
+
+fn wire_headers(headers: &HeaderMap) -> Vec<WireHeader> {
+    headers
+        .iter()
+        .map(|(name, value)| WireHeader {
+            name: name.to_string(),
+            value: String::from_utf8_lossy(value.as_bytes()).to_string(),
+        })
+        .collect()
+}
+
+// None if the request can't be copied (a streamed body), which sends never use
+pub fn capture_request(builder: &reqwest::RequestBuilder) -> Option<SentRequest> {
+    let request = builder.try_clone()?.build().ok()?;
+    let url = request.url();
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(|bytes| String::from_utf8_lossy(bytes).to_string());
+
+    // The HTTP client adds these itself while sending, so they're not on the request yet
+    let mut headers = wire_headers(request.headers());
+    let has = |headers: &[WireHeader], name: &str| headers.iter().any(|h| h.name.eq_ignore_ascii_case(name));
+    if !has(&headers, "host") {
+        if let Some(host) = url.host_str() {
+            let value = match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            };
+            headers.insert(0, WireHeader { name: "host".to_string(), value });
+        }
+    }
+    if !has(&headers, "accept") {
+        headers.push(WireHeader { name: "accept".to_string(), value: "*/*".to_string() });
+    }
+    if let Some(body) = body.as_ref().filter(|_| !has(&headers, "content-length")) {
+        headers.push(WireHeader { name: "content-length".to_string(), value: body.len().to_string() });
+    }
+
+    Some(SentRequest {
+        method: request.method().to_string(),
+        target,
+        headers,
+        body,
+    })
+}
+
+pub fn finish(sent: SentRequest, response: &reqwest::Response) -> WireLog {
+    let version = format!("{:?}", response.version());
+    let status = response.status();
+    WireLog {
+        request_line: format!("{} {} {}", sent.method, sent.target, version),
+        request_headers: sent.headers,
+        request_body: sent.body,
+        status_line: format!("{} {} {}", version, status.as_u16(), status.canonical_reason().unwrap_or(""))
+            .trim_end()
+            .to_string(),
+        response_headers: wire_headers(response.headers()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(name: &str, value: &str) -> WireHeader {
+        WireHeader { name: name.to_string(), value: value.to_string() }
+    }
+
+    #[test]
+    fn test_wire_log() {
+        let builder = reqwest::Client::new()
+            .post("http://api.example.com:8080/users?page=2")
+            .bearer_auth("abc")
+            .header("Content-Type", "application/json")
+            .body(r#"{"name":"Ada"}"#);
+        let sent = capture_request(&builder).unwrap();
+
+        let response = hyper::Response::builder()
+            .status(201)
+            .header("Set-Cookie", "a=1")
+            .header("Set-Cookie", "b=2")
+            .body("")
+            .unwrap();
+        let log = finish(sent, &reqwest::Response::from(response));
+
+        assert_eq!(log.request_line, "POST /users?page=2 HTTP/1.1");
+        assert_eq!(
+            log.request_headers,
+            vec![
+                header("host", "api.example.com:8080"),
+                header("authorization", "Bearer abc"),
+                header("content-type", "application/json"),
+                header("accept", "*/*"),
+                header("content-length", "14"),
+            ]
+        );
+        assert_eq!(log.request_body.as_deref(), Some(r#"{"name":"Ada"}"#));
+        assert_eq!(log.status_line, "HTTP/1.1 201 Created");
+        assert_eq!(log.response_headers, vec![header("set-cookie", "a=1"), header("set-cookie", "b=2")]);
+    }
+
+    #[test]
+    fn test_default_port_and_no_body() {
+        let builder = reqwest::Client::new().get("https://example.com/").header("Accept", "text/plain");
+        let sent = capture_request(&builder).unwrap();
+        assert_eq!(sent.target, "/");
+        assert_eq!(sent.headers, vec![header("host", "example.com"), header("accept", "text/plain")]);
+        assert_eq!(sent.body, None);
+    }
+}
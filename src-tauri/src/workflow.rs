@@ -0,0 +1,345 @@
+// 🎓 TEACHING: Workflows
+// A workflow is a graph of saved requests. Each edge says what to pass from one request's
+// response to the next (capture rules, the same ones requests use for variables) and,
+// optionally, when to follow it, e.g. only if the first request answered 2xx.
+//
+// Nodes run one at a time in dependency order. A node runs once everything pointing at it
+// has run and every incoming edge's condition holds; otherwise it's skipped, and so is
+// everything downstream of it. That makes branches like "create, then on 409 look the
+// record up instead" possible without scripting. Values passed along edges are one-off
+// variables for the receiving request only; nothing is written to the environment.
+
+use crate::capture::{self, CaptureRule};
+use crate::database::{Database, Workflow};
+use crate::error::AppError;
+use crate::runner::{self, RunOptions, RunRequestResult};
+use crate::send_environment;
+use crate::session::SessionCache;
+use crate::ApiResponse;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WorkflowNode {
+    pub id: String, // Unique within the workflow; the same request can appear twice
+    pub request_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WorkflowEdge {
+    pub from: String,
+    pub to: String,
+    // Values taken from `from`'s response; each rule's `variable` is what `to` sees
+    #[serde(default)]
+    pub extract: Vec<CaptureRule>,
+    // "always" (the default), "success" (2xx and tests passed), "failure", a status
+    // class like "2xx" or an exact status like "201"
+    pub condition: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    Always,
+    Success,
+    Failure,
+    StatusClass(u16), // 2 for "2xx"
+    Status(u16),
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct NodeResult {
+    pub node_id: String,
+    pub request_id: String,
+    pub skipped: Option<String>, // Why the node didn't run
+    pub inputs: HashMap<String, String>, // Values passed in along edges
+    pub result: Option<RunRequestResult>, // None when skipped
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WorkflowRun {
+    pub workflow_id: String,
+    pub nodes: Vec<NodeResult>, // In the order they were visited
+    pub passed: bool,           // Every node that ran passed; skips don't count as failures
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub type NodeSink = Arc<dyn Fn(&NodeResult) + Send + Sync>;
+
+pub fn parse_condition(condition: Option<&str>) -> Result<Condition> {
+    let condition = condition.map(str::trim).filter(|c| !c.is_empty()).unwrap_or("always");
+    let invalid = || anyhow::anyhow!("Invalid edge condition: {}", condition);
+    match condition.to_lowercase().as_str() {
+        "always" => Ok(Condition::Always),
+        "success" => Ok(Condition::Success),
+        "failure" => Ok(Condition::Failure),
+        other => {
+            if let Some(class) = other.strip_suffix("xx") {
+                match class.parse::<u16>() {
+                    Ok(digit @ 1..=5) if class.len() == 1 => Ok(Condition::StatusClass(digit)),
+                    _ => Err(invalid()),
+                }
+            } else {
+                match other.parse::<u16>() {
+                    Ok(status @ 100..=599) => Ok(Condition::Status(status)),
+                    _ => Err(invalid()),
+                }
+            }
+        }
+    }
+}
+
+pub fn condition_met(condition: Condition, result: &RunRequestResult) -> bool {
+    match (condition, result.status) {
+        (Condition::Always, _) => true,
+        (Condition::Success, Some(status)) => (200..300).contains(&status) && result.passed,
+        (Condition::Success, None) => false,
+        (Condition::Failure, Some(status)) => !(200..300).contains(&status) || !result.passed,
+        (Condition::Failure, None) => true,
+        (Condition::StatusClass(class), Some(status)) => status / 100 == class,
+        (Condition::Status(expected), Some(status)) => status == expected,
+        (_, None) => false,
+    }
+}
+
+pub fn parse_graph(workflow: &Workflow) -> Result<(Vec<WorkflowNode>, Vec<WorkflowEdge>)> {
+    let nodes: Vec<WorkflowNode> =
+        serde_json::from_str(&workflow.nodes).map_err(|e| anyhow::anyhow!("Invalid workflow nodes: {}", e))?;
+    let edges: Vec<WorkflowEdge> =
+        serde_json::from_str(&workflow.edges).map_err(|e| anyhow::anyhow!("Invalid workflow edges: {}", e))?;
+    Ok((nodes, edges))
+}
+
+// 🎓 TEACHING: Check the graph and put its nodes in run order (Kahn's algorithm). Ties keep
+// the order nodes were listed in, so the run order is predictable.
+pub fn run_order(nodes: &[WorkflowNode], edges: &[WorkflowEdge]) -> Result<Vec<usize>> {
+    let mut index = HashMap::new();
+    for (position, node) in nodes.iter().enumerate() {
+        if index.insert(node.id.as_str(), position).is_some() {
+            return Err(anyhow::anyhow!("Duplicate workflow node: {}", node.id));
+        }
+    }
+
+    let mut incoming = vec![0; nodes.len()];
+    let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    for edge in edges {
+        let (Some(&from), Some(&to)) = (index.get(edge.from.as_str()), index.get(edge.to.as_str())) else {
+            return Err(anyhow::anyhow!("Edge {} -> {} refers to a missing node", edge.from, edge.to));
+        };
+        if from == to {
+            return Err(anyhow::anyhow!("Node {} can't depend on itself", edge.from));
+        }
+        parse_condition(edge.condition.as_deref())?;
+        incoming[to] += 1;
+        outgoing[from].push(to);
+    }
+
+    let mut ready: VecDeque<usize> = (0..nodes.len()).filter(|&node| incoming[node] == 0).collect();
+    let mut order = Vec::with_capacity(nodes.len());
+    while let Some(node) = ready.pop_front() {
+        order.push(node);
+        let mut next: Vec<usize> = Vec::new();
+        for &child in &outgoing[node] {
+            incoming[child] -= 1;
+            if incoming[child] == 0 {
+                next.push(child);
+            }
+        }
+        next.sort_unstable();
+        ready.extend(next);
+    }
+    if order.len() < nodes.len() {
+        return Err(anyhow::anyhow!("The workflow has a cycle"));
+    }
+    Ok(order)
+}
+
+// What a visited node left behind for the edges leaving it
+enum Visited {
+    Skipped,
+    Ran(RunRequestResult, Option<Box<ApiResponse>>),
+}
+
+pub async fn run_workflow(
+    db: &Database,
+    session_cache: &SessionCache,
+    workflow: &Workflow,
+    environment_id: Option<String>,
+    progress: NodeSink,
+) -> Result<WorkflowRun, AppError> {
+    let (nodes, edges) = parse_graph(workflow).map_err(|e| AppError::validation(e.to_string()))?;
+    let order = run_order(&nodes, &edges).map_err(|e| AppError::validation(e.to_string()))?;
+    let options = RunOptions {
+        // Pin the environment now, so switching the active one mid-run doesn't affect it
+        environment_id: send_environment(db, environment_id.as_deref()).await?.map(|env| env.id),
+        ..Default::default()
+    };
+
+    let started_at = chrono::Utc::now();
+    let mut visited: HashMap<&str, Visited> = HashMap::new();
+    let mut results = Vec::with_capacity(nodes.len());
+    for position in order {
+        let node = &nodes[position];
+        let mut inputs = HashMap::new();
+        let mut skipped = None;
+        for edge in edges.iter().filter(|edge| edge.to == node.id) {
+            match visited.get(edge.from.as_str()) {
+                Some(Visited::Ran(result, response)) => {
+                    let condition = parse_condition(edge.condition.as_deref())
+                        .map_err(|e| AppError::validation(e.to_string()))?;
+                    if !condition_met(condition, result) {
+                        skipped = Some(format!(
+                            "The condition on the edge from {} wasn't met ({})",
+                            edge.from,
+                            result.status.map(|s| format!("status {}", s)).unwrap_or_else(|| "not sent".to_string())
+                        ));
+                        break;
+                    }
+                    if let Some(response) = response {
+                        for captured in capture::apply(&edge.extract, &response.headers, &response.body) {
+                            if let Some(value) = captured.value {
+                                inputs.insert(captured.variable, value);
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    skipped = Some(format!("{} was skipped", edge.from));
+                    break;
+                }
+            }
+        }
+
+        let node_result = match skipped {
+            Some(reason) => {
+                visited.insert(&node.id, Visited::Skipped);
+                NodeResult {
+                    node_id: node.id.clone(),
+                    request_id: node.request_id.clone(),
+                    skipped: Some(reason),
+                    inputs,
+                    result: None,
+                }
+            }
+            None => {
+                let (result, response) = match db.get_request_by_id(&node.request_id).await? {
+                    Some(saved) => runner::send_request(db, session_cache, &saved, &options, 0, &inputs, None).await,
+                    None => (missing_request(&node.request_id), None),
+                };
+                visited.insert(&node.id, Visited::Ran(result.clone(), response.map(Box::new)));
+                NodeResult {
+                    node_id: node.id.clone(),
+                    request_id: node.request_id.clone(),
+                    skipped: None,
+                    inputs,
+                    result: Some(result),
+                }
+            }
+        };
+        progress(&node_result);
+        results.push(node_result);
+    }
+
+    Ok(WorkflowRun {
+        workflow_id: workflow.id.clone(),
+        passed: results
+            .iter()
+            .all(|node| node.result.as_ref().is_none_or(|result| result.passed)),
+        nodes: results,
+        started_at,
+        finished_at: chrono::Utc::now(),
+    })
+}
+
+// A node whose request was deleted fails rather than stopping the whole workflow
+fn missing_request(request_id: &str) -> RunRequestResult {
+    RunRequestResult {
+        iteration: 0,
+        request_id: request_id.to_string(),
+        name: String::new(),
+        method: String::new(),
+        status: None,
+        duration_ms: 0,
+        error: Some(format!("Request not found: {}", request_id)),
+        tests: Vec::new(),
+        passed: false,
+        attempts: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str) -> WorkflowNode {
+        WorkflowNode {
+            id: id.to_string(),
+            request_id: format!("req-{}", id),
+        }
+    }
+
+    fn edge(from: &str, to: &str, condition: Option<&str>) -> WorkflowEdge {
+        WorkflowEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+            extract: Vec::new(),
+            condition: condition.map(str::to_string),
+        }
+    }
+
+    fn result(status: Option<u16>, passed: bool) -> RunRequestResult {
+        RunRequestResult {
+            status,
+            passed,
+            ..missing_request("r")
+        }
+    }
+
+    #[test]
+    fn test_run_order() {
+        let nodes = vec![node("report"), node("login"), node("create"), node("lookup")];
+        let edges = vec![
+            edge("login", "create", Some("2xx")),
+            edge("create", "report", None),
+            edge("login", "lookup", None),
+            edge("lookup", "report", None),
+        ];
+        let order: Vec<&str> = run_order(&nodes, &edges)
+            .unwrap()
+            .into_iter()
+            .map(|i| nodes[i].id.as_str())
+            .collect();
+        assert_eq!(order, vec!["login", "create", "lookup", "report"]);
+
+        let cycle = vec![edge("login", "create", None), edge("create", "login", None)];
+        assert!(run_order(&nodes, &cycle).unwrap_err().to_string().contains("cycle"));
+        assert!(run_order(&nodes, &[edge("login", "nope", None)]).is_err());
+        assert!(run_order(&nodes, &[edge("login", "login", None)]).is_err());
+        assert!(run_order(&nodes, &[edge("login", "create", Some("sometimes"))]).is_err());
+        assert!(run_order(&[node("a"), node("a")], &[]).is_err());
+    }
+
+    #[test]
+    fn test_conditions() {
+        assert_eq!(parse_condition(None).unwrap(), Condition::Always);
+        assert_eq!(parse_condition(Some("2XX")).unwrap(), Condition::StatusClass(2));
+        assert_eq!(parse_condition(Some("409")).unwrap(), Condition::Status(409));
+        assert!(parse_condition(Some("9xx")).is_err());
+        assert!(parse_condition(Some("02xx")).is_err());
+        assert!(parse_condition(Some("42")).is_err());
+
+        let created = result(Some(201), true);
+        let conflict = result(Some(409), true);
+        let not_sent = result(None, false);
+        assert!(condition_met(Condition::Success, &created));
+        assert!(!condition_met(Condition::Success, &result(Some(200), false)));
+        assert!(condition_met(Condition::Failure, &conflict));
+        assert!(condition_met(Condition::Failure, &not_sent));
+        assert!(condition_met(Condition::StatusClass(4), &conflict));
+        assert!(condition_met(Condition::Status(201), &created));
+        assert!(!condition_met(Condition::StatusClass(2), &not_sent));
+        assert!(condition_met(Condition::Always, &not_sent));
+    }
+}